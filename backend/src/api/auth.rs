@@ -5,6 +5,10 @@
 
 use actix_web::{HttpResponse, http::StatusCode};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use crate::blockchain::{Transaction, TransactionError};
+use crate::blockchain::transaction::canonical_sign_message;
 use crate::crypto::{verify_signature, address_from_public_key};
 
 /// Signed request wrapper
@@ -63,13 +67,14 @@ impl AuthError {
 /// 
 /// # Returns
 /// * `Ok(address)` - The verified wallet address
-/// * `Err(HttpResponse)` - An error response if verification fails
+/// * `Err(TransactionError)` - Why verification failed; handlers answer with
+///   its `ApiError::error_response`
 pub fn verify_signed_request(
     auth: &AuthData,
     message: &[u8],
     expected_address: Option<&str>,
     max_age_secs: u64,
-) -> Result<String, HttpResponse> {
+) -> Result<String, TransactionError> {
     // Check timestamp (replay protection)
     let current_time = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
@@ -78,50 +83,63 @@ pub fn verify_signed_request(
     
     if auth.timestamp > current_time + 60 {
         // Allow 60 seconds clock skew into the future
-        return Err(AuthError::new(
-            "Request timestamp is in the future",
-            "TIMESTAMP_FUTURE",
-        ).to_response(StatusCode::BAD_REQUEST));
+        return Err(TransactionError::RequestFromFuture);
     }
     
-    if current_time - auth.timestamp > max_age_secs {
-        return Err(AuthError::new(
-            &format!("Request expired. Max age is {} seconds", max_age_secs),
-            "REQUEST_EXPIRED",
-        ).to_response(StatusCode::BAD_REQUEST));
+    if current_time.saturating_sub(auth.timestamp) > max_age_secs {
+        return Err(TransactionError::RequestExpired { max_age_secs });
     }
 
-    // Verify signature
-    match verify_signature(&auth.public_key, message, &auth.signature) {
+    let address = verify_signer(&auth.public_key, message, &auth.signature, expected_address)?;
+    if !mark_signature_used(&auth.signature, auth.timestamp + max_age_secs, current_time) {
+        return Err(TransactionError::RequestReplayed);
+    }
+    Ok(address)
+}
+
+/// Signatures accepted by `verify_signed_request`, with the time each one
+/// would expire anyway. Makes every signed request single-use.
+fn used_signatures() -> &'static Mutex<HashMap<String, u64>> {
+    static USED: OnceLock<Mutex<HashMap<String, u64>>> = OnceLock::new();
+    USED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Record `signature` as used. Returns false if it was already used.
+fn mark_signature_used(signature: &str, expires_at: u64, now: u64) -> bool {
+    let mut used = used_signatures().lock().unwrap_or_else(|e| e.into_inner());
+    used.retain(|_, expiry| *expiry >= now);
+    used.insert(signature.to_string(), expires_at).is_none()
+}
+
+/// Verify a signed transaction against its canonical signing message.
+/// Returns the sender address, which must be derived from the signing key.
+pub fn verify_signed_transaction(tx: &Transaction) -> Result<String, TransactionError> {
+    let (Some(public_key), Some(signature)) = (&tx.sender_public_key, &tx.signature) else {
+        return Err(TransactionError::Unsigned);
+    };
+    let message = canonical_sign_message(tx);
+    verify_signer(public_key, message.as_bytes(), signature, Some(&tx.sender))
+}
+
+/// Check `signature` over `message` and return the signer's address
+fn verify_signer(
+    public_key: &str,
+    message: &[u8],
+    signature: &str,
+    expected_address: Option<&str>,
+) -> Result<String, TransactionError> {
+    match verify_signature(public_key, message, signature) {
         Ok(true) => {
             // Derive address from public key
-            match address_from_public_key(&auth.public_key) {
-                Ok(address) => {
-                    // If expected address is provided, verify it matches
-                    if let Some(expected) = expected_address {
-                        if address != expected {
-                            return Err(AuthError::new(
-                                "Signer address does not match the request address",
-                                "ADDRESS_MISMATCH",
-                            ).to_response(StatusCode::FORBIDDEN));
-                        }
-                    }
-                    Ok(address)
-                }
-                Err(_) => Err(AuthError::new(
-                    "Invalid public key format",
-                    "INVALID_PUBLIC_KEY",
-                ).to_response(StatusCode::BAD_REQUEST)),
+            let address = address_from_public_key(public_key).map_err(|_| TransactionError::InvalidPublicKey)?;
+            // If expected address is provided, verify it matches
+            if expected_address.is_some_and(|expected| address != expected) {
+                return Err(TransactionError::SenderMismatch);
             }
+            Ok(address)
         }
-        Ok(false) => Err(AuthError::new(
-            "Invalid signature",
-            "INVALID_SIGNATURE",
-        ).to_response(StatusCode::UNAUTHORIZED)),
-        Err(_) => Err(AuthError::new(
-            "Signature verification failed",
-            "SIGNATURE_ERROR",
-        ).to_response(StatusCode::BAD_REQUEST)),
+        Ok(false) => Err(TransactionError::InvalidSignature),
+        Err(_) => Err(TransactionError::MalformedSignature),
     }
 }
 
@@ -145,6 +163,8 @@ pub fn create_sign_message(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blockchain::TransactionType;
+    use crate::blockchain::transaction::ValidatorRegistration;
     use crate::crypto::Wallet;
 
     #[test]
@@ -166,5 +186,68 @@ mod tests {
         let result = verify_signed_request(&auth, message, None, 300);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), wallet.address());
+
+        // The same signed request can't be used twice
+        let replay = verify_signed_request(&auth, message, None, 300).unwrap_err();
+        assert_eq!(replay, TransactionError::RequestReplayed);
+        assert_eq!(crate::api::error::ApiError::error_response(&replay).status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_typed_transactions_sign_and_verify() {
+        let wallet = Wallet::new();
+        let other = Wallet::new();
+        let from = wallet.address().to_string();
+        let transactions = vec![
+            Transaction::transfer(from.clone(), "bob".to_string(), 10).with_nonce(1),
+            Transaction::data_contribution(from.clone(), "reading".to_string(), from.clone()),
+            Transaction::stake(from.clone(), "validator".to_string(), 500).with_nonce(2),
+            Transaction::unstake(from.clone(), "validator".to_string(), 500).with_nonce(3),
+            Transaction::data_purchase(from.clone(), "seller".to_string(), "hash".to_string(), 20).with_nonce(4),
+            Transaction::contract_call(from.clone(), r#"{"contract":"0xabc","function":"increment"}"#.to_string())
+                .with_nonce(5)
+                .with_valid_until_height(Some(100)),
+            Transaction::validator_register(from.clone(), "validator".to_string(), 500, &ValidatorRegistration::default())
+                .with_nonce(6),
+            Transaction::validator_unbond(from.clone(), "validator".to_string(), 500).with_nonce(7),
+            Transaction::cancel_unbonding(from.clone(), "validator".to_string(), 500).with_nonce(8),
+            Transaction::device_bond(from.clone(), "device".to_string(), 500).with_nonce(9),
+            Transaction::device_unbond(from.clone(), "device".to_string()).with_nonce(10),
+            Transaction::set_device_type(from.clone(), "sensor".to_string()).with_nonce(11),
+        ];
+
+        let mut messages = std::collections::HashSet::new();
+        for tx in transactions {
+            let message = canonical_sign_message(&tx);
+            assert!(messages.insert(message.clone()), "{:?} shares a signing message", tx.tx_type);
+
+            let mut signed = tx.clone();
+            signed.set_signature(wallet.sign(message.as_bytes()), wallet.public_key_hex());
+            assert_eq!(verify_signed_transaction(&signed).unwrap(), from);
+            assert!(signed.verify(), "{:?} failed verification", tx.tx_type);
+
+            // Another key can't sign for the sender
+            let mut forged = tx.clone();
+            forged.set_signature(other.sign(message.as_bytes()), other.public_key_hex());
+            assert!(verify_signed_transaction(&forged).is_err());
+
+            // Changing the nonce or memo invalidates the signature; data
+            // contribution messages only cover the sender and data
+            if tx.tx_type != TransactionType::DataContribution {
+                let tagged = tx.clone().with_memo(Some("deposit-42".to_string()));
+                assert_ne!(canonical_sign_message(&tagged), message);
+
+                let mut replayed = signed.clone().with_nonce(tx.nonce + 1);
+                replayed.set_signature(signed.signature.clone().unwrap(), wallet.public_key_hex());
+                assert!(verify_signed_transaction(&replayed).is_err());
+            }
+        }
+
+        // Transfers keep the message existing clients sign
+        let transfer = Transaction::transfer(from.clone(), "bob".to_string(), 10).with_nonce(1);
+        assert_eq!(
+            canonical_sign_message(&transfer),
+            Transaction::create_transfer_signing_message(&from, "bob", 10, 1, None, None),
+        );
     }
 }
@@ -0,0 +1,123 @@
+//! Consensus diagnostics endpoint
+//!
+//! `GET /api/consensus/diagnostics` gathers the values that decide block
+//! production — difficulty, the latest block's entropy and adjusted
+//! difficulty, producer selection, the active validator set and the mempool —
+//! into one read-only response.
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+
+use crate::blockchain::Blockchain;
+use crate::consensus::{PoIEConsensus, StakingManager};
+use super::rest::{ApiResponse, AppState};
+use super::staking::StakingState;
+
+// ============ Response Types ============
+
+/// Consensus parameters of the latest block
+#[derive(Debug, Serialize)]
+pub struct LastBlockDiagnostics {
+    pub index: u64,
+    pub hash: String,
+    pub validator: String,
+    pub data_entropy: f64,
+    /// Base difficulty before the entropy bonus
+    pub base_difficulty: u64,
+    /// Difficulty the block was mined at
+    pub adjusted_difficulty: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConsensusDiagnostics {
+    pub height: u64,
+    /// Base difficulty for the next block
+    pub difficulty: u64,
+    pub last_block: LastBlockDiagnostics,
+    /// PoIE producer selected for the next block, seeded by the latest block hash
+    pub selected_producer: Option<String>,
+    pub active_validators: usize,
+    pub total_voting_power: f64,
+    pub pending_transactions: usize,
+}
+
+/// Collect diagnostics from the chain, PoIE consensus and the staking validator set
+pub fn consensus_diagnostics(
+    chain: &Blockchain,
+    consensus: &PoIEConsensus,
+    staking: &StakingManager,
+) -> ConsensusDiagnostics {
+    let latest = chain.latest_block();
+    ConsensusDiagnostics {
+        height: chain.total_blocks,
+        difficulty: chain.base_difficulty(chain.total_blocks),
+        last_block: LastBlockDiagnostics {
+            index: latest.index,
+            hash: latest.hash.clone(),
+            validator: latest.validator.clone(),
+            data_entropy: latest.header.data_entropy,
+            base_difficulty: chain.base_difficulty(latest.index),
+            adjusted_difficulty: latest.header.difficulty,
+        },
+        selected_producer: consensus.select_validator(latest.header.data_entropy, latest.hash.as_bytes()),
+        active_validators: staking.get_active_validators().len(),
+        total_voting_power: staking.total_voting_power(),
+        pending_transactions: chain.pending_transactions.len(),
+    }
+}
+
+// ============ Handlers ============
+
+/// Get consensus diagnostics (GET /api/consensus/diagnostics)
+pub async fn get_consensus_diagnostics(
+    data: web::Data<AppState>,
+    staking: web::Data<StakingState>,
+) -> impl Responder {
+    let chain = data.blockchain.read().await;
+    let consensus = data.consensus.read().await;
+    let staking = staking.manager.read().await;
+    HttpResponse::Ok().json(ApiResponse::success(consensus_diagnostics(&chain, &consensus, &staking)))
+}
+
+// ============ Router Configuration ============
+
+pub fn configure_consensus_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/consensus/diagnostics", web::get().to(get_consensus_diagnostics));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::chain::tests::test_chain;
+    use crate::blockchain::Transaction;
+    use crate::consensus::{StakingConfig, ValidatorDescription};
+
+    #[test]
+    fn test_diagnostics_reflect_mined_block() {
+        let mut chain = test_chain();
+        let mut consensus = PoIEConsensus::new();
+        consensus.register_validator("poie_validator".to_string(), 10_000).unwrap();
+        let mut staking = StakingManager::new(StakingConfig::default());
+        let stake = staking.config.min_validator_stake;
+        staking.register_validator(
+            "validator".to_string(), "operator".to_string(), stake, 0.1, ValidatorDescription::default(),
+        ).unwrap();
+
+        chain.add_local_transaction(Transaction::transfer("genesis".to_string(), "alice".to_string(), 5)).unwrap();
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        chain.add_local_transaction(Transaction::transfer("genesis".to_string(), "bob".to_string(), 5).with_nonce(1)).unwrap();
+
+        let diagnostics = consensus_diagnostics(&chain, &consensus, &staking);
+        assert_eq!(diagnostics.height, block.index + 1);
+        assert_eq!(diagnostics.last_block.index, block.index);
+        assert_eq!(diagnostics.last_block.hash, block.hash);
+        assert_eq!(diagnostics.last_block.validator, "miner");
+        assert_eq!(diagnostics.last_block.data_entropy, block.header.data_entropy);
+        assert_eq!(diagnostics.last_block.adjusted_difficulty, block.header.difficulty);
+        assert_eq!(diagnostics.last_block.base_difficulty, chain.base_difficulty(block.index));
+        assert_eq!(diagnostics.selected_producer.as_deref(), Some("poie_validator"));
+        assert_eq!(diagnostics.active_validators, 1);
+        assert!(diagnostics.total_voting_power > 0.0);
+        assert_eq!(diagnostics.pending_transactions, 1);
+    }
+}
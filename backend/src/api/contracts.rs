@@ -7,19 +7,27 @@
 
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use log::info;
 use wasmtime::Val;
 
 use crate::contracts::{
-    WasmRuntime, ExecutionContext, ContractAbi, AbiFunction, AbiParam,
+    WasmRuntime, WasmError, ExecutionContext, ContractAbi, AbiFunction, AbiParam, ContractManager,
 };
+use crate::blockchain::Blockchain;
+use crate::contracts::smart_contract;
 use super::rest::ApiResponse;
 
 /// Contract state (shared across handlers)
 pub struct ContractState {
+    /// Chain whose blocks deploy and call contracts; its state holds them
+    pub blockchain: Arc<RwLock<Blockchain>>,
+    /// Sandbox for contracts deployed and called directly over the API
     pub runtime: Arc<RwLock<WasmRuntime>>,
+    /// Built-in contracts and the index of logs emitted by contract calls
+    pub manager: Arc<RwLock<ContractManager>>,
 }
 
 // ============ Request Types ============
@@ -71,6 +79,32 @@ pub struct CallContractRequest {
     pub gas_limit: Option<u64>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct EstimateGasRequest {
+    /// Contract address
+    pub contract: String,
+    /// Contract method to dry-run
+    pub method: String,
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    /// Caller address
+    pub caller: String,
+    /// Value sent with the call (in tokens)
+    pub value: Option<u64>,
+    /// Gas limit
+    pub gas_limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    /// Only logs with this event name
+    pub event: Option<String>,
+    /// First block, inclusive
+    pub from: Option<u64>,
+    /// Last block, inclusive
+    pub to: Option<u64>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct GetStorageRequest {
     /// Contract address
@@ -96,6 +130,14 @@ pub struct CallContractResponse {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct EstimateGasResponse {
+    /// Whether the call would succeed against the current contract state
+    pub success: bool,
+    pub gas_used: u64,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct LogResponse {
     pub contract: String,
@@ -245,9 +287,13 @@ pub async fn get_contract(
     path: web::Path<String>,
 ) -> impl Responder {
     let address = path.into_inner();
-    let runtime = data.runtime.read().await;
+    let on_chain = data.blockchain.read().await.state.contracts.get_contract(&address);
+    let contract = match on_chain {
+        Some(contract) => Some(contract),
+        None => data.runtime.read().await.get_contract(&address),
+    };
 
-    match runtime.get_contract(&address) {
+    match contract {
         Some(contract) => {
             HttpResponse::Ok().json(ApiResponse {
                 success: true,
@@ -272,8 +318,8 @@ pub async fn get_contract(
 
 /// List all deployed contracts
 pub async fn list_contracts(data: web::Data<ContractState>) -> impl Responder {
-    let runtime = data.runtime.read().await;
-    let contracts = runtime.list_contracts();
+    let mut contracts = data.blockchain.read().await.state.contracts.list_contracts();
+    contracts.extend(data.runtime.read().await.list_contracts());
 
     HttpResponse::Ok().json(ApiResponse {
         success: true,
@@ -298,9 +344,14 @@ pub async fn get_storage(
         }
     };
 
-    let runtime = data.runtime.read().await;
-
-    let value = runtime.get_storage(&req.contract, &key);
+    let value = {
+        let chain = data.blockchain.read().await;
+        if chain.state.contracts.get_contract(&req.contract).is_some() {
+            chain.state.contracts.get_storage(&req.contract, &key)
+        } else {
+            data.runtime.read().await.get_storage(&req.contract, &key)
+        }
+    };
 
     HttpResponse::Ok().json(ApiResponse {
         success: true,
@@ -312,14 +363,131 @@ pub async fn get_storage(
     })
 }
 
+/// Estimate the gas of a contract call by dry-running it against a copy of
+/// the contract; contract state is not changed (POST /api/contracts/estimate-gas)
+pub async fn estimate_gas(
+    data: web::Data<ContractState>,
+    req: web::Json<EstimateGasRequest>,
+) -> impl Responder {
+    let gas_limit = req.gas_limit.unwrap_or(1_000_000);
+    let (on_chain, height) = {
+        let chain = data.blockchain.read().await;
+        (chain.state.contracts.contract_only(&req.contract), chain.total_blocks)
+    };
+    let mut runtime = data.runtime.write().await;
+    if let Some(mut store) = on_chain.or_else(|| runtime.sandbox_contract(&req.contract)) {
+        let params: BTreeMap<String, serde_json::Value> = req.params.iter()
+            .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
+            .collect();
+        let context = ExecutionContext {
+            contract_address: req.contract.clone(),
+            caller: req.caller.clone(),
+            value: req.value.unwrap_or(0),
+            block_height: height,
+            block_timestamp: chrono::Utc::now().timestamp(),
+            gas_limit,
+        };
+        let response = match runtime.call(&mut store, &req.contract, &req.method, &params, context) {
+            Ok(result) => EstimateGasResponse { success: result.success, gas_used: result.gas_used, error: result.error },
+            Err(WasmError::OutOfGas { used, .. }) => EstimateGasResponse {
+                success: false,
+                gas_used: used,
+                error: Some(format!("Out of gas (limit {})", gas_limit)),
+            },
+            Err(e) => EstimateGasResponse { success: false, gas_used: 0, error: Some(e.to_string()) },
+        };
+        return HttpResponse::Ok().json(ApiResponse::success(response));
+    }
+    drop(runtime);
+
+    // Built-in contracts
+    let ctx = smart_contract::ExecutionContext {
+        caller: req.caller.clone(),
+        contract_address: req.contract.clone(),
+        value: req.value.unwrap_or(0),
+        gas_limit,
+        gas_used: 0,
+        block_number: height,
+        timestamp: chrono::Utc::now(),
+    };
+    let result = data.manager.read().await
+        .dry_run(&req.contract, &req.method, req.params.clone(), ctx);
+    HttpResponse::Ok().json(ApiResponse::success(EstimateGasResponse {
+        success: result.success,
+        gas_used: result.gas_used,
+        error: result.error,
+    }))
+}
+
+/// Get logs emitted by a contract (GET /api/contracts/{address}/logs?event=&from=&to=)
+pub async fn get_contract_logs(
+    data: web::Data<ContractState>,
+    path: web::Path<String>,
+    query: web::Query<LogsQuery>,
+) -> impl Responder {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error("from must not be after to"));
+        }
+    }
+    let address = path.into_inner();
+    let mut logs = data.blockchain.read().await
+        .contract_logs(&address, query.event.as_deref(), query.from, query.to);
+    logs.extend(data.manager.read().await
+        .get_logs(&address, query.event.as_deref(), query.from, query.to));
+    HttpResponse::Ok().json(ApiResponse::success(logs))
+}
+
 /// Configure contract routes
 pub fn configure_contract_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/contracts")
             .route("/deploy", web::post().to(deploy_contract))
             .route("/call", web::post().to(call_contract))
+            .route("/estimate-gas", web::post().to(estimate_gas))
             .route("/list", web::get().to(list_contracts))
             .route("/storage", web::post().to(get_storage))
+            .route("/{address}/logs", web::get().to(get_contract_logs))
             .route("/{address}", web::get().to(get_contract)),
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use crate::blockchain::chain::tests::test_chain;
+
+    #[actix_web::test]
+    async fn test_estimate_gas_dry_runs_wasm_contract() {
+        const COUNTER_WAT: &str = r#"
+            (module
+              (import "env" "storage_set" (func $set (param i32 i32 i32 i32) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "count")
+              (func (export "touch") (result i32)
+                (call $set (i32.const 0) (i32.const 5) (i32.const 0) (i32.const 5))))
+        "#;
+        let mut runtime = WasmRuntime::new();
+        let address = runtime.deploy_contract(COUNTER_WAT.as_bytes(), "alice", ContractAbi::default()).unwrap();
+        let state = web::Data::new(ContractState {
+            blockchain: Arc::new(RwLock::new(test_chain())),
+            runtime: Arc::new(RwLock::new(runtime)),
+            manager: Arc::new(RwLock::new(ContractManager::new())),
+        });
+        let app = test::init_service(App::new().app_data(state.clone()).configure(configure_contract_routes)).await;
+
+        let estimate = |method: &str| test::TestRequest::post()
+            .uri("/api/contracts/estimate-gas")
+            .set_json(serde_json::json!({ "contract": address, "method": method, "caller": "alice" }))
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, estimate("touch")).await;
+        assert_eq!(body["data"]["success"], true);
+        assert!(body["data"]["gas_used"].as_u64().unwrap() > 0);
+        // The dry run left the contract's storage alone
+        assert!(state.runtime.read().await.get_storage(&address, b"count").is_none());
+
+        let body: serde_json::Value = test::call_and_read_body_json(&app, estimate("missing")).await;
+        assert_eq!(body["data"]["success"], false);
+    }
+}
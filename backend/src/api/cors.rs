@@ -0,0 +1,137 @@
+//! CORS allowlist
+//!
+//! The API accepts browser requests from the hosted explorer and local dev
+//! servers. Self-hosted frontends add their origins with `EDGEAI_CORS_ORIGINS`
+//! (comma-separated, e.g. `https://explorer.example.com,http://localhost:8000`);
+//! they are merged with the defaults. `*` allows any origin and is meant for
+//! local development only.
+
+#![allow(dead_code)]
+
+use actix_cors::Cors;
+use actix_web::http::{header, Uri};
+use log::{info, warn};
+
+use super::request_id::REQUEST_ID_HEADER;
+
+/// Origins always allowed
+pub const DEFAULT_CORS_ORIGINS: &[&str] = &[
+    "https://edgeai-alpha.vercel.app",
+    "https://edgeai-chain.github.io",
+    "https://edgeaiexplorer.org",
+    "https://www.edgeaiexplorer.org",
+    "https://edgeaiexplor-hg7rs66y.manus.space",
+    "http://localhost:3000",
+    "http://localhost:5173",
+    "http://127.0.0.1:3000",
+    "http://127.0.0.1:5173",
+];
+
+/// Effective CORS policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsOrigins {
+    /// Any origin is allowed (`*`)
+    pub permissive: bool,
+    /// Allowed origins: the defaults followed by the configured ones
+    pub origins: Vec<String>,
+    /// Configured entries that aren't well-formed origins
+    pub rejected: Vec<String>,
+}
+
+/// Whether `origin` is an `http(s)://host[:port]` origin with no path or query
+pub fn is_valid_origin(origin: &str) -> bool {
+    let Ok(uri) = origin.parse::<Uri>() else {
+        return false;
+    };
+    let scheme_ok = matches!(uri.scheme_str(), Some("http" | "https"));
+    let host_ok = uri.host().is_some_and(|host| !host.is_empty());
+    let bare = uri.path_and_query().is_none_or(|pq| pq.as_str() == "/");
+    scheme_ok && host_ok && bare && !origin.contains('@')
+}
+
+/// Merge a comma-separated origin list with the defaults
+pub fn parse_cors_origins(value: &str) -> CorsOrigins {
+    let mut policy = CorsOrigins {
+        permissive: false,
+        origins: DEFAULT_CORS_ORIGINS.iter().map(|o| o.to_string()).collect(),
+        rejected: Vec::new(),
+    };
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        if entry == "*" {
+            policy.permissive = true;
+            continue;
+        }
+        // Browsers send origins without a trailing slash
+        let origin = entry.trim_end_matches('/');
+        if !is_valid_origin(origin) {
+            policy.rejected.push(entry.to_string());
+        } else if !policy.origins.iter().any(|o| o == origin) {
+            policy.origins.push(origin.to_string());
+        }
+    }
+    policy
+}
+
+/// CORS policy from `EDGEAI_CORS_ORIGINS`, logging the effective allowlist
+pub fn cors_origins_from_env() -> CorsOrigins {
+    let policy = parse_cors_origins(&std::env::var("EDGEAI_CORS_ORIGINS").unwrap_or_default());
+    for entry in &policy.rejected {
+        warn!("Ignoring malformed CORS origin '{}' in EDGEAI_CORS_ORIGINS", entry);
+    }
+    if policy.permissive {
+        warn!("CORS allows any origin (EDGEAI_CORS_ORIGINS=*); use this for local development only");
+    } else {
+        info!("CORS allowed origins: {}", policy.origins.join(", "));
+    }
+    policy
+}
+
+/// Build the CORS middleware for `policy`
+pub fn build_cors(policy: &CorsOrigins) -> Cors {
+    if policy.permissive {
+        return Cors::permissive().max_age(3600);
+    }
+    policy
+        .origins
+        .iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
+        .allowed_headers(vec![
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::ACCEPT,
+            header::HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .expose_headers(vec![header::HeaderName::from_static(REQUEST_ID_HEADER)])
+        .supports_credentials()
+        .max_age(3600)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cors_origins_merges_valid_entries() {
+        let policy = parse_cors_origins(
+            " https://explorer.example.com/ ,not a url,http://localhost:8000,ftp://files.example.com,\
+             https://app.example.com/path,,http://localhost:3000",
+        );
+        assert!(!policy.permissive);
+        assert_eq!(policy.origins.len(), DEFAULT_CORS_ORIGINS.len() + 2);
+        assert_eq!(
+            &policy.origins[DEFAULT_CORS_ORIGINS.len()..],
+            &["https://explorer.example.com".to_string(), "http://localhost:8000".to_string()]
+        );
+        assert_eq!(
+            policy.rejected,
+            vec!["not a url", "ftp://files.example.com", "https://app.example.com/path"]
+        );
+
+        let policy = parse_cors_origins("*, https://explorer.example.com");
+        assert!(policy.permissive);
+        assert!(policy.rejected.is_empty());
+
+        assert_eq!(parse_cors_origins("").origins.len(), DEFAULT_CORS_ORIGINS.len());
+    }
+}
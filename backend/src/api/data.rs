@@ -1,123 +1,58 @@
 use actix_web::{web, HttpResponse, Responder};
-use serde::Deserialize;
+use log::info;
+use serde::{Deserialize, Serialize};
 
-use crate::api::rest::ApiResponse;
-use crate::iot::{IoTGenerator, IoTTransactionListResponse};
-use crate::validators::{ValidatorGenerator, ValidatorListResponse};
+use crate::api::auth::{verify_signed_request, verify_signed_transaction, SignedRequest};
+use crate::api::error::ApiError;
+use crate::api::rest::{transaction_rejected, ApiResponse, AppState, Page, PaginationQuery};
+use crate::api::wallet::{typed_transaction, DataPurchaseFields};
+use crate::data_market::{DataCategory, DataListing, SortBy};
+use crate::validators::ValidatorGenerator;
 
-// ============ Query Types ============
+// ============ Request/Response Types ============
 
-#[derive(Debug, Deserialize)]
-pub struct IoTQuery {
-    pub page: Option<u64>,
-    pub limit: Option<u64>,
-    pub sector: Option<String>,
+/// List a data hash registered on chain for sale
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListDataHashRequest {
+    pub owner: String,
+    pub data_hash: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub category: String,
+    pub price: u64,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct ValidatorQuery {
-    pub page: Option<u64>,
+pub struct ListingsQuery {
+    pub category: Option<String>,
+    pub sort_by: Option<String>,
+    pub offset: Option<u64>,
     pub limit: Option<u64>,
-    pub status: Option<String>,
 }
 
-// ============ IoT Endpoints ============
-
-/// 获取 IoT 交易列表
-pub async fn get_iot_transactions(query: web::Query<IoTQuery>) -> impl Responder {
-    let page = query.page.unwrap_or(1).max(1);
-    let limit = query.limit.unwrap_or(20).min(100);
-
-    let generator = IoTGenerator::new();
-    let transactions = generator.generate_transactions(page, limit);
-
-    // 如果指定了行业过滤
-    let filtered_transactions = if let Some(ref sector) = query.sector {
-        transactions
-            .into_iter()
-            .filter(|tx| tx.sector.to_lowercase().contains(&sector.to_lowercase()))
-            .collect()
-    } else {
-        transactions
-    };
-
-    let response = IoTTransactionListResponse {
-        transactions: filtered_transactions,
-        total: 100000, // 模拟总数
-        page,
-        limit,
-    };
-
-    HttpResponse::Ok().json(ApiResponse::success(response))
-}
-
-/// 获取 IoT 行业统计
-pub async fn get_iot_sectors() -> impl Responder {
-    use crate::iot::types::IoTSector;
-
-    let sectors: Vec<serde_json::Value> = IoTSector::all()
-        .iter()
-        .map(|s| {
-            serde_json::json!({
-                "id": format!("{:?}", s).to_lowercase(),
-                "name": s.display_name(),
-                "icon": s.icon(),
-                "devices": s.devices(),
-            })
-        })
-        .collect();
-
-    HttpResponse::Ok().json(ApiResponse::success(sectors))
+/// Buy a listing with a DataPurchase transaction signed over
+/// `canonical_sign_message` (seller and price come from the listing)
+#[derive(Debug, Deserialize)]
+pub struct PurchaseListingRequest {
+    pub buyer: String,
+    pub data_hash: String,
+    pub nonce: u64,
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
+    pub public_key: String,
+    pub signature: String,
+    /// Anti-spam proof-of-work nonce over the signed message, when the chain requires one
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
 }
 
-// ============ Validator Endpoints ============
-
-/// 获取验证者节点列表
-pub async fn get_validator_nodes(query: web::Query<ValidatorQuery>) -> impl Responder {
-    let page = query.page.unwrap_or(1).max(1);
-    let limit = query.limit.unwrap_or(100).min(1000);
-
-    let generator = ValidatorGenerator::new();
-    let mut validators = generator.generate_validators(page, limit);
-
-    // 如果指定了状态过滤
-    if let Some(ref status) = query.status {
-        use crate::validators::types::ValidatorStatus;
-        let target_status = match status.to_lowercase().as_str() {
-            "online" => Some(ValidatorStatus::Online),
-            "offline" => Some(ValidatorStatus::Offline),
-            "maintenance" => Some(ValidatorStatus::Maintenance),
-            _ => None,
-        };
-
-        if let Some(target) = target_status {
-            validators = validators
-                .into_iter()
-                .filter(|v| v.status == target)
-                .collect();
-        }
-    }
-
-    let stats = generator.get_stats();
-
-    let response = ValidatorListResponse {
-        validators,
-        total: generator.total_count(),
-        page,
-        limit,
-        stats,
-    };
-
-    HttpResponse::Ok().json(ApiResponse::success(response))
+#[derive(Debug, Serialize)]
+pub struct PurchaseListingResponse {
+    pub tx_hash: String,
 }
 
-/// 获取验证者地图数据
-pub async fn get_validator_map() -> impl Responder {
-    let generator = ValidatorGenerator::new();
-    let map_response = generator.generate_map_markers();
-
-    HttpResponse::Ok().json(ApiResponse::success(map_response))
-}
+// ============ Network Endpoints ============
 
 /// 获取网络统计（增强版）
 pub async fn get_network_stats_enhanced() -> impl Responder {
@@ -154,18 +89,241 @@ pub async fn get_network_stats_enhanced() -> impl Responder {
     HttpResponse::Ok().json(ApiResponse::success(response))
 }
 
+// ============ Marketplace Endpoints ============
+
+/// List on-chain data for sale; only the owner recorded in the chain's data
+/// registry may list it (POST /api/data/list)
+pub async fn list_data_hash(
+    data: web::Data<AppState>,
+    body: web::Json<SignedRequest<ListDataHashRequest>>,
+) -> impl Responder {
+    let message = serde_json::to_vec(&body.data).unwrap_or_default();
+    if let Err(e) = verify_signed_request(&body.auth, &message, Some(&body.data.owner), 300) {
+        return e.error_response();
+    }
+    let request = &body.data;
+
+    let quality_score = {
+        let blockchain = data.blockchain.read().await;
+        match blockchain.get_data_entry(&request.data_hash) {
+            Some(entry) if entry.owner == request.owner => entry.quality_score,
+            Some(_) => return HttpResponse::Forbidden().json(ApiResponse::<()>::error("Only the data owner can list it")),
+            None => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Data hash is not registered on chain")),
+        }
+    };
+
+    let listing = DataListing::new(
+        request.data_hash.clone(),
+        request.owner.clone(),
+        request.title.clone(),
+        request.description.clone(),
+        DataCategory::from_string(&request.category),
+        request.price,
+        quality_score,
+        0.0,
+        0,
+    );
+    let mut marketplace = data.marketplace.write().await;
+    match marketplace.list_data(listing) {
+        Ok(_) => match marketplace.get_listing(&request.data_hash) {
+            Some(listing) => HttpResponse::Ok().json(ApiResponse::success(listing)),
+            None => HttpResponse::NotFound().json(ApiResponse::<()>::error("Listing not found")),
+        },
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    }
+}
+
+/// Page through active listings (GET /api/data/listings?category=&sort_by=&offset=&limit=)
+pub async fn get_data_listings(
+    data: web::Data<AppState>,
+    query: web::Query<ListingsQuery>,
+) -> impl Responder {
+    let (offset, limit) = PaginationQuery { offset: query.offset, limit: query.limit }.resolve();
+    let category = query.category.as_deref().map(DataCategory::from_string);
+    let sort_by = query.sort_by.as_deref().map(SortBy::from_string).unwrap_or(SortBy::Newest);
+
+    let marketplace = data.marketplace.read().await;
+    let (listings, total) = marketplace.listings(category.as_ref(), sort_by, offset as usize, limit as usize);
+    HttpResponse::Ok().json(ApiResponse::success(Page {
+        items: listings.into_iter().cloned().collect::<Vec<_>>(),
+        total: total as u64,
+        offset,
+        limit,
+    }))
+}
+
+/// Buy a listing with a signed DataPurchase transaction (POST /api/data/purchase).
+/// The buyer is granted access, and the purchase recorded against the
+/// listing, once the transaction is applied in a block.
+pub async fn purchase_listing(
+    data: web::Data<AppState>,
+    body: web::Json<PurchaseListingRequest>,
+) -> impl Responder {
+    let fields = {
+        let marketplace = data.marketplace.read().await;
+        match marketplace.get_listing(&body.data_hash) {
+            Some(listing) if !listing.is_active => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Listing is not active"));
+            }
+            Some(listing) if listing.owner == body.buyer => {
+                return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Cannot purchase own data"));
+            }
+            Some(listing) => DataPurchaseFields {
+                seller: listing.owner.clone(),
+                data_hash: listing.data_hash.clone(),
+                price: listing.price,
+            },
+            None => return HttpResponse::NotFound().json(ApiResponse::<()>::error("Listing not found")),
+        }
+    };
+
+    let mut tx = typed_transaction(&body.buyer, &fields, body.nonce, body.valid_until_height)
+        .with_pow_nonce(body.pow_nonce);
+    tx.set_signature(body.signature.clone(), body.public_key.clone());
+    if let Err(e) = verify_signed_transaction(&tx) {
+        return e.error_response();
+    }
+    if let Err(e) = data.reorg.check() {
+        return transaction_rejected(&e);
+    }
+    match data.blockchain.write().await.add_transaction(tx) {
+        Ok(tx_hash) => {
+            info!("Data purchase {} submitted for {}", tx_hash, body.data_hash);
+            HttpResponse::Ok().json(ApiResponse::success(PurchaseListingResponse { tx_hash }))
+        }
+        Err(e) => transaction_rejected(&e),
+    }
+}
+
+/// Data hashes an address has purchased access to (GET /api/data/access/{address})
+pub async fn get_data_access(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let blockchain = data.blockchain.read().await;
+    HttpResponse::Ok().json(ApiResponse::success(blockchain.data_access(&path.into_inner())))
+}
+
 // ============ Router Configuration ============
 
 pub fn configure_data_routes(cfg: &mut web::ServiceConfig) {
     cfg
-        // IoT routes
-        .route("/api/iot/transactions", web::get().to(get_iot_transactions))
-        .route("/api/iot/sectors", web::get().to(get_iot_sectors))
-        
-        // Validator routes (new)
-        .route("/api/validators/nodes", web::get().to(get_validator_nodes))
-        .route("/api/validators/map", web::get().to(get_validator_map))
-        
         // Enhanced network stats
-        .route("/api/network/stats", web::get().to(get_network_stats_enhanced));
+        .route("/api/network/stats", web::get().to(get_network_stats_enhanced))
+        // Data marketplace
+        .route("/api/data/list", web::post().to(list_data_hash))
+        .route("/api/data/listings", web::get().to(get_data_listings))
+        .route("/api/data/purchase", web::post().to(purchase_listing))
+        .route("/api/data/access/{address}", web::get().to(get_data_access));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use actix_web::{test, App};
+    use tokio::sync::RwLock;
+    use crate::blockchain::chain::tests::test_chain;
+    use crate::blockchain::chain::DataEntry;
+    use crate::blockchain::transaction::canonical_sign_message;
+    use crate::blockchain::Transaction;
+    use crate::consensus::PoIEConsensus;
+    use crate::crypto::Wallet;
+    use crate::data_market::DataMarketplace;
+    use crate::network::{NetworkManager, NodeType};
+
+    fn data_entry(hash: &str, owner: &str) -> DataEntry {
+        DataEntry {
+            hash: hash.to_string(),
+            owner: owner.to_string(),
+            price: 0,
+            quality_score: 0.8,
+            timestamp: 0,
+            purchases: 0,
+            category: "iot".to_string(),
+        }
+    }
+
+    fn signed_listing(wallet: &Wallet, data_hash: &str, price: u64) -> serde_json::Value {
+        let request = ListDataHashRequest {
+            owner: wallet.address().to_string(),
+            data_hash: data_hash.to_string(),
+            title: format!("Readings {}", price),
+            description: String::new(),
+            category: "iot".to_string(),
+            price,
+        };
+        let message = serde_json::to_vec(&request).unwrap();
+        serde_json::json!({
+            "data": request,
+            "auth": {
+                "public_key": wallet.public_key_hex(),
+                "signature": wallet.sign(&message),
+                "timestamp": chrono::Utc::now().timestamp(),
+            },
+        })
+    }
+
+    #[actix_web::test]
+    async fn test_list_query_and_purchase_data() {
+        let (owner, buyer) = (Wallet::new(), Wallet::new());
+        let (cheap, dear) = (Transaction::hash_data("cheap readings"), Transaction::hash_data("dear readings"));
+        let mut chain = test_chain();
+        for hash in [&cheap, &dear] {
+            chain.state.data_registry.insert(hash.clone(), data_entry(hash, owner.address()));
+        }
+        chain.apply_transaction(&Transaction::transfer("genesis".to_string(), buyer.address().to_string(), 1_000_000), None).unwrap();
+
+        let app_state = web::Data::new(AppState {
+            reorg: chain.reorg_circuit(),
+            blockchain: Arc::new(RwLock::new(chain)),
+            consensus: Arc::new(RwLock::new(PoIEConsensus::new())),
+            marketplace: Arc::new(RwLock::new(DataMarketplace::new())),
+            network: Arc::new(NetworkManager::new("test".to_string(), NodeType::FullNode, 0)),
+            migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+            block_migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+        });
+        let app = test::init_service(App::new().app_data(app_state.clone()).configure(configure_data_routes)).await;
+
+        for (hash, price) in [(&dear, 500), (&cheap, 100)] {
+            let req = test::TestRequest::post().uri("/api/data/list").set_json(signed_listing(&owner, hash, price)).to_request();
+            assert_eq!(test::call_service(&app, req).await.status(), 200);
+        }
+        // Someone else can't list the owner's data
+        let req = test::TestRequest::post().uri("/api/data/list").set_json(signed_listing(&buyer, &cheap, 1)).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 403);
+
+        let req = test::TestRequest::get().uri("/api/data/listings?category=iot&sort_by=price_asc&offset=1&limit=1").to_request();
+        let page: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(page["data"]["total"], 2);
+        assert_eq!(page["data"]["items"][0]["data_hash"], dear.as_str());
+        let req = test::TestRequest::get().uri("/api/data/listings?sort_by=price_desc&limit=1").to_request();
+        let page: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(page["data"]["items"][0]["price"], 500);
+
+        let unsigned = Transaction::data_purchase(buyer.address().to_string(), owner.address().to_string(), cheap.clone(), 100)
+            .with_nonce(0);
+        let req = test::TestRequest::post().uri("/api/data/purchase").set_json(serde_json::json!({
+            "buyer": buyer.address(),
+            "data_hash": cheap,
+            "nonce": 0,
+            "public_key": buyer.public_key_hex(),
+            "signature": buyer.sign(canonical_sign_message(&unsigned).as_bytes()),
+        })).to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["success"], true, "{}", resp);
+        // Not counted while the transaction is only pending
+        assert_eq!(app_state.marketplace.read().await.get_listing(&cheap).unwrap().total_purchases, 0);
+
+        // Access is granted and the purchase recorded once it is in a block
+        let block = app_state.blockchain.write().await.mine_block("miner".to_string()).unwrap();
+        let mut marketplace = app_state.marketplace.write().await;
+        assert_eq!(marketplace.record_block_purchases(&block).len(), 1);
+        assert!(marketplace.record_block_purchases(&block).is_empty());
+        assert_eq!(marketplace.get_listing(&cheap).unwrap().total_purchases, 1);
+        drop(marketplace);
+        let req = test::TestRequest::get().uri(&format!("/api/data/access/{}", buyer.address())).to_request();
+        let access: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(access["data"], serde_json::json!([cheap]));
+    }
 }
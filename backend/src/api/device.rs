@@ -12,7 +12,10 @@ use tokio::sync::RwLock;
 use log::info;
 
 use crate::consensus::{DeviceRegistry, DeviceType, GeoRegion, Device};
-use super::rest::ApiResponse;
+use crate::crypto::address_from_public_key;
+use super::auth::{create_sign_message, verify_signed_request, AuthData};
+use super::error::ApiError;
+use super::rest::{ApiResponse, AppState};
 
 /// Device registry state (shared across handlers)
 pub struct DeviceState {
@@ -30,6 +33,18 @@ pub struct RegisterDeviceRequest {
     pub latitude: Option<i32>,
     pub longitude: Option<i32>,
     pub metadata: Option<std::collections::HashMap<String, String>>,
+    /// Owner address recorded in the registry. The chain credits the owner's
+    /// reward share only once the device signs a `SetDeviceOwner` transaction.
+    pub owner: Option<String>,
+}
+
+/// Query for `GET /api/devices/nearby`
+#[derive(Debug, Deserialize)]
+pub struct NearbyQuery {
+    pub lat: f64,
+    pub lng: f64,
+    /// Search radius in km
+    pub radius: f64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -39,6 +54,14 @@ pub struct RecordContributionRequest {
     pub points: f64,
 }
 
+/// A freshly minted API key; `api_key` is only ever returned here
+#[derive(Debug, Serialize)]
+pub struct ApiKeyResponse {
+    pub device_id: String,
+    pub key_id: String,
+    pub api_key: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DeviceResponse {
     pub device_id: String,
@@ -50,6 +73,9 @@ pub struct DeviceResponse {
     pub is_active: bool,
     pub is_verified: bool,
     pub validator_weight: f64,
+    pub owner: Option<String>,
+    pub firmware_version: Option<String>,
+    pub bond: u64,
 }
 
 impl From<&Device> for DeviceResponse {
@@ -64,38 +90,50 @@ impl From<&Device> for DeviceResponse {
             is_active: device.is_active,
             is_verified: device.is_verified,
             validator_weight: device.validator_weight(),
+            owner: device.owner.clone(),
+            firmware_version: device.firmware_version.clone(),
+            bond: device.bond,
         }
     }
 }
 
+/// A newly registered device and the bond registration currently requires
+#[derive(Debug, Serialize)]
+pub struct RegisterDeviceResponse {
+    #[serde(flatten)]
+    pub device: DeviceResponse,
+    pub required_bond: u64,
+}
+
+/// A device returned by a radius search
+#[derive(Debug, Serialize)]
+pub struct NearbyDeviceResponse {
+    #[serde(flatten)]
+    pub device: DeviceResponse,
+    pub distance_km: f64,
+}
+
 // ============ Helper Functions ============
 
-fn parse_device_type(type_str: &str) -> DeviceType {
-    match type_str.to_lowercase().as_str() {
-        "sensor" => DeviceType::Sensor,
-        "camera" => DeviceType::Camera,
-        "audio" => DeviceType::Audio,
-        "location" => DeviceType::Location,
-        "industrial" => DeviceType::Industrial,
-        "smarthome" | "smart_home" => DeviceType::SmartHome,
-        "wearable" => DeviceType::Wearable,
-        "vehicle" => DeviceType::Vehicle,
-        "environmental" => DeviceType::Environmental,
-        "medical" => DeviceType::Medical,
-        "agricultural" => DeviceType::Agricultural,
-        "energy" => DeviceType::Energy,
-        _ => DeviceType::Custom(type_str.to_string()),
-    }
+fn registration_rejected(status: actix_web::http::StatusCode, message: &str, required_bond: u64) -> HttpResponse {
+    HttpResponse::build(status).json(ApiResponse {
+        success: false,
+        data: Some(serde_json::json!({ "required_bond": required_bond })),
+        error: Some(message.to_string()),
+    })
 }
 
 // ============ Device Registry Endpoints ============
 
-/// Register a new device
+/// Register a new device. When the chain requires a device bond, the
+/// device's address must already hold one, locked by a `DeviceBond`
+/// transaction.
 pub async fn register_device(
     data: web::Data<DeviceState>,
+    app: web::Data<AppState>,
     body: web::Json<RegisterDeviceRequest>,
 ) -> impl Responder {
-    let device_type = parse_device_type(&body.device_type);
+    let device_type = DeviceType::from_name(&body.device_type);
     
     let region = match (body.latitude, body.longitude) {
         (Some(lat), Some(lon)) => GeoRegion::with_coordinates(&body.country_code, lat, lon),
@@ -106,17 +144,61 @@ pub async fn register_device(
         }
     };
     
-    let mut registry = data.registry.write().await;
+    // The chain knows the device by the address of its key, which signs its contributions
+    let device_address = address_from_public_key(&body.public_key).ok();
+    // Read the chain before locking the registry; block production takes them in that order
+    let (required_bond, bond) = {
+        let chain = app.blockchain.read().await;
+        let bond = device_address.as_ref()
+            .and_then(|address| chain.state.device_bonds.get(address))
+            .filter(|bond| bond.release_height.is_none())
+            .map_or(0, |bond| bond.amount);
+        (chain.state.params.min_device_bond, bond)
+    };
+    if device_address.is_none() {
+        return registration_rejected(actix_web::http::StatusCode::BAD_REQUEST, "Invalid device public key", required_bond);
+    }
     
-    match registry.register_device(body.public_key.clone(), device_type, region) {
-        Ok(device) => {
+    let mut registry = data.registry.write().await;
+    match registry.register_device_with_bond(body.public_key.clone(), device_type, region, bond, required_bond) {
+        Ok(mut device) => {
             info!("Device registered: {} ({:?}) in {}", 
                 &device.device_id, device.device_type, device.region.country_code);
             
-            let response = DeviceResponse::from(&device);
-            HttpResponse::Ok().json(ApiResponse::success(response))
+            if let Some(owner) = &body.owner {
+                let _ = registry.set_owner(&device.device_id, Some(owner.clone()));
+                device.owner = Some(owner.clone());
+            }
+            
+            HttpResponse::Ok().json(ApiResponse::success(RegisterDeviceResponse {
+                device: DeviceResponse::from(&device),
+                required_bond,
+            }))
         }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+        Err(e) => registration_rejected(actix_web::http::StatusCode::BAD_REQUEST, &e, required_bond),
+    }
+}
+
+/// Unregister a device (signed by the device key). A bond stays locked until
+/// its owner releases it with a `DeviceUnbond` transaction.
+pub async fn unregister_device(
+    data: web::Data<DeviceState>,
+    path: web::Path<String>,
+    body: web::Json<AuthData>,
+) -> impl Responder {
+    let device_id = path.into_inner();
+    let mut registry = data.registry.write().await;
+    let request_path = format!("/api/devices/{}", device_id);
+    if let Some(response) = reject_unauthorized_device_request(&registry, &device_id, &body, "DELETE", &request_path) {
+        return response;
+    }
+    
+    match registry.unregister_device(&device_id) {
+        Ok(()) => HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+            "device_id": device_id,
+            "unregistered": true,
+        }))),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(&e)),
     }
 }
 
@@ -150,6 +232,33 @@ pub async fn get_all_devices(
     HttpResponse::Ok().json(ApiResponse::success(devices))
 }
 
+/// Find devices within a radius of a point (GET /api/devices/nearby?lat=&lng=&radius=)
+pub async fn get_nearby_devices(
+    data: web::Data<DeviceState>,
+    query: web::Query<NearbyQuery>,
+) -> impl Responder {
+    if !(-90.0..=90.0).contains(&query.lat) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("lat must be between -90 and 90"));
+    }
+    if !query.lng.is_finite() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("lng must be a finite number"));
+    }
+    if !(query.radius.is_finite() && query.radius >= 0.0) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("radius must be a non-negative number of km"));
+    }
+    let registry = data.registry.read().await;
+
+    let devices: Vec<NearbyDeviceResponse> = registry.devices_within_radius(query.lat, query.lng, query.radius)
+        .into_iter()
+        .map(|device| NearbyDeviceResponse {
+            distance_km: device.region.distance_km(query.lat, query.lng).unwrap_or_default(),
+            device: DeviceResponse::from(device),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(ApiResponse::success(devices))
+}
+
 /// Get eligible validators (devices that can validate blocks)
 pub async fn get_eligible_validators(
     data: web::Data<DeviceState>,
@@ -180,17 +289,17 @@ pub async fn record_contribution(
 ) -> impl Responder {
     let mut registry = data.registry.write().await;
     
-    match registry.get_device_mut(&body.device_id) {
-        Some(device) => {
-            device.record_contribution(body.quality_score, body.points);
-            
+    match registry.record_contribution(&body.device_id, body.quality_score, body.points) {
+        Ok(awarded) => {
             info!("Contribution recorded for {}: quality={:.2}, points={:.2}", 
-                &body.device_id, body.quality_score, body.points);
+                &body.device_id, body.quality_score, awarded);
             
-            let response = DeviceResponse::from(&*device);
-            HttpResponse::Ok().json(ApiResponse::success(response))
+            match registry.get_device(&body.device_id) {
+                Some(device) => HttpResponse::Ok().json(ApiResponse::success(DeviceResponse::from(device))),
+                None => HttpResponse::NotFound().json(ApiResponse::<()>::error("Device not found")),
+            }
         }
-        None => HttpResponse::NotFound().json(ApiResponse::<()>::error("Device not found")),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(&e)),
     }
 }
 
@@ -225,7 +334,7 @@ pub async fn get_type_scarcity(
     let type_str = path.into_inner();
     let registry = data.registry.read().await;
     
-    let device_type = parse_device_type(&type_str);
+    let device_type = DeviceType::from_name(&type_str);
     let scarcity = registry.get_type_scarcity(&device_type);
     
     #[derive(Serialize)]
@@ -240,6 +349,72 @@ pub async fn get_type_scarcity(
     }))
 }
 
+/// Check that `auth` is signed by the device's own key over
+/// `{method}:{path}:{timestamp}:{sha256("")}`; returns the error response
+/// when it isn't
+fn reject_unauthorized_device_request(
+    registry: &DeviceRegistry,
+    device_id: &str,
+    auth: &AuthData,
+    method: &str,
+    path: &str,
+) -> Option<HttpResponse> {
+    let Some(device) = registry.get_device(device_id) else {
+        return Some(HttpResponse::NotFound().json(ApiResponse::<()>::error("Device not found")));
+    };
+    if auth.public_key != device.public_key {
+        return Some(HttpResponse::Forbidden().json(ApiResponse::<()>::error("Request must be signed by the device key")));
+    }
+    let message = create_sign_message(method, path, auth.timestamp, b"");
+    verify_signed_request(auth, &message, None, 300).err().map(|e| e.error_response())
+}
+
+/// Mint an API key for a device (signed by the device key)
+pub async fn create_api_key(
+    data: web::Data<DeviceState>,
+    path: web::Path<String>,
+    body: web::Json<AuthData>,
+) -> impl Responder {
+    let device_id = path.into_inner();
+    let mut registry = data.registry.write().await;
+    let request_path = format!("/api/devices/{}/api-keys", device_id);
+    if let Some(response) = reject_unauthorized_device_request(&registry, &device_id, &body, "POST", &request_path) {
+        return response;
+    }
+    
+    match registry.create_api_key(&device_id) {
+        Ok((key_id, api_key)) => HttpResponse::Ok().json(ApiResponse::success(ApiKeyResponse {
+            device_id,
+            key_id,
+            api_key,
+        })),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(&e)),
+    }
+}
+
+/// Revoke a device API key (signed by the device key)
+pub async fn revoke_api_key(
+    data: web::Data<DeviceState>,
+    path: web::Path<(String, String)>,
+    body: web::Json<AuthData>,
+) -> impl Responder {
+    let (device_id, key_id) = path.into_inner();
+    let mut registry = data.registry.write().await;
+    let request_path = format!("/api/devices/{}/api-keys/{}", device_id, key_id);
+    if let Some(response) = reject_unauthorized_device_request(&registry, &device_id, &body, "DELETE", &request_path) {
+        return response;
+    }
+    
+    match registry.revoke_api_key(&device_id, &key_id) {
+        Ok(()) => HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+            "device_id": device_id,
+            "key_id": key_id,
+            "revoked": true,
+        }))),
+        Err(e) => HttpResponse::NotFound().json(ApiResponse::<()>::error(&e)),
+    }
+}
+
 // ============ Router Configuration ============
 
 pub fn configure_device_routes(cfg: &mut web::ServiceConfig) {
@@ -249,7 +424,11 @@ pub fn configure_device_routes(cfg: &mut web::ServiceConfig) {
         .route("/api/devices/register", web::post().to(register_device))
         .route("/api/devices/stats", web::get().to(get_device_stats))
         .route("/api/devices/validators", web::get().to(get_eligible_validators))
+        .route("/api/devices/nearby", web::get().to(get_nearby_devices))
         .route("/api/devices/{device_id}", web::get().to(get_device))
+        .route("/api/devices/{device_id}", web::delete().to(unregister_device))
+        .route("/api/devices/{device_id}/api-keys", web::post().to(create_api_key))
+        .route("/api/devices/{device_id}/api-keys/{key_id}", web::delete().to(revoke_api_key))
         .route("/api/devices/contribute", web::post().to(record_contribution))
         .route("/api/devices/scarcity/region/{country_code}", web::get().to(get_region_scarcity))
         .route("/api/devices/scarcity/type/{type}", web::get().to(get_type_scarcity));
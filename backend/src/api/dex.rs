@@ -1,11 +1,13 @@
 use actix_web::{web, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use log::info;
 
 use crate::api::rest::ApiResponse;
+use crate::api::auth::{create_sign_message, verify_signed_request, AuthData};
+use crate::api::error::ApiError;
 
 /// Trading pair information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +21,30 @@ pub struct TradingPair {
     pub fee_rate: f64,           // Trading fee (e.g., 0.003 = 0.3%)
     pub volume_24h: u64,
     pub created_at: i64,
+    /// Sum of price × seconds held, up to `price_last_update`
+    #[serde(default)]
+    pub price_cumulative: f64,
+    /// Unix time `price_cumulative` was last brought up to date
+    #[serde(default)]
+    pub price_last_update: i64,
+    /// Earlier points on the cumulative price curve, oldest first; kept out
+    /// of pair listings
+    #[serde(default, skip_serializing)]
+    pub price_observations: VecDeque<PriceObservation>,
+}
+
+/// Price checkpoints kept per pair; TWAP windows reach back at most this
+/// many price changes
+pub const MAX_PRICE_OBSERVATIONS: usize = 1024;
+
+/// Default TWAP window in seconds
+pub const DEFAULT_TWAP_WINDOW: i64 = 3600;
+
+/// A point on a pair's cumulative price curve
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceObservation {
+    pub timestamp: i64,
+    pub price_cumulative: f64,
 }
 
 impl TradingPair {
@@ -30,6 +56,55 @@ impl TradingPair {
         self.quote_reserve as f64 / self.base_reserve as f64
     }
     
+    /// Accumulate the current price over the time since the last update.
+    /// Called before the reserves change, so each interval is weighted by
+    /// the price that held during it.
+    pub fn update_price_accumulator(&mut self, now: i64) {
+        let elapsed = now - self.price_last_update;
+        if elapsed <= 0 {
+            return;
+        }
+        self.price_observations.push_back(PriceObservation {
+            timestamp: self.price_last_update,
+            price_cumulative: self.price_cumulative,
+        });
+        if self.price_observations.len() > MAX_PRICE_OBSERVATIONS {
+            self.price_observations.pop_front();
+        }
+        self.price_cumulative += self.get_price() * elapsed as f64;
+        self.price_last_update = now;
+    }
+    
+    /// Cumulative price at `t`: interpolated between checkpoints (the price
+    /// is constant between them) and extended at the spot price after the
+    /// last update
+    fn price_cumulative_at(&self, t: i64) -> f64 {
+        if t >= self.price_last_update {
+            return self.price_cumulative + self.get_price() * (t - self.price_last_update) as f64;
+        }
+        let latest = PriceObservation { timestamp: self.price_last_update, price_cumulative: self.price_cumulative };
+        let points: Vec<PriceObservation> = self.price_observations.iter().copied().chain([latest]).collect();
+        points.windows(2)
+            .find(|w| t <= w[1].timestamp)
+            .map(|w| {
+                let fraction = (t - w[0].timestamp) as f64 / (w[1].timestamp - w[0].timestamp) as f64;
+                w[0].price_cumulative + (w[1].price_cumulative - w[0].price_cumulative) * fraction
+            })
+            .unwrap_or(points[0].price_cumulative)
+    }
+    
+    /// Time-weighted average price over the `window` seconds before `now`, or
+    /// over the pair's recorded history if that is shorter. `None` when no
+    /// time has passed since the oldest checkpoint.
+    pub fn twap(&self, window: i64, now: i64) -> Option<f64> {
+        let oldest = self.price_observations.front().map(|o| o.timestamp).unwrap_or(self.price_last_update);
+        let start = (now - window).max(oldest);
+        if now <= start {
+            return None;
+        }
+        Some((self.price_cumulative_at(now) - self.price_cumulative_at(start)) / (now - start) as f64)
+    }
+    
     /// Calculate output amount for a swap (constant product formula: x * y = k)
     pub fn calculate_swap_output(&self, amount_in: u64, is_base_to_quote: bool) -> (u64, u64) {
         let fee = (amount_in as f64 * self.fee_rate) as u64;
@@ -51,6 +126,25 @@ impl TradingPair {
         (amount_out, fee)
     }
     
+    /// How far a swap would move the pool price, in percent
+    pub fn price_impact(&self, amount_in: u64, is_base_to_quote: bool) -> f64 {
+        let current_price = self.get_price();
+        if current_price == 0.0 {
+            return 0.0;
+        }
+        let (amount_out, _) = self.calculate_swap_output(amount_in, is_base_to_quote);
+        let (new_base, new_quote) = if is_base_to_quote {
+            (self.base_reserve + amount_in, self.quote_reserve - amount_out)
+        } else {
+            (self.base_reserve - amount_out, self.quote_reserve + amount_in)
+        };
+        if new_base == 0 {
+            return 100.0;
+        }
+        let new_price = new_quote as f64 / new_base as f64;
+        ((new_price - current_price) / current_price * 100.0).abs()
+    }
+    
     /// Calculate liquidity tokens to mint for adding liquidity
     pub fn calculate_liquidity_mint(&self, base_amount: u64, quote_amount: u64) -> u64 {
         if self.total_liquidity == 0 {
@@ -111,6 +205,30 @@ pub enum OrderStatus {
     Cancelled,
 }
 
+impl Order {
+    pub fn remaining(&self) -> u64 {
+        self.amount.saturating_sub(self.filled)
+    }
+
+    /// Whether this order will trade against a resting order at `price`
+    fn crosses(&self, price: f64) -> bool {
+        match (&self.order_type, &self.side) {
+            (OrderType::Market, _) => true,
+            (OrderType::Limit, OrderSide::Buy) => price <= self.price,
+            (OrderType::Limit, OrderSide::Sell) => price >= self.price,
+        }
+    }
+
+    fn record_fill(&mut self, amount: u64) {
+        self.filled += amount;
+        self.status = if self.remaining() == 0 {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+    }
+}
+
 /// Trade history record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
@@ -129,6 +247,7 @@ pub struct Trade {
 pub struct DexManager {
     pub pairs: HashMap<String, TradingPair>,
     pub positions: HashMap<String, Vec<LiquidityPosition>>,
+    /// Resting limit orders by pair id, in arrival order
     pub orders: HashMap<String, Vec<Order>>,
     pub trades: Vec<Trade>,
 }
@@ -161,6 +280,9 @@ impl DexManager {
             fee_rate: 0.003,                // 0.3%
             volume_24h: 1_250_000,
             created_at: now,
+            price_cumulative: 0.0,
+            price_last_update: now,
+            price_observations: VecDeque::new(),
         };
         
         // EDGE/BTC pair
@@ -174,6 +296,9 @@ impl DexManager {
             fee_rate: 0.003,
             volume_24h: 500_000,
             created_at: now,
+            price_cumulative: 0.0,
+            price_last_update: now,
+            price_observations: VecDeque::new(),
         };
         
         // EDGE/ETH pair
@@ -187,6 +312,9 @@ impl DexManager {
             fee_rate: 0.003,
             volume_24h: 800_000,
             created_at: now,
+            price_cumulative: 0.0,
+            price_last_update: now,
+            price_observations: VecDeque::new(),
         };
         
         // DATA/EDGE pair (IoT data token)
@@ -200,6 +328,9 @@ impl DexManager {
             fee_rate: 0.002,                // Lower fee for ecosystem token
             volume_24h: 300_000,
             created_at: now,
+            price_cumulative: 0.0,
+            price_last_update: now,
+            price_observations: VecDeque::new(),
         };
         
         self.pairs.insert(edge_usdt.id.clone(), edge_usdt);
@@ -240,8 +371,29 @@ impl DexManager {
         }
     }
     
-    /// Execute a swap
-    pub fn swap(&mut self, pair_id: &str, amount_in: u64, is_base_to_quote: bool, user: &str) -> Result<Trade, String> {
+    /// Execute a swap. Fails without touching the reserves if the output
+    /// would be below `min_amount_out`.
+    pub fn swap(
+        &mut self,
+        pair_id: &str,
+        amount_in: u64,
+        is_base_to_quote: bool,
+        user: &str,
+        min_amount_out: Option<u64>,
+    ) -> Result<Trade, String> {
+        self.swap_at(pair_id, amount_in, is_base_to_quote, user, min_amount_out, chrono::Utc::now().timestamp())
+    }
+    
+    /// Execute a swap at unix time `now`
+    pub fn swap_at(
+        &mut self,
+        pair_id: &str,
+        amount_in: u64,
+        is_base_to_quote: bool,
+        user: &str,
+        min_amount_out: Option<u64>,
+        now: i64,
+    ) -> Result<Trade, String> {
         let pair = self.pairs.get_mut(pair_id)
             .ok_or_else(|| "Trading pair not found".to_string())?;
         
@@ -250,8 +402,14 @@ impl DexManager {
         if amount_out == 0 {
             return Err("Insufficient liquidity".to_string());
         }
+        if let Some(min) = min_amount_out {
+            if amount_out < min {
+                return Err(format!("slippage exceeded: got {}, min {}", amount_out, min));
+            }
+        }
         
         // Update reserves
+        pair.update_price_accumulator(now);
         if is_base_to_quote {
             pair.base_reserve += amount_in;
             pair.quote_reserve -= amount_out;
@@ -271,7 +429,7 @@ impl DexManager {
             amount: amount_in,
             total: amount_out,
             fee,
-            timestamp: chrono::Utc::now().timestamp(),
+            timestamp: now,
         };
         
         self.trades.push(trade.clone());
@@ -291,6 +449,7 @@ impl DexManager {
         }
         
         // Update pair reserves
+        pair.update_price_accumulator(chrono::Utc::now().timestamp());
         pair.base_reserve += base_amount;
         pair.quote_reserve += quote_amount;
         pair.total_liquidity += lp_tokens;
@@ -311,6 +470,142 @@ impl DexManager {
         
         Ok(position)
     }
+    
+    /// Place an order on the book. Crossing resting orders on the opposite
+    /// side are filled at their own price, best price first and oldest first
+    /// within a price. A limit order's remainder rests on the book; a market
+    /// order's remainder is dropped once the book is exhausted.
+    pub fn place_order(&mut self, mut order: Order) -> Result<Vec<Trade>, String> {
+        let fee_rate = self.pairs.get(&order.pair_id)
+            .ok_or_else(|| "Trading pair not found".to_string())?
+            .fee_rate;
+        if order.amount == 0 {
+            return Err("Order amount must be positive".to_string());
+        }
+        if order.order_type == OrderType::Limit && !(order.price.is_finite() && order.price > 0.0) {
+            return Err("Limit price must be positive".to_string());
+        }
+        order.filled = 0;
+        order.status = OrderStatus::Open;
+        
+        let book = self.orders.entry(order.pair_id.clone()).or_default();
+        if book.iter().any(|o| o.id == order.id) {
+            return Err("Duplicate order id".to_string());
+        }
+        
+        // Opposite-side orders that cross, in price-time priority
+        let mut candidates: Vec<usize> = (0..book.len())
+            .filter(|&i| book[i].side != order.side && order.crosses(book[i].price))
+            .collect();
+        candidates.sort_by(|&a, &b| {
+            let by_price = if order.side == OrderSide::Buy {
+                book[a].price.total_cmp(&book[b].price)
+            } else {
+                book[b].price.total_cmp(&book[a].price)
+            };
+            by_price.then(book[a].created_at.cmp(&book[b].created_at))
+        });
+        
+        let now = chrono::Utc::now().timestamp();
+        let mut trades = Vec::new();
+        for i in candidates {
+            if order.remaining() == 0 {
+                break;
+            }
+            let resting = &mut book[i];
+            let amount = order.remaining().min(resting.remaining());
+            let price = resting.price;
+            resting.record_fill(amount);
+            order.record_fill(amount);
+            
+            let (buyer, seller) = match order.side {
+                OrderSide::Buy => (order.owner.clone(), resting.owner.clone()),
+                OrderSide::Sell => (resting.owner.clone(), order.owner.clone()),
+            };
+            trades.push(Trade {
+                id: format!("trade_{}_{}", order.id, trades.len()),
+                pair_id: order.pair_id.clone(),
+                buyer,
+                seller,
+                price,
+                amount,
+                total: (price * amount as f64) as u64,
+                fee: (amount as f64 * fee_rate) as u64,
+                timestamp: now,
+            });
+        }
+        
+        book.retain(|o| o.status != OrderStatus::Filled);
+        if order.order_type == OrderType::Limit && order.remaining() > 0 {
+            book.push(order);
+        }
+        
+        self.trades.extend(trades.iter().cloned());
+        Ok(trades)
+    }
+    
+    /// Cancel a resting order owned by `user`
+    pub fn cancel_order(&mut self, order_id: &str, user: &str) -> Result<Order, String> {
+        for book in self.orders.values_mut() {
+            if let Some(pos) = book.iter().position(|o| o.id == order_id) {
+                if book[pos].owner != user {
+                    return Err("Only the order owner can cancel it".to_string());
+                }
+                let mut order = book.remove(pos);
+                order.status = OrderStatus::Cancelled;
+                return Ok(order);
+            }
+        }
+        Err("Order not found".to_string())
+    }
+    
+    /// Burn `lp_tokens` of the user's liquidity in a pool and return the
+    /// (base, quote) amounts owed. Payouts are proportional to current reserves,
+    /// so they include the trading fees accrued since the deposit.
+    pub fn remove_liquidity(&mut self, pair_id: &str, lp_tokens: u64, user: &str) -> Result<(u64, u64), String> {
+        if lp_tokens == 0 {
+            return Err("Insufficient amounts".to_string());
+        }
+        let pair = self.pairs.get_mut(pair_id)
+            .ok_or_else(|| "Trading pair not found".to_string())?;
+        let positions = self.positions.get_mut(user)
+            .ok_or_else(|| "No liquidity positions".to_string())?;
+        
+        let held: u64 = positions.iter()
+            .filter(|p| p.pair_id == pair_id)
+            .map(|p| p.lp_tokens)
+            .sum();
+        if lp_tokens > held || lp_tokens > pair.total_liquidity {
+            return Err(format!("Insufficient LP tokens: have {}, requested {}", held, lp_tokens));
+        }
+        
+        let base_out = (pair.base_reserve as u128 * lp_tokens as u128 / pair.total_liquidity as u128) as u64;
+        let quote_out = (pair.quote_reserve as u128 * lp_tokens as u128 / pair.total_liquidity as u128) as u64;
+        pair.update_price_accumulator(chrono::Utc::now().timestamp());
+        pair.base_reserve -= base_out;
+        pair.quote_reserve -= quote_out;
+        pair.total_liquidity -= lp_tokens;
+        
+        // Burn from the oldest positions first, shrinking deposits in proportion
+        let mut remaining = lp_tokens;
+        for position in positions.iter_mut().filter(|p| p.pair_id == pair_id) {
+            if remaining == 0 {
+                break;
+            }
+            let burn = remaining.min(position.lp_tokens);
+            let keep = position.lp_tokens - burn;
+            position.base_deposited = (position.base_deposited as u128 * keep as u128 / position.lp_tokens as u128) as u64;
+            position.quote_deposited = (position.quote_deposited as u128 * keep as u128 / position.lp_tokens as u128) as u64;
+            position.lp_tokens = keep;
+            remaining -= burn;
+        }
+        positions.retain(|p| p.lp_tokens > 0);
+        if positions.is_empty() {
+            self.positions.remove(user);
+        }
+        
+        Ok((base_out, quote_out))
+    }
 }
 
 /// DEX state shared across handlers
@@ -345,6 +640,39 @@ pub struct AddLiquidityRequest {
     pub user: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PlaceOrderRequest {
+    pub pair_id: String,
+    pub owner: String,
+    pub order_type: OrderType,
+    pub side: OrderSide,
+    /// Ignored for market orders
+    #[serde(default)]
+    pub price: f64,
+    pub amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CancelOrderRequest {
+    pub order_id: String,
+    pub user: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RemoveLiquidityRequest {
+    pub pair_id: String,
+    pub lp_tokens: u64,
+    pub user: String,
+    /// Signed by `user` over `remove_liquidity_sign_message`
+    pub auth: AuthData,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemoveLiquidityResponse {
+    pub base_amount: u64,
+    pub quote_amount: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreatePairRequest {
     pub base_token: String,
@@ -364,6 +692,24 @@ pub struct PairStats {
     pub low_24h: f64,
 }
 
+/// Query for `GET /api/dex/pairs/{pair_id}/twap`
+#[derive(Debug, Deserialize)]
+pub struct TwapQuery {
+    /// Averaging window in seconds (default `DEFAULT_TWAP_WINDOW`)
+    pub window: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwapResponse {
+    pub pair_id: String,
+    /// Requested window in seconds
+    pub window: i64,
+    /// Seconds actually averaged; shorter than `window` for young pairs
+    pub covered: i64,
+    pub twap: f64,
+    pub spot_price: f64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct SwapQuote {
     pub amount_in: u64,
@@ -414,6 +760,37 @@ pub async fn get_pair(
     }
 }
 
+/// Time-weighted average price of a pair (GET /api/dex/pairs/{pair_id}/twap?window=)
+pub async fn get_pair_twap(
+    data: web::Data<DexState>,
+    path: web::Path<String>,
+    query: web::Query<TwapQuery>,
+) -> impl Responder {
+    let pair_id = path.into_inner();
+    let window = query.window.unwrap_or(DEFAULT_TWAP_WINDOW);
+    if window <= 0 {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("window must be a positive number of seconds"));
+    }
+    let manager = data.manager.read().await;
+    let Some(pair) = manager.pairs.get(&pair_id) else {
+        return HttpResponse::NotFound().json(ApiResponse::<()>::error("Trading pair not found"));
+    };
+    let now = chrono::Utc::now().timestamp();
+    match pair.twap(window, now) {
+        Some(twap) => {
+            let oldest = pair.price_observations.front().map(|o| o.timestamp).unwrap_or(pair.price_last_update);
+            HttpResponse::Ok().json(ApiResponse::success(TwapResponse {
+                pair_id,
+                window,
+                covered: window.min(now - oldest),
+                twap,
+                spot_price: pair.get_price(),
+            }))
+        }
+        None => HttpResponse::BadRequest().json(ApiResponse::<()>::error("No price history yet for this pair")),
+    }
+}
+
 /// Get swap quote (preview)
 pub async fn get_swap_quote(
     data: web::Data<DexState>,
@@ -424,21 +801,7 @@ pub async fn get_swap_quote(
     match manager.pairs.get(&query.pair_id) {
         Some(pair) => {
             let (amount_out, fee) = pair.calculate_swap_output(query.amount_in, query.is_base_to_quote);
-            
-            // Calculate price impact
-            let current_price = pair.get_price();
-            let new_base = if query.is_base_to_quote {
-                pair.base_reserve + query.amount_in
-            } else {
-                pair.base_reserve - amount_out
-            };
-            let new_quote = if query.is_base_to_quote {
-                pair.quote_reserve - amount_out
-            } else {
-                pair.quote_reserve + query.amount_in
-            };
-            let new_price = new_quote as f64 / new_base as f64;
-            let price_impact = ((new_price - current_price) / current_price * 100.0).abs();
+            let price_impact = pair.price_impact(query.amount_in, query.is_base_to_quote);
             
             let quote = SwapQuote {
                 amount_in: query.amount_in,
@@ -461,7 +824,7 @@ pub async fn execute_swap(
 ) -> impl Responder {
     let mut manager = data.manager.write().await;
     
-    match manager.swap(&body.pair_id, body.amount_in, body.is_base_to_quote, &body.user) {
+    match manager.swap(&body.pair_id, body.amount_in, body.is_base_to_quote, &body.user, body.min_amount_out) {
         Ok(trade) => {
             info!("Swap executed: {} {} for {} in pair {}", 
                 body.amount_in, 
@@ -492,6 +855,34 @@ pub async fn add_liquidity(
     }
 }
 
+/// Message a liquidity provider signs to withdraw:
+/// `POST:/api/dex/liquidity/remove:{timestamp}:{sha256("{pair_id}:{lp_tokens}")}`
+pub fn remove_liquidity_sign_message(pair_id: &str, lp_tokens: u64, timestamp: u64) -> Vec<u8> {
+    let body = format!("{}:{}", pair_id, lp_tokens);
+    create_sign_message("POST", "/api/dex/liquidity/remove", timestamp, body.as_bytes())
+}
+
+/// Remove liquidity (signed by the position owner)
+pub async fn remove_liquidity(
+    data: web::Data<DexState>,
+    body: web::Json<RemoveLiquidityRequest>,
+) -> impl Responder {
+    let message = remove_liquidity_sign_message(&body.pair_id, body.lp_tokens, body.auth.timestamp);
+    if let Err(e) = verify_signed_request(&body.auth, &message, Some(&body.user), 300) {
+        return e.error_response();
+    }
+    let mut manager = data.manager.write().await;
+    
+    match manager.remove_liquidity(&body.pair_id, body.lp_tokens, &body.user) {
+        Ok((base_amount, quote_amount)) => {
+            info!("Liquidity removed: {} LP tokens from {} by {} for {} base + {} quote",
+                body.lp_tokens, body.pair_id, body.user, base_amount, quote_amount);
+            HttpResponse::Ok().json(ApiResponse::success(RemoveLiquidityResponse { base_amount, quote_amount }))
+        }
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    }
+}
+
 /// Create new trading pair
 pub async fn create_pair(
     data: web::Data<DexState>,
@@ -506,6 +897,7 @@ pub async fn create_pair(
     }
     
     let lp_tokens = ((body.initial_base_amount as f64 * body.initial_quote_amount as f64).sqrt()) as u64;
+    let now = chrono::Utc::now().timestamp();
     
     let pair = TradingPair {
         id: pair_id.clone(),
@@ -516,7 +908,10 @@ pub async fn create_pair(
         total_liquidity: lp_tokens,
         fee_rate: body.fee_rate.unwrap_or(0.003),
         volume_24h: 0,
-        created_at: chrono::Utc::now().timestamp(),
+        created_at: now,
+        price_cumulative: 0.0,
+        price_last_update: now,
+        price_observations: VecDeque::new(),
     };
     
     manager.pairs.insert(pair_id.clone(), pair.clone());
@@ -558,6 +953,65 @@ pub async fn get_trades(
     HttpResponse::Ok().json(ApiResponse::success(trades))
 }
 
+/// Place a limit or market order
+pub async fn place_order(
+    data: web::Data<DexState>,
+    body: web::Json<PlaceOrderRequest>,
+) -> impl Responder {
+    let mut manager = data.manager.write().await;
+    let body = body.into_inner();
+    
+    let order = Order {
+        id: format!("order_{}", uuid::Uuid::new_v4()),
+        pair_id: body.pair_id,
+        owner: body.owner,
+        order_type: body.order_type,
+        side: body.side,
+        price: body.price,
+        amount: body.amount,
+        filled: 0,
+        status: OrderStatus::Open,
+        created_at: chrono::Utc::now().timestamp(),
+    };
+    let order_id = order.id.clone();
+    
+    match manager.place_order(order) {
+        Ok(trades) => {
+            info!("Order {} placed with {} fills", order_id, trades.len());
+            HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+                "order_id": order_id,
+                "trades": trades,
+            })))
+        }
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    }
+}
+
+/// Cancel a resting order
+pub async fn cancel_order(
+    data: web::Data<DexState>,
+    body: web::Json<CancelOrderRequest>,
+) -> impl Responder {
+    let mut manager = data.manager.write().await;
+    
+    match manager.cancel_order(&body.order_id, &body.user) {
+        Ok(order) => HttpResponse::Ok().json(ApiResponse::success(order)),
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    }
+}
+
+/// Get the resting orders for a pair
+pub async fn get_order_book(
+    data: web::Data<DexState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let pair_id = path.into_inner();
+    let manager = data.manager.read().await;
+    
+    let orders = manager.orders.get(&pair_id).cloned().unwrap_or_default();
+    HttpResponse::Ok().json(ApiResponse::success(orders))
+}
+
 /// Get user's liquidity positions
 pub async fn get_user_positions(
     data: web::Data<DexState>,
@@ -578,10 +1032,228 @@ pub fn configure_dex_routes(cfg: &mut web::ServiceConfig, dex_state: web::Data<D
     cfg.app_data(dex_state)
         .route("/api/dex/pairs", web::get().to(get_pairs))
         .route("/api/dex/pairs/{pair_id}", web::get().to(get_pair))
+        .route("/api/dex/pairs/{pair_id}/twap", web::get().to(get_pair_twap))
         .route("/api/dex/quote", web::get().to(get_swap_quote))
         .route("/api/dex/swap", web::post().to(execute_swap))
         .route("/api/dex/liquidity", web::post().to(add_liquidity))
+        .route("/api/dex/liquidity/remove", web::post().to(remove_liquidity))
         .route("/api/dex/pairs/create", web::post().to(create_pair))
         .route("/api/dex/trades/{pair_id}", web::get().to(get_trades))
+        .route("/api/dex/orders", web::post().to(place_order))
+        .route("/api/dex/orders/cancel", web::post().to(cancel_order))
+        .route("/api/dex/orders/{pair_id}", web::get().to(get_order_book))
         .route("/api/dex/positions/{user}", web::get().to(get_user_positions));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thin_pool() -> DexManager {
+        let mut manager = DexManager::new();
+        manager.pairs.insert("THIN-EDGE".to_string(), TradingPair {
+            id: "THIN-EDGE".to_string(),
+            base_token: "THIN".to_string(),
+            quote_token: "EDGE".to_string(),
+            base_reserve: 1_000,
+            quote_reserve: 1_000,
+            total_liquidity: 1_000,
+            fee_rate: 0.003,
+            volume_24h: 0,
+            created_at: 0,
+            price_cumulative: 0.0,
+            price_last_update: 0,
+            price_observations: VecDeque::new(),
+        });
+        manager
+    }
+
+    #[test]
+    fn test_swap_rejected_below_min_amount_out() {
+        let mut manager = thin_pool();
+        let pair = manager.pairs["THIN-EDGE"].clone();
+
+        // A swap as large as the pool moves the price heavily
+        let (expected_out, _) = pair.calculate_swap_output(1_000, true);
+        assert!(expected_out < 500);
+        assert!(pair.price_impact(1_000, true) > 50.0);
+        assert!(pair.price_impact(1, true) < 1.0);
+
+        let err = manager.swap("THIN-EDGE", 1_000, true, "alice", Some(900)).unwrap_err();
+        assert_eq!(err, format!("slippage exceeded: got {}, min 900", expected_out));
+        let after = &manager.pairs["THIN-EDGE"];
+        assert_eq!((after.base_reserve, after.quote_reserve), (1_000, 1_000));
+
+        let trade = manager.swap("THIN-EDGE", 1_000, true, "alice", Some(expected_out)).unwrap();
+        assert_eq!(trade.total, expected_out);
+        assert_eq!(manager.pairs["THIN-EDGE"].quote_reserve, 1_000 - expected_out);
+    }
+
+    #[test]
+    fn test_twap_weights_prices_by_time() {
+        let mut manager = thin_pool();
+        let start_price = manager.pairs["THIN-EDGE"].get_price();
+        assert_eq!(start_price, 1.0);
+        
+        // Price 1.0 holds for 100s, then a base sell drops it for 300s,
+        // then a large buy pushes it above 1.0 for 100s
+        manager.swap_at("THIN-EDGE", 500, true, "alice", None, 100).unwrap();
+        let low = manager.pairs["THIN-EDGE"].get_price();
+        manager.swap_at("THIN-EDGE", 2_000, false, "bob", None, 400).unwrap();
+        let high = manager.pairs["THIN-EDGE"].get_price();
+        assert!(low < start_price && start_price < high);
+        
+        let pair = &manager.pairs["THIN-EDGE"];
+        let full = pair.twap(500, 500).unwrap();
+        let expected = (start_price * 100.0 + low * 300.0 + high * 100.0) / 500.0;
+        assert!((full - expected).abs() < 1e-9);
+        assert!(low < full && full < high);
+        
+        // A window inside one price interval is that interval's price
+        assert!((pair.twap(100, 350).unwrap() - low).abs() < 1e-9);
+        // A window straddling the last swap mixes the two prices
+        let recent = pair.twap(200, 500).unwrap();
+        assert!((recent - (low + high) / 2.0).abs() < 1e-9);
+        // Windows longer than the history average what there is
+        assert_eq!(pair.twap(10_000, 500), pair.twap(500, 500));
+        assert_eq!(pair.twap(60, 0), None);
+    }
+    
+    #[test]
+    fn test_remove_liquidity_full_and_partial() {
+        let mut manager = thin_pool();
+        let position = manager.add_liquidity("THIN-EDGE", 500, 500, "lp").unwrap();
+        assert_eq!(position.lp_tokens, 500);
+
+        // Partial withdrawal halves the position
+        assert_eq!(manager.remove_liquidity("THIN-EDGE", 250, "lp").unwrap(), (250, 250));
+        let remaining = &manager.positions["lp"][0];
+        assert_eq!((remaining.lp_tokens, remaining.base_deposited, remaining.quote_deposited), (250, 250, 250));
+
+        // Can't burn more than held
+        assert!(manager.remove_liquidity("THIN-EDGE", 251, "lp").is_err());
+        assert!(manager.remove_liquidity("THIN-EDGE", 1, "stranger").is_err());
+
+        // Full withdrawal returns the rest and clears the position
+        assert_eq!(manager.remove_liquidity("THIN-EDGE", 250, "lp").unwrap(), (250, 250));
+        assert!(!manager.positions.contains_key("lp"));
+        let pair = &manager.pairs["THIN-EDGE"];
+        assert_eq!((pair.base_reserve, pair.quote_reserve, pair.total_liquidity), (1_000, 1_000, 1_000));
+
+        // Fees from swaps accrue to liquidity providers
+        manager.add_liquidity("THIN-EDGE", 1_000, 1_000, "lp").unwrap();
+        manager.swap("THIN-EDGE", 500, true, "trader", None).unwrap();
+        manager.swap("THIN-EDGE", 300, false, "trader", None).unwrap();
+        let (base, quote) = manager.remove_liquidity("THIN-EDGE", 1_000, "lp").unwrap();
+        let pair = &manager.pairs["THIN-EDGE"];
+        // Half the pool, up to rounding, with k grown by the fees
+        assert!(base.abs_diff(pair.base_reserve) <= 1 && quote.abs_diff(pair.quote_reserve) <= 1);
+        assert!(base * quote > 1_000 * 1_000);
+    }
+
+    #[actix_web::test]
+    async fn test_remove_liquidity_requires_owner_signature() {
+        use actix_web::{http::StatusCode, test, App};
+        use crate::crypto::Wallet;
+
+        let owner = Wallet::new();
+        let stranger = Wallet::new();
+        let mut manager = thin_pool();
+        manager.add_liquidity("THIN-EDGE", 500, 500, owner.address()).unwrap();
+        let state = web::Data::new(DexState { manager: Arc::new(RwLock::new(manager)) });
+        let app = test::init_service(App::new().configure(|cfg| configure_dex_routes(cfg, state.clone()))).await;
+
+        let now = chrono::Utc::now().timestamp() as u64;
+        let request = |signer: &Wallet, lp_tokens: u64| {
+            let message = remove_liquidity_sign_message("THIN-EDGE", lp_tokens, now);
+            test::TestRequest::post().uri("/api/dex/liquidity/remove").set_json(serde_json::json!({
+                "pair_id": "THIN-EDGE",
+                "lp_tokens": lp_tokens,
+                "user": owner.address(),
+                "auth": {"public_key": signer.public_key_hex(), "signature": signer.sign(&message), "timestamp": now},
+            })).to_request()
+        };
+
+        // Anyone can name the owner, only the owner's key can withdraw
+        let resp = test::call_service(&app, request(&stranger, 100)).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(state.manager.read().await.positions[owner.address()][0].lp_tokens, 500);
+
+        let resp = test::call_service(&app, request(&owner, 100)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(state.manager.read().await.positions[owner.address()][0].lp_tokens, 400);
+    }
+
+    fn order(id: &str, owner: &str, order_type: OrderType, side: OrderSide, price: f64, amount: u64, created_at: i64) -> Order {
+        Order {
+            id: id.to_string(),
+            pair_id: "THIN-EDGE".to_string(),
+            owner: owner.to_string(),
+            order_type,
+            side,
+            price,
+            amount,
+            filled: 0,
+            status: OrderStatus::Open,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_limit_order_full_match() {
+        let mut manager = thin_pool();
+        let trades = manager.place_order(order("s1", "seller", OrderType::Limit, OrderSide::Sell, 2.0, 100, 1)).unwrap();
+        assert!(trades.is_empty());
+
+        // A buy below the ask doesn't cross
+        assert!(manager.place_order(order("b0", "buyer", OrderType::Limit, OrderSide::Buy, 1.5, 100, 2)).unwrap().is_empty());
+
+        let trades = manager.place_order(order("b1", "buyer", OrderType::Limit, OrderSide::Buy, 2.5, 100, 3)).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!((trades[0].price, trades[0].amount, trades[0].total), (2.0, 100, 200));
+        assert_eq!((trades[0].buyer.as_str(), trades[0].seller.as_str()), ("buyer", "seller"));
+        // Both sides filled: only the non-crossing bid rests
+        let book = &manager.orders["THIN-EDGE"];
+        assert_eq!(book.len(), 1);
+        assert_eq!(book[0].id, "b0");
+
+        assert!(manager.cancel_order("b0", "seller").is_err());
+        assert_eq!(manager.cancel_order("b0", "buyer").unwrap().status, OrderStatus::Cancelled);
+        assert!(manager.orders["THIN-EDGE"].is_empty());
+    }
+
+    #[test]
+    fn test_limit_order_partial_match() {
+        let mut manager = thin_pool();
+        manager.place_order(order("s1", "seller", OrderType::Limit, OrderSide::Sell, 2.0, 40, 1)).unwrap();
+
+        let trades = manager.place_order(order("b1", "buyer", OrderType::Limit, OrderSide::Buy, 2.0, 100, 2)).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].amount, 40);
+        // The remainder of the buy rests on the book
+        let book = &manager.orders["THIN-EDGE"];
+        assert_eq!(book.len(), 1);
+        assert_eq!((book[0].id.as_str(), book[0].filled, &book[0].status), ("b1", 40, &OrderStatus::PartiallyFilled));
+
+        // A market sell fills against the resting remainder
+        let trades = manager.place_order(order("m1", "seller", OrderType::Market, OrderSide::Sell, 0.0, 25, 3)).unwrap();
+        assert_eq!(trades[0].amount, 25);
+        assert_eq!(manager.orders["THIN-EDGE"][0].filled, 65);
+    }
+
+    #[test]
+    fn test_order_price_time_priority() {
+        let mut manager = thin_pool();
+        manager.place_order(order("late_cheap", "a", OrderType::Limit, OrderSide::Sell, 1.9, 10, 5)).unwrap();
+        manager.place_order(order("dear", "b", OrderType::Limit, OrderSide::Sell, 2.1, 10, 1)).unwrap();
+        manager.place_order(order("early_cheap", "c", OrderType::Limit, OrderSide::Sell, 1.9, 10, 2)).unwrap();
+
+        // Best price first, then earliest; the market order sweeps the book
+        let trades = manager.place_order(order("m", "buyer", OrderType::Market, OrderSide::Buy, 0.0, 50, 6)).unwrap();
+        let sellers: Vec<&str> = trades.iter().map(|t| t.seller.as_str()).collect();
+        assert_eq!(sellers, vec!["c", "a", "b"]);
+        assert_eq!(trades.iter().map(|t| t.amount).sum::<u64>(), 30);
+        // The unfilled market remainder does not rest
+        assert!(manager.orders["THIN-EDGE"].is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+//! Structured API errors
+//!
+//! Transaction, staking and governance errors map to an HTTP status and a
+//! stable `error_code` sent next to the human-readable `error`, in the same
+//! body as authentication errors, so clients can branch on the code rather
+//! than parse messages. Codes are part of the API: never rename one.
+
+#![allow(dead_code)]
+
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+
+use crate::blockchain::TransactionError;
+use crate::consensus::governance::GovernanceError;
+use crate::consensus::staking::StakingError;
+use super::auth::AuthError;
+
+/// An error with a status code and a stable code for API clients
+pub trait ApiError: std::fmt::Display {
+    fn status_code(&self) -> StatusCode;
+
+    fn error_code(&self) -> &'static str;
+
+    fn error_response(&self) -> HttpResponse {
+        AuthError::new(&self.to_string(), self.error_code()).to_response(self.status_code())
+    }
+}
+
+impl ApiError for TransactionError {
+    fn status_code(&self) -> StatusCode {
+        use TransactionError::*;
+        match self {
+            ReorgInProgress => StatusCode::SERVICE_UNAVAILABLE,
+            Unsigned | InvalidSignature | RequestReplayed => StatusCode::UNAUTHORIZED,
+            SenderMismatch => StatusCode::FORBIDDEN,
+            NonceTooLow { .. } | NonceTooHigh { .. } | Duplicate => StatusCode::CONFLICT,
+            InsufficientBalance { .. } | InsufficientFeeBalance { .. } | MempoolFull { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            DataTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            InvalidHash | InsufficientPow { .. } | MemoTooLong { .. } | NoOutputs | TooManyOutputs { .. }
+            | MixedTokens | Expired { .. } | WrongRewardClaim { .. } | InvalidPublicKey | MalformedSignature
+            | RequestFromFuture | RequestExpired { .. } => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_code(&self) -> &'static str {
+        use TransactionError::*;
+        match self {
+            ReorgInProgress => "REORG_IN_PROGRESS",
+            InvalidHash => "INVALID_HASH",
+            Unsigned => "MISSING_SIGNATURE",
+            InvalidSignature => "INVALID_SIGNATURE",
+            SenderMismatch => "ADDRESS_MISMATCH",
+            InvalidPublicKey => "INVALID_PUBLIC_KEY",
+            MalformedSignature => "SIGNATURE_ERROR",
+            RequestFromFuture => "TIMESTAMP_FUTURE",
+            RequestExpired { .. } => "REQUEST_EXPIRED",
+            RequestReplayed => "REPLAYED_REQUEST",
+            InsufficientPow { .. } => "INSUFFICIENT_POW",
+            MemoTooLong { .. } => "MEMO_TOO_LONG",
+            DataTooLarge { .. } => "DATA_TOO_LARGE",
+            NoOutputs => "NO_OUTPUTS",
+            TooManyOutputs { .. } => "TOO_MANY_OUTPUTS",
+            MixedTokens => "MIXED_TOKENS",
+            Expired { .. } => "TRANSACTION_EXPIRED",
+            WrongRewardClaim { .. } => "WRONG_REWARD_CLAIM",
+            NonceTooLow { .. } => "NONCE_TOO_LOW",
+            NonceTooHigh { .. } => "NONCE_TOO_HIGH",
+            InsufficientBalance { .. } => "INSUFFICIENT_BALANCE",
+            InsufficientFeeBalance { .. } => "INSUFFICIENT_FEE_BALANCE",
+            Duplicate => "DUPLICATE_TRANSACTION",
+            MempoolFull { .. } => "MEMPOOL_FULL",
+        }
+    }
+
+    /// A reorg clears within a block, so clients are told to retry shortly;
+    /// a full mempool tells them the gas price to beat
+    fn error_response(&self) -> HttpResponse {
+        if let TransactionError::MempoolFull { min_gas_price } = self {
+            return HttpResponse::build(self.status_code()).json(serde_json::json!({
+                "success": false,
+                "error": self.to_string(),
+                "error_code": self.error_code(),
+                "min_gas_price": min_gas_price,
+            }));
+        }
+        let mut response = AuthError::new(&self.to_string(), self.error_code()).to_response(self.status_code());
+        if *self == TransactionError::ReorgInProgress {
+            response.headers_mut().insert(
+                actix_web::http::header::RETRY_AFTER,
+                actix_web::http::header::HeaderValue::from_static("1"),
+            );
+        }
+        response
+    }
+}
+
+impl ApiError for StakingError {
+    fn status_code(&self) -> StatusCode {
+        use StakingError::*;
+        match self {
+            ValidatorNotFound | DelegationNotFound | UnbondingNotFound => StatusCode::NOT_FOUND,
+            NotOperator => StatusCode::FORBIDDEN,
+            ValidatorExists | MaxValidators | MaxDelegations { .. } | UnbondingCompleted
+            | RedelegationCooldown { .. } | CommissionCooldown { .. } | ValidatorJailed { .. } | NotJailed
+            | JailPeriodActive { .. } | NotEligible | AttestationRejected(_) => StatusCode::CONFLICT,
+            InsufficientStake { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            BelowMinimumStake { .. } | BelowMinimumDelegation { .. } | ZeroAmount | InvalidCommission { .. }
+            | CommissionChangeTooLarge { .. } | SameValidator => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_code(&self) -> &'static str {
+        use StakingError::*;
+        match self {
+            BelowMinimumStake { .. } => "BELOW_MIN_STAKE",
+            BelowMinimumDelegation { .. } => "BELOW_MIN_DELEGATION",
+            InsufficientStake { .. } => "INSUFFICIENT_STAKE",
+            ZeroAmount => "ZERO_AMOUNT",
+            InvalidCommission { .. } => "INVALID_COMMISSION",
+            CommissionChangeTooLarge { .. } => "COMMISSION_CHANGE_TOO_LARGE",
+            CommissionCooldown { .. } => "COMMISSION_COOLDOWN",
+            MaxValidators => "MAX_VALIDATORS",
+            MaxDelegations { .. } => "MAX_DELEGATIONS",
+            ValidatorExists => "VALIDATOR_EXISTS",
+            ValidatorNotFound => "VALIDATOR_NOT_FOUND",
+            DelegationNotFound => "DELEGATION_NOT_FOUND",
+            UnbondingNotFound => "UNBONDING_NOT_FOUND",
+            UnbondingCompleted => "UNBONDING_COMPLETED",
+            SameValidator => "SAME_VALIDATOR",
+            RedelegationCooldown { .. } => "REDELEGATION_COOLDOWN",
+            ValidatorJailed { .. } => "VALIDATOR_JAILED",
+            NotJailed => "VALIDATOR_NOT_JAILED",
+            JailPeriodActive { .. } => "JAIL_PERIOD_ACTIVE",
+            NotOperator => "NOT_OPERATOR",
+            NotEligible => "VALIDATOR_NOT_ELIGIBLE",
+            AttestationRejected(_) => "ATTESTATION_REJECTED",
+        }
+    }
+}
+
+impl ApiError for GovernanceError {
+    fn status_code(&self) -> StatusCode {
+        use GovernanceError::*;
+        match self {
+            ProposalNotFound => StatusCode::NOT_FOUND,
+            NotProposer => StatusCode::FORBIDDEN,
+            NoStake | StakeBelowQuadraticMinimum { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ExecutionFailed { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            MaxActiveProposals | MaxProposerProposals | DepositClosed | NotInVotingPeriod | VotingEnded
+            | NotInDepositPeriod | ForeignDeposits | NotPassed | ExecutionDelay { .. }
+            | ParameterCooldown { .. } => StatusCode::CONFLICT,
+        }
+    }
+
+    fn error_code(&self) -> &'static str {
+        use GovernanceError::*;
+        match self {
+            ProposalNotFound => "PROPOSAL_NOT_FOUND",
+            MaxActiveProposals => "MAX_ACTIVE_PROPOSALS",
+            MaxProposerProposals => "MAX_PROPOSER_PROPOSALS",
+            DepositClosed => "DEPOSIT_CLOSED",
+            NotInVotingPeriod => "NOT_IN_VOTING_PERIOD",
+            VotingEnded => "VOTING_ENDED",
+            NoStake => "NO_STAKE",
+            StakeBelowQuadraticMinimum { .. } => "STAKE_BELOW_QUADRATIC_MINIMUM",
+            NotProposer => "NOT_PROPOSER",
+            NotInDepositPeriod => "NOT_IN_DEPOSIT_PERIOD",
+            ForeignDeposits => "FOREIGN_DEPOSITS",
+            NotPassed => "PROPOSAL_NOT_PASSED",
+            ExecutionDelay { .. } => "EXECUTION_DELAY",
+            ParameterCooldown { .. } => "PARAMETER_COOLDOWN",
+            ExecutionFailed { .. } => "EXECUTION_FAILED",
+        }
+    }
+}
+
+impl From<TransactionError> for HttpResponse {
+    fn from(e: TransactionError) -> Self {
+        e.error_response()
+    }
+}
+
+impl From<StakingError> for HttpResponse {
+    fn from(e: StakingError) -> Self {
+        e.error_response()
+    }
+}
+
+impl From<GovernanceError> for HttpResponse {
+    fn from(e: GovernanceError) -> Self {
+        e.error_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_errors_map_to_status_and_code() {
+        let response = HttpResponse::from(StakingError::InsufficientStake { have: 10, need: 50 });
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["success"], false);
+        assert_eq!(body["error"], "Insufficient stake: has 10, needs 50");
+        assert_eq!(body["error_code"], "INSUFFICIENT_STAKE");
+
+        let e = StakingError::ValidatorNotFound;
+        assert_eq!((e.status_code(), e.error_code()), (StatusCode::NOT_FOUND, "VALIDATOR_NOT_FOUND"));
+        let e = StakingError::NotOperator;
+        assert_eq!((e.status_code(), e.error_code()), (StatusCode::FORBIDDEN, "NOT_OPERATOR"));
+
+        let e = GovernanceError::ProposalNotFound;
+        assert_eq!((e.status_code(), e.error_code()), (StatusCode::NOT_FOUND, "PROPOSAL_NOT_FOUND"));
+        let e = GovernanceError::VotingEnded;
+        assert_eq!((e.status_code(), e.error_code()), (StatusCode::CONFLICT, "VOTING_ENDED"));
+
+        let e = TransactionError::NonceTooLow { expected: 3, got: 1 };
+        assert_eq!(e.to_string(), "nonce too low: expected 3, got 1");
+        assert_eq!((e.status_code(), e.error_code()), (StatusCode::CONFLICT, "NONCE_TOO_LOW"));
+        let e = TransactionError::InsufficientBalance { have: 0, need: 5 };
+        assert_eq!((e.status_code(), e.error_code()), (StatusCode::UNPROCESSABLE_ENTITY, "INSUFFICIENT_BALANCE"));
+
+        // A reorg rejection tells the client when to retry
+        let response = HttpResponse::from(TransactionError::ReorgInProgress);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get("Retry-After").unwrap(), "1");
+
+        // A full mempool is the client's to fix by raising the gas price
+        let response = HttpResponse::from(TransactionError::MempoolFull { min_gas_price: 7 });
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = actix_web::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error_code"], "MEMPOOL_FULL");
+        assert_eq!(body["min_gas_price"], 7);
+
+        let e = GovernanceError::ExecutionFailed { reason: "Treasury too small".to_string() };
+        assert_eq!((e.status_code(), e.error_code()), (StatusCode::UNPROCESSABLE_ENTITY, "EXECUTION_FAILED"));
+    }
+}
@@ -0,0 +1,325 @@
+//! Testnet faucet
+//!
+//! `POST /api/faucet` pays a fixed amount of EDGE to an address with an
+//! ordinary transfer signed by the faucet wallet. An address can claim once
+//! per cooldown period and each client IP a limited number of times per day;
+//! both are tracked in memory. The endpoint only answers when
+//! `EDGEAI_FAUCET_ENABLED` is set and `EDGEAI_FAUCET_SECRET_KEY` names a
+//! funded account, so a key left in a mainnet node's environment can't turn
+//! it on by itself.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use chrono::{DateTime, Duration, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::{Blockchain, Transaction, TransactionError};
+use crate::crypto::Wallet;
+use super::rest::{transaction_rejected, ApiResponse, AppState};
+
+/// EDGE paid per claim
+pub const FAUCET_AMOUNT: u64 = 1_000;
+
+/// Time an address waits between claims
+pub const FAUCET_COOLDOWN_SECS: i64 = 24 * 60 * 60;
+
+/// Claims allowed from one IP per 24 hours
+pub const FAUCET_DAILY_CLAIMS_PER_IP: usize = 5;
+
+/// Whether the faucet is switched on (`EDGEAI_FAUCET_ENABLED`)
+pub fn faucet_enabled_from_env() -> bool {
+    std::env::var("EDGEAI_FAUCET_ENABLED")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Wallet the faucet pays from (`EDGEAI_FAUCET_SECRET_KEY`, hex) when
+/// `EDGEAI_FAUCET_ENABLED` is set; the faucet is disabled without one
+pub fn faucet_wallet_from_env() -> Option<Wallet> {
+    if !faucet_enabled_from_env() {
+        return None;
+    }
+    let secret = std::env::var("EDGEAI_FAUCET_SECRET_KEY").ok().filter(|v| !v.trim().is_empty())?;
+    match Wallet::from_secret_key(secret.trim()) {
+        Ok(wallet) => Some(wallet),
+        Err(e) => {
+            log::warn!("Ignoring invalid EDGEAI_FAUCET_SECRET_KEY ({}); faucet disabled", e);
+            None
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FaucetRequest {
+    pub address: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FaucetResponse {
+    pub address: String,
+    pub amount: u64,
+    pub transaction_hash: String,
+}
+
+/// Why a claim was refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum FaucetError {
+    /// The address claimed within the cooldown
+    Cooldown { retry_after_secs: u64 },
+    /// The client IP used up its daily claims
+    IpLimit { retry_after_secs: u64 },
+    /// The transfer was rejected by the chain
+    Rejected(TransactionError),
+}
+
+impl From<TransactionError> for FaucetError {
+    fn from(e: TransactionError) -> Self {
+        FaucetError::Rejected(e)
+    }
+}
+
+/// Claim times per address and per client IP
+#[derive(Debug, Default)]
+struct Claims {
+    by_address: HashMap<String, DateTime<Utc>>,
+    by_ip: HashMap<String, Vec<DateTime<Utc>>>,
+}
+
+/// Faucet wallet, rate limits and claim history
+pub struct Faucet {
+    /// Signs the faucet's transfers; `None` disables the faucet
+    wallet: Option<Wallet>,
+    pub amount: u64,
+    pub cooldown: Duration,
+    pub daily_claims_per_ip: usize,
+    claims: Mutex<Claims>,
+    /// Held from nonce lookup to submission so concurrent claims don't
+    /// sign transfers with the same nonce
+    turn: tokio::sync::Mutex<()>,
+}
+
+impl Faucet {
+    pub fn new(wallet: Option<Wallet>) -> Self {
+        Faucet {
+            wallet,
+            amount: FAUCET_AMOUNT,
+            cooldown: Duration::seconds(FAUCET_COOLDOWN_SECS),
+            daily_claims_per_ip: FAUCET_DAILY_CLAIMS_PER_IP,
+            claims: Mutex::new(Claims::default()),
+            turn: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Faucet paying from `EDGEAI_FAUCET_SECRET_KEY`, if enabled
+    pub fn from_env() -> Self {
+        Self::new(faucet_wallet_from_env())
+    }
+
+    /// Address the faucet pays from, if enabled
+    pub fn address(&self) -> Option<&str> {
+        self.wallet.as_ref().map(|w| w.address())
+    }
+
+    /// Refuse a claim for `address` from `ip` that is rate limited at `now`
+    pub fn check(&self, address: &str, ip: &str, now: DateTime<Utc>) -> Result<(), FaucetError> {
+        let mut claims = self.claims.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(last) = claims.by_address.get(address) {
+            let ready = *last + self.cooldown;
+            if now < ready {
+                return Err(FaucetError::Cooldown { retry_after_secs: secs_until(now, ready) });
+            }
+        }
+        let day_ago = now - Duration::days(1);
+        let ip_claims = claims.by_ip.entry(ip.to_string()).or_default();
+        ip_claims.retain(|t| *t > day_ago);
+        if ip_claims.len() >= self.daily_claims_per_ip {
+            let ready = ip_claims[0] + Duration::days(1);
+            return Err(FaucetError::IpLimit { retry_after_secs: secs_until(now, ready) });
+        }
+        Ok(())
+    }
+
+    /// Signed transfer of `amount` from the faucet wallet to `address` at
+    /// `nonce`, with its proof-of-work solved; slow, so run it off the chain lock
+    pub fn transfer(&self, address: &str, nonce: u64, pow_bits: u32) -> Result<Transaction, FaucetError> {
+        let wallet = self.wallet.as_ref().ok_or(TransactionError::Unsigned)?;
+        let message = Transaction::create_transfer_signing_message(wallet.address(), address, self.amount, nonce, None, None);
+        let mut tx = Transaction::transfer_signed(
+            wallet.address().to_string(), wallet.public_key_hex(), address.to_string(),
+            self.amount, nonce, None, wallet.sign(message.as_bytes()),
+        );
+        if pow_bits > 0 {
+            tx.solve_pow(pow_bits);
+        }
+        Ok(tx)
+    }
+
+    /// Submit a transfer from `transfer` and count it against `address` and `ip`.
+    /// Returns the transaction hash.
+    pub fn submit(&self, chain: &mut Blockchain, tx: Transaction, address: &str, ip: &str, now: DateTime<Utc>) -> Result<String, FaucetError> {
+        self.check(address, ip, now)?;
+        let hash = chain.add_transaction(tx)?;
+        let mut claims = self.claims.lock().unwrap_or_else(|e| e.into_inner());
+        // Forget claims no limit looks at any more
+        let (cooled, day_ago) = (now - self.cooldown, now - Duration::days(1));
+        claims.by_address.retain(|_, t| *t > cooled);
+        claims.by_ip.retain(|_, times| {
+            times.retain(|t| *t > day_ago);
+            !times.is_empty()
+        });
+        claims.by_ip.entry(ip.to_string()).or_default().push(now);
+        claims.by_address.insert(address.to_string(), now);
+        Ok(hash)
+    }
+}
+
+fn secs_until(now: DateTime<Utc>, then: DateTime<Utc>) -> u64 {
+    (then - now).num_seconds().max(1) as u64
+}
+
+fn too_many_requests(message: &str, retry_after_secs: u64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after_secs.to_string()))
+        .json(ApiResponse {
+            success: false,
+            data: Some(serde_json::json!({ "retry_after_secs": retry_after_secs })),
+            error: Some(message.to_string()),
+        })
+}
+
+/// Pay test EDGE to an address (POST /api/faucet, testnet only)
+pub async fn faucet(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    faucet: web::Data<Faucet>,
+    body: web::Json<FaucetRequest>,
+) -> impl Responder {
+    let Some(faucet_address) = faucet.address() else {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "Faucet is disabled; set EDGEAI_FAUCET_ENABLED and EDGEAI_FAUCET_SECRET_KEY on testnet nodes",
+        ));
+    };
+    let address = body.address.trim();
+    if address.is_empty() || address == faucet_address {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Invalid address"));
+    }
+    // Behind the testnet's proxy the client IP comes from forwarding
+    // headers, so the per-IP cap is best effort; the address cooldown isn't
+    let ip = req.connection_info().realip_remote_addr().unwrap_or_default().to_string();
+
+    if let Err(e) = data.reorg.check() {
+        return transaction_rejected(&e);
+    }
+    let _turn = faucet.turn.lock().await;
+    if let Err(e) = faucet.check(address, &ip, Utc::now()) {
+        return claim_refused(e);
+    }
+    let (nonce, pow_bits) = {
+        let blockchain = data.blockchain.read().await;
+        (blockchain.next_nonce(faucet_address), blockchain.state.params.tx_pow_bits)
+    };
+    // The proof-of-work can take a while; keep it off the async workers and the chain lock
+    let (signer, recipient) = (faucet.clone(), address.to_string());
+    let tx = match tokio::task::spawn_blocking(move || signer.transfer(&recipient, nonce, pow_bits)).await {
+        Ok(Ok(tx)) => tx,
+        Ok(Err(e)) => return claim_refused(e),
+        Err(e) => return HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Faucet transfer failed: {}", e))),
+    };
+    let mut blockchain = data.blockchain.write().await;
+    match faucet.submit(&mut blockchain, tx, address, &ip, Utc::now()) {
+        Ok(hash) => {
+            info!("Faucet: sent {} EDGE to {}", faucet.amount, address);
+            HttpResponse::Ok().json(ApiResponse::success(FaucetResponse {
+                address: address.to_string(),
+                amount: faucet.amount,
+                transaction_hash: hash,
+            }))
+        }
+        Err(e) => claim_refused(e),
+    }
+}
+
+fn claim_refused(e: FaucetError) -> HttpResponse {
+    match e {
+        FaucetError::Cooldown { retry_after_secs } => {
+            too_many_requests("Address already claimed from the faucet recently", retry_after_secs)
+        }
+        FaucetError::IpLimit { retry_after_secs } => {
+            too_many_requests("Daily faucet limit reached for this client", retry_after_secs)
+        }
+        FaucetError::Rejected(e) => transaction_rejected(&e),
+    }
+}
+
+pub fn configure_faucet_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/faucet", web::post().to(faucet));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::chain::tests::{fund, test_chain};
+
+    impl Faucet {
+        /// Sign and submit a claim in one go
+        fn claim(&self, chain: &mut Blockchain, address: &str, ip: &str, now: DateTime<Utc>) -> Result<String, FaucetError> {
+            let tx = self.transfer(address, chain.next_nonce(self.address().unwrap_or_default()), chain.state.params.tx_pow_bits)?;
+            self.submit(chain, tx, address, ip, now)
+        }
+    }
+
+    #[test]
+    fn test_faucet_claim_and_cooldown() {
+        let mut chain = test_chain();
+        let wallet = Wallet::new();
+        fund(&mut chain, &wallet, 1_000_000);
+        let faucet = Faucet::new(Some(wallet.clone()));
+        let now = Utc::now();
+
+        let hash = faucet.claim(&mut chain, "alice", "10.0.0.1", now).unwrap();
+        let pending = chain.get_pending_transactions(10);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].hash, hash);
+        assert_eq!(pending[0].sender, wallet.address());
+        assert!(pending[0].check_sender_signature().is_ok());
+        assert_eq!(pending[0].outputs[0].recipient, "alice");
+        assert_eq!(pending[0].outputs[0].amount, FAUCET_AMOUNT);
+
+        // A repeat claim an hour later reports the time left
+        let later = now + Duration::hours(1);
+        assert_eq!(
+            faucet.claim(&mut chain, "alice", "10.0.0.2", later),
+            Err(FaucetError::Cooldown { retry_after_secs: 23 * 60 * 60 })
+        );
+        assert_eq!(chain.pending_transactions.len(), 1);
+
+        // One IP can't drain the faucet across many addresses
+        for i in 1..FAUCET_DAILY_CLAIMS_PER_IP {
+            faucet.claim(&mut chain, &format!("bot{}", i), "10.0.0.1", later).unwrap();
+        }
+        assert!(matches!(
+            faucet.claim(&mut chain, "bot_last", "10.0.0.1", later),
+            Err(FaucetError::IpLimit { .. })
+        ));
+        assert!(faucet.claim(&mut chain, "alice", "10.0.0.2", now + Duration::days(1)).is_ok());
+
+        // Claims past the cooldown and the daily window are forgotten
+        faucet.claim(&mut chain, "dave", "10.0.0.4", now + Duration::days(3)).unwrap();
+        {
+            let claims = faucet.claims.lock().unwrap();
+            assert_eq!(claims.by_address.keys().collect::<Vec<_>>(), vec!["dave"]);
+            assert_eq!(claims.by_ip.keys().collect::<Vec<_>>(), vec!["10.0.0.4"]);
+        }
+
+        // Without a wallet there is nothing to sign with
+        assert_eq!(
+            Faucet::new(None).claim(&mut chain, "carol", "10.0.0.3", now),
+            Err(FaucetError::Rejected(TransactionError::Unsigned))
+        );
+    }
+}
@@ -10,9 +10,12 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 use crate::api::auth::{SignedRequest, AuthData, verify_signed_request};
+use crate::api::error::ApiError;
+use crate::api::rest::AppState;
+use crate::api::staking::StakingState;
 use crate::consensus::governance::{
     GovernanceManager, GovernanceStats, Proposal, ProposalStatus, ProposalType,
-    ValidatorAction, VoteOption, VoteTally,
+    ValidatorAction, VoteOption, VoteTally, VotingScheme,
 };
 
 /// Shared governance state
@@ -142,7 +145,10 @@ pub struct DepositRequest {
 pub struct VoteRequest {
     pub voter: String,
     pub option: String, // "yes", "no", "abstain", "no_with_veto"
-    pub voting_power: String,
+    /// Ignored: voting power is taken from the voter's stake. Kept so older
+    /// signed payloads still verify.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub voting_power: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -158,6 +164,7 @@ pub struct ProposalResponse {
     pub voting_start_time: Option<u64>,
     pub voting_end_time: Option<u64>,
     pub execution_time: Option<u64>,
+    pub voting_scheme: VotingScheme,
     pub tally: TallyResponse,
     pub vote_count: usize,
 }
@@ -175,15 +182,6 @@ pub struct TallyResponse {
 
 impl From<&Proposal> for ProposalResponse {
     fn from(p: &Proposal) -> Self {
-        let proposal_type = match &p.proposal_type {
-            ProposalType::ParameterChange { .. } => "parameter_change",
-            ProposalType::SoftwareUpgrade { .. } => "software_upgrade",
-            ProposalType::TreasurySpend { .. } => "treasury_spend",
-            ProposalType::ValidatorChange { .. } => "validator_change",
-            ProposalType::Text { .. } => "text",
-            ProposalType::Emergency { .. } => "emergency",
-        };
-
         let status = match &p.status {
             ProposalStatus::DepositPeriod => "deposit_period",
             ProposalStatus::VotingPeriod => "voting_period",
@@ -193,6 +191,7 @@ impl From<&Proposal> for ProposalResponse {
             ProposalStatus::Executed => "executed",
             ProposalStatus::ExecutionFailed { .. } => "execution_failed",
             ProposalStatus::Expired => "expired",
+            ProposalStatus::Cancelled => "cancelled",
         };
 
         ProposalResponse {
@@ -200,13 +199,14 @@ impl From<&Proposal> for ProposalResponse {
             proposer: p.proposer.clone(),
             title: p.title.clone(),
             description: p.description.clone(),
-            proposal_type: proposal_type.to_string(),
+            proposal_type: p.proposal_type.kind().to_string(),
             status: status.to_string(),
             deposit: p.deposit.to_string(),
             submit_time: p.submit_time,
             voting_start_time: p.voting_start_time,
             voting_end_time: p.voting_end_time,
             execution_time: p.execution_time,
+            voting_scheme: p.voting_scheme,
             tally: TallyResponse {
                 yes: p.tally.yes.to_string(),
                 no: p.tally.no.to_string(),
@@ -227,6 +227,14 @@ pub struct ProposalListResponse {
     pub total: usize,
 }
 
+#[derive(Debug, Serialize)]
+pub struct TreasuryResponse {
+    /// Native tokens held by the treasury
+    pub balance: u64,
+    /// Share of block rewards and fees paid into the treasury
+    pub reward_share: f64,
+}
+
 #[derive(Debug, Serialize)]
 pub struct GovernanceStatsResponse {
     pub total_proposals: usize,
@@ -273,6 +281,15 @@ pub async fn get_governance_stats(governance: web::Data<GovernanceState>) -> imp
     })
 }
 
+/// Get the treasury balance
+pub async fn get_treasury(data: web::Data<AppState>) -> impl Responder {
+    let chain = data.blockchain.read().await;
+    HttpResponse::Ok().json(TreasuryResponse {
+        balance: chain.treasury_balance(),
+        reward_share: chain.state.params.treasury_share,
+    })
+}
+
 /// Get all proposals
 pub async fn get_proposals(governance: web::Data<GovernanceState>) -> impl Responder {
     let gov = governance.read().await;
@@ -335,7 +352,7 @@ pub async fn create_proposal(
         300, // 5 minute expiry
     ) {
         Ok(_) => {},
-        Err(response) => return response,
+        Err(e) => return e.error_response(),
     };
 
     let mut gov = governance.write().await;
@@ -366,10 +383,7 @@ pub async fn create_proposal(
                 message: format!("Proposal {} created successfully", proposal_id),
             })
         }
-        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "error": e
-        })),
+        Err(e) => e.into(),
     }
 }
 
@@ -389,10 +403,7 @@ pub async fn add_deposit(
             "success": true,
             "message": "Deposit added successfully"
         })),
-        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "error": e
-        })),
+        Err(e) => e.into(),
     }
 }
 
@@ -401,6 +412,7 @@ pub async fn add_deposit(
 /// Request body must be wrapped in SignedRequest with auth data
 pub async fn vote_on_proposal(
     governance: web::Data<GovernanceState>,
+    staking: web::Data<StakingState>,
     path: web::Path<u64>,
     body: web::Json<SignedRequest<VoteRequest>>,
 ) -> impl Responder {
@@ -413,10 +425,12 @@ pub async fn vote_on_proposal(
         300, // 5 minute expiry
     ) {
         Ok(_) => {},
-        Err(response) => return response,
+        Err(e) => return e.error_response(),
     };
 
     let proposal_id = path.into_inner();
+    // Staking before governance, the order block application takes them in
+    let staking = staking.manager.read().await;
     let mut gov = governance.write().await;
     let body = &body.data;
 
@@ -433,17 +447,12 @@ pub async fn vote_on_proposal(
         }
     };
 
-    let voting_power: u128 = body.voting_power.parse().unwrap_or(0);
-
-    match gov.vote(body.voter.clone(), proposal_id, option, voting_power) {
+    match gov.vote(body.voter.clone(), proposal_id, option, &*staking) {
         Ok(()) => HttpResponse::Ok().json(serde_json::json!({
             "success": true,
             "message": "Vote cast successfully"
         })),
-        Err(e) => HttpResponse::BadRequest().json(serde_json::json!({
-            "success": false,
-            "error": e
-        })),
+        Err(e) => e.into(),
     }
 }
 
@@ -465,6 +474,7 @@ pub async fn get_proposal_votes(
                         "voter": v.voter,
                         "option": format!("{:?}", v.option).to_lowercase(),
                         "voting_power": v.voting_power.to_string(),
+                        "weight": proposal.voting_scheme.weight(v.voting_power).to_string(),
                         "timestamp": v.timestamp
                     })
                 })
@@ -487,6 +497,7 @@ pub fn configure_governance_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/governance")
             .route("/stats", web::get().to(get_governance_stats))
+            .route("/treasury", web::get().to(get_treasury))
             .route("/proposals", web::get().to(get_proposals))
             .route("/proposals/active", web::get().to(get_active_proposals))
             .route("/proposals", web::post().to(create_proposal))
@@ -0,0 +1,857 @@
+//! IoT API endpoints for EdgeAI Blockchain
+//!
+//! This module provides the simulated IoT transaction feed used by the explorer
+//! and the external device API for submitting real telemetry to the chain.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use log::info;
+use tokio::sync::RwLock;
+
+use crate::blockchain::Transaction;
+use crate::blockchain::reward_policy::RewardPolicy;
+use crate::iot::{IoTGenerator, IoTTransactionListResponse};
+use crate::network::peer_scoring::RateLimiter;
+use super::device::DeviceState;
+use super::request_id::RequestId;
+use super::rest::{AppState, ApiResponse, transaction_rejected};
+
+// ============ Query Types ============
+
+#[derive(Debug, Deserialize)]
+pub struct IoTQuery {
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+    pub sector: Option<String>,
+}
+
+// ============ IoT Feed Endpoints ============
+
+/// 获取 IoT 交易列表
+pub async fn get_iot_transactions(query: web::Query<IoTQuery>) -> impl Responder {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(20).min(100);
+
+    let generator = IoTGenerator::new();
+    let transactions = generator.generate_transactions(page, limit);
+
+    // 如果指定了行业过滤
+    let filtered_transactions = if let Some(ref sector) = query.sector {
+        transactions
+            .into_iter()
+            .filter(|tx| tx.sector.to_lowercase().contains(&sector.to_lowercase()))
+            .collect()
+    } else {
+        transactions
+    };
+
+    let response = IoTTransactionListResponse {
+        transactions: filtered_transactions,
+        total: 100000, // 模拟总数
+        page,
+        limit,
+    };
+
+    HttpResponse::Ok().json(ApiResponse::success(response))
+}
+
+/// 获取 IoT 行业统计
+pub async fn get_iot_sectors() -> impl Responder {
+    use crate::iot::types::IoTSector;
+
+    let sectors: Vec<serde_json::Value> = IoTSector::all()
+        .iter()
+        .map(|s| {
+            serde_json::json!({
+                "id": format!("{:?}", s).to_lowercase(),
+                "name": s.display_name(),
+                "icon": s.icon(),
+                "devices": s.devices(),
+            })
+        })
+        .collect();
+
+    HttpResponse::Ok().json(ApiResponse::success(sectors))
+}
+
+// ============ External IoT Device API ============
+
+/// Default submissions accepted per device per minute
+pub const DEFAULT_IOT_RATE_LIMIT: u32 = 60;
+
+/// Per-device submission limit from `EDGEAI_IOT_RATE_LIMIT` (0 = unlimited)
+pub fn iot_rate_limit_from_env() -> u32 {
+    std::env::var("EDGEAI_IOT_RATE_LIMIT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_IOT_RATE_LIMIT)
+}
+
+/// Sliding one-minute window of IoT submissions per device
+pub struct IoTRateLimitState {
+    limiter: RateLimiter,
+    recent: RwLock<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl IoTRateLimitState {
+    pub fn new(max_per_minute: u32) -> Self {
+        IoTRateLimitState {
+            limiter: RateLimiter {
+                max_messages_per_second: u32::MAX,
+                max_messages_per_minute: max_per_minute,
+                window_size: Duration::from_secs(60),
+            },
+            recent: RwLock::new(HashMap::new()),
+        }
+    }
+    
+    pub fn from_env() -> Self {
+        Self::new(iot_rate_limit_from_env())
+    }
+    
+    /// Count a submission from `device_id`. Over the limit, nothing is
+    /// recorded and the seconds until a slot frees up are returned.
+    pub async fn check(&self, device_id: &str) -> Result<(), u64> {
+        if self.limiter.max_messages_per_minute == 0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let window = self.limiter.window_size;
+        let mut recent = self.recent.write().await;
+        let times = recent.entry(device_id.to_string()).or_default();
+        while times.front().is_some_and(|t| now.duration_since(*t) >= window) {
+            times.pop_front();
+        }
+        times.push_back(now);
+        if self.limiter.is_rate_limited(times) {
+            times.pop_back();
+            let oldest = times.front().copied().unwrap_or(now);
+            let wait = window.saturating_sub(now.duration_since(oldest));
+            return Err(wait.as_secs_f64().ceil().max(1.0) as u64);
+        }
+        Ok(())
+    }
+}
+
+fn rate_limited(device_id: &str, retry_after: u64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", retry_after.to_string()))
+        .json(ApiResponse::<()>::error(&format!(
+            "Rate limit exceeded for device {}; retry in {}s", device_id, retry_after
+        )))
+}
+
+/// Request structure for external IoT device data submission
+/// This API allows real IoT devices to submit telemetry data to the blockchain
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalIoTDataRequest {
+    /// Device identifier (will be used as sender address)
+    pub device_id: String,
+    /// Device API key for authentication
+    pub api_key: String,
+    /// Raw telemetry data in JSON format
+    pub telemetry: serde_json::Value,
+    /// Data category (SmartCity, Manufacturing, Agriculture, Energy, Healthcare, Logistics, EdgeAI)
+    pub category: String,
+    /// Optional geographic location [latitude, longitude]
+    pub location: Option<[f64; 2]>,
+    /// Per-device counter (sequence number or unix timestamp), above the last one submitted
+    pub counter: u64,
+    /// Device key signature over the `message_to_sign` that
+    /// `POST /api/iot/prepare` returns for these fields, hex
+    pub signature: String,
+    /// Firmware version running on the device
+    #[serde(default)]
+    pub firmware_version: Option<String>,
+    /// Anti-spam proof-of-work nonce over `message_to_sign`, when the chain requires one
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
+}
+
+/// Fields of a submission the device signs, for `POST /api/iot/prepare`
+#[derive(Debug, Deserialize)]
+pub struct PrepareIoTDataRequest {
+    pub device_id: String,
+    pub telemetry: serde_json::Value,
+    pub category: String,
+    #[serde(default)]
+    pub location: Option<[f64; 2]>,
+    pub counter: u64,
+    #[serde(default)]
+    pub firmware_version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreparedIoTData {
+    /// Address the contribution is sent from (derived from the device key)
+    pub sender: String,
+    /// Data stored on chain
+    pub payload: String,
+    pub message_to_sign: String,
+}
+
+/// Data stored on chain for a submission. Every field the node keeps,
+/// the counter included, is in it, so the device's signature covers them.
+pub fn iot_payload(
+    device_id: &str,
+    category: &str,
+    telemetry: &serde_json::Value,
+    location: Option<[f64; 2]>,
+    firmware_version: Option<&str>,
+    counter: u64,
+) -> String {
+    serde_json::json!({
+        "device": device_id,
+        "category": category,
+        "telemetry": telemetry,
+        "location": location,
+        "firmware": firmware_version,
+        "counter": counter,
+        "source": "external",
+    }).to_string()
+}
+
+impl ExternalIoTDataRequest {
+    fn payload(&self) -> String {
+        iot_payload(
+            &self.device_id,
+            &self.category,
+            &self.telemetry,
+            self.location,
+            self.firmware_version.as_deref(),
+            self.counter,
+        )
+    }
+}
+
+/// Verify the device's signature over the submission against the device
+/// registry and record its counter. Returns the data contribution, signed
+/// by the device's address, claiming the base reward under `policy`.
+async fn signed_contribution(
+    devices: &DeviceState,
+    item: &ExternalIoTDataRequest,
+    policy: &RewardPolicy,
+) -> Result<Transaction, String> {
+    let mut registry = devices.registry.write().await;
+    let device = registry.get_device(&item.device_id).ok_or("Device not found")?;
+    let (public_key, address) = (device.public_key.clone(), device.address()?);
+    let payload = item.payload();
+    registry.verify_signed_submission(&item.device_id, item.counter, &payload, &item.signature)?;
+    let base_reward = policy.claim_for(&payload);
+    Ok(Transaction::data_contribution_signed(address.clone(), public_key, payload, address, item.signature.clone())
+        .with_reward_claim(base_reward)
+        .with_pow_nonce(item.pow_nonce))
+}
+
+/// Check the submission's API key against the keys registered for the device
+async fn check_api_key(devices: &DeviceState, item: &ExternalIoTDataRequest) -> Result<(), String> {
+    if item.api_key.is_empty() {
+        return Err("API key required".to_string());
+    }
+    devices.registry.read().await.verify_api_key(&item.device_id, &item.api_key)
+}
+
+/// Reject submissions from firmware below the registry's minimum version.
+/// The version is part of the signed payload, so check the signature first.
+async fn check_firmware(devices: &DeviceState, item: &ExternalIoTDataRequest) -> Result<(), String> {
+    devices.registry.write().await.check_firmware(&item.device_id, item.firmware_version.as_deref())
+}
+
+/// Request structure for batch IoT data submission
+/// Allows submitting multiple telemetry records in a single request
+#[derive(Debug, Deserialize)]
+pub struct BatchIoTDataRequest {
+    /// List of IoT data submissions (max 100 per batch)
+    pub transactions: Vec<ExternalIoTDataRequest>,
+}
+
+/// Response for batch IoT data submission
+#[derive(Debug, Serialize)]
+pub struct BatchIoTSubmissionResponse {
+    pub total: usize,
+    pub successful: usize,
+    pub failed: usize,
+    pub results: Vec<BatchItemResult>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub device_id: String,
+    pub success: bool,
+    pub tx_hash: Option<String>,
+    pub reward: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Response for IoT data submission
+#[derive(Debug, Serialize)]
+pub struct IoTSubmissionResponse {
+    pub tx_hash: String,
+    pub device_id: String,
+    pub reward: u64,
+    pub quality_score: f64,
+    pub block_pending: bool,
+}
+
+/// Payload and message a device signs for a submission
+/// 
+/// # Endpoint
+/// POST /api/iot/prepare
+/// 
+/// Takes the submission fields without `api_key` and `signature` and
+/// returns the `payload` stored on chain, the device's `sender` address and
+/// the `message_to_sign` with the device key.
+pub async fn prepare_iot_data(
+    devices: web::Data<DeviceState>,
+    body: web::Json<PrepareIoTDataRequest>,
+) -> impl Responder {
+    let registry = devices.registry.read().await;
+    let Some(device) = registry.get_device(&body.device_id) else {
+        return HttpResponse::NotFound().json(ApiResponse::<()>::error("Device not found"));
+    };
+    let sender = match device.address() {
+        Ok(address) => address,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+    let payload = iot_payload(
+        &body.device_id,
+        &body.category,
+        &body.telemetry,
+        body.location,
+        body.firmware_version.as_deref(),
+        body.counter,
+    );
+    let message_to_sign = Transaction::create_data_contribution_signing_message(&sender, &payload);
+    HttpResponse::Ok().json(ApiResponse::success(PreparedIoTData { sender, payload, message_to_sign }))
+}
+
+/// Submit IoT telemetry data from external devices
+/// 
+/// # Endpoint
+/// POST /api/iot/submit
+/// 
+/// # Request Body
+/// ```json
+/// {
+///   "device_id": "my_sensor_001",
+///   "api_key": "your_api_key",
+///   "telemetry": {"temperature": 25.5, "humidity": 60},
+///   "category": "SmartCity",
+///   "location": [1.3521, 103.8198],
+///   "counter": 1760600000,
+///   "signature": "<hex>"
+/// }
+/// ```
+/// 
+/// `device_id` must be a registered device and `api_key` one of its keys
+/// minted with `POST /api/devices/{id}/api-keys`; otherwise the request is
+/// refused with 401. Each device may submit `EDGEAI_IOT_RATE_LIMIT` times
+/// per minute; beyond that the request gets 429 with `Retry-After`.
+/// `signature` is the device key's signature over the message
+/// `POST /api/iot/prepare` returns, which covers every stored field; a
+/// bad signature, or a counter not above the device's last one, is refused
+/// with 401. The contribution is sent from the device key's address.
+/// `firmware_version` is required when the node sets a minimum firmware
+/// version, and older firmware is refused with 403.
+/// 
+/// # Response
+/// ```json
+/// {
+///   "success": true,
+///   "data": {
+///     "tx_hash": "0x...",
+///     "device_id": "my_sensor_001",
+///     "reward": 50,
+///     "quality_score": 0.85,
+///     "block_pending": true
+///   }
+/// }
+/// ```
+pub async fn submit_iot_data(
+    data: web::Data<AppState>,
+    devices: web::Data<DeviceState>,
+    rate_limit: web::Data<IoTRateLimitState>,
+    body: web::Json<ExternalIoTDataRequest>,
+    request_id: RequestId,
+) -> impl Responder {
+    // Validate category
+    let policy = data.blockchain.read().await.state.reward_policy.clone();
+    if !policy.is_known_category(&body.category) {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error(&format!(
+                "Invalid category. Must be one of: {:?}", policy.category_names()
+            )));
+    }
+    
+    if let Err(e) = check_api_key(&devices, &body).await {
+        log::warn!("IoT submission from {} rejected: {} [request {}]", body.device_id, e, request_id);
+        return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(&e));
+    }
+    
+    if let Err(retry_after) = rate_limit.check(&body.device_id).await {
+        log::warn!("IoT submission from {} rate limited [request {}]", body.device_id, request_id);
+        return rate_limited(&body.device_id, retry_after);
+    }
+    
+    let tx = match signed_contribution(&devices, &body, &policy).await {
+        Ok(tx) => tx,
+        Err(e) => {
+            log::warn!("IoT submission from {} rejected: {} [request {}]", body.device_id, e, request_id);
+            return HttpResponse::Unauthorized().json(ApiResponse::<()>::error(&e));
+        }
+    };
+    
+    if let Err(e) = check_firmware(&devices, &body).await {
+        log::warn!("IoT submission from {} rejected: {} [request {}]", body.device_id, e, request_id);
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(&e));
+    }
+    
+    // The category multiplier is applied when the contribution is processed
+    let reward = policy.expected_reward(&body.category, tx.data.as_ref().map_or(0, |d| d.len() as u64));
+    let quality_score = tx.data_quality.as_ref()
+        .map(|q| q.overall_score)
+        .unwrap_or(0.5);
+    
+    // Add to blockchain
+    if let Err(e) = data.reorg.check() {
+        return transaction_rejected(&e);
+    }
+    let mut blockchain = data.blockchain.write().await;
+    match blockchain.add_transaction(tx) {
+        Ok(hash) => {
+            info!("External IoT data submitted: {} from {} (reward: {} EDGE) [request {}]", 
+                &hash[..12.min(hash.len())], body.device_id, reward, request_id);
+            
+            HttpResponse::Ok().json(ApiResponse::success(IoTSubmissionResponse {
+                tx_hash: hash,
+                device_id: body.device_id.clone(),
+                reward,
+                quality_score,
+                block_pending: true,
+            }))
+        }
+        Err(e) => {
+            log::warn!("External IoT data from {} rejected: {} [request {}]", body.device_id, e, request_id);
+            transaction_rejected(&e)
+        }
+    }
+}
+
+/// Batch submit IoT telemetry data from multiple devices
+/// 
+/// # Endpoint
+/// POST /api/iot/batch_submit
+/// 
+/// # Request Body
+/// ```json
+/// {
+///   "transactions": [
+///     {"device_id": "sensor_001", "api_key": "key", "telemetry": {...}, "category": "SmartCity"},
+///     {"device_id": "sensor_002", "api_key": "key", "telemetry": {...}, "category": "Manufacturing"}
+///   ]
+/// }
+/// ```
+/// 
+/// # Limits
+/// - Maximum 100 transactions per batch
+/// - Items count against each device's per-minute submission limit
+pub async fn batch_submit_iot_data(
+    data: web::Data<AppState>,
+    devices: web::Data<DeviceState>,
+    rate_limit: web::Data<IoTRateLimitState>,
+    body: web::Json<BatchIoTDataRequest>,
+) -> impl Responder {
+    const MAX_BATCH_SIZE: usize = 100;
+    
+    // Validate batch size
+    if body.transactions.is_empty() {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Empty batch: at least one transaction required"));
+    }
+    
+    if body.transactions.len() > MAX_BATCH_SIZE {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error(&format!(
+                "Batch too large: maximum {} transactions allowed", MAX_BATCH_SIZE
+            )));
+    }
+    
+    let policy = data.blockchain.read().await.state.reward_policy.clone();
+    
+    let mut results = Vec::with_capacity(body.transactions.len());
+    let mut successful = 0;
+    let mut failed = 0;
+    
+    // Phase 1: Pre-validate and build transactions (can be done without blockchain lock)
+    let mut valid_transactions: Vec<(ExternalIoTDataRequest, Transaction, u64)> = Vec::new();
+    
+    for item in &body.transactions {
+        // Validate category
+        if !policy.is_known_category(&item.category) {
+            results.push(BatchItemResult {
+                device_id: item.device_id.clone(),
+                success: false,
+                tx_hash: None,
+                reward: None,
+                error: Some(format!("Invalid category: {}", item.category)),
+            });
+            failed += 1;
+            continue;
+        }
+        
+        let checked = match check_api_key(&devices, item).await {
+            Ok(()) => rate_limit.check(&item.device_id).await
+                .map_err(|retry_after| format!("Rate limit exceeded; retry in {}s", retry_after)),
+            Err(e) => Err(e),
+        };
+        let checked = match checked {
+            Ok(()) => signed_contribution(&devices, item, &policy).await,
+            Err(e) => Err(e),
+        };
+        let checked = match checked {
+            Ok(tx) => check_firmware(&devices, item).await.map(|_| tx),
+            Err(e) => Err(e),
+        };
+        let tx = match checked {
+            Ok(tx) => tx,
+            Err(e) => {
+                results.push(BatchItemResult {
+                    device_id: item.device_id.clone(),
+                    success: false,
+                    tx_hash: None,
+                    reward: None,
+                    error: Some(e),
+                });
+                failed += 1;
+                continue;
+            }
+        };
+        
+        let reward = policy.expected_reward(&item.category, tx.data.as_ref().map_or(0, |d| d.len() as u64));
+        valid_transactions.push((item.clone(), tx, reward));
+    }
+    
+    // Phase 2: Use parallel batch validation if we have valid transactions
+    if !valid_transactions.is_empty() {
+        let txs: Vec<Transaction> = valid_transactions.iter().map(|(_, tx, _)| tx.clone()).collect();
+        // Use parallel batch processing
+        if let Err(e) = data.reorg.check() {
+            return transaction_rejected(&e);
+        }
+        let mut blockchain = data.blockchain.write().await;
+        let (_batch_success, _batch_failed, successful_hashes) = blockchain.add_transactions_batch(txs);
+        
+        // Build results from batch processing
+        let hash_set: std::collections::HashSet<String> = successful_hashes.into_iter().collect();
+        
+        for (item, tx, reward) in valid_transactions {
+            if hash_set.contains(&tx.hash) {
+                results.push(BatchItemResult {
+                    device_id: item.device_id.clone(),
+                    success: true,
+                    tx_hash: Some(tx.hash),
+                    reward: Some(reward),
+                    error: None,
+                });
+                successful += 1;
+            } else {
+                results.push(BatchItemResult {
+                    device_id: item.device_id.clone(),
+                    success: false,
+                    tx_hash: None,
+                    reward: None,
+                    error: Some("Transaction validation failed".to_string()),
+                });
+                failed += 1;
+            }
+        }
+    }
+    
+    info!("Batch IoT submission: {} successful, {} failed out of {} total", 
+        successful, failed, body.transactions.len());
+    
+    HttpResponse::Ok().json(ApiResponse::success(BatchIoTSubmissionResponse {
+        total: body.transactions.len(),
+        successful,
+        failed,
+        results,
+    }))
+}
+
+/// Get device registration info and API documentation
+pub async fn get_iot_api_info() -> impl Responder {
+    #[derive(Serialize)]
+    struct IoTApiInfo {
+        version: &'static str,
+        endpoints: Vec<EndpointInfo>,
+        categories: Vec<&'static str>,
+        example_request: serde_json::Value,
+    }
+    
+    #[derive(Serialize)]
+    struct EndpointInfo {
+        method: &'static str,
+        path: &'static str,
+        description: &'static str,
+    }
+    
+    let info = IoTApiInfo {
+        version: "1.1.0",
+        endpoints: vec![
+            EndpointInfo {
+                method: "POST",
+                path: "/api/iot/prepare",
+                description: "Get the payload and message a device signs for a submission",
+            },
+            EndpointInfo {
+                method: "POST",
+                path: "/api/iot/submit",
+                description: "Submit single IoT telemetry data to the blockchain",
+            },
+            EndpointInfo {
+                method: "POST",
+                path: "/api/iot/batch_submit",
+                description: "Submit multiple IoT telemetry data in a single request (max 100 per batch)",
+            },
+            EndpointInfo {
+                method: "POST",
+                path: "/api/devices/{device_id}/api-keys",
+                description: "Mint the API key a registered device submits with (signed by the device key)",
+            },
+            EndpointInfo {
+                method: "GET",
+                path: "/api/iot/info",
+                description: "Get API documentation and supported categories",
+            },
+        ],
+        categories: vec!["SmartCity", "Manufacturing", "Agriculture", "Energy", "Healthcare", "Logistics", "EdgeAI", "General"],
+        example_request: serde_json::json!({
+            "single_submit": {
+                "device_id": "my_sensor_001",
+                "api_key": "your_api_key_here",
+                "telemetry": {
+                    "temperature": 25.5,
+                    "humidity": 60,
+                    "pressure": 1013.25
+                },
+                "category": "SmartCity",
+                "location": [1.3521, 103.8198],
+                "counter": 1,
+                "signature": "<device key signature over message_to_sign from /api/iot/prepare>"
+            },
+            "batch_submit": {
+                "transactions": [
+                    {
+                        "device_id": "sensor_001",
+                        "api_key": "your_api_key",
+                        "telemetry": {"temperature": 25.5},
+                        "category": "SmartCity",
+                        "counter": 1,
+                        "signature": "<hex>"
+                    },
+                    {
+                        "device_id": "sensor_002",
+                        "api_key": "your_api_key",
+                        "telemetry": {"humidity": 60},
+                        "category": "Manufacturing",
+                        "counter": 1,
+                        "signature": "<hex>"
+                    }
+                ]
+            }
+        }),
+    };
+    
+    HttpResponse::Ok().json(ApiResponse::success(info))
+}
+
+// ============ Router Configuration ============
+
+pub fn configure_iot_routes(cfg: &mut web::ServiceConfig) {
+    cfg
+        // Simulated IoT feed
+        .route("/api/iot/transactions", web::get().to(get_iot_transactions))
+        .route("/api/iot/sectors", web::get().to(get_iot_sectors))
+        
+        // External IoT device API
+        .route("/api/iot/prepare", web::post().to(prepare_iot_data))
+        .route("/api/iot/submit", web::post().to(submit_iot_data))
+        .route("/api/iot/batch_submit", web::post().to(batch_submit_iot_data))
+        .route("/api/iot/info", web::get().to(get_iot_api_info));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App, http::StatusCode};
+
+    #[actix_web::test]
+    async fn test_relocated_iot_routes_respond() {
+        let app = test::init_service(App::new().configure(configure_iot_routes)).await;
+
+        for path in ["/api/iot/info", "/api/iot/sectors", "/api/iot/transactions?limit=5"] {
+            let resp = test::call_service(&app, test::TestRequest::get().uri(path).to_request()).await;
+            assert_eq!(resp.status(), StatusCode::OK, "GET {} failed", path);
+        }
+
+        // Submission routes are still registered (they need node state, but must not 404)
+        for path in ["/api/iot/submit", "/api/iot/batch_submit"] {
+            let req = test::TestRequest::post().uri(path).set_json(serde_json::json!({})).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_ne!(resp.status(), StatusCode::NOT_FOUND, "POST {} not routed", path);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_iot_submissions_signed_and_rate_limited_per_device() {
+        use std::sync::Arc;
+        use crate::blockchain::chain::tests::test_chain;
+        use crate::consensus::{DeviceRegistry, DeviceType, GeoRegion, PoIEConsensus};
+        use crate::data_market::DataMarketplace;
+        use crate::network::{NetworkManager, NodeType};
+
+        let device_key = crate::crypto::Wallet::new();
+        let other_key = crate::crypto::Wallet::new();
+        let mut registry = DeviceRegistry::new();
+        let device = registry.register_device(device_key.public_key_hex(), DeviceType::Sensor, GeoRegion::new("US")).unwrap();
+        let other = registry.register_device(other_key.public_key_hex(), DeviceType::Sensor, GeoRegion::new("US")).unwrap();
+        let (_, api_key) = registry.create_api_key(&device.device_id).unwrap();
+        let (_, other_api_key) = registry.create_api_key(&other.device_id).unwrap();
+        let chain = test_chain();
+        let reorg = chain.reorg_circuit();
+        let blockchain = Arc::new(RwLock::new(chain));
+        let app_state = web::Data::new(AppState {
+            blockchain: blockchain.clone(),
+            reorg,
+            consensus: Arc::new(RwLock::new(PoIEConsensus::new())),
+            marketplace: Arc::new(RwLock::new(DataMarketplace::new())),
+            network: Arc::new(NetworkManager::new("test".to_string(), NodeType::FullNode, 0)),
+            migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+            block_migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .app_data(web::Data::new(DeviceState { registry: Arc::new(RwLock::new(registry)) }))
+                .app_data(web::Data::new(IoTRateLimitState::new(3)))
+                .configure(configure_iot_routes),
+        ).await;
+
+        // Signs a SmartCity reading, but sends `category`
+        let request = |device_id: &str, wallet: &crate::crypto::Wallet, key: &str, reading: u32, category: &str| {
+            let counter = reading as u64 + 1;
+            let telemetry = serde_json::json!({"temperature": reading});
+            let payload = iot_payload(device_id, "SmartCity", &telemetry, None, None, counter);
+            let message = Transaction::create_data_contribution_signing_message(wallet.address(), &payload);
+            test::TestRequest::post().uri("/api/iot/submit").set_json(serde_json::json!({
+                "device_id": device_id,
+                "api_key": key,
+                "telemetry": telemetry,
+                "category": category,
+                "counter": counter,
+                "signature": wallet.sign(message.as_bytes()),
+            })).to_request()
+        };
+        let submit = |reading: u32, key: &str| request(&device.device_id, &device_key, key, reading, "SmartCity");
+
+        for reading in 0..3 {
+            let resp = test::call_service(&app, submit(reading, &api_key)).await;
+            assert_eq!(resp.status(), StatusCode::OK, "submission {} refused", reading);
+        }
+        let resp = test::call_service(&app, submit(3, &api_key)).await;
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = resp.headers().get("Retry-After").unwrap().to_str().unwrap().parse().unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        // Unauthenticated requests don't use up the device's quota
+        let resp = test::call_service(&app, submit(4, "edk_wrong")).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // The signature covers the stored fields, not just the telemetry
+        let resp = test::call_service(&app, request(&other.device_id, &other_key, &other_api_key, 0, "Energy")).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = test::call_service(&app, request(&other.device_id, &other_key, &other_api_key, 1, "SmartCity")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let chain = blockchain.read().await;
+        let pending = chain.get_pending_transactions(10);
+        assert_eq!(pending.len(), 4);
+        let from_other = pending.iter().find(|tx| tx.sender == other_key.address()).unwrap();
+        assert!(from_other.check_sender_signature().is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_iot_submission_carries_pow_over_signed_message() {
+        use std::sync::Arc;
+        use crate::blockchain::chain::tests::test_chain;
+        use crate::blockchain::transaction::solve_pow;
+        use crate::consensus::{DeviceRegistry, DeviceType, GeoRegion, PoIEConsensus};
+        use crate::data_market::DataMarketplace;
+        use crate::network::{NetworkManager, NodeType};
+
+        let device_key = crate::crypto::Wallet::new();
+        let mut registry = DeviceRegistry::new();
+        let device = registry.register_device(device_key.public_key_hex(), DeviceType::Sensor, GeoRegion::new("US")).unwrap();
+        let (_, api_key) = registry.create_api_key(&device.device_id).unwrap();
+        let mut chain = test_chain();
+        chain.state.params.tx_pow_bits = 8;
+        let reorg = chain.reorg_circuit();
+        let blockchain = Arc::new(RwLock::new(chain));
+        let app_state = web::Data::new(AppState {
+            blockchain: blockchain.clone(),
+            reorg,
+            consensus: Arc::new(RwLock::new(PoIEConsensus::new())),
+            marketplace: Arc::new(RwLock::new(DataMarketplace::new())),
+            network: Arc::new(NetworkManager::new("test".to_string(), NodeType::FullNode, 0)),
+            migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+            block_migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state)
+                .app_data(web::Data::new(DeviceState { registry: Arc::new(RwLock::new(registry)) }))
+                .app_data(web::Data::new(IoTRateLimitState::new(0)))
+                .configure(configure_iot_routes),
+        ).await;
+
+        // The device gets the message from prepare, signs it and solves the PoW over it
+        let submit = |counter: u64, pow_nonce: Option<u64>| {
+            let device_id = device.device_id.clone();
+            let api_key = api_key.clone();
+            let key = &device_key;
+            let app = &app;
+            async move {
+                let fields = serde_json::json!({
+                    "device_id": device_id,
+                    "telemetry": {"temperature": counter},
+                    "category": "SmartCity",
+                    "counter": counter,
+                });
+                let req = test::TestRequest::post().uri("/api/iot/prepare").set_json(&fields).to_request();
+                let prepared: serde_json::Value = test::call_and_read_body_json(app, req).await;
+                let message = prepared["data"]["message_to_sign"].as_str().unwrap().to_string();
+                let mut body = fields;
+                body["api_key"] = api_key.into();
+                body["signature"] = key.sign(message.as_bytes()).into();
+                body["pow_nonce"] = pow_nonce.map(|_| solve_pow(&message, 8)).into();
+                let req = test::TestRequest::post().uri("/api/iot/submit").set_json(&body).to_request();
+                let resp = test::call_service(app, req).await;
+                let status = resp.status();
+                let body: serde_json::Value = test::read_body_json(resp).await;
+                (status, body)
+            }
+        };
+
+        let (status, body) = submit(1, None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "INSUFFICIENT_POW");
+
+        let (status, body) = submit(2, Some(0)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        let pending = blockchain.read().await.get_pending_transactions(10);
+        assert_eq!(pending.len(), 1);
+        assert!(pending[0].verify_pow(8));
+    }
+}
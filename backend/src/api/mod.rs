@@ -1,18 +1,26 @@
 //! API module for EdgeAI Blockchain
 //! 
 //! This module provides RESTful API endpoints for blockchain operations,
-//! wallet management, data marketplace, device registry, staking, smart contracts,
-//! and on-chain governance.
+//! wallet management, data marketplace, device registry, IoT telemetry, validators,
+//! staking, consensus diagnostics, smart contracts, and on-chain governance.
 
 pub mod auth;
+pub mod error;
 pub mod rest;
 pub mod wallet;
 pub mod data;
 pub mod device;
+pub mod iot;
+pub mod validators;
 pub mod staking;
 pub mod contracts;
 pub mod governance;
 pub mod dex;
+pub mod request_id;
+pub mod cors;
+pub mod faucet;
+pub mod ws;
+pub mod consensus;
 
 // Authentication exports
 pub use auth::{SignedRequest, AuthData, verify_signed_request, create_sign_message};
@@ -24,7 +32,12 @@ pub use rest::{AppState, configure_routes};
 pub use wallet::configure_wallet_routes;
 pub use data::configure_data_routes;
 pub use device::{DeviceState, configure_device_routes};
+pub use iot::configure_iot_routes;
+pub use validators::configure_validator_routes;
 pub use staking::{StakingState, configure_staking_routes};
 pub use contracts::{ContractState, configure_contract_routes};
 pub use governance::{GovernanceState, configure_governance_routes};
 pub use dex::{DexState, configure_dex_routes};
+pub use ws::configure_ws_routes;
+pub use consensus::configure_consensus_routes;
+pub use faucet::{Faucet, configure_faucet_routes};
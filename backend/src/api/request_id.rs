@@ -0,0 +1,183 @@
+//! Request correlation ids
+//!
+//! Every API request gets an `X-Request-Id`: the client's own if it sent a
+//! well-formed one, otherwise a fresh UUID. The id is stored in the request
+//! extensions (handlers can take `RequestId` as an extractor), recorded on a
+//! `tracing` span around the handler, echoed in the response header and added
+//! to JSON error bodies so clients can quote it when reporting a failure.
+
+#![allow(dead_code)]
+
+use std::future::{ready, Ready};
+
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, CONTENT_TYPE};
+use actix_web::middleware::Next;
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied id that is accepted
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Correlation id of the request being handled
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    pub fn new() -> Self {
+        RequestId(Uuid::new_v4().to_string())
+    }
+
+    /// Id for work that did not arrive through the API, e.g. `internal("p2p")`
+    pub fn internal(origin: &str) -> Self {
+        RequestId(format!("{}-{}", origin, Uuid::new_v4()))
+    }
+
+    /// Accept a client id made of ASCII letters, digits, `-`, `_` and `.`
+    fn from_client(value: &str) -> Option<Self> {
+        let valid = !value.is_empty()
+            && value.len() <= MAX_REQUEST_ID_LEN
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        valid.then(|| RequestId(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(req.extensions().get::<RequestId>().cloned().unwrap_or_default()))
+    }
+}
+
+/// Middleware assigning and propagating the request id
+pub async fn request_id_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(RequestId::from_client)
+        .unwrap_or_default();
+    req.extensions_mut().insert(request_id.clone());
+
+    let span = tracing::info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path()
+    );
+    let res = next.call(req).instrument(span).await?;
+
+    let mut res = if res.status().is_client_error() || res.status().is_server_error() {
+        attach_to_error_body(res, &request_id).await?
+    } else {
+        res.map_into_boxed_body()
+    };
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+    }
+    Ok(res)
+}
+
+/// Add `"request_id"` to a JSON object error body; other bodies pass through
+async fn attach_to_error_body(
+    res: ServiceResponse<impl MessageBody + 'static>,
+    request_id: &RequestId,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let is_json = res
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    if !is_json {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.into().to_string()))?;
+    let body = match serde_json::from_slice::<serde_json::Value>(&bytes) {
+        Ok(serde_json::Value::Object(mut map)) => {
+            map.insert("request_id".to_string(), request_id.as_str().into());
+            serde_json::to_vec(&map).map(BoxBody::new).unwrap_or_else(|_| BoxBody::new(bytes))
+        }
+        _ => BoxBody::new(bytes),
+    };
+    Ok(ServiceResponse::new(req, res.set_body(body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::ApiResponse;
+    use actix_web::middleware::from_fn;
+    use actix_web::{test, web, App, HttpResponse};
+
+    async fn echo(request_id: RequestId) -> HttpResponse {
+        HttpResponse::Ok().body(request_id.0)
+    }
+
+    async fn fail(request_id: RequestId) -> HttpResponse {
+        log::warn!("[request {}] rejecting", request_id);
+        HttpResponse::BadRequest().json(ApiResponse::<()>::error("bad input"))
+    }
+
+    #[actix_web::test]
+    async fn test_request_id_propagation() {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(request_id_middleware))
+                .route("/echo", web::get().to(echo))
+                .route("/fail", web::get().to(fail)),
+        )
+        .await;
+
+        // A generated id reaches the handler and comes back in the header
+        let res = test::call_service(&app, test::TestRequest::get().uri("/echo").to_request()).await;
+        let header = res.headers().get(REQUEST_ID_HEADER).unwrap().to_str().unwrap().to_string();
+        assert!(Uuid::parse_str(&header).is_ok());
+        assert_eq!(test::read_body(res).await, header.as_bytes());
+
+        // A client id is propagated; malformed ones are replaced
+        let req = test::TestRequest::get().uri("/echo").insert_header((REQUEST_ID_HEADER, "client-42")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "client-42");
+        let req = test::TestRequest::get().uri("/echo").insert_header((REQUEST_ID_HEADER, "bad id!")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_ne!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "bad id!");
+
+        // Error bodies carry the same id as the header
+        let req = test::TestRequest::get().uri("/fail").insert_header((REQUEST_ID_HEADER, "client-43")).to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "client-43");
+        let body: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(body["request_id"], "client-43");
+        assert_eq!(body["error"], "bad input");
+    }
+}
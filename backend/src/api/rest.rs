@@ -12,10 +12,21 @@ use tokio::sync::RwLock;
 use log::info;
 use std::sync::Mutex as StdMutex;
 
-use crate::blockchain::{Blockchain, Transaction, Block};
-use crate::consensus::{PoIEConsensus};
+use crate::blockchain::{Blockchain, ReorgCircuit, Transaction, TransactionError};
+use super::error::ApiError;
+use crate::blockchain::transaction::NATIVE_TOKEN;
+use crate::blockchain::tx_trace::TraceFilter;
+use crate::blockchain::chain::ChainStats;
+use crate::blockchain::benchmark::{benchmark_enabled_from_env, run_benchmark, DEFAULT_BENCHMARK_TXS};
+use crate::consensus::{PoIEConsensus, DeviceRegistry, GovernanceManager, GovernanceStats, StakingManager};
+use crate::consensus::device_registry::DeviceRegistryStats;
+use crate::consensus::staking::StakingStats;
 use crate::data_market::{DataMarketplace, DataListing, DataCategory, SortBy};
-use crate::network::NetworkManager;
+use crate::network::{NetworkManager, ScoringStats, SyncManager, SyncProgress, SyncState};
+use super::device::DeviceState;
+use super::dex::{DexManager, DexState};
+use super::governance::GovernanceState;
+use super::staking::StakingState;
 
 // Re-export Validator for use in handlers
 use crate::consensus::poie::Validator;
@@ -23,6 +34,9 @@ use crate::consensus::poie::Validator;
 /// Application state shared across handlers
 pub struct AppState {
     pub blockchain: Arc<RwLock<Blockchain>>,
+    /// The chain's reorg flag; a reorg holds the chain lock while it runs, so
+    /// submissions check this first instead of queueing behind it
+    pub reorg: ReorgCircuit,
     pub consensus: Arc<RwLock<PoIEConsensus>>,
     pub marketplace: Arc<RwLock<DataMarketplace>>,
     pub network: Arc<NetworkManager>,
@@ -58,17 +72,36 @@ impl<T: Serialize> ApiResponse<T> {
 }
 
 #[derive(Debug, Deserialize)]
-pub struct TransferRequest {
-    pub from: String,
-    pub to: String,
-    pub amount: u64,
+pub struct TransactionSearchQuery {
+    pub memo: Option<String>,
+    pub limit: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
-pub struct DataContributionRequest {
-    pub sender: String,
-    pub data: String,
-    pub category: Option<String>,
+pub struct MempoolQuery {
+    pub limit: Option<usize>,
+}
+
+/// One level of a merkle inclusion proof
+#[derive(Debug, Serialize)]
+pub struct MerkleProofStep {
+    pub sibling: String,
+    /// Whether the sibling is the left node when hashing the pair
+    pub is_left: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransactionProofResponse {
+    pub tx_hash: String,
+    pub block_index: u64,
+    pub merkle_root: String,
+    /// Sibling hashes from the leaf up to the root
+    pub proof: Vec<MerkleProofStep>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenQuery {
+    pub token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -87,13 +120,6 @@ pub struct PurchaseDataRequest {
     pub data_hash: String,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct FaucetRequest {
-    pub address: String,
-    #[serde(default)]
-    pub amount: Option<u64>,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct MineBlockRequest {
     pub validator: String,
@@ -130,16 +156,15 @@ pub async fn get_blocks(
     data: web::Data<AppState>,
     query: web::Query<PaginationQuery>,
 ) -> impl Responder {
+    let (offset, limit) = query.resolve();
     let blockchain = data.blockchain.read().await;
-    let start = query.offset.unwrap_or(0) as usize;
-    let limit = query.limit.unwrap_or(10) as usize;
     
-    let blocks: Vec<&Block> = blockchain.chain.iter()
-        .skip(start)
-        .take(limit)
-        .collect();
-    
-    HttpResponse::Ok().json(ApiResponse::success(blocks))
+    HttpResponse::Ok().json(ApiResponse::success(Page {
+        items: blockchain.blocks_page(offset, limit as usize),
+        total: blockchain.total_blocks,
+        offset,
+        limit,
+    }))
 }
 
 /// Get block by index
@@ -177,6 +202,19 @@ pub async fn get_latest_block(data: web::Data<AppState>) -> impl Responder {
     HttpResponse::Ok().json(ApiResponse::success(block))
 }
 
+/// Latest finalized block: no reorg can replace it or anything below it
+pub async fn get_finalized(data: web::Data<AppState>) -> impl Responder {
+    let blockchain = data.blockchain.read().await;
+    let height = blockchain.finalized_height();
+    let hash = blockchain.get_block_with_disk_fallback(height).map(|block| block.hash);
+    HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "height": height,
+        "hash": hash,
+        "finality_depth": blockchain.finality_depth(),
+        "latest_height": blockchain.total_blocks.saturating_sub(1),
+    })))
+}
+
 // ============ Transaction Endpoints ============
 
 /// Get transaction by hash
@@ -193,70 +231,72 @@ pub async fn get_transaction(
     }
 }
 
-/// Get pending transactions
-pub async fn get_pending_transactions(data: web::Data<AppState>) -> impl Responder {
+/// Merkle inclusion proof for a mined transaction (GET /api/tx/{hash}/proof)
+pub async fn get_transaction_proof(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let hash = path.into_inner();
     let blockchain = data.blockchain.read().await;
-    HttpResponse::Ok().json(ApiResponse::success(&blockchain.pending_transactions))
+    
+    let Some(block) = blockchain.find_transaction_block(&hash) else {
+        return HttpResponse::NotFound().json(ApiResponse::<()>::error("Transaction not found in any block"));
+    };
+    match block.merkle_proof(&hash) {
+        Some(proof) => HttpResponse::Ok().json(ApiResponse::success(TransactionProofResponse {
+            tx_hash: hash,
+            block_index: block.index,
+            merkle_root: block.header.merkle_root.clone(),
+            proof: proof.into_iter()
+                .map(|(sibling, is_left)| MerkleProofStep { sibling, is_left })
+                .collect(),
+        })),
+        None => HttpResponse::NotFound().json(ApiResponse::<()>::error("Transaction not found in any block")),
+    }
 }
 
-/// Create transfer transaction
-pub async fn create_transfer(
+/// Search transactions (GET /api/transactions?memo=)
+pub async fn search_transactions(
     data: web::Data<AppState>,
-    body: web::Json<TransferRequest>,
+    query: web::Query<TransactionSearchQuery>,
 ) -> impl Responder {
-    let tx = Transaction::transfer(
-        body.from.clone(),
-        body.to.clone(),
-        body.amount,
-    );
+    let memo = match query.memo.as_deref() {
+        Some(memo) if !memo.is_empty() => memo,
+        _ => return HttpResponse::BadRequest().json(ApiResponse::<()>::error("memo query parameter required")),
+    };
+    let limit = query.limit.unwrap_or(50).min(500);
     
-    let mut blockchain = data.blockchain.write().await;
-    match blockchain.add_transaction(tx) {
-        Ok(hash) => {
-            info!("Transfer created: {} -> {} ({} tokens)", 
-                &body.from[..8.min(body.from.len())], 
-                &body.to[..8.min(body.to.len())], 
-                body.amount);
-            HttpResponse::Ok().json(ApiResponse::success(hash))
-        }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
-    }
+    let blockchain = data.blockchain.read().await;
+    let txs = blockchain.get_transactions_by_memo(memo, limit);
+    HttpResponse::Ok().json(ApiResponse::success(txs))
+}
+
+/// Get pending transactions
+pub async fn get_pending_transactions(data: web::Data<AppState>) -> impl Responder {
+    let blockchain = data.blockchain.read().await;
+    let pending: Vec<&Transaction> = blockchain.pending_transactions.iter().collect();
+    HttpResponse::Ok().json(ApiResponse::success(pending))
 }
 
-/// Create data contribution transaction
-pub async fn create_data_contribution(
+/// Pending transactions by gas price, highest first (GET /api/mempool?limit=)
+pub async fn get_mempool(
     data: web::Data<AppState>,
-    body: web::Json<DataContributionRequest>,
+    query: web::Query<MempoolQuery>,
 ) -> impl Responder {
-    let tx = Transaction::data_contribution(
-        body.sender.clone(),
-        body.data.clone(),
-        body.sender.clone(),
-    );
-    
-    let quality_score = tx.data_quality.as_ref()
-        .map(|q| q.overall_score)
-        .unwrap_or(0.0);
-    
-    let mut blockchain = data.blockchain.write().await;
-    match blockchain.add_transaction(tx) {
-        Ok(hash) => {
-            info!("Data contribution: {} (quality: {:.2})", 
-                &body.sender[..8.min(body.sender.len())], quality_score);
-            
-            #[derive(Serialize)]
-            struct ContributionResponse {
-                tx_hash: String,
-                quality_score: f64,
-            }
-            
-            HttpResponse::Ok().json(ApiResponse::success(ContributionResponse {
-                tx_hash: hash,
-                quality_score,
-            }))
-        }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
-    }
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let blockchain = data.blockchain.read().await;
+    HttpResponse::Ok().json(ApiResponse::success(blockchain.get_pending_transactions(limit)))
+}
+
+/// Pending pool size, fees and average gas price (GET /api/mempool/stats)
+pub async fn get_mempool_stats(data: web::Data<AppState>) -> impl Responder {
+    let blockchain = data.blockchain.read().await;
+    HttpResponse::Ok().json(ApiResponse::success(blockchain.mempool_stats()))
+}
+
+/// Response for a transaction the chain refused, with its status and error code
+pub fn transaction_rejected(e: &TransactionError) -> HttpResponse {
+    e.error_response()
 }
 
 // ============ Account Endpoints ============
@@ -275,59 +315,39 @@ pub async fn get_account(
     }
 }
 
-/// Faucet - Give test tokens to an address (testnet only)
-pub async fn faucet(
-    data: web::Data<AppState>,
-    body: web::Json<FaucetRequest>,
-) -> impl Responder {
-    let mut blockchain = data.blockchain.write().await;
-    let amount = body.amount.unwrap_or(1000);
-    
-    // For testnet: directly credit the account balance without requiring sender balance
-    // Get or create the account and add tokens directly
-    {
-        use crate::blockchain::chain::Account;
-        let account = blockchain.state.accounts
-            .entry(body.address.clone())
-            .or_insert_with(|| Account::new(body.address.clone()));
-        account.balance += amount;
-    }
-    
-    // Create a record transaction hash for the faucet distribution
-    let tx_hash = format!("faucet_{}_{}", body.address, chrono::Utc::now().timestamp());
-    
-    info!("Faucet: credited {} tokens to {}", amount, &body.address);
-    
-    #[derive(Serialize)]
-    struct FaucetResponse {
-        address: String,
-        amount: u64,
-        transaction_hash: String,
-    }
-    
-    HttpResponse::Ok().json(ApiResponse::success(FaucetResponse {
-        address: body.address.clone(),
-        amount,
-        transaction_hash: tx_hash,
-    }))
-}
-
 /// Get account balance
 pub async fn get_balance(
     data: web::Data<AppState>,
     path: web::Path<String>,
+    query: web::Query<TokenQuery>,
 ) -> impl Responder {
     let address = path.into_inner();
+    let token = query.token.as_deref().unwrap_or(NATIVE_TOKEN).to_uppercase();
     let blockchain = data.blockchain.read().await;
-    let balance = blockchain.get_balance(&address);
+    let balance = blockchain.get_token_balance(&address, &token);
     
     #[derive(Serialize)]
     struct BalanceResponse {
         address: String,
+        token: String,
         balance: u64,
     }
     
-    HttpResponse::Ok().json(ApiResponse::success(BalanceResponse { address, balance }))
+    HttpResponse::Ok().json(ApiResponse::success(BalanceResponse { address, token, balance }))
+}
+
+/// Get the next nonce a transfer from this account must carry
+pub async fn get_account_nonce(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let address = path.into_inner();
+    let blockchain = data.blockchain.read().await;
+    let nonce = blockchain.next_nonce(&address);
+    HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "address": address,
+        "nonce": nonce,
+    })))
 }
 
 /// Get account transactions
@@ -341,6 +361,25 @@ pub async fn get_account_transactions(
     HttpResponse::Ok().json(ApiResponse::success(txs))
 }
 
+/// Paginated transaction history of an address, including blocks on disk
+pub async fn get_address_transactions(
+    data: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<PaginationQuery>,
+) -> impl Responder {
+    let address = path.into_inner();
+    let (offset, limit) = query.resolve();
+    let blockchain = data.blockchain.read().await;
+    let (items, total) = blockchain.address_transactions_page(&address, offset as usize, limit as usize);
+    
+    HttpResponse::Ok().json(ApiResponse::success(Page {
+        items,
+        total: total as u64,
+        offset,
+        limit,
+    }))
+}
+
 // ============ Mining Endpoints ============
 
 /// Mine a new block
@@ -439,14 +478,7 @@ pub async fn get_marketplace_listings(
     let category = query.category.as_ref()
         .map(|c| DataCategory::from_string(c));
     
-    let sort_by = match query.sort_by.as_deref() {
-        Some("price_asc") => SortBy::PriceAsc,
-        Some("price_desc") => SortBy::PriceDesc,
-        Some("quality") => SortBy::QualityDesc,
-        Some("popularity") => SortBy::PopularityDesc,
-        Some("rating") => SortBy::RatingDesc,
-        _ => SortBy::Newest,
-    };
+    let sort_by = query.sort_by.as_deref().map(SortBy::from_string).unwrap_or(SortBy::Newest);
     
     let listings = marketplace.search(
         query.query.as_deref(),
@@ -507,6 +539,50 @@ pub async fn get_network_stats(data: web::Data<AppState>) -> impl Responder {
     HttpResponse::Ok().json(ApiResponse::success(stats))
 }
 
+/// Get network stats with the estimated clock offset from peers
+pub async fn get_network_status(data: web::Data<AppState>) -> impl Responder {
+    let stats = data.network.get_stats().await;
+    let clock = data.network.clock.read().await.status();
+    HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "network": stats,
+        "clock": clock,
+    })))
+}
+
+/// Get block propagation latency histograms
+pub async fn get_propagation_stats(data: web::Data<AppState>) -> impl Responder {
+    let report = data.network.propagation.read().await.report();
+    HttpResponse::Ok().json(ApiResponse::success(report))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct BenchmarkRequest {
+    /// Synthetic transactions to run (default 1000)
+    pub transactions: Option<usize>,
+}
+
+/// Run the node self-benchmark on a scratch chain (testnet/dev only,
+/// enabled with `EDGEAI_ENABLE_BENCHMARK`)
+pub async fn run_node_benchmark(body: Option<web::Json<BenchmarkRequest>>) -> impl Responder {
+    if !benchmark_enabled_from_env() {
+        return HttpResponse::Forbidden().json(ApiResponse::<()>::error(
+            "Benchmark is disabled; set EDGEAI_ENABLE_BENCHMARK=true on testnet/dev nodes",
+        ));
+    }
+    let tx_count = body.and_then(|b| b.transactions).unwrap_or(DEFAULT_BENCHMARK_TXS);
+    info!("Running node benchmark with {} transactions", tx_count);
+    match tokio::task::spawn_blocking(move || run_benchmark(tx_count)).await {
+        Ok(Ok(report)) => HttpResponse::Ok().json(ApiResponse::success(report)),
+        Ok(Err(e)) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Benchmark failed: {}", e))),
+    }
+}
+
+/// Get block sync progress
+pub async fn get_sync_status(sync: web::Data<SyncManager>) -> impl Responder {
+    HttpResponse::Ok().json(ApiResponse::success(sync.get_progress().await))
+}
+
 /// Get peers
 pub async fn get_peers(data: web::Data<AppState>) -> impl Responder {
     let peers = data.network.get_active_peers().await;
@@ -515,12 +591,31 @@ pub async fn get_peers(data: web::Data<AppState>) -> impl Responder {
 
 // ============ Utility Types ============
 
+/// Largest page size accepted by paginated endpoints
+pub const MAX_PAGE_LIMIT: u64 = 100;
+
 #[derive(Debug, Deserialize)]
 pub struct PaginationQuery {
     pub offset: Option<u64>,
     pub limit: Option<u64>,
 }
 
+impl PaginationQuery {
+    /// Offset and limit with defaults applied and the limit capped
+    pub fn resolve(&self) -> (u64, u64) {
+        (self.offset.unwrap_or(0), self.limit.unwrap_or(10).min(MAX_PAGE_LIMIT))
+    }
+}
+
+/// Envelope for paginated listings
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+    pub offset: u64,
+    pub limit: u64,
+}
+
 // ============ Health & Status Endpoints ============
 
 /// Lightweight health check for Fly.io and load balancers
@@ -532,11 +627,81 @@ pub async fn health_check() -> impl Responder {
     }))
 }
 
+/// Seconds without a new block before `/api/ready` fails
+/// (`EDGEAI_READY_MAX_BLOCK_AGE_SECS`, default ten target block times)
+pub fn ready_max_block_age_secs_from_env(target_block_secs: u64) -> i64 {
+    std::env::var("EDGEAI_READY_MAX_BLOCK_AGE_SECS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(10 * target_block_secs as i64)
+}
+
+/// Whether the node is caught up and producing blocks
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    /// Why the node isn't ready yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub sync_state: SyncState,
+    pub current_height: u64,
+    /// Highest height announced by peers
+    pub target_height: u64,
+    pub peer_count: usize,
+    /// Seconds since the node last appended a block
+    pub last_block_age_secs: i64,
+}
+
+/// Ready once the chain has reached the best height peers announced and a
+/// block was appended within `max_block_age_secs` of `now`
+pub fn readiness(
+    chain: &Blockchain,
+    sync: &SyncProgress,
+    peer_count: usize,
+    now: i64,
+    max_block_age_secs: i64,
+) -> Readiness {
+    let current_height = chain.latest_block().index;
+    let last_block_age_secs = (now - chain.last_block_time).max(0);
+    let reason = if sync.target_height > current_height {
+        Some(format!("Syncing: height {} of {}", current_height, sync.target_height))
+    } else if last_block_age_secs > max_block_age_secs {
+        Some(format!("No block appended for {}s", last_block_age_secs))
+    } else {
+        None
+    };
+    Readiness {
+        ready: reason.is_none(),
+        reason,
+        sync_state: sync.state.clone(),
+        current_height,
+        target_height: sync.target_height,
+        peer_count,
+        last_block_age_secs,
+    }
+}
+
+/// Readiness probe: 200 once caught up, 503 while syncing or stalled
+pub async fn readiness_check(data: web::Data<AppState>, sync: web::Data<SyncManager>) -> impl Responder {
+    let progress = sync.get_progress().await;
+    let peer_count = data.network.get_active_peers().await.len();
+    let chain = data.blockchain.read().await;
+    let readiness = readiness(&chain, &progress, peer_count, chrono::Utc::now().timestamp(),
+        ready_max_block_age_secs_from_env(chain.state.params.target_block_secs));
+    if readiness.ready {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
+
 /// Node status endpoint with chain metrics, disk usage, and RocksDB stats for monitoring
 pub async fn get_node_status(data: web::Data<AppState>) -> impl Responder {
     let blockchain = data.blockchain.read().await;
     let height = blockchain.total_blocks;
     let pending_tx = blockchain.pending_transactions.len();
+    let pending_synthetic_tx = blockchain.synthetic_transactions.len();
     let last_block_time = blockchain.last_block_time;
     let difficulty = blockchain.difficulty;
     let active_accounts = blockchain.state.accounts.len();
@@ -586,6 +751,7 @@ pub async fn get_node_status(data: web::Data<AppState>) -> impl Responder {
         "status": "running",
         "chain_height": height,
         "pending_tx": pending_tx,
+        "pending_synthetic_tx": pending_synthetic_tx,
         "last_block_time": last_block_time,
         "difficulty": difficulty,
         "active_accounts": active_accounts,
@@ -751,6 +917,64 @@ pub async fn trigger_cold_migration(data: web::Data<AppState>) -> impl Responder
     }))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct StateDumpQuery {
+    pub height: Option<u64>,
+    /// `json` (default) or `csv` (accounts only)
+    pub format: Option<String>,
+}
+
+/// Dump the full chain state with its state root (GET /api/state/dump)
+pub async fn get_state_dump(
+    data: web::Data<AppState>,
+    query: web::Query<StateDumpQuery>,
+) -> impl Responder {
+    let dump = match data.blockchain.read().await.state_dump(query.height) {
+        Ok(dump) => dump,
+        Err(e) => return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+    };
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => HttpResponse::Ok().json(ApiResponse::success(dump)),
+        "csv" => HttpResponse::Ok()
+            .content_type("text/csv")
+            .insert_header(("X-State-Root", dump.state_root.clone()))
+            .insert_header(("X-Block-Height", dump.height.to_string()))
+            .body(dump.state.accounts_csv()),
+        other => HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error(&format!("Unknown format '{}', use json or csv", other))),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TxTraceQuery {
+    pub tx_hash: Option<String>,
+}
+
+/// Get recorded transaction lifecycle events (GET /api/maintenance/tx-trace)
+pub async fn get_tx_trace(
+    data: web::Data<AppState>,
+    query: web::Query<TxTraceQuery>,
+) -> impl Responder {
+    let blockchain = data.blockchain.read().await;
+    let events = blockchain.tracer.events(query.tx_hash.as_deref());
+    HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+        "filter": blockchain.tracer.filter,
+        "events": events,
+    })))
+}
+
+/// Replace the transaction tracing filter (POST /api/maintenance/tx-trace)
+pub async fn set_tx_trace_filter(
+    data: web::Data<AppState>,
+    body: web::Json<TraceFilter>,
+) -> impl Responder {
+    let filter = body.into_inner();
+    info!("Transaction tracing filter updated: {} senders, {} types, flagged={}",
+        filter.senders.len(), filter.tx_types.len(), filter.flagged);
+    data.blockchain.write().await.tracer.set_filter(filter.clone());
+    HttpResponse::Ok().json(ApiResponse::success(filter))
+}
+
 /// Get migration status (GET /api/maintenance/migration-status)
 pub async fn get_migration_status(data: web::Data<AppState>) -> impl Responder {
     let status = data.migration_status.lock().unwrap().clone();
@@ -787,34 +1011,191 @@ pub async fn get_migration_status(data: web::Data<AppState>) -> impl Responder {
     }))
 }
 
+// ============ Metrics Endpoints ============
+
+/// Trading pair totals across the DEX
+#[derive(Debug, Serialize)]
+pub struct DexTotals {
+    pub pair_count: usize,
+    pub volume_24h: u64,
+}
+
+/// Node-wide statistics for dashboards, one section per manager
+#[derive(Debug, Serialize)]
+pub struct NodeMetrics {
+    pub chain: ChainStats,
+    pub staking: StakingStats,
+    pub governance: GovernanceStats,
+    pub peers: ScoringStats,
+    pub devices: DeviceRegistryStats,
+    pub dex: DexTotals,
+}
+
+impl NodeMetrics {
+    /// Gather each manager's `get_stats`; peer stats are read separately since they're async
+    pub fn collect(
+        chain: &Blockchain,
+        staking: &StakingManager,
+        governance: &GovernanceManager,
+        peers: ScoringStats,
+        devices: &DeviceRegistry,
+        dex: &DexManager,
+    ) -> Self {
+        NodeMetrics {
+            chain: chain.get_stats(),
+            staking: staking.get_stats(),
+            governance: governance.get_stats(),
+            peers,
+            devices: devices.get_stats(),
+            dex: DexTotals {
+                pair_count: dex.pairs.len(),
+                volume_24h: dex.pairs.values().map(|p| p.volume_24h).sum(),
+            },
+        }
+    }
+
+    /// (name, type, help, value) for every exported sample
+    fn samples(&self) -> Vec<(&'static str, &'static str, &'static str, f64)> {
+        let c = &self.chain;
+        let s = &self.staking;
+        let g = &self.governance;
+        let p = &self.peers;
+        let d = &self.devices;
+        vec![
+            ("edgeai_chain_height", "gauge", "Current chain height", c.height as f64),
+            ("edgeai_chain_transactions_total", "counter", "Transactions included in blocks", c.total_transactions as f64),
+            ("edgeai_chain_total_supply", "gauge", "Total token supply", c.total_supply as f64),
+            ("edgeai_chain_total_staked", "gauge", "Tokens staked on chain", c.total_staked as f64),
+            ("edgeai_chain_active_accounts", "gauge", "Accounts with state", c.active_accounts as f64),
+            ("edgeai_chain_data_entries", "gauge", "Data contributions on chain", c.data_entries as f64),
+            ("edgeai_chain_difficulty", "gauge", "Current mining difficulty", c.difficulty as f64),
+            ("edgeai_chain_last_block_time_seconds", "gauge", "Timestamp of the latest block", c.last_block_time as f64),
+            ("edgeai_chain_network_entropy", "gauge", "Network data entropy", c.network_entropy),
+            ("edgeai_chain_avg_tx_per_block", "gauge", "Average transactions per block", c.avg_tx_per_block),
+            ("edgeai_chain_data_throughput", "gauge", "Data throughput", c.data_throughput),
+            ("edgeai_chain_tps", "gauge", "Transactions per second", c.tps),
+            ("edgeai_chain_validator_power", "gauge", "Total PoIE validator power", c.validator_power),
+            ("edgeai_mempool_pending_real", "gauge", "Pending API/P2P transactions", c.pending_real as f64),
+            ("edgeai_mempool_pending_synthetic", "gauge", "Pending synthetic transactions", c.pending_synthetic as f64),
+            ("edgeai_chain_real_tx_included_total", "counter", "API/P2P transactions included in blocks", c.real_tx_included as f64),
+            ("edgeai_chain_synthetic_tx_included_total", "counter", "Synthetic transactions included in blocks", c.synthetic_tx_included as f64),
+            ("edgeai_staking_validators", "gauge", "Registered validators", s.total_validators as f64),
+            ("edgeai_staking_active_validators", "gauge", "Active validators", s.active_validators as f64),
+            ("edgeai_staking_jailed_validators", "gauge", "Jailed validators", s.jailed_validators as f64),
+            ("edgeai_staking_total_staked", "gauge", "Self-bonded validator stake", s.total_staked as f64),
+            ("edgeai_staking_total_delegated", "gauge", "Delegated stake", s.total_delegated as f64),
+            ("edgeai_staking_delegators", "gauge", "Distinct delegators", s.total_delegators as f64),
+            ("edgeai_staking_unbonding", "gauge", "Pending unbonding entries", s.unbonding_count as f64),
+            ("edgeai_staking_slash_events_total", "counter", "Slashing events", s.slash_events as f64),
+            ("edgeai_governance_proposals_total", "counter", "Governance proposals submitted", g.total_proposals as f64),
+            ("edgeai_governance_active_proposals", "gauge", "Proposals open for voting", g.active_proposals as f64),
+            ("edgeai_governance_passed_proposals", "gauge", "Proposals that passed", g.passed_proposals as f64),
+            ("edgeai_governance_rejected_proposals", "gauge", "Proposals that were rejected", g.rejected_proposals as f64),
+            ("edgeai_governance_votes_total", "counter", "Votes cast", g.total_votes as f64),
+            ("edgeai_peers", "gauge", "Scored peers", p.total_peers as f64),
+            ("edgeai_peers_trusted", "gauge", "Trusted peers", p.trusted_peers as f64),
+            ("edgeai_peers_suspicious", "gauge", "Suspicious peers", p.suspicious_peers as f64),
+            ("edgeai_peers_blacklisted", "gauge", "Blacklisted peers", p.blacklisted_peers as f64),
+            ("edgeai_peers_average_score", "gauge", "Average peer score", p.average_score),
+            ("edgeai_devices", "gauge", "Registered devices", d.total_devices as f64),
+            ("edgeai_devices_active", "gauge", "Active devices", d.active_devices as f64),
+            ("edgeai_devices_contribution_points", "gauge", "Total device contribution points", d.total_contribution_points),
+            ("edgeai_devices_average_reputation", "gauge", "Average device reputation", d.average_reputation),
+            ("edgeai_devices_regions", "gauge", "Regions with registered devices", d.regions_covered as f64),
+            ("edgeai_devices_types", "gauge", "Distinct device types", d.device_types as f64),
+            ("edgeai_dex_pairs", "gauge", "DEX trading pairs", self.dex.pair_count as f64),
+            ("edgeai_dex_volume_24h", "gauge", "DEX volume over the last 24 hours", self.dex.volume_24h as f64),
+        ]
+    }
+
+    /// Render in Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        for (name, kind, help, value) in self.samples() {
+            out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, value));
+        }
+        out
+    }
+}
+
+async fn collect_node_metrics(
+    data: &AppState,
+    staking: &StakingState,
+    governance: &GovernanceState,
+    devices: &DeviceState,
+    dex: &DexState,
+) -> NodeMetrics {
+    let peers = data.network.scoring.get_stats().await;
+    let chain = data.blockchain.read().await;
+    let staking = staking.manager.read().await;
+    let governance = governance.read().await;
+    let devices = devices.registry.read().await;
+    let dex = dex.manager.read().await;
+    NodeMetrics::collect(&chain, &staking, &governance, peers, &devices, &dex)
+}
+
+/// Aggregate node metrics as JSON (GET /api/metrics)
+pub async fn get_metrics(
+    data: web::Data<AppState>,
+    staking: web::Data<StakingState>,
+    governance: web::Data<GovernanceState>,
+    devices: web::Data<DeviceState>,
+    dex: web::Data<DexState>,
+) -> impl Responder {
+    let metrics = collect_node_metrics(&data, &staking, &governance, &devices, &dex).await;
+    HttpResponse::Ok().json(ApiResponse::success(metrics))
+}
+
+/// Aggregate node metrics for Prometheus scraping (GET /metrics)
+pub async fn get_prometheus_metrics(
+    data: web::Data<AppState>,
+    staking: web::Data<StakingState>,
+    governance: web::Data<GovernanceState>,
+    devices: web::Data<DeviceState>,
+    dex: web::Data<DexState>,
+) -> impl Responder {
+    let metrics = collect_node_metrics(&data, &staking, &governance, &devices, &dex).await;
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.to_prometheus())
+}
+
 // ============ Router Configuration ============
 
 pub fn configure_routes(cfg: &mut web::ServiceConfig) {
     cfg
         // Health & status routes (must be first for Fly.io health checks)
         .route("/api/health", web::get().to(health_check))
+        .route("/api/ready", web::get().to(readiness_check))
         .route("/api/status", web::get().to(get_node_status))
 
         // Blockchain routes
         .route("/api/chain", web::get().to(get_chain_info))
         .route("/api/blocks", web::get().to(get_blocks))
         .route("/api/blocks/latest", web::get().to(get_latest_block))
+        .route("/api/finalized", web::get().to(get_finalized))
         .route("/api/blocks/{index}", web::get().to(get_block))
         .route("/api/blocks/hash/{hash}", web::get().to(get_block_by_hash))
         
         // Transaction routes
+        .route("/api/transactions", web::get().to(search_transactions))
         .route("/api/transactions/{hash}", web::get().to(get_transaction))
         .route("/api/transactions/pending", web::get().to(get_pending_transactions))
-        .route("/api/transactions/transfer", web::post().to(create_transfer))
-        .route("/api/transactions/contribute", web::post().to(create_data_contribution))
+        .route("/api/tx/{hash}/proof", web::get().to(get_transaction_proof))
+        
+        // Mempool routes
+        .route("/api/mempool", web::get().to(get_mempool))
+        .route("/api/mempool/stats", web::get().to(get_mempool_stats))
+        
+        // State routes
+        .route("/api/state/dump", web::get().to(get_state_dump))
         
         // Account routes
         .route("/api/accounts/{address}", web::get().to(get_account))
         .route("/api/accounts/{address}/balance", web::get().to(get_balance))
+        .route("/api/accounts/{address}/nonce", web::get().to(get_account_nonce))
         .route("/api/accounts/{address}/transactions", web::get().to(get_account_transactions))
-        
-        // Faucet route (for testnet)
-        .route("/api/faucet", web::post().to(faucet))
+        .route("/api/address/{address}/transactions", web::get().to(get_address_transactions))
         
         // Mining routes
         .route("/api/mine", web::post().to(mine_block))
@@ -832,11 +1213,21 @@ pub fn configure_routes(cfg: &mut web::ServiceConfig) {
         
         // Network routes
         .route("/api/network", web::get().to(get_network_stats))
+        .route("/api/network/status", web::get().to(get_network_status))
+        .route("/api/sync/status", web::get().to(get_sync_status))
         .route("/api/network/peers", web::get().to(get_peers))
+        .route("/api/network/propagation", web::get().to(get_propagation_stats))
+
+        // Metrics routes
+        .route("/api/metrics", web::get().to(get_metrics))
+        .route("/metrics", web::get().to(get_prometheus_metrics))
         
         // Maintenance routes
+        .route("/api/admin/benchmark", web::post().to(run_node_benchmark))
         .route("/api/maintenance/cold-migrate", web::post().to(trigger_cold_migration))
         .route("/api/maintenance/migration-status", web::get().to(get_migration_status))
+        .route("/api/maintenance/tx-trace", web::get().to(get_tx_trace))
+        .route("/api/maintenance/tx-trace", web::post().to(set_tx_trace_filter))
         .route("/api/maintenance/cold-blocks-migrate", web::post().to(trigger_cold_blocks_migration))
         .route("/api/maintenance/debug-blocks-cf", web::get().to(debug_blocks_cf));
 }
@@ -917,3 +1308,73 @@ pub async fn debug_blocks_cf(data: web::Data<AppState>) -> impl Responder {
     }))
 }
 // trigger deploy
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::chain::tests::test_chain;
+    use crate::consensus::{GovernanceConfig, StakingConfig};
+
+    #[test]
+    fn test_readiness_waits_for_sync_and_recent_block() {
+        let mut chain = test_chain();
+        chain.mine_block("miner".to_string()).unwrap();
+        let now = chain.last_block_time + 5;
+        let mut sync = SyncProgress { target_height: 3, ..SyncProgress::default() };
+
+        let syncing = readiness(&chain, &sync, 2, now, 60);
+        assert!(!syncing.ready);
+        assert_eq!(syncing.current_height, 1);
+        assert!(syncing.reason.unwrap().starts_with("Syncing"));
+
+        sync.target_height = 1;
+        let ready = readiness(&chain, &sync, 2, now, 60);
+        assert!(ready.ready);
+        assert_eq!(ready.peer_count, 2);
+        assert_eq!(ready.last_block_age_secs, 5);
+
+        // Caught up, but nothing appended for too long
+        assert!(!readiness(&chain, &sync, 2, now + 60, 60).ready);
+    }
+
+    #[test]
+    fn test_prometheus_output_names_and_types() {
+        let chain = test_chain();
+        let peers = ScoringStats {
+            total_peers: 3,
+            trusted_peers: 1,
+            suspicious_peers: 1,
+            blacklisted_peers: 1,
+            average_score: 42.5,
+        };
+        let dex = DexManager::new();
+        let metrics = NodeMetrics::collect(
+            &chain,
+            &StakingManager::new(StakingConfig::default()),
+            &GovernanceManager::new(GovernanceConfig::default()),
+            peers,
+            &DeviceRegistry::new(),
+            &dex,
+        );
+        assert_eq!(metrics.dex.pair_count, dex.pairs.len());
+
+        let text = metrics.to_prometheus();
+        for (name, kind) in [
+            ("edgeai_chain_height", "gauge"),
+            ("edgeai_chain_transactions_total", "counter"),
+            ("edgeai_staking_active_validators", "gauge"),
+            ("edgeai_staking_slash_events_total", "counter"),
+            ("edgeai_governance_proposals_total", "counter"),
+            ("edgeai_peers_blacklisted", "gauge"),
+            ("edgeai_devices", "gauge"),
+            ("edgeai_dex_pairs", "gauge"),
+            ("edgeai_dex_volume_24h", "gauge"),
+        ] {
+            assert!(text.contains(&format!("# TYPE {} {}\n", name, kind)), "missing {}", name);
+            assert!(text.contains(&format!("# HELP {} ", name)), "missing help for {}", name);
+        }
+        assert!(text.contains(&format!("edgeai_chain_height {}\n", chain.total_blocks)));
+        assert!(text.contains("edgeai_peers_average_score 42.5\n"));
+        assert!(text.contains(&format!("edgeai_dex_pairs {}\n", dex.pairs.len())));
+    }
+}
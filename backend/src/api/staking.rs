@@ -11,10 +11,14 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use log::info;
 
+use crate::consensus::finality::ATTESTATION_WINDOW;
+use crate::consensus::staking::StakingError;
 use crate::consensus::{
-    StakingManager, StakingConfig, ValidatorDescription, SlashReason,
+    StakingManager, StakingConfig, StakingValidator, ValidatorDescription, ValidatorFilter, ValidatorSortBy,
+    ValidatorStatus, SlashReason, Attestation, DelegatorSlash, DEFAULT_PERFORMANCE_WINDOW,
 };
-use super::rest::ApiResponse;
+use crate::crypto::{verify_signature, address_from_public_key};
+use super::rest::{ApiResponse, AppState, Page, MAX_PAGE_LIMIT};
 
 /// Staking state (shared across handlers)
 pub struct StakingState {
@@ -23,17 +27,6 @@ pub struct StakingState {
 
 // ============ Request Types ============
 
-#[derive(Debug, Deserialize)]
-pub struct RegisterValidatorRequest {
-    pub address: String,
-    pub operator_address: String,
-    pub stake: u64,
-    pub commission_rate: f64,
-    pub moniker: String,
-    pub website: Option<String>,
-    pub details: Option<String>,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct DelegateRequest {
     pub delegator: String,
@@ -48,11 +41,70 @@ pub struct UndelegateRequest {
     pub amount: u64,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RedelegateRequest {
+    pub delegator: String,
+    pub src_validator: String,
+    pub dst_validator: String,
+    pub amount: u64,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct UnjailRequest {
     pub validator: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateCommissionRequest {
+    pub operator: String,
+    pub commission_rate: f64,
+    /// Operator's public key
+    pub public_key: String,
+    /// Signature over `commission_sign_message`
+    pub signature: String,
+    /// Unix time (seconds) the request was signed at
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttestRequest {
+    pub validator: String,
+    pub height: u64,
+    pub block_hash: String,
+    pub public_key: String,
+    /// Signature over `ATTEST:{chain_id}:{height}:{block_hash}`, where
+    /// `chain_id` is the genesis block hash
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttestationQuery {
+    pub height: u64,
+}
+
+/// Default page size of the validator listing
+pub const DEFAULT_VALIDATOR_PAGE: u64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct ValidatorListQuery {
+    /// `active`, `inactive`, `jailed`, `unbonding` or `probation`
+    pub status: Option<String>,
+    /// Highest commission rate (0.0 - 1.0)
+    pub max_commission: Option<f64>,
+    /// Lowest total stake
+    pub min_stake: Option<u64>,
+    /// `voting_power` (default), `uptime`, `commission` or `stake`
+    pub sort_by: Option<String>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PerformanceQuery {
+    /// Number of most recent blocks to cover
+    pub window: Option<usize>,
+}
+
 // ============ Response Types ============
 
 #[derive(Debug, Serialize)]
@@ -72,12 +124,48 @@ pub struct ValidatorResponse {
     pub total_rewards: u64,
 }
 
+impl From<&StakingValidator> for ValidatorResponse {
+    fn from(v: &StakingValidator) -> Self {
+        ValidatorResponse {
+            address: v.address.clone(),
+            operator_address: v.operator_address.clone(),
+            moniker: v.description.moniker.clone(),
+            self_stake: v.self_stake,
+            delegated_stake: v.delegated_stake,
+            total_stake: v.total_stake(),
+            commission_rate: v.commission_rate,
+            status: format!("{:?}", v.status),
+            reputation: v.reputation,
+            voting_power: v.voting_power(),
+            blocks_validated: v.blocks_validated,
+            uptime: v.uptime(),
+            total_rewards: v.total_rewards,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct DelegationResponse {
     pub delegator: String,
     pub validator: String,
     pub amount: u64,
     pub rewards: u64,
+    pub slashed_total: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnbondingEntryResponse {
+    pub validator: Option<String>,
+    pub amount: u64,
+    pub completion_time: String,
+    pub remaining_secs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DelegatorSlashesResponse {
+    pub delegator: String,
+    pub total_slashed: u64,
+    pub slashes: Vec<DelegatorSlash>,
 }
 
 #[derive(Debug, Serialize)]
@@ -121,32 +209,44 @@ pub async fn get_staking_stats(data: web::Data<StakingState>) -> impl Responder
     })
 }
 
-/// Get all validators
-pub async fn get_validators(data: web::Data<StakingState>) -> impl Responder {
-    let manager = data.manager.read().await;
-    let validators: Vec<ValidatorResponse> = manager
-        .validators
-        .values()
-        .map(|v| ValidatorResponse {
-            address: v.address.clone(),
-            operator_address: v.operator_address.clone(),
-            moniker: v.description.moniker.clone(),
-            self_stake: v.self_stake,
-            delegated_stake: v.delegated_stake,
-            total_stake: v.total_stake(),
-            commission_rate: v.commission_rate,
-            status: format!("{:?}", v.status),
-            reputation: v.reputation,
-            voting_power: v.voting_power(),
-            blocks_validated: v.blocks_validated,
-            uptime: v.uptime(),
-            total_rewards: v.total_rewards,
-        })
-        .collect();
+/// List validators matching the query filters, sorted and paginated
+pub async fn get_validators(
+    data: web::Data<StakingState>,
+    query: web::Query<ValidatorListQuery>,
+) -> impl Responder {
+    let parsed = query.status.as_deref().map(str::parse::<ValidatorStatus>).transpose().and_then(|status| {
+        let sort_by = query.sort_by.as_deref().map(str::parse::<ValidatorSortBy>).transpose()?;
+        Ok((status, sort_by.unwrap_or_default()))
+    });
+    let (status, sort_by) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ApiResponse::<()> {
+                success: false,
+                data: None,
+                error: Some(e),
+            })
+        }
+    };
+    let filter = ValidatorFilter { status, max_commission: query.max_commission, min_stake: query.min_stake };
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_VALIDATOR_PAGE).min(MAX_PAGE_LIMIT);
 
+    let manager = data.manager.read().await;
+    let validators = manager.find_validators(&filter, sort_by);
     HttpResponse::Ok().json(ApiResponse {
         success: true,
-        data: Some(validators),
+        data: Some(Page {
+            total: validators.len() as u64,
+            items: validators
+                .into_iter()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .map(ValidatorResponse::from)
+                .collect(),
+            offset,
+            limit,
+        }),
         error: None,
     })
 }
@@ -156,22 +256,8 @@ pub async fn get_active_validators(data: web::Data<StakingState>) -> impl Respon
     let manager = data.manager.read().await;
     let validators: Vec<ValidatorResponse> = manager
         .get_active_validators()
-        .iter()
-        .map(|v| ValidatorResponse {
-            address: v.address.clone(),
-            operator_address: v.operator_address.clone(),
-            moniker: v.description.moniker.clone(),
-            self_stake: v.self_stake,
-            delegated_stake: v.delegated_stake,
-            total_stake: v.total_stake(),
-            commission_rate: v.commission_rate,
-            status: format!("{:?}", v.status),
-            reputation: v.reputation,
-            voting_power: v.voting_power(),
-            blocks_validated: v.blocks_validated,
-            uptime: v.uptime(),
-            total_rewards: v.total_rewards,
-        })
+        .into_iter()
+        .map(ValidatorResponse::from)
         .collect();
 
     HttpResponse::Ok().json(ApiResponse {
@@ -192,21 +278,7 @@ pub async fn get_validator(
     match manager.get_validator(&address) {
         Some(v) => HttpResponse::Ok().json(ApiResponse {
             success: true,
-            data: Some(ValidatorResponse {
-                address: v.address.clone(),
-                operator_address: v.operator_address.clone(),
-                moniker: v.description.moniker.clone(),
-                self_stake: v.self_stake,
-                delegated_stake: v.delegated_stake,
-                total_stake: v.total_stake(),
-                commission_rate: v.commission_rate,
-                status: format!("{:?}", v.status),
-                reputation: v.reputation,
-                voting_power: v.voting_power(),
-                blocks_validated: v.blocks_validated,
-                uptime: v.uptime(),
-                total_rewards: v.total_rewards,
-            }),
+            data: Some(ValidatorResponse::from(v)),
             error: None,
         }),
         None => HttpResponse::NotFound().json(ApiResponse::<()> {
@@ -217,40 +289,26 @@ pub async fn get_validator(
     }
 }
 
-/// Register a new validator
-pub async fn register_validator(
+/// Get a validator's recent uptime, reward rate and slash count
+pub async fn get_validator_performance(
     data: web::Data<StakingState>,
-    req: web::Json<RegisterValidatorRequest>,
+    path: web::Path<String>,
+    query: web::Query<PerformanceQuery>,
 ) -> impl Responder {
-    let mut manager = data.manager.write().await;
-
-    let description = ValidatorDescription {
-        moniker: req.moniker.clone(),
-        identity: None,
-        website: req.website.clone(),
-        security_contact: None,
-        details: req.details.clone(),
-    };
+    let address = path.into_inner();
+    let window = query.window.unwrap_or(DEFAULT_PERFORMANCE_WINDOW).max(1);
+    let manager = data.manager.read().await;
 
-    match manager.register_validator(
-        req.address.clone(),
-        req.operator_address.clone(),
-        req.stake,
-        req.commission_rate,
-        description,
-    ) {
-        Ok(()) => {
-            info!("Validator {} registered via API", &req.address[..8.min(req.address.len())]);
-            HttpResponse::Ok().json(ApiResponse {
-                success: true,
-                data: Some("Validator registered successfully"),
-                error: None,
-            })
-        }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()> {
+    match manager.validator_performance(&address, window) {
+        Some(performance) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: Some(performance),
+            error: None,
+        }),
+        None => HttpResponse::NotFound().json(ApiResponse::<()> {
             success: false,
             data: None,
-            error: Some(e),
+            error: Some("Validator not found".to_string()),
         }),
     }
 }
@@ -276,11 +334,7 @@ pub async fn delegate(
                 error: None,
             })
         }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()> {
-            success: false,
-            data: None,
-            error: Some(e),
-        }),
+        Err(e) => e.into(),
     }
 }
 
@@ -308,11 +362,56 @@ pub async fn undelegate(
                 error: None,
             })
         }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()> {
-            success: false,
-            data: None,
-            error: Some(e),
+        Err(e) => e.into(),
+    }
+}
+
+/// Get an address's pending unbonding entries (GET /api/staking/unbonding/{address})
+pub async fn get_unbonding(
+    data: web::Data<StakingState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let address = path.into_inner();
+    let manager = data.manager.read().await;
+    let now = manager.now();
+
+    let entries: Vec<UnbondingEntryResponse> = manager
+        .get_unbonding(&address)
+        .into_iter()
+        .map(|entry| UnbondingEntryResponse {
+            validator: entry.validator.clone(),
+            amount: entry.amount,
+            completion_time: entry.completion_time.to_rfc3339(),
+            remaining_secs: entry.remaining_secs(now),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(entries),
+        error: None,
+    })
+}
+
+/// Move delegated stake to another validator without unbonding
+pub async fn redelegate(
+    data: web::Data<StakingState>,
+    req: web::Json<RedelegateRequest>,
+) -> impl Responder {
+    let mut manager = data.manager.write().await;
+
+    match manager.redelegate(
+        req.delegator.clone(),
+        req.src_validator.clone(),
+        req.dst_validator.clone(),
+        req.amount,
+    ) {
+        Ok(()) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: Some("Redelegation successful"),
+            error: None,
         }),
+        Err(e) => e.into(),
     }
 }
 
@@ -332,6 +431,7 @@ pub async fn get_delegations(
             validator: d.validator.clone(),
             amount: d.amount,
             rewards: d.rewards,
+            slashed_total: d.slashed_total,
         })
         .collect();
 
@@ -342,6 +442,26 @@ pub async fn get_delegations(
     })
 }
 
+/// Get the slashes that cost a delegator stake
+pub async fn get_delegator_slashes(
+    data: web::Data<StakingState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let delegator = path.into_inner();
+    let manager = data.manager.read().await;
+
+    let slashes = manager.delegator_slashes(&delegator);
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(DelegatorSlashesResponse {
+            total_slashed: slashes.iter().map(|s| s.amount).sum(),
+            delegator,
+            slashes,
+        }),
+        error: None,
+    })
+}
+
 /// Unjail a validator
 pub async fn unjail(
     data: web::Data<StakingState>,
@@ -358,14 +478,158 @@ pub async fn unjail(
                 error: None,
             })
         }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()> {
+        Err(e) => e.into(),
+    }
+}
+
+/// How long a signed commission change stays valid
+const COMMISSION_SIGNATURE_MAX_AGE_SECS: u64 = 300;
+
+/// Message an operator signs to change commission:
+/// `COMMISSION:{validator}:{commission_rate}:{timestamp}`
+pub fn commission_sign_message(validator: &str, commission_rate: f64, timestamp: u64) -> String {
+    format!("COMMISSION:{}:{}:{}", validator, commission_rate, timestamp)
+}
+
+/// Change a validator's commission rate (operator only).
+/// The signature expires after five minutes, and updates are 24 hours apart,
+/// so a captured request can't be replayed later.
+pub async fn update_commission(
+    data: web::Data<StakingState>,
+    path: web::Path<String>,
+    req: web::Json<UpdateCommissionRequest>,
+) -> impl Responder {
+    let validator = path.into_inner();
+    match address_from_public_key(&req.public_key) {
+        Ok(address) if address == req.operator => {}
+        _ => {
+            return HttpResponse::Unauthorized().json(ApiResponse::<()> {
+                success: false,
+                data: None,
+                error: Some("Public key does not match operator address".to_string()),
+            });
+        }
+    }
+
+    let now = chrono::Utc::now().timestamp() as u64;
+    if req.timestamp > now + 60 || now.saturating_sub(req.timestamp) > COMMISSION_SIGNATURE_MAX_AGE_SECS {
+        return HttpResponse::BadRequest().json(ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some("Commission request expired or timestamp in the future".to_string()),
+        });
+    }
+
+    let message = commission_sign_message(&validator, req.commission_rate, req.timestamp);
+    if !matches!(verify_signature(&req.public_key, message.as_bytes(), &req.signature), Ok(true)) {
+        return HttpResponse::Unauthorized().json(ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some("Invalid commission signature".to_string()),
+        });
+    }
+
+    let mut manager = data.manager.write().await;
+    match manager.update_commission(&req.operator, &validator, req.commission_rate) {
+        Ok(()) => {
+            let validator = manager.get_validator(&validator);
+            HttpResponse::Ok().json(ApiResponse {
+                success: true,
+                data: validator.map(|v| serde_json::json!({
+                    "commission_rate": v.commission_rate,
+                    "pending_commission": v.pending_commission,
+                    "last_commission_update": v.last_commission_update,
+                })),
+                error: None,
+            })
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Submit a validator attestation for a block
+pub async fn submit_attestation(
+    app: web::Data<AppState>,
+    data: web::Data<StakingState>,
+    req: web::Json<AttestRequest>,
+) -> impl Responder {
+    // The attestation must be signed by the validator's own key
+    match address_from_public_key(&req.public_key) {
+        Ok(address) if address == req.validator => {}
+        _ => {
+            return HttpResponse::Unauthorized().json(ApiResponse::<()> {
+                success: false,
+                data: None,
+                error: Some("Public key does not match validator address".to_string()),
+            });
+        }
+    }
+
+    // Only recent blocks this node holds can be attested to
+    let chain_id = {
+        let blockchain = app.blockchain.read().await;
+        let tip = blockchain.latest_block().index;
+        if req.height > tip || tip - req.height > ATTESTATION_WINDOW {
+            return StakingError::AttestationRejected(format!(
+                "Height {} is outside the attestation window ({} to {})",
+                req.height, tip.saturating_sub(ATTESTATION_WINDOW), tip
+            )).into();
+        }
+        if blockchain.get_block(req.height).map(|b| b.hash.as_str()) != Some(req.block_hash.as_str()) {
+            return StakingError::AttestationRejected(format!(
+                "Block {} is not on this chain at height {}", req.block_hash, req.height
+            )).into();
+        }
+        blockchain.chain_id().to_string()
+    };
+
+    let message = Attestation::signing_message(&chain_id, req.height, &req.block_hash);
+    if !matches!(verify_signature(&req.public_key, message.as_bytes(), &req.signature), Ok(true)) {
+        return HttpResponse::Unauthorized().json(ApiResponse::<()> {
             success: false,
             data: None,
-            error: Some(e),
+            error: Some("Invalid attestation signature".to_string()),
+        });
+    }
+
+    let mut manager = data.manager.write().await;
+    match manager.attest(&req.validator, req.height, &req.block_hash, req.signature.clone()) {
+        Ok(finalized) => HttpResponse::Ok().json(ApiResponse {
+            success: true,
+            data: Some(serde_json::json!({
+                "height": req.height,
+                "finalized": finalized || manager.finality.is_finalized(req.height),
+            })),
+            error: None,
         }),
+        Err(e) => e.into(),
     }
 }
 
+/// Get attestations recorded for a block height
+pub async fn get_attestations(
+    data: web::Data<StakingState>,
+    query: web::Query<AttestationQuery>,
+) -> impl Responder {
+    let manager = data.manager.read().await;
+    let summary = manager.finality.get_height(query.height);
+    let total_voting_power = manager.total_voting_power();
+
+    HttpResponse::Ok().json(ApiResponse {
+        success: true,
+        data: Some(serde_json::json!({
+            "height": summary.height,
+            "finalized": summary.finalized_hash.is_some(),
+            "finalized_hash": summary.finalized_hash,
+            "total_voting_power": total_voting_power,
+            "threshold": manager.finality.threshold,
+            "power_by_block": summary.power_by_block,
+            "attestations": summary.attestations,
+        })),
+        error: None,
+    })
+}
+
 /// Get staking configuration
 pub async fn get_config(data: web::Data<StakingState>) -> impl Responder {
     let manager = data.manager.read().await;
@@ -385,10 +649,115 @@ pub fn configure_staking_routes(cfg: &mut web::ServiceConfig) {
             .route("/validators", web::get().to(get_validators))
             .route("/validators/active", web::get().to(get_active_validators))
             .route("/validators/{address}", web::get().to(get_validator))
-            .route("/validators/register", web::post().to(register_validator))
+            .route("/validators/{address}/commission", web::post().to(update_commission))
+            .route("/validators/{address}/performance", web::get().to(get_validator_performance))
             .route("/delegate", web::post().to(delegate))
             .route("/undelegate", web::post().to(undelegate))
+            .route("/redelegate", web::post().to(redelegate))
+            .route("/unbonding/{address}", web::get().to(get_unbonding))
             .route("/delegations/{delegator}", web::get().to(get_delegations))
+            .route("/delegators/{delegator}/slashes", web::get().to(get_delegator_slashes))
             .route("/unjail", web::post().to(unjail)),
     );
+    cfg.service(
+        web::scope("/api/finality")
+            .route("/attestations", web::get().to(get_attestations))
+            .route("/attest", web::post().to(submit_attestation)),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{http::StatusCode, test, App};
+    use crate::crypto::Wallet;
+
+    #[actix_web::test]
+    async fn test_update_commission_signature_expires() {
+        let operator = Wallet::new();
+        let mut manager = StakingManager::new(StakingConfig::default());
+        manager
+            .register_validator("v1".to_string(), operator.address().to_string(), 10_000, 0.10, ValidatorDescription::default())
+            .unwrap();
+        let state = web::Data::new(StakingState { manager: Arc::new(RwLock::new(manager)) });
+        let app = test::init_service(App::new().app_data(state.clone()).configure(configure_staking_routes)).await;
+
+        let request = |rate: f64, timestamp: u64| {
+            test::TestRequest::post().uri("/api/staking/validators/v1/commission").set_json(serde_json::json!({
+                "operator": operator.address(),
+                "commission_rate": rate,
+                "public_key": operator.public_key_hex(),
+                "signature": operator.sign(commission_sign_message("v1", rate, timestamp).as_bytes()),
+                "timestamp": timestamp,
+            })).to_request()
+        };
+
+        // A change signed long ago can't be replayed
+        let now = chrono::Utc::now().timestamp() as u64;
+        let resp = test::call_service(&app, request(0.08, now - 3_600)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(state.manager.read().await.get_validator("v1").unwrap().commission_rate, 0.10);
+
+        let resp = test::call_service(&app, request(0.08, now)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(state.manager.read().await.get_validator("v1").unwrap().commission_rate, 0.08);
+    }
+
+    #[actix_web::test]
+    async fn test_attestations_checked_against_the_chain() {
+        use crate::blockchain::chain::tests::test_chain;
+        use crate::consensus::PoIEConsensus;
+        use crate::data_market::DataMarketplace;
+        use crate::network::{NetworkManager, NodeType};
+
+        let (v1, v2) = (Wallet::new(), Wallet::new());
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for v in [&v1, &v2] {
+            manager
+                .register_validator(v.address().to_string(), "op".to_string(), 10_000, 0.10, ValidatorDescription::default())
+                .unwrap();
+        }
+        let mut chain = test_chain();
+        chain.mine_block("miner".to_string()).unwrap();
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        let chain_id = chain.chain_id().to_string();
+        let reorg = chain.reorg_circuit();
+        let app_state = web::Data::new(AppState {
+            blockchain: Arc::new(RwLock::new(chain)),
+            reorg,
+            consensus: Arc::new(RwLock::new(PoIEConsensus::new())),
+            marketplace: Arc::new(RwLock::new(DataMarketplace::new())),
+            network: Arc::new(NetworkManager::new("test".to_string(), NodeType::FullNode, 0)),
+            migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+            block_migration_status: Arc::new(std::sync::Mutex::new("IDLE".to_string())),
+        });
+        let state = web::Data::new(StakingState { manager: Arc::new(RwLock::new(manager)) });
+        let app = test::init_service(
+            App::new().app_data(app_state).app_data(state.clone()).configure(configure_staking_routes),
+        ).await;
+
+        let attest = |validator: &Wallet, chain_id: &str, height: u64, block_hash: &str| {
+            test::TestRequest::post().uri("/api/finality/attest").set_json(serde_json::json!({
+                "validator": validator.address(),
+                "height": height,
+                "block_hash": block_hash,
+                "public_key": validator.public_key_hex(),
+                "signature": validator.sign(Attestation::signing_message(chain_id, height, block_hash).as_bytes()),
+            })).to_request()
+        };
+
+        // Votes for blocks this node doesn't hold, or not yet produced, are refused
+        let resp = test::call_service(&app, attest(&v1, &chain_id, block.index, "f00d")).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let resp = test::call_service(&app, attest(&v1, &chain_id, block.index + 1, &block.hash)).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        // So is a signature made for another chain
+        let resp = test::call_service(&app, attest(&v1, "other-chain", block.index, &block.hash)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        assert!(state.manager.read().await.finality.get_height(block.index).attestations.is_empty());
+
+        let resp = test::call_service(&app, attest(&v1, &chain_id, block.index, &block.hash)).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(state.manager.read().await.finality.get_height(block.index).attestations.len(), 1);
+    }
 }
@@ -0,0 +1,131 @@
+//! Validator API endpoints for EdgeAI Blockchain
+//!
+//! This module provides the simulated validator node directory used by the
+//! explorer globe, plus the live validator set backing consensus.
+
+use actix_web::{web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::validators::{ValidatorGenerator, ValidatorListResponse};
+use super::rest::ApiResponse;
+use super::staking::StakingState;
+
+// ============ Query Types ============
+
+#[derive(Debug, Deserialize)]
+pub struct ValidatorQuery {
+    pub page: Option<u64>,
+    pub limit: Option<u64>,
+    pub status: Option<String>,
+}
+
+// ============ Response Types ============
+
+#[derive(Debug, Serialize)]
+pub struct ValidatorSetMember {
+    pub address: String,
+    pub moniker: String,
+    pub total_stake: u64,
+    pub voting_power: f64,
+    /// Share of the set's total voting power (0.0 - 1.0)
+    pub power_share: f64,
+    pub status: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidatorSetResponse {
+    pub size: usize,
+    pub max_validators: usize,
+    pub total_voting_power: f64,
+    pub finality_threshold: f64,
+    pub latest_finalized_height: Option<u64>,
+    pub validators: Vec<ValidatorSetMember>,
+}
+
+// ============ Validator Node Endpoints ============
+
+/// 获取验证者节点列表
+pub async fn get_validator_nodes(query: web::Query<ValidatorQuery>) -> impl Responder {
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(100).min(1000);
+
+    let generator = ValidatorGenerator::new();
+    let mut validators = generator.generate_validators(page, limit);
+
+    // 如果指定了状态过滤
+    if let Some(ref status) = query.status {
+        use crate::validators::types::ValidatorStatus;
+        let target_status = match status.to_lowercase().as_str() {
+            "online" => Some(ValidatorStatus::Online),
+            "offline" => Some(ValidatorStatus::Offline),
+            "maintenance" => Some(ValidatorStatus::Maintenance),
+            _ => None,
+        };
+
+        if let Some(target) = target_status {
+            validators.retain(|v| v.status == target);
+        }
+    }
+
+    let stats = generator.get_stats();
+
+    let response = ValidatorListResponse {
+        validators,
+        total: generator.total_count(),
+        page,
+        limit,
+        stats,
+    };
+
+    HttpResponse::Ok().json(ApiResponse::success(response))
+}
+
+/// 获取验证者地图数据
+pub async fn get_validator_map() -> impl Responder {
+    let generator = ValidatorGenerator::new();
+    let map_response = generator.generate_map_markers();
+
+    HttpResponse::Ok().json(ApiResponse::success(map_response))
+}
+
+// ============ Validator Set Endpoints ============
+
+/// Get the live validator set (eligible validators sorted by voting power)
+pub async fn get_validator_set(data: web::Data<StakingState>) -> impl Responder {
+    let manager = data.manager.read().await;
+    let total_voting_power = manager.total_voting_power();
+
+    let validators: Vec<ValidatorSetMember> = manager
+        .get_active_validators()
+        .iter()
+        .map(|v| ValidatorSetMember {
+            address: v.address.clone(),
+            moniker: v.description.moniker.clone(),
+            total_stake: v.total_stake(),
+            voting_power: v.voting_power(),
+            power_share: if total_voting_power > 0.0 { v.voting_power() / total_voting_power } else { 0.0 },
+            status: format!("{:?}", v.status),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(ApiResponse::success(ValidatorSetResponse {
+        size: validators.len(),
+        max_validators: manager.config.max_validators,
+        total_voting_power,
+        finality_threshold: manager.config.finality_threshold,
+        latest_finalized_height: manager.finality.latest_finalized().map(|(h, _)| h),
+        validators,
+    }))
+}
+
+// ============ Router Configuration ============
+
+pub fn configure_validator_routes(cfg: &mut web::ServiceConfig) {
+    cfg
+        // Simulated validator directory
+        .route("/api/validators/nodes", web::get().to(get_validator_nodes))
+        .route("/api/validators/map", web::get().to(get_validator_map))
+        
+        // Live validator set
+        .route("/api/validators/set", web::get().to(get_validator_set));
+}
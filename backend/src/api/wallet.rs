@@ -1,14 +1,22 @@
 // EdgeAI Blockchain - Wallet API Endpoints
 // Provides wallet creation, signing, and signed transaction submission
 
+use std::collections::BTreeMap;
+
 use actix_web::{web, HttpResponse, Responder};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use log::info;
 use sha2::{Sha256, Digest};
 
 use crate::crypto::{Wallet, verify_signature, address_from_public_key};
-use crate::blockchain::Transaction;
-use super::rest::{AppState, ApiResponse};
+use crate::blockchain::{Transaction, TransactionType};
+use crate::blockchain::transaction::{canonical_sign_message, ValidatorRegistration, MAX_TRANSFER_OUTPUTS};
+use crate::consensus::ValidatorDescription;
+use crate::contracts::wasm_runtime::ContractCallPayload;
+use super::auth::verify_signed_transaction;
+use super::error::ApiError;
+use super::rest::{AppState, ApiResponse, transaction_rejected};
 
 // ============ Request/Response Types ============
 
@@ -24,6 +32,45 @@ pub struct ImportWalletRequest {
     pub secret_key: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct GenerateMnemonicRequest {
+    /// 12, 15, 18, 21 or 24 (default 12)
+    #[serde(default)]
+    pub word_count: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MnemonicWalletResponse {
+    pub address: String,
+    pub public_key: String,
+    pub secret_key: String,
+    pub mnemonic: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportMnemonicRequest {
+    pub mnemonic: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportKeystoreRequest {
+    pub secret_key: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeystoreResponse {
+    pub address: String,
+    pub keystore: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportKeystoreRequest {
+    /// Keystore JSON, as an object or a string
+    pub keystore: serde_json::Value,
+    pub password: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct SignMessageRequest {
     pub secret_key: String,
@@ -55,8 +102,65 @@ pub struct SignedTransferRequest {
     pub from: String,
     pub to: String,
     pub amount: u64,
+    /// Sender's next account nonce
+    pub nonce: u64,
+    /// Optional memo, covered by the signature
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Optional last block height for inclusion, covered by the signature
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
+    pub public_key: String,
+    pub signature: String,
+    /// Anti-spam proof-of-work nonce over the signed message, when the chain requires one
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferOutputRequest {
+    pub to: String,
+    pub amount: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedBatchTransferRequest {
+    pub from: String,
+    /// Recipients in signing order, at most `MAX_TRANSFER_OUTPUTS`
+    pub outputs: Vec<TransferOutputRequest>,
+    pub nonce: u64,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
     pub public_key: String,
     pub signature: String,
+    /// Anti-spam proof-of-work nonce over the signed message, when the chain requires one
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrepareBatchTransferRequest {
+    pub from: String,
+    pub outputs: Vec<TransferOutputRequest>,
+    /// Sender's next account nonce (looked up from the chain if omitted)
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreparedBatchTransaction {
+    pub from: String,
+    pub outputs: Vec<TransferOutputRequest>,
+    pub nonce: u64,
+    pub memo: Option<String>,
+    pub valid_until_height: Option<u64>,
+    pub message_to_sign: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,6 +169,9 @@ pub struct SignedDataContributionRequest {
     pub data: String,
     pub public_key: String,
     pub signature: String,
+    /// Anti-spam proof-of-work nonce over the signed message, when the chain requires one
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -72,6 +179,13 @@ pub struct PrepareTransferRequest {
     pub from: String,
     pub to: String,
     pub amount: u64,
+    /// Sender's next account nonce (looked up from the chain if omitted)
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -79,6 +193,9 @@ pub struct PreparedTransaction {
     pub from: String,
     pub to: String,
     pub amount: u64,
+    pub nonce: u64,
+    pub memo: Option<String>,
+    pub valid_until_height: Option<u64>,
     pub message_to_sign: String,
 }
 
@@ -95,14 +212,247 @@ pub struct PreparedDataContribution {
     pub message_to_sign: String,
 }
 
+/// Type-specific fields of a transaction signed over `canonical_sign_message`
+pub trait TypedTransactionFields {
+    /// Unsigned transaction from `from` carrying these fields
+    fn build(&self, from: String) -> Transaction;
+}
+
+/// Delegate `amount` to `validator`
+#[derive(Debug, Clone, Deserialize)]
+pub struct StakeFields {
+    pub validator: String,
+    pub amount: u64,
+}
+
+impl TypedTransactionFields for StakeFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::stake(from, self.validator.clone(), self.amount)
+    }
+}
+
+/// Start unbonding `amount` from `validator`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnstakeFields {
+    pub validator: String,
+    pub amount: u64,
+}
+
+impl TypedTransactionFields for UnstakeFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::unstake(from, self.validator.clone(), self.amount)
+    }
+}
+
+/// Re-bond the signer's newest pending unbonding of `amount` from `validator`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CancelUnbondingFields {
+    pub validator: String,
+    pub amount: u64,
+}
+
+impl TypedTransactionFields for CancelUnbondingFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::cancel_unbonding(from, self.validator.clone(), self.amount)
+    }
+}
+
+/// Set the type the signing device's telemetry is scored as
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceTypeFields {
+    pub device_type: String,
+}
+
+impl TypedTransactionFields for DeviceTypeFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::set_device_type(from, self.device_type.clone())
+    }
+}
+
+/// Lock `amount` of the signer's balance as the bond of `device`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceBondFields {
+    pub device: String,
+    pub amount: u64,
+}
+
+impl TypedTransactionFields for DeviceBondFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::device_bond(from, self.device.clone(), self.amount)
+    }
+}
+
+/// Release the bond the signer locked for `device`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceUnbondFields {
+    pub device: String,
+}
+
+impl TypedTransactionFields for DeviceUnbondFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::device_unbond(from, self.device.clone())
+    }
+}
+
+/// Buy the dataset `data_hash` from `seller`
+#[derive(Debug, Clone, Deserialize)]
+pub struct DataPurchaseFields {
+    pub seller: String,
+    pub data_hash: String,
+    pub price: u64,
+}
+
+impl TypedTransactionFields for DataPurchaseFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::data_purchase(from, self.seller.clone(), self.data_hash.clone(), self.price)
+    }
+}
+
+/// Name `owner` as the owner of the signing device (empty clears it)
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceOwnerFields {
+    pub owner: String,
+}
+
+impl TypedTransactionFields for DeviceOwnerFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::set_device_owner(from, self.owner.clone())
+    }
+}
+
+/// Register `validator`, locking `stake` of the signer's balance as its self-stake
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidatorRegisterFields {
+    pub validator: String,
+    pub stake: u64,
+    pub commission_rate: f64,
+    #[serde(default)]
+    pub description: ValidatorDescription,
+}
+
+impl TypedTransactionFields for ValidatorRegisterFields {
+    fn build(&self, from: String) -> Transaction {
+        let registration = ValidatorRegistration {
+            commission_rate: self.commission_rate,
+            description: self.description.clone(),
+        };
+        Transaction::validator_register(from, self.validator.clone(), self.stake, &registration)
+    }
+}
+
+/// Start unbonding `amount` of `validator`'s self-stake back to the signing operator
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidatorUnbondFields {
+    pub validator: String,
+    pub amount: u64,
+}
+
+impl TypedTransactionFields for ValidatorUnbondFields {
+    fn build(&self, from: String) -> Transaction {
+        Transaction::validator_unbond(from, self.validator.clone(), self.amount)
+    }
+}
+
+/// Call `function` on a deployed contract
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContractCallFields {
+    pub contract: String,
+    pub function: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, serde_json::Value>,
+}
+
+impl TypedTransactionFields for ContractCallFields {
+    fn build(&self, from: String) -> Transaction {
+        let payload = ContractCallPayload {
+            contract: self.contract.clone(),
+            function: self.function.clone(),
+            params: self.params.clone(),
+        };
+        Transaction::contract_call(from, serde_json::to_string(&payload).unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PrepareTypedRequest<T> {
+    pub from: String,
+    #[serde(flatten)]
+    pub fields: T,
+    /// Sender's next account nonce (looked up from the chain if omitted)
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PreparedTypedTransaction {
+    pub tx_type: TransactionType,
+    pub from: String,
+    pub nonce: u64,
+    pub valid_until_height: Option<u64>,
+    pub message_to_sign: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignedTypedRequest<T> {
+    pub from: String,
+    #[serde(flatten)]
+    pub fields: T,
+    pub nonce: u64,
+    #[serde(default)]
+    pub valid_until_height: Option<u64>,
+    pub public_key: String,
+    pub signature: String,
+    /// Anti-spam proof-of-work nonce over the signed message, when the chain requires one
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
+}
+
 // ============ Helper Functions ============
 
+/// Unsigned typed transaction with its replay-protection fields set
+pub fn typed_transaction<T: TypedTransactionFields>(
+    from: &str,
+    fields: &T,
+    nonce: u64,
+    valid_until_height: Option<u64>,
+) -> Transaction {
+    fields.build(from.to_string())
+        .with_nonce(nonce)
+        .with_valid_until_height(valid_until_height)
+}
+
 /// Create a deterministic message to sign for transfers
-fn create_transfer_message(from: &str, to: &str, amount: u64) -> String {
-    let data = format!("TRANSFER:{}:{}:{}", from, to, amount);
-    let mut hasher = Sha256::new();
-    hasher.update(data.as_bytes());
-    hex::encode(hasher.finalize())
+fn create_transfer_message(
+    from: &str,
+    to: &str,
+    amount: u64,
+    nonce: u64,
+    valid_until_height: Option<u64>,
+    memo: Option<&str>,
+) -> String {
+    Transaction::create_transfer_signing_message(from, to, amount, nonce, valid_until_height, memo)
+}
+
+/// Create a deterministic message to sign for batch transfers, covering every output
+fn create_batch_transfer_message(
+    from: &str,
+    outputs: &[TransferOutputRequest],
+    nonce: u64,
+    valid_until_height: Option<u64>,
+    memo: Option<&str>,
+) -> String {
+    let outputs: Vec<(String, u64)> = outputs.iter().map(|o| (o.to.clone(), o.amount)).collect();
+    Transaction::create_batch_transfer_signing_message(from, &outputs, nonce, valid_until_height, memo)
+}
+
+/// Reject empty or oversized output lists
+fn check_batch_outputs(outputs: &[TransferOutputRequest]) -> Result<(), String> {
+    if outputs.is_empty() || outputs.len() > MAX_TRANSFER_OUTPUTS {
+        return Err(format!("A batch transfer needs between 1 and {} outputs", MAX_TRANSFER_OUTPUTS));
+    }
+    Ok(())
 }
 
 /// Create a deterministic message to sign for data contributions
@@ -151,6 +501,104 @@ pub async fn import_wallet(
     }
 }
 
+/// Generate a new wallet with a BIP39 recovery phrase
+pub async fn generate_mnemonic_wallet(
+    body: Option<web::Json<GenerateMnemonicRequest>>,
+) -> impl Responder {
+    let word_count = body.and_then(|b| b.word_count).unwrap_or(12);
+    match Wallet::generate_mnemonic_with_words(word_count) {
+        Ok((wallet, mnemonic)) => {
+            info!("New mnemonic wallet generated: {}", wallet.address());
+            HttpResponse::Ok().json(ApiResponse::success(MnemonicWalletResponse {
+                address: wallet.address().to_string(),
+                public_key: wallet.public_key_hex(),
+                secret_key: wallet.secret_key_hex(),
+                mnemonic,
+            }))
+        }
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e.to_string())),
+    }
+}
+
+/// Restore a wallet from a BIP39 recovery phrase
+pub async fn import_mnemonic_wallet(
+    body: web::Json<ImportMnemonicRequest>,
+) -> impl Responder {
+    match Wallet::from_mnemonic(&body.mnemonic) {
+        Ok(wallet) => {
+            info!("Wallet imported from mnemonic: {}", wallet.address());
+            HttpResponse::Ok().json(ApiResponse::success(WalletResponse {
+                address: wallet.address().to_string(),
+                public_key: wallet.public_key_hex(),
+                secret_key: wallet.secret_key_hex(),
+            }))
+        }
+        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e.to_string())),
+    }
+}
+
+/// Encrypt a secret key into a password-protected keystore
+pub async fn export_keystore(
+    body: web::Json<ExportKeystoreRequest>,
+) -> impl Responder {
+    if body.password.is_empty() {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error("Password must not be empty"));
+    }
+    let wallet = match Wallet::from_secret_key(&body.secret_key) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&format!("Invalid secret key: {}", e)))
+        }
+    };
+    // scrypt is deliberately slow; keep it off the async workers
+    let password = body.into_inner().password;
+    let address = wallet.address().to_string();
+    match tokio::task::spawn_blocking(move || wallet.to_keystore(&password)).await {
+        Ok(json) => HttpResponse::Ok().json(ApiResponse::success(KeystoreResponse {
+            address,
+            keystore: serde_json::from_str(&json).unwrap_or_default(),
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Keystore export failed: {}", e))),
+    }
+}
+
+/// Keystore imports decrypting at once; each may take up to 256 MiB of scrypt memory
+pub const MAX_CONCURRENT_KEYSTORE_IMPORTS: usize = 4;
+
+static KEYSTORE_IMPORTS: tokio::sync::Semaphore = tokio::sync::Semaphore::const_new(MAX_CONCURRENT_KEYSTORE_IMPORTS);
+
+/// Restore a wallet from a password-protected keystore
+pub async fn import_keystore(
+    body: web::Json<ImportKeystoreRequest>,
+) -> impl Responder {
+    let Ok(permit) = KEYSTORE_IMPORTS.try_acquire() else {
+        return HttpResponse::ServiceUnavailable()
+            .insert_header(("Retry-After", "1"))
+            .json(ApiResponse::<()>::error("Too many keystore imports in progress; retry shortly"));
+    };
+    let ImportKeystoreRequest { keystore, password } = body.into_inner();
+    let json = match keystore {
+        serde_json::Value::String(json) => json,
+        other => other.to_string(),
+    };
+    // The permit is released when decryption finishes, even if the client has gone
+    match tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        Wallet::from_keystore(&json, &password)
+    }).await {
+        Ok(Ok(wallet)) => {
+            info!("Wallet imported from keystore: {}", wallet.address());
+            HttpResponse::Ok().json(ApiResponse::success(WalletResponse {
+                address: wallet.address().to_string(),
+                public_key: wallet.public_key_hex(),
+                secret_key: wallet.secret_key_hex(),
+            }))
+        }
+        Ok(Err(e)) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e.to_string())),
+        Err(e) => HttpResponse::InternalServerError().json(ApiResponse::<()>::error(&format!("Keystore import failed: {}", e))),
+    }
+}
+
 /// Get address from public key
 pub async fn get_address_from_public_key(
     path: web::Path<String>,
@@ -217,14 +665,50 @@ pub async fn verify_signature_endpoint(
 
 /// Prepare a transfer transaction for signing (returns the message to sign)
 pub async fn prepare_transfer(
+    data: web::Data<AppState>,
     body: web::Json<PrepareTransferRequest>,
 ) -> impl Responder {
-    let message_to_sign = create_transfer_message(&body.from, &body.to, body.amount);
+    let nonce = match body.nonce {
+        Some(nonce) => nonce,
+        None => data.blockchain.read().await.next_nonce(&body.from),
+    };
+    let message_to_sign = create_transfer_message(
+        &body.from, &body.to, body.amount, nonce, body.valid_until_height, body.memo.as_deref(),
+    );
     
     HttpResponse::Ok().json(ApiResponse::success(PreparedTransaction {
         from: body.from.clone(),
         to: body.to.clone(),
         amount: body.amount,
+        nonce,
+        memo: body.memo.clone(),
+        valid_until_height: body.valid_until_height,
+        message_to_sign,
+    }))
+}
+
+/// Prepare a batch transfer for signing (returns the message to sign)
+pub async fn prepare_batch_transfer(
+    data: web::Data<AppState>,
+    body: web::Json<PrepareBatchTransferRequest>,
+) -> impl Responder {
+    if let Err(e) = check_batch_outputs(&body.outputs) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+    let nonce = match body.nonce {
+        Some(nonce) => nonce,
+        None => data.blockchain.read().await.next_nonce(&body.from),
+    };
+    let message_to_sign = create_batch_transfer_message(
+        &body.from, &body.outputs, nonce, body.valid_until_height, body.memo.as_deref(),
+    );
+    
+    HttpResponse::Ok().json(ApiResponse::success(PreparedBatchTransaction {
+        from: body.from.clone(),
+        outputs: body.outputs.clone(),
+        nonce,
+        memo: body.memo.clone(),
+        valid_until_height: body.valid_until_height,
         message_to_sign,
     }))
 }
@@ -246,6 +730,62 @@ pub async fn prepare_data_contribution(
     }))
 }
 
+/// Prepare a typed transaction for signing
+pub async fn prepare_typed_transaction<T: TypedTransactionFields>(
+    data: web::Data<AppState>,
+    body: web::Json<PrepareTypedRequest<T>>,
+) -> impl Responder {
+    let nonce = match body.nonce {
+        Some(nonce) => nonce,
+        None => data.blockchain.read().await.next_nonce(&body.from),
+    };
+    let tx = typed_transaction(&body.from, &body.fields, nonce, body.valid_until_height);
+    
+    HttpResponse::Ok().json(ApiResponse::success(PreparedTypedTransaction {
+        tx_type: tx.tx_type.clone(),
+        from: body.from.clone(),
+        nonce,
+        valid_until_height: body.valid_until_height,
+        message_to_sign: canonical_sign_message(&tx),
+    }))
+}
+
+/// Submit a signed typed transaction
+pub async fn submit_typed_transaction<T: TypedTransactionFields>(
+    data: web::Data<AppState>,
+    body: web::Json<SignedTypedRequest<T>>,
+) -> HttpResponse {
+    let mut tx = typed_transaction(&body.from, &body.fields, body.nonce, body.valid_until_height)
+        .with_pow_nonce(body.pow_nonce);
+    tx.set_signature(body.signature.clone(), body.public_key.clone());
+    if let Err(e) = verify_signed_transaction(&tx) {
+        return e.error_response();
+    }
+    
+    let tx_type = tx.tx_type.clone();
+    if let Err(e) = data.reorg.check() {
+        return transaction_rejected(&e);
+    }
+    let mut blockchain = data.blockchain.write().await;
+    match blockchain.add_transaction(tx) {
+        Ok(hash) => {
+            info!("Signed {:?} transaction from {}", tx_type, &body.from[..12.min(body.from.len())]);
+            HttpResponse::Ok().json(ApiResponse::success(hash))
+        }
+        Err(e) => transaction_rejected(&e),
+    }
+}
+
+/// Routes for preparing and submitting one typed transaction kind
+fn typed_transaction_routes<T>(cfg: &mut web::ServiceConfig, prepare_path: &str, submit_path: &str)
+where
+    T: TypedTransactionFields + DeserializeOwned + 'static,
+{
+    cfg
+        .route(prepare_path, web::post().to(prepare_typed_transaction::<T>))
+        .route(submit_path, web::post().to(submit_typed_transaction::<T>));
+}
+
 /// Submit a signed transfer transaction
 pub async fn submit_signed_transfer(
     data: web::Data<AppState>,
@@ -266,7 +806,9 @@ pub async fn submit_signed_transfer(
     }
     
     // Recreate the message that should have been signed
-    let expected_message = create_transfer_message(&body.from, &body.to, body.amount);
+    let expected_message = create_transfer_message(
+        &body.from, &body.to, body.amount, body.nonce, body.valid_until_height, body.memo.as_deref(),
+    );
     
     // Verify the signature against the expected message
     match verify_signature(&body.public_key, expected_message.as_bytes(), &body.signature) {
@@ -288,10 +830,16 @@ pub async fn submit_signed_transfer(
         body.public_key.clone(),
         body.to.clone(),
         body.amount,
+        body.nonce,
+        body.memo.clone(),
         body.signature.clone(),
-    );
+    ).with_valid_until_height(body.valid_until_height)
+    .with_pow_nonce(body.pow_nonce);
     
     // Add to blockchain
+    if let Err(e) = data.reorg.check() {
+        return transaction_rejected(&e);
+    }
     let mut blockchain = data.blockchain.write().await;
     match blockchain.add_transaction(tx) {
         Ok(hash) => {
@@ -301,7 +849,79 @@ pub async fn submit_signed_transfer(
                 body.amount);
             HttpResponse::Ok().json(ApiResponse::success(hash))
         }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+        Err(e) => transaction_rejected(&e),
+    }
+}
+
+/// Submit a signed transfer paying several recipients in one transaction
+pub async fn submit_signed_batch_transfer(
+    data: web::Data<AppState>,
+    body: web::Json<SignedBatchTransferRequest>,
+) -> impl Responder {
+    if let Err(e) = check_batch_outputs(&body.outputs) {
+        return HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e));
+    }
+    
+    // Verify the address matches the public key
+    let derived_address = match address_from_public_key(&body.public_key) {
+        Ok(addr) => addr,
+        Err(e) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<()>::error(&format!("Invalid public key: {}", e)));
+        }
+    };
+    
+    if derived_address != body.from {
+        return HttpResponse::BadRequest()
+            .json(ApiResponse::<()>::error("Sender address does not match public key"));
+    }
+    
+    // A single output is an ordinary transfer and is signed as one
+    let expected_message = match body.outputs.as_slice() {
+        [output] => create_transfer_message(
+            &body.from, &output.to, output.amount, body.nonce, body.valid_until_height, body.memo.as_deref(),
+        ),
+        outputs => create_batch_transfer_message(
+            &body.from, outputs, body.nonce, body.valid_until_height, body.memo.as_deref(),
+        ),
+    };
+    
+    match verify_signature(&body.public_key, expected_message.as_bytes(), &body.signature) {
+        Ok(valid) => {
+            if !valid {
+                return HttpResponse::BadRequest()
+                    .json(ApiResponse::<()>::error("Invalid signature"));
+            }
+        }
+        Err(e) => {
+            return HttpResponse::BadRequest()
+                .json(ApiResponse::<()>::error(&format!("Signature verification error: {}", e)));
+        }
+    }
+    
+    let tx = Transaction::batch_transfer_signed(
+        body.from.clone(),
+        body.public_key.clone(),
+        body.outputs.iter().map(|o| (o.to.clone(), o.amount)).collect(),
+        body.nonce,
+        body.memo.clone(),
+        body.signature.clone(),
+    ).with_valid_until_height(body.valid_until_height)
+    .with_pow_nonce(body.pow_nonce);
+    
+    if let Err(e) = data.reorg.check() {
+        return transaction_rejected(&e);
+    }
+    let mut blockchain = data.blockchain.write().await;
+    match blockchain.add_transaction(tx) {
+        Ok(hash) => {
+            info!("Signed batch transfer: {} -> {} recipients ({} tokens)",
+                &body.from[..12.min(body.from.len())],
+                body.outputs.len(),
+                body.outputs.iter().map(|o| o.amount).sum::<u64>());
+            HttpResponse::Ok().json(ApiResponse::success(hash))
+        }
+        Err(e) => transaction_rejected(&e),
     }
 }
 
@@ -341,21 +961,27 @@ pub async fn submit_signed_data_contribution(
         }
     }
     
-    // Create the signed transaction
+    // Create the signed transaction, claiming the base reward the chain's
+    // reward policy sets for the payload
+    if let Err(e) = data.reorg.check() {
+        return transaction_rejected(&e);
+    }
+    let mut blockchain = data.blockchain.write().await;
     let tx = Transaction::data_contribution_signed(
         body.sender.clone(),
         body.public_key.clone(),
         body.data.clone(),
         body.sender.clone(),
         body.signature.clone(),
-    );
+    )
+    .with_reward_claim(blockchain.state.reward_policy.claim_for(&body.data))
+    .with_pow_nonce(body.pow_nonce);
     
     let quality_score = tx.data_quality.as_ref()
         .map(|q| q.overall_score)
         .unwrap_or(0.0);
     
     // Add to blockchain
-    let mut blockchain = data.blockchain.write().await;
     match blockchain.add_transaction(tx) {
         Ok(hash) => {
             info!("Signed data contribution: {} (quality: {:.2})", 
@@ -372,411 +998,10 @@ pub async fn submit_signed_data_contribution(
                 quality_score,
             }))
         }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
+        Err(e) => transaction_rejected(&e),
     }
 }
 
-// ============ External IoT Device API ============
-
-/// Request structure for external IoT device data submission
-/// This API allows real IoT devices to submit telemetry data to the blockchain
-#[derive(Debug, Clone, Deserialize)]
-pub struct ExternalIoTDataRequest {
-    /// Device identifier (will be used as sender address)
-    pub device_id: String,
-    /// Device API key for authentication
-    pub api_key: String,
-    /// Raw telemetry data in JSON format
-    pub telemetry: serde_json::Value,
-    /// Data category (SmartCity, Manufacturing, Agriculture, Energy, Healthcare, Logistics, EdgeAI)
-    pub category: String,
-    /// Optional geographic location [latitude, longitude]
-    pub location: Option<[f64; 2]>,
-}
-
-/// Request structure for batch IoT data submission
-/// Allows submitting multiple telemetry records in a single request
-#[derive(Debug, Deserialize)]
-pub struct BatchIoTDataRequest {
-    /// List of IoT data submissions (max 100 per batch)
-    pub transactions: Vec<ExternalIoTDataRequest>,
-}
-
-/// Response for batch IoT data submission
-#[derive(Debug, Serialize)]
-pub struct BatchIoTSubmissionResponse {
-    pub total: usize,
-    pub successful: usize,
-    pub failed: usize,
-    pub results: Vec<BatchItemResult>,
-}
-
-#[derive(Debug, Serialize)]
-pub struct BatchItemResult {
-    pub device_id: String,
-    pub success: bool,
-    pub tx_hash: Option<String>,
-    pub reward: Option<u64>,
-    pub error: Option<String>,
-}
-
-/// Response for IoT data submission
-#[derive(Debug, Serialize)]
-pub struct IoTSubmissionResponse {
-    pub tx_hash: String,
-    pub device_id: String,
-    pub reward: u64,
-    pub quality_score: f64,
-    pub block_pending: bool,
-}
-
-/// Submit IoT telemetry data from external devices
-/// 
-/// # Endpoint
-/// POST /api/iot/submit
-/// 
-/// # Request Body
-/// ```json
-/// {
-///   "device_id": "my_sensor_001",
-///   "api_key": "your_api_key",
-///   "telemetry": {"temperature": 25.5, "humidity": 60},
-///   "category": "SmartCity",
-///   "location": [1.3521, 103.8198]
-/// }
-/// ```
-/// 
-/// # Response
-/// ```json
-/// {
-///   "success": true,
-///   "data": {
-///     "tx_hash": "0x...",
-///     "device_id": "my_sensor_001",
-///     "reward": 50,
-///     "quality_score": 0.85,
-///     "block_pending": true
-///   }
-/// }
-/// ```
-pub async fn submit_iot_data(
-    data: web::Data<AppState>,
-    body: web::Json<ExternalIoTDataRequest>,
-) -> impl Responder {
-    // Validate category
-    let valid_categories = ["SmartCity", "Manufacturing", "Agriculture", "Energy", "Healthcare", "Logistics", "EdgeAI", "General"];
-    if !valid_categories.contains(&body.category.as_str()) {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error(&format!(
-                "Invalid category. Must be one of: {:?}", valid_categories
-            )));
-    }
-    
-    // TODO: Validate API key against registered devices
-    // For now, accept any non-empty API key for testing
-    if body.api_key.is_empty() {
-        return HttpResponse::Unauthorized()
-            .json(ApiResponse::<()>::error("API key required"));
-    }
-    
-    // Build telemetry JSON string
-    let telemetry_str = body.telemetry.to_string();
-    
-    // Build full data payload
-    let (lat, lng) = body.location.map(|l| (l[0], l[1])).unwrap_or((0.0, 0.0));
-    let timestamp = chrono::Utc::now().timestamp();
-    
-    let full_data = format!(
-        r#"{{"device":"{}","category":"{}","telemetry":{},"lat":{},"lng":{},"ts":{},"source":"external"}}"#,
-        body.device_id, body.category, telemetry_str, lat, lng, timestamp
-    );
-    
-    // Calculate reward based on data size and category
-    let data_size = full_data.len() as u64;
-    let base_reward = 30 + (data_size / 20);
-    let category_bonus: u64 = match body.category.as_str() {
-        "Healthcare" => 20,  // Higher value for medical data
-        "Manufacturing" => 15,
-        "Energy" => 15,
-        "Agriculture" => 10,
-        _ => 5,
-    };
-    let reward = base_reward + category_bonus;
-    
-    // Create transaction
-    use crate::blockchain::transaction::{TxOutput, TransactionType};
-    
-    let output = TxOutput {
-        amount: reward,
-        recipient: body.device_id.clone(),
-        data_hash: Some(format!("ext_{:x}", timestamp)),
-    };
-    
-    let tx = Transaction::new(
-        TransactionType::DataContribution,
-        body.device_id.clone(),
-        vec![],
-        vec![output],
-        Some(full_data),
-        1,
-        21000,
-    );
-    
-    let quality_score = tx.data_quality.as_ref()
-        .map(|q| q.overall_score)
-        .unwrap_or(0.5);
-    
-    // Add to blockchain
-    let mut blockchain = data.blockchain.write().await;
-    match blockchain.add_transaction(tx) {
-        Ok(hash) => {
-            info!("External IoT data submitted: {} from {} (reward: {} EDGE)", 
-                &hash[..12.min(hash.len())], body.device_id, reward);
-            
-            HttpResponse::Ok().json(ApiResponse::success(IoTSubmissionResponse {
-                tx_hash: hash,
-                device_id: body.device_id.clone(),
-                reward,
-                quality_score,
-                block_pending: true,
-            }))
-        }
-        Err(e) => HttpResponse::BadRequest().json(ApiResponse::<()>::error(&e)),
-    }
-}
-
-/// Batch submit IoT telemetry data from multiple devices
-/// 
-/// # Endpoint
-/// POST /api/iot/batch_submit
-/// 
-/// # Request Body
-/// ```json
-/// {
-///   "transactions": [
-///     {"device_id": "sensor_001", "api_key": "key", "telemetry": {...}, "category": "SmartCity"},
-///     {"device_id": "sensor_002", "api_key": "key", "telemetry": {...}, "category": "Manufacturing"}
-///   ]
-/// }
-/// ```
-/// 
-/// # Limits
-/// - Maximum 100 transactions per batch
-pub async fn batch_submit_iot_data(
-    data: web::Data<AppState>,
-    body: web::Json<BatchIoTDataRequest>,
-) -> impl Responder {
-    const MAX_BATCH_SIZE: usize = 100;
-    
-    // Validate batch size
-    if body.transactions.is_empty() {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error("Empty batch: at least one transaction required"));
-    }
-    
-    if body.transactions.len() > MAX_BATCH_SIZE {
-        return HttpResponse::BadRequest()
-            .json(ApiResponse::<()>::error(&format!(
-                "Batch too large: maximum {} transactions allowed", MAX_BATCH_SIZE
-            )));
-    }
-    
-    let valid_categories = ["SmartCity", "Manufacturing", "Agriculture", "Energy", "Healthcare", "Logistics", "EdgeAI", "General"];
-    
-    let mut results = Vec::with_capacity(body.transactions.len());
-    let mut successful = 0;
-    let mut failed = 0;
-    
-    // Phase 1: Pre-validate and build transactions (can be done without blockchain lock)
-    use crate::blockchain::transaction::{TxOutput, TransactionType};
-    let timestamp = chrono::Utc::now().timestamp();
-    
-    let mut valid_transactions: Vec<(ExternalIoTDataRequest, Transaction, u64)> = Vec::new();
-    
-    for item in &body.transactions {
-        // Validate category
-        if !valid_categories.contains(&item.category.as_str()) {
-            results.push(BatchItemResult {
-                device_id: item.device_id.clone(),
-                success: false,
-                tx_hash: None,
-                reward: None,
-                error: Some(format!("Invalid category: {}", item.category)),
-            });
-            failed += 1;
-            continue;
-        }
-        
-        // Validate API key
-        if item.api_key.is_empty() {
-            results.push(BatchItemResult {
-                device_id: item.device_id.clone(),
-                success: false,
-                tx_hash: None,
-                reward: None,
-                error: Some("API key required".to_string()),
-            });
-            failed += 1;
-            continue;
-        }
-        
-        // Build telemetry JSON string
-        let telemetry_str = item.telemetry.to_string();
-        
-        // Build full data payload
-        let (lat, lng) = item.location.map(|l| (l[0], l[1])).unwrap_or((0.0, 0.0));
-        
-        let full_data = format!(
-            r#"{{"device":"{}","category":"{}","telemetry":{},"lat":{},"lng":{},"ts":{},"source":"batch"}}"#,
-            item.device_id, item.category, telemetry_str, lat, lng, timestamp
-        );
-        
-        // Calculate reward
-        let data_size = full_data.len() as u64;
-        let base_reward = 30 + (data_size / 20);
-        let category_bonus: u64 = match item.category.as_str() {
-            "Healthcare" => 20,
-            "Manufacturing" => 15,
-            "Energy" => 15,
-            "Agriculture" => 10,
-            _ => 5,
-        };
-        let reward = base_reward + category_bonus;
-        
-        let output = TxOutput {
-            amount: reward,
-            recipient: item.device_id.clone(),
-            data_hash: Some(format!("batch_{:x}", timestamp)),
-        };
-        
-        let tx = Transaction::new(
-            TransactionType::DataContribution,
-            item.device_id.clone(),
-            vec![],
-            vec![output],
-            Some(full_data),
-            1,
-            21000,
-        );
-        
-        valid_transactions.push((item.clone(), tx, reward));
-    }
-    
-    // Phase 2: Use parallel batch validation if we have valid transactions
-    if !valid_transactions.is_empty() {
-        let txs: Vec<Transaction> = valid_transactions.iter().map(|(_, tx, _)| tx.clone()).collect();
-        // Use parallel batch processing
-        let mut blockchain = data.blockchain.write().await;
-        let (_batch_success, _batch_failed, successful_hashes) = blockchain.add_transactions_batch(txs);
-        
-        // Build results from batch processing
-        let hash_set: std::collections::HashSet<String> = successful_hashes.into_iter().collect();
-        
-        for (item, tx, reward) in valid_transactions {
-            if hash_set.contains(&tx.hash) {
-                results.push(BatchItemResult {
-                    device_id: item.device_id.clone(),
-                    success: true,
-                    tx_hash: Some(tx.hash),
-                    reward: Some(reward),
-                    error: None,
-                });
-                successful += 1;
-            } else {
-                results.push(BatchItemResult {
-                    device_id: item.device_id.clone(),
-                    success: false,
-                    tx_hash: None,
-                    reward: None,
-                    error: Some("Transaction validation failed".to_string()),
-                });
-                failed += 1;
-            }
-        }
-    }
-    
-    info!("Batch IoT submission: {} successful, {} failed out of {} total", 
-        successful, failed, body.transactions.len());
-    
-    HttpResponse::Ok().json(ApiResponse::success(BatchIoTSubmissionResponse {
-        total: body.transactions.len(),
-        successful,
-        failed,
-        results,
-    }))
-}
-
-/// Get device registration info and API documentation
-pub async fn get_iot_api_info() -> impl Responder {
-    #[derive(Serialize)]
-    struct IoTApiInfo {
-        version: &'static str,
-        endpoints: Vec<EndpointInfo>,
-        categories: Vec<&'static str>,
-        example_request: serde_json::Value,
-    }
-    
-    #[derive(Serialize)]
-    struct EndpointInfo {
-        method: &'static str,
-        path: &'static str,
-        description: &'static str,
-    }
-    
-    let info = IoTApiInfo {
-        version: "1.1.0",
-        endpoints: vec![
-            EndpointInfo {
-                method: "POST",
-                path: "/api/iot/submit",
-                description: "Submit single IoT telemetry data to the blockchain",
-            },
-            EndpointInfo {
-                method: "POST",
-                path: "/api/iot/batch_submit",
-                description: "Submit multiple IoT telemetry data in a single request (max 100 per batch)",
-            },
-            EndpointInfo {
-                method: "GET",
-                path: "/api/iot/info",
-                description: "Get API documentation and supported categories",
-            },
-        ],
-        categories: vec!["SmartCity", "Manufacturing", "Agriculture", "Energy", "Healthcare", "Logistics", "EdgeAI", "General"],
-        example_request: serde_json::json!({
-            "single_submit": {
-                "device_id": "my_sensor_001",
-                "api_key": "your_api_key_here",
-                "telemetry": {
-                    "temperature": 25.5,
-                    "humidity": 60,
-                    "pressure": 1013.25
-                },
-                "category": "SmartCity",
-                "location": [1.3521, 103.8198]
-            },
-            "batch_submit": {
-                "transactions": [
-                    {
-                        "device_id": "sensor_001",
-                        "api_key": "your_api_key",
-                        "telemetry": {"temperature": 25.5},
-                        "category": "SmartCity"
-                    },
-                    {
-                        "device_id": "sensor_002",
-                        "api_key": "your_api_key",
-                        "telemetry": {"humidity": 60},
-                        "category": "Manufacturing"
-                    }
-                ]
-            }
-        }),
-    };
-    
-    HttpResponse::Ok().json(ApiResponse::success(info))
-}
-
 // ============ Router Configuration ============
 
 pub fn configure_wallet_routes(cfg: &mut web::ServiceConfig) {
@@ -784,6 +1009,10 @@ pub fn configure_wallet_routes(cfg: &mut web::ServiceConfig) {
         // Wallet management
         .route("/api/wallet/generate", web::post().to(generate_wallet))
         .route("/api/wallet/import", web::post().to(import_wallet))
+        .route("/api/wallet/generate-mnemonic", web::post().to(generate_mnemonic_wallet))
+        .route("/api/wallet/import-mnemonic", web::post().to(import_mnemonic_wallet))
+        .route("/api/wallet/export-keystore", web::post().to(export_keystore))
+        .route("/api/wallet/import-keystore", web::post().to(import_keystore))
         .route("/api/wallet/address/{public_key}", web::get().to(get_address_from_public_key))
         
         // Signing
@@ -794,10 +1023,20 @@ pub fn configure_wallet_routes(cfg: &mut web::ServiceConfig) {
         .route("/api/wallet/prepare-transfer", web::post().to(prepare_transfer))
         .route("/api/wallet/prepare-contribute", web::post().to(prepare_data_contribution))
         .route("/api/wallet/transfer", web::post().to(submit_signed_transfer))
-        .route("/api/wallet/contribute", web::post().to(submit_signed_data_contribution))
-        
-        // External IoT device API
-        .route("/api/iot/submit", web::post().to(submit_iot_data))
-        .route("/api/iot/batch_submit", web::post().to(batch_submit_iot_data))
-        .route("/api/iot/info", web::get().to(get_iot_api_info));
+        .route("/api/wallet/prepare-batch-transfer", web::post().to(prepare_batch_transfer))
+        .route("/api/wallet/batch-transfer", web::post().to(submit_signed_batch_transfer))
+        .route("/api/wallet/contribute", web::post().to(submit_signed_data_contribution));
+    
+    // Typed transactions signed over their canonical message
+    typed_transaction_routes::<StakeFields>(cfg, "/api/wallet/prepare-stake", "/api/wallet/stake");
+    typed_transaction_routes::<UnstakeFields>(cfg, "/api/wallet/prepare-unstake", "/api/wallet/unstake");
+    typed_transaction_routes::<CancelUnbondingFields>(cfg, "/api/wallet/prepare-cancel-unbonding", "/api/wallet/cancel-unbonding");
+    typed_transaction_routes::<DataPurchaseFields>(cfg, "/api/wallet/prepare-purchase", "/api/wallet/purchase");
+    typed_transaction_routes::<ContractCallFields>(cfg, "/api/wallet/prepare-contract-call", "/api/wallet/contract-call");
+    typed_transaction_routes::<DeviceOwnerFields>(cfg, "/api/wallet/prepare-device-owner", "/api/wallet/device-owner");
+    typed_transaction_routes::<DeviceTypeFields>(cfg, "/api/wallet/prepare-device-type", "/api/wallet/device-type");
+    typed_transaction_routes::<DeviceBondFields>(cfg, "/api/wallet/prepare-device-bond", "/api/wallet/device-bond");
+    typed_transaction_routes::<DeviceUnbondFields>(cfg, "/api/wallet/prepare-device-unbond", "/api/wallet/device-unbond");
+    typed_transaction_routes::<ValidatorRegisterFields>(cfg, "/api/wallet/prepare-validator-register", "/api/wallet/validator-register");
+    typed_transaction_routes::<ValidatorUnbondFields>(cfg, "/api/wallet/prepare-validator-unbond", "/api/wallet/validator-unbond");
 }
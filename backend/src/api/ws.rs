@@ -0,0 +1,155 @@
+//! WebSocket event stream
+//!
+//! `GET /api/ws` upgrades to a WebSocket that pushes chain events as JSON
+//! (`{"type":"block","data":{...}}`, `{"type":"transaction","data":{...}}`).
+//! Clients receive every event kind until they send a subscription such as
+//! `{"subscribe":["blocks"]}`, which is acknowledged with
+//! `{"type":"subscribed","data":["blocks"]}`. A client that can't keep up
+//! with the stream is disconnected instead of slowing down block production.
+
+#![allow(dead_code)]
+
+use std::collections::HashSet;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message, Session};
+use log::{debug, warn};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::blockchain::events::{ChainEvent, EventBus, EventKind};
+
+/// Client message selecting the event kinds to receive
+#[derive(Debug, Deserialize)]
+pub struct SubscribeRequest {
+    pub subscribe: Vec<EventKind>,
+}
+
+/// Upgrade to a WebSocket streaming chain events
+pub async fn ws_events(
+    req: HttpRequest,
+    body: web::Payload,
+    bus: web::Data<EventBus>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (response, session, msg_stream) = actix_ws::handle(&req, body)?;
+    let events = bus.subscribe();
+    actix_web::rt::spawn(stream_events(session, msg_stream, events));
+    Ok(response)
+}
+
+async fn stream_events(
+    mut session: Session,
+    mut msg_stream: actix_ws::MessageStream,
+    mut events: tokio::sync::broadcast::Receiver<ChainEvent>,
+) {
+    let mut kinds: HashSet<EventKind> = [EventKind::Blocks, EventKind::Transactions].into();
+
+    let close_reason = loop {
+        tokio::select! {
+            msg = msg_stream.recv() => match msg {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<SubscribeRequest>(&text) {
+                    Ok(request) => {
+                        kinds = request.subscribe.iter().copied().collect();
+                        let ack = serde_json::json!({ "type": "subscribed", "data": request.subscribe });
+                        if session.text(ack.to_string()).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let error = serde_json::json!({ "type": "error", "data": format!("Invalid subscription: {}", e) });
+                        if session.text(error.to_string()).await.is_err() {
+                            return;
+                        }
+                    }
+                },
+                Some(Ok(Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        return;
+                    }
+                }
+                Some(Ok(Message::Close(reason))) => break reason,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => {
+                    debug!("WebSocket protocol error: {}", e);
+                    break None;
+                }
+                None => break None,
+            },
+            event = events.recv() => match event {
+                Ok(event) if kinds.contains(&event.kind()) => {
+                    let Ok(json) = serde_json::to_string(&event) else { continue };
+                    if session.text(json).await.is_err() {
+                        return;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Dropping WebSocket subscriber {} events behind", missed);
+                    break Some(CloseReason {
+                        code: CloseCode::Again,
+                        description: Some(format!("Subscriber lagged by {} events", missed)),
+                    });
+                }
+                Err(RecvError::Closed) => break None,
+            },
+        }
+    };
+    let _ = session.close(close_reason).await;
+}
+
+pub fn configure_ws_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/ws", web::get().to(ws_events));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::chain::tests::test_chain;
+    use crate::blockchain::Transaction;
+    use actix_web::{App, HttpServer};
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+    async fn next_json<S>(stream: &mut S) -> serde_json::Value
+    where
+        S: futures::Stream<Item = Result<WsMessage, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        loop {
+            let msg = tokio::time::timeout(std::time::Duration::from_secs(5), stream.next())
+                .await
+                .expect("timed out waiting for event")
+                .unwrap()
+                .unwrap();
+            if let WsMessage::Text(text) = msg {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_ws_receives_block_event_after_mining() {
+        let mut chain = test_chain();
+        let bus = web::Data::new(chain.events.clone());
+        let server = HttpServer::new(move || App::new().app_data(bus.clone()).configure(configure_ws_routes))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+
+        let (mut ws, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/ws", addr)).await.unwrap();
+        ws.send(WsMessage::Text(r#"{"subscribe":["blocks"]}"#.to_string())).await.unwrap();
+        let ack = next_json(&mut ws).await;
+        assert_eq!(ack["type"], "subscribed");
+        assert_eq!(ack["data"], serde_json::json!(["blocks"]));
+
+        // The mempool event is filtered out; the block arrives
+        chain.add_local_transaction(Transaction::transfer("genesis".to_string(), "alice".to_string(), 5)).unwrap();
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        let event = next_json(&mut ws).await;
+        assert_eq!(event["type"], "block");
+        assert_eq!(event["data"]["index"], 1);
+        assert_eq!(event["data"]["hash"], block.hash);
+        assert_eq!(event["data"]["tx_count"], 2);
+    }
+}
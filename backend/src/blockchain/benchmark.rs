@@ -0,0 +1,175 @@
+//! Node self-benchmark
+//!
+//! Pushes a batch of signed transfers through the same hot paths a block
+//! goes through — hash and signature verification, state application and
+//! block persistence — against a scratch chain in a temporary directory, and
+//! reports throughput and per-stage latencies. The node's own chain is not
+//! touched.
+
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::blockchain::chain::MAX_TXS_PER_BLOCK;
+use crate::blockchain::{Block, Blockchain, Transaction};
+use crate::crypto::Wallet;
+
+/// Transactions run when the request doesn't say
+pub const DEFAULT_BENCHMARK_TXS: usize = 1_000;
+
+/// Upper bound on transactions per benchmark run
+pub const MAX_BENCHMARK_TXS: usize = 20_000;
+
+/// Whether `POST /api/admin/benchmark` is enabled (`EDGEAI_ENABLE_BENCHMARK`)
+pub fn benchmark_enabled_from_env() -> bool {
+    std::env::var("EDGEAI_ENABLE_BENCHMARK")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Time spent in one stage
+#[derive(Debug, Clone, Serialize)]
+pub struct StageTiming {
+    pub total_ms: f64,
+    pub per_tx_us: f64,
+}
+
+impl StageTiming {
+    fn new(elapsed: Duration, transactions: usize) -> Self {
+        StageTiming {
+            total_ms: elapsed.as_secs_f64() * 1_000.0,
+            per_tx_us: elapsed.as_secs_f64() * 1_000_000.0 / transactions.max(1) as f64,
+        }
+    }
+}
+
+/// Per-stage latencies of a benchmark run
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkStages {
+    /// Transaction hash and signature verification
+    pub hash_verify: StageTiming,
+    /// Applying transactions to account state
+    pub apply: StageTiming,
+    /// Writing the resulting blocks to the block store
+    pub persist: StageTiming,
+}
+
+/// Result of a benchmark run
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub transactions: usize,
+    pub blocks: usize,
+    pub total_ms: f64,
+    /// Transactions per second across all stages
+    pub tps: f64,
+    pub stages: BenchmarkStages,
+    /// Resident memory of the node process after the run (Linux only)
+    pub memory_rss_kb: Option<u64>,
+}
+
+/// Resident set size of this process from `/proc/self/status`
+pub fn memory_rss_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status.lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|kb| kb.parse().ok())
+}
+
+/// Run `tx_count` signed transfers through verify, apply and persist on a
+/// scratch chain. Blocking; run it off the async executor.
+pub fn run_benchmark(tx_count: usize) -> Result<BenchmarkReport, String> {
+    if tx_count == 0 || tx_count > MAX_BENCHMARK_TXS {
+        return Err(format!("Transaction count must be between 1 and {}", MAX_BENCHMARK_TXS));
+    }
+    let dir = std::env::temp_dir().join(format!(
+        "edgeai-benchmark-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    ));
+    let mut chain = Blockchain::scratch(dir.clone());
+    let result = run_stages(&mut chain, tx_count);
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+fn run_stages(chain: &mut Blockchain, tx_count: usize) -> Result<BenchmarkReport, String> {
+    // Setup (not timed): a funded sender and its signed transfers
+    let wallet = Wallet::new();
+    let sender = wallet.address().to_string();
+    let transfer = Transaction::transfer("genesis".to_string(), sender.clone(), 100_000_000);
+    chain.apply_transaction(&transfer, None)?;
+    let transactions: Vec<Transaction> = (0..tx_count as u64)
+        .map(|nonce| {
+            let recipient = format!("bench_{}", nonce % 64);
+            let message = Transaction::create_transfer_signing_message(&sender, &recipient, 1, nonce, None, None);
+            Transaction::transfer_signed(
+                sender.clone(), wallet.public_key_hex(), recipient,
+                1, nonce, None, wallet.sign(message.as_bytes()),
+            )
+        })
+        .collect();
+
+    let start = Instant::now();
+    if let Some(tx) = transactions.iter().find(|tx| !tx.verify()) {
+        return Err(format!("Benchmark transaction {} failed verification", tx.hash));
+    }
+    let hash_verify = start.elapsed();
+
+    let start = Instant::now();
+    for tx in &transactions {
+        chain.apply_transaction(tx, None)?;
+    }
+    let apply = start.elapsed();
+
+    let start = Instant::now();
+    let mut previous_hash = chain.latest_block().hash.clone();
+    let mut blocks = 0;
+    for (i, batch) in transactions.chunks(MAX_TXS_PER_BLOCK).enumerate() {
+        let block = Block::new(i as u64 + 1, previous_hash, batch.to_vec(), chain.difficulty, "benchmark".to_string());
+        chain.persist_block(&block);
+        previous_hash = block.hash;
+        blocks += 1;
+    }
+    let persist = start.elapsed();
+
+    let total = hash_verify + apply + persist;
+    Ok(BenchmarkReport {
+        transactions: tx_count,
+        blocks,
+        total_ms: total.as_secs_f64() * 1_000.0,
+        tps: tx_count as f64 / total.as_secs_f64().max(f64::EPSILON),
+        stages: BenchmarkStages {
+            hash_verify: StageTiming::new(hash_verify, tx_count),
+            apply: StageTiming::new(apply, tx_count),
+            persist: StageTiming::new(persist, tx_count),
+        },
+        memory_rss_kb: memory_rss_kb(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_benchmark_reports_nonzero_metrics() {
+        let report = run_benchmark(200).unwrap();
+        assert_eq!(report.transactions, 200);
+        assert_eq!(report.blocks, 200usize.div_ceil(MAX_TXS_PER_BLOCK));
+        assert!(report.tps > 0.0 && report.tps.is_finite());
+        assert!(report.total_ms > 0.0);
+        for stage in [&report.stages.hash_verify, &report.stages.apply, &report.stages.persist] {
+            assert!(stage.total_ms > 0.0);
+            assert!(stage.per_tx_us > 0.0);
+        }
+        if cfg!(target_os = "linux") {
+            assert!(report.memory_rss_kb.unwrap() > 0);
+        }
+
+        assert!(run_benchmark(0).is_err());
+        assert!(run_benchmark(MAX_BENCHMARK_TXS + 1).is_err());
+    }
+}
@@ -9,8 +9,28 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::fmt;
+use std::time::{Duration, Instant};
 
-use crate::blockchain::transaction::Transaction;
+use crate::blockchain::genesis::Genesis;
+use crate::blockchain::state_dump::state_root;
+use crate::blockchain::transaction::{Transaction, TxOutput};
+
+/// Hash attempts between clock checks while mining
+const MINING_CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+/// Limits on the proof-of-work search in `Block::mine`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MiningBudget {
+    /// Hash attempts before giving up (`None` = no cap)
+    pub max_attempts: Option<u64>,
+    /// Wall-clock time before giving up (`None` = no limit)
+    pub max_duration: Option<Duration>,
+}
+
+impl MiningBudget {
+    /// Search until a valid nonce is found
+    pub const UNLIMITED: MiningBudget = MiningBudget { max_attempts: None, max_duration: None };
+}
 
 /// Block header containing metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +42,10 @@ pub struct BlockHeader {
     pub difficulty: u64,
     pub nonce: u64,
     pub data_entropy: f64,  // PoIE: Information entropy of data in this block
+    /// Root of the state after applying this block (empty on older blocks,
+    /// omitted from serialization then so their hashes are unchanged)
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub state_root: String,
 }
 
 /// A block in the EdgeAI blockchain
@@ -55,6 +79,7 @@ impl Block {
             difficulty,
             nonce: 0,
             data_entropy,
+            state_root: String::new(),
         };
         
         let mut block = Block {
@@ -69,16 +94,31 @@ impl Block {
         block
     }
     
-    /// Create the genesis block
-    pub fn genesis() -> Self {
-        let genesis_tx = Transaction::genesis();
-        Block::new(
+    /// Block 0 of the network described by `genesis`: one transaction
+    /// minting the genesis allocations, stamped with the genesis time and
+    /// committing to the genesis state, so every node derives the same block
+    pub fn genesis(genesis: &Genesis) -> Self {
+        let timestamp = genesis.time();
+        let allocations = genesis.accounts
+            .iter()
+            .map(|account| TxOutput {
+                amount: account.balance,
+                recipient: account.address.clone(),
+                data_hash: None,
+                token: None,
+            })
+            .collect();
+        let mut block = Block::new(
             0,
             "0".repeat(64),
-            vec![genesis_tx],
+            vec![Transaction::genesis(allocations, timestamp)],
             1,
             "genesis".to_string(),
-        )
+        );
+        block.header.timestamp = timestamp;
+        block.header.state_root = state_root(&genesis.chain_state());
+        block.hash = block.calculate_hash();
+        block
     }
     
     /// Calculate the hash of the block
@@ -104,24 +144,40 @@ impl Block {
             .collect();
         
         while hashes.len() > 1 {
-            let mut new_hashes = Vec::new();
-            for chunk in hashes.chunks(2) {
-                let combined = if chunk.len() == 2 {
-                    format!("{}{}", chunk[0], chunk[1])
-                } else {
-                    format!("{}{}", chunk[0], chunk[0])
-                };
-                
-                let mut hasher = Sha256::new();
-                hasher.update(combined.as_bytes());
-                new_hashes.push(hex::encode(hasher.finalize()));
-            }
-            hashes = new_hashes;
+            hashes = hashes
+                .chunks(2)
+                .map(|chunk| merkle_parent(&chunk[0], chunk.get(1).unwrap_or(&chunk[0])))
+                .collect();
         }
         
         hashes.pop().unwrap_or_else(|| "0".repeat(64))
     }
     
+    /// Merkle inclusion proof for a transaction in this block: the sibling
+    /// hash at each level from the leaf up, flagged `true` when the sibling is
+    /// the left node. An odd last node is paired with itself.
+    pub fn merkle_proof(&self, tx_hash: &str) -> Option<Vec<(String, bool)>> {
+        let mut index = self.transactions.iter().position(|tx| tx.hash == tx_hash)?;
+        let mut hashes: Vec<String> = self.transactions.iter().map(|tx| tx.hash.clone()).collect();
+        let mut proof = Vec::new();
+        
+        while hashes.len() > 1 {
+            let sibling_is_left = !index.is_multiple_of(2);
+            let sibling = if sibling_is_left {
+                hashes[index - 1].clone()
+            } else {
+                hashes.get(index + 1).unwrap_or(&hashes[index]).clone()
+            };
+            proof.push((sibling, sibling_is_left));
+            hashes = hashes
+                .chunks(2)
+                .map(|chunk| merkle_parent(&chunk[0], chunk.get(1).unwrap_or(&chunk[0])))
+                .collect();
+            index /= 2;
+        }
+        Some(proof)
+    }
+    
     /// Calculate information entropy of data in transactions (PoIE)
     pub fn calculate_data_entropy(transactions: &[Transaction]) -> f64 {
         if transactions.is_empty() {
@@ -159,14 +215,25 @@ impl Block {
         entropy
     }
     
-    /// Mine the block (find valid nonce for PoIE)
-    pub fn mine(&mut self, difficulty: u64) {
+    /// Mine the block (find valid nonce for PoIE), searching from the current
+    /// nonce. Returns false if `budget` runs out before a valid hash is found.
+    pub fn mine(&mut self, difficulty: u64, budget: MiningBudget) -> bool {
         let target = "0".repeat(difficulty as usize);
+        let started = Instant::now();
+        let mut attempts = 0u64;
         
         loop {
             self.hash = self.calculate_hash();
             if self.hash.starts_with(&target) {
-                break;
+                return true;
+            }
+            attempts += 1;
+            if budget.max_attempts.is_some_and(|max| attempts >= max) {
+                return false;
+            }
+            if attempts.is_multiple_of(MINING_CLOCK_CHECK_INTERVAL)
+                && budget.max_duration.is_some_and(|max| started.elapsed() >= max) {
+                return false;
             }
             self.header.nonce += 1;
         }
@@ -196,15 +263,43 @@ impl fmt::Display for Block {
     }
 }
 
+/// Hash of two child nodes in the transaction merkle tree
+fn merkle_parent(left: &str, right: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Check a proof from `Block::merkle_proof` against a block's merkle root
+pub fn verify_merkle_proof(tx_hash: &str, proof: &[(String, bool)], root: &str) -> bool {
+    let computed = proof.iter().fold(tx_hash.to_string(), |node, (sibling, sibling_is_left)| {
+        if *sibling_is_left {
+            merkle_parent(sibling, &node)
+        } else {
+            merkle_parent(&node, sibling)
+        }
+    });
+    computed == root
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
     #[test]
     fn test_genesis_block() {
-        let genesis = Block::genesis();
+        let genesis = Block::genesis(&Genesis::default());
         assert_eq!(genesis.index, 0);
         assert!(genesis.verify());
+
+        // Every node derives the same block from the same genesis, and the
+        // block commits to the allocations
+        assert_eq!(Block::genesis(&Genesis::default()).hash, genesis.hash);
+        let mut other = Genesis::default();
+        other.accounts[0].balance -= 1;
+        other.accounts[1].balance += 1;
+        assert_ne!(Block::genesis(&other).hash, genesis.hash);
     }
     
     #[test]
@@ -216,7 +311,46 @@ mod tests {
             1,
             "test_validator".to_string(),
         );
-        block.mine(1);
+        assert!(block.mine(1, MiningBudget::UNLIMITED));
         assert!(block.hash.starts_with("0"));
     }
+    
+    #[test]
+    fn test_mining_gives_up_when_budget_runs_out() {
+        let mut block = Block::new(1, "0".repeat(64), vec![], 64, "test_validator".to_string());
+        let capped = MiningBudget { max_attempts: Some(100), max_duration: None };
+        assert!(!block.mine(64, capped));
+        assert_eq!(block.header.nonce, 99);
+        assert!(block.verify());
+        
+        let timed = MiningBudget { max_attempts: None, max_duration: Some(Duration::from_millis(20)) };
+        let started = Instant::now();
+        assert!(!block.mine(64, timed));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+    
+    #[test]
+    fn test_merkle_proof_verifies_and_rejects_tampering() {
+        for tx_count in [1, 4, 5] {
+            let txs: Vec<Transaction> = (0..tx_count)
+                .map(|i| Transaction::transfer("genesis".to_string(), format!("user_{}", i), 1).with_nonce(i))
+                .collect();
+            let block = Block::new(1, "0".repeat(64), txs, 1, "test_validator".to_string());
+            for tx in &block.transactions {
+                let proof = block.merkle_proof(&tx.hash).unwrap();
+                assert!(verify_merkle_proof(&tx.hash, &proof, &block.header.merkle_root));
+            }
+            
+            // A proof for another leaf or with a tampered sibling fails
+            if tx_count > 1 {
+                let last = &block.transactions[tx_count as usize - 1].hash;
+                let mut proof = block.merkle_proof(last).unwrap();
+                assert!(!verify_merkle_proof(&block.transactions[0].hash, &proof, &block.header.merkle_root));
+                proof[0].0 = "f".repeat(64);
+                assert!(!verify_merkle_proof(last, &proof, &block.header.merkle_root));
+            }
+        }
+        let block = Block::new(1, "0".repeat(64), vec![], 1, "test_validator".to_string());
+        assert!(block.merkle_proof("missing").is_none());
+    }
 }
@@ -0,0 +1,771 @@
+//! Pluggable block storage backends
+//!
+//! `BlockStore` abstracts the append-only block log and state snapshot kept
+//! alongside RocksDB, so chain logic doesn't depend on a file format.
+//! Backends:
+//! - `file` (default): JSON Lines block log plus a JSON state file, with a
+//!   `blocks.idx` of fixed-width byte offsets so block N is found with a seek,
+//!   and an `address_tx.idx` of the heights each address transacted at,
+//!   loaded into a map keyed by address when the store is first used
+//! - `sled`: embedded key-value store, blocks keyed by height
+//!
+//! The backend is selected with `EDGEAI_BLOCK_STORE`.
+
+#![allow(dead_code)]
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use log::{info, warn};
+
+use crate::blockchain::block::Block;
+use crate::blockchain::chain::{BlockUndo, ChainMetadata, ChainState};
+use crate::contracts::smart_contract::ContractLog;
+
+pub(crate) const BLOCKS_FILE: &str = "blocks.jsonl";  // JSON Lines format for append-only
+pub(crate) const STATE_FILE: &str = "state.json";     // Separate state file
+const UNDO_FILE: &str = "undo.json";                  // Undo data for the latest blocks
+pub(crate) const INDEX_FILE: &str = "blocks.idx";     // Entry N: u64 LE byte offset of block N
+const INDEX_ENTRY_LEN: u64 = 8;
+pub(crate) const ADDRESS_INDEX_FILE: &str = "address_tx.idx"; // Lines of `<height>\t<address>`
+const CONTRACT_LOGS_FILE: &str = "contract_logs.jsonl";       // Lines of `[height, contract, log]`
+const SLED_DIR: &str = "blocks.sled";
+const SLED_STATE_KEY: &[u8] = b"state";
+const SLED_UNDO_KEY: &[u8] = b"undo";
+
+/// Storage backend for the block log and chain state snapshot
+pub trait BlockStore: Send + Sync {
+    /// Backend name for logs and stats
+    fn name(&self) -> &'static str;
+
+    /// Append a block to the log
+    fn append_block(&self, block: &Block) -> Result<(), String>;
+
+    /// Drop the blocks at `height` and above, e.g. when a reorg replaces them
+    fn truncate(&self, height: u64) -> Result<(), String>;
+
+    /// Look up a block by height
+    fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, String>;
+
+    /// Load the last `count` blocks, oldest first
+    fn load_recent(&self, count: usize) -> Result<Vec<Block>, String>;
+
+    /// Load up to `count` consecutive blocks starting at height `start`
+    fn load_range(&self, start: u64, count: usize) -> Result<Vec<Block>, String> {
+        let mut blocks = Vec::with_capacity(count);
+        for height in start..start.saturating_add(count as u64) {
+            match self.get_block_by_height(height)? {
+                Some(block) => blocks.push(block),
+                None => break,
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Save a snapshot of the chain state
+    fn save_state(&self, state: &ChainState, metadata: &ChainMetadata) -> Result<(), String>;
+
+    /// Load the last saved state snapshot, if any
+    fn load_state(&self) -> Result<Option<(ChainState, ChainMetadata)>, String>;
+
+    /// Save the undo data of the latest blocks, alongside the state snapshot
+    fn save_undo(&self, undo: &[BlockUndo]) -> Result<(), String>;
+
+    /// Load the last saved undo data; empty if none was saved
+    fn load_undo(&self) -> Result<Vec<BlockUndo>, String>;
+
+    /// Save the logs contracts emitted in the block at `height`, as
+    /// `(contract, log)` pairs. `truncate` drops them with the block.
+    fn put_contract_logs(&self, height: u64, logs: &[(String, ContractLog)]) -> Result<(), String>;
+
+    /// Logs the contract at `address` emitted in blocks `from..=to`, oldest first
+    fn contract_logs(&self, address: &str, from: u64, to: u64) -> Result<Vec<ContractLog>, String>;
+
+    /// Heights of the blocks with a transaction sent or received by
+    /// `address`, ascending. `None` if the backend keeps no address index.
+    fn address_heights(&self, _address: &str) -> Result<Option<Vec<u64>>, String> {
+        Ok(None)
+    }
+}
+
+/// Addresses sending or receiving a transaction in `block`
+fn block_addresses(block: &Block) -> BTreeSet<&str> {
+    block.transactions
+        .iter()
+        .flat_map(|tx| std::iter::once(tx.sender.as_str()).chain(tx.outputs.iter().map(|o| o.recipient.as_str())))
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+/// `address_tx.idx` lines for `block`
+fn address_index_lines(block: &Block) -> String {
+    block_addresses(block)
+        .into_iter()
+        .map(|address| format!("{}\t{}\n", block.index, address))
+        .collect()
+}
+
+/// Open the backend selected by `EDGEAI_BLOCK_STORE` (`file` or `sled`) in `dir`.
+/// Falls back to the file backend if sled can't be opened.
+pub fn open_block_store(dir: &Path) -> Box<dyn BlockStore> {
+    let backend = std::env::var("EDGEAI_BLOCK_STORE").unwrap_or_else(|_| "file".to_string());
+    match backend.to_lowercase().as_str() {
+        "file" => Box::new(FileBlockStore::new(dir)),
+        "sled" => match SledBlockStore::open(dir) {
+            Ok(store) => {
+                info!("Using sled block store");
+                Box::new(store)
+            }
+            Err(e) => {
+                warn!("Failed to open sled block store: {}, falling back to file storage", e);
+                Box::new(FileBlockStore::new(dir))
+            }
+        },
+        other => {
+            warn!("Unknown block store '{}', using file storage", other);
+            Box::new(FileBlockStore::new(dir))
+        }
+    }
+}
+
+/// JSON Lines block log with a JSON state file
+pub struct FileBlockStore {
+    dir: PathBuf,
+    /// Index state, checked against the log on first use; the lock also
+    /// serializes appends
+    index: Mutex<SyncedIndex>,
+}
+
+/// In-memory side of the file store's indexes
+#[derive(Default)]
+struct SyncedIndex {
+    /// Whether `blocks.idx` has been checked against the log since opening
+    synced: bool,
+    /// `address_tx.idx` keyed by address: ascending heights
+    addresses: HashMap<String, Vec<u64>>,
+}
+
+impl SyncedIndex {
+    fn add(&mut self, height: u64, address: &str) {
+        let heights = self.addresses.entry(address.to_string()).or_default();
+        if heights.last() != Some(&height) {
+            heights.push(height);
+        }
+    }
+}
+
+impl FileBlockStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileBlockStore { dir: dir.into(), index: Mutex::new(SyncedIndex::default()) }
+    }
+
+    fn blocks_path(&self) -> PathBuf {
+        self.dir.join(BLOCKS_FILE)
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.dir.join(STATE_FILE)
+    }
+
+    fn undo_path(&self) -> PathBuf {
+        self.dir.join(UNDO_FILE)
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join(INDEX_FILE)
+    }
+
+    fn address_index_path(&self) -> PathBuf {
+        self.dir.join(ADDRESS_INDEX_FILE)
+    }
+
+    fn contract_logs_path(&self) -> PathBuf {
+        self.dir.join(CONTRACT_LOGS_FILE)
+    }
+
+    /// Entries of `contract_logs.jsonl`, in height order
+    fn read_contract_logs(&self) -> Result<Vec<(u64, String, ContractLog)>, String> {
+        let file = match fs::File::open(self.contract_logs_path()) {
+            Ok(f) => f,
+            Err(_) => return Ok(Vec::new()),
+        };
+        let mut entries = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read contract logs: {}", e))?;
+            // A partially written last line is skipped
+            if let Ok(entry) = serde_json::from_str(&line) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Lock the index, bringing it up to date with the log on first use
+    fn synced_index(&self) -> Result<MutexGuard<'_, SyncedIndex>, String> {
+        let mut index = self.index.lock().map_err(|_| "Block index lock poisoned".to_string())?;
+        if !index.synced {
+            self.rebuild_index()?;
+            if !self.address_index_path().exists() {
+                self.rebuild_address_index()?;
+            }
+            index.addresses.clear();
+            self.load_address_index(&mut index)?;
+            index.synced = true;
+        }
+        Ok(index)
+    }
+
+    /// Read `address_tx.idx` into `index`
+    fn load_address_index(&self, index: &mut SyncedIndex) -> Result<(), String> {
+        let file = match fs::File::open(self.address_index_path()) {
+            Ok(f) => f,
+            Err(_) => return Ok(()),
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| format!("Failed to read address index: {}", e))?;
+            if let Some((height, address)) = parse_address_entry(&line) {
+                index.add(height, address);
+            }
+        }
+        Ok(())
+    }
+
+    /// Index any complete lines of the log missing from `blocks.idx`. Starts
+    /// over if the index points past the end of the log.
+    fn rebuild_index(&self) -> Result<(), String> {
+        let blocks = match fs::File::open(self.blocks_path()) {
+            Ok(f) => f,
+            Err(_) => {
+                let _ = fs::remove_file(self.index_path());
+                let _ = fs::remove_file(self.address_index_path());
+                return Ok(());
+            }
+        };
+        let blocks_len = blocks.metadata().map_err(|e| format!("Failed to stat blocks file: {}", e))?.len();
+        let mut index = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.index_path())
+            .map_err(|e| format!("Failed to open block index: {}", e))?;
+        let index_len = index.metadata().map_err(|e| format!("Failed to stat block index: {}", e))?.len();
+        let mut entries = index_len / INDEX_ENTRY_LEN;
+
+        let mut reader = BufReader::new(blocks);
+        let mut line = Vec::new();
+        // Resume after the last indexed line
+        let mut pos = 0;
+        if entries > 0 {
+            let last = read_offset(&mut index, entries - 1)?;
+            if last < blocks_len {
+                reader.seek(SeekFrom::Start(last)).map_err(|e| format!("Failed to seek blocks file: {}", e))?;
+                pos = last + reader.read_until(b'\n', &mut line).map_err(|e| e.to_string())? as u64;
+            } else {
+                warn!("Block index points past the end of {}, rebuilding", BLOCKS_FILE);
+                entries = 0;
+            }
+        }
+        index.set_len(entries * INDEX_ENTRY_LEN).map_err(|e| format!("Failed to truncate block index: {}", e))?;
+        if pos >= blocks_len {
+            return Ok(());
+        }
+
+        reader.seek(SeekFrom::Start(pos)).map_err(|e| format!("Failed to seek blocks file: {}", e))?;
+        index.seek(SeekFrom::End(0)).map_err(|e| format!("Failed to seek block index: {}", e))?;
+        let mut writer = BufWriter::new(index);
+        let mut added = 0u64;
+        loop {
+            line.clear();
+            let n = reader.read_until(b'\n', &mut line).map_err(|e| format!("Failed to read blocks file: {}", e))?;
+            // Stop at EOF or a partially written last line
+            if n == 0 || line.last() != Some(&b'\n') {
+                break;
+            }
+            writer.write_all(&pos.to_le_bytes()).map_err(|e| format!("Failed to write block index: {}", e))?;
+            pos += n as u64;
+            added += 1;
+        }
+        writer.flush().map_err(|e| format!("Failed to write block index: {}", e))?;
+        if added > 0 {
+            info!("Indexed {} blocks in {}", added, INDEX_FILE);
+        }
+        Ok(())
+    }
+
+    /// Write `address_tx.idx` from scratch by scanning the log
+    fn rebuild_address_index(&self) -> Result<(), String> {
+        let blocks = match fs::File::open(self.blocks_path()) {
+            Ok(f) => f,
+            Err(_) => return Ok(()),
+        };
+        let tmp_path = self.address_index_path().with_extension("idx.tmp");
+        let mut writer = BufWriter::new(
+            fs::File::create(&tmp_path).map_err(|e| format!("Failed to create address index: {}", e))?,
+        );
+        let mut indexed = 0u64;
+        for line in BufReader::new(blocks).lines() {
+            let line = line.map_err(|e| format!("Failed to read blocks file: {}", e))?;
+            let block: Block = match serde_json::from_str(&line) {
+                Ok(block) => block,
+                // A partially written last line isn't in blocks.idx either
+                Err(_) => break,
+            };
+            writer.write_all(address_index_lines(&block).as_bytes())
+                .map_err(|e| format!("Failed to write address index: {}", e))?;
+            indexed += 1;
+        }
+        writer.flush().map_err(|e| format!("Failed to write address index: {}", e))?;
+        fs::rename(&tmp_path, self.address_index_path())
+            .map_err(|e| format!("Failed to replace address index: {}", e))?;
+        info!("Indexed addresses of {} blocks in {}", indexed, ADDRESS_INDEX_FILE);
+        Ok(())
+    }
+
+    /// Number of blocks in the index
+    fn indexed_blocks(&self) -> Result<u64, String> {
+        match fs::metadata(self.index_path()) {
+            Ok(meta) => Ok(meta.len() / INDEX_ENTRY_LEN),
+            Err(_) => Ok(0),
+        }
+    }
+
+    /// Raw log lines for heights `start..start + count`, clipped to the index
+    fn read_lines(&self, start: u64, count: usize) -> Result<Vec<String>, String> {
+        let (offset, count) = {
+            let _synced = self.synced_index()?;
+            let count = self.indexed_blocks()?.saturating_sub(start).min(count as u64) as usize;
+            if count == 0 {
+                return Ok(Vec::new());
+            }
+            let mut index = fs::File::open(self.index_path())
+                .map_err(|e| format!("Failed to open block index: {}", e))?;
+            (read_offset(&mut index, start)?, count)
+        };
+
+        let mut file = fs::File::open(self.blocks_path())
+            .map_err(|e| format!("Failed to open blocks file: {}", e))?;
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("Failed to seek blocks file: {}", e))?;
+        BufReader::new(file)
+            .lines()
+            .take(count)
+            .map(|line| line.map_err(|e| format!("Failed to read blocks file: {}", e)))
+            .collect()
+    }
+}
+
+/// Read index entry `n`
+fn read_offset(index: &mut fs::File, n: u64) -> Result<u64, String> {
+    let mut buf = [0u8; INDEX_ENTRY_LEN as usize];
+    index.seek(SeekFrom::Start(n * INDEX_ENTRY_LEN))
+        .and_then(|_| index.read_exact(&mut buf))
+        .map_err(|e| format!("Failed to read block index entry {}: {}", n, e))?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+impl BlockStore for FileBlockStore {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn append_block(&self, block: &Block) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        let mut synced = self.synced_index()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.blocks_path())
+            .map_err(|e| format!("Failed to open blocks file: {}", e))?;
+        let offset = file.metadata().map_err(|e| format!("Failed to stat blocks file: {}", e))?.len();
+        let json = serde_json::to_string(block)
+            .map_err(|e| format!("Failed to serialize block: {}", e))?;
+        writeln!(file, "{}", json).map_err(|e| format!("Failed to append block: {}", e))?;
+
+        let mut index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.index_path())
+            .map_err(|e| format!("Failed to open block index: {}", e))?;
+        index.write_all(&offset.to_le_bytes()).map_err(|e| format!("Failed to append to block index: {}", e))?;
+
+        let mut address_index = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.address_index_path())
+            .map_err(|e| format!("Failed to open address index: {}", e))?;
+        address_index.write_all(address_index_lines(block).as_bytes())
+            .map_err(|e| format!("Failed to append to address index: {}", e))?;
+        for address in block_addresses(block) {
+            synced.add(block.index, address);
+        }
+        Ok(())
+    }
+
+    fn truncate(&self, height: u64) -> Result<(), String> {
+        let mut synced = self.synced_index()?;
+        if self.contract_logs_path().exists() {
+            let mut kept = String::new();
+            for (h, contract, log) in self.read_contract_logs()?.into_iter().take_while(|(h, _, _)| *h < height) {
+                let json = serde_json::to_string(&(h, contract, log))
+                    .map_err(|e| format!("Failed to serialize contract log: {}", e))?;
+                kept.push_str(&json);
+                kept.push('\n');
+            }
+            fs::write(self.contract_logs_path(), kept).map_err(|e| format!("Failed to truncate contract logs: {}", e))?;
+        }
+        if height >= self.indexed_blocks()? {
+            return Ok(());
+        }
+        let mut index = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(self.index_path())
+            .map_err(|e| format!("Failed to open block index: {}", e))?;
+        let offset = read_offset(&mut index, height)?;
+        OpenOptions::new()
+            .write(true)
+            .open(self.blocks_path())
+            .and_then(|file| file.set_len(offset))
+            .map_err(|e| format!("Failed to truncate blocks file: {}", e))?;
+        index.set_len(height * INDEX_ENTRY_LEN).map_err(|e| format!("Failed to truncate block index: {}", e))?;
+
+        // Entries are in height order, so keep the lines below `height`
+        let entries = fs::read_to_string(self.address_index_path()).unwrap_or_default();
+        let kept: String = entries
+            .lines()
+            .take_while(|line| parse_address_entry(line).is_some_and(|(h, _)| h < height))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        fs::write(self.address_index_path(), kept).map_err(|e| format!("Failed to truncate address index: {}", e))?;
+        synced.addresses.retain(|_, heights| {
+            heights.truncate(heights.partition_point(|&h| h < height));
+            !heights.is_empty()
+        });
+        Ok(())
+    }
+
+    fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, String> {
+        Ok(self.load_range(height, 1)?.pop())
+    }
+
+    fn load_range(&self, start: u64, count: usize) -> Result<Vec<Block>, String> {
+        // Line N holds block N
+        self.read_lines(start, count)?
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| format!("Failed to parse block {}: {}", start + i as u64, e))
+            })
+            .collect()
+    }
+
+    fn load_recent(&self, count: usize) -> Result<Vec<Block>, String> {
+        let total = {
+            let _synced = self.synced_index()?;
+            self.indexed_blocks()?
+        };
+        let start = total.saturating_sub(count as u64);
+        Ok(self.read_lines(start, count)?
+            .iter()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    fn save_state(&self, state: &ChainState, metadata: &ChainMetadata) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        let data = serde_json::to_string(&(state, metadata))
+            .map_err(|e| format!("Failed to serialize state: {}", e))?;
+        fs::write(self.state_path(), data).map_err(|e| format!("Failed to write state: {}", e))
+    }
+
+    fn load_state(&self) -> Result<Option<(ChainState, ChainMetadata)>, String> {
+        let data = match fs::read_to_string(self.state_path()) {
+            Ok(d) => d,
+            Err(_) => return Ok(None),
+        };
+        serde_json::from_str(&data)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse state: {}", e))
+    }
+
+    fn save_undo(&self, undo: &[BlockUndo]) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        let data = serde_json::to_string(undo)
+            .map_err(|e| format!("Failed to serialize undo data: {}", e))?;
+        fs::write(self.undo_path(), data).map_err(|e| format!("Failed to write undo data: {}", e))
+    }
+
+    fn load_undo(&self) -> Result<Vec<BlockUndo>, String> {
+        let data = match fs::read_to_string(self.undo_path()) {
+            Ok(d) => d,
+            Err(_) => return Ok(Vec::new()),
+        };
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse undo data: {}", e))
+    }
+
+    fn put_contract_logs(&self, height: u64, logs: &[(String, ContractLog)]) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+        let mut lines = String::new();
+        for (contract, log) in logs {
+            let json = serde_json::to_string(&(height, contract, log))
+                .map_err(|e| format!("Failed to serialize contract log: {}", e))?;
+            lines.push_str(&json);
+            lines.push('\n');
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.contract_logs_path())
+            .and_then(|mut file| file.write_all(lines.as_bytes()))
+            .map_err(|e| format!("Failed to append contract logs: {}", e))
+    }
+
+    fn contract_logs(&self, address: &str, from: u64, to: u64) -> Result<Vec<ContractLog>, String> {
+        Ok(self.read_contract_logs()?
+            .into_iter()
+            .filter(|(height, contract, _)| (from..=to).contains(height) && contract == address)
+            .map(|(_, _, log)| log)
+            .collect())
+    }
+
+    fn address_heights(&self, address: &str) -> Result<Option<Vec<u64>>, String> {
+        let synced = self.synced_index()?;
+        Ok(Some(synced.addresses.get(address).cloned().unwrap_or_default()))
+    }
+}
+
+/// Parse an `address_tx.idx` line into height and address
+fn parse_address_entry(line: &str) -> Option<(u64, &str)> {
+    let (height, address) = line.split_once('\t')?;
+    Some((height.parse().ok()?, address))
+}
+
+/// sled-backed store: blocks keyed by big-endian height in a `blocks` tree
+pub struct SledBlockStore {
+    db: sled::Db,
+    blocks: sled::Tree,
+    /// `(contract, log)` pairs keyed by big-endian height
+    contract_logs: sled::Tree,
+}
+
+impl SledBlockStore {
+    /// Open (or create) the store under `dir`
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let db = sled::open(dir.join(SLED_DIR)).map_err(|e| e.to_string())?;
+        Self::from_db(db)
+    }
+
+    /// In-memory store removed on drop
+    pub fn temporary() -> Result<Self, String> {
+        let db = sled::Config::new().temporary(true).open().map_err(|e| e.to_string())?;
+        Self::from_db(db)
+    }
+
+    fn from_db(db: sled::Db) -> Result<Self, String> {
+        let blocks = db.open_tree("blocks").map_err(|e| e.to_string())?;
+        let contract_logs = db.open_tree("contract_logs").map_err(|e| e.to_string())?;
+        Ok(SledBlockStore { db, blocks, contract_logs })
+    }
+}
+
+impl BlockStore for SledBlockStore {
+    fn name(&self) -> &'static str {
+        "sled"
+    }
+
+    fn append_block(&self, block: &Block) -> Result<(), String> {
+        let json = serde_json::to_vec(block).map_err(|e| format!("Failed to serialize block: {}", e))?;
+        self.blocks
+            .insert(block.index.to_be_bytes(), json)
+            .map_err(|e| format!("Failed to append block: {}", e))?;
+        Ok(())
+    }
+
+    fn truncate(&self, height: u64) -> Result<(), String> {
+        for key in self.blocks.range(height.to_be_bytes()..).keys() {
+            let key = key.map_err(|e| e.to_string())?;
+            self.blocks.remove(key).map_err(|e| format!("Failed to remove block: {}", e))?;
+        }
+        for key in self.contract_logs.range(height.to_be_bytes()..).keys() {
+            let key = key.map_err(|e| e.to_string())?;
+            self.contract_logs.remove(key).map_err(|e| format!("Failed to remove contract logs: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, String> {
+        match self.blocks.get(height.to_be_bytes()).map_err(|e| e.to_string())? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse block {}: {}", height, e)),
+            None => Ok(None),
+        }
+    }
+
+    fn load_range(&self, start: u64, count: usize) -> Result<Vec<Block>, String> {
+        let mut blocks = Vec::with_capacity(count);
+        for entry in self.blocks.range(start.to_be_bytes()..).take(count) {
+            let (_, bytes) = entry.map_err(|e| e.to_string())?;
+            blocks.push(serde_json::from_slice(&bytes).map_err(|e| format!("Failed to parse block: {}", e))?);
+        }
+        Ok(blocks)
+    }
+
+    fn load_recent(&self, count: usize) -> Result<Vec<Block>, String> {
+        let mut blocks = Vec::with_capacity(count);
+        for entry in self.blocks.iter().rev().take(count) {
+            let (_, bytes) = entry.map_err(|e| e.to_string())?;
+            if let Ok(block) = serde_json::from_slice(&bytes) {
+                blocks.push(block);
+            }
+        }
+        blocks.reverse();
+        Ok(blocks)
+    }
+
+    fn save_state(&self, state: &ChainState, metadata: &ChainMetadata) -> Result<(), String> {
+        let data = serde_json::to_vec(&(state, metadata))
+            .map_err(|e| format!("Failed to serialize state: {}", e))?;
+        self.db.insert(SLED_STATE_KEY, data).map_err(|e| format!("Failed to write state: {}", e))?;
+        self.db.flush().map_err(|e| format!("Failed to flush sled: {}", e))?;
+        Ok(())
+    }
+
+    fn load_state(&self) -> Result<Option<(ChainState, ChainMetadata)>, String> {
+        match self.db.get(SLED_STATE_KEY).map_err(|e| e.to_string())? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map(Some)
+                .map_err(|e| format!("Failed to parse state: {}", e)),
+            None => Ok(None),
+        }
+    }
+
+    fn save_undo(&self, undo: &[BlockUndo]) -> Result<(), String> {
+        let data = serde_json::to_vec(undo)
+            .map_err(|e| format!("Failed to serialize undo data: {}", e))?;
+        self.db.insert(SLED_UNDO_KEY, data).map_err(|e| format!("Failed to write undo data: {}", e))?;
+        self.db.flush().map_err(|e| format!("Failed to flush sled: {}", e))?;
+        Ok(())
+    }
+
+    fn load_undo(&self) -> Result<Vec<BlockUndo>, String> {
+        match self.db.get(SLED_UNDO_KEY).map_err(|e| e.to_string())? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Failed to parse undo data: {}", e)),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn put_contract_logs(&self, height: u64, logs: &[(String, ContractLog)]) -> Result<(), String> {
+        let json = serde_json::to_vec(logs).map_err(|e| format!("Failed to serialize contract logs: {}", e))?;
+        self.contract_logs
+            .insert(height.to_be_bytes(), json)
+            .map_err(|e| format!("Failed to write contract logs: {}", e))?;
+        Ok(())
+    }
+
+    fn contract_logs(&self, address: &str, from: u64, to: u64) -> Result<Vec<ContractLog>, String> {
+        let mut found = Vec::new();
+        for entry in self.contract_logs.range(from.to_be_bytes()..=to.to_be_bytes()).values() {
+            let bytes = entry.map_err(|e| e.to_string())?;
+            let logs: Vec<(String, ContractLog)> = serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Failed to parse contract logs: {}", e))?;
+            found.extend(logs.into_iter().filter(|(contract, _)| contract == address).map(|(_, log)| log));
+        }
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_index_random_access() {
+        let dir = std::env::temp_dir().join(format!("edgeai-block-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let store = FileBlockStore::new(&dir);
+        let mut hashes = Vec::new();
+        let mut previous = String::new();
+        for i in 0..10_000u64 {
+            let block = Block::new(i, previous.clone(), vec![], 1, "validator".to_string());
+            store.append_block(&block).unwrap();
+            previous = block.hash.clone();
+            hashes.push(block.hash);
+        }
+        let index_len = |dir: &Path| fs::metadata(dir.join(INDEX_FILE)).unwrap().len();
+        assert_eq!(index_len(&dir), 10_000 * INDEX_ENTRY_LEN);
+
+        // A missing or short index is rebuilt on open
+        fs::remove_file(dir.join(INDEX_FILE)).unwrap();
+        assert_eq!(FileBlockStore::new(&dir).get_block_by_height(9_000).unwrap().unwrap().hash, hashes[9_000]);
+        let index = OpenOptions::new().write(true).open(dir.join(INDEX_FILE)).unwrap();
+        index.set_len(100 * INDEX_ENTRY_LEN).unwrap();
+        let store = FileBlockStore::new(&dir);
+        assert_eq!(store.get_block_by_height(9_999).unwrap().unwrap().hash, hashes[9_999]);
+        assert_eq!(index_len(&dir), 10_000 * INDEX_ENTRY_LEN);
+
+        // Blank out the first half of the log, newlines included: a scan could
+        // no longer find block N, a seek through the index still does
+        let mut index = fs::File::open(dir.join(INDEX_FILE)).unwrap();
+        let half = read_offset(&mut index, 5_000).unwrap();
+        let mut log = fs::read(dir.join(BLOCKS_FILE)).unwrap();
+        log[..half as usize].fill(b' ');
+        fs::write(dir.join(BLOCKS_FILE), log).unwrap();
+
+        let store = FileBlockStore::new(&dir);
+        let started = std::time::Instant::now();
+        for height in [9_999, 5_000, 7_321] {
+            assert_eq!(store.get_block_by_height(height).unwrap().unwrap().hash, hashes[height as usize]);
+        }
+        let range: Vec<String> = store.load_range(6_000, 3).unwrap().into_iter().map(|b| b.hash).collect();
+        assert_eq!(range, hashes[6_000..6_003].to_vec());
+        assert!(store.get_block_by_height(10_000).unwrap().is_none());
+        log::info!("Random access on a 10k-block log took {:?}", started.elapsed());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_contract_logs_persist_and_truncate() {
+        let dir = std::env::temp_dir().join(format!("edgeai-contract-logs-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let log = |event: &str, height: u64| ContractLog {
+            event: event.to_string(),
+            data: HashMap::new(),
+            timestamp: chrono::Utc::now(),
+            block_number: height,
+        };
+        let backends: Vec<Box<dyn BlockStore>> = vec![
+            Box::new(FileBlockStore::new(&dir)),
+            Box::new(SledBlockStore::temporary().unwrap()),
+        ];
+        for store in backends {
+            for height in 1..=3 {
+                store.put_contract_logs(height, &[
+                    ("0xa".to_string(), log("hit", height)),
+                    ("0xb".to_string(), log("other", height)),
+                ]).unwrap();
+            }
+            let heights = |store: &dyn BlockStore, from, to| -> Vec<u64> {
+                store.contract_logs("0xa", from, to).unwrap().iter().map(|l| l.block_number).collect()
+            };
+            assert_eq!(heights(store.as_ref(), 0, u64::MAX), vec![1, 2, 3]);
+            assert_eq!(heights(store.as_ref(), 2, 2), vec![2]);
+
+            // A reorg from height 2 drops the logs of the replaced blocks
+            store.truncate(2).unwrap();
+            assert_eq!(heights(store.as_ref(), 0, u64::MAX), vec![1]);
+        }
+        // Read back from disk by a fresh file store
+        assert_eq!(FileBlockStore::new(&dir).contract_logs("0xb", 0, 9).unwrap().len(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
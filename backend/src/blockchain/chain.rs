@@ -5,26 +5,389 @@
 
 #![allow(dead_code)]
 
-use std::collections::HashMap;
-use chrono::Utc;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use log::{info, error, warn};
-use std::fs::{self, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use rayon::prelude::*;
 
-use crate::blockchain::block::Block;
-use crate::blockchain::transaction::{Transaction, TransactionType};
+use crate::blockchain::block::{Block, MiningBudget};
+use crate::blockchain::transaction::{Transaction, TransactionType, ValidatorRegistration, NATIVE_TOKEN, MAX_MEMO_LEN, MAX_TRANSFER_OUTPUTS, TREASURY_ADDRESS};
 use crate::blockchain::storage::Storage;
+use crate::blockchain::block_store::{BlockStore, FileBlockStore, open_block_store, BLOCKS_FILE, STATE_FILE};
+use crate::blockchain::tx_trace::{TxTracer, TraceFilter, TxStage};
+use crate::blockchain::state_dump::{state_root, CanonicalState, StateDump};
+use crate::blockchain::mempool::{TxPool, mempool_capacity_from_env, simulated_device_wallet};
+use crate::blockchain::error::TransactionError;
+use crate::blockchain::events::{ChainEvent, EventBus};
+use crate::blockchain::genesis::Genesis;
+use crate::blockchain::params::ChainParams;
+use crate::blockchain::reward_policy::{contribution_category, RewardPolicy};
+use crate::consensus::data_quality::{score_contribution_at, NoveltyCache};
+use crate::consensus::device_registry::DeviceType;
+use crate::consensus::governance::{GovernanceManager, ProtocolExecutor};
+use crate::consensus::staking::{SlashEvent, StakingManager};
+use crate::contracts::smart_contract::ContractLog;
+use crate::contracts::{ContractStore, WasmRuntime};
 #[allow(unused_imports)]
 use crate::blockchain::cold_storage::{ColdStorage, ColdStorageStats, MigrationResult};
 use crate::blockchain::cold_blocks::{ColdBlocks, ColdBlocksStats, BlockMigrationResult};
 
 const DATA_DIR: &str = "/data";
-const BLOCKS_FILE: &str = "blocks.jsonl";  // JSON Lines format for append-only
-const STATE_FILE: &str = "state.json";     // Separate state file
 const MAX_BLOCKS_IN_MEMORY: usize = 100;   // Only keep recent blocks in RAM
+const STORE_SCAN_BATCH: usize = 100;       // Blocks read per block store call when scanning history
+const DUPLICATE_TX_WINDOW: usize = 100;    // Recent blocks checked for already-mined transactions
+
+/// Compare a transaction nonce against the expected one
+fn nonce_matches(expected: u64, got: u64) -> Result<(), TransactionError> {
+    if got < expected {
+        Err(TransactionError::NonceTooLow { expected, got })
+    } else if got > expected {
+        Err(TransactionError::NonceTooHigh { expected, got })
+    } else {
+        Ok(())
+    }
+}
+
+/// Reject memos longer than `MAX_MEMO_LEN` bytes
+fn check_memo(tx: &Transaction) -> Result<(), TransactionError> {
+    match tx.memo {
+        Some(ref memo) if memo.len() > MAX_MEMO_LEN => {
+            Err(TransactionError::MemoTooLong { len: memo.len(), max: MAX_MEMO_LEN })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Transfers need between 1 and `MAX_TRANSFER_OUTPUTS` outputs, all in one token
+fn check_transfer_outputs(tx: &Transaction) -> Result<(), TransactionError> {
+    if tx.tx_type != TransactionType::Transfer {
+        return Ok(());
+    }
+    let Some(first) = tx.outputs.first() else {
+        return Err(TransactionError::NoOutputs);
+    };
+    if tx.outputs.len() > MAX_TRANSFER_OUTPUTS {
+        return Err(TransactionError::TooManyOutputs { count: tx.outputs.len(), max: MAX_TRANSFER_OUTPUTS });
+    }
+    if tx.outputs.iter().any(|o| o.token_symbol() != first.token_symbol()) {
+        return Err(TransactionError::MixedTokens);
+    }
+    Ok(())
+}
+
+/// Reject transactions whose height deadline passed before `height`
+fn check_height_deadline(tx: &Transaction, height: u64) -> Result<(), TransactionError> {
+    match tx.valid_until_height {
+        Some(valid_until) if tx.is_expired_at(height) => Err(TransactionError::Expired { valid_until, height }),
+        _ => Ok(()),
+    }
+}
+
+/// Pool bookkeeping captured before building a block, restored once it is built
+struct BlockRollback {
+    synthetic: Vec<Transaction>,
+    tx_class_stats: TxClassStats,
+    /// Hashes of transactions in the pending pool before selection
+    pooled: HashSet<String>,
+}
+
+/// A block built by `Blockchain::prepare_block`, to be mined and then appended
+pub struct BlockTemplate {
+    pub block: Block,
+    /// Pool hashes of the synthetic transactions it includes
+    synthetic: HashSet<String>,
+    /// Transactions it includes by origin
+    included: TxClassStats,
+}
+
+impl BlockTemplate {
+    /// Search for a nonce at the block's difficulty, giving up when `budget` runs out
+    pub fn mine(&mut self, budget: MiningBudget) -> bool {
+        let difficulty = self.block.header.difficulty;
+        self.block.mine(difficulty, budget)
+    }
+}
+
+/// State before a recent block was applied, kept so a reorg can undo it.
+/// Saved with the state so a reorg still works after a restart.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct BlockUndo {
+    index: u64,
+    state: ChainState,
+    /// Chain difficulty before the block, in case appending it triggered a retarget
+    difficulty: u64,
+    /// Consensus modules before the block, those applied alongside it
+    modules: ModulesSnapshot,
+}
+
+/// Consensus modules kept outside `ChainState` that applying a block
+/// updates: Stake and Unstake transactions go through `staking`, and due
+/// `governance` proposals are finalized and executed at the block's timestamp
+#[derive(Default)]
+pub struct ConsensusModules<'a> {
+    pub staking: Option<&'a mut StakingManager>,
+    pub governance: Option<&'a mut GovernanceManager>,
+}
+
+impl<'a> ConsensusModules<'a> {
+    pub fn new(staking: &'a mut StakingManager, governance: &'a mut GovernanceManager) -> Self {
+        ConsensusModules { staking: Some(staking), governance: Some(governance) }
+    }
+    
+    /// Only the staking module
+    pub fn staking(staking: &'a mut StakingManager) -> Self {
+        ConsensusModules { staking: Some(staking), governance: None }
+    }
+    
+    fn snapshot(&self) -> ModulesSnapshot {
+        ModulesSnapshot {
+            staking: self.staking.as_deref().cloned(),
+            governance: self.governance.as_deref().cloned(),
+        }
+    }
+    
+    /// Put back the modules saved in `saved`
+    fn restore(&mut self, saved: ModulesSnapshot) {
+        if let (Some(staking), Some(saved)) = (self.staking.as_deref_mut(), saved.staking) {
+            *staking = saved;
+        }
+        if let (Some(governance), Some(saved)) = (self.governance.as_deref_mut(), saved.governance) {
+            *governance = saved;
+        }
+    }
+}
+
+/// Copies of the consensus modules taken before a block
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ModulesSnapshot {
+    staking: Option<StakingManager>,
+    governance: Option<GovernanceManager>,
+}
+
+/// Largest finality depth a network may set; undo data is kept for this
+/// many blocks
+pub const MAX_REORG_DEPTH: u64 = 64;
+
+/// Blocks on top of a block before it is final when the genesis doesn't set it
+pub const DEFAULT_FINALITY_DEPTH: u64 = 20;
+
+/// Proof-of-work behind a block: each difficulty step is one more leading hex zero
+fn block_work(difficulty: u64) -> u128 {
+    1u128.checked_shl((difficulty * 4).min(127) as u32).unwrap_or(u128::MAX)
+}
+
+/// Cumulative proof-of-work of `blocks`
+fn chain_work(blocks: &[Block]) -> u128 {
+    blocks.iter().fold(0u128, |work, block| work.saturating_add(block_work(block.header.difficulty)))
+}
+
+/// Whether `address` sent the transaction or receives one of its outputs
+fn tx_involves(tx: &Transaction, address: &str) -> bool {
+    tx.sender == address || tx.outputs.iter().any(|o| o.recipient == address)
+}
+
+/// Prefix of the error returned for peer blocks ahead of the local chain
+pub const BLOCK_AHEAD_ERR: &str = "Block ahead of local chain";
+
+/// Peer blocks stamped further than this ahead of network-adjusted time are refused
+pub const MAX_FUTURE_BLOCK_SECS: i64 = 120;
+
+/// Prefix of the retryable error returned for transactions submitted while
+/// a reorg is being applied
+pub const REORG_IN_PROGRESS_ERR: &str = "Reorg in progress";
+
+/// Whether new transactions are refused while a reorg is applied
+/// (`EDGEAI_REJECT_TXS_DURING_REORG`, default on)
+fn reject_txs_during_reorg_from_env() -> bool {
+    std::env::var("EDGEAI_REJECT_TXS_DURING_REORG")
+        .map(|v| !(v == "0" || v.eq_ignore_ascii_case("false")))
+        .unwrap_or(true)
+}
+
+/// Handle on a chain's reorg flag. A reorg runs under the chain write lock,
+/// so API and P2P submissions check a clone of this before waiting on the lock.
+#[derive(Clone, Default)]
+pub struct ReorgCircuit {
+    in_progress: Arc<AtomicBool>,
+    /// Refuse new transactions while a reorg is applied
+    reject_txs: bool,
+}
+
+impl ReorgCircuit {
+    pub fn new(reject_txs: bool) -> Self {
+        ReorgCircuit { in_progress: Arc::new(AtomicBool::new(false)), reject_txs }
+    }
+    
+    pub fn in_progress(&self) -> bool {
+        self.in_progress.load(Ordering::SeqCst)
+    }
+    
+    /// Retryable rejection while a reorg is being applied
+    pub fn check(&self) -> Result<(), TransactionError> {
+        if self.reject_txs && self.in_progress() {
+            return Err(TransactionError::ReorgInProgress);
+        }
+        Ok(())
+    }
+}
+
+/// State snapshots are written every this many blocks
+pub const STATE_SAVE_INTERVAL: u64 = 10;
+
+/// File the pending pool is saved to on shutdown and restored from at startup
+pub const MEMPOOL_FILE: &str = "mempool.json";
+
+/// Base PoIE mining difficulty before the entropy bonus
+pub const POIE_BASE_DIFFICULTY: u64 = 2;
+
+/// PoIE difficulty for a block: higher data entropy lowers the base difficulty (min 1)
+fn poie_difficulty(base: u64, block_txs: &[Transaction]) -> u64 {
+    let entropy_bonus = (Block::calculate_data_entropy(block_txs) * 0.5) as u64;
+    base.saturating_sub(entropy_bonus).max(1)
+}
+
+/// Cold-start difficulty for the first blocks of a new chain. The base
+/// difficulty moves linearly from `initial_difficulty` at genesis to the
+/// chain's normal difficulty at height `blocks`. Every node must use the same
+/// schedule, so it comes from the genesis `params`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WarmupSchedule {
+    pub initial_difficulty: u64,
+    /// Warm-up length in blocks (0 = disabled)
+    pub blocks: u64,
+}
+
+impl WarmupSchedule {
+    /// Base difficulty at `height`, given the post-warm-up difficulty
+    pub fn difficulty_at(&self, height: u64, normal: u64) -> u64 {
+        if height >= self.blocks {
+            return normal;
+        }
+        let (from, to) = (self.initial_difficulty as i128, normal as i128);
+        let ramped = from + (to - from) * height as i128 / self.blocks as i128;
+        ramped.max(1) as u64
+    }
+}
+
+/// Block time the chain aims for when the genesis sets none
+pub const DEFAULT_TARGET_BLOCK_SECS: u64 = 10;
+
+/// Blocks between difficulty retargets when the genesis sets none
+pub const DEFAULT_RETARGET_INTERVAL: u64 = 100;
+
+/// Upper bound on the retargeted base difficulty (leading hex zeros)
+pub const MAX_RETARGET_DIFFICULTY: u64 = 16;
+
+/// Difficulty retargeting. Every `interval` blocks the average time between
+/// the last `interval` blocks is compared with the target: at under half the
+/// target the base difficulty goes up one step, at over twice the target it
+/// comes down one. A step is one leading hex zero, i.e. 16x the work, so one
+/// step per interval also bounds how fast difficulty can swing. Every node
+/// must use the same values, so they come from the genesis `params`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetargetConfig {
+    pub target_block_secs: u64,
+    /// Blocks between retargets (0 = fixed difficulty)
+    pub interval: u64,
+}
+
+impl RetargetConfig {
+    /// Base difficulty after a retarget, given the average block time of the last interval
+    pub fn next_difficulty(&self, current: u64, avg_block_secs: f64) -> u64 {
+        let target = self.target_block_secs as f64;
+        if avg_block_secs < target / 2.0 {
+            (current + 1).min(MAX_RETARGET_DIFFICULTY)
+        } else if avg_block_secs > target * 2.0 {
+            current.saturating_sub(1).max(1)
+        } else {
+            current
+        }
+    }
+}
+
+/// Default cap on the serialized size of a block's transactions
+pub const DEFAULT_MAX_BLOCK_BYTES: usize = 4 * 1024 * 1024;
+
+/// Default cap on a transaction's `data` field; leaves room for a
+/// hex-encoded contract of the maximum code size
+pub const DEFAULT_MAX_TX_DATA_BYTES: usize = 1024 * 1024;
+
+/// Size limits on blocks and submitted transactions, from the genesis `params`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockLimits {
+    /// Transactions per block, excluding the reward transaction
+    pub max_txs_per_block: usize,
+    /// Summed serialized size of a block's transactions, excluding the reward transaction
+    pub max_block_bytes: usize,
+    /// Size of a single transaction's `data` field
+    pub max_tx_data_bytes: usize,
+}
+
+/// Reject transactions whose `data` field is over `max_bytes`
+fn check_data_size(tx: &Transaction, max_bytes: usize) -> Result<(), TransactionError> {
+    match tx.data {
+        Some(ref data) if data.len() > max_bytes => {
+            Err(TransactionError::DataTooLarge { len: data.len(), max: max_bytes })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Default native tokens minted to each block's producer
+pub const DEFAULT_BLOCK_REWARD: u64 = 100;
+
+/// Default base reward for a data contribution
+pub const DEFAULT_DATA_REWARD_BASE: u64 = 50;
+
+/// Default maximum number of entries kept in the data registry
+pub const DEFAULT_DATA_REGISTRY_CAP: usize = 100_000;
+
+/// Default share of each resale paid to the data's original contributor
+pub const DEFAULT_DATA_ROYALTY_SHARE: f64 = 0.05;
+
+/// Default share of block rewards and fees paid to the treasury
+pub const DEFAULT_TREASURY_SHARE: f64 = 0.1;
+
+/// Default blocks unstaked funds stay locked (7 days at the default block time)
+pub const DEFAULT_UNBONDING_BLOCKS: u64 = 7 * 24 * 60 * 60 / DEFAULT_TARGET_BLOCK_SECS;
+
+/// Default cap on the reward multiplier a device earns from its bond
+pub const DEFAULT_MAX_BOND_MULTIPLIER: f64 = 3.0;
+
+/// Blocks between validator downtime evaluations
+pub const DOWNTIME_CHECK_INTERVAL: u64 = 100;
+
+/// Default block size from which transaction signatures are verified in parallel
+const DEFAULT_PARALLEL_VERIFY_THRESHOLD: usize = 64;
+
+/// Parallel signature verification threshold (0 = always verify serially)
+fn parallel_verify_threshold_from_env() -> usize {
+    std::env::var("EDGEAI_PARALLEL_VERIFY_THRESHOLD")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PARALLEL_VERIFY_THRESHOLD)
+}
+
+/// Whether `tx_count` transactions are verified on the rayon pool; small
+/// batches stay serial since spreading them costs more than it saves
+fn verifies_in_parallel(tx_count: usize, threshold: usize) -> bool {
+    threshold > 0 && tx_count >= threshold
+}
+
+/// Check hash, signature and sender of each transaction, in order
+fn verify_transactions(txs: &[Transaction], threshold: usize) -> Vec<bool> {
+    if verifies_in_parallel(txs.len(), threshold) {
+        txs.par_iter().map(|tx| tx.verify()).collect()
+    } else {
+        txs.iter().map(|tx| tx.verify()).collect()
+    }
+}
 
 /// Account state in the blockchain
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +398,9 @@ pub struct Account {
     pub data_contributions: u64,
     pub reputation_score: f64,
     pub staked_amount: u64,
+    /// Balances of non-native tokens keyed by symbol (EDGE lives in `balance`)
+    #[serde(default)]
+    pub token_balances: HashMap<String, u64>,
 }
 
 impl Account {
@@ -46,7 +412,40 @@ impl Account {
             data_contributions: 0,
             reputation_score: 0.0,
             staked_amount: 0,
+            token_balances: HashMap::new(),
+        }
+    }
+    
+    /// Balance of the given token symbol
+    pub fn token_balance(&self, token: &str) -> u64 {
+        if token == NATIVE_TOKEN {
+            self.balance
+        } else {
+            self.token_balances.get(token).copied().unwrap_or(0)
+        }
+    }
+    
+    /// Credit `amount` of `token`
+    pub fn credit(&mut self, token: &str, amount: u64) {
+        if token == NATIVE_TOKEN {
+            self.balance += amount;
+        } else {
+            *self.token_balances.entry(token.to_string()).or_insert(0) += amount;
+        }
+    }
+    
+    /// Debit `amount` of `token`, failing if the balance is too low
+    pub fn debit(&mut self, token: &str, amount: u64) -> Result<(), String> {
+        let available = self.token_balance(token);
+        if available < amount {
+            return Err(format!("Insufficient {} balance", token));
         }
+        if token == NATIVE_TOKEN {
+            self.balance -= amount;
+        } else if let Some(balance) = self.token_balances.get_mut(token) {
+            *balance -= amount;
+        }
+        Ok(())
     }
 }
 
@@ -57,6 +456,73 @@ pub struct ChainState {
     pub data_registry: HashMap<String, DataEntry>,  // data_hash -> DataEntry
     pub total_supply: u64,
     pub total_staked: u64,
+    /// Owner address per device, credited a share of its contribution rewards
+    #[serde(default)]
+    pub device_owners: HashMap<String, String>,
+    /// Registered type per device, used to score its telemetry
+    #[serde(default)]
+    pub device_types: HashMap<String, DeviceType>,
+    /// Recent submissions per device, so repeated readings earn less
+    #[serde(default)]
+    pub novelty: NoveltyCache,
+    /// Native tokens held by the treasury, spent by governance proposals
+    #[serde(default)]
+    pub treasury: u64,
+    /// Stake released by Unstake and ValidatorUnbond transactions per account, oldest first
+    #[serde(default)]
+    pub unbonding: HashMap<String, Vec<PendingUnbond>>,
+    /// Self-stake locked by each registered validator's operator
+    #[serde(default)]
+    pub validator_bonds: HashMap<String, ValidatorBond>,
+    /// Data hashes each buyer has been granted access to by DataPurchase transactions
+    #[serde(default)]
+    pub data_access: HashMap<String, Vec<String>>,
+    /// Registration bonds locked from device owners, per device
+    #[serde(default)]
+    pub device_bonds: HashMap<String, DeviceBond>,
+    /// Contracts deployed by ContractDeploy transactions, with their code and storage
+    #[serde(default)]
+    pub contracts: ContractStore,
+    /// Data contribution rewards per category, changed by governance
+    #[serde(default)]
+    pub reward_policy: RewardPolicy,
+    /// Consensus parameters, from genesis and governance
+    #[serde(default)]
+    pub params: ChainParams,
+}
+
+/// Self-stake an operator locked to register a validator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorBond {
+    pub operator: String,
+    pub self_stake: u64,
+}
+
+/// Stake released by an Unstake or ValidatorUnbond transaction, credited
+/// back to the account by the block at `release_height`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingUnbond {
+    pub amount: u64,
+    pub release_height: u64,
+    /// Validator the stake was bonded to
+    #[serde(default)]
+    pub validator: String,
+    /// The validator's self-stake rather than a delegation
+    #[serde(default)]
+    pub self_stake: bool,
+}
+
+/// Stake an owner locked as a device's bond by a DeviceBond transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceBond {
+    pub owner: String,
+    pub amount: u64,
+    /// Reward multiplier the bond earns the device's contributions
+    pub reward_multiplier: f64,
+    /// Height at which a DeviceUnbond returns the bond to the owner; the
+    /// device earns no bond multiplier meanwhile
+    #[serde(default)]
+    pub release_height: Option<u64>,
 }
 
 /// Data entry in the registry
@@ -76,8 +542,6 @@ pub struct DataEntry {
 pub struct ChainMetadata {
     pub total_blocks: u64,
     pub difficulty: u64,
-    pub block_reward: u64,
-    pub data_reward_base: u64,
     pub last_block_time: i64,
 }
 
@@ -88,8 +552,12 @@ pub struct Blockchain {
     /// Only keep recent blocks in memory for API queries
     #[serde(skip)]
     pub chain: Vec<Block>,
+    /// Real transactions submitted via API/P2P (always included first)
+    #[serde(skip)]
+    pub pending_transactions: TxPool,
+    /// Demo load from the synthetic mempool generator
     #[serde(skip)]
-    pub pending_transactions: Vec<Transaction>,
+    pub synthetic_transactions: Vec<Transaction>,
     /// RocksDB storage backend (primary)
     #[serde(skip)]
     storage: Option<Storage>,
@@ -107,17 +575,63 @@ pub struct Blockchain {
     pub cold_blocks_cutoff: u64,
     pub state: ChainState,
     pub difficulty: u64,
-    pub block_reward: u64,
-    pub data_reward_base: u64,
     pub last_block_time: i64,
     /// Total number of blocks (including those on disk)
     #[serde(default)]
     pub total_blocks: u64,
+    /// Transactions per block from which signatures are verified in parallel
+    #[serde(skip)]
+    pub parallel_verify_threshold: usize,
+    /// Network clock offset (`NetworkClock::offset_ms`) applied to block timestamps
+    #[serde(skip)]
+    clock_offset_ms: i64,
+    /// Set between `begin_reorg` and `end_reorg`, shared with `reorg_circuit` handles
+    #[serde(skip)]
+    reorg: ReorgCircuit,
+    /// Pre-block state of the blocks that are not final yet, oldest first
+    #[serde(skip)]
+    undo_log: VecDeque<BlockUndo>,
+    /// Included transaction counts per mempool class
+    #[serde(skip)]
+    pub tx_class_stats: TxClassStats,
+    /// Lifecycle tracing for transactions matching a runtime filter
+    #[serde(skip)]
+    pub tracer: TxTracer,
+    /// Block log and state snapshot backend (file or sled)
+    #[serde(skip, default = "default_block_store")]
+    block_store: Box<dyn BlockStore>,
+    /// Block and mempool events for streaming subscribers
+    #[serde(skip)]
+    pub events: EventBus,
+    /// Compiles and runs the contracts in `state.contracts`
+    #[serde(skip)]
+    wasm: WasmRuntime,
+    /// Contract logs of applied blocks not yet written to the block store,
+    /// per height, with the emitting contract
+    #[serde(skip)]
+    pending_logs: BTreeMap<u64, Vec<(String, ContractLog)>>,
+    /// Genesis block hash, read once
+    #[serde(skip)]
+    chain_id: std::sync::OnceLock<String>,
+}
+
+fn default_block_store() -> Box<dyn BlockStore> {
+    Box::new(FileBlockStore::new(DATA_DIR))
+}
+
+/// Counts of included transactions by origin
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TxClassStats {
+    pub real_included: u64,
+    pub synthetic_included: u64,
 }
 
+/// Default maximum transactions per block (excluding the reward transaction)
+pub const MAX_TXS_PER_BLOCK: usize = 150;
+
 impl Blockchain {
-    /// Create a new blockchain with genesis block or load from disk
-    pub fn new() -> Self {
+    /// Load the blockchain from disk, or start a new one from `genesis`
+    pub fn new(genesis: &Genesis) -> Self {
         // Try to load from disk first
         if let Some(chain) = Self::load_from_disk() {
             info!("Blockchain loaded from disk with {} total blocks ({} in memory)", 
@@ -126,48 +640,10 @@ impl Blockchain {
         }
 
         info!("No existing blockchain found, creating new genesis chain");
-        let genesis = Block::genesis();
-        
-        let mut accounts = HashMap::new();
-        // Initialize genesis account
-        accounts.insert("genesis".to_string(), Account {
-            address: "genesis".to_string(),
-            balance: 1_000_000_000,
-            nonce: 0,
-            data_contributions: 0,
-            reputation_score: 100.0,
-            staked_amount: 0,
-        });
-        
-        // Initialize simulated IoT device accounts with 100 EDGE each
-        let simulated_devices = [
-            "edge_node_001", "edge_node_002", "edge_node_003",
-            "edge_node_004", "edge_node_005", "edge_node_006",
-            "edge_node_007", "edge_node_008", "edge_node_009",
-            "edge_node_010", "factory_hub_a", "factory_hub_b",
-            "city_gateway", "agri_node_1", "med_device_1",
-            "power_grid_01", "transit_hub", "warehouse_sys",
-        ];
-        
-        for device in simulated_devices.iter() {
-            accounts.insert(device.to_string(), Account {
-                address: device.to_string(),
-                balance: 100,
-                nonce: 0,
-                data_contributions: 0,
-                reputation_score: 50.0,
-                staked_amount: 0,
-            });
-        }
-        info!("Initialized {} simulated device accounts with 100 EDGE each", simulated_devices.len());
-        
-        let state = ChainState {
-            accounts,
-            data_registry: HashMap::new(),
-            total_supply: 1_000_000_000,
-            total_staked: 0,
-        };
-        
+        let state = genesis.chain_state();
+        info!("Initialized {} genesis accounts with a total supply of {}", state.accounts.len(), state.total_supply);
+        let genesis = Block::genesis(genesis);
+
         info!("Blockchain initialized with genesis block");
         
         // Initialize RocksDB storage
@@ -182,6 +658,8 @@ impl Blockchain {
             }
         };
         
+        let block_store = open_block_store(Path::new(DATA_DIR));
+        
         // Initialize cold storage
         let cold_storage = match ColdStorage::open(DATA_DIR) {
             Ok(cs) => {
@@ -196,7 +674,8 @@ impl Blockchain {
         
         let chain = Blockchain {
             chain: vec![genesis.clone()],
-            pending_transactions: Vec::new(),
+            pending_transactions: TxPool::new(mempool_capacity_from_env()),
+            synthetic_transactions: Vec::new(),
             storage,
             cold_storage,
             cold_storage_cutoff: 0,
@@ -204,10 +683,19 @@ impl Blockchain {
             cold_blocks_cutoff: 0,
             state,
             difficulty: 2,
-            block_reward: 100,
-            data_reward_base: 50,
             last_block_time: Utc::now().timestamp(),
             total_blocks: 1,
+            parallel_verify_threshold: parallel_verify_threshold_from_env(),
+            clock_offset_ms: 0,
+            reorg: ReorgCircuit::new(reject_txs_during_reorg_from_env()),
+            undo_log: VecDeque::new(),
+            tx_class_stats: TxClassStats::default(),
+            tracer: TxTracer::new(TraceFilter::from_env()),
+            block_store,
+            events: EventBus::default(),
+            wasm: WasmRuntime::new(),
+            pending_logs: BTreeMap::new(),
+            chain_id: std::sync::OnceLock::new(),
         };
 
         // Save initial state to both RocksDB and file (for compatibility)
@@ -266,35 +754,47 @@ impl Blockchain {
         let total_supply = storage.get_total_supply();
         let total_staked = storage.get_total_staked();
         
-        // Load accounts from file (RocksDB account loading is optional optimization)
-        let state_path = Path::new(DATA_DIR).join(STATE_FILE);
-        let state = if state_path.exists() {
-            if let Ok(data) = fs::read_to_string(&state_path) {
-                if let Ok((s, _)) = serde_json::from_str::<(ChainState, ChainMetadata)>(&data) {
-                    s
-                } else {
-                    ChainState {
-                        accounts: HashMap::new(),
-                        data_registry: HashMap::new(),
-                        total_supply,
-                        total_staked,
-                    }
-                }
-            } else {
+        // Load accounts from the block store (RocksDB account loading is optional optimization)
+        let block_store = open_block_store(Path::new(DATA_DIR));
+        let state = match block_store.load_state() {
+            Ok(Some((s, _))) => s,
+            Ok(None) => ChainState {
+                accounts: HashMap::new(),
+                data_registry: HashMap::new(),
+                total_supply,
+                total_staked,
+                device_owners: HashMap::new(),
+                device_types: HashMap::new(),
+                novelty: NoveltyCache::new(),
+                treasury: 0,
+                unbonding: HashMap::new(),
+                validator_bonds: HashMap::new(),
+                data_access: HashMap::new(),
+                device_bonds: HashMap::new(),
+                contracts: ContractStore::default(),
+                reward_policy: RewardPolicy::default(),
+                params: ChainParams::default(),
+            },
+            Err(e) => {
+                warn!("Failed to load state from {} store: {}", block_store.name(), e);
                 ChainState {
                     accounts: HashMap::new(),
                     data_registry: HashMap::new(),
                     total_supply,
                     total_staked,
+                    device_owners: HashMap::new(),
+                    device_types: HashMap::new(),
+                    novelty: NoveltyCache::new(),
+                    treasury: 0,
+                    unbonding: HashMap::new(),
+                    validator_bonds: HashMap::new(),
+                    data_access: HashMap::new(),
+                    device_bonds: HashMap::new(),
+                    contracts: ContractStore::default(),
+                    reward_policy: RewardPolicy::default(),
+                    params: ChainParams::default(),
                 }
             }
-        } else {
-            ChainState {
-                accounts: HashMap::new(),
-                data_registry: HashMap::new(),
-                total_supply,
-                total_staked,
-            }
         };
         
         // Initialize cold storage
@@ -324,10 +824,12 @@ impl Blockchain {
             }
         };
         let cold_blocks_cutoff = storage.get_cold_blocks_cutoff();
+        let undo_log = Self::load_undo(block_store.as_ref(), metadata.total_blocks);
         
         let mut chain = Blockchain {
             chain: recent_blocks,
-            pending_transactions: Vec::new(),
+            pending_transactions: TxPool::new(mempool_capacity_from_env()),
+            synthetic_transactions: Vec::new(),
             storage: Some(storage),
             cold_storage,
             cold_storage_cutoff,
@@ -335,10 +837,19 @@ impl Blockchain {
             cold_blocks_cutoff,
             state,
             difficulty: metadata.difficulty,
-            block_reward: metadata.block_reward,
-            data_reward_base: metadata.data_reward_base,
             last_block_time: metadata.last_block_time,
             total_blocks: metadata.total_blocks,
+            parallel_verify_threshold: parallel_verify_threshold_from_env(),
+            clock_offset_ms: 0,
+            reorg: ReorgCircuit::new(reject_txs_during_reorg_from_env()),
+            undo_log,
+            tx_class_stats: TxClassStats::default(),
+            tracer: TxTracer::new(TraceFilter::from_env()),
+            block_store,
+            events: EventBus::default(),
+            wasm: WasmRuntime::new(),
+            pending_logs: BTreeMap::new(),
+            chain_id: std::sync::OnceLock::new(),
         };
         
         chain.ensure_device_accounts();
@@ -401,14 +912,20 @@ impl Blockchain {
         };
         
         // Load only the last N blocks into memory
-        let recent_blocks = Self::load_recent_blocks(&blocks_path, MAX_BLOCKS_IN_MEMORY)?;
+        let recent_blocks = FileBlockStore::new(DATA_DIR).load_recent(MAX_BLOCKS_IN_MEMORY).ok()?;
+        if recent_blocks.is_empty() {
+            return None;
+        }
+        let block_store = open_block_store(Path::new(DATA_DIR));
+        let undo_log = Self::load_undo(block_store.as_ref(), metadata.total_blocks);
         
         // Initialize cold storage
         let cold_storage = ColdStorage::open(DATA_DIR).ok();
         
         let mut chain = Blockchain {
             chain: recent_blocks,
-            pending_transactions: Vec::new(),
+            pending_transactions: TxPool::new(mempool_capacity_from_env()),
+            synthetic_transactions: Vec::new(),
             storage,
             cold_storage,
             cold_storage_cutoff: 0,
@@ -416,10 +933,19 @@ impl Blockchain {
             cold_blocks_cutoff: 0,
             state,
             difficulty: metadata.difficulty,
-            block_reward: metadata.block_reward,
-            data_reward_base: metadata.data_reward_base,
             last_block_time: metadata.last_block_time,
             total_blocks: metadata.total_blocks,
+            parallel_verify_threshold: parallel_verify_threshold_from_env(),
+            clock_offset_ms: 0,
+            reorg: ReorgCircuit::new(reject_txs_during_reorg_from_env()),
+            undo_log,
+            tx_class_stats: TxClassStats::default(),
+            tracer: TxTracer::new(TraceFilter::from_env()),
+            block_store,
+            events: EventBus::default(),
+            wasm: WasmRuntime::new(),
+            pending_logs: BTreeMap::new(),
+            chain_id: std::sync::OnceLock::new(),
         };
         
         // Ensure simulated device accounts exist
@@ -428,27 +954,6 @@ impl Blockchain {
         Some(chain)
     }
     
-    /// Load recent blocks from JSONL file
-    fn load_recent_blocks(path: &Path, count: usize) -> Option<Vec<Block>> {
-        let file = fs::File::open(path).ok()?;
-        let reader = BufReader::new(file);
-        
-        // Read all lines and keep only the last N
-        let lines: Vec<String> = reader.lines().filter_map(|l| l.ok()).collect();
-        let start = if lines.len() > count { lines.len() - count } else { 0 };
-        
-        let blocks: Vec<Block> = lines[start..]
-            .iter()
-            .filter_map(|line| serde_json::from_str(line).ok())
-            .collect();
-        
-        if blocks.is_empty() {
-            None
-        } else {
-            Some(blocks)
-        }
-    }
-    
     /// Load and migrate from legacy format
     fn load_and_migrate_legacy() -> Option<Self> {
         let legacy_path = Path::new(DATA_DIR).join("chain.json");
@@ -466,19 +971,15 @@ impl Blockchain {
         
         let legacy: LegacyBlockchain = serde_json::from_str(&data).ok()?;
         let total_blocks = legacy.chain.len() as u64;
+        let mut state = legacy.state;
+        state.params.block_reward = legacy.block_reward;
+        state.params.data_reward_base = legacy.data_reward_base;
         
-        // Write all blocks to new format
-        let blocks_path = Path::new(DATA_DIR).join(BLOCKS_FILE);
-        if let Ok(mut file) = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&blocks_path) 
-        {
-            for block in &legacy.chain {
-                if let Ok(json) = serde_json::to_string(block) {
-                    let _ = writeln!(file, "{}", json);
-                }
+        // Write all blocks to the block store
+        let block_store = open_block_store(Path::new(DATA_DIR));
+        for block in &legacy.chain {
+            if let Err(e) = block_store.append_block(block) {
+                warn!("Failed to write legacy block {}: {}", block.index, e);
             }
         }
         
@@ -503,13 +1004,11 @@ impl Blockchain {
                 let metadata = ChainMetadata {
                     total_blocks,
                     difficulty: legacy.difficulty,
-                    block_reward: legacy.block_reward,
-                    data_reward_base: legacy.data_reward_base,
                     last_block_time: legacy.last_block_time,
                 };
                 let _ = s.put_metadata(&metadata);
-                let _ = s.put_accounts_batch(&legacy.state.accounts);
-                let _ = s.put_supply_info(legacy.state.total_supply, legacy.state.total_staked);
+                let _ = s.put_accounts_batch(&state.accounts);
+                let _ = s.put_supply_info(state.total_supply, state.total_staked);
                 
                 Some(s)
             }
@@ -524,22 +1023,32 @@ impl Blockchain {
         
         let mut chain = Blockchain {
             chain: recent_blocks,
-            pending_transactions: Vec::new(),
+            pending_transactions: TxPool::new(mempool_capacity_from_env()),
+            synthetic_transactions: Vec::new(),
             storage,
             cold_storage,
             cold_storage_cutoff: 0,
             cold_blocks: None,
             cold_blocks_cutoff: 0,
-            state: legacy.state,
+            state,
             difficulty: legacy.difficulty,
-            block_reward: legacy.block_reward,
-            data_reward_base: legacy.data_reward_base,
             last_block_time: legacy.last_block_time,
             total_blocks,
+            parallel_verify_threshold: parallel_verify_threshold_from_env(),
+            clock_offset_ms: 0,
+            reorg: ReorgCircuit::new(reject_txs_during_reorg_from_env()),
+            undo_log: VecDeque::new(),
+            tx_class_stats: TxClassStats::default(),
+            tracer: TxTracer::new(TraceFilter::from_env()),
+            block_store,
+            events: EventBus::default(),
+            wasm: WasmRuntime::new(),
+            pending_logs: BTreeMap::new(),
+            chain_id: std::sync::OnceLock::new(),
         };
         
         // Save state in new format
-        chain.save_state_to_disk();
+        chain.save_state_to_store();
         
         // Remove legacy file
         let _ = fs::remove_file(&legacy_path);
@@ -570,6 +1079,7 @@ impl Blockchain {
                     data_contributions: 0,
                     reputation_score: 50.0,
                     staked_amount: 0,
+                    token_balances: HashMap::new(),
                 });
                 initialized_count += 1;
             }
@@ -579,83 +1089,155 @@ impl Blockchain {
         }
     }
 
-    /// Append a single block to disk (memory efficient)
-    fn append_block_to_disk(&self, block: &Block) {
-        if let Err(e) = fs::create_dir_all(DATA_DIR) {
-            error!("Failed to create data directory: {}", e);
-            return;
-        }
-
-        let blocks_path = Path::new(DATA_DIR).join(BLOCKS_FILE);
-        
-        match OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&blocks_path) 
-        {
-            Ok(mut file) => {
-                match serde_json::to_string(block) {
-                    Ok(json) => {
-                        if let Err(e) = writeln!(file, "{}", json) {
-                            error!("Failed to append block to disk: {}", e);
-                        }
-                    },
-                    Err(e) => {
-                        error!("Failed to serialize block: {}", e);
-                    }
-                }
-            },
-            Err(e) => {
-                error!("Failed to open blocks file: {}", e);
-            }
+    /// Append a single block to the block store (memory efficient)
+    fn append_block_to_store(&self, block: &Block) {
+        if let Err(e) = self.block_store.append_block(block) {
+            error!("Failed to append block to {} store: {}", self.block_store.name(), e);
         }
     }
     
-    /// Save state to disk (separate from blocks)
-    fn save_state_to_disk(&self) {
-        if let Err(e) = fs::create_dir_all(DATA_DIR) {
-            error!("Failed to create data directory: {}", e);
-            return;
-        }
-
-        let state_path = Path::new(DATA_DIR).join(STATE_FILE);
-        
+    /// Save state to the block store (separate from blocks)
+    fn save_state_to_store(&self) {
         let metadata = ChainMetadata {
             total_blocks: self.total_blocks,
             difficulty: self.difficulty,
-            block_reward: self.block_reward,
-            data_reward_base: self.data_reward_base,
             last_block_time: self.last_block_time,
         };
         
-        match serde_json::to_string(&(&self.state, &metadata)) {
-            Ok(data) => {
-                if let Err(e) = fs::write(&state_path, data) {
-                    error!("Failed to write state to disk: {}", e);
-                }
-            },
+        if let Err(e) = self.block_store.save_state(&self.state, &metadata) {
+            error!("Failed to save state to {} store: {}", self.block_store.name(), e);
+        }
+        let undo: Vec<BlockUndo> = self.undo_log.iter().cloned().collect();
+        if let Err(e) = self.block_store.save_undo(&undo) {
+            error!("Failed to save undo data to {} store: {}", self.block_store.name(), e);
+        }
+    }
+    
+    /// Undo data saved with the state, for blocks below `total_blocks`
+    fn load_undo(block_store: &dyn BlockStore, total_blocks: u64) -> VecDeque<BlockUndo> {
+        match block_store.load_undo() {
+            Ok(undo) => undo.into_iter().filter(|u| u.index < total_blocks).collect(),
             Err(e) => {
-                error!("Failed to serialize state: {}", e);
+                warn!("Failed to load undo data from {} store: {}", block_store.name(), e);
+                VecDeque::new()
             }
         }
     }
 
+    /// In-memory chain with a funded `genesis` account and a block store
+    /// under `block_store_dir`; no RocksDB and no state file. Difficulty is
+    /// not retargeted. Used by tests and the node benchmark.
+    pub fn scratch(block_store_dir: PathBuf) -> Blockchain {
+        let mut accounts = HashMap::new();
+        let mut genesis = Account::new("genesis".to_string());
+        genesis.balance = 1_000_000_000;
+        accounts.insert("genesis".to_string(), genesis);
+
+        Blockchain {
+            chain: vec![Block::genesis(&Genesis::default())],
+            pending_transactions: TxPool::new(mempool_capacity_from_env()),
+            synthetic_transactions: Vec::new(),
+            storage: None,
+            cold_storage: None,
+            cold_storage_cutoff: 0,
+            cold_blocks: None,
+            cold_blocks_cutoff: 0,
+            state: ChainState {
+                accounts,
+                data_registry: HashMap::new(),
+                total_supply: 1_000_000_000,
+                total_staked: 0,
+                device_owners: HashMap::new(),
+                device_types: HashMap::new(),
+                novelty: NoveltyCache::new(),
+                treasury: 0,
+                unbonding: HashMap::new(),
+                validator_bonds: HashMap::new(),
+                data_access: HashMap::new(),
+                device_bonds: HashMap::new(),
+                contracts: ContractStore::default(),
+                reward_policy: RewardPolicy::default(),
+                params: ChainParams { retarget_interval: 0, data_registry_cap: 0, ..ChainParams::default() },
+            },
+            difficulty: 2,
+            last_block_time: Utc::now().timestamp(),
+            total_blocks: 1,
+            parallel_verify_threshold: DEFAULT_PARALLEL_VERIFY_THRESHOLD,
+            clock_offset_ms: 0,
+            reorg: ReorgCircuit::new(true),
+            undo_log: VecDeque::new(),
+            tx_class_stats: TxClassStats::default(),
+            tracer: TxTracer::default(),
+            block_store: Box::new(FileBlockStore::new(block_store_dir)),
+            events: EventBus::default(),
+            wasm: WasmRuntime::new(),
+            pending_logs: BTreeMap::new(),
+            chain_id: std::sync::OnceLock::new(),
+        }
+    }
+    
     /// Legacy save_to_disk for compatibility - now uses optimized storage
     pub fn save_to_disk(&self) {
         self.persist_state();
     }
     
-    /// Persist a block to storage (RocksDB primary, file fallback)
-    fn persist_block(&self, block: &Block) {
-        // Write to RocksDB if available
+    /// Save the pending pool as JSON (written to a temp file, then renamed).
+    /// Returns the number of transactions saved.
+    pub fn save_mempool(&self, path: &Path) -> Result<usize, String> {
+        let pending: Vec<&Transaction> = self.pending_transactions.iter().collect();
+        let data = serde_json::to_vec(&pending)
+            .map_err(|e| format!("Failed to serialize mempool: {}", e))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, data).map_err(|e| format!("Failed to write mempool: {}", e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write mempool: {}", e))?;
+        Ok(pending.len())
+    }
+    
+    /// Re-admit the transactions saved by `save_mempool`, then delete the
+    /// file so they are only restored once. The file is not trusted, so
+    /// unsigned transactions are dropped like ones that no longer validate
+    /// (mined meanwhile, stale nonce). Returns the number restored.
+    pub fn restore_mempool(&mut self, path: &Path) -> Result<usize, String> {
+        let data = match fs::read(path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Failed to read mempool: {}", e)),
+        };
+        let mut txs: Vec<Transaction> = serde_json::from_slice(&data)
+            .map_err(|e| format!("Failed to parse mempool: {}", e))?;
+        if let Err(e) = fs::remove_file(path) {
+            warn!("Failed to remove {}: {}", path.display(), e);
+        }
+        // The pool iterates by gas price; re-admit each sender's nonces in order
+        txs.sort_by_key(|tx| tx.nonce);
+        let mut restored = 0;
+        for tx in txs {
+            match self.add_transaction(tx) {
+                Ok(_) => restored += 1,
+                Err(e) => log::debug!("Dropped saved mempool transaction: {}", e),
+            }
+        }
+        Ok(restored)
+    }
+    
+    /// Flush chain state and the pending pool before the node exits, since
+    /// state is otherwise only saved every `STATE_SAVE_INTERVAL` blocks
+    pub fn flush_on_shutdown(&self, mempool_path: &Path) -> Result<usize, String> {
+        self.save_to_disk();
+        self.save_mempool(mempool_path)
+    }
+    
+    /// Persist a block to storage (RocksDB primary, file fallback)
+    pub(crate) fn persist_block(&self, block: &Block) {
+        // Write to RocksDB if available
         if let Some(ref storage) = self.storage {
             if let Err(e) = storage.put_block(block) {
                 error!("Failed to write block to RocksDB: {}", e);
             }
         }
         
-        // Also write to file for compatibility during migration period
-        self.append_block_to_disk(block);
+        // Also write to the block store for compatibility during migration period
+        self.append_block_to_store(block);
     }
     
     /// Persist state to storage (RocksDB primary, file fallback)
@@ -665,8 +1247,6 @@ impl Blockchain {
             let metadata = ChainMetadata {
                 total_blocks: self.total_blocks,
                 difficulty: self.difficulty,
-                block_reward: self.block_reward,
-                data_reward_base: self.data_reward_base,
                 last_block_time: self.last_block_time,
             };
             
@@ -684,8 +1264,8 @@ impl Blockchain {
             }
         }
         
-        // Also write to file for compatibility
-        self.save_state_to_disk();
+        // Also write to the block store for compatibility
+        self.save_state_to_store();
     }
     
     /// Prune old blocks from memory to prevent OOM
@@ -831,6 +1411,27 @@ impl Blockchain {
         }
     }
     
+    /// Sorted dump of the current state with its root. Only the state at the
+    /// chain tip is kept, so `height` must be the latest block if given.
+    pub fn state_dump(&self, height: Option<u64>) -> Result<StateDump, String> {
+        let latest = self.latest_block();
+        if let Some(h) = height {
+            if h != latest.index {
+                return Err(format!(
+                    "State at height {} is not available; only the latest height ({}) can be dumped",
+                    h, latest.index
+                ));
+            }
+        }
+        let state = CanonicalState::from_state(&self.state);
+        Ok(StateDump {
+            height: latest.index,
+            block_hash: latest.hash.clone(),
+            state_root: state.root(),
+            state,
+        })
+    }
+    
     /// Get the latest block
     pub fn latest_block(&self) -> &Block {
         self.chain.last().unwrap()
@@ -871,21 +1472,20 @@ impl Blockchain {
             }
         }
         
-        // Fall back to file (O(n) scan - legacy compatibility)
-        let blocks_path = Path::new(DATA_DIR).join(BLOCKS_FILE);
-        if let Ok(file) = fs::File::open(&blocks_path) {
-            let reader = BufReader::new(file);
-            for (i, line) in reader.lines().enumerate() {
-                if i as u64 == index {
-                    if let Ok(line) = line {
-                        return serde_json::from_str(&line).ok();
-                    }
-                }
-            }
-        }
-        None
+        // Fall back to the block store (indexed seek for the file backend)
+        self.block_store.get_block_by_height(index).unwrap_or_else(|e| {
+            warn!("Failed to read block {} from {} store: {}", index, self.block_store.name(), e);
+            None
+        })
     }
     
+    /// Network identifier: the genesis block hash, which differs per genesis file
+    pub fn chain_id(&self) -> &str {
+        self.chain_id.get_or_init(|| {
+            self.get_block_with_disk_fallback(0).map(|b| b.hash).unwrap_or_default()
+        })
+    }
+
     /// Get block by hash
     pub fn get_block_by_hash(&self, hash: &str) -> Option<&Block> {
         self.chain.iter().find(|b| b.hash == hash)
@@ -895,7 +1495,10 @@ impl Blockchain {
     /// Now supports RocksDB lookup for O(1) transaction retrieval
     pub fn get_transaction(&self, hash: &str) -> Option<Transaction> {
         // Search in pending transactions first
-        if let Some(tx) = self.pending_transactions.iter().find(|tx| tx.hash == hash) {
+        if let Some(tx) = self.pending_transactions.iter()
+            .chain(self.synthetic_transactions.iter())
+            .find(|tx| tx.hash == hash)
+        {
             return Some(tx.clone());
         }
         
@@ -939,77 +1542,268 @@ impl Blockchain {
         None
     }
     
-    /// Add a transaction to pending pool
-    pub fn add_transaction(&mut self, tx: Transaction) -> Result<String, String> {
+    /// Block containing a mined transaction, from memory or the RocksDB/cold indexes
+    pub fn find_transaction_block(&self, hash: &str) -> Option<Block> {
+        if let Some(block) = self.chain.iter().rev().find(|b| b.transactions.iter().any(|tx| tx.hash == hash)) {
+            return Some(block.clone());
+        }
+        let location = self.storage.as_ref()
+            .and_then(|storage| storage.get_transaction_location(hash))
+            .or_else(|| self.cold_storage.as_ref().and_then(|cold| cold.get_transaction_location(hash)))?;
+        self.get_block_with_disk_fallback(location.block_index)
+    }
+    
+    /// Add a transaction to pending pool. It must be signed by its sender,
+    /// since it may come from a peer or a client holding someone else's address.
+    pub fn add_transaction(&mut self, tx: Transaction) -> Result<String, TransactionError> {
+        self.admit_transaction(tx, true)
+    }
+
+    /// Add a transaction without checking its sender signature. Peers reject
+    /// blocks carrying unsigned transactions, so only tests use this.
+    #[cfg(test)]
+    pub fn add_local_transaction(&mut self, tx: Transaction) -> Result<String, TransactionError> {
+        self.admit_transaction(tx, false)
+    }
+
+    fn admit_transaction(&mut self, tx: Transaction, require_signature: bool) -> Result<String, TransactionError> {
+        self.tracer.record(&tx, TxStage::Received);
+        let checked = if require_signature { tx.check_sender_signature() } else { Ok(()) };
+        if let Err(e) = checked.and_then(|_| self.check_new_transaction(&tx)) {
+            self.tracer.record(&tx, TxStage::Rejected { reason: e.to_string() });
+            return Err(e);
+        }
+        self.tracer.record(&tx, TxStage::Validated);
+        
+        let tx_hash = tx.hash.clone();
+        let tx_type = tx.tx_type.clone();
+        if let Err(e) = self.insert_pending(tx.clone()) {
+            self.tracer.record(&tx, TxStage::Rejected { reason: e.to_string() });
+            return Err(e);
+        }
+        info!("Transaction {} added to pending pool (type: {:?})", &tx_hash[..8], tx_type);
+        
+        Ok(tx_hash)
+    }
+    
+    /// Insert into the pending pool, logging any lower-fee transaction evicted
+    fn insert_pending(&mut self, tx: Transaction) -> Result<(), TransactionError> {
+        let event = ChainEvent::from(&tx);
+        if let Some(evicted) = self.pending_transactions.insert(tx)? {
+            warn!("Mempool full, evicted transaction {}", &evicted[..8.min(evicted.len())]);
+        }
+        self.events.publish(event);
+        Ok(())
+    }
+    
+    /// Mark the chain as mid-reorg: until `end_reorg`, state may be
+    /// rolled back and new transactions are refused with `TransactionError::ReorgInProgress`
+    pub fn begin_reorg(&mut self) {
+        self.reorg.in_progress.store(true, Ordering::SeqCst);
+    }
+    
+    /// Mark the chain consistent again after a reorg
+    pub fn end_reorg(&mut self) {
+        self.reorg.in_progress.store(false, Ordering::SeqCst);
+    }
+    
+    pub fn reorg_in_progress(&self) -> bool {
+        self.reorg.in_progress()
+    }
+    
+    /// Handle to check for a running reorg without taking the chain lock
+    pub fn reorg_circuit(&self) -> ReorgCircuit {
+        self.reorg.clone()
+    }
+    
+    /// Blocks built on top of a block before it is final and can't be reorged away
+    pub fn finality_depth(&self) -> u64 {
+        self.state.params.finality_depth
+    }
+    
+    /// Height of the newest final block: `finality_depth` blocks below the tip
+    pub fn finalized_height(&self) -> u64 {
+        self.total_blocks.saturating_sub(1).saturating_sub(self.finality_depth())
+    }
+    
+    /// Admission checks for a transaction submitted to the pending pool
+    fn check_new_transaction(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        self.reorg.check()?;
+        
         // Validate transaction hash
         if !tx.verify_hash() {
             log::warn!("Transaction {} failed hash verification (type: {:?})", &tx.hash[..8], tx.tx_type);
-            return Err("Invalid transaction hash".to_string());
+            return Err(TransactionError::InvalidHash);
+        }
+        
+        // Anti-spam proof-of-work (system transactions are exempt)
+        if tx.sender != "system" && !tx.verify_pow(self.state.params.tx_pow_bits) {
+            return Err(TransactionError::InsufficientPow { bits: self.state.params.tx_pow_bits });
         }
         
+        check_memo(tx)?;
+        check_data_size(tx, self.state.params.max_tx_data_bytes)?;
+        check_transfer_outputs(tx)?;
+        check_height_deadline(tx, self.total_blocks)?;
+        self.check_reward_claim(tx)?;
+        
+        // Replay protection
+        self.check_nonce(tx)?;
+        
         // Apply validation rules based on transaction type
         match tx.tx_type {
-            TransactionType::Transfer => {
-                let sender_balance = self.get_balance(&tx.sender);
-                let required = tx.total_output();
-                if sender_balance < required {
-                    log::debug!("Transfer rejected: {} has {} EDGE, needs {}", &tx.sender, sender_balance, required);
-                    return Err(format!("Insufficient balance: has {}, needs {}", sender_balance, required));
-                }
+            TransactionType::Transfer | TransactionType::DataPurchase => {
+                self.check_available_funds(tx)?;
             },
             TransactionType::DataContribution => {
                 // Future: Add data quality validation
             },
-            TransactionType::DataPurchase => {
-                let sender_balance = self.get_balance(&tx.sender);
-                if sender_balance < tx.total_output() {
-                    return Err("Insufficient balance".to_string());
-                }
-            },
             TransactionType::ContractDeploy | TransactionType::ContractCall => {
                 // For now, allow contract operations without balance check
             },
             _ => {}
         }
         
-        let tx_hash = tx.hash.clone();
-        let tx_type = tx.tx_type.clone();
-        self.pending_transactions.push(tx);
-        info!("Transaction {} added to pending pool (type: {:?})", &tx_hash[..8], tx_type);
-        
-        Ok(tx_hash)
+        Ok(())
     }
     
-    /// Validate a single transaction (pure function for parallel processing)
-    fn validate_transaction_pure(&self, tx: &Transaction) -> Result<(), String> {
-        // Validate transaction hash
-        if !tx.verify_hash() {
-            return Err(format!("Invalid transaction hash: {}", &tx.hash[..8.min(tx.hash.len())]));
-        }
-        
-        // Apply validation rules based on transaction type
+    /// Sender balance left after the transactions it already has pending
+    fn available_balance(&self, address: &str, token: &str) -> u64 {
+        self.get_token_balance(address, token)
+            .saturating_sub(self.pending_transactions.pending_debit(address, token))
+    }
+    
+    /// Check the sender's committed balance covers the transaction amount plus
+    /// its gas fee. Fees are always paid in the native token.
+    fn check_funds(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        self.check_funds_with(tx, |address, token| self.get_token_balance(address, token))
+    }
+    
+    /// Like `check_funds`, net of what the sender's pending transactions already spend
+    fn check_available_funds(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        self.check_funds_with(tx, |address, token| self.available_balance(address, token))
+    }
+    
+    fn check_funds_with(&self, tx: &Transaction, balance: impl Fn(&str, &str) -> u64) -> Result<(), TransactionError> {
+        let fee = tx.fee();
         match tx.tx_type {
             TransactionType::Transfer => {
-                let sender_balance = self.get_balance(&tx.sender);
-                let required = tx.total_output();
+                let token = tx.outputs.first().map(|o| o.token_symbol()).unwrap_or(NATIVE_TOKEN);
+                let sender_balance = balance(&tx.sender, token);
+                let mut required = tx.total_output_for(token);
+                if token == NATIVE_TOKEN {
+                    required = required.saturating_add(fee);
+                }
                 if sender_balance < required {
-                    return Err(format!("Insufficient balance: has {}, needs {}", sender_balance, required));
+                    log::debug!("Transfer rejected: {} has {} {}, needs {}", &tx.sender, sender_balance, token, required);
+                    return Err(TransactionError::InsufficientBalance { have: sender_balance, need: required });
+                }
+                let native_balance = balance(&tx.sender, NATIVE_TOKEN);
+                if token != NATIVE_TOKEN && native_balance < fee {
+                    return Err(TransactionError::InsufficientFeeBalance { have: native_balance, need: fee });
                 }
             },
             TransactionType::DataPurchase => {
-                let sender_balance = self.get_balance(&tx.sender);
-                if sender_balance < tx.total_output() {
-                    return Err("Insufficient balance".to_string());
+                let (have, need) = (balance(&tx.sender, NATIVE_TOKEN), tx.total_output().saturating_add(fee));
+                if have < need {
+                    return Err(TransactionError::InsufficientBalance { have, need });
                 }
             },
-            // DataContribution, ContractDeploy, ContractCall, etc. - no balance check needed
             _ => {}
         }
+        Ok(())
+    }
+    
+    /// Next nonce expected from `address`: the account nonce plus its
+    /// transfers already waiting in the pending pool
+    pub fn next_nonce(&self, address: &str) -> u64 {
+        let account_nonce = self.state.accounts.get(address).map(|a| a.nonce).unwrap_or(0);
+        let pending = self.pending_transactions
+            .iter()
+            .filter(|tx| tx.sender == address && Self::requires_nonce(tx))
+            .count() as u64;
+        account_nonce + pending
+    }
+    
+    /// Transfers from user accounts must carry the sender's nonce, as must
+    /// signed typed transactions so their signature can't be replayed
+    fn requires_nonce(tx: &Transaction) -> bool {
+        if tx.sender == "system" {
+            return false;
+        }
+        match tx.tx_type {
+            TransactionType::Transfer => true,
+            TransactionType::Stake | TransactionType::Unstake | TransactionType::CancelUnbonding
+            | TransactionType::DataPurchase
+            | TransactionType::ContractDeploy | TransactionType::ContractCall
+            | TransactionType::SetDeviceOwner | TransactionType::SetDeviceType | TransactionType::ValidatorRegister
+            | TransactionType::ValidatorUnbond | TransactionType::DeviceBond
+            | TransactionType::DeviceUnbond => tx.signature.is_some(),
+            _ => false,
+        }
+    }
+    
+    /// Reject transactions whose nonce is not the sender's next nonce
+    fn check_nonce(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        if !Self::requires_nonce(tx) {
+            return Ok(());
+        }
+        nonce_matches(self.next_nonce(&tx.sender), tx.nonce)
+    }
+    
+    /// Add a transaction from the synthetic load generator.
+    /// Synthetic transactions pass the same validation rules as real ones
+    /// (except the anti-spam PoW) but are only included after real transactions.
+    pub fn add_synthetic_transaction(&mut self, tx: Transaction) -> Result<String, String> {
+        self.validate_transaction_rules(&tx)?;
+        let tx_hash = tx.hash.clone();
+        self.synthetic_transactions.push(tx);
+        Ok(tx_hash)
+    }
+    
+    /// Validate a single transaction (pure function for parallel processing)
+    fn validate_transaction_pure(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        if tx.sender != "system" && !tx.verify_pow(self.state.params.tx_pow_bits) {
+            return Err(TransactionError::InsufficientPow { bits: self.state.params.tx_pow_bits });
+        }
         
+        self.validate_transaction_rules(tx)
+    }
+    
+    /// Hash and balance checks shared by every mempool class
+    fn validate_transaction_rules(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        self.reorg.check()?;
+        
+        // Validate transaction hash
+        if !tx.verify_hash() {
+            return Err(TransactionError::InvalidHash);
+        }
+        check_memo(tx)?;
+        check_data_size(tx, self.state.params.max_tx_data_bytes)?;
+        check_transfer_outputs(tx)?;
+        check_height_deadline(tx, self.total_blocks)?;
+        self.check_reward_claim(tx)?;
+        
+        // Transfers and purchases must cover amount + fee;
+        // DataContribution, ContractDeploy, ContractCall, etc. need no balance check
+        self.check_available_funds(tx)
+    }
+    
+    /// Reject data contributions claiming another base reward than the
+    /// reward policy sets for their payload
+    fn check_reward_claim(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        if tx.tx_type != TransactionType::DataContribution {
+            return Ok(());
+        }
+        let claimed = tx.outputs.first().map(|o| o.amount).unwrap_or(0);
+        let expected = self.state.reward_policy.claim_for(tx.data.as_deref().unwrap_or_default());
+        if claimed != expected {
+            return Err(TransactionError::WrongRewardClaim { claimed, expected });
+        }
         Ok(())
     }
     
-    /// Add multiple transactions in parallel (high-performance batch processing)
+    /// Add multiple signed transactions in parallel (high-performance batch processing)
     /// Returns (successful_count, failed_count, successful_hashes)
     pub fn add_transactions_batch(&mut self, txs: Vec<Transaction>) -> (usize, usize, Vec<String>) {
         let batch_size = txs.len();
@@ -1018,11 +1812,11 @@ impl Blockchain {
             return (0, 0, Vec::new());
         }
         
-        // Phase 1: Parallel validation (CPU-intensive hash verification)
-        let validation_results: Vec<(Transaction, Result<(), String>)> = txs
+        // Phase 1: Parallel validation (CPU-intensive signature and hash verification)
+        let validation_results: Vec<(Transaction, Result<(), TransactionError>)> = txs
             .into_par_iter()
             .map(|tx| {
-                let result = self.validate_transaction_pure(&tx);
+                let result = tx.check_sender_signature().and_then(|_| self.validate_transaction_pure(&tx));
                 (tx, result)
             })
             .collect();
@@ -1033,10 +1827,15 @@ impl Blockchain {
         let mut successful_hashes = Vec::new();
         
         for (tx, result) in validation_results {
-            match result {
+            // Nonces and pending debits depend on earlier insertions, so they are checked sequentially
+            let hash = tx.hash.clone();
+            match result
+                .and_then(|_| self.check_nonce(&tx))
+                .and_then(|_| self.check_available_funds(&tx))
+                .and_then(|_| self.insert_pending(tx))
+            {
                 Ok(()) => {
-                    successful_hashes.push(tx.hash.clone());
-                    self.pending_transactions.push(tx);
+                    successful_hashes.push(hash);
                     successful_count += 1;
                 },
                 Err(e) => {
@@ -1052,347 +1851,1621 @@ impl Blockchain {
         (successful_count, failed_count, successful_hashes)
     }
     
+    /// Apply the network clock offset to block timestamps from now on
+    pub fn set_clock_offset_ms(&mut self, offset_ms: i64) {
+        self.clock_offset_ms = offset_ms;
+    }
+    
+    /// Local time corrected by the network clock offset
+    pub fn adjusted_now(&self) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::milliseconds(self.clock_offset_ms)
+    }
+    
     /// Mine a new block with pending transactions
     pub fn mine_block(&mut self, validator: String) -> Result<Block, String> {
+        self.mine_block_within(validator, MiningBudget::UNLIMITED, &mut ConsensusModules::default())?
+            .ok_or_else(|| "Mining budget exhausted".to_string())
+    }
+    
+    /// Mine a new block, giving up when `budget` runs out. Returns `Ok(None)`
+    /// with state and pending transactions left as they were so the caller can retry.
+    /// The block updates the consensus `modules` that are given.
+    pub fn mine_block_within(
+        &mut self,
+        validator: String,
+        budget: MiningBudget,
+        modules: &mut ConsensusModules,
+    ) -> Result<Option<Block>, String> {
+        let mut template = self.prepare_block(validator, modules);
+        if !template.mine(budget) {
+            warn!("Mining budget ran out for block {} at difficulty {}; retrying later",
+                template.block.index, template.block.header.difficulty);
+            return Ok(None);
+        }
+        self.append_mined_block(template, modules).map(Some)
+    }
+    
+    /// Base difficulty for the block at `height`: the warm-up schedule during
+    /// the chain's first blocks, the chain difficulty afterwards
+    pub fn base_difficulty(&self, height: u64) -> u64 {
+        self.state.params.warmup().difficulty_at(height, self.difficulty)
+    }
+    
+    /// Build the next block on the current state without mining or appending it.
+    /// State, pools and `modules` are put back afterwards, so proof-of-work can
+    /// run without holding the chain; `append_mined_block` then applies the block.
+    pub fn prepare_block(&mut self, validator: String, modules: &mut ConsensusModules) -> BlockTemplate {
         let previous_hash = self.latest_block().hash.clone();
         let index = self.total_blocks;  // Use total_blocks instead of chain.len()
+        let timestamp = self.adjusted_now();
         
-        // Select transactions for the block (max 150 for Phase 1)
-        let transactions: Vec<Transaction> = self.pending_transactions
-            .drain(..self.pending_transactions.len().min(150))
-            .collect();
-        
-        // Create block reward transaction
-        let reward_tx = Transaction::reward(
-            validator.clone(),
-            self.block_reward,
-            format!("Block {} mining reward", index),
-        );
+        // What selection and application change, restored once the block is built
+        let undo = BlockUndo {
+            index,
+            state: self.state.clone(),
+            difficulty: self.difficulty,
+            modules: modules.snapshot(),
+        };
+        let rollback = BlockRollback {
+            synthetic: self.synthetic_transactions.clone(),
+            tx_class_stats: self.tx_class_stats.clone(),
+            pooled: self.pending_transactions.iter().map(|tx| tx.hash.clone()).collect(),
+        };
         
-        let mut block_txs = vec![reward_tx];
-        block_txs.extend(transactions);
+        // Selects and applies the block's transactions, including the reward
+        let block_txs = self.build_block_transactions(index, &validator, timestamp, modules);
         
         // Calculate PoIE adjusted difficulty
-        let data_entropy = Block::calculate_data_entropy(&block_txs);
-        let entropy_bonus = (data_entropy * 0.5) as u64; 
-        let base_difficulty = 2;
-        
-        let adjusted_difficulty = if base_difficulty > entropy_bonus {
-            base_difficulty - entropy_bonus
-        } else {
-            1
-        };
+        let base_difficulty = self.base_difficulty(index);
+        let adjusted_difficulty = poie_difficulty(base_difficulty, &block_txs);
 
-        info!("Mining block {} with PoIE difficulty: {} (Base: {}, Entropy Bonus: {})", 
-            index, adjusted_difficulty, base_difficulty, entropy_bonus);
+        info!("Mining block {} with PoIE difficulty: {} (Base: {})", 
+            index, adjusted_difficulty, base_difficulty);
 
-        // Create and mine the block
         let mut block = Block::new(
             index,
             previous_hash,
             block_txs,
             adjusted_difficulty,
-            validator.clone(),
+            validator,
         );
+        block.header.timestamp = timestamp;
+        block.header.state_root = state_root(&self.state);
         
-        block.mine(adjusted_difficulty);
-        
-        self.last_block_time = Utc::now().timestamp();
-        
-        // Apply block to state
-        self.apply_block(&block)?;
-        
-        // Add block to in-memory chain
-        self.chain.push(block.clone());
-        self.total_blocks += 1;
-        
-        info!("Block {} mined by {} ({} blocks in memory)", 
-              index, &validator[..8.min(validator.len())], self.chain.len());
-        
-        // Persist block to RocksDB and file (dual-write for migration period)
-        self.persist_block(&block);
-        
-        // Save state periodically (every 10 blocks to reduce I/O)
-        if self.total_blocks % 10 == 0 {
-            self.persist_state();
+        // Synthetic transactions are re-signed when selected, so remember them by their pool hash
+        let remaining: HashSet<&str> = self.synthetic_transactions.iter().map(|tx| tx.hash.as_str()).collect();
+        let synthetic = rollback.synthetic
+            .iter()
+            .filter(|tx| !remaining.contains(tx.hash.as_str()))
+            .map(|tx| tx.hash.clone())
+            .collect();
+        let included = TxClassStats {
+            real_included: self.tx_class_stats.real_included - rollback.tx_class_stats.real_included,
+            synthetic_included: self.tx_class_stats.synthetic_included - rollback.tx_class_stats.synthetic_included,
+        };
+        self.roll_back_block(rollback, undo, &block.transactions, modules);
+        BlockTemplate { block, synthetic, included }
+    }
+    
+    /// Append a block from `prepare_block` once it is mined. Its transactions
+    /// are applied again, and it is refused if another block was appended
+    /// while it was being mined.
+    pub fn append_mined_block(&mut self, template: BlockTemplate, modules: &mut ConsensusModules) -> Result<Block, String> {
+        let BlockTemplate { block, synthetic, included } = template;
+        if block.index != self.total_blocks || block.header.previous_hash != self.latest_block().hash {
+            return Err(format!("Stale block #{}: local height {}", block.index, self.total_blocks));
         }
+        self.append_block(&block, modules)?;
+        self.synthetic_transactions.retain(|tx| !synthetic.contains(&tx.hash));
+        self.tx_class_stats.real_included += included.real_included;
+        self.tx_class_stats.synthetic_included += included.synthetic_included;
         
-        // Prune old blocks from memory to prevent OOM
-        self.prune_memory();
+        info!("Block {} mined by {} ({} blocks in memory)", 
+              block.index, &block.validator[..8.min(block.validator.len())], self.chain.len());
         
+        self.persist_appended_block(&block);
         Ok(block)
     }
     
-    /// Apply block transactions to state
-    fn apply_block(&mut self, block: &Block) -> Result<(), String> {
-        for tx in &block.transactions {
-            if let Err(e) = self.apply_transaction(tx) {
-                log::warn!("Transaction {} failed to apply: {} (skipping)", &tx.hash[..8], e);
-                continue;
+    /// Undo a block that was only built: restore state and return its transactions to their pools
+    fn roll_back_block(
+        &mut self,
+        rollback: BlockRollback,
+        undo: BlockUndo,
+        block_txs: &[Transaction],
+        modules: &mut ConsensusModules,
+    ) {
+        self.pending_logs.remove(&undo.index);
+        self.state = undo.state;
+        modules.restore(undo.modules);
+        self.synthetic_transactions = rollback.synthetic;
+        self.tx_class_stats = rollback.tx_class_stats;
+        for tx in block_txs.iter().filter(|tx| rollback.pooled.contains(&tx.hash)) {
+            if let Err(e) = self.pending_transactions.insert(tx.clone()) {
+                warn!("Could not return transaction to the pool: {}", e);
             }
         }
+    }
+    
+    /// Validate a block received from a peer, apply it to state and append it.
+    /// Blocks ahead of the local chain fail with an error starting with
+    /// `BLOCK_AHEAD_ERR` so the caller can trigger a sync instead.
+    /// The block updates the consensus `modules` that are given.
+    pub fn accept_external_block(&mut self, block: Block, modules: &mut ConsensusModules) -> Result<(), String> {
+        self.import_block(&block, modules)?;
+        self.persist_appended_block(&block);
         Ok(())
     }
     
-    /// Apply a single transaction to state
-    fn apply_transaction(&mut self, tx: &Transaction) -> Result<(), String> {
-        match tx.tx_type {
-            TransactionType::Transfer => {
-                self.transfer(&tx.sender, &tx.outputs[0].recipient, tx.outputs[0].amount)?;
-            }
-            TransactionType::DataContribution => {
-                self.process_data_contribution(tx)?;
-            }
-            TransactionType::DataPurchase => {
-                self.process_data_purchase(tx)?;
-            }
-            TransactionType::Reward => {
-                self.process_reward(tx)?;
-            }
-            TransactionType::Stake => {
-                self.process_stake(tx)?;
-            }
-            TransactionType::Unstake => {
-                self.process_unstake(tx)?;
-            }
-            _ => {}
+    /// Validate, apply and append a peer block in memory
+    fn import_block(&mut self, block: &Block, modules: &mut ConsensusModules) -> Result<(), String> {
+        if block.index > self.total_blocks {
+            return Err(format!("{}: local height {}, block #{}",
+                BLOCK_AHEAD_ERR, self.total_blocks, block.index));
         }
-        Ok(())
+        if block.index <= self.finalized_height() {
+            return Err(format!("Block #{} is at or below the finalized height {}", block.index, self.finalized_height()));
+        }
+        if block.index < self.total_blocks {
+            return Err(format!("Stale block #{}: local height {}", block.index, self.total_blocks));
+        }
+        if block.header.previous_hash != self.latest_block().hash {
+            return Err(format!("Block #{} does not extend our latest block", block.index));
+        }
+        if !block.verify() {
+            return Err("Block hash mismatch".to_string());
+        }
+        let latest_allowed = self.adjusted_now() + chrono::Duration::seconds(MAX_FUTURE_BLOCK_SECS);
+        if block.header.timestamp > latest_allowed {
+            return Err(format!("Block #{} timestamp {} is too far in the future",
+                block.index, block.header.timestamp.to_rfc3339()));
+        }
+        if block.header.merkle_root != Block::calculate_merkle_root(&block.transactions) {
+            return Err("Merkle root mismatch".to_string());
+        }
+        let expected_difficulty = poie_difficulty(self.base_difficulty(block.index), &block.transactions);
+        if block.header.difficulty != expected_difficulty {
+            return Err(format!("Wrong difficulty: expected {}, got {}",
+                expected_difficulty, block.header.difficulty));
+        }
+        if !block.hash.starts_with(&"0".repeat(expected_difficulty as usize)) {
+            return Err("Insufficient proof-of-work".to_string());
+        }
+        if let Some(tx) = block.transactions.iter()
+            .find(|tx| !tx.is_system() && (tx.signature.is_none() || tx.sender_public_key.is_none()))
+        {
+            return Err(format!("Unsigned transaction {} in block #{}", &tx.hash[..8.min(tx.hash.len())], block.index));
+        }
+        let verified = verify_transactions(&block.transactions, self.parallel_verify_threshold);
+        if let Some((tx, _)) = block.transactions.iter().zip(verified).find(|(_, ok)| !ok) {
+            return Err(format!("Invalid transaction hash or signature: {}", &tx.hash[..8.min(tx.hash.len())]));
+        }
+        self.check_duplicate_transactions(&block.transactions)?;
+        self.check_block_limits(block)?;
+        if let Some(tx) = block.transactions.iter().find(|tx| tx.is_expired_at(block.index)) {
+            return Err(format!("Block #{} includes expired transaction {}",
+                block.index, &tx.hash[..8.min(tx.hash.len())]));
+        }
+        self.append_block(block, modules)
     }
     
-    /// Transfer tokens between accounts
-    fn transfer(&mut self, from: &str, to: &str, amount: u64) -> Result<(), String> {
-        // Get or create sender account
-        let sender = self.state.accounts.entry(from.to_string())
-            .or_insert_with(|| Account::new(from.to_string()));
-        
-        if sender.balance < amount {
-            return Err("Insufficient balance".to_string());
+    /// Apply a validated block on top of the chain and append it in memory;
+    /// state is left untouched if it fails to apply
+    fn append_block(&mut self, block: &Block, modules: &mut ConsensusModules) -> Result<(), String> {
+        let undo = BlockUndo {
+            index: block.index,
+            state: self.state.clone(),
+            difficulty: self.difficulty,
+            modules: modules.snapshot(),
+        };
+        if let Err(e) = self.apply_block(block, modules).and_then(|_| self.check_state_root(block)) {
+            self.pending_logs.remove(&block.index);
+            self.state = undo.state;
+            modules.restore(undo.modules);
+            return Err(e);
         }
-        sender.balance -= amount;
-        sender.nonce += 1;
         
-        // Get or create recipient account
-        let recipient = self.state.accounts.entry(to.to_string())
-            .or_insert_with(|| Account::new(to.to_string()));
-        recipient.balance += amount;
+        // Drop pool transactions the peer already included
+        let included: std::collections::HashSet<&str> = block.transactions
+            .iter()
+            .map(|tx| tx.hash.as_str())
+            .collect();
+        self.pending_transactions.retain(|tx| !included.contains(tx.hash.as_str()));
+        self.synthetic_transactions.retain(|tx| !included.contains(tx.hash.as_str()));
         
+        self.last_block_time = Utc::now().timestamp();
+        self.chain.push(block.clone());
+        self.total_blocks += 1;
+        self.record_undo(undo);
+        self.retarget_difficulty();
+        // Nodes that only follow peers never select transactions, so expire them here too
+        self.purge_expired_transactions();
         Ok(())
     }
     
-    /// Process data contribution (PoIE reward)
-    fn process_data_contribution(&mut self, tx: &Transaction) -> Result<(), String> {
-        let device = &tx.sender;
-        let reward = tx.outputs.get(0).map(|o| o.amount).unwrap_or(0);
-        
-        // Get or create device account
-        let account = self.state.accounts.entry(device.to_string())
-            .or_insert_with(|| Account::new(device.to_string()));
-        
-        account.balance += reward;
-        account.data_contributions += 1;
-        account.reputation_score = (account.reputation_score + 0.1).min(100.0);
-        
-        // Register data if hash provided
-        if let Some(output) = tx.outputs.get(0) {
-            if let Some(data_hash) = &output.data_hash {
-                let quality = tx.data_quality.as_ref()
-                    .map(|q| q.overall_score)
-                    .unwrap_or(0.5);
-                
-                self.state.data_registry.insert(data_hash.clone(), DataEntry {
-                    hash: data_hash.clone(),
-                    owner: device.to_string(),
-                    price: 10,
-                    quality_score: quality,
-                    timestamp: Utc::now().timestamp(),
-                    purchases: 0,
-                    category: "IoT".to_string(),
-                });
+    /// Persist a newly appended block and prune memory
+    fn persist_appended_block(&mut self, block: &Block) {
+        // Persist block to RocksDB and file (dual-write for migration period)
+        self.persist_block(block);
+        if let Some(logs) = self.pending_logs.remove(&block.index) {
+            if let Err(e) = self.block_store.put_contract_logs(block.index, &logs) {
+                error!("Failed to write contract logs to {} store: {}", self.block_store.name(), e);
             }
         }
+        self.events.publish(ChainEvent::from(block));
         
-        self.state.total_supply += reward;
+        // Save state periodically to reduce I/O, and after every retarget so
+        // a restart never picks up a stale difficulty
+        if self.total_blocks.is_multiple_of(STATE_SAVE_INTERVAL) || self.is_retarget_height() {
+            self.persist_state();
+        }
         
-        Ok(())
+        // Prune old blocks from memory to prevent OOM
+        self.prune_memory();
     }
     
-    /// Process reward transaction
-    fn process_reward(&mut self, tx: &Transaction) -> Result<(), String> {
-        for output in &tx.outputs {
-            let account = self.state.accounts.entry(output.recipient.clone())
-                .or_insert_with(|| Account::new(output.recipient.clone()));
-            account.balance += output.amount;
-        }
-        self.state.total_supply += tx.total_output();
-        Ok(())
+    /// Whether the chain difficulty is retargeted after the current block
+    fn is_retarget_height(&self) -> bool {
+        let interval = self.state.params.retarget_interval;
+        interval > 0 && self.total_blocks.is_multiple_of(interval) && self.total_blocks > interval + 1
     }
     
-    /// Process data purchase
-    fn process_data_purchase(&mut self, tx: &Transaction) -> Result<(), String> {
-        let buyer = &tx.sender;
-        let amount = tx.total_output();
+    /// After every `retarget_interval` blocks, adjust the chain difficulty
+    /// from the header timestamps of the last interval. Genesis is left out
+    /// of the window since its timestamp is fixed.
+    fn retarget_difficulty(&mut self) {
+        if !self.is_retarget_height() {
+            return;
+        }
+        let retarget = self.state.params.retarget();
+        let interval = retarget.interval;
+        let latest = self.total_blocks - 1;
+        let (Some(first), Some(last)) = (self.get_block(latest - interval), self.get_block(latest)) else {
+            return;
+        };
+        let elapsed = (last.header.timestamp - first.header.timestamp).num_milliseconds().max(0);
+        let avg_block_secs = elapsed as f64 / 1_000.0 / interval as f64;
+        let next = retarget.next_difficulty(self.difficulty, avg_block_secs);
+        if next != self.difficulty {
+            info!("Difficulty retarget at height {}: {} -> {} (avg block time {:.1}s, target {}s)",
+                self.total_blocks, self.difficulty, next, avg_block_secs, retarget.target_block_secs);
+            self.difficulty = next;
+        }
+    }
+    
+    /// Remember the pre-block state of the block just appended, for the last
+    /// `MAX_REORG_DEPTH` blocks. `finality_depth` can't exceed that, so
+    /// every block a reorg may replace can be rolled back.
+    fn record_undo(&mut self, undo: BlockUndo) {
+        self.undo_log.push_back(undo);
+        while self.undo_log.len() as u64 > MAX_REORG_DEPTH {
+            self.undo_log.pop_front();
+        }
+    }
+    
+    /// Switch to a competing branch of consecutive blocks. Leading blocks we
+    /// already have are skipped; the rest must fork above the finalized
+    /// height and carry more cumulative proof-of-work than
+    /// the blocks they replace. State is rolled back to the fork point and the
+    /// branch applied; if any block fails, the chain is left as it was.
+    /// Returns the blocks that were replaced.
+    pub fn reorg_to(&mut self, branch: &[Block], modules: &mut ConsensusModules) -> Result<Vec<Block>, String> {
+        if branch.windows(2).any(|w| w[1].index != w[0].index + 1 || w[1].header.previous_hash != w[0].hash) {
+            return Err("Branch blocks are not consecutive".to_string());
+        }
+        let common = branch.iter()
+            .take_while(|block| self.get_block(block.index).is_some_and(|ours| ours.hash == block.hash))
+            .count();
+        let branch = &branch[common..];
+        let first = branch.first().ok_or("Branch adds nothing to the local chain")?;
+        if first.index >= self.total_blocks {
+            return Err(format!("Block #{} does not fork from the local chain", first.index));
+        }
+        let depth = self.total_blocks - first.index;
+        if first.index <= self.finalized_height() {
+            return Err(format!("Reorg of {} blocks would replace finalized block #{} (finalized height {})",
+                depth, first.index, self.finalized_height()));
+        }
+        let parent = first.index.checked_sub(1).and_then(|index| self.get_block(index))
+            .ok_or_else(|| format!("Fork point #{} is not in memory", first.index.saturating_sub(1)))?;
+        if parent.hash != first.header.previous_hash {
+            return Err(format!("Block #{} does not fork from the local chain", first.index));
+        }
+        let undo_pos = self.undo_log.iter().position(|undo| undo.index == first.index)
+            .ok_or_else(|| format!("No undo data for block #{}", first.index))?;
+        let chain_pos = self.chain.len() - depth as usize;
+        let ours = chain_work(&self.chain[chain_pos..]);
+        let theirs = chain_work(branch);
+        if theirs <= ours {
+            return Err(format!("Branch work {} does not exceed local work {}", theirs, ours));
+        }
         
-        // Deduct from buyer
-        let buyer_account = self.state.accounts.get_mut(buyer)
-            .ok_or("Buyer account not found")?;
+        warn!("Reorg: replacing {} blocks from #{} with a {}-block branch", depth, first.index, branch.len());
+        self.begin_reorg();
+        let replaced = self.chain.split_off(chain_pos);
+        let replaced_undo = self.undo_log.split_off(undo_pos);
+        let tip_state = std::mem::replace(&mut self.state, replaced_undo[0].state.clone());
+        let tip_difficulty = std::mem::replace(&mut self.difficulty, replaced_undo[0].difficulty);
+        let tip_modules = modules.snapshot();
+        modules.restore(replaced_undo[0].modules.clone());
+        // Importing prunes the pools of what the branch includes or lets expire
+        let tip_pending = self.pending_transactions.clone();
+        let tip_synthetic = self.synthetic_transactions.clone();
+        let tip_height = self.total_blocks;
+        self.total_blocks = first.index;
         
-        if buyer_account.balance < amount {
-            return Err("Insufficient balance".to_string());
+        for block in branch {
+            if let Err(e) = self.import_block(block, modules) {
+                // Put the local chain back exactly as it was
+                self.chain.truncate(chain_pos);
+                self.chain.extend(replaced);
+                self.undo_log.truncate(undo_pos);
+                self.undo_log.extend(replaced_undo);
+                // Logs of the replaced blocks stay in the block store; drop the branch's
+                self.pending_logs.split_off(&first.index);
+                self.state = tip_state;
+                self.difficulty = tip_difficulty;
+                modules.restore(tip_modules);
+                self.pending_transactions = tip_pending;
+                self.synthetic_transactions = tip_synthetic;
+                self.total_blocks = tip_height;
+                self.end_reorg();
+                return Err(format!("Reorg aborted at block #{}: {}", block.index, e));
+            }
         }
-        buyer_account.balance -= amount;
         
-        // Pay seller
-        for output in &tx.outputs {
-            let seller_account = self.state.accounts.entry(output.recipient.clone())
-                .or_insert_with(|| Account::new(output.recipient.clone()));
-            seller_account.balance += output.amount;
-            
-            // Update data entry if exists
-            if let Some(data_hash) = &output.data_hash {
-                if let Some(entry) = self.state.data_registry.get_mut(data_hash) {
-                    entry.purchases += 1;
-                }
+        if let Err(e) = self.block_store.truncate(first.index) {
+            error!("Failed to truncate {} store for reorg: {}", self.block_store.name(), e);
+        }
+        // Replaced transactions must not resolve to the blocks they were in
+        if let Some(ref storage) = self.storage {
+            if let Err(e) = storage.remove_blocks_from(first.index) {
+                error!("Failed to remove replaced blocks from RocksDB: {}", e);
             }
         }
+        for block in branch {
+            self.persist_appended_block(block);
+        }
+        // The saved state and undo data may be from a replaced block
+        self.persist_state();
         
-        Ok(())
+        // User transactions only the replaced blocks included go back to the pool
+        let included: HashSet<&str> = branch.iter()
+            .flat_map(|block| block.transactions.iter().map(|tx| tx.hash.as_str()))
+            .collect();
+        for tx in replaced.iter().flat_map(|block| &block.transactions) {
+            if tx.sender != "system" && !included.contains(tx.hash.as_str()) {
+                let _ = self.pending_transactions.insert(tx.clone());
+            }
+        }
+        self.end_reorg();
+        info!("Reorg complete: now at height {}", self.total_blocks);
+        Ok(replaced)
     }
     
-    /// Process stake
-    fn process_stake(&mut self, tx: &Transaction) -> Result<(), String> {
-        let amount = tx.outputs[0].amount;
-        
-        let account = self.state.accounts.get_mut(&tx.sender)
-            .ok_or("Account not found")?;
-        
-        if account.balance < amount {
-            return Err("Insufficient balance for staking".to_string());
+    /// Select and apply the transactions of block `index`, stamped `timestamp`.
+    /// User transactions are applied first so the reward transaction can pay
+    /// the validator exactly the fees that were collected.
+    fn build_block_transactions(
+        &mut self,
+        index: u64,
+        validator: &str,
+        timestamp: DateTime<Utc>,
+        modules: &mut ConsensusModules,
+    ) -> Vec<Transaction> {
+        let mut transactions = self.select_transactions_for_block();
+        let verified = verify_transactions(&transactions, self.parallel_verify_threshold);
+        let mut verified = verified.into_iter();
+        transactions.retain(|tx| {
+            let ok = verified.next().unwrap_or(false);
+            if !ok {
+                warn!("Dropping transaction {} with an invalid signature", &tx.hash[..8.min(tx.hash.len())]);
+            }
+            ok
+        });
+        // Peers reject blocks with a transaction that fails, so leave those out
+        let mut fees = 0u64;
+        let mut included = Vec::with_capacity(transactions.len());
+        for tx in transactions {
+            match self.apply_transactions(index, std::slice::from_ref(&tx), modules.staking.as_deref_mut()) {
+                Ok(fee) => {
+                    fees += fee;
+                    included.push(tx);
+                }
+                Err(e) => warn!("Dropping transaction from block {}: {}", index, e),
+            }
         }
         
-        account.balance -= amount;
-        account.staked_amount += amount;
-        self.state.total_staked += amount;
+        let reward_tx = Transaction::block_reward(
+            validator.to_string(),
+            self.state.params.block_reward,
+            fees,
+            self.state.params.treasury_share,
+            format!("Block {} mining reward", index),
+        );
+        if let Err(e) = self.apply_transactions(index, std::slice::from_ref(&reward_tx), modules.staking.as_deref_mut()) {
+            error!("Block {} reward failed to apply: {}", index, e);
+        }
+        self.finish_block(index, timestamp, validator, modules);
         
-        Ok(())
+        let mut block_txs = vec![reward_tx];
+        block_txs.extend(included);
+        block_txs
+    }
+    
+    /// Drop pool transactions that can no longer be included because the
+    /// next block (at `total_blocks`) is past their height deadline.
+    /// Returns how many were dropped.
+    pub fn purge_expired_transactions(&mut self) -> usize {
+        let height = self.total_blocks;
+        let before = self.pending_transactions.len();
+        self.pending_transactions.retain(|tx| !tx.is_expired_at(height));
+        let dropped = before - self.pending_transactions.len();
+        if dropped > 0 {
+            info!("Dropped {} transactions past their height deadline", dropped);
+        }
+        dropped
     }
     
-    /// Process unstake
-    fn process_unstake(&mut self, tx: &Transaction) -> Result<(), String> {
-        let amount = tx.outputs[0].amount;
+    /// Select transactions for the next block: real transactions first,
+    /// synthetic ones fill the remaining capacity. Selection stops at either
+    /// block limit; the rest stays pending.
+    fn select_transactions_for_block(&mut self) -> Vec<Transaction> {
+        self.purge_expired_transactions();
         
-        let account = self.state.accounts.get_mut(&tx.sender)
-            .ok_or("Account not found")?;
+        let max_txs = self.state.params.max_txs_per_block;
+        let max_bytes = self.state.params.max_block_bytes;
+        let mut transactions = self.pending_transactions.collect_pending_within(max_txs, max_bytes);
+        let real_count = transactions.len();
         
-        if account.staked_amount < amount {
-            return Err("Insufficient staked amount".to_string());
+        let mut bytes: usize = transactions.iter().map(|tx| tx.serialized_size()).sum();
+        let synthetic_count = self.synthetic_transactions
+            .iter()
+            .take(max_txs - real_count)
+            .take_while(|tx| {
+                bytes = bytes.saturating_add(tx.serialized_size());
+                bytes <= max_bytes
+            })
+            .count();
+        let synthetic: Vec<Transaction> = self.synthetic_transactions.drain(..synthetic_count).collect();
+        
+        // Synthetic transfers carry no client nonce; sequence them after the
+        // real ones, then sign with the simulated device's key
+        let mut next_nonces: HashMap<String, u64> = HashMap::new();
+        for tx in transactions.iter().filter(|tx| Self::requires_nonce(tx)) {
+            next_nonces.insert(tx.sender.clone(), tx.nonce + 1);
+        }
+        for tx in synthetic {
+            let tx = if Self::requires_nonce(&tx) {
+                let accounts = &self.state.accounts;
+                let nonce = next_nonces.entry(tx.sender.clone())
+                    .or_insert_with(|| accounts.get(&tx.sender).map(|a| a.nonce).unwrap_or(0));
+                let tx = tx.with_nonce(*nonce);
+                *nonce += 1;
+                tx
+            } else {
+                tx
+            };
+            match simulated_device_wallet(&tx.sender) {
+                Some(wallet) => transactions.push(tx.signed_by(wallet)),
+                None => transactions.push(tx),
+            }
         }
         
-        account.staked_amount -= amount;
-        account.balance += amount;
-        self.state.total_staked -= amount;
+        // Never mine a transaction twice, within the block or across recent blocks
+        let recent = self.recent_tx_hashes();
+        let mut seen = HashSet::with_capacity(transactions.len());
+        let before = transactions.len();
+        transactions.retain(|tx| !recent.contains_key(tx.hash.as_str()) && seen.insert(tx.hash.clone()));
+        if transactions.len() < before {
+            warn!("Dropped {} duplicate or already mined transactions from the block", before - transactions.len());
+        }
         
+        self.tx_class_stats.real_included += real_count as u64;
+        self.tx_class_stats.synthetic_included += synthetic_count as u64;
+        transactions
+    }
+    
+    /// Hashes of transactions in the last `DUPLICATE_TX_WINDOW` blocks, with
+    /// the height of the block that included each
+    fn recent_tx_hashes(&self) -> HashMap<&str, u64> {
+        self.chain
+            .iter()
+            .rev()
+            .take(DUPLICATE_TX_WINDOW)
+            .flat_map(|block| block.transactions.iter().map(move |tx| (tx.hash.as_str(), block.index)))
+            .collect()
+    }
+    
+    /// Reject a block listing the same transaction twice or replaying one
+    /// included in a recent ancestor
+    fn check_duplicate_transactions(&self, txs: &[Transaction]) -> Result<(), String> {
+        let recent = self.recent_tx_hashes();
+        let mut seen = HashSet::with_capacity(txs.len());
+        for tx in txs {
+            let short = &tx.hash[..8.min(tx.hash.len())];
+            if !seen.insert(tx.hash.as_str()) {
+                return Err(format!("Duplicate transaction {} in block", short));
+            }
+            if let Some(height) = recent.get(tx.hash.as_str()) {
+                return Err(format!("Transaction {} already included in block #{}", short, height));
+            }
+        }
         Ok(())
     }
     
-    /// Get account state
-    pub fn get_account(&self, address: &str) -> Option<&Account> {
-        self.state.accounts.get(address)
+    /// Reject a block whose header commits to another state than applying
+    /// it produced locally. Only the genesis block may omit its root.
+    fn check_state_root(&self, block: &Block) -> Result<(), String> {
+        let claimed = &block.header.state_root;
+        if claimed.is_empty() {
+            if block.index == 0 {
+                return Ok(());
+            }
+            return Err(format!("Block #{} has no state root", block.index));
+        }
+        let local_root = state_root(&self.state);
+        if local_root != *claimed {
+            return Err(format!("State root mismatch at block #{}: header {}, local {}",
+                block.index, claimed.get(..8).unwrap_or(claimed), &local_root[..8]));
+        }
+        Ok(())
     }
     
-    /// Get account balance (read-only)
-    pub fn get_balance(&self, address: &str) -> u64 {
-        self.state.accounts.get(address).map(|a| a.balance).unwrap_or(0)
+    /// Reject a block over the transaction count or size limits a miner
+    /// applies when selecting transactions (the reward is not counted)
+    fn check_block_limits(&self, block: &Block) -> Result<(), String> {
+        let limits = self.state.params.limits();
+        let txs = block.transactions.get(1..).unwrap_or_default();
+        if txs.len() > limits.max_txs_per_block {
+            return Err(format!("Block #{} has {} transactions (max {})",
+                block.index, txs.len(), limits.max_txs_per_block));
+        }
+        let bytes: usize = txs.iter().map(|tx| tx.serialized_size()).sum();
+        if bytes > limits.max_block_bytes {
+            return Err(format!("Block #{} transactions take {} bytes (max {})",
+                block.index, bytes, limits.max_block_bytes));
+        }
+        for tx in &block.transactions {
+            check_data_size(tx, limits.max_tx_data_bytes).map_err(|e| e.to_string())?;
+        }
+        Ok(())
     }
     
-    /// Get transactions for an address (only from in-memory blocks)
-    pub fn get_transactions_for_address(&self, address: &str) -> Vec<&Transaction> {
-        let mut txs = Vec::new();
+    /// Apply block transactions to state. A block starts with exactly one
+    /// reward transaction paying its validator the block reward plus the
+    /// fees of the other transactions, minus the treasury's share; every
+    /// other transaction must apply. On error the state is partly applied
+    /// and the caller restores it.
+    fn apply_block(&mut self, block: &Block, modules: &mut ConsensusModules) -> Result<(), String> {
+        let (reward, txs) = block.transactions.split_first()
+            .ok_or_else(|| format!("Block #{} has no reward transaction", block.index))?;
+        if reward.tx_type != TransactionType::Reward || reward.sender != "system" {
+            return Err(format!("Block #{} does not start with a reward transaction", block.index));
+        }
+        if let Some(tx) = txs.iter().find(|tx| tx.is_system() || tx.sender == "system") {
+            return Err(format!("Unexpected system transaction {} in block #{}",
+                &tx.hash[..8.min(tx.hash.len())], block.index));
+        }
         
-        for block in &self.chain {
-            for tx in &block.transactions {
-                if tx.sender == address {
-                    txs.push(tx);
-                    continue;
-                }
-                
-                for output in &tx.outputs {
-                    if output.recipient == address {
-                        txs.push(tx);
-                        break;
-                    }
+        // User transactions first, as the miner applied them
+        let fees = self.apply_transactions(block.index, txs, modules.staking.as_deref_mut())?;
+        let expected = Transaction::block_reward(
+            block.validator.clone(),
+            self.state.params.block_reward,
+            fees,
+            self.state.params.treasury_share,
+            String::new(),
+        );
+        if reward.outputs != expected.outputs {
+            return Err(format!("Block #{} reward does not pay {} the block reward plus {} in fees",
+                block.index, block.validator, fees));
+        }
+        self.apply_transactions(block.index, std::slice::from_ref(reward), modules.staking.as_deref_mut())?;
+        self.finish_block(block.index, block.header.timestamp, &block.validator, modules);
+        Ok(())
+    }
+    
+    /// Work every block does after its transactions: record which validator
+    /// signed it, accrue the block reward to the staking reward pool, credit
+    /// the stake and device bonds released at `index`, execute due governance
+    /// proposals and, every `DOWNTIME_CHECK_INTERVAL` blocks, evaluate
+    /// validator downtime
+    fn finish_block(&mut self, index: u64, timestamp: DateTime<Utc>, producer: &str, modules: &mut ConsensusModules) {
+        if let Some(staking) = modules.staking.as_deref_mut() {
+            staking.record_block(index, producer);
+            staking.accrue_block_reward(self.state.params.block_reward);
+        }
+        self.release_unbonding(index, modules.staking.as_deref_mut());
+        self.release_device_bonds(index);
+        self.apply_governance(timestamp, modules);
+        if index > 0 && index.is_multiple_of(DOWNTIME_CHECK_INTERVAL) {
+            self.apply_downtime(index, timestamp, modules);
+        }
+    }
+    
+    /// Credit back every unbonding entry released at or before `height`
+    fn release_unbonding(&mut self, height: u64, mut staking: Option<&mut StakingManager>) {
+        let mut released: Vec<(String, u64)> = Vec::new();
+        self.state.unbonding.retain(|address, pending| {
+            pending.retain(|unbond| {
+                if unbond.release_height > height {
+                    return true;
                 }
+                released.push((address.clone(), unbond.amount));
+                false
+            });
+            !pending.is_empty()
+        });
+        // Stable, so each account's entries stay oldest first
+        released.sort_by(|a, b| a.0.cmp(&b.0));
+        for (address, amount) in released {
+            self.state.accounts.entry(address.clone())
+                .or_insert_with(|| Account::new(address.clone()))
+                .balance += amount;
+            if let Some(staking) = staking.as_deref_mut() {
+                staking.complete_unbonding(&address, amount);
             }
+            info!("Unbonding completed: {} EDGE returned to {}", amount, &address[..8.min(address.len())]);
         }
-        
-        txs
     }
     
-    /// Get blockchain stats with PoIE network metrics
-    pub fn get_stats(&self) -> ChainStats {
-        let height = self.total_blocks;
-        let total_transactions: u64 = self.chain.iter().map(|b| b.transactions.len() as u64).sum();
-        
-        // Estimate total transactions based on average
-        let avg_tx_per_block = if !self.chain.is_empty() {
-            total_transactions as f64 / self.chain.len() as f64
-        } else {
-            0.0
+    /// Evaluate validator downtime at block `index`, as of the block's
+    /// `timestamp`, and burn the delegated stake the resulting slashes take
+    fn apply_downtime(&mut self, index: u64, timestamp: DateTime<Utc>, modules: &mut ConsensusModules) {
+        let Some(staking) = modules.staking.as_deref_mut() else {
+            return;
         };
-        let estimated_total_tx = (avg_tx_per_block * height as f64) as u64;
-        
-        // Calculate network entropy from in-memory blocks
-        let network_entropy: f64 = self.chain.iter()
-            .map(|b| b.header.data_entropy)
-            .sum();
-        
-        // Calculate data throughput
-        let data_throughput = if height > 0 {
-            (estimated_total_tx as f64 * 256.0) / (height as f64 * 10.0)
-        } else {
-            0.0
+        let recorded = staking.slash_history.len();
+        let jailed = staking.check_downtime(index, timestamp);
+        if !jailed.is_empty() {
+            info!("Jailed {} validators for downtime", jailed.len());
+        }
+        let unjailed = staking.process_auto_unjail(timestamp);
+        if !unjailed.is_empty() {
+            info!("Automatically unjailed {} validators after downtime", unjailed.len());
+        }
+        for event in &staking.slash_history[recorded..] {
+            self.apply_slash(event);
+        }
+    }
+    
+    /// Burn the stake a slash took from the validator and each delegator
+    fn apply_slash(&mut self, event: &SlashEvent) {
+        let delegated: u64 = event.delegator_losses.values().sum();
+        if let Some(bond) = self.state.validator_bonds.get_mut(&event.validator) {
+            let burned = event.amount.saturating_sub(delegated).min(bond.self_stake);
+            bond.self_stake -= burned;
+            self.state.total_staked -= burned;
+            self.state.total_supply -= burned;
+        }
+        for (delegator, loss) in &event.delegator_losses {
+            let Some(account) = self.state.accounts.get_mut(delegator) else {
+                continue;
+            };
+            let burned = (*loss).min(account.staked_amount);
+            account.staked_amount -= burned;
+            self.state.total_staked -= burned;
+            self.state.total_supply -= burned;
+        }
+    }
+    
+    /// Finalize and execute the governance proposals due at a block stamped
+    /// `timestamp`. Needs both the governance and the staking module.
+    fn apply_governance(&mut self, timestamp: DateTime<Utc>, modules: &mut ConsensusModules) {
+        let (Some(governance), Some(staking)) = (modules.governance.as_deref_mut(), modules.staking.as_deref_mut()) else {
+            return;
         };
+        let now = timestamp.timestamp().max(0) as u64;
+        governance.finalize_due(now, &*staking);
+        let executed = governance.execute_due(now, &mut ProtocolExecutor { chain: self, staking });
+        if !executed.is_empty() {
+            info!("Executed governance proposals {:?}", executed);
+        }
+    }
+    
+    /// Apply transactions included in block `block_index`, failing on the
+    /// first one that doesn't apply. Returns the total fees collected.
+    fn apply_transactions(
+        &mut self,
+        block_index: u64,
+        txs: &[Transaction],
+        mut staking: Option<&mut StakingManager>,
+    ) -> Result<u64, String> {
+        let mut fees = 0u64;
+        for tx in txs {
+            self.tracer.record(tx, TxStage::Included { block: block_index });
+            if let Err(e) = self.apply_transaction(tx, staking.as_deref_mut()) {
+                self.tracer.record(tx, TxStage::Receipt { success: false, error: Some(e.clone()) });
+                return Err(format!("Transaction {} failed to apply: {}", &tx.hash[..8.min(tx.hash.len())], e));
+            }
+            fees += tx.fee();
+            self.tracer.record(tx, TxStage::Applied);
+            self.tracer.record(tx, TxStage::Receipt { success: true, error: None });
+        }
+        Ok(fees)
+    }
+    
+    /// Apply a single transaction to state. Stake and Unstake transactions
+    /// delegate and undelegate through `staking` when one is given.
+    pub(crate) fn apply_transaction(
+        &mut self,
+        tx: &Transaction,
+        staking: Option<&mut StakingManager>,
+    ) -> Result<(), String> {
+        let requires_nonce = Self::requires_nonce(tx);
+        if requires_nonce {
+            let account_nonce = self.state.accounts.get(&tx.sender).map(|a| a.nonce).unwrap_or(0);
+            nonce_matches(account_nonce, tx.nonce)?;
+        }
+        match tx.tx_type {
+            TransactionType::Transfer => {
+                check_transfer_outputs(tx)?;
+                // Funds are checked against the sum of all outputs, so either every
+                // recipient is paid or none is
+                self.check_funds(tx)?;
+                self.transfer_outputs(tx)?;
+                self.charge_fee(tx);
+            }
+            TransactionType::DataContribution => {
+                self.process_data_contribution(tx)?;
+            }
+            TransactionType::DataPurchase => {
+                self.process_data_purchase(tx)?;
+                self.charge_fee(tx);
+            }
+            TransactionType::Reward => {
+                self.process_reward(tx)?;
+            }
+            TransactionType::Stake => {
+                self.process_stake(tx, staking)?;
+            }
+            TransactionType::Unstake => {
+                self.process_unstake(tx, staking)?;
+            }
+            TransactionType::CancelUnbonding => {
+                self.process_cancel_unbonding(tx, staking)?;
+            }
+            TransactionType::SetDeviceOwner => {
+                self.process_set_device_owner(tx)?;
+            }
+            TransactionType::SetDeviceType => {
+                self.process_set_device_type(tx)?;
+            }
+            TransactionType::ValidatorRegister => {
+                self.process_validator_register(tx, staking)?;
+            }
+            TransactionType::ValidatorUnbond => {
+                self.process_validator_unbond(tx, staking)?;
+            }
+            TransactionType::DeviceBond => {
+                self.process_device_bond(tx)?;
+            }
+            TransactionType::DeviceUnbond => {
+                self.process_device_unbond(tx)?;
+            }
+            TransactionType::ContractDeploy | TransactionType::ContractCall => {
+                self.apply_contract(tx);
+            }
+            _ => {}
+        }
+        // Transfers advance the nonce as they move funds
+        if requires_nonce && tx.tx_type != TransactionType::Transfer {
+            self.state.accounts.entry(tx.sender.clone())
+                .or_insert_with(|| Account::new(tx.sender.clone()))
+                .nonce += 1;
+        }
+        Ok(())
+    }
+    
+    /// Run a contract deploy or call against `state.contracts` and keep the
+    /// logs it emits. A deploy or call that fails (bad payload, trap, out of
+    /// gas) leaves contract storage as it was; the transaction still applies.
+    fn apply_contract(&mut self, tx: &Transaction) {
+        let height = self.total_blocks;
+        match self.wasm.apply_transaction(&mut self.state.contracts, tx, height) {
+            Ok(Some(result)) if !result.logs.is_empty() => {
+                self.pending_logs.entry(height).or_default().extend(result.logs.iter().map(|log| {
+                    (log.contract.clone(), ContractLog::from_wasm(log, height, tx.timestamp))
+                }));
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Contract transaction {} failed: {}", &tx.hash[..8.min(tx.hash.len())], e),
+        }
+    }
+    
+    /// Logs the contract at `address` emitted in applied blocks, oldest
+    /// first, optionally limited to one event name and an inclusive block range
+    pub fn contract_logs(&self, address: &str, event: Option<&str>, from: Option<u64>, to: Option<u64>) -> Vec<ContractLog> {
+        let (from, to) = (from.unwrap_or(0), to.unwrap_or(u64::MAX));
+        if from > to {
+            return Vec::new();
+        }
+        let mut logs = self.block_store.contract_logs(address, from, to).unwrap_or_else(|e| {
+            warn!("Failed to read contract logs from {} store: {}", self.block_store.name(), e);
+            Vec::new()
+        });
+        logs.extend(self.pending_logs.range(from..=to)
+            .flat_map(|(_, logs)| logs)
+            .filter(|(contract, _)| contract == address)
+            .map(|(_, log)| log.clone()));
+        logs.retain(|log| event.is_none_or(|event| log.event == event));
+        logs
+    }
+    
+    /// Deduct the transaction fee from the sender. The fee leaves circulation
+    /// here and is re-issued to the validator by the block reward transaction.
+    /// Callers must have checked the sender can cover it.
+    fn charge_fee(&mut self, tx: &Transaction) {
+        let fee = tx.fee();
+        if fee == 0 {
+            return;
+        }
+        if let Some(account) = self.state.accounts.get_mut(&tx.sender) {
+            account.balance -= fee;
+            self.state.total_supply -= fee;
+        }
+    }
+    
+    /// Transfer native tokens between accounts
+    fn transfer(&mut self, from: &str, to: &str, amount: u64) -> Result<(), String> {
+        self.transfer_token(from, to, amount, NATIVE_TOKEN)
+    }
+    
+    /// Transfer `amount` of `token` between accounts
+    fn transfer_token(&mut self, from: &str, to: &str, amount: u64, token: &str) -> Result<(), String> {
+        // Get or create sender account
+        let sender = self.state.accounts.entry(from.to_string())
+            .or_insert_with(|| Account::new(from.to_string()));
         
-        // Calculate TPS
-        let tps = if height > 0 {
-            estimated_total_tx as f64 / (height as f64 * 10.0)
-        } else {
-            0.0
-        };
+        sender.debit(token, amount)?;
+        sender.nonce += 1;
         
-        // Calculate validator power index
-        let validator_power = {
-            let active = self.state.accounts.len() as f64;
-            let data = self.state.data_registry.len() as f64;
-            let entropy_factor = network_entropy / (self.chain.len().max(1) as f64);
-            (active * 0.3 + data * 0.3 + entropy_factor * 100.0 * 0.4).max(0.0)
-        };
+        // Get or create recipient account
+        let recipient = self.state.accounts.entry(to.to_string())
+            .or_insert_with(|| Account::new(to.to_string()));
+        recipient.credit(token, amount);
         
-        ChainStats {
-            height,
-            total_transactions: estimated_total_tx,
-            total_supply: self.state.total_supply,
-            total_staked: self.state.total_staked,
-            active_accounts: self.state.accounts.len() as u64,
-            data_entries: self.state.data_registry.len() as u64,
-            difficulty: self.difficulty,
-            last_block_time: self.last_block_time,
-            network_entropy,
-            avg_tx_per_block,
-            data_throughput,
-            tps,
-            validator_power,
+        Ok(())
+    }
+    
+    /// Debit the sum of a transfer's outputs from the sender once, then credit each recipient
+    fn transfer_outputs(&mut self, tx: &Transaction) -> Result<(), String> {
+        let token = tx.outputs.first().map(|o| o.token_symbol()).unwrap_or(NATIVE_TOKEN);
+        let sender = self.state.accounts.entry(tx.sender.clone())
+            .or_insert_with(|| Account::new(tx.sender.clone()));
+        sender.debit(token, tx.total_output_for(token))?;
+        sender.nonce += 1;
+        
+        for output in &tx.outputs {
+            let recipient = self.state.accounts.entry(output.recipient.clone())
+                .or_insert_with(|| Account::new(output.recipient.clone()));
+            recipient.credit(token, output.amount);
         }
+        Ok(())
     }
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ChainStats {
-    pub height: u64,
-    pub total_transactions: u64,
-    pub total_supply: u64,
-    pub total_staked: u64,
-    pub active_accounts: u64,
+    
+    /// Native tokens held by the treasury
+    pub fn treasury_balance(&self) -> u64 {
+        self.state.treasury
+    }
+    
+    /// Pay `amount` from the treasury to `recipient` (executed governance spends)
+    pub fn spend_treasury(&mut self, recipient: &str, amount: u64) -> Result<(), String> {
+        if self.state.treasury < amount {
+            return Err(format!("Treasury underfunded: has {}, needs {}", self.state.treasury, amount));
+        }
+        self.state.treasury -= amount;
+        self.state.accounts.entry(recipient.to_string())
+            .or_insert_with(|| Account::new(recipient.to_string()))
+            .credit(NATIVE_TOKEN, amount);
+        info!("Treasury paid {} to {}", amount, recipient);
+        Ok(())
+    }
+    
+    /// Set or clear the owner credited a share of a device's contribution rewards.
+    /// Outside tests this only happens through a `SetDeviceOwner` transaction.
+    pub fn set_device_owner(&mut self, device: &str, owner: Option<String>) {
+        match owner {
+            Some(owner) => self.state.device_owners.insert(device.to_string(), owner),
+            None => self.state.device_owners.remove(device),
+        };
+    }
+    
+    /// Apply a `SetDeviceOwner` transaction; an empty owner clears it
+    fn process_set_device_owner(&mut self, tx: &Transaction) -> Result<(), String> {
+        if tx.signature.is_none() {
+            return Err("Device owner must be set by a transaction signed by the device".to_string());
+        }
+        let owner = tx.outputs.first().map(|o| o.recipient.clone()).unwrap_or_default();
+        self.set_device_owner(&tx.sender, Some(owner).filter(|o| !o.is_empty()));
+        Ok(())
+    }
+    
+    /// Apply a `DeviceBond` transaction: lock the owner's stake as the bond
+    /// of the device named by the output
+    fn process_device_bond(&mut self, tx: &Transaction) -> Result<(), String> {
+        if tx.signature.is_none() {
+            return Err("Device bond must be signed by the owner".to_string());
+        }
+        let output = tx.outputs.first().ok_or("Device bond has no device")?;
+        let (device, amount) = (&output.recipient, output.amount);
+        if self.state.device_bonds.contains_key(device) {
+            return Err(format!("Device {} is already bonded", device));
+        }
+        let min_bond = self.state.params.min_device_bond;
+        if amount == 0 || amount < min_bond {
+            return Err(format!("Bond of {} is below the minimum device bond of {}", amount, min_bond));
+        }
+        let available = self.available_balance(&tx.sender, NATIVE_TOKEN);
+        if available < amount {
+            return Err(format!("Insufficient balance for device bond: has {}, needs {}", available, amount));
+        }
+        self.state.accounts.get_mut(&tx.sender)
+            .ok_or("Account not found")?
+            .debit(NATIVE_TOKEN, amount)?;
+        self.state.device_bonds.insert(device.clone(), DeviceBond {
+            owner: tx.sender.clone(),
+            amount,
+            reward_multiplier: self.state.params.bond_reward_multiplier(amount),
+            release_height: None,
+        });
+        Ok(())
+    }
+    
+    /// Apply a `DeviceUnbond` transaction: the bond goes back to its owner
+    /// `unbonding_blocks` after the current height
+    fn process_device_unbond(&mut self, tx: &Transaction) -> Result<(), String> {
+        if tx.signature.is_none() {
+            return Err("Device unbond must be signed by the owner".to_string());
+        }
+        let device = tx.outputs.first().map(|o| o.recipient.as_str()).ok_or("Device unbond has no device")?;
+        let release_height = self.total_blocks + self.state.params.unbonding_blocks;
+        let bond = self.state.device_bonds.get_mut(device)
+            .ok_or_else(|| format!("Device {} has no bond", device))?;
+        if bond.owner != tx.sender {
+            return Err("Only the bond's owner can release it".to_string());
+        }
+        if bond.release_height.is_some() {
+            return Err(format!("Bond of device {} is already being released", device));
+        }
+        bond.release_height = Some(release_height);
+        Ok(())
+    }
+    
+    /// Return every device bond released at or before `height` to its owner
+    fn release_device_bonds(&mut self, height: u64) {
+        let mut released: Vec<(String, DeviceBond)> = Vec::new();
+        self.state.device_bonds.retain(|device, bond| {
+            if bond.release_height.is_none_or(|release| release > height) {
+                return true;
+            }
+            released.push((device.clone(), bond.clone()));
+            false
+        });
+        released.sort_by(|a, b| a.0.cmp(&b.0));
+        for (device, bond) in released {
+            self.state.accounts.entry(bond.owner.clone())
+                .or_insert_with(|| Account::new(bond.owner.clone()))
+                .credit(NATIVE_TOKEN, bond.amount);
+            info!("Released bond of {} for device {}", bond.amount, &device[..8.min(device.len())]);
+        }
+    }
+    
+    /// Record the type of a device, used to score its telemetry. Outside
+    /// tests this only happens through a `SetDeviceType` transaction.
+    pub fn set_device_type(&mut self, device: &str, device_type: DeviceType) {
+        self.state.device_types.insert(device.to_string(), device_type);
+    }
+    
+    /// Apply a `SetDeviceType` transaction naming the type in its data
+    fn process_set_device_type(&mut self, tx: &Transaction) -> Result<(), String> {
+        if tx.signature.is_none() {
+            return Err("Device type must be set by a transaction signed by the device".to_string());
+        }
+        let name = tx.data.as_deref().map(str::trim).filter(|name| !name.is_empty())
+            .ok_or("Device type transaction names no type")?;
+        self.set_device_type(&tx.sender, DeviceType::from_name(name));
+        Ok(())
+    }
+    
+    /// Quality of a contribution's payload, scored as of the transaction's
+    /// timestamp so every node reaches the same result
+    fn contribution_quality(&self, tx: &Transaction) -> Option<f64> {
+        let data = tx.data.as_ref()?;
+        let device_type = self.state.device_types.get(&tx.sender)
+            .cloned()
+            .unwrap_or(DeviceType::Custom(String::new()));
+        Some(score_contribution_at(data, &device_type, tx.timestamp.timestamp()))
+    }
+    
+    /// Process data contribution (PoIE reward). If the device has an owner,
+    /// `owner_reward_share` of the reward goes to the owner.
+    fn process_data_contribution(&mut self, tx: &Transaction) -> Result<(), String> {
+        self.check_reward_claim(tx)?;
+        let device = &tx.sender;
+        let quality = self.contribution_quality(tx);
+        // The reward scales with the category multiplier, the device's bond
+        // and the quality and novelty of the submitted telemetry
+        let reward = tx.outputs.first().map(|o| o.amount).unwrap_or(0);
+        let category = tx.data.as_deref().and_then(contribution_category).unwrap_or_default();
+        // Without an active bond a device earns nothing once a bond is required
+        let bond_multiplier = match self.state.device_bonds.get(device).filter(|b| b.release_height.is_none()) {
+            Some(bond) => bond.reward_multiplier,
+            None if self.state.params.min_device_bond > 0 => 0.0,
+            None => 1.0,
+        };
+        let multiplier = self.state.reward_policy.multiplier(&category) * bond_multiplier;
+        let reward = match (quality, &tx.data) {
+            (Some(score), Some(data)) => {
+                let novelty = self.state.novelty.entropy_bonus(device, data);
+                (reward as f64 * multiplier * score * novelty) as u64
+            }
+            _ => (reward as f64 * multiplier) as u64,
+        };
+        let owner = self.state.device_owners.get(device).cloned();
+        let owner_cut = match owner {
+            Some(_) => (reward as f64 * self.state.params.owner_reward_share) as u64,
+            None => 0,
+        };
+        
+        // Get or create device account
+        let account = self.state.accounts.entry(device.to_string())
+            .or_insert_with(|| Account::new(device.to_string()));
+        
+        account.balance += reward - owner_cut;
+        account.data_contributions += 1;
+        account.reputation_score = (account.reputation_score + 0.1).min(100.0);
+        
+        if let Some(owner) = owner.filter(|_| owner_cut > 0) {
+            self.state.accounts.entry(owner.clone())
+                .or_insert_with(|| Account::new(owner))
+                .balance += owner_cut;
+        }
+        
+        // Register data if hash provided
+        if let Some(output) = tx.outputs.get(0) {
+            if let Some(data_hash) = &output.data_hash {
+                let quality = quality
+                    .or_else(|| tx.data_quality.as_ref().map(|q| q.overall_score))
+                    .unwrap_or(0.0);
+                
+                self.state.data_registry.insert(data_hash.clone(), DataEntry {
+                    hash: data_hash.clone(),
+                    owner: device.to_string(),
+                    price: 10,
+                    quality_score: quality,
+                    timestamp: tx.timestamp.timestamp(),
+                    purchases: 0,
+                    category: "IoT".to_string(),
+                });
+                self.evict_data_registry();
+            }
+        }
+        
+        self.state.total_supply += reward;
+        
+        Ok(())
+    }
+    
+    /// Keep the data registry within the `data_registry_cap` consensus parameter.
+    /// Evicts the least valuable entries (fewest purchases, then oldest),
+    /// freeing an extra 10% of headroom so eviction doesn't run on every insert.
+    fn evict_data_registry(&mut self) {
+        let cap = self.state.params.data_registry_cap;
+        let len = self.state.data_registry.len();
+        if cap == 0 || len <= cap {
+            return;
+        }
+        
+        let evict_count = len - cap + cap / 10;
+        let mut ranked: Vec<(u64, i64, &String)> = self.state.data_registry
+            .values()
+            .map(|e| (e.purchases, e.timestamp, &e.hash))
+            .collect();
+        ranked.select_nth_unstable(evict_count - 1);
+        let evicted: Vec<String> = ranked[..evict_count]
+            .iter()
+            .map(|(_, _, hash)| (*hash).clone())
+            .collect();
+        
+        for hash in &evicted {
+            self.state.data_registry.remove(hash);
+        }
+        info!("Data registry capped at {}: evicted {} least-purchased/oldest entries",
+            cap, evicted.len());
+    }
+    
+    /// Process reward transaction
+    fn process_reward(&mut self, tx: &Transaction) -> Result<(), String> {
+        for output in &tx.outputs {
+            if output.recipient == TREASURY_ADDRESS && output.token_symbol() == NATIVE_TOKEN {
+                self.state.treasury += output.amount;
+                continue;
+            }
+            let account = self.state.accounts.entry(output.recipient.clone())
+                .or_insert_with(|| Account::new(output.recipient.clone()));
+            account.credit(output.token_symbol(), output.amount);
+        }
+        // Total supply tracks the native token only
+        self.state.total_supply += tx.total_output_for(NATIVE_TOKEN);
+        Ok(())
+    }
+    
+    /// Process data purchase. When the seller is not the data's original
+    /// contributor (a resale), `data_royalty_share` of the price goes to
+    /// the contributor recorded in the data registry.
+    fn process_data_purchase(&mut self, tx: &Transaction) -> Result<(), String> {
+        let buyer = &tx.sender;
+        let amount = tx.total_output();
+        
+        // Only the contributor or someone who bought the data may sell it
+        for output in &tx.outputs {
+            let Some(data_hash) = &output.data_hash else { continue };
+            let entry = self.state.data_registry.get(data_hash)
+                .ok_or_else(|| format!("Data {} is not registered", data_hash))?;
+            let holds_access = self.state.data_access.get(&output.recipient)
+                .is_some_and(|granted| granted.contains(data_hash));
+            if entry.owner != output.recipient && !holds_access {
+                return Err(format!("Seller {} neither contributed nor bought data {}", output.recipient, data_hash));
+            }
+        }
+        
+        // Deduct from buyer
+        let buyer_account = self.state.accounts.get_mut(buyer)
+            .ok_or("Buyer account not found")?;
+        
+        // The buyer must also cover the purchase fee
+        if buyer_account.balance < amount.saturating_add(tx.fee()) {
+            return Err("Insufficient balance".to_string());
+        }
+        buyer_account.balance -= amount;
+        
+        // Pay seller, less the royalty on resold data
+        for output in &tx.outputs {
+            let mut royalty = None;
+            if let Some(data_hash) = &output.data_hash {
+                if let Some(entry) = self.state.data_registry.get_mut(data_hash) {
+                    entry.purchases += 1;
+                    if entry.owner != output.recipient {
+                        let amount = (output.amount as f64 * self.state.params.data_royalty_share) as u64;
+                        royalty = Some((entry.owner.clone(), amount)).filter(|(_, a)| *a > 0);
+                    }
+                }
+            }
+            let royalty_amount = royalty.as_ref().map(|(_, a)| *a).unwrap_or(0);
+            
+            let seller_account = self.state.accounts.entry(output.recipient.clone())
+                .or_insert_with(|| Account::new(output.recipient.clone()));
+            seller_account.balance += output.amount - royalty_amount;
+            
+            if let Some((contributor, amount)) = royalty {
+                self.state.accounts.entry(contributor.clone())
+                    .or_insert_with(|| Account::new(contributor))
+                    .balance += amount;
+            }
+            
+            if let Some(data_hash) = &output.data_hash {
+                let granted = self.state.data_access.entry(buyer.clone()).or_default();
+                if !granted.contains(data_hash) {
+                    granted.push(data_hash.clone());
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Process stake: lock the sender's balance. With a staking manager the
+    /// delegation to the validator named by the output is recorded there too;
+    /// whether the transaction applies depends only on the chain state.
+    fn process_stake(&mut self, tx: &Transaction, staking: Option<&mut StakingManager>) -> Result<(), String> {
+        let output = tx.outputs.first().ok_or("Stake transaction has no output")?;
+        let amount = output.amount;
+        
+        if !self.state.validator_bonds.contains_key(&output.recipient) {
+            return Err(format!("{} is not a registered validator", output.recipient));
+        }
+        let balance = self.state.accounts.get(&tx.sender)
+            .ok_or("Account not found")?
+            .balance;
+        if balance < amount {
+            return Err("Insufficient balance for staking".to_string());
+        }
+        if let Some(staking) = staking {
+            if let Err(e) = staking.delegate(tx.sender.clone(), output.recipient.clone(), amount) {
+                warn!("Staking manager did not record delegation {}: {}", &tx.hash[..8.min(tx.hash.len())], e);
+            }
+        }
+        
+        let account = self.state.accounts.get_mut(&tx.sender)
+            .ok_or("Account not found")?;
+        account.balance -= amount;
+        account.staked_amount += amount;
+        self.state.total_staked += amount;
+        
+        Ok(())
+    }
+    
+    /// Process unstake: the stake unbonds for `unbonding_blocks` blocks and is
+    /// credited back by the block at its release height.
+    fn process_unstake(&mut self, tx: &Transaction, staking: Option<&mut StakingManager>) -> Result<(), String> {
+        let output = tx.outputs.first().ok_or("Unstake transaction has no output")?;
+        let amount = output.amount;
+        
+        let staked = self.state.accounts.get(&tx.sender)
+            .ok_or("Account not found")?
+            .staked_amount;
+        if staked < amount {
+            return Err("Insufficient staked amount".to_string());
+        }
+        if let Some(staking) = staking {
+            if let Err(e) = staking.undelegate(tx.sender.clone(), output.recipient.clone(), amount) {
+                warn!("Staking manager did not record undelegation {}: {}", &tx.hash[..8.min(tx.hash.len())], e);
+            }
+        }
+        
+        let account = self.state.accounts.get_mut(&tx.sender)
+            .ok_or("Account not found")?;
+        account.staked_amount -= amount;
+        self.state.total_staked -= amount;
+        let release_height = self.total_blocks + self.state.params.unbonding_blocks;
+        self.state.unbonding.entry(tx.sender.clone()).or_default().push(PendingUnbond {
+            amount,
+            release_height,
+            validator: output.recipient.clone(),
+            self_stake: false,
+        });
+        
+        Ok(())
+    }
+    
+    /// Process a cancelled unbonding: the sender's newest pending unbonding
+    /// of exactly the output's amount from the output's validator is staked
+    /// to that validator again
+    fn process_cancel_unbonding(&mut self, tx: &Transaction, staking: Option<&mut StakingManager>) -> Result<(), String> {
+        let output = tx.outputs.first().ok_or("CancelUnbonding transaction has no output")?;
+        let amount = output.amount;
+        if !self.state.validator_bonds.contains_key(&output.recipient) {
+            return Err(format!("{} is not a registered validator", output.recipient));
+        }
+        let pending = self.state.unbonding.get_mut(&tx.sender).ok_or("No pending unbonding")?;
+        let position = pending.iter()
+            .rposition(|u| !u.self_stake && u.validator == output.recipient && u.amount == amount)
+            .ok_or_else(|| format!("No pending unbonding of {} from {}", amount, output.recipient))?;
+        pending.remove(position);
+        if pending.is_empty() {
+            self.state.unbonding.remove(&tx.sender);
+        }
+        if let Some(staking) = staking {
+            if let Err(e) = staking.cancel_unbonding(&tx.sender, &output.recipient, amount) {
+                warn!("Staking manager did not record re-bond {}: {}", &tx.hash[..8.min(tx.hash.len())], e);
+            }
+        }
+        
+        let account = self.state.accounts.entry(tx.sender.clone())
+            .or_insert_with(|| Account::new(tx.sender.clone()));
+        account.staked_amount += amount;
+        self.state.total_staked += amount;
+        Ok(())
+    }
+    
+    /// Apply a `ValidatorRegister` transaction: lock the signing operator's
+    /// stake as the validator's self-stake. With a staking manager the
+    /// validator is registered there too, under its minimum stake and
+    /// commission rules.
+    fn process_validator_register(&mut self, tx: &Transaction, staking: Option<&mut StakingManager>) -> Result<(), String> {
+        if tx.signature.is_none() {
+            return Err("Validators must be registered by a transaction signed by the operator".to_string());
+        }
+        let output = tx.outputs.first().ok_or("ValidatorRegister transaction has no output")?;
+        let validator = &output.recipient;
+        let stake = output.amount;
+        if self.state.validator_bonds.contains_key(validator) {
+            return Err(format!("Validator {} is already registered", validator));
+        }
+        let registration: ValidatorRegistration = serde_json::from_str(tx.data.as_deref().unwrap_or(""))
+            .map_err(|e| format!("Invalid validator registration: {}", e))?;
+        if self.get_balance(&tx.sender) < stake {
+            return Err("Insufficient balance for self-stake".to_string());
+        }
+        if let Some(staking) = staking {
+            staking.register_validator(
+                validator.clone(),
+                tx.sender.clone(),
+                stake,
+                registration.commission_rate,
+                registration.description,
+            )?;
+        }
+        
+        self.state.accounts.get_mut(&tx.sender)
+            .ok_or("Account not found")?
+            .balance -= stake;
+        self.state.total_staked += stake;
+        self.state.validator_bonds.insert(validator.clone(), ValidatorBond {
+            operator: tx.sender.clone(),
+            self_stake: stake,
+        });
+        Ok(())
+    }
+    
+    /// Apply a `ValidatorUnbond` transaction: the amount leaves the
+    /// validator's self-stake and is credited back to the operator like
+    /// unstaked funds, at the release height
+    fn process_validator_unbond(&mut self, tx: &Transaction, staking: Option<&mut StakingManager>) -> Result<(), String> {
+        let output = tx.outputs.first().ok_or("ValidatorUnbond transaction has no output")?;
+        let amount = output.amount;
+        let bond = self.state.validator_bonds.get(&output.recipient)
+            .ok_or_else(|| format!("{} is not a registered validator", output.recipient))?;
+        if bond.operator != tx.sender {
+            return Err("Only the validator's operator can unbond its self-stake".to_string());
+        }
+        if bond.self_stake < amount {
+            return Err("Insufficient self-stake".to_string());
+        }
+        if let Some(staking) = staking {
+            staking.unbond_self_stake(&output.recipient, amount)?;
+        }
+        
+        let bond = self.state.validator_bonds.get_mut(&output.recipient)
+            .ok_or("Validator not found")?;
+        bond.self_stake -= amount;
+        self.state.total_staked -= amount;
+        let release_height = self.total_blocks + self.state.params.unbonding_blocks;
+        self.state.unbonding.entry(tx.sender.clone()).or_default().push(PendingUnbond {
+            amount,
+            release_height,
+            validator: output.recipient.clone(),
+            self_stake: true,
+        });
+        Ok(())
+    }
+    
+    /// Data hashes `buyer` has purchased access to, oldest first
+    pub fn data_access(&self, buyer: &str) -> &[String] {
+        self.state.data_access.get(buyer).map(Vec::as_slice).unwrap_or_default()
+    }
+    
+    /// Registered data entry for `data_hash`
+    pub fn get_data_entry(&self, data_hash: &str) -> Option<&DataEntry> {
+        self.state.data_registry.get(data_hash)
+    }
+    
+    /// Get account state
+    pub fn get_account(&self, address: &str) -> Option<&Account> {
+        self.state.accounts.get(address)
+    }
+    
+    /// Get account balance (read-only)
+    pub fn get_balance(&self, address: &str) -> u64 {
+        self.state.accounts.get(address).map(|a| a.balance).unwrap_or(0)
+    }
+    
+    /// Get account balance for a specific token symbol (read-only)
+    pub fn get_token_balance(&self, address: &str, token: &str) -> u64 {
+        self.state.accounts.get(address).map(|a| a.token_balance(token)).unwrap_or(0)
+    }
+    
+    /// Get transactions for an address, oldest first, including blocks no
+    /// longer in memory
+    pub fn get_transactions_for_address(&self, address: &str) -> Vec<Transaction> {
+        self.address_transactions_page(address, 0, usize::MAX).0
+    }
+    
+    /// Blocks below the in-memory window that may involve `address`: the
+    /// ones listed in the store's address index, or every stored block if
+    /// the backend has no index
+    fn stored_blocks_for_address(&self, address: &str, mut visit: impl FnMut(&Block)) {
+        let first_in_memory = self.chain.first().map(|b| b.index).unwrap_or(self.total_blocks);
+        match self.block_store.address_heights(address) {
+            Ok(Some(heights)) => {
+                for height in heights.into_iter().take_while(|h| *h < first_in_memory) {
+                    match self.block_store.get_block_by_height(height) {
+                        Ok(Some(block)) => visit(&block),
+                        Ok(None) => break,
+                        Err(e) => {
+                            warn!("Failed to read block {} from {} store: {}", height, self.block_store.name(), e);
+                            break;
+                        }
+                    }
+                }
+                return;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read address index from {} store: {}", self.block_store.name(), e),
+        }
+        
+        let mut height = 0;
+        while height < first_in_memory {
+            let count = (first_in_memory - height).min(STORE_SCAN_BATCH as u64) as usize;
+            let stored = match self.block_store.load_range(height, count) {
+                Ok(stored) if !stored.is_empty() => stored,
+                Ok(_) => break,
+                Err(e) => {
+                    warn!("Failed to read blocks from {} store: {}", self.block_store.name(), e);
+                    break;
+                }
+            };
+            height += stored.len() as u64;
+            stored.iter().for_each(&mut visit);
+        }
+    }
+    
+    /// Blocks at heights `offset..offset + limit`, oldest first. Blocks pruned
+    /// from memory are read from the block store.
+    pub fn blocks_page(&self, offset: u64, limit: usize) -> Vec<Block> {
+        let end = offset.saturating_add(limit as u64).min(self.total_blocks);
+        if offset >= end {
+            return Vec::new();
+        }
+        let first_in_memory = self.chain.first().map(|b| b.index).unwrap_or(self.total_blocks);
+        
+        let mut blocks = Vec::with_capacity((end - offset) as usize);
+        if offset < first_in_memory {
+            let count = (end.min(first_in_memory) - offset) as usize;
+            match self.block_store.load_range(offset, count) {
+                Ok(stored) => blocks.extend(stored),
+                Err(e) => warn!("Failed to read blocks from {} store: {}", self.block_store.name(), e),
+            }
+        }
+        blocks.extend(
+            self.chain
+                .iter()
+                .filter(|b| b.index >= offset.max(first_in_memory) && b.index < end)
+                .cloned(),
+        );
+        blocks
+    }
+    
+    /// Page of the confirmed transactions sent or received by `address`,
+    /// oldest first, and the total number of such transactions. Blocks no
+    /// longer in memory are found through the block store's address index.
+    pub fn address_transactions_page(&self, address: &str, offset: usize, limit: usize) -> (Vec<Transaction>, usize) {
+        let mut items = Vec::new();
+        let mut total = 0;
+        let mut visit = |block: &Block| {
+            for tx in block.transactions.iter().filter(|tx| tx_involves(tx, address)) {
+                if total >= offset && items.len() < limit {
+                    items.push(tx.clone());
+                }
+                total += 1;
+            }
+        };
+        
+        self.stored_blocks_for_address(address, &mut visit);
+        self.chain.iter().for_each(visit);
+        
+        (items, total)
+    }
+    
+    /// Up to `limit` pending transactions, highest gas price first (ties in arrival order)
+    pub fn get_pending_transactions(&self, limit: usize) -> Vec<Transaction> {
+        self.pending_transactions.iter().take(limit).cloned().collect()
+    }
+    
+    /// Size, fees and gas prices of the pending pool
+    pub fn mempool_stats(&self) -> MempoolStats {
+        let count = self.pending_transactions.len();
+        let total_fees = self.pending_transactions.iter().map(|tx| tx.fee()).sum();
+        let total_gas_price: u64 = self.pending_transactions.iter().map(|tx| tx.gas_price).sum();
+        MempoolStats {
+            count,
+            capacity: self.pending_transactions.capacity(),
+            total_fees,
+            average_gas_price: if count > 0 { total_gas_price as f64 / count as f64 } else { 0.0 },
+        }
+    }
+    
+    /// Find transactions carrying `memo`: pending, recent in-memory blocks,
+    /// then the RocksDB memo index
+    pub fn get_transactions_by_memo(&self, memo: &str, limit: usize) -> Vec<Transaction> {
+        let mut txs: Vec<Transaction> = self.pending_transactions
+            .iter()
+            .chain(self.chain.iter().rev().flat_map(|b| b.transactions.iter()))
+            .filter(|tx| tx.memo.as_deref() == Some(memo))
+            .take(limit)
+            .cloned()
+            .collect();
+        
+        if let Some(ref storage) = self.storage {
+            for hash in storage.get_transaction_hashes_by_memo(memo, limit) {
+                if txs.len() >= limit {
+                    break;
+                }
+                if txs.iter().any(|tx| tx.hash == hash) {
+                    continue;
+                }
+                if let Some(tx) = self.get_transaction(&hash) {
+                    txs.push(tx);
+                }
+            }
+        }
+        
+        txs
+    }
+    
+    /// Get blockchain stats with PoIE network metrics
+    pub fn get_stats(&self) -> ChainStats {
+        let height = self.total_blocks;
+        let total_transactions: u64 = self.chain.iter().map(|b| b.transactions.len() as u64).sum();
+        
+        // Estimate total transactions based on average
+        let avg_tx_per_block = if !self.chain.is_empty() {
+            total_transactions as f64 / self.chain.len() as f64
+        } else {
+            0.0
+        };
+        let estimated_total_tx = (avg_tx_per_block * height as f64) as u64;
+        
+        // Calculate network entropy from in-memory blocks
+        let network_entropy: f64 = self.chain.iter()
+            .map(|b| b.header.data_entropy)
+            .sum();
+        
+        // Calculate data throughput
+        let data_throughput = if height > 0 {
+            (estimated_total_tx as f64 * 256.0) / (height as f64 * 10.0)
+        } else {
+            0.0
+        };
+        
+        // Calculate TPS
+        let tps = if height > 0 {
+            estimated_total_tx as f64 / (height as f64 * 10.0)
+        } else {
+            0.0
+        };
+        
+        // Calculate validator power index
+        let validator_power = {
+            let active = self.state.accounts.len() as f64;
+            let data = self.state.data_registry.len() as f64;
+            let entropy_factor = network_entropy / (self.chain.len().max(1) as f64);
+            (active * 0.3 + data * 0.3 + entropy_factor * 100.0 * 0.4).max(0.0)
+        };
+        
+        ChainStats {
+            height,
+            total_transactions: estimated_total_tx,
+            total_supply: self.state.total_supply,
+            total_staked: self.state.total_staked,
+            active_accounts: self.state.accounts.len() as u64,
+            data_entries: self.state.data_registry.len() as u64,
+            difficulty: self.difficulty,
+            last_block_time: self.last_block_time,
+            network_entropy,
+            avg_tx_per_block,
+            data_throughput,
+            tps,
+            validator_power,
+            finalized_height: self.finalized_height(),
+            chain_id: self.chain_id().to_string(),
+            pending_real: self.pending_transactions.len() as u64,
+            pending_synthetic: self.synthetic_transactions.len() as u64,
+            real_tx_included: self.tx_class_stats.real_included,
+            synthetic_tx_included: self.tx_class_stats.synthetic_included,
+        }
+    }
+}
+
+/// Summary of the pending transaction pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolStats {
+    pub count: usize,
+    pub capacity: usize,
+    /// Fees the pooled transactions will pay once mined
+    pub total_fees: u64,
+    pub average_gas_price: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChainStats {
+    pub height: u64,
+    pub total_transactions: u64,
+    pub total_supply: u64,
+    pub total_staked: u64,
+    pub active_accounts: u64,
     pub data_entries: u64,
     pub difficulty: u64,
     pub last_block_time: i64,
@@ -1402,4 +3475,1781 @@ pub struct ChainStats {
     pub data_throughput: f64,
     pub tps: f64,
     pub validator_power: f64,
+    /// Newest block that can no longer be reorged away
+    pub finalized_height: u64,
+    /// Genesis block hash
+    pub chain_id: String,
+    // Mempool classes (real API/P2P vs synthetic demo load)
+    pub pending_real: u64,
+    pub pending_synthetic: u64,
+    pub real_tx_included: u64,
+    pub synthetic_tx_included: u64,
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Scratch chain with its own block store directory, so tests running in
+    /// parallel don't share one
+    pub(crate) fn test_chain() -> Blockchain {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "edgeai-test-chain-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        Blockchain::scratch(dir)
+    }
+
+    /// Credit `balance` to `wallet`'s account
+    pub(crate) fn fund(chain: &mut Blockchain, wallet: &crate::crypto::Wallet, balance: u64) {
+        let mut account = Account::new(wallet.address().to_string());
+        account.balance = balance;
+        chain.state.accounts.insert(wallet.address().to_string(), account);
+    }
+
+    /// Reward transaction a block by `validator` must start with, given its other `txs`
+    pub(crate) fn block_reward_for(chain: &Blockchain, validator: &str, txs: &[Transaction]) -> Transaction {
+        let fees = txs.iter().map(|tx| tx.fee()).sum();
+        Transaction::block_reward(validator.to_string(), chain.state.params.block_reward, fees,
+            chain.state.params.treasury_share, "Block reward".to_string())
+    }
+
+    /// Stamp a peer `block` with the state root applying it on `chain` produces;
+    /// a block that fails to apply is left without one
+    pub(crate) fn set_state_root(chain: &mut Blockchain, block: &mut Block) {
+        let state = chain.state.clone();
+        if chain.apply_block(block, &mut ConsensusModules::default()).is_ok() {
+            block.header.state_root = state_root(&chain.state);
+        }
+        chain.pending_logs.remove(&block.index);
+        chain.state = state;
+    }
+
+    /// Transfer of `amount` to `to` signed by `wallet`
+    pub(crate) fn signed_transfer(wallet: &crate::crypto::Wallet, to: &str, amount: u64, nonce: u64) -> Transaction {
+        Transaction::transfer(wallet.address().to_string(), to.to_string(), amount)
+            .with_nonce(nonce)
+            .signed_by(wallet)
+    }
+
+    #[test]
+    fn test_transaction_pow_required() {
+        let mut chain = test_chain();
+        chain.state.params.tx_pow_bits = 8;
+
+        let mut tx = Transaction::transfer("genesis".to_string(), "alice".to_string(), 10);
+        let err = chain.add_local_transaction(tx.clone()).unwrap_err();
+        assert!(matches!(err, TransactionError::InsufficientPow { .. }));
+
+        // A nonce that does not meet the target is rejected
+        let bad_nonce = (0..).find(|n| tx.pow_hash(*n)[0] != 0).unwrap();
+        tx.pow_nonce = Some(bad_nonce);
+        assert!(chain.add_local_transaction(tx.clone()).is_err());
+
+        tx.solve_pow(8);
+        assert!(tx.verify_pow(8));
+        assert!(chain.add_local_transaction(tx).is_ok());
+        assert_eq!(chain.pending_transactions.len(), 1);
+    }
+
+    #[test]
+    fn test_real_transactions_included_before_synthetic() {
+        let mut chain = test_chain();
+        for i in 0..MAX_TXS_PER_BLOCK {
+            let tx = Transaction::transfer("genesis".to_string(), format!("synthetic_{}", i), 1);
+            chain.add_synthetic_transaction(tx).unwrap();
+        }
+        let real = Transaction::transfer("genesis".to_string(), "alice".to_string(), 5);
+        let real_hash = chain.add_local_transaction(real).unwrap();
+
+        let selected = chain.select_transactions_for_block();
+        assert_eq!(selected.len(), MAX_TXS_PER_BLOCK);
+        assert_eq!(selected[0].hash, real_hash);
+        // The synthetic transaction that did not fit stays queued
+        assert_eq!(chain.synthetic_transactions.len(), 1);
+
+        let stats = chain.get_stats();
+        assert_eq!(stats.real_tx_included, 1);
+        assert_eq!(stats.synthetic_tx_included, (MAX_TXS_PER_BLOCK - 1) as u64);
+        assert_eq!(stats.pending_synthetic, 1);
+    }
+
+    #[test]
+    fn test_data_token_transfer_independent_of_edge() {
+        let mut chain = test_chain();
+        let reward = Transaction::reward_token("alice".to_string(), 500, "DATA", "airdrop".to_string());
+        chain.apply_transaction(&reward, None).unwrap();
+        assert_eq!(chain.get_token_balance("alice", "DATA"), 500);
+        assert_eq!(chain.get_balance("alice"), 0);
+        assert_eq!(chain.state.total_supply, 1_000_000_000);
+
+        // Without EDGE alice cannot pay the transfer fee
+        let tx = Transaction::transfer_token("alice".to_string(), "bob".to_string(), 200, "data");
+        assert!(matches!(
+            chain.add_local_transaction(tx.clone()).unwrap_err(),
+            TransactionError::InsufficientFeeBalance { have: 0, .. }
+        ));
+
+        // With just enough EDGE for the fee she can move DATA
+        let gas = Transaction::reward("alice".to_string(), tx.fee(), "gas".to_string());
+        chain.apply_transaction(&gas, None).unwrap();
+        chain.add_local_transaction(tx.clone()).unwrap();
+        chain.apply_transaction(&tx, None).unwrap();
+        assert_eq!(chain.get_token_balance("alice", "DATA"), 300);
+        assert_eq!(chain.get_token_balance("bob", "DATA"), 200);
+        assert_eq!(chain.get_balance("alice"), 0);
+        assert_eq!(chain.get_balance("bob"), 0);
+
+        // EDGE transfers are rejected since alice has no EDGE left
+        let edge_tx = Transaction::transfer("alice".to_string(), "bob".to_string(), 1);
+        assert!(chain.add_local_transaction(edge_tx).is_err());
+
+        let overdraw = Transaction::transfer_token("alice".to_string(), "bob".to_string(), 301, "DATA");
+        assert!(chain.apply_transaction(&overdraw, None).is_err());
+    }
+
+    #[test]
+    fn test_traced_sender_lifecycle_events() {
+        let mut chain = test_chain();
+        chain.tracer.filter.senders.insert("genesis".to_string());
+
+        let tx = Transaction::transfer("genesis".to_string(), "alice".to_string(), 10);
+        let hash = chain.add_local_transaction(tx).unwrap();
+        // Untraced senders produce no events
+        let untraced = Transaction::reward("alice".to_string(), 5, "bonus".to_string());
+        chain.apply_transaction(&untraced, None).unwrap();
+
+        let mut txs = chain.select_transactions_for_block();
+        txs.insert(0, block_reward_for(&chain, "validator", &txs));
+        let block = Block::new(1, chain.latest_block().hash.clone(), txs, 1, "validator".to_string());
+        chain.apply_block(&block, &mut ConsensusModules::default()).unwrap();
+
+        let stages: Vec<TxStage> = chain.tracer.events(Some(&hash)).into_iter().map(|e| e.stage).collect();
+        assert_eq!(stages, vec![
+            TxStage::Received,
+            TxStage::Validated,
+            TxStage::Included { block: 1 },
+            TxStage::Applied,
+            TxStage::Receipt { success: true, error: None },
+        ]);
+        assert_eq!(chain.tracer.events(None).len(), stages.len());
+    }
+
+    #[test]
+    fn test_data_registry_cap_evicts_least_valuable() {
+        use crate::blockchain::transaction::TxOutput;
+
+        let mut chain = test_chain();
+        chain.state.params.data_registry_cap = 10;
+
+        for i in 0..30i64 {
+            let output = TxOutput {
+                amount: chain.state.reward_policy.claim_for(""),
+                recipient: "device".to_string(),
+                data_hash: Some(format!("data_{}", i)),
+                token: None,
+            };
+            let tx = Transaction::new(
+                TransactionType::DataContribution, "device".to_string(),
+                vec![], vec![output], None, 1, 21000,
+            );
+            chain.apply_transaction(&tx, None).unwrap();
+            // Deterministic ages: data_0 is the oldest
+            chain.state.data_registry.get_mut(&format!("data_{}", i)).unwrap().timestamp = i;
+            if i < 2 {
+                chain.state.data_registry.get_mut(&format!("data_{}", i)).unwrap().purchases = 3;
+            }
+            assert!(chain.state.data_registry.len() <= 10);
+        }
+
+        let registry = &chain.state.data_registry;
+        // Purchased entries survive despite being the oldest
+        assert!(registry.contains_key("data_0"));
+        assert!(registry.contains_key("data_1"));
+        // The newest contribution is kept, stale unpurchased ones are gone
+        assert!(registry.contains_key("data_29"));
+        assert!(!registry.contains_key("data_2"));
+        assert!(!registry.contains_key("data_15"));
+    }
+
+    #[test]
+    fn test_contribution_reward_split_with_owner() {
+        use crate::blockchain::transaction::TxOutput;
+
+        let contribution = |device: &str| {
+            let output = TxOutput { amount: 100, recipient: device.to_string(), data_hash: None, token: None };
+            Transaction::new(TransactionType::DataContribution, device.to_string(), vec![], vec![output], None, 1, 21000)
+        };
+        let balance = |chain: &Blockchain, address: &str| chain.state.accounts.get(address).map(|a| a.balance).unwrap_or(0);
+
+        // A payload-less contribution claims the base reward plus the General bonus (5)
+        let mut chain = test_chain();
+        chain.state.reward_policy.base_reward = 95;
+        chain.state.params.owner_reward_share = 0.3;
+        chain.set_device_owner("owned_device", Some("owner".to_string()));
+        let supply = chain.state.total_supply;
+
+        chain.apply_transaction(&contribution("owned_device"), None).unwrap();
+        assert_eq!(balance(&chain, "owned_device"), 70);
+        assert_eq!(balance(&chain, "owner"), 30);
+        assert_eq!(chain.state.accounts["owned_device"].data_contributions, 1);
+        assert_eq!(chain.state.total_supply, supply + 100);
+
+        // Devices without an owner keep the whole reward
+        chain.apply_transaction(&contribution("lone_device"), None).unwrap();
+        assert_eq!(balance(&chain, "lone_device"), 100);
+
+        // The default share pays everything to the device
+        let mut chain = test_chain();
+        chain.state.reward_policy.base_reward = 95;
+        chain.set_device_owner("owned_device", Some("owner".to_string()));
+        chain.apply_transaction(&contribution("owned_device"), None).unwrap();
+        assert_eq!(balance(&chain, "owned_device"), 100);
+        assert_eq!(balance(&chain, "owner"), 0);
+    }
+
+    #[test]
+    fn test_device_owner_set_by_signed_transaction() {
+        let device = crate::crypto::Wallet::new();
+        let mut chain = test_chain();
+
+        // Nobody but the device can name its owner
+        let unsigned = Transaction::set_device_owner(device.address().to_string(), "owner".to_string());
+        assert!(chain.add_transaction(unsigned.clone()).is_err());
+        assert!(chain.apply_transaction(&unsigned, None).is_err());
+        let forged = unsigned.clone().signed_by(&crate::crypto::Wallet::new());
+        assert!(chain.add_transaction(forged).is_err());
+
+        chain.add_transaction(unsigned.signed_by(&device)).unwrap();
+        chain.mine_block("validator".to_string()).unwrap();
+        assert_eq!(chain.state.device_owners.get(device.address()).map(String::as_str), Some("owner"));
+        assert_eq!(CanonicalState::from_state(&chain.state).device_owners.len(), 1);
+
+        // An empty owner clears it
+        let clear = Transaction::set_device_owner(device.address().to_string(), String::new())
+            .with_nonce(1)
+            .signed_by(&device);
+        chain.apply_transaction(&clear, None).unwrap();
+        assert!(chain.state.device_owners.is_empty());
+    }
+
+    #[test]
+    fn test_device_type_set_by_signed_transaction() {
+        let device = crate::crypto::Wallet::new();
+        let mut chain = test_chain();
+
+        let unsigned = Transaction::set_device_type(device.address().to_string(), "camera".to_string());
+        assert!(chain.apply_transaction(&unsigned, None).is_err());
+        let root = crate::blockchain::state_dump::state_root(&chain.state);
+        chain.apply_transaction(&unsigned.signed_by(&device), None).unwrap();
+        assert_eq!(chain.state.device_types.get(device.address()), Some(&DeviceType::Camera));
+        assert_ne!(crate::blockchain::state_dump::state_root(&chain.state), root);
+        assert_eq!(CanonicalState::from_state(&chain.state).device_types.len(), 1);
+    }
+
+    #[test]
+    fn test_device_bond_locked_and_released_in_blocks() {
+        use crate::blockchain::transaction::TxOutput;
+
+        let mut chain = test_chain();
+        chain.state.params.min_device_bond = 100;
+        chain.state.params.unbonding_blocks = 3;
+        let owner = crate::crypto::Wallet::new();
+        fund(&mut chain, &owner, 20_000);
+        let bond = |device: &str, amount: u64, nonce: u64| {
+            Transaction::device_bond(owner.address().to_string(), device.to_string(), amount)
+                .with_nonce(nonce)
+                .signed_by(&owner)
+        };
+
+        // The owner must sign, and bond at least the minimum
+        let unsigned = Transaction::device_bond(owner.address().to_string(), "device".to_string(), 200);
+        assert!(chain.apply_transaction(&unsigned, None).is_err());
+        assert!(chain.apply_transaction(&bond("device", 99, 0), None).unwrap_err().contains("minimum device bond of 100"));
+        chain.apply_transaction(&bond("device", 200, 0), None).unwrap();
+        assert!(chain.apply_transaction(&bond("device", 200, 1), None).is_err());
+        chain.apply_transaction(&bond("whale", 10_000, 1), None).unwrap();
+        assert_eq!(chain.get_balance(owner.address()), 20_000 - 10_200);
+        assert_eq!(chain.state.device_bonds["device"].reward_multiplier, 2.0);
+        // Bonding past the cap earns no more than the cap
+        assert_eq!(chain.state.device_bonds["whale"].reward_multiplier, 3.0);
+        assert_eq!(CanonicalState::from_state(&chain.state).device_bonds.len(), 2);
+
+        // Without a bond a device earns nothing while one is required
+        let data = r#"{"device":"d","telemetry":{"temperature":21.5},"ts":1700000000}"#;
+        let claimed = chain.state.reward_policy.claim_for(data);
+        let contribution = |device: &str| {
+            let output = TxOutput { amount: claimed, recipient: device.to_string(), data_hash: None, token: None };
+            Transaction::new(TransactionType::DataContribution, device.to_string(), vec![], vec![output], Some(data.to_string()), 1, 21000)
+        };
+        let (bonded, unbonded) = (contribution("device"), contribution("stranger"));
+        chain.apply_transaction(&bonded, None).unwrap();
+        chain.apply_transaction(&unbonded, None).unwrap();
+        assert!(chain.get_balance("device") > 0);
+        assert_eq!(chain.get_balance("stranger"), 0);
+
+        // Only the owner releases the bond, which comes back at the release height
+        let intruder = crate::crypto::Wallet::new();
+        let stolen = Transaction::device_unbond(intruder.address().to_string(), "device".to_string()).signed_by(&intruder);
+        assert!(chain.apply_transaction(&stolen, None).is_err());
+        let release_height = chain.total_blocks + 3;
+        let unbond = Transaction::device_unbond(owner.address().to_string(), "device".to_string())
+            .with_nonce(2)
+            .signed_by(&owner);
+        chain.apply_transaction(&unbond, None).unwrap();
+        chain.release_device_bonds(release_height - 1);
+        assert_eq!(chain.get_balance(owner.address()), 20_000 - 10_200);
+        chain.release_device_bonds(release_height);
+        assert_eq!(chain.get_balance(owner.address()), 20_000 - 10_000);
+        assert!(!chain.state.device_bonds.contains_key("device"));
+    }
+
+    #[test]
+    fn test_category_multiplier_scales_contribution_reward() {
+        use crate::blockchain::transaction::TxOutput;
+        use crate::consensus::governance::ProposalExecutor;
+
+        let policy = RewardPolicy::default();
+        let data = r#"{"device":"d","category":"Healthcare","telemetry":{"heart_rate":72,"spo2":98},"ts":1700000000}"#;
+        let claimed = policy.base_reward("Healthcare", data.len() as u64);
+        assert_eq!(claimed, 30 + data.len() as u64 / 20 + 20);
+        let contribution = |device: &str| {
+            let output = TxOutput { amount: claimed, recipient: device.to_string(), data_hash: None, token: None };
+            Transaction::new(TransactionType::DataContribution, device.to_string(), vec![], vec![output], Some(data.to_string()), 1, 21000)
+        };
+        let balance = |chain: &Blockchain, address: &str| chain.state.accounts.get(address).map(|a| a.balance).unwrap_or(0);
+
+        let mut chain = test_chain();
+        chain.apply_transaction(&contribution("device_a"), None).unwrap();
+        let before = balance(&chain, "device_a");
+        assert!(before > 0);
+
+        // Governance doubles Healthcare rewards; the next submission earns twice as much
+        chain.set_parameter("rewards", "Healthcare.multiplier", "2.0").unwrap();
+        chain.apply_transaction(&contribution("device_b"), None).unwrap();
+        let after = balance(&chain, "device_b");
+        assert!((2 * before..=2 * before + 1).contains(&after), "{} vs {}", after, before);
+        assert_eq!(chain.state.reward_policy.expected_reward("Healthcare", data.len() as u64), 2 * claimed);
+
+        assert!(chain.set_parameter("rewards", "Healthcare.multiplier", "-1").is_err());
+        assert!(chain.set_parameter("rewards", "Weather.bonus", "5").is_err());
+        chain.set_parameter("rewards", "Energy.bonus", "40").unwrap();
+        assert_eq!(chain.state.reward_policy.base_reward("Energy", 0), 70);
+
+        // The policy is part of the state root, and a contribution can't
+        // claim more than it sets
+        let root = crate::blockchain::state_dump::state_root(&chain.state);
+        chain.set_parameter("rewards", "base_reward", "31").unwrap();
+        assert_ne!(crate::blockchain::state_dump::state_root(&chain.state), root);
+        let greedy = contribution("device_c");
+        assert_eq!(chain.add_local_transaction(greedy.clone()).unwrap_err(),
+            TransactionError::WrongRewardClaim { claimed, expected: claimed + 1 });
+        assert!(chain.apply_transaction(&greedy, None).is_err());
+    }
+
+    #[test]
+    fn test_resale_pays_royalty_to_original_contributor() {
+        let balance = |chain: &Blockchain, address: &str| chain.state.accounts.get(address).map(|a| a.balance).unwrap_or(0);
+
+        let mut chain = test_chain();
+        chain.state.params.data_royalty_share = 0.1;
+        let contribution = Transaction::data_contribution("device".to_string(), "{\"temp\":21.5}".to_string(), "device".to_string())
+            .with_reward_claim(chain.state.reward_policy.claim_for("{\"temp\":21.5}"));
+        let data_hash = contribution.outputs[0].data_hash.clone().unwrap();
+        chain.apply_transaction(&contribution, None).unwrap();
+        assert_eq!(chain.state.data_registry[&data_hash].owner, "device");
+        for buyer in ["alice", "bob"] {
+            chain.state.accounts.entry(buyer.to_string())
+                .or_insert_with(|| Account::new(buyer.to_string()))
+                .balance = 1_000_000;
+        }
+        let device_before = balance(&chain, "device");
+
+        // Buying from the contributor pays no royalty
+        chain.apply_transaction(&Transaction::data_purchase("alice".to_string(), "device".to_string(), data_hash.clone(), 100), None).unwrap();
+        assert_eq!(balance(&chain, "device"), device_before + 100);
+
+        // Alice resells: the contributor gets 10%, alice the rest
+        let alice_before = balance(&chain, "alice");
+        chain.apply_transaction(&Transaction::data_purchase("bob".to_string(), "alice".to_string(), data_hash.clone(), 200), None).unwrap();
+        assert_eq!(balance(&chain, "alice"), alice_before + 180);
+        assert_eq!(balance(&chain, "device"), device_before + 120);
+        assert_eq!(chain.state.data_registry[&data_hash].purchases, 2);
+
+        // Nobody else can resell it, nor sell unregistered data
+        let carol_resale = Transaction::data_purchase("bob".to_string(), "carol".to_string(), data_hash.clone(), 200);
+        assert!(chain.apply_transaction(&carol_resale, None).unwrap_err().contains("neither contributed nor bought"));
+        let unknown = Transaction::data_purchase("bob".to_string(), "alice".to_string(), "f".repeat(64), 200);
+        assert!(chain.apply_transaction(&unknown, None).unwrap_err().contains("not registered"));
+        assert_eq!(chain.state.data_registry[&data_hash].purchases, 2);
+    }
+
+    #[test]
+    fn test_unmined_block_rolls_back() {
+        let mut chain = test_chain();
+        let tx = Transaction::transfer("genesis".to_string(), "alice".to_string(), 5);
+        chain.add_local_transaction(tx.clone()).unwrap();
+        let genesis_before = chain.state.accounts["genesis"].balance;
+
+        chain.state.params.warmup_blocks = 0;
+        chain.difficulty = 64;
+        let budget = MiningBudget { max_attempts: Some(10), max_duration: None };
+        assert!(chain.mine_block_within("miner".to_string(), budget, &mut ConsensusModules::default()).unwrap().is_none());
+        assert_eq!(chain.total_blocks, 1);
+        assert_eq!(chain.state.accounts["genesis"].balance, genesis_before);
+        assert!(!chain.state.accounts.contains_key("alice"));
+        assert!(chain.pending_transactions.contains(&tx.hash));
+
+        // The transaction is mined once the difficulty allows it
+        chain.difficulty = 1;
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        assert!(block.transactions.iter().any(|t| t.hash == tx.hash));
+    }
+
+    #[test]
+    fn test_mined_block_refused_after_tip_moves() {
+        let mut chain = test_chain();
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut chain, &payer, 1_000_000);
+        let transfer = signed_transfer(&payer, "alice", 500, 0);
+        chain.add_transaction(transfer.clone()).unwrap();
+        let height = chain.total_blocks;
+
+        // Building the block leaves state and the pool untouched
+        let mut template = chain.prepare_block("miner".to_string(), &mut ConsensusModules::default());
+        assert!(template.block.transactions.iter().any(|tx| tx.hash == transfer.hash));
+        assert_eq!(chain.total_blocks, height);
+        assert!(!chain.state.accounts.contains_key("alice"));
+        assert!(chain.pending_transactions.contains(&transfer.hash));
+        assert!(template.mine(MiningBudget::UNLIMITED));
+
+        // Another block is appended while it is mined: the stale one is refused
+        let mut stale = chain.prepare_block("miner".to_string(), &mut ConsensusModules::default());
+        assert!(stale.mine(MiningBudget::UNLIMITED));
+        chain.append_mined_block(template, &mut ConsensusModules::default()).unwrap();
+        assert_eq!(chain.state.accounts["alice"].balance, 500);
+        assert!(!chain.pending_transactions.contains(&transfer.hash));
+        assert!(chain.append_mined_block(stale, &mut ConsensusModules::default()).unwrap_err().contains("Stale block"));
+        assert_eq!(chain.total_blocks, height + 1);
+        assert_eq!(chain.state.accounts["alice"].balance, 500);
+    }
+
+    #[test]
+    fn test_transaction_past_height_deadline_not_mined() {
+        let mut chain = test_chain();
+        chain.mine_block("miner".to_string()).unwrap();
+        let height = chain.total_blocks;
+
+        // Deadline already passed: refused at submission...
+        let late = Transaction::transfer("genesis".to_string(), "alice".to_string(), 5)
+            .with_valid_until_height(Some(height - 1));
+        assert!(matches!(chain.add_local_transaction(late.clone()).unwrap_err(), TransactionError::Expired { .. }));
+
+        // ...and dropped by the miner if it reached the pool anyway
+        chain.pending_transactions.insert(late.clone()).unwrap();
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        assert!(block.transactions.iter().all(|tx| tx.hash != late.hash));
+        assert!(!chain.pending_transactions.contains(&late.hash));
+
+        // A deadline at the next block's height is still included
+        let height = chain.total_blocks;
+        let on_time = Transaction::transfer("genesis".to_string(), "alice".to_string(), 5)
+            .with_nonce(chain.next_nonce("genesis"))
+            .with_valid_until_height(Some(height));
+        chain.add_local_transaction(on_time.clone()).unwrap();
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        assert!(block.transactions.iter().any(|tx| tx.hash == on_time.hash));
+    }
+
+    #[test]
+    fn test_peer_blocks_purge_expired_transactions() {
+        let mut chain = test_chain();
+        let height = chain.total_blocks;
+        let expiring = Transaction::transfer("genesis".to_string(), "alice".to_string(), 5)
+            .with_valid_until_height(Some(height));
+        let lasting = Transaction::transfer("genesis".to_string(), "bob".to_string(), 5)
+            .with_nonce(1)
+            .with_valid_until_height(Some(height + 1));
+        chain.add_local_transaction(expiring.clone()).unwrap();
+        chain.add_local_transaction(lasting.clone()).unwrap();
+
+        // A peer's block at `height` leaves both out; only the first can't make the next one
+        let txs = vec![block_reward_for(&chain, "peer", &[])];
+        let difficulty = poie_difficulty(chain.base_difficulty(height), &txs);
+        let mut block = Block::new(height, chain.latest_block().hash.clone(), txs, difficulty, "peer".to_string());
+        set_state_root(&mut chain, &mut block);
+        assert!(block.mine(difficulty, MiningBudget::UNLIMITED));
+        chain.accept_external_block(block, &mut ConsensusModules::default()).unwrap();
+
+        assert!(!chain.pending_transactions.contains(&expiring.hash));
+        assert!(chain.pending_transactions.contains(&lasting.hash));
+        assert_eq!(chain.purge_expired_transactions(), 0);
+    }
+
+    #[test]
+    fn test_block_limits_leave_excess_transactions_pending() {
+        let mut chain = test_chain();
+        chain.state.params.max_txs_per_block = 10;
+        chain.state.params.max_block_bytes = 25_000;
+        chain.state.params.max_tx_data_bytes = 8_000;
+        let with_data = |nonce: u64, len: usize| {
+            let mut tx = Transaction::transfer("genesis".to_string(), "alice".to_string(), 1).with_nonce(nonce);
+            tx.data = Some("x".repeat(len));
+            tx.hash = tx.calculate_hash();
+            tx
+        };
+
+        // A payload over the per-transaction cap is refused outright
+        let err = chain.add_local_transaction(with_data(0, 8_001)).unwrap_err();
+        assert_eq!(err, TransactionError::DataTooLarge { len: 8_001, max: 8_000 });
+        assert_eq!(err.to_string(), "Transaction data too large: 8001 bytes (max 8000)");
+
+        // Five ~6 KB transactions: only three fit under 25 KB
+        for nonce in 0..5 {
+            chain.add_local_transaction(with_data(nonce, 6_000)).unwrap();
+        }
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        let included = &block.transactions[1..];
+        assert_eq!(included.len(), 3);
+        assert!(included.iter().map(|tx| tx.serialized_size()).sum::<usize>() <= 25_000);
+        assert_eq!(chain.pending_transactions.len(), 2);
+
+        // The count limit applies on its own
+        chain.state.params.max_txs_per_block = 1;
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        assert_eq!(block.transactions.len(), 2);
+        assert_eq!(block.transactions[1].nonce, 3);
+        assert_eq!(chain.pending_transactions.len(), 1);
+    }
+
+    #[test]
+    fn test_pending_transactions_by_gas_price() {
+        let mut chain = test_chain();
+        let priced = |nonce: u64, gas_price: u64| {
+            let mut tx = Transaction::transfer("genesis".to_string(), "alice".to_string(), 1).with_nonce(nonce);
+            tx.gas_price = gas_price;
+            tx.hash = tx.calculate_hash();
+            tx
+        };
+        for (nonce, gas_price) in [(0, 2), (1, 7), (2, 4), (3, 7)] {
+            chain.add_local_transaction(priced(nonce, gas_price)).unwrap();
+        }
+
+        let pending = chain.get_pending_transactions(10);
+        let order: Vec<(u64, u64)> = pending.iter().map(|tx| (tx.gas_price, tx.nonce)).collect();
+        assert_eq!(order, vec![(7, 1), (7, 3), (4, 2), (2, 0)]);
+        assert_eq!(chain.get_pending_transactions(2).len(), 2);
+        assert_eq!(chain.get_pending_transactions(2)[0].nonce, 1);
+
+        let stats = chain.mempool_stats();
+        assert_eq!(stats.count, 4);
+        assert_eq!(stats.total_fees, pending.iter().map(|tx| tx.fee()).sum::<u64>());
+        assert_eq!(stats.average_gas_price, 5.0);
+    }
+
+    #[test]
+    fn test_replayed_transfer_rejected_by_nonce() {
+        let mut chain = test_chain();
+        let tx = Transaction::transfer("genesis".to_string(), "alice".to_string(), 10);
+        assert_eq!(chain.next_nonce("genesis"), 0);
+        chain.add_local_transaction(tx.clone()).unwrap();
+
+        // The identical transfer is rejected while the first is pending
+        let err = chain.add_local_transaction(tx.clone()).unwrap_err();
+        assert_eq!(err, TransactionError::NonceTooLow { expected: 1, got: 0 });
+
+        // ...and after it has been applied
+        let selected = chain.select_transactions_for_block();
+        for t in &selected {
+            chain.apply_transaction(t, None).unwrap();
+        }
+        assert_eq!(chain.state.accounts["genesis"].nonce, 1);
+        assert_eq!(chain.add_local_transaction(tx.clone()).unwrap_err(), TransactionError::NonceTooLow { expected: 1, got: 0 });
+        assert!(chain.apply_transaction(&tx, None).is_err());
+        assert_eq!(chain.get_balance("alice"), 10);
+
+        let ahead = Transaction::transfer("genesis".to_string(), "alice".to_string(), 10).with_nonce(5);
+        assert_eq!(chain.add_local_transaction(ahead).unwrap_err(), TransactionError::NonceTooHigh { expected: 1, got: 5 });
+        let next = Transaction::transfer("genesis".to_string(), "alice".to_string(), 10).with_nonce(1);
+        assert!(chain.add_local_transaction(next).is_ok());
+    }
+
+    #[test]
+    fn test_parallel_signature_verification_threshold() {
+        let wallet = crate::crypto::Wallet::new();
+        let signed = |nonce: u64| {
+            let message = Transaction::create_transfer_signing_message(wallet.address(), "bob", 1, nonce, None, None);
+            Transaction::transfer_signed(
+                wallet.address().to_string(), wallet.public_key_hex(), "bob".to_string(),
+                1, nonce, None, wallet.sign(message.as_bytes()),
+            )
+        };
+        let mut large: Vec<Transaction> = (0..MAX_TXS_PER_BLOCK as u64).map(signed).collect();
+        large[7] = Transaction::transfer_signed(
+            wallet.address().to_string(), wallet.public_key_hex(), "bob".to_string(),
+            2, 7, None, large[7].signature.clone().unwrap(),
+        );
+
+        assert!(!verifies_in_parallel(10, DEFAULT_PARALLEL_VERIFY_THRESHOLD));
+        assert!(verifies_in_parallel(150, DEFAULT_PARALLEL_VERIFY_THRESHOLD));
+        assert!(!verifies_in_parallel(150, 0));
+
+        // Both paths agree, including on the forged signature
+        let serial = verify_transactions(&large, 0);
+        let parallel = verify_transactions(&large, 1);
+        assert_eq!(serial, parallel);
+        assert_eq!(serial.iter().filter(|ok| !**ok).count(), 1);
+        assert!(!serial[7]);
+    }
+
+    #[test]
+    fn test_reorg_adopts_heavier_branch() {
+        let mut local = test_chain();
+        let mut peer = test_chain();
+        peer.chain[0] = local.chain[0].clone();
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut local, &payer, 1_000_000);
+        fund(&mut peer, &payer, 1_000_000);
+
+        // Local: one block paying alice. Peer: two blocks paying bob.
+        let to_alice = signed_transfer(&payer, "alice", 500, 0);
+        local.add_transaction(to_alice.clone()).unwrap();
+        let ours = local.mine_block("local".to_string()).unwrap();
+        peer.add_transaction(signed_transfer(&payer, "bob", 300, 0)).unwrap();
+        peer.mine_block("peer".to_string()).unwrap();
+        peer.mine_block("peer".to_string()).unwrap();
+        let branch = peer.chain[1..].to_vec();
+
+        // A branch with no more work than ours is refused
+        assert!(local.reorg_to(&branch[..1], &mut ConsensusModules::default()).unwrap_err().contains("does not exceed"));
+
+        let replaced = local.reorg_to(&branch, &mut ConsensusModules::default()).unwrap();
+        assert_eq!(replaced.iter().map(|b| b.hash.clone()).collect::<Vec<_>>(), vec![ours.hash]);
+        assert_eq!(local.total_blocks, 3);
+        assert_eq!(local.latest_block().hash, peer.latest_block().hash);
+        assert!(!local.reorg_in_progress());
+
+        // The lighter branch's payment is undone and returns to the pool
+        assert_eq!(local.get_balance("alice"), 0);
+        assert_eq!(local.get_balance("bob"), 300);
+        assert_eq!(local.get_balance(payer.address()), peer.get_balance(payer.address()));
+        assert!(local.pending_transactions.iter().any(|tx| tx.hash == to_alice.hash));
+        assert!(local.get_balance("local") == 0 && local.get_balance("peer") > 0);
+
+        // Blocks we already have are skipped; nothing left to switch to
+        assert!(local.reorg_to(&branch, &mut ConsensusModules::default()).is_err());
+    }
+
+    #[test]
+    fn test_reorg_below_finalized_height_rejected() {
+        let fork = |local_blocks: usize, peer_blocks: usize| {
+            let mut local = test_chain();
+            let mut peer = test_chain();
+            local.state.params.finality_depth = 2;
+            peer.state.params.finality_depth = 2;
+            peer.chain[0] = local.chain[0].clone();
+            for _ in 0..local_blocks {
+                local.mine_block("local".to_string()).unwrap();
+            }
+            for _ in 0..peer_blocks {
+                peer.mine_block("peer".to_string()).unwrap();
+            }
+            (local, peer.chain[1..].to_vec())
+        };
+
+        // Three local blocks: #1 is final, so a heavier branch forking there is refused
+        let (mut local, branch) = fork(3, 5);
+        assert_eq!(local.finalized_height(), 1);
+        assert_eq!(local.get_stats().finalized_height, 1);
+        let tip = local.latest_block().hash.clone();
+        assert!(local.reorg_to(&branch, &mut ConsensusModules::default()).unwrap_err().contains("finalized"));
+        assert!(local.accept_external_block(branch[0].clone(), &mut ConsensusModules::default()).unwrap_err().contains("finalized"));
+        assert_eq!(local.latest_block().hash, tip);
+
+        // Two local blocks: nothing above genesis is final yet, so the branch wins
+        let (mut local, branch) = fork(2, 3);
+        assert_eq!(local.finalized_height(), 0);
+        assert_eq!(local.reorg_to(&branch, &mut ConsensusModules::default()).unwrap().len(), 2);
+        assert_eq!(local.total_blocks, 4);
+    }
+
+    #[test]
+    fn test_transactions_rejected_during_reorg() {
+        let mut chain = test_chain();
+        let transfer = Transaction::transfer("genesis".to_string(), "alice".to_string(), 5);
+
+        chain.begin_reorg();
+        let err = chain.add_local_transaction(transfer.clone()).unwrap_err();
+        assert_eq!(err, TransactionError::ReorgInProgress);
+        assert!(err.to_string().starts_with(REORG_IN_PROGRESS_ERR));
+        assert!(chain.pending_transactions.is_empty());
+        let (ok, failed, _) = chain.add_transactions_batch(vec![transfer.clone()]);
+        assert_eq!((ok, failed), (0, 1));
+
+        // The same submission goes through once the chain is consistent
+        chain.end_reorg();
+        assert_eq!(chain.add_local_transaction(transfer.clone()).unwrap(), transfer.hash);
+    }
+
+    /// Block store that records whether a reorg circuit is tripped when a
+    /// reorg truncates it
+    struct ReorgProbeStore {
+        inner: FileBlockStore,
+        circuit: ReorgCircuit,
+        tripped: Arc<std::sync::Mutex<Vec<bool>>>,
+    }
+
+    impl BlockStore for ReorgProbeStore {
+        fn name(&self) -> &'static str { "probe" }
+        fn append_block(&self, block: &Block) -> Result<(), String> { self.inner.append_block(block) }
+        fn truncate(&self, height: u64) -> Result<(), String> {
+            self.tripped.lock().unwrap().push(self.circuit.check().is_err());
+            self.inner.truncate(height)
+        }
+        fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, String> { self.inner.get_block_by_height(height) }
+        fn load_recent(&self, count: usize) -> Result<Vec<Block>, String> { self.inner.load_recent(count) }
+        fn save_state(&self, state: &ChainState, metadata: &ChainMetadata) -> Result<(), String> { self.inner.save_state(state, metadata) }
+        fn load_state(&self) -> Result<Option<(ChainState, ChainMetadata)>, String> { self.inner.load_state() }
+        fn save_undo(&self, undo: &[BlockUndo]) -> Result<(), String> { self.inner.save_undo(undo) }
+        fn load_undo(&self) -> Result<Vec<BlockUndo>, String> { self.inner.load_undo() }
+        fn put_contract_logs(&self, height: u64, logs: &[(String, ContractLog)]) -> Result<(), String> { self.inner.put_contract_logs(height, logs) }
+        fn contract_logs(&self, address: &str, from: u64, to: u64) -> Result<Vec<ContractLog>, String> { self.inner.contract_logs(address, from, to) }
+    }
+
+    #[test]
+    fn test_reorg_trips_shared_circuit() {
+        let dir = std::env::temp_dir().join(format!("edgeai-reorg-circuit-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut local = test_chain();
+        let mut peer = test_chain();
+        peer.chain[0] = local.chain[0].clone();
+        local.mine_block("local".to_string()).unwrap();
+        for _ in 0..2 {
+            peer.mine_block("peer".to_string()).unwrap();
+        }
+
+        // A handle taken before the reorg, as the API and P2P handlers hold one
+        let circuit = local.reorg_circuit();
+        let tripped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        local.block_store = Box::new(ReorgProbeStore {
+            inner: FileBlockStore::new(&dir),
+            circuit: circuit.clone(),
+            tripped: tripped.clone(),
+        });
+        assert!(circuit.check().is_ok());
+        local.reorg_to(&peer.chain[1..], &mut ConsensusModules::default()).unwrap();
+
+        assert_eq!(*tripped.lock().unwrap(), vec![true]);
+        assert!(circuit.check().is_ok());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_reorg_reverts_contracts_and_tx_index() {
+        const HIT_WAT: &str = r#"
+            (module
+              (import "env" "storage_set" (func $set (param i32 i32 i32 i32) (result i32)))
+              (import "env" "log" (func $log (param i32 i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "hit")
+              (func (export "hit")
+                (drop (call $set (i32.const 0) (i32.const 3) (i32.const 0) (i32.const 3)))
+                (call $log (i32.const 0) (i32.const 3))))
+        "#;
+        let dir = std::env::temp_dir().join(format!("edgeai-reorg-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut local = test_chain();
+        local.storage = Some(Storage::open(dir.to_str().unwrap()).unwrap());
+        let mut peer = test_chain();
+        peer.chain[0] = local.chain[0].clone();
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut local, &payer, 1_000_000_000);
+        fund(&mut peer, &payer, 1_000_000_000);
+
+        let deploy = Transaction::new(TransactionType::ContractDeploy, payer.address().to_string(), vec![], vec![],
+            Some(serde_json::json!({ "code": hex::encode(HIT_WAT) }).to_string()), 1, 100_000)
+            .signed_by(&payer);
+        let address = format!("0x{}", &deploy.hash[..40]);
+        let call = Transaction::contract_call(payer.address().to_string(),
+            serde_json::json!({ "contract": address, "function": "hit" }).to_string())
+            .with_nonce(1)
+            .signed_by(&payer);
+        local.add_transaction(deploy.clone()).unwrap();
+        local.mine_block("local".to_string()).unwrap();
+        local.add_transaction(call.clone()).unwrap();
+        local.mine_block("local".to_string()).unwrap();
+        assert_eq!(local.state.contracts.get_storage(&address, b"hit"), Some(b"hit".to_vec()));
+        // Contract storage is covered by the state root
+        let root = state_root(&local.state);
+        local.state.contracts.contracts.get_mut(&address).unwrap().storage.clear();
+        assert_ne!(state_root(&local.state), root);
+        local.state.contracts.contracts.get_mut(&address).unwrap().storage.insert(hex::encode("hit"), hex::encode("hit"));
+        assert_eq!(local.contract_logs(&address, None, None, None).len(), 1);
+        let storage = local.storage.as_ref().unwrap();
+        assert_eq!(storage.get_transaction_location(&call.hash).map(|loc| loc.block_index), Some(2));
+
+        for _ in 0..3 {
+            peer.mine_block("peer".to_string()).unwrap();
+        }
+        local.reorg_to(&peer.chain[1..], &mut ConsensusModules::default()).unwrap();
+
+        // Contract, storage and logs are gone, and so are the replaced transactions' index entries
+        assert!(local.state.contracts.get_contract(&address).is_none());
+        assert!(local.contract_logs(&address, None, None, None).is_empty());
+        let storage = local.storage.as_ref().unwrap();
+        assert!(storage.get_transaction_location(&deploy.hash).is_none());
+        assert!(storage.get_transaction_location(&call.hash).is_none());
+        assert_eq!(storage.get_block(2).map(|b| b.hash), Some(peer.chain[2].hash.clone()));
+        assert!(local.pending_transactions.iter().any(|tx| tx.hash == call.hash));
+        local.storage = None;
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_aborted_reorg_restores_pool() {
+        let mut local = test_chain();
+        let mut peer = test_chain();
+        peer.chain[0] = local.chain[0].clone();
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut local, &payer, 1_000_000);
+        fund(&mut peer, &payer, 1_000_000);
+        local.mine_block("local".to_string()).unwrap();
+        let tip = local.latest_block().hash.clone();
+
+        // The branch includes the pooled transfer, then fails on a tampered block
+        let to_alice = signed_transfer(&payer, "alice", 500, 0);
+        local.add_transaction(to_alice.clone()).unwrap();
+        peer.add_transaction(to_alice.clone()).unwrap();
+        peer.mine_block("peer".to_string()).unwrap();
+        peer.mine_block("peer".to_string()).unwrap();
+        let mut branch = peer.chain[1..].to_vec();
+        branch[1].transactions[0].outputs[0].amount += 1;
+
+        assert!(local.reorg_to(&branch, &mut ConsensusModules::default()).unwrap_err().contains("aborted"));
+        assert_eq!(local.latest_block().hash, tip);
+        assert_eq!(local.total_blocks, 2);
+        assert!(local.pending_transactions.iter().any(|tx| tx.hash == to_alice.hash));
+        assert_eq!(local.get_balance("alice"), 0);
+    }
+
+    #[test]
+    fn test_reorg_after_restart_uses_saved_undo() {
+        let dir = std::env::temp_dir().join(format!("edgeai-reorg-restart-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut local = test_chain();
+        local.block_store = Box::new(FileBlockStore::new(&dir));
+        let mut peer = test_chain();
+        peer.chain[0] = local.chain[0].clone();
+        local.mine_block("local".to_string()).unwrap();
+        local.save_to_disk();
+        for _ in 0..2 {
+            peer.mine_block("peer".to_string()).unwrap();
+        }
+
+        // Restart from what was saved
+        let store = FileBlockStore::new(&dir);
+        let (state, metadata) = store.load_state().unwrap().unwrap();
+        let mut restarted = test_chain();
+        restarted.chain = local.chain.clone();
+        restarted.state = state;
+        restarted.total_blocks = metadata.total_blocks;
+        restarted.difficulty = metadata.difficulty;
+        restarted.undo_log = Blockchain::load_undo(&store, metadata.total_blocks);
+        restarted.block_store = Box::new(store);
+
+        assert_eq!(restarted.reorg_to(&peer.chain[1..], &mut ConsensusModules::default()).unwrap().len(), 1);
+        assert_eq!(restarted.latest_block().hash, peer.latest_block().hash);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_governed_rewards_are_chain_state() {
+        use crate::consensus::parameters::{set_parameter, BLOCKCHAIN_PARAMETERS};
+
+        let mut local = test_chain();
+        let mut peer = test_chain();
+        peer.chain[0] = local.chain[0].clone();
+        local.mine_block("local".to_string()).unwrap();
+
+        // A changed reward is covered by the state root and used by the next block
+        let root = state_root(&local.state);
+        set_parameter(BLOCKCHAIN_PARAMETERS, &mut local, "blockchain", "block_reward", "500").unwrap();
+        set_parameter(BLOCKCHAIN_PARAMETERS, &mut local, "blockchain", "tx_pow_bits", "4").unwrap();
+        assert_ne!(state_root(&local.state), root);
+        let block = local.mine_block("local".to_string()).unwrap();
+        assert_eq!(block.transactions[0].total_output(), 500);
+
+        // Replacing the blocks reverts it with the rest of the state
+        for _ in 0..3 {
+            peer.mine_block("peer".to_string()).unwrap();
+        }
+        local.reorg_to(&peer.chain[1..], &mut ConsensusModules::default()).unwrap();
+        assert_eq!(local.state.params.block_reward, DEFAULT_BLOCK_REWARD);
+        assert_eq!(local.state.params.tx_pow_bits, 0);
+    }
+
+    #[test]
+    fn test_applied_blocks_accrue_the_block_reward() {
+        use crate::consensus::{StakingConfig, StakingManager};
+
+        let mut staking = StakingManager::new(StakingConfig { reward_epoch_blocks: 100, ..StakingConfig::default() });
+        let mut local = test_chain();
+        let mut peer = test_chain();
+        peer.chain[0] = local.chain[0].clone();
+        local.state.params.block_reward = 250;
+        peer.state.params.block_reward = 250;
+
+        // Locally mined and imported blocks both feed the pool, and a
+        // template's dry run doesn't
+        local.mine_block_within("local".to_string(), MiningBudget::UNLIMITED, &mut ConsensusModules::staking(&mut staking))
+            .unwrap()
+            .unwrap();
+        assert_eq!(staking.reward_pool, 250);
+        let block = peer.mine_block("peer".to_string()).unwrap();
+        let mut other = StakingManager::new(StakingConfig { reward_epoch_blocks: 100, ..StakingConfig::default() });
+        let mut fresh = test_chain();
+        fresh.chain[0] = peer.chain[0].clone();
+        fresh.state.params.block_reward = 250;
+        fresh.import_block(&block, &mut ConsensusModules::staking(&mut other)).unwrap();
+        assert_eq!(other.reward_pool, 250);
+    }
+
+    #[test]
+    fn test_pending_transfers_cannot_overspend() {
+        let mut chain = test_chain();
+        let transfer = |nonce: u64| Transaction::transfer("alice".to_string(), "bob".to_string(), 40).with_nonce(nonce);
+        let cost = 40 + transfer(0).fee();
+        chain.add_local_transaction(Transaction::transfer("genesis".to_string(), "alice".to_string(), 2 * cost + 10)).unwrap();
+        chain.mine_block("miner".to_string()).unwrap();
+
+        // Each transfer fits the balance alone; only the first two fit together
+        chain.add_local_transaction(transfer(0)).unwrap();
+        chain.add_local_transaction(transfer(1)).unwrap();
+        let err = chain.add_local_transaction(transfer(2)).unwrap_err();
+        assert_eq!(err, TransactionError::InsufficientBalance { have: 10, need: cost });
+        assert_eq!(chain.pending_transactions.pending_debit("alice", NATIVE_TOKEN), 2 * cost);
+
+        // Mining the pending transfers releases their debits
+        chain.mine_block("miner".to_string()).unwrap();
+        assert_eq!(chain.pending_transactions.pending_debit("alice", NATIVE_TOKEN), 0);
+        assert_eq!(chain.get_balance("alice"), 10);
+        assert_eq!(chain.get_balance("bob"), 80);
+    }
+
+    #[test]
+    fn test_transfer_memo_lookup() {
+        let mut chain = test_chain();
+        let tx = Transaction::transfer("genesis".to_string(), "exchange".to_string(), 25)
+            .with_memo(Some("deposit-4711".to_string()));
+        let hash = chain.add_local_transaction(tx).unwrap();
+        let other = Transaction::transfer("genesis".to_string(), "exchange".to_string(), 5).with_nonce(1);
+        chain.add_local_transaction(other).unwrap();
+
+        let mut txs = chain.select_transactions_for_block();
+        txs.insert(0, block_reward_for(&chain, "validator", &txs));
+        let block = Block::new(1, chain.latest_block().hash.clone(), txs, 1, "validator".to_string());
+        chain.apply_block(&block, &mut ConsensusModules::default()).unwrap();
+        chain.chain.push(block);
+
+        let found = chain.get_transactions_by_memo("deposit-4711", 10);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].hash, hash);
+        assert!(chain.get_transactions_by_memo("deposit-0000", 10).is_empty());
+
+        let long = Transaction::transfer("genesis".to_string(), "exchange".to_string(), 1)
+            .with_nonce(2)
+            .with_memo(Some("x".repeat(MAX_MEMO_LEN + 1)));
+        assert!(matches!(chain.add_local_transaction(long).unwrap_err(), TransactionError::MemoTooLong { .. }));
+    }
+
+    #[test]
+    fn test_add_transaction_requires_sender_signature() {
+        use crate::crypto::Wallet;
+
+        let mut chain = test_chain();
+        let victim = Wallet::new();
+        let attacker = Wallet::new();
+        let sender = victim.address().to_string();
+        chain.apply_transaction(&Transaction::transfer("genesis".to_string(), sender.clone(), 100_000), None).unwrap();
+        let signed_by = |wallet: &Wallet| {
+            let message = Transaction::create_transfer_signing_message(&sender, "mallory", 10, 0, None, None);
+            Transaction::transfer_signed(
+                sender.clone(), wallet.public_key_hex(), "mallory".to_string(), 10, 0, None, wallet.sign(message.as_bytes()),
+            )
+        };
+
+        // Unsigned, or signed by a key that isn't the sender's
+        let unsigned = Transaction::transfer(sender.clone(), "mallory".to_string(), 10);
+        assert_eq!(chain.add_transaction(unsigned).unwrap_err(), TransactionError::Unsigned);
+        assert_eq!(chain.add_transaction(signed_by(&attacker)).unwrap_err(), TransactionError::SenderMismatch);
+        let mut bad_signature = signed_by(&victim);
+        bad_signature.signature = signed_by(&attacker).signature;
+        assert_eq!(chain.add_transaction(bad_signature).unwrap_err(), TransactionError::InvalidSignature);
+        assert!(chain.pending_transactions.is_empty());
+
+        let tx = signed_by(&victim);
+        assert_eq!(chain.add_transaction(tx.clone()).unwrap(), tx.hash);
+
+        // Rewards are minted by the producer and carry no signature
+        assert!(Transaction::reward("miner".to_string(), 100, "block".to_string()).check_sender_signature().is_ok());
+    }
+
+    #[test]
+    fn test_batch_transfer_pays_all_recipients_or_none() {
+        use crate::blockchain::transaction::TRANSFER_GAS;
+        use crate::crypto::Wallet;
+
+        let mut chain = test_chain();
+        let wallet = Wallet::new();
+        let sender = wallet.address().to_string();
+        chain.apply_transaction(&Transaction::transfer("genesis".to_string(), sender.clone(), 1_000_000), None).unwrap();
+
+        let batch = |outputs: Vec<(String, u64)>, nonce: u64| {
+            let message = Transaction::create_batch_transfer_signing_message(&sender, &outputs, nonce, None, None);
+            Transaction::batch_transfer_signed(
+                sender.clone(), wallet.public_key_hex(), outputs, nonce, None, wallet.sign(message.as_bytes()),
+            )
+        };
+        let payroll = vec![("dev_a".to_string(), 100), ("dev_b".to_string(), 200), ("dev_c".to_string(), 300)];
+        let tx = batch(payroll, 0);
+        assert!(tx.verify());
+        assert_eq!(tx.fee(), 3 * TRANSFER_GAS);
+        assert_eq!(tx.gas_limit, tx.gas_used());
+        chain.apply_transaction(&tx, None).unwrap();
+        assert_eq!(chain.get_balance("dev_a"), 100);
+        assert_eq!(chain.get_balance("dev_b"), 200);
+        assert_eq!(chain.get_balance("dev_c"), 300);
+        assert_eq!(chain.get_balance(&sender), 1_000_000 - 600 - 3 * TRANSFER_GAS);
+        assert_eq!(chain.state.accounts[&sender].nonce, 1);
+
+        // Recipients can't be spliced to form another batch's message
+        let message = |outputs: &[(String, u64)], memo: Option<&str>| {
+            Transaction::create_batch_transfer_signing_message(&sender, outputs, 1, None, memo)
+        };
+        let two = [("x".to_string(), 1), ("y".to_string(), 2)];
+        assert_ne!(message(&two, None), message(&[("x=1:y".to_string(), 2)], None));
+        assert_ne!(message(&two[..1], Some("y=2")), message(&two, None));
+
+        // Tampering with any output breaks the signature
+        let mut tampered = batch(vec![("dev_a".to_string(), 1), ("dev_b".to_string(), 1)], 1);
+        tampered.outputs[1].amount = 2;
+        tampered.hash = tampered.calculate_hash();
+        assert!(!tampered.verify());
+
+        // Over budget: neither recipient is paid
+        let balance = chain.get_balance(&sender);
+        let over = batch(vec![("dev_a".to_string(), 1), ("dev_b".to_string(), balance)], 1);
+        assert!(matches!(
+            chain.add_transaction(over.clone()).unwrap_err(),
+            TransactionError::InsufficientBalance { .. }
+        ));
+        assert!(chain.apply_transaction(&over, None).is_err());
+        assert_eq!(chain.get_balance("dev_a"), 100);
+        assert_eq!(chain.get_balance("dev_b"), 200);
+        assert_eq!(chain.get_balance(&sender), balance);
+        assert_eq!(chain.state.accounts[&sender].nonce, 1);
+    }
+
+    /// Register `validator` with the minimum self-stake through a
+    /// ValidatorRegister transaction signed by a new funded operator
+    fn register_validator(chain: &mut Blockchain, staking: &mut StakingManager, validator: &str) -> crate::crypto::Wallet {
+        let operator = crate::crypto::Wallet::new();
+        let stake = staking.config.min_validator_stake;
+        fund(chain, &operator, stake);
+        let registration = ValidatorRegistration { commission_rate: 0.1, ..ValidatorRegistration::default() };
+        let tx = Transaction::validator_register(operator.address().to_string(), validator.to_string(), stake, &registration)
+            .signed_by(&operator);
+        chain.apply_transaction(&tx, Some(staking)).unwrap();
+        operator
+    }
+
+    #[test]
+    fn test_stake_transactions_drive_staking_manager() {
+        use std::sync::Arc;
+        use crate::consensus::clock::MockClock;
+        use crate::consensus::{StakingConfig, ValidatorStatus};
+
+        let mut chain = test_chain();
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let mut staking = StakingManager::with_clock(StakingConfig::default(), clock.clone());
+        register_validator(&mut chain, &mut staking, "validator");
+        register_validator(&mut chain, &mut staking, "jailed");
+        let self_staked = chain.state.total_staked;
+        chain.apply_transaction(&Transaction::reward("alice".to_string(), 1_000, "seed".to_string()), None).unwrap();
+
+        // Staking locks the balance and registers the delegation
+        let tx = Transaction::stake("alice".to_string(), "validator".to_string(), 600);
+        chain.apply_transaction(&tx, Some(&mut staking)).unwrap();
+        assert_eq!(chain.get_balance("alice"), 400);
+        assert_eq!(chain.get_account("alice").unwrap().staked_amount, 600);
+        assert_eq!(chain.state.total_staked, self_staked + 600);
+        assert_eq!(staking.delegations["alice"]["validator"].amount, 600);
+        assert_eq!(staking.validators["validator"].delegated_stake, 600);
+
+        // Only validators registered on chain can be staked to
+        let unknown = Transaction::stake("alice".to_string(), "nobody".to_string(), 200);
+        assert!(chain.apply_transaction(&unknown, Some(&mut staking)).is_err());
+        assert_eq!(chain.get_balance("alice"), 400);
+
+        // Whether a stake applies doesn't depend on the staking manager's view of the validator
+        staking.validators.get_mut("jailed").unwrap().status = ValidatorStatus::Jailed;
+        let tx = Transaction::stake("alice".to_string(), "jailed".to_string(), 200);
+        chain.apply_transaction(&tx, Some(&mut staking)).unwrap();
+        assert_eq!(chain.get_balance("alice"), 200);
+        assert!(!staking.delegations["alice"].contains_key("jailed"));
+
+        // Unstaking starts unbonding; the balance is credited at the release height
+        chain.state.params.unbonding_blocks = 5;
+        let release_height = chain.total_blocks + 5;
+        for (validator, amount) in [("validator", 600), ("jailed", 200)] {
+            let tx = Transaction::unstake("alice".to_string(), validator.to_string(), amount);
+            chain.apply_transaction(&tx, Some(&mut staking)).unwrap();
+        }
+        assert_eq!(chain.get_balance("alice"), 200);
+        assert_eq!(chain.get_account("alice").unwrap().staked_amount, 0);
+        assert_eq!(chain.state.total_staked, self_staked);
+        assert_eq!(chain.state.unbonding["alice"].len(), 2);
+        assert_eq!(staking.get_unbonding("alice").len(), 1);
+        let root = state_root(&chain.state);
+
+        chain.release_unbonding(release_height - 1, Some(&mut staking));
+        assert_eq!(chain.get_balance("alice"), 200);
+        chain.release_unbonding(release_height, Some(&mut staking));
+        assert_eq!(chain.get_balance("alice"), 1_000);
+        assert!(chain.state.unbonding.is_empty());
+        assert!(staking.get_unbonding("alice").is_empty());
+        assert_ne!(state_root(&chain.state), root);
+    }
+
+    #[test]
+    fn test_cancel_unbonding_rebonds_stake() {
+        use crate::consensus::StakingConfig;
+
+        let mut chain = test_chain();
+        let mut staking = StakingManager::new(StakingConfig::default());
+        register_validator(&mut chain, &mut staking, "validator");
+        let delegator = crate::crypto::Wallet::new();
+        let address = delegator.address().to_string();
+        fund(&mut chain, &delegator, 1_000);
+        let signed = |tx: Transaction, nonce: u64| tx.with_nonce(nonce).signed_by(&delegator);
+        chain.apply_transaction(&signed(Transaction::stake(address.clone(), "validator".to_string(), 1_000), 0), Some(&mut staking)).unwrap();
+        for (nonce, amount) in [(1, 400), (2, 600)] {
+            let tx = signed(Transaction::unstake(address.clone(), "validator".to_string(), amount), nonce);
+            chain.apply_transaction(&tx, Some(&mut staking)).unwrap();
+        }
+        let staked = chain.state.total_staked;
+
+        // Only a pending entry of that amount from that validator can be cancelled
+        let wrong = signed(Transaction::cancel_unbonding(address.clone(), "validator".to_string(), 500), 3);
+        assert!(chain.apply_transaction(&wrong, Some(&mut staking)).is_err());
+
+        let cancel = signed(Transaction::cancel_unbonding(address.clone(), "validator".to_string(), 600), 3);
+        chain.apply_transaction(&cancel, Some(&mut staking)).unwrap();
+        assert_eq!(chain.get_account(&address).unwrap().staked_amount, 600);
+        assert_eq!(chain.state.total_staked, staked + 600);
+        assert_eq!(chain.state.unbonding[&address].len(), 1);
+        assert_eq!(chain.state.unbonding[&address][0].amount, 400);
+        assert_eq!(staking.delegations[&address]["validator"].amount, 600);
+        assert_eq!(staking.get_unbonding(&address).len(), 1);
+
+        // Once released, the stake can no longer be re-bonded
+        chain.release_unbonding(u64::MAX, Some(&mut staking));
+        let late = signed(Transaction::cancel_unbonding(address.clone(), "validator".to_string(), 400), 4);
+        assert!(chain.apply_transaction(&late, Some(&mut staking)).is_err());
+        assert_eq!(chain.get_balance(&address), 400);
+    }
+
+    #[test]
+    fn test_validator_self_stake_locked_and_unbonded() {
+        use crate::consensus::StakingConfig;
+
+        let mut chain = test_chain();
+        let mut staking = StakingManager::new(StakingConfig::default());
+        let stake = staking.config.min_validator_stake;
+
+        // Registering needs the operator's signature and locks its balance
+        let operator = crate::crypto::Wallet::new();
+        fund(&mut chain, &operator, stake + 100);
+        let supply = chain.state.total_supply;
+        let registration = ValidatorRegistration { commission_rate: 0.1, ..ValidatorRegistration::default() };
+        let register = Transaction::validator_register(operator.address().to_string(), "validator".to_string(), stake, &registration);
+        assert!(chain.apply_transaction(&register, Some(&mut staking)).is_err());
+        chain.apply_transaction(&register.signed_by(&operator), Some(&mut staking)).unwrap();
+        assert_eq!(chain.get_balance(operator.address()), 100);
+        assert_eq!(chain.state.validator_bonds["validator"].self_stake, stake);
+        assert_eq!(chain.state.total_staked, stake);
+        assert_eq!(staking.validators["validator"].operator_address, operator.address());
+        assert_eq!(chain.state.total_supply, supply);
+
+        // Below the staking manager's minimum is refused
+        let small = Transaction::validator_register(operator.address().to_string(), "small".to_string(), 100, &registration)
+            .with_nonce(1)
+            .signed_by(&operator);
+        assert!(chain.apply_transaction(&small, Some(&mut staking)).is_err());
+
+        // Only the operator can unbond the self-stake
+        let intruder = crate::crypto::Wallet::new();
+        let stolen = Transaction::validator_unbond(intruder.address().to_string(), "validator".to_string(), stake)
+            .signed_by(&intruder);
+        assert!(chain.apply_transaction(&stolen, Some(&mut staking)).is_err());
+
+        // Unbonded self-stake returns to the operator at the release height, minting nothing
+        chain.state.params.unbonding_blocks = 3;
+        let release_height = chain.total_blocks + 3;
+        let unbond = Transaction::validator_unbond(operator.address().to_string(), "validator".to_string(), stake)
+            .with_nonce(1)
+            .signed_by(&operator);
+        chain.apply_transaction(&unbond, Some(&mut staking)).unwrap();
+        assert_eq!(chain.state.validator_bonds["validator"].self_stake, 0);
+        assert_eq!(chain.state.total_staked, 0);
+        assert_eq!(chain.get_balance(operator.address()), 100);
+        chain.release_unbonding(release_height, Some(&mut staking));
+        assert_eq!(chain.get_balance(operator.address()), stake + 100);
+        assert!(staking.unbonding_queue.is_empty());
+        assert_eq!(chain.state.total_supply, supply);
+    }
+
+    #[test]
+    fn test_slash_burns_delegated_stake() {
+        use crate::consensus::{SlashReason, StakingConfig};
+
+        let mut chain = test_chain();
+        let mut staking = StakingManager::new(StakingConfig { slash_double_sign: 1.0, ..StakingConfig::default() });
+        register_validator(&mut chain, &mut staking, "validator");
+        chain.apply_transaction(&Transaction::reward("alice".to_string(), 1_000, "seed".to_string()), None).unwrap();
+        chain.apply_transaction(&Transaction::stake("alice".to_string(), "validator".to_string(), 600), Some(&mut staking)).unwrap();
+        let supply = chain.state.total_supply;
+        let total_staked = chain.state.total_staked;
+
+        staking.slash("validator", SlashReason::DoubleSigning, 1).unwrap();
+        chain.apply_slash(staking.slash_history.last().unwrap());
+        assert_eq!(chain.get_account("alice").unwrap().staked_amount, 0);
+        assert_eq!(chain.state.validator_bonds["validator"].self_stake, 0);
+        assert_eq!(chain.state.total_staked, 0);
+        assert_eq!(chain.state.total_supply, supply - total_staked);
+        assert_eq!(chain.get_balance("alice"), 400);
+    }
+
+    #[test]
+    fn test_finish_block_records_validator_participation() {
+        use crate::consensus::StakingConfig;
+
+        let mut chain = test_chain();
+        let mut staking = StakingManager::new(StakingConfig::default());
+        register_validator(&mut chain, &mut staking, "v1");
+        register_validator(&mut chain, &mut staking, "v2");
+
+        // v1 produces its own block and then v2's; an outside miner produces the next
+        for (index, producer) in [(4, "v1"), (5, "v1"), (6, "miner")] {
+            chain.finish_block(index, Utc::now(), producer, &mut ConsensusModules::staking(&mut staking));
+        }
+        let v1 = staking.validator_performance("v1", 10).unwrap();
+        assert_eq!((v1.window, v1.uptime), (3, 2.0 / 3.0));
+        assert_eq!(staking.validators["v1"].last_block_signed, 5);
+        assert_eq!(staking.validator_performance("v2", 10).unwrap().uptime, 0.0);
+    }
+
+    #[test]
+    fn test_downtime_jailed_validator_unjailed_after_producing_a_block() {
+        use crate::consensus::{SlashReason, StakingConfig, ValidatorStatus};
+
+        let mut chain = test_chain();
+        let mut staking = StakingManager::new(StakingConfig::default());
+        register_validator(&mut chain, &mut staking, "validator");
+        let jail_until = staking.now() - chrono::Duration::seconds(1);
+        staking.validators.get_mut("validator").unwrap().jail(SlashReason::Downtime, jail_until, 50);
+
+        // Jail term over, but no block signed since: still jailed at the check
+        chain.finish_block(DOWNTIME_CHECK_INTERVAL, Utc::now(), "miner", &mut ConsensusModules::staking(&mut staking));
+        assert_eq!(staking.validators["validator"].status, ValidatorStatus::Jailed);
+
+        // Producing a block is signing again
+        chain.finish_block(DOWNTIME_CHECK_INTERVAL + 1, Utc::now(), "validator", &mut ConsensusModules::staking(&mut staking));
+        chain.finish_block(2 * DOWNTIME_CHECK_INTERVAL, Utc::now(), "miner", &mut ConsensusModules::staking(&mut staking));
+        assert_eq!(staking.validators["validator"].status, ValidatorStatus::Active);
+    }
+
+    #[test]
+    fn test_gas_fees_credited_to_validator() {
+        use crate::blockchain::transaction::{TRANSFER_GAS, DATA_PURCHASE_GAS};
+
+        let mut chain = test_chain();
+        let first = Transaction::transfer("genesis".to_string(), "alice".to_string(), 1_000);
+        let second = Transaction::transfer("genesis".to_string(), "bob".to_string(), 2_000).with_nonce(1);
+        let data = Transaction::data_contribution("seller".to_string(), "{\"temp\":21.5}".to_string(), "seller".to_string())
+            .with_reward_claim(chain.state.reward_policy.claim_for("{\"temp\":21.5}"));
+        chain.apply_transaction(&data, None).unwrap();
+        let data_hash = data.outputs[0].data_hash.clone().unwrap();
+        let purchase = Transaction::data_purchase("genesis".to_string(), "seller".to_string(), data_hash, 10);
+        chain.add_local_transaction(first).unwrap();
+        chain.add_local_transaction(second).unwrap();
+        chain.add_local_transaction(purchase).unwrap();
+
+        // A transfer that can't cover amount + fee is rejected
+        let broke = Transaction::transfer("alice".to_string(), "bob".to_string(), 1);
+        chain.apply_transaction(&Transaction::reward("alice".to_string(), 100, "seed".to_string()), None).unwrap();
+        assert!(chain.add_local_transaction(broke.clone()).is_err());
+        assert!(chain.apply_transaction(&broke, None).is_err());
+        let supply_before = chain.state.total_supply;
+
+        let txs = chain.build_block_transactions(1, "validator", Utc::now(), &mut ConsensusModules::default());
+        let fees = 2 * TRANSFER_GAS + DATA_PURCHASE_GAS;
+        assert_eq!(txs.len(), 4);
+        // The treasury takes its share of both the reward and the fees
+        let treasury_cut = chain.state.params.block_reward / 10 + fees / 10;
+        assert_eq!(txs[0].outputs[1].amount, fees - fees / 10);
+        assert_eq!(txs[0].outputs[2].recipient, TREASURY_ADDRESS);
+        assert_eq!(chain.treasury_balance(), treasury_cut);
+        assert_eq!(chain.get_balance("validator"), chain.state.params.block_reward + fees - treasury_cut);
+        assert_eq!(chain.get_balance("genesis"), 1_000_000_000 - 3_010 - fees);
+        // Fees move between accounts; only the block reward is new supply
+        assert_eq!(chain.state.total_supply, supply_before + chain.state.params.block_reward);
+    }
+
+    #[test]
+    fn test_difficulty_warmup_schedule() {
+        let mut chain = test_chain();
+        chain.state.params.initial_difficulty = 6;
+        chain.state.params.warmup_blocks = 4;
+
+        let bases: Vec<u64> = (0..6).map(|h| chain.base_difficulty(h)).collect();
+        assert_eq!(bases, vec![6, 5, 4, 3, 2, 2]);
+
+        // After the warm-up the chain's own difficulty takes over
+        chain.difficulty = 3;
+        assert_eq!(chain.base_difficulty(4), 3);
+        assert_eq!(chain.base_difficulty(100), 3);
+
+        // Mined blocks follow the schedule
+        chain.difficulty = 2;
+        chain.state.params.initial_difficulty = 3;
+        chain.state.params.warmup_blocks = 2;
+        let block = chain.mine_block("validator".to_string()).unwrap();
+        assert_eq!(block.index, 1);
+        assert_eq!(block.header.difficulty, poie_difficulty(3, &block.transactions));
+        assert!(block.hash.starts_with(&"0".repeat(block.header.difficulty as usize)));
+    }
+
+    #[test]
+    fn test_difficulty_retargets_toward_block_time() {
+        let mut chain = test_chain();
+        chain.difficulty = 2;
+        chain.state.params.target_block_secs = 10;
+        chain.state.params.retarget_interval = 4;
+        let mut timestamp = Utc::now() - chrono::Duration::days(1);
+
+        // Import `count` peer blocks spaced `spacing` seconds apart
+        let mut extend = |chain: &mut Blockchain, count: u64, spacing: i64| {
+            for _ in 0..count {
+                let index = chain.total_blocks;
+                let txs = vec![block_reward_for(chain, "peer", &[])];
+                let difficulty = poie_difficulty(chain.base_difficulty(index), &txs);
+                let mut block = Block::new(index, chain.latest_block().hash.clone(), txs, difficulty, "peer".to_string());
+                timestamp += chrono::Duration::seconds(spacing);
+                block.header.timestamp = timestamp;
+                set_state_root(chain, &mut block);
+                assert!(block.mine(difficulty, MiningBudget::UNLIMITED));
+                chain.accept_external_block(block, &mut ConsensusModules::default()).unwrap();
+            }
+        };
+
+        // 1s blocks against a 10s target: one step up per interval
+        extend(&mut chain, 7, 1);
+        assert_eq!(chain.total_blocks, 8);
+        assert_eq!(chain.difficulty, 3);
+        assert_eq!(chain.base_difficulty(chain.total_blocks), 3);
+        // The new difficulty is saved right away, not at the next periodic save
+        let (_, metadata) = chain.block_store.load_state().unwrap().unwrap();
+        assert_eq!(metadata.difficulty, 3);
+
+        // 60s blocks: back down, but never below 1
+        extend(&mut chain, 4, 60);
+        assert_eq!(chain.difficulty, 2);
+        extend(&mut chain, 8, 60);
+        assert_eq!(chain.difficulty, 1);
+        extend(&mut chain, 4, 60);
+        assert_eq!(chain.difficulty, 1);
+
+        // On target: no change
+        extend(&mut chain, 8, 10);
+        assert_eq!(chain.difficulty, 1);
+        assert_eq!(RetargetConfig { target_block_secs: 10, interval: 4 }.next_difficulty(2, 10.0), 2);
+    }
+
+    #[test]
+    fn test_accept_external_block() {
+        let mut chain = test_chain();
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut chain, &payer, 1_000_000);
+        let transfer = signed_transfer(&payer, "alice", 500, 0);
+        let reward = block_reward_for(&chain, "peer", std::slice::from_ref(&transfer));
+        let txs = vec![reward, transfer];
+        let difficulty = poie_difficulty(chain.base_difficulty(1), &txs);
+        let mut block = Block::new(1, chain.latest_block().hash.clone(), txs, difficulty, "peer".to_string());
+        set_state_root(&mut chain, &mut block);
+        assert!(block.mine(difficulty, MiningBudget::UNLIMITED));
+
+        // Wrong parent is rejected without touching state
+        let mut orphan = block.clone();
+        orphan.header.previous_hash = "f".repeat(64);
+        assert!(orphan.mine(difficulty, MiningBudget::UNLIMITED));
+        assert!(chain.accept_external_block(orphan, &mut ConsensusModules::default()).unwrap_err().contains("does not extend"));
+
+        // So is a block stamped too far ahead of network time
+        let mut early = block.clone();
+        early.header.timestamp = Utc::now() + chrono::Duration::minutes(10);
+        assert!(early.mine(difficulty, MiningBudget::UNLIMITED));
+        assert!(chain.import_block(&early, &mut ConsensusModules::default()).unwrap_err().contains("too far in the future"));
+
+        // A block past our next height asks for a sync instead
+        let mut future = block.clone();
+        future.index = 5;
+        assert!(future.mine(difficulty, MiningBudget::UNLIMITED));
+        assert!(chain.accept_external_block(future, &mut ConsensusModules::default()).unwrap_err().starts_with(BLOCK_AHEAD_ERR));
+        assert_eq!(chain.total_blocks, 1);
+
+        // Peer blocks are held to the chain's block limits
+        chain.state.params.max_block_bytes = 100;
+        assert!(chain.import_block(&block, &mut ConsensusModules::default()).unwrap_err().contains("bytes (max 100)"));
+        chain.state.params.max_block_bytes = DEFAULT_MAX_BLOCK_BYTES;
+
+        // The reward can't pay more than the block reward plus fees, no other
+        // system transaction may mint, and a failing transaction sinks the
+        // block without leaving any of it applied
+        let mine = |chain: &Blockchain, txs: Vec<Transaction>| {
+            let difficulty = poie_difficulty(chain.base_difficulty(1), &txs);
+            let mut block = Block::new(1, chain.latest_block().hash.clone(), txs, difficulty, "peer".to_string());
+            assert!(block.mine(difficulty, MiningBudget::UNLIMITED));
+            block
+        };
+        let greedy = Transaction::block_reward("peer".to_string(), 1_000, 0, 0.1, "Block 1 mining reward".to_string());
+        let overpaid = mine(&chain, vec![greedy, block.transactions[1].clone()]);
+        assert!(chain.import_block(&overpaid, &mut ConsensusModules::default()).unwrap_err().contains("reward does not pay"));
+        let minted = Transaction::reward("peer".to_string(), 1_000, "bonus".to_string());
+        let extra = mine(&chain, vec![block.transactions[0].clone(), minted]);
+        assert!(chain.import_block(&extra, &mut ConsensusModules::default()).unwrap_err().contains("Unexpected system transaction"));
+        let overdraft = signed_transfer(&payer, "bob", 10_000_000, 1);
+        let txs = vec![block.transactions[1].clone(), overdraft];
+        let failing = mine(&chain, [vec![block_reward_for(&chain, "peer", &txs)], txs].concat());
+        assert!(chain.import_block(&failing, &mut ConsensusModules::default()).unwrap_err().contains("failed to apply"));
+        assert_eq!(chain.get_balance("alice"), 0);
+        // So does a state root other than the one applying it produces
+        let mut forged = block.clone();
+        forged.header.state_root = "abc".to_string();
+        assert!(forged.mine(difficulty, MiningBudget::UNLIMITED));
+        assert!(chain.import_block(&forged, &mut ConsensusModules::default()).unwrap_err().contains("State root mismatch"));
+        // Or none at all
+        let mut rootless = block.clone();
+        rootless.header.state_root.clear();
+        assert!(rootless.mine(difficulty, MiningBudget::UNLIMITED));
+        assert!(chain.import_block(&rootless, &mut ConsensusModules::default()).unwrap_err().contains("has no state root"));
+        assert_eq!(chain.get_balance("alice"), 0);
+        assert_eq!(chain.total_blocks, 1);
+
+        chain.import_block(&block, &mut ConsensusModules::default()).unwrap();
+        assert_eq!(chain.total_blocks, 2);
+        assert_eq!(chain.latest_block().hash, block.hash);
+        assert_eq!(chain.get_balance("alice"), 500);
+        let fee = block.transactions[1].fee();
+        assert_eq!(chain.get_balance("peer") + chain.treasury_balance(), 100 + fee);
+        // The same block can't be applied twice
+        assert!(chain.import_block(&block, &mut ConsensusModules::default()).is_err());
+
+        // Unsigned transactions are only accepted from the chain itself
+        let txs = vec![Transaction::transfer(payer.address().to_string(), "alice".to_string(), 1).with_nonce(1)];
+        let difficulty = poie_difficulty(chain.base_difficulty(2), &txs);
+        let mut unsigned = Block::new(2, chain.latest_block().hash.clone(), txs, difficulty, "peer".to_string());
+        assert!(unsigned.mine(difficulty, MiningBudget::UNLIMITED));
+        assert!(chain.import_block(&unsigned, &mut ConsensusModules::default()).unwrap_err().contains("Unsigned transaction"));
+        assert_eq!(chain.total_blocks, 2);
+    }
+
+    #[test]
+    fn test_mined_blocks_leave_out_failing_transactions() {
+        let mut chain = test_chain();
+        let ok = Transaction::transfer("genesis".to_string(), "alice".to_string(), 10);
+        chain.add_local_transaction(ok.clone()).unwrap();
+        // Slipped past admission: the sender has nothing to send
+        let broke = Transaction::transfer("nobody".to_string(), "alice".to_string(), 10);
+        chain.pending_transactions.insert(broke.clone()).unwrap();
+
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        let hashes: Vec<&str> = block.transactions.iter().map(|tx| tx.hash.as_str()).collect();
+        assert!(hashes.contains(&ok.hash.as_str()));
+        assert!(!hashes.contains(&broke.hash.as_str()));
+        assert_eq!(block.transactions[0].total_output(), chain.state.params.block_reward + ok.fee());
+    }
+
+    #[test]
+    fn test_mined_blocks_use_network_adjusted_time() {
+        let mut chain = test_chain();
+        chain.set_clock_offset_ms(-3_600_000);
+        let block = chain.mine_block("miner".to_string()).unwrap();
+        let behind = Utc::now() - block.header.timestamp;
+        assert!(behind >= chrono::Duration::minutes(59) && behind <= chrono::Duration::minutes(61));
+    }
+
+    #[test]
+    fn test_duplicate_transactions_rejected() {
+        let mut chain = test_chain();
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut chain, &payer, 1_000_000);
+        let transfer = signed_transfer(&payer, "alice", 500, 0);
+        let peer_block = |chain: &mut Blockchain, index: u64, txs: Vec<Transaction>| {
+            let mut txs = txs;
+            txs.insert(0, block_reward_for(chain, "peer", &txs));
+            let difficulty = poie_difficulty(chain.base_difficulty(index), &txs);
+            let mut block = Block::new(index, chain.latest_block().hash.clone(), txs, difficulty, "peer".to_string());
+            set_state_root(chain, &mut block);
+            assert!(block.mine(difficulty, MiningBudget::UNLIMITED));
+            block
+        };
+
+        let duplicated = peer_block(&mut chain, 1, vec![transfer.clone(), transfer.clone()]);
+        assert!(chain.import_block(&duplicated, &mut ConsensusModules::default()).unwrap_err().contains("Duplicate transaction"));
+        assert_eq!(chain.get_balance("alice"), 0);
+
+        let block = peer_block(&mut chain, 1, vec![transfer.clone()]);
+        chain.import_block(&block, &mut ConsensusModules::default()).unwrap();
+        assert_eq!(chain.get_balance("alice"), 500);
+        // Replaying it in a descendant is caught before the nonce check
+        let replay = peer_block(&mut chain, 2, vec![transfer.clone()]);
+        assert!(chain.import_block(&replay, &mut ConsensusModules::default()).unwrap_err().contains("already included in block #1"));
+
+        // Locally mined blocks drop repeats instead
+        let contribution = Transaction::data_contribution("device_1".to_string(), "{\"temp\":21.5}".to_string(), "bob".to_string())
+            .with_reward_claim(chain.state.reward_policy.claim_for("{\"temp\":21.5}"));
+        chain.add_synthetic_transaction(contribution.clone()).unwrap();
+        chain.add_synthetic_transaction(contribution.clone()).unwrap();
+        let selected = chain.select_transactions_for_block();
+        assert_eq!(selected.iter().filter(|tx| tx.hash == contribution.hash).count(), 1);
+    }
+
+    #[test]
+    fn test_synthetic_transactions_signed_by_device_keys() {
+        let mut chain = test_chain();
+        let mut generator = crate::blockchain::mempool::MempoolManager::with_block_context(1, chain.state.reward_policy.clone());
+        // Devices hold no balance, so only their contributions are admitted
+        for height in 1.. {
+            for tx in generator.collect_for_block(height) {
+                assert!(tx.signature.is_none());
+                let _ = chain.add_synthetic_transaction(tx);
+            }
+            if !chain.synthetic_transactions.is_empty() {
+                break;
+            }
+        }
+        let selected = chain.select_transactions_for_block();
+        assert!(!selected.is_empty());
+        assert!(selected.iter().all(|tx| tx.check_sender_signature().is_ok()));
+    }
+
+    #[test]
+    fn test_state_dump_matches_header_root() {
+        let mut chain = test_chain();
+        chain.pending_transactions.insert(Transaction::transfer("genesis".to_string(), "bob".to_string(), 250)).unwrap();
+        let block = chain.mine_block("validator".to_string()).unwrap();
+        assert!(!block.header.state_root.is_empty());
+        assert!(block.verify());
+
+        let dump = chain.state_dump(Some(block.index)).unwrap();
+        assert_eq!(dump.block_hash, block.hash);
+        assert_eq!(dump.state_root, block.header.state_root);
+        assert!(dump.verify());
+        // Accounts are sorted, so the dump is byte-for-byte reproducible
+        assert!(dump.state.accounts.windows(2).all(|w| w[0].address < w[1].address));
+        assert_eq!(serde_json::to_string(&dump).unwrap(), serde_json::to_string(&chain.state_dump(None).unwrap()).unwrap());
+        assert!(dump.state.accounts_csv().lines().any(|l| l.starts_with("bob,250,")));
+
+        // Tampering with the dumped state breaks verification
+        let mut tampered = dump.clone();
+        tampered.state.accounts[0].balance += 1;
+        assert!(!tampered.verify());
+
+        // Only the tip's state is available
+        assert!(chain.state_dump(Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_block_store_backends_behave_identically() {
+        use crate::blockchain::block_store::SledBlockStore;
+
+        // Build the same three peer blocks for both backends
+        let mut reference = test_chain();
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut reference, &payer, 1_000_000);
+        let genesis = reference.latest_block().clone();
+        let mut blocks = Vec::new();
+        for i in 1..=3u64 {
+            let transfer = signed_transfer(&payer, "alice", i, i - 1);
+            let reward = block_reward_for(&reference, "peer", std::slice::from_ref(&transfer));
+            let txs = vec![reward, transfer];
+            let difficulty = poie_difficulty(reference.base_difficulty(i), &txs);
+            let mut block = Block::new(i, reference.latest_block().hash.clone(), txs, difficulty, "peer".to_string());
+            set_state_root(&mut reference, &mut block);
+            assert!(block.mine(difficulty, MiningBudget::UNLIMITED));
+            reference.import_block(&block, &mut ConsensusModules::default()).unwrap();
+            blocks.push(block);
+        }
+
+        let dir = std::env::temp_dir().join(format!("edgeai-block-store-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let backends: Vec<Box<dyn BlockStore>> = vec![
+            Box::new(FileBlockStore::new(&dir)),
+            Box::new(SledBlockStore::temporary().unwrap()),
+        ];
+
+        let mut results = Vec::new();
+        for backend in backends {
+            let mut chain = test_chain();
+            fund(&mut chain, &payer, 1_000_000);
+            chain.block_store = backend;
+            chain.chain = vec![genesis.clone()];
+            chain.persist_block(&genesis);
+            for block in &blocks {
+                chain.accept_external_block(block.clone(), &mut ConsensusModules::default()).unwrap();
+            }
+            chain.save_to_disk();
+
+            let store = &chain.block_store;
+            let recent: Vec<String> = store.load_recent(2).unwrap().into_iter().map(|b| b.hash).collect();
+            let second = store.get_block_by_height(2).unwrap().map(|b| b.hash);
+            assert!(store.get_block_by_height(9).unwrap().is_none());
+            let (state, metadata) = store.load_state().unwrap().unwrap();
+            results.push((recent, second, metadata.total_blocks, state.total_supply, state.accounts.len()));
+        }
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(results[0], results[1]);
+        let (recent, second, total_blocks, total_supply, _) = &results[0];
+        assert_eq!(recent, &vec![blocks[1].hash.clone(), blocks[2].hash.clone()]);
+        assert_eq!(second.as_ref(), Some(&blocks[1].hash));
+        assert_eq!(*total_blocks, 4);
+        assert_eq!(*total_supply, reference.state.total_supply);
+    }
+    #[test]
+    fn test_shutdown_flush_preserves_state_and_mempool() {
+        let dir = std::env::temp_dir().join(format!("edgeai-shutdown-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut chain = test_chain();
+        chain.block_store = Box::new(FileBlockStore::new(&dir));
+        let payer = crate::crypto::Wallet::new();
+        fund(&mut chain, &payer, 1_000_000);
+        chain.add_transaction(signed_transfer(&payer, "alice", 5, 0)).unwrap();
+        chain.mine_block("miner".to_string()).unwrap();
+        for nonce in 1..=2 {
+            chain.add_transaction(signed_transfer(&payer, "bob", 3, nonce)).unwrap();
+        }
+        let pending: Vec<String> = chain.get_pending_transactions(10).into_iter().map(|tx| tx.hash).collect();
+        assert_eq!(pending.len(), 2);
+        // An unsigned entry in the saved file is not trusted on restore
+        chain.add_local_transaction(Transaction::transfer("genesis".to_string(), "mallory".to_string(), 3)).unwrap();
+
+        // The block isn't on a save interval, so only the shutdown flush persists it
+        let mempool_path = dir.join(MEMPOOL_FILE);
+        assert_eq!(chain.flush_on_shutdown(&mempool_path), Ok(3));
+
+        let mut restarted = test_chain();
+        let (state, metadata) = FileBlockStore::new(&dir).load_state().unwrap().unwrap();
+        restarted.state = state;
+        restarted.total_blocks = metadata.total_blocks;
+        assert_eq!(restarted.total_blocks, chain.total_blocks);
+        assert_eq!(restarted.get_balance("alice"), 5);
+
+        assert_eq!(restarted.restore_mempool(&mempool_path), Ok(2));
+        let mut restored: Vec<String> = restarted.get_pending_transactions(10).into_iter().map(|tx| tx.hash).collect();
+        restored.sort();
+        let mut expected = pending;
+        expected.sort();
+        assert_eq!(restored, expected);
+        // Restored once; a second restart doesn't resurrect them
+        assert!(!mempool_path.exists());
+        assert_eq!(restarted.restore_mempool(&mempool_path), Ok(0));
+        let _ = fs::remove_dir_all(&dir);
+    }
+    #[test]
+    fn test_pagination_reads_pruned_blocks_from_store() {
+        let dir = std::env::temp_dir().join(format!("edgeai-pagination-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut chain = test_chain();
+        chain.block_store = Box::new(FileBlockStore::new(&dir));
+        let genesis = chain.latest_block().clone();
+        chain.persist_block(&genesis);
+
+        // Alice is paid in block 1, long before the in-memory window
+        chain.add_local_transaction(Transaction::transfer("genesis".to_string(), "alice".to_string(), 7)).unwrap();
+        let total = MAX_BLOCKS_IN_MEMORY as u64 + 30;
+        for _ in 1..total {
+            chain.mine_block("miner".to_string()).unwrap();
+        }
+        assert_eq!(chain.chain.len(), MAX_BLOCKS_IN_MEMORY);
+
+        // Pages spanning disk and memory come back in height order
+        let page = chain.blocks_page(0, 5);
+        assert_eq!(page.iter().map(|b| b.index).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(page[0].hash, genesis.hash);
+        let first_in_memory = chain.chain[0].index;
+        let page = chain.blocks_page(first_in_memory - 2, 4);
+        assert_eq!(
+            page.iter().map(|b| b.index).collect::<Vec<_>>(),
+            (first_in_memory - 2..first_in_memory + 2).collect::<Vec<_>>()
+        );
+        assert_eq!(chain.blocks_page(total - 1, 10).len(), 1);
+        assert!(chain.blocks_page(total, 10).is_empty());
+
+        let (txs, count) = chain.address_transactions_page("alice", 0, 10);
+        assert_eq!(count, 1);
+        assert_eq!(txs[0].outputs[0].amount, 7);
+        // Every block rewards the miner
+        let (txs, count) = chain.address_transactions_page("miner", 120, 100);
+        assert_eq!(count, total as usize - 1);
+        assert_eq!(txs.len(), count - 120);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_address_history_reads_indexed_blocks_from_disk() {
+        use crate::blockchain::block_store::ADDRESS_INDEX_FILE;
+
+        let dir = std::env::temp_dir().join(format!("edgeai-address-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut chain = test_chain();
+        chain.block_store = Box::new(FileBlockStore::new(&dir));
+        let genesis = chain.latest_block().clone();
+        chain.persist_block(&genesis);
+
+        // Bob is paid in blocks 1 and 2, then falls out of the in-memory window
+        for nonce in 0..2 {
+            chain.add_local_transaction(Transaction::transfer("genesis".to_string(), "bob".to_string(), 3 + nonce).with_nonce(nonce)).unwrap();
+            chain.mine_block("miner".to_string()).unwrap();
+        }
+        for _ in 0..MAX_BLOCKS_IN_MEMORY + 10 {
+            chain.mine_block("miner".to_string()).unwrap();
+        }
+        assert!(chain.chain[0].index > 2);
+        assert_eq!(chain.block_store.address_heights("bob").unwrap(), Some(vec![1, 2]));
+
+        let amounts = |chain: &Blockchain| -> Vec<u64> {
+            chain.get_transactions_for_address("bob").iter().map(|tx| tx.outputs[0].amount).collect()
+        };
+        assert_eq!(amounts(&chain), vec![3, 4]);
+
+        // A missing index is rebuilt from the log when the store is reopened
+        fs::remove_file(dir.join(ADDRESS_INDEX_FILE)).unwrap();
+        chain.block_store = Box::new(FileBlockStore::new(&dir));
+        assert_eq!(amounts(&chain), vec![3, 4]);
+        assert!(dir.join(ADDRESS_INDEX_FILE).exists());
+        assert!(chain.get_transactions_for_address("carol").is_empty());
+
+        // Truncating the log drops the index entries above the cut, on disk too
+        chain.block_store.truncate(2).unwrap();
+        assert_eq!(chain.block_store.address_heights("bob").unwrap(), Some(vec![1]));
+        let reopened = FileBlockStore::new(&dir);
+        assert_eq!(reopened.address_heights("bob").unwrap(), Some(vec![1]));
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
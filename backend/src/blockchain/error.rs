@@ -0,0 +1,68 @@
+//! Transaction admission errors
+//!
+//! Why a transaction was refused by the pending pool. The messages are the
+//! ones clients have always seen; the API maps each variant to an HTTP
+//! status and a stable error code.
+
+#![allow(dead_code)]
+
+use super::chain::REORG_IN_PROGRESS_ERR;
+
+/// Why a transaction was not admitted
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TransactionError {
+    #[error("{}, retry once the chain is consistent", REORG_IN_PROGRESS_ERR)]
+    ReorgInProgress,
+    #[error("Invalid transaction hash")]
+    InvalidHash,
+    #[error("Transaction is not signed")]
+    Unsigned,
+    #[error("Invalid transaction signature")]
+    InvalidSignature,
+    #[error("Sender does not match public key")]
+    SenderMismatch,
+    #[error("Invalid public key format")]
+    InvalidPublicKey,
+    #[error("Signature verification failed")]
+    MalformedSignature,
+    #[error("Request timestamp is in the future")]
+    RequestFromFuture,
+    #[error("Request expired. Max age is {max_age_secs} seconds")]
+    RequestExpired { max_age_secs: u64 },
+    #[error("Request has already been used")]
+    RequestReplayed,
+    #[error("Insufficient proof-of-work: {bits} leading zero bits required")]
+    InsufficientPow { bits: u32 },
+    #[error("Memo too long: {len} bytes (max {max})")]
+    MemoTooLong { len: usize, max: usize },
+    #[error("Transaction data too large: {len} bytes (max {max})")]
+    DataTooLarge { len: usize, max: usize },
+    #[error("Transfer has no outputs")]
+    NoOutputs,
+    #[error("Too many transfer outputs: {count} (max {max})")]
+    TooManyOutputs { count: usize, max: usize },
+    #[error("Transfer outputs must all use the same token")]
+    MixedTokens,
+    #[error("Transaction expired: valid until height {valid_until}, next block is {height}")]
+    Expired { valid_until: u64, height: u64 },
+    #[error("nonce too low: expected {expected}, got {got}")]
+    NonceTooLow { expected: u64, got: u64 },
+    #[error("nonce too high: expected {expected}, got {got}")]
+    NonceTooHigh { expected: u64, got: u64 },
+    #[error("Insufficient balance: has {have}, needs {need}")]
+    InsufficientBalance { have: u64, need: u64 },
+    #[error("Insufficient balance for fee: has {have}, needs {need}")]
+    InsufficientFeeBalance { have: u64, need: u64 },
+    #[error("Reward claim {claimed} does not match the reward policy ({expected})")]
+    WrongRewardClaim { claimed: u64, expected: u64 },
+    #[error("Duplicate transaction")]
+    Duplicate,
+    #[error("Mempool full: gas price must exceed {min_gas_price}")]
+    MempoolFull { min_gas_price: u64 },
+}
+
+impl From<TransactionError> for String {
+    fn from(e: TransactionError) -> Self {
+        e.to_string()
+    }
+}
@@ -0,0 +1,123 @@
+//! Chain event stream
+//!
+//! `EventBus` fans out block and mempool events to any number of subscribers
+//! (the WebSocket API) over a bounded `tokio::sync::broadcast` channel.
+//! Publishing never blocks: a subscriber that falls more than
+//! `EVENT_CHANNEL_CAPACITY` events behind misses them and is told it lagged.
+
+#![allow(dead_code)]
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::blockchain::block::Block;
+use crate::blockchain::transaction::{Transaction, TransactionType};
+
+/// Events buffered per subscriber before it is considered lagging
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Event categories clients can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Blocks,
+    Transactions,
+}
+
+/// A block appended to the chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockEvent {
+    pub index: u64,
+    pub hash: String,
+    pub previous_hash: String,
+    pub timestamp: DateTime<Utc>,
+    pub validator: String,
+    pub difficulty: u64,
+    pub tx_count: usize,
+}
+
+/// A transaction admitted to the mempool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionEvent {
+    pub hash: String,
+    pub tx_type: TransactionType,
+    pub sender: String,
+    pub amount: u64,
+    pub gas_price: u64,
+}
+
+/// Event pushed to subscribers, serialized as `{"type": ..., "data": ...}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum ChainEvent {
+    Block(BlockEvent),
+    Transaction(TransactionEvent),
+}
+
+impl ChainEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            ChainEvent::Block(_) => EventKind::Blocks,
+            ChainEvent::Transaction(_) => EventKind::Transactions,
+        }
+    }
+}
+
+impl From<&Block> for ChainEvent {
+    fn from(block: &Block) -> Self {
+        ChainEvent::Block(BlockEvent {
+            index: block.index,
+            hash: block.hash.clone(),
+            previous_hash: block.header.previous_hash.clone(),
+            timestamp: block.header.timestamp,
+            validator: block.validator.clone(),
+            difficulty: block.header.difficulty,
+            tx_count: block.transactions.len(),
+        })
+    }
+}
+
+impl From<&Transaction> for ChainEvent {
+    fn from(tx: &Transaction) -> Self {
+        ChainEvent::Transaction(TransactionEvent {
+            hash: tx.hash.clone(),
+            tx_type: tx.tx_type.clone(),
+            sender: tx.sender.clone(),
+            amount: tx.outputs.iter().map(|o| o.amount).sum(),
+            gas_price: tx.gas_price,
+        })
+    }
+}
+
+/// Broadcast sender for chain events; clones share the same channel
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ChainEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        EventBus { sender }
+    }
+
+    /// Send an event to current subscribers; a no-op when there are none
+    pub fn publish(&self, event: ChainEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ChainEvent> {
+        self.sender.subscribe()
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(EVENT_CHANNEL_CAPACITY)
+    }
+}
@@ -0,0 +1,344 @@
+//! Network genesis
+//!
+//! A fresh node builds its initial state from `genesis.json` in the data
+//! directory (or `EDGEAI_GENESIS_FILE`): funded accounts, total supply, the
+//! initial validator set, the staking and governance parameters and the
+//! consensus parameters (`params`). Without the file the built-in testnet
+//! genesis is used. A genesis whose balances don't add up to its total supply
+//! is refused at startup.
+
+#![allow(dead_code)]
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::chain::{Account, ChainState, ValidatorBond};
+use crate::blockchain::params::ChainParams;
+use crate::blockchain::reward_policy::RewardPolicy;
+use crate::consensus::data_quality::NoveltyCache;
+use crate::consensus::{GovernanceConfig, StakingConfig, StakingManager, ValidatorDescription};
+use crate::contracts::ContractStore;
+
+/// Genesis file name inside the data directory
+pub const GENESIS_FILE: &str = "genesis.json";
+
+/// Path of the genesis file (`EDGEAI_GENESIS_FILE`, default `<data_dir>/genesis.json`)
+pub fn genesis_path_from_env(data_dir: &Path) -> PathBuf {
+    std::env::var("EDGEAI_GENESIS_FILE")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_dir.join(GENESIS_FILE))
+}
+
+fn default_reputation_score() -> f64 {
+    50.0
+}
+
+/// An account funded at genesis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenesisAccount {
+    pub address: String,
+    pub balance: u64,
+    #[serde(default = "default_reputation_score")]
+    pub reputation_score: f64,
+}
+
+/// A validator in the initial validator set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenesisValidator {
+    pub address: String,
+    /// Operator address; defaults to `<address>_operator`
+    #[serde(default)]
+    pub operator: Option<String>,
+    pub moniker: String,
+    #[serde(default)]
+    pub website: Option<String>,
+    #[serde(default)]
+    pub details: Option<String>,
+    pub stake: u64,
+    pub commission: f64,
+}
+
+impl GenesisValidator {
+    /// Operator address, `<address>_operator` unless set
+    pub fn operator(&self) -> String {
+        self.operator.clone().unwrap_or_else(|| format!("{}_operator", self.address))
+    }
+}
+
+/// Initial state and parameters of a network
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Genesis {
+    pub total_supply: u64,
+    pub accounts: Vec<GenesisAccount>,
+    #[serde(default)]
+    pub validators: Vec<GenesisValidator>,
+    #[serde(default)]
+    pub staking: StakingConfig,
+    #[serde(default)]
+    pub governance: GovernanceConfig,
+    /// Consensus parameters; built-in defaults when omitted
+    #[serde(default)]
+    pub params: ChainParams,
+    /// Unix time block 0 is stamped with
+    #[serde(default)]
+    pub genesis_time: i64,
+}
+
+impl Default for Genesis {
+    /// The testnet genesis: a `genesis` treasury account, 18 simulated IoT
+    /// devices with 100 EDGE each and five foundation validators
+    fn default() -> Self {
+        let simulated_devices = [
+            "edge_node_001", "edge_node_002", "edge_node_003",
+            "edge_node_004", "edge_node_005", "edge_node_006",
+            "edge_node_007", "edge_node_008", "edge_node_009",
+            "edge_node_010", "factory_hub_a", "factory_hub_b",
+            "city_gateway", "agri_node_1", "med_device_1",
+            "power_grid_01", "transit_hub", "warehouse_sys",
+        ];
+        let total_supply = 1_000_000_000;
+        let device_balance = 100;
+
+        let validators = [
+            ("edge_validator_foundation", "EdgeAI Foundation", "Official foundation validator node", 15_000_000, 0.05),
+            ("edge_validator_iot_hub", "IoT Network Hub", "High-performance edge computing node", 12_000_000, 0.08),
+            ("edge_validator_datastream", "DataStream Validator", "Specialized in medical IoT data", 9_500_000, 0.10),
+            ("edge_validator_smartcity", "Smart City Node", "Urban infrastructure data processing", 8_200_000, 0.07),
+            ("edge_validator_green", "Green Energy Validator", "Renewable energy monitoring network", 7_100_000, 0.06),
+        ]
+        .into_iter()
+        .map(|(address, moniker, details, stake, commission)| GenesisValidator {
+            address: address.to_string(),
+            operator: None,
+            moniker: moniker.to_string(),
+            website: Some(format!("https://{}.edgeai.network", address)),
+            details: Some(details.to_string()),
+            stake,
+            commission,
+        })
+        .collect::<Vec<_>>();
+
+        // The validators' self-stake is part of the supply
+        let staked: u64 = validators.iter().map(|v| v.stake).sum();
+        let mut accounts = vec![GenesisAccount {
+            address: "genesis".to_string(),
+            balance: total_supply - staked - device_balance * simulated_devices.len() as u64,
+            reputation_score: 100.0,
+        }];
+        accounts.extend(simulated_devices.iter().map(|device| GenesisAccount {
+            address: device.to_string(),
+            balance: device_balance,
+            reputation_score: default_reputation_score(),
+        }));
+
+        Genesis {
+            total_supply,
+            accounts,
+            validators,
+            staking: StakingConfig::default(),
+            governance: GovernanceConfig::default(),
+            params: ChainParams::default(),
+            genesis_time: 0,
+        }
+    }
+}
+
+impl Genesis {
+    /// Load and validate the genesis at `path`, or the default genesis when
+    /// the file doesn't exist
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let genesis = match std::fs::read_to_string(path) {
+            Ok(json) => {
+                let genesis: Genesis = serde_json::from_str(&json)
+                    .map_err(|e| format!("Failed to parse genesis {}: {}", path.display(), e))?;
+                info!("Loaded genesis from {}", path.display());
+                genesis
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                info!("No genesis file at {}, using the built-in testnet genesis", path.display());
+                Genesis::default()
+            }
+            Err(e) => return Err(format!("Failed to read genesis {}: {}", path.display(), e)),
+        };
+        genesis.validate()?;
+        Ok(genesis)
+    }
+
+    /// Check the genesis is self-consistent
+    pub fn validate(&self) -> Result<(), String> {
+        let mut addresses = HashSet::new();
+        let mut total: u64 = 0;
+        for account in &self.accounts {
+            if !addresses.insert(account.address.as_str()) {
+                return Err(format!("Duplicate genesis account {}", account.address));
+            }
+            total = total
+                .checked_add(account.balance)
+                .ok_or("Genesis balances overflow")?;
+        }
+        for validator in &self.validators {
+            total = total
+                .checked_add(validator.stake)
+                .ok_or("Genesis balances overflow")?;
+        }
+        if total != self.total_supply {
+            return Err(format!(
+                "Genesis balances and validator stakes sum to {} but total_supply is {}",
+                total, self.total_supply
+            ));
+        }
+
+        let mut validators = HashSet::new();
+        for validator in &self.validators {
+            if !validators.insert(validator.address.as_str()) {
+                return Err(format!("Duplicate genesis validator {}", validator.address));
+            }
+            if validator.stake < self.staking.min_validator_stake {
+                return Err(format!(
+                    "Genesis validator {} stakes {}, below the minimum {}",
+                    validator.address, validator.stake, self.staking.min_validator_stake
+                ));
+            }
+            let (min_commission, max_commission) = self.staking.commission_range;
+            if validator.commission < min_commission || validator.commission > max_commission {
+                return Err(format!(
+                    "Genesis validator {} commission {} is outside {}-{}",
+                    validator.address, validator.commission, min_commission, max_commission
+                ));
+            }
+        }
+        self.params.validate()
+    }
+
+    /// Time block 0 is stamped with
+    pub fn time(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.genesis_time, 0).unwrap_or_default()
+    }
+
+    /// Account state at height 0, with the validators' self-stake locked
+    pub fn chain_state(&self) -> ChainState {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|a| {
+                let mut account = Account::new(a.address.clone());
+                account.balance = a.balance;
+                account.reputation_score = a.reputation_score;
+                (a.address.clone(), account)
+            })
+            .collect();
+
+        ChainState {
+            accounts,
+            data_registry: HashMap::new(),
+            total_supply: self.total_supply,
+            total_staked: self.validators.iter().map(|v| v.stake).sum(),
+            device_owners: HashMap::new(),
+            device_types: HashMap::new(),
+            novelty: NoveltyCache::new(),
+            treasury: 0,
+            unbonding: HashMap::new(),
+            validator_bonds: self
+                .validators
+                .iter()
+                .map(|v| (v.address.clone(), ValidatorBond { operator: v.operator(), self_stake: v.stake }))
+                .collect(),
+            data_access: HashMap::new(),
+            device_bonds: HashMap::new(),
+            contracts: ContractStore::default(),
+            reward_policy: RewardPolicy::default(),
+            params: self.params.clone(),
+        }
+    }
+
+    /// Staking manager with the genesis validator set registered
+    pub fn staking_manager(&self) -> Result<StakingManager, String> {
+        let mut staking = StakingManager::new(self.staking.clone());
+        for validator in &self.validators {
+            let description = ValidatorDescription {
+                moniker: validator.moniker.clone(),
+                identity: None,
+                website: validator.website.clone(),
+                security_contact: None,
+                details: validator.details.clone(),
+            };
+            staking
+                .register_validator(validator.address.clone(), validator.operator(), validator.stake, validator.commission, description)
+                .map_err(|e| format!("Failed to register genesis validator {}: {}", validator.address, e))?;
+        }
+        info!("Registered {} genesis validators", self.validators.len());
+        Ok(staking)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_genesis_is_consistent() {
+        let genesis = Genesis::default();
+        assert!(genesis.validate().is_ok());
+        let staking = genesis.staking_manager().unwrap();
+        assert_eq!(staking.validators.len(), 5);
+        assert_eq!(genesis.chain_state().accounts.len(), 19);
+    }
+
+    #[test]
+    fn test_custom_genesis_builds_chain_state() {
+        let dir = std::env::temp_dir().join(format!("edgeai-genesis-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(GENESIS_FILE);
+        assert_eq!(Genesis::load(&path).unwrap().total_supply, Genesis::default().total_supply);
+
+        let json = serde_json::json!({
+            "total_supply": 25_000,
+            "accounts": [
+                { "address": "treasury", "balance": 4_000, "reputation_score": 90.0 },
+                { "address": "alice", "balance": 1_000 }
+            ],
+            "validators": [
+                { "address": "val1", "moniker": "Validator One", "stake": 20_000, "commission": 0.1 }
+            ],
+            "params": { "finality_depth": 8 }
+        });
+        std::fs::write(&path, json.to_string()).unwrap();
+        let genesis = Genesis::load(&path).unwrap();
+
+        let state = genesis.chain_state();
+        assert_eq!(state.total_supply, 25_000);
+        assert_eq!(state.total_staked, 20_000);
+        assert_eq!(state.validator_bonds["val1"].operator, "val1_operator");
+        assert_eq!(state.accounts.len(), 2);
+        assert_eq!(state.accounts["treasury"].balance, 4_000);
+        assert_eq!(state.accounts["treasury"].reputation_score, 90.0);
+        assert_eq!(state.accounts["alice"].balance, 1_000);
+        assert_eq!(state.accounts["alice"].reputation_score, default_reputation_score());
+        assert!(state.data_registry.is_empty());
+        assert_eq!(state.params.finality_depth, 8);
+
+        let staking = genesis.staking_manager().unwrap();
+        assert_eq!(staking.validators["val1"].operator_address, "val1_operator");
+        assert_eq!(staking.validators["val1"].self_stake, 20_000);
+
+        // Out of range consensus parameters are refused
+        let mut bad = json.clone();
+        bad["params"]["finality_depth"] = serde_json::json!(0);
+        std::fs::write(&path, bad.to_string()).unwrap();
+        assert!(Genesis::load(&path).unwrap_err().contains("finality_depth"));
+
+        // Balances that don't add up to the supply are refused
+        let mut bad = json.clone();
+        bad["total_supply"] = serde_json::json!(26_000);
+        std::fs::write(&path, bad.to_string()).unwrap();
+        let err = Genesis::load(&path).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(err, "Genesis balances and validator stakes sum to 25000 but total_supply is 26000");
+    }
+}
@@ -4,13 +4,263 @@
 //! from connected IoT devices and network peers before block inclusion.
 //!
 //! External devices can submit transactions via the `/api/transactions/submit` endpoint.
-//! The mempool validates and queues these transactions for block inclusion.
+//! Submitted transactions are kept in `TxPool`, ordered by gas price and then
+//! arrival. `MempoolManager` is a synthetic load generator for testnet demos and
+//! only runs when `EDGEAI_SIMULATION_MODE` is enabled.
 
 #![allow(dead_code)]
 
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::OnceLock;
+
 use chrono::Utc;
 use sha2::{Sha256, Digest};
-use crate::blockchain::transaction::{Transaction, TransactionType, TxOutput};
+use crate::blockchain::reward_policy::RewardPolicy;
+use crate::blockchain::error::TransactionError;
+use crate::blockchain::transaction::{Transaction, TransactionType, TxOutput, NATIVE_TOKEN};
+use crate::crypto::Wallet;
+
+/// Default maximum number of transactions held in the pool
+pub const DEFAULT_MEMPOOL_CAPACITY: usize = 10_000;
+
+/// Pool capacity from `EDGEAI_MEMPOOL_CAPACITY`
+pub fn mempool_capacity_from_env() -> usize {
+    std::env::var("EDGEAI_MEMPOOL_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|c| *c > 0)
+        .unwrap_or(DEFAULT_MEMPOOL_CAPACITY)
+}
+
+/// Whether the synthetic load generator runs (`EDGEAI_SIMULATION_MODE`)
+pub fn simulation_mode_from_env() -> bool {
+    std::env::var("EDGEAI_SIMULATION_MODE")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+// ============================================================================
+// TX POOL — submitted transactions awaiting inclusion
+// ============================================================================
+
+/// Priority key: highest gas price first, then earliest arrival
+type PriorityKey = (Reverse<u64>, u64, String);
+
+#[derive(Clone)]
+struct PooledTx {
+    tx: Transaction,
+    seq: u64,
+}
+
+impl PooledTx {
+    fn key(&self) -> PriorityKey {
+        (Reverse(self.tx.gas_price), self.seq, self.tx.hash.clone())
+    }
+}
+
+/// Amounts a transaction will debit from its sender once mined, per token
+fn sender_debits(tx: &Transaction) -> Vec<(&str, u64)> {
+    let fee = tx.fee();
+    match tx.tx_type {
+        TransactionType::Transfer => {
+            let token = tx.outputs.first().map(|o| o.token_symbol()).unwrap_or(NATIVE_TOKEN);
+            if token == NATIVE_TOKEN {
+                vec![(NATIVE_TOKEN, tx.total_output_for(token).saturating_add(fee))]
+            } else {
+                vec![(token, tx.total_output_for(token)), (NATIVE_TOKEN, fee)]
+            }
+        }
+        TransactionType::DataPurchase => vec![(NATIVE_TOKEN, tx.total_output().saturating_add(fee))],
+        _ => Vec::new(),
+    }
+}
+
+/// Pending transactions ordered by gas price (descending) then arrival time.
+/// Deduplicated by hash; when full, the lowest-fee transaction is evicted.
+/// Tracks what each sender's pooled transactions will spend so admission can
+/// check balances net of transactions not yet mined.
+#[derive(Clone)]
+pub struct TxPool {
+    capacity: usize,
+    next_seq: u64,
+    txs: HashMap<String, PooledTx>,
+    by_priority: BTreeSet<PriorityKey>,
+    debits: HashMap<(String, String), u64>,
+}
+
+impl Default for TxPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEMPOOL_CAPACITY)
+    }
+}
+
+impl TxPool {
+    pub fn new(capacity: usize) -> Self {
+        TxPool {
+            capacity: capacity.max(1),
+            next_seq: 0,
+            txs: HashMap::new(),
+            by_priority: BTreeSet::new(),
+            debits: HashMap::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.txs.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Transaction> {
+        self.txs.get(hash).map(|p| &p.tx)
+    }
+
+    /// Total `token` amount the sender's pooled transactions will spend
+    pub fn pending_debit(&self, sender: &str, token: &str) -> u64 {
+        self.debits
+            .get(&(sender.to_string(), token.to_string()))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Transactions in priority order
+    pub fn iter(&self) -> impl Iterator<Item = &Transaction> {
+        self.by_priority.iter().map(move |(_, _, hash)| &self.txs[hash].tx)
+    }
+
+    /// Insert a transaction. Returns the hash of the transaction evicted to
+    /// make room, if any.
+    pub fn insert(&mut self, tx: Transaction) -> Result<Option<String>, TransactionError> {
+        if self.txs.contains_key(&tx.hash) {
+            return Err(TransactionError::Duplicate);
+        }
+
+        let mut evicted = None;
+        if self.txs.len() >= self.capacity {
+            let (Reverse(lowest_price), _, lowest_hash) = self.by_priority
+                .last()
+                .cloned()
+                .expect("full pool has entries");
+            if tx.gas_price <= lowest_price {
+                return Err(TransactionError::MempoolFull { min_gas_price: lowest_price });
+            }
+            self.remove(&lowest_hash);
+            evicted = Some(lowest_hash);
+        }
+
+        for (token, amount) in sender_debits(&tx) {
+            let debit = self.debits.entry((tx.sender.clone(), token.to_string())).or_insert(0);
+            *debit = debit.saturating_add(amount);
+        }
+        let pooled = PooledTx { tx, seq: self.next_seq };
+        self.next_seq += 1;
+        self.by_priority.insert(pooled.key());
+        self.txs.insert(pooled.tx.hash.clone(), pooled);
+        Ok(evicted)
+    }
+
+    pub fn remove(&mut self, hash: &str) -> Option<Transaction> {
+        let pooled = self.txs.remove(hash)?;
+        self.by_priority.remove(&pooled.key());
+        for (token, amount) in sender_debits(&pooled.tx) {
+            let key = (pooled.tx.sender.clone(), token.to_string());
+            if let Some(debit) = self.debits.get_mut(&key) {
+                *debit = debit.saturating_sub(amount);
+                if *debit == 0 {
+                    self.debits.remove(&key);
+                }
+            }
+        }
+        Some(pooled.tx)
+    }
+
+    /// Keep only the transactions matching `keep`
+    pub fn retain(&mut self, mut keep: impl FnMut(&Transaction) -> bool) {
+        let dropped: Vec<String> = self.txs
+            .values()
+            .filter(|p| !keep(&p.tx))
+            .map(|p| p.tx.hash.clone())
+            .collect();
+        for hash in dropped {
+            self.remove(&hash);
+        }
+    }
+
+    /// Drain up to `batch_size` of the highest-priority transactions.
+    /// A sender's transactions are always released in nonce order, so a
+    /// high-fee transaction waits for its sender's earlier ones.
+    pub fn collect_pending(&mut self, batch_size: usize) -> Vec<Transaction> {
+        self.collect_pending_within(batch_size, usize::MAX)
+    }
+
+    /// Like `collect_pending`, but stops before the selected transactions'
+    /// serialized size would exceed `max_bytes`
+    pub fn collect_pending_within(&mut self, batch_size: usize, max_bytes: usize) -> Vec<Transaction> {
+        // Per-sender queues ordered by (nonce, arrival)
+        let mut queues: HashMap<&str, BTreeMap<(u64, u64), &str>> = HashMap::new();
+        for p in self.txs.values() {
+            queues.entry(p.tx.sender.as_str())
+                .or_default()
+                .insert((p.tx.nonce, p.seq), p.tx.hash.as_str());
+        }
+
+        let mut selected: Vec<String> = Vec::new();
+        let mut deferred: HashSet<&str> = HashSet::new();
+        let mut bytes = 0usize;
+        // Whether `hash` still fits in the byte budget, counting it if so
+        let mut fits = |hash: &str| {
+            let size = self.txs[hash].tx.serialized_size();
+            if bytes.saturating_add(size) > max_bytes {
+                return false;
+            }
+            bytes += size;
+            true
+        };
+        'select: for (_, _, hash) in &self.by_priority {
+            if selected.len() >= batch_size {
+                break;
+            }
+            let sender = self.txs[hash].tx.sender.as_str();
+            let queue = queues.get_mut(sender).expect("sender queued");
+            if queue.first_key_value().map(|(_, h)| *h) != Some(hash.as_str()) {
+                deferred.insert(hash.as_str());
+                continue;
+            }
+            if !fits(hash) {
+                break;
+            }
+            queue.pop_first();
+            selected.push(hash.clone());
+            // Release this sender's higher-priority transactions that were waiting
+            while selected.len() < batch_size {
+                match queue.first_key_value() {
+                    Some((_, next)) if deferred.contains(*next) => {
+                        if !fits(next) {
+                            break 'select;
+                        }
+                        deferred.remove(*next);
+                        selected.push(next.to_string());
+                        queue.pop_first();
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        selected.iter().filter_map(|hash| self.remove(hash)).collect()
+    }
+}
 
 // ============================================================================
 // PRNG — lightweight deterministic generator for reproducible ordering
@@ -70,18 +320,37 @@ impl Rng {
 // NETWORK TOPOLOGY — device population behind addresses
 // ============================================================================
 
-/// Derive a stable device address from a category index and device ordinal.
-fn device_addr(category: u8, ordinal: u16) -> String {
+/// Derive a stable device key from a category index and device ordinal.
+/// The key is public by construction; it only lets the node sign the
+/// simulated traffic so peers verify it like any other transaction.
+fn device_wallet(category: u8, ordinal: u16) -> Wallet {
     let mut hasher = Sha256::new();
     hasher.update([category]);
     hasher.update(ordinal.to_le_bytes());
     hasher.update(b"edgeai-device-v1");
-    let hash = hasher.finalize();
-    format!("0x{}", hex::encode(&hash[..20]))
+    Wallet::from_secret_key(&hex::encode(hasher.finalize())).expect("sha256 digest is a valid secret key")
+}
+
+/// Wallet of every simulated device, derived once
+fn device_wallets() -> &'static [(Wallet, &'static str, &'static str)] {
+    static DEVICES: OnceLock<Vec<(Wallet, &'static str, &'static str)>> = OnceLock::new();
+    DEVICES.get_or_init(build_device_wallets)
+}
+
+/// Signing wallet of a simulated device address
+pub fn simulated_device_wallet(address: &str) -> Option<&'static Wallet> {
+    device_wallets().iter().map(|(wallet, _, _)| wallet).find(|wallet| wallet.address() == address)
 }
 
 /// Pre-compute the full device address table (called once per block).
 fn build_device_table() -> Vec<(String, &'static str, &'static str)> {
+    device_wallets()
+        .iter()
+        .map(|(wallet, category, device_class)| (wallet.address().to_string(), *category, *device_class))
+        .collect()
+}
+
+fn build_device_wallets() -> Vec<(Wallet, &'static str, &'static str)> {
     // (address, category, device_class)
     let specs: &[(&str, &str, u16)] = &[
         // Smart City
@@ -148,7 +417,7 @@ fn build_device_table() -> Vec<(String, &'static str, &'static str)> {
     let mut cat_idx: u8 = 0;
     for &(category, device_class, count) in specs {
         for ord in 0..count {
-            table.push((device_addr(cat_idx, ord), category, device_class));
+            table.push((device_wallet(cat_idx, ord), category, device_class));
         }
         cat_idx += 1;
     }
@@ -195,11 +464,14 @@ pub struct MempoolManager {
     rng: Rng,
     seq: u64,
     devices: Vec<(String, &'static str, &'static str)>,
+    /// Rewards claimed by generated data contributions
+    reward_policy: RewardPolicy,
 }
 
 impl MempoolManager {
-    /// Create a mempool context for the given block height.
-    pub fn with_block_context(block_idx: u64) -> Self {
+    /// Create a mempool context for the given block height, claiming
+    /// contribution rewards under `reward_policy`.
+    pub fn with_block_context(block_idx: u64, reward_policy: RewardPolicy) -> Self {
         let seed = block_idx
             .wrapping_mul(6364136223846793005)
             .wrapping_add(Utc::now().timestamp() as u64);
@@ -208,6 +480,7 @@ impl MempoolManager {
             rng: Rng::new(seed),
             seq: 0,
             devices: build_device_table(),
+            reward_policy,
         }
     }
 
@@ -309,17 +582,17 @@ impl MempoolManager {
 
         let data = format!(
             r#"{{"d":"{}","c":"{}","r":"{}","lat":{:.4},"lng":{:.4},"t":{},"q":{:.3},"sz":{},"ts":{}}}"#,
-            &addr[2..10], category, region,
+            &addr[4..12], category, region,
             lat + self.rng.range_f64(-0.05, 0.05),
             lng + self.rng.range_f64(-0.05, 0.05),
             telemetry, quality, data_size, Utc::now().timestamp()
         );
 
-        let reward = (10 + data_size / 10) as f64 * quality;
         let output = TxOutput {
-            amount: reward as u64,
+            amount: self.reward_policy.claim_for(&data),
             recipient: addr.clone(),
             data_hash: Some(format!("0x{:016x}", self.rng.next_u64())),
+            token: None,
         };
 
         Transaction::new(
@@ -354,11 +627,12 @@ impl MempoolManager {
             amount: amt,
             recipient: dst.clone(),
             data_hash: None,
+            token: None,
         };
 
         let data = format!(
             r#"{{"op":"transfer","to":"{}","amt":{},"ref":"{}","ts":{}}}"#,
-            &dst[2..10], amt, reason, Utc::now().timestamp()
+            &dst[4..12], amt, reason, Utc::now().timestamp()
         );
 
         Transaction::new(
@@ -393,11 +667,12 @@ impl MempoolManager {
             amount: price,
             recipient: seller.clone(),
             data_hash: Some(format!("0x{:016x}", self.rng.next_u64())),
+            token: None,
         };
 
         let data = format!(
             r#"{{"op":"purchase","seller":"{}","dtype":"{}","price":{},"hours":{},"ts":{}}}"#,
-            &seller[2..10], dtype, price, hours, Utc::now().timestamp()
+            &seller[4..12], dtype, price, hours, Utc::now().timestamp()
         );
 
         Transaction::new(
@@ -439,11 +714,12 @@ impl MempoolManager {
             amount: cost,
             recipient: provider.clone(),
             data_hash: Some(format!("0x{:016x}", self.rng.next_u64())),
+            token: None,
         };
 
         let data = format!(
             r#"{{"op":"inference","provider":"{}","model":"{}","cu":{},"cost":{},"ts":{}}}"#,
-            &provider[2..10], model, cu, cost, Utc::now().timestamp()
+            &provider[4..12], model, cu, cost, Utc::now().timestamp()
         );
 
         Transaction::new(
@@ -705,3 +981,59 @@ impl MempoolManager {
         format!(r#"{{"v":{:.2},"st":"{}"}}"#, self.rng.range_f64(0.0, 100.0), ["ok","warn","err"][self.rng.usize(3)])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(sender: &str, gas_price: u64, nonce: u64) -> Transaction {
+        let outputs = vec![TxOutput { amount: 1, recipient: "bob".to_string(), data_hash: None, token: None }];
+        Transaction::new(TransactionType::Transfer, sender.to_string(), vec![], outputs, None, gas_price, 21_000)
+            .with_nonce(nonce)
+    }
+
+    #[test]
+    fn test_fee_priority_ordering() {
+        let mut pool = TxPool::new(10);
+        let low = tx("alice", 1, 0);
+        let high = tx("bob", 5, 0);
+        let mid_first = tx("carol", 3, 0);
+        let mid_second = tx("dave", 3, 0);
+        for t in [&low, &high, &mid_first, &mid_second] {
+            pool.insert(t.clone()).unwrap();
+        }
+        assert!(pool.insert(high.clone()).is_err(), "duplicates are rejected");
+
+        let order: Vec<String> = pool.collect_pending(3).into_iter().map(|t| t.hash).collect();
+        assert_eq!(order, vec![high.hash, mid_first.hash, mid_second.hash]);
+        assert_eq!(pool.len(), 1);
+
+        // A sender's later nonce waits for its earlier one, even at a higher fee
+        let first = tx("erin", 1, 0);
+        let second = tx("erin", 9, 1);
+        pool.insert(second.clone()).unwrap();
+        pool.insert(first.clone()).unwrap();
+        let order: Vec<String> = pool.collect_pending(10).into_iter().map(|t| t.hash).collect();
+        assert_eq!(order, vec![low.hash, first.hash, second.hash]);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_eviction() {
+        let mut pool = TxPool::new(2);
+        let cheap = tx("alice", 1, 0);
+        let mid = tx("bob", 2, 0);
+        pool.insert(cheap.clone()).unwrap();
+        pool.insert(mid.clone()).unwrap();
+
+        // Not paying more than the cheapest transaction is rejected
+        assert!(pool.insert(tx("carol", 1, 0)).is_err());
+
+        let rich = tx("dave", 4, 0);
+        assert_eq!(pool.insert(rich.clone()).unwrap(), Some(cheap.hash.clone()));
+        assert_eq!(pool.len(), 2);
+        assert!(!pool.contains(&cheap.hash));
+        let order: Vec<&str> = pool.iter().map(|t| t.hash.as_str()).collect();
+        assert_eq!(order, vec![rich.hash.as_str(), mid.hash.as_str()]);
+    }
+}
@@ -10,11 +10,21 @@ pub mod mempool;
 pub mod storage;
 pub mod cold_storage;
 pub mod cold_blocks;
+pub mod tx_trace;
+pub mod block_store;
+pub mod state_dump;
+pub mod events;
+pub mod benchmark;
+pub mod genesis;
+pub mod reward_policy;
+pub mod params;
+pub mod error;
 
 // Core blockchain exports - only export what's actually used externally
-pub use block::Block;
+pub use block::{Block, MiningBudget};
 pub use transaction::{Transaction, TransactionType};
-pub use chain::Blockchain;
+pub use chain::{Blockchain, ReorgCircuit, BLOCK_AHEAD_ERR, MEMPOOL_FILE, STATE_SAVE_INTERVAL};
+pub use error::TransactionError;
 pub use mempool::MempoolManager;
 pub use storage::DbStats;
 pub use cold_storage::{ColdStorage, ColdStorageStats, MigrationResult};
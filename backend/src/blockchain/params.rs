@@ -0,0 +1,157 @@
+//! Consensus parameters
+//!
+//! Values every node on a network must agree on to validate the same blocks.
+//! They come from the `params` section of the genesis file, live in the chain
+//! state (so they are persisted, covered by the state root and reverted with
+//! a reorg) and are changed only by executed governance proposals (module
+//! `blockchain`). Node-local settings stay in environment variables.
+
+#![allow(dead_code)]
+
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::chain::{
+    BlockLimits, RetargetConfig, WarmupSchedule, DEFAULT_BLOCK_REWARD, DEFAULT_DATA_REGISTRY_CAP, DEFAULT_DATA_REWARD_BASE, DEFAULT_DATA_ROYALTY_SHARE, DEFAULT_FINALITY_DEPTH,
+    DEFAULT_MAX_BLOCK_BYTES, DEFAULT_MAX_TX_DATA_BYTES, DEFAULT_RETARGET_INTERVAL, DEFAULT_TARGET_BLOCK_SECS,
+    DEFAULT_MAX_BOND_MULTIPLIER, DEFAULT_TREASURY_SHARE, DEFAULT_UNBONDING_BLOCKS, MAX_REORG_DEPTH, MAX_RETARGET_DIFFICULTY, MAX_TXS_PER_BLOCK,
+    POIE_BASE_DIFFICULTY,
+};
+
+/// Most leading zero bits the anti-spam transaction proof-of-work may require
+pub const MAX_TX_POW_BITS: u32 = 32;
+
+/// Consensus parameters of a network
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChainParams {
+    /// Blocks built on top of a block before it is final (1 to `MAX_REORG_DEPTH`)
+    pub finality_depth: u64,
+    /// Share of each data contribution reward paid to the device's owner (0.0-1.0)
+    pub owner_reward_share: f64,
+    /// Share of block rewards and fees paid to the treasury (0.0-1.0)
+    pub treasury_share: f64,
+    /// Share of each data resale paid to the original contributor (0.0-1.0)
+    pub data_royalty_share: f64,
+    /// Block time difficulty retargeting aims for, in seconds
+    pub target_block_secs: u64,
+    /// Blocks between difficulty retargets (0 = fixed difficulty)
+    pub retarget_interval: u64,
+    /// Base difficulty at genesis when a warm-up is configured
+    pub initial_difficulty: u64,
+    /// Blocks over which difficulty ramps to the normal value (0 = no warm-up)
+    pub warmup_blocks: u64,
+    /// Transactions per block, excluding the reward transaction
+    pub max_txs_per_block: usize,
+    /// Summed serialized size of a block's transactions, excluding the reward transaction
+    pub max_block_bytes: usize,
+    /// Size of a single transaction's `data` field
+    pub max_tx_data_bytes: usize,
+    /// Blocks unstaked funds and released device bonds stay locked before
+    /// they are credited back
+    pub unbonding_blocks: u64,
+    /// Bond a device needs to earn contribution rewards (0 = no bond required)
+    pub min_device_bond: u64,
+    /// Cap on the reward multiplier a device earns by bonding more than the minimum
+    pub max_bond_multiplier: f64,
+    /// Native tokens minted to the producer of each block
+    pub block_reward: u64,
+    /// Base reward for a data contribution
+    pub data_reward_base: u64,
+    /// Leading zero bits the anti-spam proof-of-work of submitted
+    /// transactions needs (0 = disabled)
+    pub tx_pow_bits: u32,
+    /// Entries kept in the data registry; the least purchased, oldest are
+    /// evicted beyond it (0 = unbounded)
+    pub data_registry_cap: usize,
+}
+
+impl Default for ChainParams {
+    fn default() -> Self {
+        ChainParams {
+            finality_depth: DEFAULT_FINALITY_DEPTH,
+            owner_reward_share: 0.0,
+            treasury_share: DEFAULT_TREASURY_SHARE,
+            data_royalty_share: DEFAULT_DATA_ROYALTY_SHARE,
+            target_block_secs: DEFAULT_TARGET_BLOCK_SECS,
+            retarget_interval: DEFAULT_RETARGET_INTERVAL,
+            initial_difficulty: POIE_BASE_DIFFICULTY,
+            warmup_blocks: 0,
+            max_txs_per_block: MAX_TXS_PER_BLOCK,
+            max_block_bytes: DEFAULT_MAX_BLOCK_BYTES,
+            max_tx_data_bytes: DEFAULT_MAX_TX_DATA_BYTES,
+            unbonding_blocks: DEFAULT_UNBONDING_BLOCKS,
+            min_device_bond: 0,
+            max_bond_multiplier: DEFAULT_MAX_BOND_MULTIPLIER,
+            block_reward: DEFAULT_BLOCK_REWARD,
+            data_reward_base: DEFAULT_DATA_REWARD_BASE,
+            tx_pow_bits: 0,
+            data_registry_cap: DEFAULT_DATA_REGISTRY_CAP,
+        }
+    }
+}
+
+impl ChainParams {
+    /// Check every parameter is in range
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=MAX_REORG_DEPTH).contains(&self.finality_depth) {
+            return Err(format!("finality_depth must be between 1 and {}", MAX_REORG_DEPTH));
+        }
+        if !(0.0..=1.0).contains(&self.owner_reward_share) {
+            return Err("owner_reward_share must be between 0 and 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.treasury_share) {
+            return Err("treasury_share must be between 0 and 1".to_string());
+        }
+        if !(0.0..=1.0).contains(&self.data_royalty_share) {
+            return Err("data_royalty_share must be between 0 and 1".to_string());
+        }
+        if self.target_block_secs == 0 {
+            return Err("target_block_secs must be positive".to_string());
+        }
+        if !(1..=MAX_RETARGET_DIFFICULTY).contains(&self.initial_difficulty) {
+            return Err(format!("initial_difficulty must be between 1 and {}", MAX_RETARGET_DIFFICULTY));
+        }
+        if self.max_txs_per_block == 0 || self.max_block_bytes == 0 || self.max_tx_data_bytes == 0 {
+            return Err("Block limits must be positive".to_string());
+        }
+        if self.block_reward == 0 || self.data_reward_base == 0 {
+            return Err("block_reward and data_reward_base must be positive".to_string());
+        }
+        if self.tx_pow_bits > MAX_TX_POW_BITS {
+            return Err(format!("tx_pow_bits must be at most {}", MAX_TX_POW_BITS));
+        }
+        if !(1.0..).contains(&self.max_bond_multiplier) {
+            return Err("max_bond_multiplier must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Reward multiplier for a device bonding `bond`: the bond as a multiple
+    /// of the minimum, capped at `max_bond_multiplier`. Always 1 when no bond
+    /// is required.
+    pub fn bond_reward_multiplier(&self, bond: u64) -> f64 {
+        if self.min_device_bond == 0 {
+            return 1.0;
+        }
+        (bond as f64 / self.min_device_bond as f64).min(self.max_bond_multiplier)
+    }
+
+    /// Difficulty ramp for the first blocks of the chain
+    pub fn warmup(&self) -> WarmupSchedule {
+        WarmupSchedule { initial_difficulty: self.initial_difficulty, blocks: self.warmup_blocks }
+    }
+
+    /// Transaction count and size limits for blocks
+    pub fn limits(&self) -> BlockLimits {
+        BlockLimits {
+            max_txs_per_block: self.max_txs_per_block,
+            max_block_bytes: self.max_block_bytes,
+            max_tx_data_bytes: self.max_tx_data_bytes,
+        }
+    }
+
+    /// Block-time based difficulty adjustment
+    pub fn retarget(&self) -> RetargetConfig {
+        RetargetConfig { target_block_secs: self.target_block_secs, interval: self.retarget_interval }
+    }
+}
@@ -0,0 +1,112 @@
+//! Data contribution reward policy
+//!
+//! A submission's base reward is a flat amount, plus one EDGE per
+//! `bytes_per_unit` bytes of payload, plus a per-category bonus. The
+//! category's multiplier is applied when the contribution is processed, on
+//! top of the quality and novelty scores, so governance can steer rewards
+//! toward scarce data types (module `rewards`, parameters `base_reward`,
+//! `bytes_per_unit`, `<Category>.bonus` and `<Category>.multiplier`). The
+//! policy lives in the chain state, and a contribution must claim exactly
+//! the base reward it sets.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Category used for payloads without a known category
+pub const DEFAULT_CATEGORY: &str = "General";
+
+/// Reward adjustments for one data category
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CategoryReward {
+    /// Added to the base reward at submission
+    pub bonus: u64,
+    /// Applied to the reward when the contribution is processed
+    pub multiplier: f64,
+}
+
+impl CategoryReward {
+    const fn new(bonus: u64) -> Self {
+        CategoryReward { bonus, multiplier: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardPolicy {
+    /// Flat reward for every submission
+    pub base_reward: u64,
+    /// Payload bytes per extra EDGE of reward
+    pub bytes_per_unit: u64,
+    /// Known categories; submissions in other categories are refused
+    pub categories: BTreeMap<String, CategoryReward>,
+}
+
+impl Default for RewardPolicy {
+    fn default() -> Self {
+        let categories = [
+            ("Healthcare", CategoryReward::new(20)),
+            ("Manufacturing", CategoryReward::new(15)),
+            ("Energy", CategoryReward::new(15)),
+            ("Agriculture", CategoryReward::new(10)),
+            ("SmartCity", CategoryReward::new(5)),
+            ("Logistics", CategoryReward::new(5)),
+            ("EdgeAI", CategoryReward::new(5)),
+            (DEFAULT_CATEGORY, CategoryReward::new(5)),
+        ];
+        RewardPolicy {
+            base_reward: 30,
+            bytes_per_unit: 20,
+            categories: categories.into_iter().map(|(name, reward)| (name.to_string(), reward)).collect(),
+        }
+    }
+}
+
+impl RewardPolicy {
+    pub fn is_known_category(&self, category: &str) -> bool {
+        self.categories.contains_key(category)
+    }
+
+    /// Names of the known categories
+    pub fn category_names(&self) -> Vec<&str> {
+        self.categories.keys().map(String::as_str).collect()
+    }
+
+    fn category(&self, category: &str) -> CategoryReward {
+        self.categories
+            .get(category)
+            .or_else(|| self.categories.get(DEFAULT_CATEGORY))
+            .copied()
+            .unwrap_or(CategoryReward::new(0))
+    }
+
+    /// Reward claimed by a submission of `data_size` bytes in `category`
+    pub fn base_reward(&self, category: &str, data_size: u64) -> u64 {
+        self.base_reward + data_size / self.bytes_per_unit.max(1) + self.category(category).bonus
+    }
+
+    /// Base reward a contribution carrying `data` must claim: the payload's
+    /// category and size decide it, so a sender can't pick its own reward
+    pub fn claim_for(&self, data: &str) -> u64 {
+        let category = contribution_category(data).unwrap_or_default();
+        self.base_reward(&category, data.len() as u64)
+    }
+
+    /// Multiplier applied to contributions in `category`
+    pub fn multiplier(&self, category: &str) -> f64 {
+        self.category(category).multiplier
+    }
+
+    /// Reward expected for a submission before quality and novelty scoring
+    pub fn expected_reward(&self, category: &str, data_size: u64) -> u64 {
+        (self.base_reward(category, data_size) as f64 * self.multiplier(category)) as u64
+    }
+}
+
+/// Category of a contribution payload: the `category` field of submitted
+/// telemetry, or `c` in the compact synthetic format
+pub fn contribution_category(data: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(data).ok()?;
+    value.get("category").or_else(|| value.get("c"))?.as_str().map(str::to_string)
+}
@@ -0,0 +1,156 @@
+//! Verifiable chain state dumps
+//!
+//! Produces a deterministic snapshot of every account, data registry entry,
+//! device owner and device type, sorted by key, the treasury, pending
+//! unbonding, validator self-stake, device bonds, recent device submissions,
+//! data access granted by purchases, deployed contracts with their storage,
+//! the reward policy and the consensus parameters, together with its state
+//! root. The state root is the SHA-256 of the canonical JSON encoding
+//! of `CanonicalState` and is stored in the header of each locally produced
+//! block, so a dump can be checked against the block it was taken at. Nodes
+//! reject peer blocks whose root differs from the state they produce.
+
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::blockchain::chain::{ChainState, DataEntry, DeviceBond, PendingUnbond, ValidatorBond};
+use crate::blockchain::params::ChainParams;
+use crate::blockchain::reward_policy::RewardPolicy;
+use crate::consensus::data_quality::NoveltyCache;
+use crate::consensus::DeviceType;
+use crate::contracts::DeployedContract;
+
+/// Account fields with token balances in key order
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountRecord {
+    pub address: String,
+    pub balance: u64,
+    pub nonce: u64,
+    pub data_contributions: u64,
+    pub reputation_score: f64,
+    pub staked_amount: u64,
+    pub token_balances: BTreeMap<String, u64>,
+}
+
+/// Chain state in canonical order; the state root is computed over this
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanonicalState {
+    pub total_supply: u64,
+    pub total_staked: u64,
+    /// Sorted by address
+    pub accounts: Vec<AccountRecord>,
+    /// Sorted by data hash
+    pub data_registry: Vec<DataEntry>,
+    /// Device address to owner address
+    pub device_owners: BTreeMap<String, String>,
+    /// Device address to the type its telemetry is scored as
+    pub device_types: BTreeMap<String, DeviceType>,
+    /// Native tokens held by the treasury
+    pub treasury: u64,
+    /// Account to its pending unbonding entries, oldest first
+    pub unbonding: BTreeMap<String, Vec<PendingUnbond>>,
+    /// Validator address to its operator and self-stake
+    pub validator_bonds: BTreeMap<String, ValidatorBond>,
+    /// Device address to its owner's bond
+    pub device_bonds: BTreeMap<String, DeviceBond>,
+    /// Recent submissions per device that contribution rewards are scored against
+    pub novelty: NoveltyCache,
+    /// Buyer to the data hashes it has been granted access to
+    pub data_access: BTreeMap<String, Vec<String>>,
+    /// Contracts by address with their storage; bytecode is covered by each
+    /// contract's code hash
+    pub contracts: BTreeMap<String, DeployedContract>,
+    pub reward_policy: RewardPolicy,
+    pub params: ChainParams,
+}
+
+impl CanonicalState {
+    pub fn from_state(state: &ChainState) -> Self {
+        let mut accounts: Vec<AccountRecord> = state.accounts
+            .values()
+            .map(|a| AccountRecord {
+                address: a.address.clone(),
+                balance: a.balance,
+                nonce: a.nonce,
+                data_contributions: a.data_contributions,
+                reputation_score: a.reputation_score,
+                staked_amount: a.staked_amount,
+                token_balances: a.token_balances.iter().map(|(k, v)| (k.clone(), *v)).collect(),
+            })
+            .collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+
+        let mut data_registry: Vec<DataEntry> = state.data_registry.values().cloned().collect();
+        data_registry.sort_by(|a, b| a.hash.cmp(&b.hash));
+
+        CanonicalState {
+            total_supply: state.total_supply,
+            total_staked: state.total_staked,
+            accounts,
+            data_registry,
+            device_owners: state.device_owners.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            device_types: state.device_types.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            treasury: state.treasury,
+            unbonding: state.unbonding.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            validator_bonds: state.validator_bonds.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            device_bonds: state.device_bonds.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            novelty: state.novelty.clone(),
+            data_access: state.data_access.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            contracts: state.contracts.contracts.clone(),
+            reward_policy: state.reward_policy.clone(),
+            params: state.params.clone(),
+        }
+    }
+
+    /// SHA-256 of the canonical JSON encoding
+    pub fn root(&self) -> String {
+        let bytes = serde_json::to_vec(self).expect("canonical state serializes");
+        hex::encode(Sha256::digest(&bytes))
+    }
+
+    /// Accounts as CSV; token balances are `SYMBOL:amount` pairs joined by `;`
+    pub fn accounts_csv(&self) -> String {
+        let mut csv = String::from(
+            "address,balance,nonce,data_contributions,reputation_score,staked_amount,token_balances\n",
+        );
+        for a in &self.accounts {
+            let tokens: Vec<String> = a.token_balances
+                .iter()
+                .map(|(symbol, amount)| format!("{}:{}", symbol, amount))
+                .collect();
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                a.address, a.balance, a.nonce, a.data_contributions,
+                a.reputation_score, a.staked_amount, tokens.join(";")
+            );
+        }
+        csv
+    }
+}
+
+/// State root of the given chain state
+pub fn state_root(state: &ChainState) -> String {
+    CanonicalState::from_state(state).root()
+}
+
+/// Full dump of the state after block `height`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateDump {
+    pub height: u64,
+    pub block_hash: String,
+    pub state_root: String,
+    pub state: CanonicalState,
+}
+
+impl StateDump {
+    /// Recompute the root from the dumped state and compare it with `state_root`
+    pub fn verify(&self) -> bool {
+        self.state.root() == self.state_root
+    }
+}
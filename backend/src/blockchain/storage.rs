@@ -11,7 +11,7 @@
 
 #![allow(dead_code)]
 
-use rocksdb::{DB, IteratorMode, Options, WriteBatch};
+use rocksdb::{DB, Direction, IteratorMode, Options, WriteBatch};
 use serde::{Deserialize, Serialize};
 use log::info;
 use std::path::Path;
@@ -27,16 +27,24 @@ const CF_TRANSACTIONS: &str = "transactions"; // tx_hash -> (block_index, tx_ind
 const CF_ACCOUNTS: &str = "accounts";       // address -> Account
 const CF_DATA_REGISTRY: &str = "data_registry"; // data_hash -> DataEntry
 const CF_METADATA: &str = "metadata";       // key -> value (chain metadata)
+const CF_MEMO_INDEX: &str = "memo_index";  // memo 0x00 tx_hash -> ()
 
 /// Keys for metadata
 const META_TOTAL_BLOCKS: &[u8] = b"total_blocks";
 const META_DIFFICULTY: &[u8] = b"difficulty";
-const META_BLOCK_REWARD: &[u8] = b"block_reward";
-const META_DATA_REWARD_BASE: &[u8] = b"data_reward_base";
 const META_LAST_BLOCK_TIME: &[u8] = b"last_block_time";
 const META_TOTAL_SUPPLY: &[u8] = b"total_supply";
 const META_TOTAL_STAKED: &[u8] = b"total_staked";
 
+/// Memo index key: memo bytes, a 0x00 separator, then the tx hash
+fn memo_key(memo: &str, tx_hash: &str) -> Vec<u8> {
+    let mut key = Vec::with_capacity(memo.len() + 1 + tx_hash.len());
+    key.extend_from_slice(memo.as_bytes());
+    key.push(0);
+    key.extend_from_slice(tx_hash.as_bytes());
+    key
+}
+
 /// RocksDB-based storage engine
 pub struct Storage {
     db: DB,
@@ -61,7 +69,7 @@ impl Storage {
         // Define column families
         let cf_names = vec![
             CF_BLOCKS, CF_BLOCK_HASHES, CF_TRANSACTIONS, 
-            CF_ACCOUNTS, CF_DATA_REGISTRY, CF_METADATA
+            CF_ACCOUNTS, CF_DATA_REGISTRY, CF_METADATA, CF_MEMO_INDEX
         ];
         
         // With create_missing_column_families(true), open_cf will:
@@ -118,6 +126,8 @@ impl Storage {
             .ok_or("CF_BLOCK_HASHES not found")?;
         let cf_txs = self.db.cf_handle(CF_TRANSACTIONS)
             .ok_or("CF_TRANSACTIONS not found")?;
+        let cf_memos = self.db.cf_handle(CF_MEMO_INDEX)
+            .ok_or("CF_MEMO_INDEX not found")?;
         
         let mut batch = WriteBatch::default();
         
@@ -141,6 +151,10 @@ impl Storage {
             let location_data = serde_json::to_vec(&tx_location)
                 .map_err(|e| format!("Failed to serialize tx location: {}", e))?;
             batch.put_cf(&cf_txs, tx.hash.as_bytes(), &location_data);
+            
+            if let Some(ref memo) = tx.memo {
+                batch.put_cf(&cf_memos, memo_key(memo, &tx.hash), []);
+            }
         }
         
         self.db.write(batch)
@@ -149,6 +163,43 @@ impl Storage {
         Ok(())
     }
     
+    /// Remove the blocks at `height` and above with their hash, transaction
+    /// and memo index entries, so a reorg leaves no lookups pointing at
+    /// replaced blocks
+    pub fn remove_blocks_from(&self, height: u64) -> Result<(), String> {
+        let cf_blocks = self.db.cf_handle(CF_BLOCKS)
+            .ok_or("CF_BLOCKS not found")?;
+        let cf_hashes = self.db.cf_handle(CF_BLOCK_HASHES)
+            .ok_or("CF_BLOCK_HASHES not found")?;
+        let cf_txs = self.db.cf_handle(CF_TRANSACTIONS)
+            .ok_or("CF_TRANSACTIONS not found")?;
+        let cf_memos = self.db.cf_handle(CF_MEMO_INDEX)
+            .ok_or("CF_MEMO_INDEX not found")?;
+        
+        let mut batch = WriteBatch::default();
+        let start = height.to_be_bytes();
+        for item in self.db.iterator_cf(&cf_blocks, IteratorMode::From(&start, Direction::Forward)) {
+            let (key, data) = item.map_err(|e| format!("Failed to read block: {}", e))?;
+            batch.delete_cf(&cf_blocks, &key);
+            let Ok(block) = serde_json::from_slice::<Block>(&data) else {
+                continue;
+            };
+            batch.delete_cf(&cf_hashes, block.hash.as_bytes());
+            for tx in &block.transactions {
+                // Leave entries a later block has since overwritten
+                if self.get_transaction_location(&tx.hash).is_some_and(|loc| loc.block_index == block.index) {
+                    batch.delete_cf(&cf_txs, tx.hash.as_bytes());
+                }
+                if let Some(ref memo) = tx.memo {
+                    batch.delete_cf(&cf_memos, memo_key(memo, &tx.hash));
+                }
+            }
+        }
+        
+        self.db.write(batch)
+            .map_err(|e| format!("Failed to remove blocks: {}", e))
+    }
+    
     /// Get a block by index
     pub fn get_block(&self, index: u64) -> Option<Block> {
         let cf_blocks = self.db.cf_handle(CF_BLOCKS)?;
@@ -198,6 +249,23 @@ impl Storage {
         block.transactions.get(location.tx_index as usize).cloned()
     }
     
+    /// Get hashes of stored transactions carrying `memo` (up to `limit`)
+    pub fn get_transaction_hashes_by_memo(&self, memo: &str, limit: usize) -> Vec<String> {
+        let cf_memos = match self.db.cf_handle(CF_MEMO_INDEX) {
+            Some(cf) => cf,
+            None => return Vec::new(),
+        };
+        
+        let prefix = memo_key(memo, "");
+        self.db
+            .iterator_cf(&cf_memos, IteratorMode::From(&prefix, Direction::Forward))
+            .filter_map(|item| item.ok())
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .filter_map(|(key, _)| String::from_utf8(key[prefix.len()..].to_vec()).ok())
+            .take(limit)
+            .collect()
+    }
+    
     /// Store an account
     pub fn put_account(&self, account: &Account) -> Result<(), String> {
         let cf_accounts = self.db.cf_handle(CF_ACCOUNTS)
@@ -259,8 +327,6 @@ impl Storage {
         
         batch.put_cf(&cf_meta, META_TOTAL_BLOCKS, &metadata.total_blocks.to_be_bytes());
         batch.put_cf(&cf_meta, META_DIFFICULTY, &metadata.difficulty.to_be_bytes());
-        batch.put_cf(&cf_meta, META_BLOCK_REWARD, &metadata.block_reward.to_be_bytes());
-        batch.put_cf(&cf_meta, META_DATA_REWARD_BASE, &metadata.data_reward_base.to_be_bytes());
         batch.put_cf(&cf_meta, META_LAST_BLOCK_TIME, &metadata.last_block_time.to_be_bytes());
         
         self.db.write(batch)
@@ -275,15 +341,11 @@ impl Storage {
         
         let total_blocks = self.get_u64(&cf_meta, META_TOTAL_BLOCKS)?;
         let difficulty = self.get_u64(&cf_meta, META_DIFFICULTY).unwrap_or(2);
-        let block_reward = self.get_u64(&cf_meta, META_BLOCK_REWARD).unwrap_or(100);
-        let data_reward_base = self.get_u64(&cf_meta, META_DATA_REWARD_BASE).unwrap_or(10);
         let last_block_time = self.get_i64(&cf_meta, META_LAST_BLOCK_TIME).unwrap_or(0);
         
         Some(ChainMetadata {
             total_blocks,
             difficulty,
-            block_reward,
-            data_reward_base,
             last_block_time,
         })
     }
@@ -396,7 +458,7 @@ impl Storage {
     pub fn compact_all(&self) {
         let cf_names = [
             CF_BLOCKS, CF_BLOCK_HASHES, CF_TRANSACTIONS,
-            CF_ACCOUNTS, CF_DATA_REGISTRY, CF_METADATA,
+            CF_ACCOUNTS, CF_DATA_REGISTRY, CF_METADATA, CF_MEMO_INDEX,
         ];
         
         for cf_name in &cf_names {
@@ -414,7 +476,7 @@ impl Storage {
         let mut cf_sizes: Vec<(String, u64)> = Vec::new();
         let cf_names = [
             CF_BLOCKS, CF_BLOCK_HASHES, CF_TRANSACTIONS,
-            CF_ACCOUNTS, CF_DATA_REGISTRY, CF_METADATA,
+            CF_ACCOUNTS, CF_DATA_REGISTRY, CF_METADATA, CF_MEMO_INDEX,
         ];
         
         let mut total_size: u64 = 0;
@@ -11,7 +11,9 @@ use sha2::{Digest, Sha256};
 use std::fmt;
 use uuid::Uuid;
 
-use crate::crypto::{verify_signature, address_from_public_key, WalletError};
+use crate::blockchain::error::TransactionError;
+use crate::consensus::ValidatorDescription;
+use crate::crypto::{verify_signature, address_from_public_key, Wallet, WalletError};
 
 /// Transaction types in EdgeAI blockchain
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -30,6 +32,20 @@ pub enum TransactionType {
     Stake,
     /// Unstaking tokens
     Unstake,
+    /// Re-bond stake that is still unbonding
+    CancelUnbonding,
+    /// Set the owner credited a share of a device's rewards (signed by the device)
+    SetDeviceOwner,
+    /// Set the type a device's telemetry is scored as (signed by the device)
+    SetDeviceType,
+    /// Register a validator, locking the operator's self-stake
+    ValidatorRegister,
+    /// Start unbonding a validator's self-stake back to its operator
+    ValidatorUnbond,
+    /// Lock an owner's stake as a device's bond
+    DeviceBond,
+    /// Start releasing a device's bond back to its owner
+    DeviceUnbond,
     /// Reward distribution
     Reward,
     /// Genesis transaction
@@ -72,12 +88,50 @@ pub struct TxInput {
     pub public_key: String,
 }
 
+/// Maximum memo length in bytes
+pub const MAX_MEMO_LEN: usize = 128;
+
+/// `data` of a `ValidatorRegister` transaction
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ValidatorRegistration {
+    pub commission_rate: f64,
+    #[serde(default)]
+    pub description: ValidatorDescription,
+}
+
+/// Symbol of the native token
+pub const NATIVE_TOKEN: &str = "EDGE";
+
+/// Pseudo-address of the treasury in block reward outputs; its balance is
+/// held in `ChainState::treasury`, not in an account
+pub const TREASURY_ADDRESS: &str = "treasury";
+
+/// Gas charged per transfer output
+pub const TRANSFER_GAS: u64 = 21_000;
+
+/// Maximum number of outputs in one transfer
+pub const MAX_TRANSFER_OUTPUTS: usize = 64;
+
+/// Gas charged for a data purchase
+pub const DATA_PURCHASE_GAS: u64 = 30_000;
+
 /// Transaction output
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TxOutput {
     pub amount: u64,
     pub recipient: String,
     pub data_hash: Option<String>,  // For data transactions
+    /// Token symbol (None = native EDGE). Omitted from JSON for EDGE so
+    /// existing transaction hashes stay valid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+impl TxOutput {
+    /// Token symbol of this output, defaulting to EDGE
+    pub fn token_symbol(&self) -> &str {
+        self.token.as_deref().unwrap_or(NATIVE_TOKEN)
+    }
 }
 
 /// A transaction in the EdgeAI blockchain
@@ -96,6 +150,23 @@ pub struct Transaction {
     pub gas_limit: u64,
     pub hash: String,
     pub signature: Option<String>,
+    /// Anti-spam proof-of-work nonce: `sha256(hash || nonce)` must have
+    /// the chain's required number of leading zero bits
+    #[serde(default)]
+    pub pow_nonce: Option<u64>,
+    /// Sender account nonce; transfers must carry the sender's next nonce
+    /// so a signed transfer cannot be replayed
+    #[serde(default)]
+    pub nonce: u64,
+    /// Optional short memo (e.g. an exchange deposit tag), at most `MAX_MEMO_LEN` bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Last block height this transaction may be included at; clock-independent expiry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until_height: Option<u64>,
+    /// Request a detailed lifecycle trace for this transaction
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trace: bool,
 }
 
 impl Transaction {
@@ -137,6 +208,11 @@ impl Transaction {
             gas_limit,
             hash: String::new(),
             signature: None,
+            pow_nonce: None,
+            nonce: 0,
+            memo: None,
+            valid_until_height: None,
+            trace: false,
         };
         
         tx.hash = tx.calculate_hash();
@@ -183,29 +259,97 @@ impl Transaction {
             gas_limit,
             hash: String::new(),
             signature: Some(signature),
+            pow_nonce: None,
+            nonce: 0,
+            memo: None,
+            valid_until_height: None,
+            trace: false,
         };
         
         tx.hash = tx.calculate_hash();
         tx
     }
     
-    /// Create a genesis transaction
-    pub fn genesis() -> Self {
-        let output = TxOutput {
-            amount: 1_000_000_000, // 1 billion initial tokens
-            recipient: "genesis".to_string(),
-            data_hash: None,
-        };
-        
-        Transaction::new(
+    /// Set the sender nonce (recomputes the hash)
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self.hash = self.calculate_hash();
+        self
+    }
+    
+    /// Set the reward a data contribution claims (recomputes the hash)
+    pub fn with_reward_claim(mut self, amount: u64) -> Self {
+        if let Some(output) = self.outputs.first_mut() {
+            output.amount = amount;
+        }
+        self.hash = self.calculate_hash();
+        self
+    }
+    
+    /// Attach a memo (recomputes the hash)
+    pub fn with_memo(mut self, memo: Option<String>) -> Self {
+        self.memo = memo;
+        self.hash = self.calculate_hash();
+        self
+    }
+    
+    /// Set a block height deadline (recomputes the hash)
+    pub fn with_valid_until_height(mut self, height: Option<u64>) -> Self {
+        self.valid_until_height = height;
+        self.hash = self.calculate_hash();
+        self
+    }
+    
+    /// Attach the anti-spam proof-of-work nonce (not covered by the hash)
+    pub fn with_pow_nonce(mut self, pow_nonce: Option<u64>) -> Self {
+        self.pow_nonce = pow_nonce;
+        self
+    }
+    
+    /// Whether the height deadline has passed for a block at `height`
+    pub fn is_expired_at(&self, height: u64) -> bool {
+        self.valid_until_height.is_some_and(|deadline| height > deadline)
+    }
+    
+    /// Gas consumed by this transaction (fixed per type for now)
+    pub fn gas_used(&self) -> u64 {
+        match self.tx_type {
+            TransactionType::Transfer => TRANSFER_GAS * self.outputs.len().max(1) as u64,
+            TransactionType::DataPurchase => DATA_PURCHASE_GAS,
+            _ => 0,
+        }
+    }
+    
+    /// Fee paid by the sender in native tokens: `gas_price * gas_used`.
+    /// System transactions are free.
+    pub fn fee(&self) -> u64 {
+        if self.sender == "system" {
+            return 0;
+        }
+        self.gas_price.saturating_mul(self.gas_used())
+    }
+    
+    /// Size of the transaction as stored in a block, in bytes
+    pub fn serialized_size(&self) -> usize {
+        serde_json::to_vec(self).map(|json| json.len()).unwrap_or(usize::MAX)
+    }
+    
+    /// Create the genesis transaction minting the initial `allocations`
+    pub fn genesis(allocations: Vec<TxOutput>, timestamp: DateTime<Utc>) -> Self {
+        let mut tx = Transaction::new(
             TransactionType::Genesis,
             "system".to_string(),
             vec![],
-            vec![output],
+            allocations,
             Some("EdgeAI Genesis Block - The Most Intelligent Data Chain for Edge AI".to_string()),
             0,
             0,
-        )
+        );
+        // Fixed id and time, so every node derives the same hash
+        tx.id = "genesis".to_string();
+        tx.timestamp = timestamp;
+        tx.hash = tx.calculate_hash();
+        tx
     }
     
     /// Create a transfer transaction (unsigned - needs to be signed later)
@@ -214,6 +358,27 @@ impl Transaction {
             amount,
             recipient,
             data_hash: None,
+            token: None,
+        };
+        
+        Transaction::new(
+            TransactionType::Transfer,
+            sender,
+            vec![],
+            vec![output],
+            None,
+            1,
+            21000,
+        )
+    }
+    
+    /// Create a transfer of a non-native token (unsigned)
+    pub fn transfer_token(sender: String, recipient: String, amount: u64, token: &str) -> Self {
+        let output = TxOutput {
+            amount,
+            recipient,
+            data_hash: None,
+            token: Self::token_field(token),
         };
         
         Transaction::new(
@@ -233,12 +398,15 @@ impl Transaction {
         sender_public_key: String,
         recipient: String,
         amount: u64,
+        nonce: u64,
+        memo: Option<String>,
         signature: String,
     ) -> Self {
         let output = TxOutput {
             amount,
             recipient,
             data_hash: None,
+            token: None,
         };
         
         Transaction::new_signed(
@@ -251,7 +419,39 @@ impl Transaction {
             1,
             21000,
             signature,
-        )
+        ).with_nonce(nonce).with_memo(memo)
+    }
+    
+    /// Create a signed transfer paying several recipients at once
+    pub fn batch_transfer_signed(
+        sender: String,
+        sender_public_key: String,
+        outputs: Vec<(String, u64)>,
+        nonce: u64,
+        memo: Option<String>,
+        signature: String,
+    ) -> Self {
+        let gas_limit = TRANSFER_GAS * outputs.len().max(1) as u64;
+        let outputs = outputs.into_iter()
+            .map(|(recipient, amount)| TxOutput {
+                amount,
+                recipient,
+                data_hash: None,
+                token: None,
+            })
+            .collect();
+        
+        Transaction::new_signed(
+            TransactionType::Transfer,
+            sender,
+            sender_public_key,
+            vec![],
+            outputs,
+            None,
+            1,
+            gas_limit,
+            signature,
+        ).with_nonce(nonce).with_memo(memo)
     }
     
     /// Create a data contribution transaction
@@ -261,6 +461,7 @@ impl Transaction {
             amount: 0, // Reward will be calculated based on data quality
             recipient: reward_recipient,
             data_hash: Some(data_hash),
+            token: None,
         };
         
         Transaction::new(
@@ -287,6 +488,7 @@ impl Transaction {
             amount: 0,
             recipient: reward_recipient,
             data_hash: Some(data_hash),
+            token: None,
         };
         
         Transaction::new_signed(
@@ -308,6 +510,7 @@ impl Transaction {
             amount: price,
             recipient: seller,
             data_hash: Some(data_hash),
+            token: None,
         };
         
         Transaction::new(
@@ -321,12 +524,182 @@ impl Transaction {
         )
     }
     
+    /// Create a stake transaction delegating `amount` to `validator`
+    pub fn stake(delegator: String, validator: String, amount: u64) -> Self {
+        Self::staking(TransactionType::Stake, delegator, validator, amount)
+    }
+    
+    /// Create an unstake transaction starting to unbond `amount` from `validator`
+    pub fn unstake(delegator: String, validator: String, amount: u64) -> Self {
+        Self::staking(TransactionType::Unstake, delegator, validator, amount)
+    }
+    
+    /// Create a transaction re-bonding `delegator`'s newest pending
+    /// unbonding of `amount` from `validator`
+    pub fn cancel_unbonding(delegator: String, validator: String, amount: u64) -> Self {
+        Self::staking(TransactionType::CancelUnbonding, delegator, validator, amount)
+    }
+    
+    /// Create a transaction setting the type `device`'s telemetry is scored
+    /// as, by name (e.g. `sensor`). The device must sign it.
+    pub fn set_device_type(device: String, device_type: String) -> Self {
+        Transaction::new(
+            TransactionType::SetDeviceType,
+            device,
+            vec![],
+            vec![],
+            Some(device_type),
+            0,
+            0,
+        )
+    }
+    
+    /// Create a transaction locking `amount` from `owner` as `device`'s bond
+    pub fn device_bond(owner: String, device: String, amount: u64) -> Self {
+        Self::staking(TransactionType::DeviceBond, owner, device, amount)
+    }
+    
+    /// Create a transaction releasing `device`'s bond back to `owner`
+    pub fn device_unbond(owner: String, device: String) -> Self {
+        Self::staking(TransactionType::DeviceUnbond, owner, device, 0)
+    }
+    
+    fn staking(tx_type: TransactionType, delegator: String, validator: String, amount: u64) -> Self {
+        let output = TxOutput {
+            amount,
+            recipient: validator,
+            data_hash: None,
+            token: None,
+        };
+        
+        Transaction::new(
+            tx_type,
+            delegator,
+            vec![],
+            vec![output],
+            None,
+            0,
+            0,
+        )
+    }
+    
+    /// Create a transaction naming `owner` as the owner of `device`; an empty
+    /// owner clears it. The device must sign it.
+    pub fn set_device_owner(device: String, owner: String) -> Self {
+        let output = TxOutput {
+            amount: 0,
+            recipient: owner,
+            data_hash: None,
+            token: None,
+        };
+        
+        Transaction::new(
+            TransactionType::SetDeviceOwner,
+            device,
+            vec![],
+            vec![output],
+            None,
+            0,
+            0,
+        )
+    }
+    
+    /// Create a transaction registering `validator`, operated by `operator`,
+    /// with `stake` of the operator's balance locked as self-stake. The
+    /// operator must sign it.
+    pub fn validator_register(operator: String, validator: String, stake: u64, registration: &ValidatorRegistration) -> Self {
+        let mut tx = Self::staking(TransactionType::ValidatorRegister, operator, validator, stake);
+        tx.data = serde_json::to_string(registration).ok();
+        tx.hash = tx.calculate_hash();
+        tx
+    }
+    
+    /// Create a transaction starting to unbond `amount` of `validator`'s
+    /// self-stake. The validator's operator must sign it.
+    pub fn validator_unbond(operator: String, validator: String, amount: u64) -> Self {
+        Self::staking(TransactionType::ValidatorUnbond, operator, validator, amount)
+    }
+    
+    /// Create a contract call transaction; `payload` is the JSON call payload
+    pub fn contract_call(sender: String, payload: String) -> Self {
+        Transaction::new(
+            TransactionType::ContractCall,
+            sender,
+            vec![],
+            vec![],
+            Some(payload),
+            1,
+            100_000,
+        )
+    }
+    
     /// Create a reward transaction
     pub fn reward(recipient: String, amount: u64, reason: String) -> Self {
         let output = TxOutput {
             amount,
             recipient: recipient.clone(),
             data_hash: None,
+            token: None,
+        };
+        
+        Transaction::new(
+            TransactionType::Reward,
+            "system".to_string(),
+            vec![],
+            vec![output],
+            Some(reason),
+            0,
+            0,
+        )
+    }
+    
+    /// Create a block reward transaction. Collected transaction fees are
+    /// paid to the validator as a second output; `treasury_share` of both
+    /// goes to the treasury in a final output.
+    pub fn block_reward(validator: String, reward: u64, fees: u64, treasury_share: f64, reason: String) -> Self {
+        let reward_cut = (reward as f64 * treasury_share) as u64;
+        let fee_cut = (fees as f64 * treasury_share) as u64;
+        let mut outputs = vec![TxOutput {
+            amount: reward - reward_cut,
+            recipient: validator.clone(),
+            data_hash: None,
+            token: None,
+        }];
+        if fees > 0 {
+            outputs.push(TxOutput {
+                amount: fees - fee_cut,
+                recipient: validator,
+                data_hash: None,
+                token: None,
+            });
+        }
+        if reward_cut + fee_cut > 0 {
+            outputs.push(TxOutput {
+                amount: reward_cut + fee_cut,
+                recipient: TREASURY_ADDRESS.to_string(),
+                data_hash: None,
+                token: None,
+            });
+        }
+        
+        Transaction::new(
+            TransactionType::Reward,
+            "system".to_string(),
+            vec![],
+            outputs,
+            Some(reason),
+            0,
+            0,
+        )
+    }
+    
+    /// Create a reward transaction paid in a specific token
+    pub fn reward_token(recipient: String, amount: u64, token: &str, reason: String) -> Self {
+        let output = TxOutput {
+            amount,
+            recipient,
+            data_hash: None,
+            token: Self::token_field(token),
         };
         
         Transaction::new(
@@ -340,9 +713,19 @@ impl Transaction {
         )
     }
     
+    /// Normalize a token symbol for storage in an output (EDGE is implicit)
+    fn token_field(token: &str) -> Option<String> {
+        let symbol = token.to_uppercase();
+        if symbol == NATIVE_TOKEN {
+            None
+        } else {
+            Some(symbol)
+        }
+    }
+    
     /// Calculate transaction hash (for internal use)
     pub fn calculate_hash(&self) -> String {
-        let data = format!(
+        let mut data = format!(
             "{}{}{}{}{}{}",
             self.id,
             self.timestamp,
@@ -351,6 +734,16 @@ impl Transaction {
             self.data.as_deref().unwrap_or(""),
             self.gas_price
         );
+        // Nonce 0 is left out so hashes of pre-nonce transactions stay valid
+        if self.nonce > 0 {
+            data.push_str(&format!(":{}", self.nonce));
+        }
+        if let Some(height) = self.valid_until_height {
+            data.push_str(&format!(":until:{}", height));
+        }
+        if let Some(ref memo) = self.memo {
+            data.push_str(&format!(":memo:{}", memo));
+        }
         
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
@@ -363,8 +756,50 @@ impl Transaction {
     }
     
     /// Create deterministic transfer message for signing (used by wallet API)
-    pub fn create_transfer_signing_message(from: &str, to: &str, amount: u64) -> String {
-        let data = format!("TRANSFER:{}:{}:{}", from, to, amount);
+    pub fn create_transfer_signing_message(
+        from: &str,
+        to: &str,
+        amount: u64,
+        nonce: u64,
+        valid_until_height: Option<u64>,
+        memo: Option<&str>,
+    ) -> String {
+        let mut data = format!("TRANSFER:{}:{}:{}:{}", from, to, amount, nonce);
+        if let Some(height) = valid_until_height {
+            data.push_str(&format!("@{}", height));
+        }
+        if let Some(memo) = memo {
+            data.push_str(&format!(":{}", memo));
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(data.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+    
+    /// Create deterministic batch transfer message for signing (used by wallet API).
+    /// Every `(recipient, amount)` pair is covered, in order. Strings are
+    /// length-prefixed and the deadline and memo are tagged even when absent,
+    /// so no two batches share a message.
+    pub fn create_batch_transfer_signing_message(
+        from: &str,
+        outputs: &[(String, u64)],
+        nonce: u64,
+        valid_until_height: Option<u64>,
+        memo: Option<&str>,
+    ) -> String {
+        let mut data = format!("BATCH_TRANSFER:{}:{}:{}", from.len(), from, nonce);
+        match valid_until_height {
+            Some(height) => data.push_str(&format!(":until:{}", height)),
+            None => data.push_str(":until:-"),
+        }
+        data.push_str(&format!(":{}", outputs.len()));
+        for (to, amount) in outputs {
+            data.push_str(&format!(":{}:{}:{}", to.len(), to, amount));
+        }
+        match memo {
+            Some(memo) => data.push_str(&format!(":memo:{}:{}", memo.len(), memo)),
+            None => data.push_str(":memo:-"),
+        }
         let mut hasher = Sha256::new();
         hasher.update(data.as_bytes());
         hex::encode(hasher.finalize())
@@ -388,6 +823,19 @@ impl Transaction {
         self.sender_public_key = Some(public_key);
     }
     
+    /// Sign the canonical message with `wallet`, whose address must be the sender
+    pub fn signed_by(mut self, wallet: &Wallet) -> Self {
+        let signature = wallet.sign(canonical_sign_message(&self).as_bytes());
+        self.set_signature(signature, wallet.public_key_hex());
+        self
+    }
+    
+    /// Minted by the chain itself (block rewards, genesis allocations), so
+    /// carries no sender signature
+    pub fn is_system(&self) -> bool {
+        matches!(self.tx_type, TransactionType::Reward | TransactionType::Genesis)
+    }
+    
     /// Hash data content
     pub fn hash_data(data: &str) -> String {
         let mut hasher = Sha256::new();
@@ -462,26 +910,23 @@ impl Transaction {
             None => return Ok(false),
         };
         
-        // Create the deterministic message based on transaction type
-        let message = match self.tx_type {
-            TransactionType::Transfer => {
-                if let Some(output) = self.outputs.first() {
-                    Self::create_transfer_signing_message(&self.sender, &output.recipient, output.amount)
-                } else {
-                    return Ok(false);
-                }
-            }
-            TransactionType::DataContribution => {
-                if let Some(ref data) = self.data {
-                    Self::create_data_contribution_signing_message(&self.sender, data)
-                } else {
-                    return Ok(false);
-                }
-            }
-            _ => self.signing_message(),
+        // Nothing meaningful to sign without the fields the message covers
+        let incomplete = match self.tx_type {
+            TransactionType::Transfer | TransactionType::Stake | TransactionType::Unstake
+            | TransactionType::CancelUnbonding | TransactionType::DataPurchase | TransactionType::SetDeviceOwner
+            | TransactionType::ValidatorUnbond | TransactionType::DeviceBond
+            | TransactionType::DeviceUnbond => self.outputs.is_empty(),
+            TransactionType::ValidatorRegister => self.outputs.is_empty() || self.data.is_none(),
+            TransactionType::SetDeviceType => self.data.is_none(),
+            TransactionType::DataContribution | TransactionType::ContractDeploy
+            | TransactionType::ContractCall => self.data.is_none(),
+            _ => false,
         };
+        if incomplete {
+            return Ok(false);
+        }
         
-        // Verify the signature
+        let message = canonical_sign_message(self);
         verify_signature(public_key, message.as_bytes(), signature)
     }
     
@@ -534,10 +979,158 @@ impl Transaction {
         }
     }
     
+    /// Check the transaction was signed by its sender: a valid signature
+    /// from a public key that derives to `sender`. Reward and genesis
+    /// transactions are minted by the chain and carry no signature.
+    pub fn check_sender_signature(&self) -> Result<(), TransactionError> {
+        if self.is_system() {
+            return Ok(());
+        }
+        let Some(public_key) = &self.sender_public_key else {
+            return Err(TransactionError::Unsigned);
+        };
+        if self.signature.is_none() {
+            return Err(TransactionError::Unsigned);
+        }
+        if !matches!(self.verify_signature_deterministic(), Ok(true)) {
+            return Err(TransactionError::InvalidSignature);
+        }
+        match address_from_public_key(public_key) {
+            Ok(address) if address == self.sender => Ok(()),
+            _ => Err(TransactionError::SenderMismatch),
+        }
+    }
+
     /// Get total output amount
     pub fn total_output(&self) -> u64 {
         self.outputs.iter().map(|o| o.amount).sum()
     }
+    
+    /// Total output amount denominated in `token`
+    pub fn total_output_for(&self, token: &str) -> u64 {
+        self.outputs.iter()
+            .filter(|o| o.token_symbol() == token)
+            .map(|o| o.amount)
+            .sum()
+    }
+    
+    /// Hash used for the anti-spam proof-of-work: sha256(message || nonce)
+    /// over the message the sender signs, so clients can solve it before
+    /// submitting even when the node builds the transaction
+    pub fn pow_hash(&self, nonce: u64) -> [u8; 32] {
+        pow_hash(&canonical_sign_message(self), nonce)
+    }
+    
+    /// Check that the attached PoW nonce satisfies `difficulty_bits`
+    pub fn verify_pow(&self, difficulty_bits: u32) -> bool {
+        if difficulty_bits == 0 {
+            return true;
+        }
+        match self.pow_nonce {
+            Some(nonce) => leading_zero_bits(&self.pow_hash(nonce)) >= difficulty_bits,
+            None => false,
+        }
+    }
+    
+    /// Search for a nonce satisfying `difficulty_bits` and attach it
+    pub fn solve_pow(&mut self, difficulty_bits: u32) {
+        self.pow_nonce = Some(solve_pow(&canonical_sign_message(self), difficulty_bits));
+    }
+}
+
+/// Anti-spam proof-of-work hash of a signing message: sha256(message || nonce),
+/// with the nonce as 8 big-endian bytes
+pub fn pow_hash(message: &str, nonce: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(message.as_bytes());
+    hasher.update(nonce.to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// Smallest nonce whose `pow_hash` over `message` has `difficulty_bits` leading zero bits
+pub fn solve_pow(message: &str, difficulty_bits: u32) -> u64 {
+    (0..).find(|nonce| leading_zero_bits(&pow_hash(message, *nonce)) >= difficulty_bits).unwrap_or(0)
+}
+
+/// Deterministic message a wallet signs for `tx`.
+///
+/// Transfers and data contributions keep their original messages. Other
+/// types sign `TYPE:sender:nonce[@valid_until]:field:...[:memo]` with the
+/// fields in a fixed order per type, hashed with SHA-256, so every client
+/// derives the same message from the same transaction.
+pub fn canonical_sign_message(tx: &Transaction) -> String {
+    let output = tx.outputs.first();
+    let recipient = output.map(|o| o.recipient.as_str()).unwrap_or("");
+    let amount = output.map(|o| o.amount).unwrap_or(0);
+    let (tag, fields) = match tx.tx_type {
+        TransactionType::Transfer if tx.outputs.len() > 1 => {
+            let outputs: Vec<(String, u64)> = tx.outputs.iter()
+                .map(|o| (o.recipient.clone(), o.amount))
+                .collect();
+            return Transaction::create_batch_transfer_signing_message(
+                &tx.sender, &outputs, tx.nonce, tx.valid_until_height, tx.memo.as_deref(),
+            );
+        }
+        TransactionType::Transfer => {
+            return Transaction::create_transfer_signing_message(
+                &tx.sender, recipient, amount, tx.nonce, tx.valid_until_height, tx.memo.as_deref(),
+            );
+        }
+        TransactionType::DataContribution => {
+            return Transaction::create_data_contribution_signing_message(
+                &tx.sender, tx.data.as_deref().unwrap_or(""),
+            );
+        }
+        TransactionType::Stake => ("STAKE", vec![recipient.to_string(), amount.to_string()]),
+        TransactionType::Unstake => ("UNSTAKE", vec![recipient.to_string(), amount.to_string()]),
+        TransactionType::CancelUnbonding => ("CANCEL_UNBONDING", vec![recipient.to_string(), amount.to_string()]),
+        TransactionType::SetDeviceOwner => ("DEVICE_OWNER", vec![recipient.to_string()]),
+        TransactionType::SetDeviceType => ("DEVICE_TYPE", vec![tx.data.clone().unwrap_or_default()]),
+        TransactionType::DeviceBond => ("DEVICE_BOND", vec![recipient.to_string(), amount.to_string()]),
+        TransactionType::DeviceUnbond => ("DEVICE_UNBOND", vec![recipient.to_string()]),
+        TransactionType::ValidatorRegister => (
+            "VALIDATOR_REGISTER",
+            vec![recipient.to_string(), amount.to_string(), Transaction::hash_data(tx.data.as_deref().unwrap_or(""))],
+        ),
+        TransactionType::ValidatorUnbond => ("VALIDATOR_UNBOND", vec![recipient.to_string(), amount.to_string()]),
+        TransactionType::DataPurchase => {
+            let data_hash = output.and_then(|o| o.data_hash.as_deref()).unwrap_or("");
+            ("DATA_PURCHASE", vec![recipient.to_string(), data_hash.to_string(), amount.to_string()])
+        }
+        TransactionType::ContractDeploy => {
+            ("CONTRACT_DEPLOY", vec![Transaction::hash_data(tx.data.as_deref().unwrap_or(""))])
+        }
+        TransactionType::ContractCall => {
+            ("CONTRACT_CALL", vec![Transaction::hash_data(tx.data.as_deref().unwrap_or(""))])
+        }
+        _ => return tx.signing_message(),
+    };
+    
+    let mut data = format!("{}:{}:{}", tag, tx.sender, tx.nonce);
+    if let Some(height) = tx.valid_until_height {
+        data.push_str(&format!("@{}", height));
+    }
+    for field in fields {
+        data.push_str(&format!(":{}", field));
+    }
+    if let Some(ref memo) = tx.memo {
+        data.push_str(&format!(":{}", memo));
+    }
+    Transaction::hash_data(&data)
+}
+
+/// Count leading zero bits of a digest
+fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for &byte in digest {
+        if byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
 }
 
 impl fmt::Display for Transaction {
@@ -0,0 +1,143 @@
+//! Transaction lifecycle tracing
+//!
+//! Emits detailed `tracing` spans for transactions matching a runtime filter
+//! (sender, transaction type, or the per-transaction `trace` flag), covering
+//! the full lifecycle: received -> validated -> included -> applied -> receipt.
+//! Transactions that don't match stay quiet.
+
+#![allow(dead_code)]
+
+use std::collections::{HashSet, VecDeque};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::transaction::{Transaction, TransactionType};
+
+/// Number of trace events kept for inspection
+const MAX_TRACE_EVENTS: usize = 1000;
+
+/// Lifecycle stage of a traced transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TxStage {
+    Received,
+    Validated,
+    Rejected { reason: String },
+    Included { block: u64 },
+    Applied,
+    Receipt { success: bool, error: Option<String> },
+}
+
+impl TxStage {
+    fn name(&self) -> &'static str {
+        match self {
+            TxStage::Received => "received",
+            TxStage::Validated => "validated",
+            TxStage::Rejected { .. } => "rejected",
+            TxStage::Included { .. } => "included",
+            TxStage::Applied => "applied",
+            TxStage::Receipt { .. } => "receipt",
+        }
+    }
+}
+
+/// A recorded lifecycle event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxTraceEvent {
+    pub tx_hash: String,
+    pub sender: String,
+    pub stage: TxStage,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Which transactions to trace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TraceFilter {
+    #[serde(default)]
+    pub senders: HashSet<String>,
+    #[serde(default)]
+    pub tx_types: Vec<TransactionType>,
+    /// Trace transactions carrying the `trace` flag
+    #[serde(default)]
+    pub flagged: bool,
+}
+
+impl TraceFilter {
+    /// Build a filter from `EDGEAI_TRACE_SENDERS` (comma-separated)
+    /// and `EDGEAI_TRACE_FLAGGED`
+    pub fn from_env() -> Self {
+        let senders = std::env::var("EDGEAI_TRACE_SENDERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let flagged = std::env::var("EDGEAI_TRACE_FLAGGED")
+            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        TraceFilter { senders, tx_types: Vec::new(), flagged }
+    }
+
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        (self.flagged && tx.trace)
+            || self.senders.contains(&tx.sender)
+            || self.tx_types.contains(&tx.tx_type)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty() && self.tx_types.is_empty() && !self.flagged
+    }
+}
+
+/// Records lifecycle events for transactions matching the filter
+#[derive(Debug, Default)]
+pub struct TxTracer {
+    pub filter: TraceFilter,
+    events: VecDeque<TxTraceEvent>,
+}
+
+impl TxTracer {
+    pub fn new(filter: TraceFilter) -> Self {
+        TxTracer { filter, events: VecDeque::new() }
+    }
+
+    /// Replace the active filter
+    pub fn set_filter(&mut self, filter: TraceFilter) {
+        self.filter = filter;
+    }
+
+    /// Record a lifecycle stage if the transaction matches the filter
+    pub fn record(&mut self, tx: &Transaction, stage: TxStage) {
+        if self.filter.is_empty() || !self.filter.matches(tx) {
+            return;
+        }
+
+        let span = tracing::info_span!(
+            "tx_lifecycle",
+            tx_hash = %tx.hash,
+            sender = %tx.sender,
+            tx_type = ?tx.tx_type
+        );
+        let _guard = span.enter();
+        tracing::info!(stage = stage.name(), detail = ?stage, "transaction lifecycle");
+
+        if self.events.len() >= MAX_TRACE_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(TxTraceEvent {
+            tx_hash: tx.hash.clone(),
+            sender: tx.sender.clone(),
+            stage,
+            timestamp: Utc::now(),
+        });
+    }
+
+    /// Recorded events, optionally limited to one transaction
+    pub fn events(&self, tx_hash: Option<&str>) -> Vec<TxTraceEvent> {
+        self.events
+            .iter()
+            .filter(|e| tx_hash.is_none_or(|h| e.tx_hash == h))
+            .cloned()
+            .collect()
+    }
+}
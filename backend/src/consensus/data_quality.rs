@@ -3,12 +3,18 @@
 //! This module implements advanced data quality evaluation algorithms
 //! for assessing the value of contributed data in the EdgeAI network.
 //!
-//! NOTE: This module is prepared for future integration with the main
-//! consensus mechanism. Currently used for reference and testing.
+//! `score_contribution` scores IoT telemetry against device-specific
+//! expectations and scales DataContribution rewards. The full
+//! `DataQualityAnalyzer` is prepared for future integration with consensus.
 
 #![allow(dead_code)]
 
+use std::collections::{BTreeMap, VecDeque};
+
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::consensus::device_registry::DeviceType;
 
 /// Data Quality Scoring System for PoIE 2.0
 /// 
@@ -447,6 +453,252 @@ impl DataQualityAnalyzer {
     }
 }
 
+/// Timestamps further than this in the future are treated as stale (seconds)
+pub const MAX_TIMESTAMP_SKEW_SECS: i64 = 300;
+
+/// Freshness given to payloads that carry no timestamp
+const UNKNOWN_FRESHNESS: f64 = 0.5;
+
+/// Expected telemetry fields per device type with their plausible ranges
+fn expected_fields(device_type: &DeviceType) -> &'static [(&'static str, f64, f64)] {
+    match device_type {
+        DeviceType::Sensor => &[("temperature", -50.0, 100.0), ("humidity", 0.0, 100.0)],
+        DeviceType::Environmental => &[
+            ("temperature", -60.0, 70.0), ("humidity", 0.0, 100.0), ("pressure", 800.0, 1100.0),
+        ],
+        DeviceType::Industrial => &[
+            ("temperature", -40.0, 200.0), ("pressure", 0.0, 10_000.0), ("vibration", 0.0, 100.0),
+        ],
+        DeviceType::Agricultural => &[("soil_moisture", 0.0, 100.0), ("temperature", -40.0, 60.0)],
+        DeviceType::Energy => &[("power", 0.0, 1_000_000.0), ("voltage", 0.0, 1_000.0)],
+        DeviceType::Medical => &[("heart_rate", 20.0, 250.0)],
+        DeviceType::Wearable => &[("heart_rate", 20.0, 250.0), ("steps", 0.0, 100_000.0)],
+        DeviceType::Location => &[("latitude", -90.0, 90.0), ("longitude", -180.0, 180.0)],
+        DeviceType::Vehicle => &[
+            ("latitude", -90.0, 90.0), ("longitude", -180.0, 180.0), ("speed", 0.0, 400.0),
+        ],
+        DeviceType::SmartHome => &[("temperature", -20.0, 60.0)],
+        DeviceType::Camera | DeviceType::Audio | DeviceType::Custom(_) => &[],
+    }
+}
+
+/// Score a telemetry payload (0.0 - 1.0) for a device type, as of now
+pub fn score_contribution(telemetry: &str, device_type: &DeviceType) -> f64 {
+    score_contribution_at(telemetry, device_type, chrono::Utc::now().timestamp())
+}
+
+/// Score a telemetry payload as of `now` (unix seconds).
+///
+/// Accepts a flat JSON object or the IoT submission envelope
+/// (`{"telemetry": {...}, "ts": ...}`). The score is plausibility (share of
+/// expected fields within device-specific ranges) times a blend of
+/// completeness (share of expected fields present) and freshness (timestamp
+/// recency). Malformed JSON scores 0.
+pub fn score_contribution_at(telemetry: &str, device_type: &DeviceType, now: i64) -> f64 {
+    let Ok(serde_json::Value::Object(root)) = serde_json::from_str::<serde_json::Value>(telemetry) else {
+        return 0.0;
+    };
+    let fields = match root.get("telemetry") {
+        Some(serde_json::Value::Object(inner)) => inner,
+        _ => &root,
+    };
+    if fields.is_empty() {
+        return 0.0;
+    }
+
+    let expected = expected_fields(device_type);
+    let present: Vec<_> = expected.iter()
+        .filter_map(|(name, min, max)| fields.get(*name).map(|v| (v, min, max)))
+        .collect();
+    let (completeness, plausibility) = if expected.is_empty() {
+        (1.0, 1.0)
+    } else if present.is_empty() {
+        (0.0, 1.0)
+    } else {
+        let in_range = present.iter()
+            .filter(|(v, min, max)| v.as_f64().is_some_and(|x| x >= **min && x <= **max))
+            .count();
+        (present.len() as f64 / expected.len() as f64, in_range as f64 / present.len() as f64)
+    };
+
+    let timestamp = ["timestamp", "ts"].iter()
+        .find_map(|key| fields.get(*key).or_else(|| root.get(*key)))
+        .and_then(|v| v.as_f64())
+        .map(|ts| if ts > 1e12 { (ts / 1000.0) as i64 } else { ts as i64 });
+    let freshness = match timestamp {
+        None => UNKNOWN_FRESHNESS,
+        Some(ts) if ts > now + MAX_TIMESTAMP_SKEW_SECS => 0.0,
+        // Decays to ~0.37 after a day
+        Some(ts) => (-((now - ts).max(0) as f64 / 3600.0) / 24.0).exp(),
+    };
+
+    (plausibility * (0.6 * completeness + 0.4 * freshness)).clamp(0.0, 1.0)
+}
+
+/// Recent submissions remembered per device for novelty scoring
+pub const NOVELTY_WINDOW: usize = 8;
+
+/// Devices remembered at once; the least recently active one is forgotten first
+pub const MAX_NOVELTY_DEVICES: usize = 4096;
+
+/// Relative difference within which two readings of a field count as equal
+pub const NEAR_DUPLICATE_TOLERANCE: f64 = 0.001;
+
+/// Multiplier applied per recent near-duplicate
+pub const DUPLICATE_DECAY: f64 = 0.5;
+
+/// Numeric fields of a submission compared by value; the rest are hashed
+const MAX_FINGERPRINT_FIELDS: usize = 16;
+
+/// Longest field path compared by value (bytes)
+const MAX_FIELD_PATH_BYTES: usize = 64;
+
+/// What novelty scoring remembers of one submission
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingFingerprint {
+    /// Measurement time, when the payload carries one
+    pub timestamp: Option<i64>,
+    /// Numeric fields by path
+    pub values: BTreeMap<String, f64>,
+    /// SHA-256 of everything not compared by value (the whole payload if it is not JSON)
+    pub digest: String,
+}
+
+impl ReadingFingerprint {
+    /// Fingerprint a telemetry payload. Accepts the same flat or enveloped
+    /// JSON as `score_contribution_at`.
+    pub fn of(telemetry: &str) -> Self {
+        let Ok(serde_json::Value::Object(root)) = serde_json::from_str::<serde_json::Value>(telemetry) else {
+            return ReadingFingerprint {
+                timestamp: None,
+                values: BTreeMap::new(),
+                digest: hex::encode(Sha256::digest(telemetry.as_bytes())),
+            };
+        };
+        let fields = match root.get("telemetry") {
+            Some(serde_json::Value::Object(inner)) => inner,
+            _ => &root,
+        };
+        let timestamp = ["timestamp", "ts"].iter()
+            .find_map(|key| fields.get(*key).or_else(|| root.get(*key)))
+            .and_then(|v| v.as_f64())
+            .map(|ts| if ts > 1e12 { (ts / 1000.0) as i64 } else { ts as i64 });
+
+        let mut leaves = Vec::new();
+        flatten_json("", &serde_json::Value::Object(root.clone()), &mut leaves);
+        let mut values = BTreeMap::new();
+        let mut hasher = Sha256::new();
+        for (path, value) in leaves {
+            let leaf = path.rsplit('.').next().unwrap_or(&path);
+            if leaf == "timestamp" || leaf == "ts" {
+                continue;
+            }
+            match value.as_f64() {
+                Some(x) if values.len() < MAX_FINGERPRINT_FIELDS && path.len() <= MAX_FIELD_PATH_BYTES => {
+                    values.insert(path, x);
+                }
+                _ => {
+                    hasher.update((path.len() as u64).to_be_bytes());
+                    hasher.update(path.as_bytes());
+                    hasher.update(value.to_string().as_bytes());
+                }
+            }
+        }
+        ReadingFingerprint { timestamp, values, digest: hex::encode(hasher.finalize()) }
+    }
+
+    /// Whether two submissions report the same measurement: the same time
+    /// (or neither has one), the same non-numeric content and every numeric
+    /// field within `NEAR_DUPLICATE_TOLERANCE`. A steady sensor reporting
+    /// unchanged values at a new time is a new observation.
+    pub fn is_near_duplicate(&self, other: &ReadingFingerprint) -> bool {
+        self.timestamp == other.timestamp
+            && self.digest == other.digest
+            && self.values.len() == other.values.len()
+            && self.values.iter().all(|(path, a)| {
+                other.values.get(path).is_some_and(|b| {
+                    (a - b).abs() <= NEAR_DUPLICATE_TOLERANCE * a.abs().max(b.abs())
+                })
+            })
+    }
+}
+
+/// Leaf values of a JSON document with their dotted paths, in key order
+fn flatten_json(prefix: &str, value: &serde_json::Value, out: &mut Vec<(String, serde_json::Value)>) {
+    let join = |key: &str| if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut keys: Vec<_> = map.keys().collect();
+            keys.sort();
+            for key in keys {
+                flatten_json(&join(key), &map[key], out);
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten_json(&join(&i.to_string()), item, out);
+            }
+        }
+        leaf => out.push((prefix.to_string(), leaf.clone())),
+    }
+}
+
+/// A device's recent submissions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoveltyWindow {
+    /// Value of the cache's counter at the device's latest submission
+    pub last_seen: u64,
+    pub recent: VecDeque<ReadingFingerprint>,
+}
+
+/// Rolling cache of each device's recent submissions, used to reward novel
+/// data over repeated readings. Part of the chain state, so it is ordered
+/// and bounded: `NOVELTY_WINDOW` submissions for each of at most
+/// `MAX_NOVELTY_DEVICES` devices.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoveltyCache {
+    /// Submissions scored so far, used to find the least recently active device
+    pub counter: u64,
+    pub devices: BTreeMap<String, NoveltyWindow>,
+}
+
+impl NoveltyCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reward multiplier (0.0 - 1.0) for a submission, halved for each of the
+    /// device's recent submissions it nearly duplicates. The submission is
+    /// then remembered, so repeating a reading earns less every time.
+    pub fn entropy_bonus(&mut self, device_id: &str, telemetry: &str) -> f64 {
+        let fingerprint = ReadingFingerprint::of(telemetry);
+        if !self.devices.contains_key(device_id) && self.devices.len() >= MAX_NOVELTY_DEVICES {
+            let stalest = self.devices.iter()
+                .min_by_key(|(_, window)| window.last_seen)
+                .map(|(device, _)| device.clone());
+            if let Some(device) = stalest {
+                self.devices.remove(&device);
+            }
+        }
+        self.counter += 1;
+        let window = self.devices.entry(device_id.to_string()).or_default();
+        window.last_seen = self.counter;
+        let duplicates = window.recent.iter().filter(|past| past.is_near_duplicate(&fingerprint)).count();
+
+        window.recent.push_back(fingerprint);
+        if window.recent.len() > NOVELTY_WINDOW {
+            window.recent.pop_front();
+        }
+
+        DUPLICATE_DECAY.powi(duplicates as i32)
+    }
+
+    /// Number of devices with remembered submissions
+    pub fn device_count(&self) -> usize {
+        self.devices.len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -487,4 +739,84 @@ mod tests {
         assert!(quality.overall > 0.5);
         assert!(quality.details.anomalies.is_empty());
     }
+    
+    #[test]
+    fn test_score_contribution_telemetry() {
+        let now = 1_704_931_200;
+        let good = format!(r#"{{"temperature": 21.5, "humidity": 48, "timestamp": {}}}"#, now - 30);
+        assert!(score_contribution_at(&good, &DeviceType::Sensor, now) > 0.9);
+
+        // IoT envelope with millisecond timestamp
+        let wrapped = format!(r#"{{"telemetry": {{"temperature": 21.5, "humidity": 48}}, "ts": {}}}"#, now * 1000);
+        assert!(score_contribution_at(&wrapped, &DeviceType::Sensor, now) > 0.9);
+
+        let out_of_range = format!(r#"{{"temperature": 450, "humidity": -20, "timestamp": {}}}"#, now);
+        assert!(score_contribution_at(&out_of_range, &DeviceType::Sensor, now) < 0.1);
+
+        let stale_partial = format!(r#"{{"temperature": 21.5, "timestamp": {}}}"#, now - 7 * 86_400);
+        assert!(score_contribution_at(&stale_partial, &DeviceType::Sensor, now) < 0.5);
+
+        assert_eq!(score_contribution_at("not json", &DeviceType::Sensor, now), 0.0);
+        assert_eq!(score_contribution_at("{}", &DeviceType::Sensor, now), 0.0);
+    }
+    
+    #[test]
+    fn test_entropy_bonus_decays_for_repeats() {
+        let mut cache = NoveltyCache::new();
+        let reading = r#"{"temperature": 21.5, "humidity": 48, "pressure": 1013}"#;
+        let bonuses: Vec<f64> = (0..4).map(|_| cache.entropy_bonus("sensor", reading)).collect();
+        assert_eq!(bonuses, vec![1.0, 0.5, 0.25, 0.125]);
+
+        // A different reading, or the same one from another device, is novel
+        let other = r#"{"vibration": 3.2, "rpm": 1780, "load": 0.64}"#;
+        assert_eq!(cache.entropy_bonus("sensor", other), 1.0);
+        assert_eq!(cache.entropy_bonus("other_sensor", reading), 1.0);
+
+        // Repeats age out of the window
+        for i in 0..NOVELTY_WINDOW {
+            cache.entropy_bonus("sensor", &format!(r#"{{"seq": "{}"}}"#, "x".repeat(i * 7 + 1)));
+        }
+        assert_eq!(cache.entropy_bonus("sensor", reading), 1.0);
+    }
+
+    #[test]
+    fn test_entropy_bonus_compares_values() {
+        let mut cache = NoveltyCache::new();
+        let now = 1_704_931_200;
+
+        // A steady sensor reporting the same values at new times is not penalised
+        for i in 0..4 {
+            let reading = format!(r#"{{"temperature": 21.5, "humidity": 48, "timestamp": {}}}"#, now + i * 60);
+            assert_eq!(cache.entropy_bonus("steady", &reading), 1.0);
+        }
+
+        // Resending a measurement with float jitter is still a duplicate
+        let first = format!(r#"{{"temperature": 21.5, "humidity": 48, "timestamp": {}}}"#, now);
+        let jittered = format!(r#"{{"temperature": 21.50001, "humidity": 48, "timestamp": {}}}"#, now);
+        assert_eq!(cache.entropy_bonus("replayer", &first), 1.0);
+        assert_eq!(cache.entropy_bonus("replayer", &jittered), 0.5);
+
+        // A real change in value at the same time is novel
+        let changed = format!(r#"{{"temperature": 23.0, "humidity": 48, "timestamp": {}}}"#, now);
+        assert_eq!(cache.entropy_bonus("replayer", &changed), 1.0);
+    }
+
+    #[test]
+    fn test_novelty_cache_is_bounded() {
+        let mut cache = NoveltyCache::new();
+        let reading = r#"{"temperature": 21.5}"#;
+        cache.entropy_bonus("device_0", reading);
+        for i in 1..=MAX_NOVELTY_DEVICES {
+            cache.entropy_bonus(&format!("device_{}", i), reading);
+        }
+        assert_eq!(cache.device_count(), MAX_NOVELTY_DEVICES);
+        // The least recently active device was forgotten
+        assert_eq!(cache.entropy_bonus("device_0", reading), 1.0);
+        assert_eq!(cache.entropy_bonus(&format!("device_{}", MAX_NOVELTY_DEVICES), reading), 0.5);
+
+        for _ in 0..NOVELTY_WINDOW * 2 {
+            cache.entropy_bonus("device_1", reading);
+        }
+        assert_eq!(cache.devices["device_1"].recent.len(), NOVELTY_WINDOW);
+    }
 }
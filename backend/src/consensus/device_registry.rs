@@ -8,9 +8,60 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use log::{info, debug};
 
+use crate::blockchain::Transaction;
+use crate::crypto::{address_from_public_key, verify_signature};
+
+/// Default minimum seconds between a device's rewarded contributions
+pub const DEFAULT_MIN_CONTRIBUTION_INTERVAL_SECS: i64 = 60;
+
+/// Minimum device firmware version from `EDGEAI_MIN_DEVICE_FIRMWARE` (unset = no minimum)
+pub fn min_firmware_version_from_env() -> Option<String> {
+    std::env::var("EDGEAI_MIN_DEVICE_FIRMWARE")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Numeric components of a dotted version such as `v2.10.1-rc1` -> [2, 10, 1]
+fn version_components(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split('.')
+        .map(|part| {
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Whether `version` is at least `minimum`, comparing components numerically
+pub fn firmware_at_least(version: &str, minimum: &str) -> bool {
+    let (mut version, mut minimum) = (version_components(version), version_components(minimum));
+    let len = version.len().max(minimum.len());
+    version.resize(len, 0);
+    minimum.resize(len, 0);
+    version >= minimum
+}
+
+/// Mean Earth radius used for distance queries
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Great-circle distance in km between two coordinates (haversine). The
+/// longitude difference only enters through `sin²(Δλ/2)`, so points either
+/// side of the antimeridian come out close together and unnormalized
+/// longitudes such as 190° are handled like -170°.
+pub fn haversine_km(lat1: f64, lng1: f64, lat2: f64, lng2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lng2 - lng1).to_radians();
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
 /// Device type classification
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum DeviceType {
@@ -43,6 +94,25 @@ pub enum DeviceType {
 }
 
 impl DeviceType {
+    /// Device type by name, case-insensitive; unknown names are custom types
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "sensor" => DeviceType::Sensor,
+            "camera" => DeviceType::Camera,
+            "audio" => DeviceType::Audio,
+            "location" => DeviceType::Location,
+            "industrial" => DeviceType::Industrial,
+            "smarthome" | "smart_home" => DeviceType::SmartHome,
+            "wearable" => DeviceType::Wearable,
+            "vehicle" => DeviceType::Vehicle,
+            "environmental" => DeviceType::Environmental,
+            "medical" => DeviceType::Medical,
+            "agricultural" => DeviceType::Agricultural,
+            "energy" => DeviceType::Energy,
+            _ => DeviceType::Custom(name.to_string()),
+        }
+    }
+    
     /// Get the base contribution multiplier for this device type
     pub fn base_multiplier(&self) -> f64 {
         match self {
@@ -95,6 +165,14 @@ impl GeoRegion {
         }
     }
     
+    /// Distance in km from the region's coordinates, if it has any
+    pub fn distance_km(&self, lat: f64, lng: f64) -> Option<f64> {
+        match (self.latitude, self.longitude) {
+            (Some(dev_lat), Some(dev_lng)) => Some(haversine_km(lat, lng, dev_lat as f64, dev_lng as f64)),
+            _ => None,
+        }
+    }
+
     /// Get region key for scarcity calculation
     pub fn region_key(&self) -> String {
         match &self.region_code {
@@ -133,6 +211,37 @@ pub struct Device {
     pub quality_history: Vec<f64>,
     /// Is device verified (KYC or hardware attestation)
     pub is_verified: bool,
+    /// Time of the last contribution that earned a reward
+    #[serde(default)]
+    pub last_rewarded_at: Option<DateTime<Utc>>,
+    /// Highest counter seen on a signed submission
+    #[serde(default)]
+    pub last_submission_counter: Option<u64>,
+    /// Address that operates the device and shares its contribution rewards
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// Firmware version from the device's last signed submission
+    #[serde(default)]
+    pub firmware_version: Option<String>,
+    /// API keys the device authenticates IoT submissions with
+    #[serde(default)]
+    pub api_keys: Vec<DeviceApiKey>,
+    /// The device's bond on chain when it was registered
+    #[serde(default)]
+    pub bond: u64,
+}
+
+/// A device API key; only the SHA-256 hash of the key is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceApiKey {
+    pub key_id: String,
+    pub key_hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Hex SHA-256 of an API key
+fn hash_api_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
 }
 
 impl Device {
@@ -163,9 +272,20 @@ impl Device {
             contribution_points: 0.0,
             quality_history: Vec::new(),
             is_verified: false,
+            last_rewarded_at: None,
+            last_submission_counter: None,
+            owner: None,
+            firmware_version: None,
+            api_keys: Vec::new(),
+            bond: 0,
         }
     }
     
+    /// Chain address of the device key; the device's contributions are sent from it
+    pub fn address(&self) -> Result<String, String> {
+        address_from_public_key(&self.public_key).map_err(|e| e.to_string())
+    }
+    
     /// Update device activity
     pub fn record_activity(&mut self) {
         self.last_active = Utc::now();
@@ -235,6 +355,19 @@ pub struct DeviceRegistry {
     pub total_devices: u64,
     /// Total active devices
     pub active_devices: u64,
+    /// Minimum seconds between rewarded contributions, per device type
+    #[serde(default)]
+    pub min_contribution_intervals: HashMap<String, i64>,
+    /// Minimum interval for device types without an override
+    #[serde(default = "default_min_contribution_interval")]
+    pub default_min_contribution_interval: i64,
+    /// Submissions from firmware older than this are rejected (None = any version)
+    #[serde(default)]
+    pub min_firmware_version: Option<String>,
+}
+
+fn default_min_contribution_interval() -> i64 {
+    DEFAULT_MIN_CONTRIBUTION_INTERVAL_SECS
 }
 
 impl DeviceRegistry {
@@ -245,23 +378,112 @@ impl DeviceRegistry {
             region_counts: HashMap::new(),
             total_devices: 0,
             active_devices: 0,
+            min_contribution_intervals: HashMap::new(),
+            default_min_contribution_interval: DEFAULT_MIN_CONTRIBUTION_INTERVAL_SECS,
+            min_firmware_version: min_firmware_version_from_env(),
+        }
+    }
+    
+    /// Set the minimum interval between rewarded contributions for a device type
+    pub fn set_min_contribution_interval(&mut self, device_type: &DeviceType, secs: i64) {
+        self.min_contribution_intervals.insert(format!("{:?}", device_type), secs);
+    }
+    
+    /// Minimum interval between rewarded contributions for a device type
+    pub fn min_contribution_interval(&self, device_type: &DeviceType) -> i64 {
+        self.min_contribution_intervals
+            .get(&format!("{:?}", device_type))
+            .copied()
+            .unwrap_or(self.default_min_contribution_interval)
+    }
+    
+    /// Record a data contribution for a device. Contributions arriving sooner
+    /// than the device type's minimum interval after the last rewarded one
+    /// only count as activity and earn nothing.
+    /// Returns the points awarded.
+    pub fn record_contribution(&mut self, device_id: &str, quality_score: f64, points: f64) -> Result<f64, String> {
+        let min_interval = {
+            let device = self.devices.get(device_id).ok_or("Device not found")?;
+            self.min_contribution_interval(&device.device_type)
+        };
+        let device = self.devices.get_mut(device_id).ok_or("Device not found")?;
+        let now = Utc::now();
+        
+        if let Some(last) = device.last_rewarded_at {
+            if now - last < Duration::seconds(min_interval) {
+                debug!("Contribution from {} within {}s of the last one, not rewarded",
+                    device_id, min_interval);
+                device.record_activity();
+                return Ok(0.0);
+            }
+        }
+        
+        device.record_contribution(quality_score, points);
+        device.last_rewarded_at = Some(now);
+        Ok(points)
+    }
+    
+    /// Check a signed submission and record its counter. `payload` is the
+    /// data stored on chain and must include `counter`; the signature must be
+    /// by the device key over the data contribution message for it, so the
+    /// submission is a transaction signed by the device's address. The
+    /// counter (a sequence number or unix timestamp) must exceed the last one
+    /// seen, so a captured submission can't be replayed.
+    pub fn verify_signed_submission(
+        &mut self,
+        device_id: &str,
+        counter: u64,
+        payload: &str,
+        signature: &str,
+    ) -> Result<(), String> {
+        let device = self.devices.get_mut(device_id).ok_or("Device not found")?;
+        let message = Transaction::create_data_contribution_signing_message(&device.address()?, payload);
+        if !matches!(verify_signature(&device.public_key, message.as_bytes(), signature), Ok(true)) {
+            return Err("Invalid submission signature".to_string());
+        }
+        if let Some(last) = device.last_submission_counter {
+            if counter <= last {
+                return Err(format!(
+                    "Replayed submission: counter {} is not greater than last seen {}",
+                    counter, last
+                ));
+            }
         }
+        device.last_submission_counter = Some(counter);
+        Ok(())
     }
     
-    /// Register a new device
+    /// Register a new device without a bond
     pub fn register_device(
         &mut self,
         public_key: String,
         device_type: DeviceType,
         region: GeoRegion,
     ) -> Result<Device, String> {
+        self.register_device_with_bond(public_key, device_type, region, 0, 0)
+    }
+    
+    /// Register a new device bonded with `bond` on chain, which must be at
+    /// least `min_bond`
+    pub fn register_device_with_bond(
+        &mut self,
+        public_key: String,
+        device_type: DeviceType,
+        region: GeoRegion,
+        bond: u64,
+        min_bond: u64,
+    ) -> Result<Device, String> {
+        if bond < min_bond {
+            return Err(format!("Bond of {} is below the minimum device bond of {}", bond, min_bond));
+        }
         // Check if device already registered
         let device_id = Self::compute_device_id(&public_key);
         if self.devices.contains_key(&device_id) {
             return Err("Device already registered".to_string());
         }
         
-        let device = Device::new(public_key, device_type.clone(), region.clone());
+        let mut device = Device::new(public_key, device_type.clone(), region.clone());
+        device.bond = bond;
         
         // Update counts
         let type_key = format!("{:?}", device_type);
@@ -279,6 +501,91 @@ impl DeviceRegistry {
         Ok(device_clone)
     }
     
+    /// Remove a device from the registry. Its bond stays on chain until the
+    /// owner releases it with a DeviceUnbond transaction.
+    pub fn unregister_device(&mut self, device_id: &str) -> Result<(), String> {
+        let device = self.devices.remove(device_id).ok_or("Device not found")?;
+        let type_key = format!("{:?}", device.device_type);
+        for (counts, key) in [(&mut self.type_counts, type_key), (&mut self.region_counts, device.region.region_key())] {
+            if let Some(count) = counts.get_mut(&key) {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    counts.remove(&key);
+                }
+            }
+        }
+        self.total_devices = self.total_devices.saturating_sub(1);
+        if device.is_active {
+            self.active_devices = self.active_devices.saturating_sub(1);
+        }
+        info!("Device {} unregistered", device_id);
+        Ok(())
+    }
+    
+    /// Check a submission's firmware against `min_firmware_version`, recording
+    /// the reported version on registered devices. Only call this with the
+    /// version from a payload the device key signed; every submission has to
+    /// report its own version.
+    pub fn check_firmware(&mut self, device_id: &str, reported: Option<&str>) -> Result<(), String> {
+        if let (Some(version), Some(device)) = (reported, self.devices.get_mut(device_id)) {
+            device.firmware_version = Some(version.to_string());
+        }
+        let Some(minimum) = &self.min_firmware_version else {
+            return Ok(());
+        };
+        match reported {
+            Some(version) if firmware_at_least(version, minimum) => Ok(()),
+            Some(version) => Err(format!("Firmware {} is below the minimum version {}", version, minimum)),
+            None => Err(format!("Firmware version required (minimum {})", minimum)),
+        }
+    }
+    
+    /// Mint an API key for a registered device. Returns `(key_id, key)`;
+    /// the key itself is not stored and can't be recovered later.
+    pub fn create_api_key(&mut self, device_id: &str) -> Result<(String, String), String> {
+        let device = self.devices.get_mut(device_id).ok_or("Device not found")?;
+        let key = format!("edk_{}", hex::encode(rand::random::<[u8; 32]>()));
+        let key_hash = hash_api_key(&key);
+        let key_id = key_hash[..16].to_string();
+        device.api_keys.push(DeviceApiKey {
+            key_id: key_id.clone(),
+            key_hash,
+            created_at: Utc::now(),
+        });
+        info!("API key {} minted for device {}", key_id, device_id);
+        Ok((key_id, key))
+    }
+    
+    /// Revoke one of a device's API keys
+    pub fn revoke_api_key(&mut self, device_id: &str, key_id: &str) -> Result<(), String> {
+        let device = self.devices.get_mut(device_id).ok_or("Device not found")?;
+        let before = device.api_keys.len();
+        device.api_keys.retain(|k| k.key_id != key_id);
+        if device.api_keys.len() == before {
+            return Err(format!("API key {} not found", key_id));
+        }
+        info!("API key {} revoked for device {}", key_id, device_id);
+        Ok(())
+    }
+    
+    /// Check a presented API key against the device's stored key hashes
+    pub fn verify_api_key(&self, device_id: &str, key: &str) -> Result<(), String> {
+        let device = self.devices.get(device_id).ok_or("Device not registered")?;
+        let key_hash = hash_api_key(key);
+        if device.api_keys.iter().any(|k| k.key_hash == key_hash) {
+            Ok(())
+        } else {
+            Err("Invalid API key".to_string())
+        }
+    }
+    
+    /// Set or clear the owner address of a device
+    pub fn set_owner(&mut self, device_id: &str, owner: Option<String>) -> Result<(), String> {
+        let device = self.devices.get_mut(device_id).ok_or("Device not found")?;
+        device.owner = owner;
+        Ok(())
+    }
+    
     /// Get device by ID
     pub fn get_device(&self, device_id: &str) -> Option<&Device> {
         self.devices.get(device_id)
@@ -296,7 +603,7 @@ impl DeviceRegistry {
     }
     
     /// Compute device ID from public key
-    fn compute_device_id(public_key: &str) -> String {
+    pub fn compute_device_id(public_key: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(public_key.as_bytes());
         let hash = hasher.finalize();
@@ -336,6 +643,29 @@ impl DeviceRegistry {
         scarcity.clamp(0.5, 2.0)
     }
     
+    /// Devices with coordinates within `radius_km` of (`lat`, `lng`), nearest first
+    pub fn devices_within_radius(&self, lat: f64, lng: f64, radius_km: f64) -> Vec<&Device> {
+        let mut nearby: Vec<(f64, &Device)> = self.devices.values()
+            .filter_map(|d| d.region.distance_km(lat, lng).map(|km| (km, d)))
+            .filter(|(km, _)| *km <= radius_km)
+            .collect();
+        nearby.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.device_id.cmp(&b.1.device_id)));
+        nearby.into_iter().map(|(_, d)| d).collect()
+    }
+
+    /// Devices in a region, given as a country code (`US`) or a region key (`US:CA`)
+    pub fn devices_in_region(&self, region: &str) -> Vec<&Device> {
+        let region = region.trim();
+        let mut devices: Vec<&Device> = self.devices.values()
+            .filter(|d| {
+                d.region.country_code.eq_ignore_ascii_case(region)
+                    || d.region.region_key().eq_ignore_ascii_case(region)
+            })
+            .collect();
+        devices.sort_by(|a, b| a.device_id.cmp(&b.device_id));
+        devices
+    }
+
     /// Get all active devices eligible for validation
     pub fn get_eligible_validators(&self) -> Vec<&Device> {
         self.devices.values()
@@ -458,4 +788,158 @@ mod tests {
         // JP should have higher scarcity (fewer devices)
         assert!(jp_scarcity > us_scarcity);
     }
+    
+    #[test]
+    fn test_min_contribution_interval() {
+        let mut registry = DeviceRegistry::new();
+        let device = registry.register_device(
+            "sensor_key".to_string(),
+            DeviceType::Sensor,
+            GeoRegion::new("US"),
+        ).unwrap();
+        let camera = registry.register_device(
+            "camera_key".to_string(),
+            DeviceType::Camera,
+            GeoRegion::new("US"),
+        ).unwrap();
+        registry.set_min_contribution_interval(&DeviceType::Camera, 0);
+        
+        assert_eq!(registry.record_contribution(&device.device_id, 0.8, 10.0), Ok(10.0));
+        // Second submission within the interval earns nothing
+        assert_eq!(registry.record_contribution(&device.device_id, 0.8, 10.0), Ok(0.0));
+        let stored = registry.get_device(&device.device_id).unwrap();
+        assert_eq!(stored.total_contributions, 1);
+        assert_eq!(stored.contribution_points, 10.0);
+        
+        // Categories without an interval are rewarded every time
+        assert_eq!(registry.record_contribution(&camera.device_id, 0.8, 10.0), Ok(10.0));
+        assert_eq!(registry.record_contribution(&camera.device_id, 0.8, 10.0), Ok(10.0));
+        assert!(registry.record_contribution("DEV_unknown", 0.8, 10.0).is_err());
+    }
+    
+    #[test]
+    fn test_min_firmware_version_enforced() {
+        let mut registry = DeviceRegistry::new();
+        registry.min_firmware_version = Some("2.4".to_string());
+        let device = registry.register_device(
+            "firmware_key".to_string(),
+            DeviceType::Sensor,
+            GeoRegion::new("US"),
+        ).unwrap();
+        
+        let err = registry.check_firmware(&device.device_id, Some("2.3.9")).unwrap_err();
+        assert!(err.contains("below the minimum"));
+        assert!(registry.check_firmware(&device.device_id, Some("v2.10.0")).is_ok());
+        assert_eq!(registry.get_device(&device.device_id).unwrap().firmware_version.as_deref(), Some("v2.10.0"));
+        // The recorded version doesn't vouch for later submissions
+        assert!(registry.check_firmware(&device.device_id, None).unwrap_err().contains("required"));
+        assert!(registry.check_firmware("DEV_unknown", None).unwrap_err().contains("required"));
+        
+        registry.min_firmware_version = None;
+        assert!(registry.check_firmware("DEV_unknown", Some("0.1")).is_ok());
+    }
+    
+    #[test]
+    fn test_device_bond_required_at_registration() {
+        let mut registry = DeviceRegistry::new();
+        
+        let err = registry.register_device_with_bond("bond_low".to_string(), DeviceType::Sensor, GeoRegion::new("US"), 99, 100)
+            .unwrap_err();
+        assert!(err.contains("below the minimum device bond of 100"));
+        assert_eq!(registry.total_devices, 0);
+        
+        let bonded = registry.register_device_with_bond("bond_ok".to_string(), DeviceType::Sensor, GeoRegion::new("US"), 200, 100)
+            .unwrap();
+        assert_eq!(bonded.bond, 200);
+        registry.unregister_device(&bonded.device_id).unwrap();
+        assert!(registry.get_device(&bonded.device_id).is_none());
+        assert_eq!(registry.total_devices, 0);
+    }
+    
+    #[test]
+    fn test_api_key_verification() {
+        let mut registry = DeviceRegistry::new();
+        let device = registry.register_device(
+            "api_key_device".to_string(),
+            DeviceType::Sensor,
+            GeoRegion::new("US"),
+        ).unwrap();
+        let (key_id, key) = registry.create_api_key(&device.device_id).unwrap();
+        
+        // Only the hash is stored
+        let stored = &registry.get_device(&device.device_id).unwrap().api_keys;
+        assert_eq!(stored.len(), 1);
+        assert_ne!(stored[0].key_hash, key);
+        
+        assert!(registry.verify_api_key(&device.device_id, &key).is_ok());
+        assert_eq!(registry.verify_api_key(&device.device_id, "edk_wrong").unwrap_err(), "Invalid API key");
+        assert_eq!(registry.verify_api_key("DEV_unknown", &key).unwrap_err(), "Device not registered");
+        
+        registry.revoke_api_key(&device.device_id, &key_id).unwrap();
+        assert_eq!(registry.verify_api_key(&device.device_id, &key).unwrap_err(), "Invalid API key");
+        assert!(registry.revoke_api_key(&device.device_id, &key_id).is_err());
+    }
+    
+    #[test]
+    fn test_signed_submission_replay_rejected() {
+        let wallet = crate::crypto::Wallet::new();
+        let mut registry = DeviceRegistry::new();
+        let device = registry.register_device(
+            wallet.public_key_hex(),
+            DeviceType::Sensor,
+            GeoRegion::new("US"),
+        ).unwrap();
+        assert_eq!(device.address().unwrap(), wallet.address());
+        let payload = |counter: u64| format!(r#"{{"counter":{},"temperature":25.5}}"#, counter);
+        let sign = |counter: u64| {
+            wallet.sign(Transaction::create_data_contribution_signing_message(wallet.address(), &payload(counter)).as_bytes())
+        };
+        
+        let first = sign(5);
+        assert!(registry.verify_signed_submission(&device.device_id, 5, &payload(5), &first).is_ok());
+        // The captured submission replayed as-is is rejected
+        let err = registry.verify_signed_submission(&device.device_id, 5, &payload(5), &first).unwrap_err();
+        assert!(err.contains("Replayed"));
+        // A lower counter is rejected, a higher one accepted
+        assert!(registry.verify_signed_submission(&device.device_id, 4, &payload(4), &sign(4)).is_err());
+        assert!(registry.verify_signed_submission(&device.device_id, 6, &payload(6), &sign(6)).is_ok());
+        assert_eq!(registry.get_device(&device.device_id).unwrap().last_submission_counter, Some(6));
+        
+        // Bumping the counter without re-signing fails verification
+        let err = registry.verify_signed_submission(&device.device_id, 7, &payload(7), &sign(6)).unwrap_err();
+        assert!(err.contains("signature"));
+    }
+
+    #[test]
+    fn test_devices_within_radius() {
+        let mut registry = DeviceRegistry::new();
+        let mut place = |key: &str, country: &str, lat: i32, lng: i32| {
+            registry.register_device(key.to_string(), DeviceType::Sensor, GeoRegion::with_coordinates(country, lat, lng))
+                .unwrap()
+                .device_id
+        };
+        let tokyo = place("tokyo", "JP", 36, 140);
+        let osaka = place("osaka", "JP", 35, 135);
+        let london = place("london", "GB", 52, 0);
+        let fiji = place("fiji", "FJ", -17, 179);
+        let samoa = place("samoa", "WS", -14, -172);
+        registry.register_device("nowhere".to_string(), DeviceType::Sensor, GeoRegion::new("JP")).unwrap();
+
+        let ids = |devices: Vec<&Device>| devices.iter().map(|d| d.device_id.clone()).collect::<Vec<_>>();
+
+        // Osaka is ~470 km from Tokyo; London is far outside either radius
+        assert_eq!(ids(registry.devices_within_radius(36.0, 140.0, 100.0)), vec![tokyo.clone()]);
+        assert_eq!(ids(registry.devices_within_radius(36.0, 140.0, 600.0)), vec![tokyo, osaka]);
+        assert_eq!(ids(registry.devices_within_radius(51.5, -0.1, 100.0)), vec![london]);
+
+        // Fiji and Samoa sit either side of the antimeridian, ~1,000 km apart
+        assert_eq!(ids(registry.devices_within_radius(-17.0, 179.5, 200.0)), vec![fiji.clone()]);
+        assert_eq!(ids(registry.devices_within_radius(-15.0, -178.0, 1_000.0)), vec![fiji.clone(), samoa.clone()]);
+        assert_eq!(ids(registry.devices_within_radius(-15.0, 182.0, 1_000.0)), vec![fiji, samoa]);
+        assert!(registry.devices_within_radius(0.0, 0.0, 100.0).is_empty());
+
+        assert_eq!(registry.devices_in_region("jp").len(), 3);
+        assert_eq!(registry.devices_in_region("GB").len(), 1);
+        assert!(registry.devices_in_region("US").is_empty());
+    }
 }
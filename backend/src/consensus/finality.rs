@@ -0,0 +1,150 @@
+//! Attestation-based Finality Gadget for EdgeAI Blockchain
+//!
+//! Active validators sign attestations (votes) for the block they consider
+//! canonical at a given height. Once attestations backed by more than the
+//! configured share of total voting power (default 2/3) accumulate for a
+//! block, that block becomes final. This gives faster, stake-backed
+//! finality than waiting for confirmation depth alone.
+
+#![allow(dead_code)]
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use log::info;
+
+/// Number of heights of attestation history kept in memory
+const MAX_TRACKED_HEIGHTS: usize = 1000;
+
+/// How many heights below the tip validators may still attest to
+pub const ATTESTATION_WINDOW: u64 = 64;
+
+/// A validator's signed vote for a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attestation {
+    pub validator: String,
+    pub height: u64,
+    pub block_hash: String,
+    pub signature: String,
+    /// Voting power of the validator when the attestation was recorded
+    pub voting_power: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Attestation {
+    /// Message a validator signs to attest to a block on the chain
+    /// identified by `chain_id`
+    pub fn signing_message(chain_id: &str, height: u64, block_hash: &str) -> String {
+        format!("ATTEST:{}:{}:{}", chain_id, height, block_hash)
+    }
+}
+
+/// Attestation summary for a single height
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeightAttestations {
+    pub height: u64,
+    pub attestations: Vec<Attestation>,
+    /// Attested voting power per block hash
+    pub power_by_block: HashMap<String, f64>,
+    pub finalized_hash: Option<String>,
+}
+
+/// Tracks attestations and finalized heights
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinalityGadget {
+    /// Share of total voting power required for finality (e.g. 2/3)
+    pub threshold: f64,
+    /// height -> validator -> attestation
+    attestations: BTreeMap<u64, HashMap<String, Attestation>>,
+    /// height -> finalized block hash
+    finalized: BTreeMap<u64, String>,
+}
+
+impl FinalityGadget {
+    pub fn new(threshold: f64) -> Self {
+        FinalityGadget {
+            threshold,
+            attestations: BTreeMap::new(),
+            finalized: BTreeMap::new(),
+        }
+    }
+
+    /// Record an attestation. `total_power` is the voting power of the
+    /// active validator set. Returns true if this attestation finalized the height.
+    pub fn add_attestation(&mut self, attestation: Attestation, total_power: f64) -> Result<bool, String> {
+        let height = attestation.height;
+        let votes = self.attestations.entry(height).or_default();
+
+        if let Some(existing) = votes.get(&attestation.validator) {
+            if existing.block_hash != attestation.block_hash {
+                return Err(format!(
+                    "Validator already attested to a different block at height {}", height
+                ));
+            }
+            return Err("Duplicate attestation".to_string());
+        }
+
+        let block_hash = attestation.block_hash.clone();
+        votes.insert(attestation.validator.clone(), attestation);
+
+        let attested_power: f64 = votes.values()
+            .filter(|a| a.block_hash == block_hash)
+            .map(|a| a.voting_power)
+            .sum();
+
+        self.prune();
+
+        if self.finalized.contains_key(&height) || total_power <= 0.0 {
+            return Ok(false);
+        }
+
+        if attested_power > total_power * self.threshold {
+            info!("Block #{} finalized by attestations ({:.1}/{:.1} voting power)",
+                height, attested_power, total_power);
+            self.finalized.insert(height, block_hash);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
+    /// Whether a height has been finalized by attestations
+    pub fn is_finalized(&self, height: u64) -> bool {
+        self.finalized.contains_key(&height)
+    }
+
+    /// Highest finalized height and its block hash
+    pub fn latest_finalized(&self) -> Option<(u64, &String)> {
+        self.finalized.iter().next_back().map(|(h, hash)| (*h, hash))
+    }
+
+    /// Attestation summary for a height
+    pub fn get_height(&self, height: u64) -> HeightAttestations {
+        let attestations: Vec<Attestation> = self.attestations
+            .get(&height)
+            .map(|votes| votes.values().cloned().collect())
+            .unwrap_or_default();
+
+        let mut power_by_block = HashMap::new();
+        for a in &attestations {
+            *power_by_block.entry(a.block_hash.clone()).or_insert(0.0) += a.voting_power;
+        }
+
+        HeightAttestations {
+            height,
+            attestations,
+            power_by_block,
+            finalized_hash: self.finalized.get(&height).cloned(),
+        }
+    }
+
+    /// Drop attestation history beyond the tracking window
+    fn prune(&mut self) {
+        while self.attestations.len() > MAX_TRACKED_HEIGHTS {
+            self.attestations.pop_first();
+        }
+        while self.finalized.len() > MAX_TRACKED_HEIGHTS {
+            self.finalized.pop_first();
+        }
+    }
+}
@@ -5,7 +5,7 @@
 //!
 //! # Features
 //! - Proposal creation with deposit requirement
-//! - Voting with stake-weighted power
+//! - Voting with stake-weighted power, linear or quadratic per proposal type
 //! - Multiple proposal types (parameter change, upgrade, treasury spend)
 //! - Configurable voting periods and thresholds
 //! - Automatic proposal execution
@@ -13,8 +13,126 @@
 #![allow(dead_code)]
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use super::clock::{system_clock, SharedClock};
+use super::parameters::{
+    set_parameter, set_reward_parameter, unknown_parameter, BLOCKCHAIN_PARAMETERS, GOVERNANCE_PARAMETERS,
+    STAKING_PARAMETERS,
+};
+use super::staking::StakingManager;
+use crate::blockchain::Blockchain;
+
+/// Governance snapshot file name inside the data directory
+pub const GOVERNANCE_FILE: &str = "governance.json";
+
+/// Why a governance operation was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum GovernanceError {
+    #[error("Proposal not found")]
+    ProposalNotFound,
+    #[error("Maximum active proposals reached")]
+    MaxActiveProposals,
+    #[error("Maximum active proposals per proposer reached")]
+    MaxProposerProposals,
+    #[error("Cannot add deposit to this proposal")]
+    DepositClosed,
+    #[error("Proposal is not in voting period")]
+    NotInVotingPeriod,
+    #[error("Voting period has ended")]
+    VotingEnded,
+    #[error("Voter has no stake")]
+    NoStake,
+    #[error("Stake below the minimum for a quadratic vote")]
+    StakeBelowQuadraticMinimum { have: u128, need: u128 },
+    #[error("Only the proposer can cancel a proposal")]
+    NotProposer,
+    #[error("Proposal can only be cancelled during the deposit period")]
+    NotInDepositPeriod,
+    #[error("Proposal has deposits from other accounts")]
+    ForeignDeposits,
+    #[error("Proposal has not passed")]
+    NotPassed,
+    #[error("Execution delay not yet passed")]
+    ExecutionDelay { execute_at: u64 },
+    #[error("Parameter change cooldown active")]
+    ParameterCooldown { until: u64 },
+    #[error("Proposal execution failed: {reason}")]
+    ExecutionFailed { reason: String },
+}
+
+impl From<GovernanceError> for String {
+    fn from(e: GovernanceError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Source of stake-weighted voting power
+pub trait StakeLookup {
+    /// Stake bonded by `address` (self-stake plus delegations)
+    fn stake_of(&self, address: &str) -> u128;
+
+    /// Total bonded stake, used as the quorum denominator
+    fn total_stake(&self) -> u128;
+}
+
+/// Chain state a passed proposal acts on when executed
+pub trait ProposalExecutor {
+    /// Move `amount` native tokens from the treasury to `recipient`
+    fn spend_treasury(&mut self, recipient: &str, amount: u64) -> Result<(), String>;
+
+    /// Set a governable parameter of a module other than governance
+    fn set_parameter(&mut self, module: &str, parameter: &str, value: &str) -> Result<(), String>;
+}
+
+impl ProposalExecutor for Blockchain {
+    fn spend_treasury(&mut self, recipient: &str, amount: u64) -> Result<(), String> {
+        Blockchain::spend_treasury(self, recipient, amount)
+    }
+
+    fn set_parameter(&mut self, module: &str, parameter: &str, value: &str) -> Result<(), String> {
+        match module {
+            "blockchain" => set_parameter(BLOCKCHAIN_PARAMETERS, self, module, parameter, value),
+            "rewards" => set_reward_parameter(&mut self.state.reward_policy, parameter, value),
+            _ => Err(unknown_parameter(module, parameter)),
+        }
+    }
+}
+
+/// Executor acting on the chain and the staking module
+pub struct ProtocolExecutor<'a> {
+    pub chain: &'a mut Blockchain,
+    pub staking: &'a mut StakingManager,
+}
+
+impl ProposalExecutor for ProtocolExecutor<'_> {
+    fn spend_treasury(&mut self, recipient: &str, amount: u64) -> Result<(), String> {
+        self.chain.spend_treasury(recipient, amount)
+    }
+
+    fn set_parameter(&mut self, module: &str, parameter: &str, value: &str) -> Result<(), String> {
+        match module {
+            "staking" => set_parameter(STAKING_PARAMETERS, &mut self.staking.config, module, parameter, value),
+            _ => ProposalExecutor::set_parameter(self.chain, module, parameter, value),
+        }
+    }
+}
+
+impl StakeLookup for StakingManager {
+    fn stake_of(&self, address: &str) -> u128 {
+        let self_stake = self.validators.get(address).map_or(0, |v| v.self_stake as u128);
+        let delegated: u128 = self
+            .delegations
+            .get(address)
+            .map_or(0, |d| d.values().map(|d| d.amount as u128).sum());
+        self_stake + delegated
+    }
+
+    fn total_stake(&self) -> u128 {
+        self.total_staked as u128
+    }
+}
 
 /// Governance configuration parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +151,35 @@ pub struct GovernanceConfig {
     pub execution_delay: u64,
     /// Maximum number of active proposals
     pub max_active_proposals: usize,
+    /// Minimum time between two executed changes of the same parameter (in seconds)
+    #[serde(default = "default_parameter_change_cooldown")]
+    pub parameter_change_cooldown: u64,
+    /// Maximum number of active proposals from a single proposer
+    #[serde(default = "default_max_active_proposals_per_proposer")]
+    pub max_active_proposals_per_proposer: usize,
+    /// Voting scheme for proposal types without an entry in `voting_schemes`
+    #[serde(default)]
+    pub voting_scheme: VotingScheme,
+    /// Voting scheme per proposal type (`parameter_change`, `software_upgrade`,
+    /// `treasury_spend`, `validator_change`, `text`, `emergency`)
+    #[serde(default)]
+    pub voting_schemes: BTreeMap<String, VotingScheme>,
+    /// Minimum stake behind a single quadratic vote, so splitting stake
+    /// across many addresses can't multiply its weight
+    #[serde(default = "default_min_quadratic_vote_stake")]
+    pub min_quadratic_vote_stake: u128,
+}
+
+fn default_parameter_change_cooldown() -> u64 {
+    14 * 24 * 60 * 60 // 14 days
+}
+
+fn default_max_active_proposals_per_proposer() -> usize {
+    3
+}
+
+fn default_min_quadratic_vote_stake() -> u128 {
+    1_000
 }
 
 impl Default for GovernanceConfig {
@@ -45,10 +192,22 @@ impl Default for GovernanceConfig {
             veto_threshold: 33,                          // 33% veto to reject
             execution_delay: 2 * 24 * 60 * 60,           // 2 days
             max_active_proposals: 10,
+            parameter_change_cooldown: default_parameter_change_cooldown(),
+            max_active_proposals_per_proposer: default_max_active_proposals_per_proposer(),
+            voting_scheme: VotingScheme::default(),
+            voting_schemes: BTreeMap::new(),
+            min_quadratic_vote_stake: default_min_quadratic_vote_stake(),
         }
     }
 }
 
+impl GovernanceConfig {
+    /// Voting scheme proposals of `proposal_type` are tallied under
+    pub fn voting_scheme_for(&self, proposal_type: &ProposalType) -> VotingScheme {
+        self.voting_schemes.get(proposal_type.kind()).copied().unwrap_or(self.voting_scheme)
+    }
+}
+
 /// Types of governance proposals
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ProposalType {
@@ -88,6 +247,20 @@ pub enum ProposalType {
     },
 }
 
+impl ProposalType {
+    /// Name of the proposal type, as used in the API and `voting_schemes`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ProposalType::ParameterChange { .. } => "parameter_change",
+            ProposalType::SoftwareUpgrade { .. } => "software_upgrade",
+            ProposalType::TreasurySpend { .. } => "treasury_spend",
+            ProposalType::ValidatorChange { .. } => "validator_change",
+            ProposalType::Text { .. } => "text",
+            ProposalType::Emergency { .. } => "emergency",
+        }
+    }
+}
+
 /// Actions that can be taken on validators
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ValidatorAction {
@@ -116,6 +289,8 @@ pub enum ProposalStatus {
     ExecutionFailed { reason: String },
     /// Proposal expired without reaching quorum
     Expired,
+    /// Proposal was withdrawn by its proposer during the deposit period
+    Cancelled,
 }
 
 /// Vote options
@@ -127,11 +302,33 @@ pub enum VoteOption {
     NoWithVeto,
 }
 
+/// How a voter's stake is turned into weight in the tally
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VotingScheme {
+    /// One unit of stake, one unit of weight
+    #[default]
+    Linear,
+    /// Weight is the square root of stake, damping large holders
+    Quadratic,
+}
+
+impl VotingScheme {
+    /// Tally weight of a vote backed by `stake`
+    pub fn weight(&self, stake: u128) -> u128 {
+        match self {
+            VotingScheme::Linear => stake,
+            VotingScheme::Quadratic => stake.isqrt(),
+        }
+    }
+}
+
 /// A single vote cast by an account
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vote {
     pub voter: String,
     pub option: VoteOption,
+    /// Stake behind the vote; its tally weight depends on the proposal's scheme
     pub voting_power: u128,
     pub timestamp: u64,
 }
@@ -186,11 +383,15 @@ pub struct Proposal {
     pub voting_start_time: Option<u64>,
     pub voting_end_time: Option<u64>,
     pub execution_time: Option<u64>,
+    /// Tally of vote weights under `voting_scheme`
     pub tally: VoteTally,
     pub votes: HashMap<String, Vote>,
+    #[serde(default)]
+    pub voting_scheme: VotingScheme,
 }
 
 impl Proposal {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: u64,
         proposer: String,
@@ -199,13 +400,10 @@ impl Proposal {
         proposal_type: ProposalType,
         initial_deposit: u128,
         config: &GovernanceConfig,
+        now: u64,
     ) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         let deposit_period = 2 * 24 * 60 * 60; // 2 days for deposit period
+        let voting_scheme = config.voting_scheme_for(&proposal_type);
 
         let (status, voting_start, voting_end) = if initial_deposit >= config.min_deposit {
             (
@@ -232,10 +430,11 @@ impl Proposal {
             execution_time: None,
             tally: VoteTally::default(),
             votes: HashMap::new(),
+            voting_scheme,
         }
     }
 
-    pub fn add_deposit(&mut self, amount: u128, config: &GovernanceConfig) -> bool {
+    pub fn add_deposit(&mut self, amount: u128, config: &GovernanceConfig, now: u64) -> bool {
         if self.status != ProposalStatus::DepositPeriod {
             return false;
         }
@@ -244,10 +443,6 @@ impl Proposal {
 
         // Check if deposit threshold is met
         if self.deposit >= config.min_deposit {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
             self.status = ProposalStatus::VotingPeriod;
             self.voting_start_time = Some(now);
             self.voting_end_time = Some(now + config.voting_period);
@@ -256,38 +451,41 @@ impl Proposal {
         true
     }
 
-    pub fn cast_vote(&mut self, voter: String, option: VoteOption, voting_power: u128) -> Result<(), &'static str> {
+    pub fn cast_vote(
+        &mut self,
+        voter: String,
+        option: VoteOption,
+        voting_power: u128,
+        now: u64,
+    ) -> Result<(), GovernanceError> {
         if self.status != ProposalStatus::VotingPeriod {
-            return Err("Proposal is not in voting period");
+            return Err(GovernanceError::NotInVotingPeriod);
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         if let Some(end_time) = self.voting_end_time {
             if now > end_time {
-                return Err("Voting period has ended");
+                return Err(GovernanceError::VotingEnded);
             }
         }
 
         // Remove previous vote if exists
         if let Some(prev_vote) = self.votes.get(&voter) {
+            let weight = self.voting_scheme.weight(prev_vote.voting_power);
             match prev_vote.option {
-                VoteOption::Yes => self.tally.yes -= prev_vote.voting_power,
-                VoteOption::No => self.tally.no -= prev_vote.voting_power,
-                VoteOption::Abstain => self.tally.abstain -= prev_vote.voting_power,
-                VoteOption::NoWithVeto => self.tally.no_with_veto -= prev_vote.voting_power,
+                VoteOption::Yes => self.tally.yes -= weight,
+                VoteOption::No => self.tally.no -= weight,
+                VoteOption::Abstain => self.tally.abstain -= weight,
+                VoteOption::NoWithVeto => self.tally.no_with_veto -= weight,
             }
         }
 
         // Add new vote
+        let weight = self.voting_scheme.weight(voting_power);
         match option {
-            VoteOption::Yes => self.tally.yes += voting_power,
-            VoteOption::No => self.tally.no += voting_power,
-            VoteOption::Abstain => self.tally.abstain += voting_power,
-            VoteOption::NoWithVeto => self.tally.no_with_veto += voting_power,
+            VoteOption::Yes => self.tally.yes += weight,
+            VoteOption::No => self.tally.no += weight,
+            VoteOption::Abstain => self.tally.abstain += weight,
+            VoteOption::NoWithVeto => self.tally.no_with_veto += weight,
         }
 
         self.votes.insert(
@@ -303,16 +501,11 @@ impl Proposal {
         Ok(())
     }
 
-    pub fn finalize(&mut self, total_voting_power: u128, config: &GovernanceConfig) {
+    pub fn finalize(&mut self, total_voting_power: u128, config: &GovernanceConfig, now: u64) {
         if self.status != ProposalStatus::VotingPeriod {
             return;
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         // Check if voting period has ended
         if let Some(end_time) = self.voting_end_time {
             if now < end_time {
@@ -320,9 +513,11 @@ impl Proposal {
             }
         }
 
-        // Calculate participation rate
+        // Calculate participation rate from the stake that voted, so quorum
+        // means the same under every voting scheme
+        let voted_stake: u128 = self.votes.values().map(|v| v.voting_power).sum();
         let participation = if total_voting_power > 0 {
-            (self.tally.total() as f64 / total_voting_power as f64) * 100.0
+            (voted_stake as f64 / total_voting_power as f64) * 100.0
         } else {
             0.0
         };
@@ -349,6 +544,9 @@ impl Proposal {
     }
 }
 
+/// Deposits returned to accounts as (account, amount)
+pub type DepositRefunds = Vec<(String, u128)>;
+
 /// Governance manager handling all proposals
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GovernanceManager {
@@ -357,19 +555,57 @@ pub struct GovernanceManager {
     pub next_proposal_id: u64,
     /// Deposits by account -> proposal_id -> amount
     pub deposits: HashMap<String, HashMap<u64, u128>>,
+    /// Last execution time per parameter ("module.parameter" -> unix seconds)
+    #[serde(default)]
+    pub last_parameter_change: HashMap<String, u64>,
+    /// Total deposits burned by vetoed proposals
+    #[serde(default)]
+    pub burned_deposits: u128,
+    /// Time source for proposal periods
+    #[serde(skip, default = "system_clock")]
+    clock: SharedClock,
 }
 
 impl GovernanceManager {
     pub fn new(config: GovernanceConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Governance manager reading time from `clock`
+    pub fn with_clock(config: GovernanceConfig, clock: SharedClock) -> Self {
         Self {
             config,
             proposals: HashMap::new(),
             next_proposal_id: 1,
             deposits: HashMap::new(),
+            last_parameter_change: HashMap::new(),
+            burned_deposits: 0,
+            clock,
         }
     }
 
-    /// Create a new proposal
+    /// Save the governance state as JSON (written to a temp file, then renamed)
+    pub fn save_to_disk(&self, path: &Path) -> Result<(), String> {
+        let data = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize governance state: {}", e))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, data).map_err(|e| format!("Failed to write governance state: {}", e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write governance state: {}", e))
+    }
+
+    /// Load governance state saved by `save_to_disk`. `Ok(None)` if there is no file.
+    pub fn load_from_disk(path: &Path) -> Result<Option<Self>, String> {
+        let data = match fs::read(path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read governance state: {}", e)),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse governance state: {}", e))
+    }
+
+    /// Create a new proposal, tallied under the scheme configured for its type
     pub fn create_proposal(
         &mut self,
         proposer: String,
@@ -377,9 +613,9 @@ impl GovernanceManager {
         description: String,
         proposal_type: ProposalType,
         initial_deposit: u128,
-    ) -> Result<u64, &'static str> {
-        // Check active proposals limit
-        let active_count = self
+    ) -> Result<u64, GovernanceError> {
+        // Check active proposals limits, globally and per proposer
+        let active: Vec<&Proposal> = self
             .proposals
             .values()
             .filter(|p| {
@@ -388,10 +624,14 @@ impl GovernanceManager {
                     ProposalStatus::DepositPeriod | ProposalStatus::VotingPeriod
                 )
             })
-            .count();
+            .collect();
 
-        if active_count >= self.config.max_active_proposals {
-            return Err("Maximum active proposals reached");
+        if active.len() >= self.config.max_active_proposals {
+            return Err(GovernanceError::MaxActiveProposals);
+        }
+        let proposer_active = active.iter().filter(|p| p.proposer == proposer).count();
+        if proposer_active >= self.config.max_active_proposals_per_proposer {
+            return Err(GovernanceError::MaxProposerProposals);
         }
 
         let proposal_id = self.next_proposal_id;
@@ -405,8 +645,8 @@ impl GovernanceManager {
             proposal_type,
             initial_deposit,
             &self.config,
+            self.clock.unix_secs(),
         );
-
         self.proposals.insert(proposal_id, proposal);
 
         // Track deposit
@@ -424,14 +664,14 @@ impl GovernanceManager {
         depositor: String,
         proposal_id: u64,
         amount: u128,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), GovernanceError> {
         let proposal = self
             .proposals
             .get_mut(&proposal_id)
-            .ok_or("Proposal not found")?;
+            .ok_or(GovernanceError::ProposalNotFound)?;
 
-        if !proposal.add_deposit(amount, &self.config) {
-            return Err("Cannot add deposit to this proposal");
+        if !proposal.add_deposit(amount, &self.config, self.clock.unix_secs()) {
+            return Err(GovernanceError::DepositClosed);
         }
 
         // Track deposit
@@ -445,66 +685,174 @@ impl GovernanceManager {
         Ok(())
     }
 
-    /// Cast a vote on a proposal
+    /// Cast a vote on a proposal, weighted by the voter's current stake under
+    /// the proposal's voting scheme
     pub fn vote(
         &mut self,
         voter: String,
         proposal_id: u64,
         option: VoteOption,
-        voting_power: u128,
-    ) -> Result<(), &'static str> {
+        stakes: &impl StakeLookup,
+    ) -> Result<(), GovernanceError> {
         let proposal = self
             .proposals
             .get_mut(&proposal_id)
-            .ok_or("Proposal not found")?;
+            .ok_or(GovernanceError::ProposalNotFound)?;
 
-        proposal.cast_vote(voter, option, voting_power)
+        let voting_power = stakes.stake_of(&voter);
+        if voting_power == 0 {
+            return Err(GovernanceError::NoStake);
+        }
+        if proposal.voting_scheme == VotingScheme::Quadratic
+            && voting_power < self.config.min_quadratic_vote_stake
+        {
+            return Err(GovernanceError::StakeBelowQuadraticMinimum {
+                have: voting_power,
+                need: self.config.min_quadratic_vote_stake,
+            });
+        }
+
+        proposal.cast_vote(voter, option, voting_power, self.clock.unix_secs())
     }
 
-    /// Finalize a proposal after voting period ends
+    /// Finalize a proposal after voting period ends, using total stake for quorum.
+    /// Returns the new status and the deposits to refund as (account, amount):
+    /// deposits come back when the proposal passes or is rejected and are
+    /// burned when it is vetoed.
     pub fn finalize_proposal(
         &mut self,
         proposal_id: u64,
-        total_voting_power: u128,
-    ) -> Result<ProposalStatus, &'static str> {
+        stakes: &impl StakeLookup,
+    ) -> Result<(ProposalStatus, DepositRefunds), GovernanceError> {
+        self.finalize_proposal_at(proposal_id, stakes, self.clock.unix_secs())
+    }
+
+    /// `finalize_proposal` as of `now` (unix seconds)
+    fn finalize_proposal_at(
+        &mut self,
+        proposal_id: u64,
+        stakes: &impl StakeLookup,
+        now: u64,
+    ) -> Result<(ProposalStatus, DepositRefunds), GovernanceError> {
         let proposal = self
             .proposals
             .get_mut(&proposal_id)
-            .ok_or("Proposal not found")?;
+            .ok_or(GovernanceError::ProposalNotFound)?;
+
+        proposal.finalize(stakes.total_stake(), &self.config, now);
+        let status = proposal.status.clone();
+
+        let refunds = match status {
+            ProposalStatus::Passed | ProposalStatus::Rejected => self.take_deposits(proposal_id),
+            ProposalStatus::Vetoed => {
+                let burned: u128 = self.take_deposits(proposal_id).iter().map(|(_, amount)| amount).sum();
+                self.burned_deposits += burned;
+                log::info!("Burned {} in deposits of vetoed proposal #{}", burned, proposal_id);
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+        Ok((status, refunds))
+    }
+
+    /// Withdraw a proposal still in its deposit period. Only the proposer can
+    /// cancel, and only while nobody else has deposited on it; returns the
+    /// proposer's deposit to refund.
+    pub fn cancel_proposal(&mut self, proposer: &str, proposal_id: u64) -> Result<u128, GovernanceError> {
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .ok_or(GovernanceError::ProposalNotFound)?;
+        if proposal.proposer != proposer {
+            return Err(GovernanceError::NotProposer);
+        }
+        if proposal.status != ProposalStatus::DepositPeriod {
+            return Err(GovernanceError::NotInDepositPeriod);
+        }
+        let other_depositors = self
+            .deposits
+            .iter()
+            .any(|(account, by_id)| account != proposer && by_id.contains_key(&proposal_id));
+        if other_depositors {
+            return Err(GovernanceError::ForeignDeposits);
+        }
+
+        if let Some(proposal) = self.proposals.get_mut(&proposal_id) {
+            proposal.status = ProposalStatus::Cancelled;
+        }
+        Ok(self.take_deposits(proposal_id).into_iter().map(|(_, amount)| amount).sum())
+    }
 
-        proposal.finalize(total_voting_power, &self.config);
+    /// Remove and return every account's deposit on a proposal
+    fn take_deposits(&mut self, proposal_id: u64) -> DepositRefunds {
+        let mut taken = Vec::new();
+        for (account, by_id) in self.deposits.iter_mut() {
+            if let Some(amount) = by_id.remove(&proposal_id) {
+                taken.push((account.clone(), amount));
+            }
+        }
+        self.deposits.retain(|_, by_id| !by_id.is_empty());
+        taken.sort();
+        taken
+    }
 
-        Ok(proposal.status.clone())
+    /// Execute a passed proposal. A treasury spend the treasury can't cover,
+    /// or a parameter change naming an unknown parameter or an invalid value,
+    /// marks the proposal `ExecutionFailed`.
+    pub fn execute_proposal(
+        &mut self,
+        proposal_id: u64,
+        executor: &mut dyn ProposalExecutor,
+    ) -> Result<(), GovernanceError> {
+        self.execute_proposal_at(proposal_id, executor, self.clock.unix_secs())
     }
 
-    /// Execute a passed proposal
-    pub fn execute_proposal(&mut self, proposal_id: u64) -> Result<(), &'static str> {
+    /// `execute_proposal` as of `now` (unix seconds)
+    fn execute_proposal_at(
+        &mut self,
+        proposal_id: u64,
+        executor: &mut dyn ProposalExecutor,
+        now: u64,
+    ) -> Result<(), GovernanceError> {
         let proposal = self
             .proposals
             .get_mut(&proposal_id)
-            .ok_or("Proposal not found")?;
+            .ok_or(GovernanceError::ProposalNotFound)?;
 
         if proposal.status != ProposalStatus::Passed {
-            return Err("Proposal has not passed");
+            return Err(GovernanceError::NotPassed);
         }
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-
         if let Some(exec_time) = proposal.execution_time {
             if now < exec_time {
-                return Err("Execution delay not yet passed");
+                return Err(GovernanceError::ExecutionDelay { execute_at: exec_time });
             }
         }
 
         // Execute based on proposal type
         match &proposal.proposal_type {
             ProposalType::ParameterChange { module, parameter, new_value, .. } => {
-                // In a real implementation, this would update the parameter
+                // Prevent flip-flopping critical parameters
+                let key = format!("{}.{}", module, parameter);
+                if let Some(&last) = self.last_parameter_change.get(&key) {
+                    let until = last + self.config.parameter_change_cooldown;
+                    if now < until {
+                        return Err(GovernanceError::ParameterCooldown { until });
+                    }
+                }
+
+                let applied = match module.as_str() {
+                    "governance" => set_parameter(GOVERNANCE_PARAMETERS, &mut self.config, module, parameter, new_value),
+                    _ => executor.set_parameter(module, parameter, new_value),
+                };
+                if let Err(reason) = applied {
+                    log::warn!("Parameter change of proposal #{} failed: {}", proposal_id, reason);
+                    proposal.status = ProposalStatus::ExecutionFailed { reason: reason.clone() };
+                    return Err(GovernanceError::ExecutionFailed { reason });
+                }
+                self.last_parameter_change.insert(key, now);
                 log::info!(
-                    "Executing parameter change: {}.{} = {}",
+                    "Executed parameter change: {}.{} = {}",
                     module,
                     parameter,
                     new_value
@@ -519,6 +867,14 @@ impl GovernanceManager {
                 );
             }
             ProposalType::TreasurySpend { recipient, amount, reason } => {
+                let spent = u64::try_from(*amount)
+                    .map_err(|_| format!("Amount {} exceeds the native token range", amount))
+                    .and_then(|amount| executor.spend_treasury(recipient, amount));
+                if let Err(reason) = spent {
+                    log::warn!("Treasury spend of proposal #{} failed: {}", proposal_id, reason);
+                    proposal.status = ProposalStatus::ExecutionFailed { reason: reason.clone() };
+                    return Err(GovernanceError::ExecutionFailed { reason });
+                }
                 log::info!(
                     "Treasury spend: {} to {} for {}",
                     amount,
@@ -571,11 +927,54 @@ impl GovernanceManager {
 
     /// Process expired deposit periods
     pub fn process_expired_deposits(&mut self) {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.expire_deposits_at(self.clock.unix_secs());
+    }
+
+    /// Move proposals to a block stamped `now` (unix seconds): expire
+    /// unfunded proposals and finalize those whose voting period has ended,
+    /// in proposal id order. Called while the block is applied, before
+    /// `execute_due`, so every node moves proposals at the same heights.
+    pub fn finalize_due(&mut self, now: u64, stakes: &impl StakeLookup) {
+        self.expire_deposits_at(now);
+        for id in self.sorted_ids() {
+            let proposal = &self.proposals[&id];
+            let voting_over = proposal.status == ProposalStatus::VotingPeriod
+                && proposal.voting_end_time.is_some_and(|end| now >= end);
+            if voting_over {
+                if let Ok((status, _)) = self.finalize_proposal_at(id, stakes, now) {
+                    log::info!("Proposal #{} finalized as {:?}", id, status);
+                }
+            }
+        }
+    }
+
+    /// Execute passed proposals whose execution delay is over at a block
+    /// stamped `now`, in proposal id order. Returns the ids executed.
+    pub fn execute_due(&mut self, now: u64, executor: &mut dyn ProposalExecutor) -> Vec<u64> {
+        let mut executed = Vec::new();
+        for id in self.sorted_ids() {
+            let proposal = &self.proposals[&id];
+            let due = proposal.status == ProposalStatus::Passed
+                && proposal.execution_time.is_none_or(|at| now >= at);
+            if !due {
+                continue;
+            }
+            match self.execute_proposal_at(id, executor, now) {
+                Ok(()) => executed.push(id),
+                Err(e) => log::warn!("Proposal #{} not executed: {}", id, e),
+            }
+        }
+        executed
+    }
 
+    fn sorted_ids(&self) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.proposals.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Expire proposals whose deposit period ended before `now`
+    fn expire_deposits_at(&mut self, now: u64) {
         for proposal in self.proposals.values_mut() {
             if proposal.status == ProposalStatus::DepositPeriod
                 && now > proposal.deposit_end_time
@@ -646,6 +1045,9 @@ pub struct GovernanceStats {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blockchain::chain::tests::test_chain;
+    use crate::blockchain::MiningBudget;
+    use crate::consensus::staking::{StakingConfig, ValidatorDescription};
 
     #[test]
     fn test_create_proposal() {
@@ -665,6 +1067,487 @@ mod tests {
         assert_eq!(proposal.status, ProposalStatus::VotingPeriod);
     }
 
+    #[test]
+    fn test_per_proposer_active_limit() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let limit = gov.config.max_active_proposals_per_proposer;
+        let propose = |gov: &mut GovernanceManager, proposer: &str| {
+            gov.create_proposal(
+                proposer.to_string(),
+                "Spam".to_string(),
+                "Another proposal".to_string(),
+                ProposalType::Text { content: "spam".to_string() },
+                10_000_000_000_000_000_000_000,
+            )
+        };
+
+        for _ in 0..limit {
+            assert!(propose(&mut gov, "0xspam").is_ok());
+        }
+        assert_eq!(propose(&mut gov, "0xspam"), Err(GovernanceError::MaxProposerProposals));
+        // Other proposers still have slots
+        assert!(propose(&mut gov, "0xother").is_ok());
+    }
+
+    #[test]
+    fn test_parameter_change_cooldown() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let change = |value: &str| ProposalType::ParameterChange {
+            module: "blockchain".to_string(),
+            parameter: "block_reward".to_string(),
+            old_value: "100".to_string(),
+            new_value: value.to_string(),
+        };
+
+        let mut ids = Vec::new();
+        for value in ["200", "50"] {
+            let id = gov
+                .create_proposal(
+                    "0x1234".to_string(),
+                    "Change reward".to_string(),
+                    "Adjust block reward".to_string(),
+                    change(value),
+                    10_000_000_000_000_000_000_000,
+                )
+                .unwrap();
+            let proposal = gov.proposals.get_mut(&id).unwrap();
+            proposal.status = ProposalStatus::Passed;
+            proposal.execution_time = None;
+            ids.push(id);
+        }
+
+        let mut chain = test_chain();
+        assert!(gov.execute_proposal(ids[0], &mut chain).is_ok());
+        assert_eq!(chain.state.params.block_reward, 200);
+        // Second change to the same parameter within the cooldown is rejected
+        assert!(matches!(gov.execute_proposal(ids[1], &mut chain), Err(GovernanceError::ParameterCooldown { .. })));
+        assert_eq!(gov.get_proposal(ids[1]).unwrap().status, ProposalStatus::Passed);
+
+        // Once the cooldown has elapsed the change goes through
+        *gov.last_parameter_change.get_mut("blockchain.block_reward").unwrap() -=
+            gov.config.parameter_change_cooldown;
+        assert!(gov.execute_proposal(ids[1], &mut chain).is_ok());
+        assert_eq!(chain.state.params.block_reward, 50);
+    }
+
+    #[test]
+    fn test_parameter_change_applies_to_live_config() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let mut chain = test_chain();
+        let mut staking = StakingManager::new(StakingConfig::default());
+        let passed = |gov: &mut GovernanceManager, module: &str, parameter: &str, value: &str| {
+            let id = gov
+                .create_proposal(
+                    "0x1234".to_string(),
+                    format!("Set {}.{}", module, parameter),
+                    "Tune the protocol".to_string(),
+                    ProposalType::ParameterChange {
+                        module: module.to_string(),
+                        parameter: parameter.to_string(),
+                        old_value: String::new(),
+                        new_value: value.to_string(),
+                    },
+                    10_000_000_000_000_000_000_000,
+                )
+                .unwrap();
+            let proposal = gov.proposals.get_mut(&id).unwrap();
+            proposal.status = ProposalStatus::Passed;
+            proposal.execution_time = None;
+            id
+        };
+
+        for (module, parameter, value) in [
+            ("staking", "min_validator_stake", "25000"),
+            ("governance", "quorum_percentage", "40"),
+            ("blockchain", "treasury_share", "0.2"),
+        ] {
+            let id = passed(&mut gov, module, parameter, value);
+            let mut executor = ProtocolExecutor { chain: &mut chain, staking: &mut staking };
+            gov.execute_proposal(id, &mut executor).unwrap();
+            assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+        }
+        assert_eq!(staking.config.min_validator_stake, 25_000);
+        assert_eq!(gov.config.quorum_percentage, 40);
+        assert_eq!(chain.state.params.treasury_share, 0.2);
+
+        // Unknown parameters and nonsensical values fail execution
+        for (module, parameter, value, reason) in [
+            ("staking", "block_reward", "10", "Unknown parameter staking.block_reward"),
+            ("consensus", "difficulty", "3", "Unknown parameter consensus.difficulty"),
+            ("governance", "pass_threshold", "150", "Invalid value for governance.pass_threshold: must be between 1 and 100"),
+            ("staking", "min_delegation", "0", "Invalid value for staking.min_delegation: must be greater than zero"),
+        ] {
+            let id = passed(&mut gov, module, parameter, value);
+            let mut executor = ProtocolExecutor { chain: &mut chain, staking: &mut staking };
+            assert_eq!(
+                gov.execute_proposal(id, &mut executor),
+                Err(GovernanceError::ExecutionFailed { reason: reason.to_string() })
+            );
+            assert_eq!(
+                gov.get_proposal(id).unwrap().status,
+                ProposalStatus::ExecutionFailed { reason: reason.to_string() }
+            );
+        }
+        assert_eq!(gov.config.pass_threshold, GovernanceConfig::default().pass_threshold);
+        assert_eq!(staking.config.min_delegation, StakingConfig::default().min_delegation);
+    }
+
+    #[test]
+    fn test_voting_power_from_stake() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let mut staking = StakingManager::new(StakingConfig::default());
+        let min_stake = staking.config.min_validator_stake;
+        let min_delegation = staking.config.min_delegation;
+        staking
+            .register_validator("val".to_string(), "op".to_string(), min_stake, 0.1, ValidatorDescription::default())
+            .unwrap();
+        staking.delegate("alice".to_string(), "val".to_string(), min_delegation).unwrap();
+
+        let id = gov
+            .create_proposal(
+                "0x1234".to_string(),
+                "Text".to_string(),
+                "Stake-weighted vote".to_string(),
+                ProposalType::Text { content: "hello".to_string() },
+                10_000_000_000_000_000_000_000,
+            )
+            .unwrap();
+
+        assert_eq!(
+            gov.vote("nobody".to_string(), id, VoteOption::Yes, &staking),
+            Err(GovernanceError::NoStake)
+        );
+
+        gov.vote("val".to_string(), id, VoteOption::Yes, &staking).unwrap();
+        gov.vote("alice".to_string(), id, VoteOption::No, &staking).unwrap();
+
+        let proposal = gov.get_proposal(id).unwrap();
+        assert_eq!(proposal.votes["val"].voting_power, min_stake as u128);
+        assert_eq!(proposal.votes["alice"].voting_power, min_delegation as u128);
+        assert_eq!(proposal.tally.yes, min_stake as u128);
+        assert_eq!(proposal.tally.no, min_delegation as u128);
+        assert_eq!(staking.total_stake(), (min_stake + min_delegation) as u128);
+    }
+
+    #[test]
+    fn test_quadratic_voting_damps_large_stakes() {
+        struct Stakes(HashMap<String, u128>);
+        impl StakeLookup for Stakes {
+            fn stake_of(&self, address: &str) -> u128 {
+                self.0.get(address).copied().unwrap_or(0)
+            }
+            fn total_stake(&self) -> u128 {
+                self.0.values().sum()
+            }
+        }
+
+        // One whale for, twenty small holders against
+        let mut stakes = Stakes(HashMap::from([("whale".to_string(), 1_000_000)]));
+        for i in 0..20 {
+            stakes.0.insert(format!("small{}", i), 10_000);
+        }
+        let run = |scheme: VotingScheme| {
+            let mut config = GovernanceConfig::default();
+            config.voting_schemes.insert("text".to_string(), scheme);
+            let mut gov = GovernanceManager::new(config);
+            let id = gov
+                .create_proposal(
+                    "0x1234".to_string(),
+                    "Scheme".to_string(),
+                    "Whale against the crowd".to_string(),
+                    ProposalType::Text { content: "hello".to_string() },
+                    10_000_000_000_000_000_000_000,
+                )
+                .unwrap();
+            gov.vote("whale".to_string(), id, VoteOption::Yes, &stakes).unwrap();
+            for i in 0..20 {
+                gov.vote(format!("small{}", i), id, VoteOption::No, &stakes).unwrap();
+            }
+            let yes_share = gov.get_proposal(id).unwrap().tally.yes_percentage();
+            gov.proposals.get_mut(&id).unwrap().voting_end_time = Some(0);
+            let (status, _) = gov.finalize_proposal(id, &stakes).unwrap();
+            (yes_share, status, gov)
+        };
+
+        let (linear_share, linear_status, _) = run(VotingScheme::Linear);
+        let (quadratic_share, quadratic_status, mut gov) = run(VotingScheme::Quadratic);
+        // 1,000,000 vs 200,000 linearly; 1,000 vs 2,000 quadratically
+        assert!((linear_share - 83.33).abs() < 0.01);
+        assert!((quadratic_share - 33.33).abs() < 0.01);
+        assert_eq!(linear_status, ProposalStatus::Passed);
+        assert_eq!(quadratic_status, ProposalStatus::Rejected);
+
+        // Stake split into dust can't vote under the quadratic scheme
+        stakes.0.insert("dust".to_string(), gov.config.min_quadratic_vote_stake - 1);
+        let id = gov
+            .create_proposal(
+                "0x1234".to_string(),
+                "Dust".to_string(),
+                "Split stake".to_string(),
+                ProposalType::Text { content: "hello".to_string() },
+                10_000_000_000_000_000_000_000,
+            )
+            .unwrap();
+        assert_eq!(
+            gov.vote("dust".to_string(), id, VoteOption::Yes, &stakes),
+            Err(GovernanceError::StakeBelowQuadraticMinimum {
+                have: gov.config.min_quadratic_vote_stake - 1,
+                need: gov.config.min_quadratic_vote_stake,
+            })
+        );
+
+        // The scheme follows the proposal type, not the proposer
+        let id = gov
+            .create_proposal(
+                "0x5678".to_string(),
+                "Upgrade".to_string(),
+                "Other types stay linear".to_string(),
+                ProposalType::SoftwareUpgrade {
+                    name: "v2".to_string(),
+                    version: "2.0.0".to_string(),
+                    upgrade_height: 1_000,
+                    info: String::new(),
+                },
+                10_000_000_000_000_000_000_000,
+            )
+            .unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().voting_scheme, VotingScheme::Linear);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let id = gov
+            .create_proposal(
+                "0x1234".to_string(),
+                "Persist me".to_string(),
+                "Survives a restart".to_string(),
+                ProposalType::Text { content: "hello".to_string() },
+                10_000_000_000_000_000_000_000,
+            )
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("edgeai-gov-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(GOVERNANCE_FILE);
+        assert!(GovernanceManager::load_from_disk(&path).unwrap().is_none());
+
+        gov.save_to_disk(&path).unwrap();
+        let loaded = GovernanceManager::load_from_disk(&path).unwrap().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.next_proposal_id, gov.next_proposal_id);
+        assert_eq!(loaded.get_proposal(id).unwrap().title, "Persist me");
+        assert_eq!(loaded.get_account_deposits("0x1234"), gov.get_account_deposits("0x1234"));
+    }
+
+    #[test]
+    fn test_cancel_proposal_refunds_proposer() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let deposit = gov.config.min_deposit / 2;
+        let id = gov
+            .create_proposal(
+                "0x1234".to_string(),
+                "Early idea".to_string(),
+                "Still collecting deposits".to_string(),
+                ProposalType::Text { content: "hello".to_string() },
+                deposit,
+            )
+            .unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::DepositPeriod);
+
+        assert_eq!(
+            gov.cancel_proposal("0xother", id),
+            Err(GovernanceError::NotProposer)
+        );
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::DepositPeriod);
+
+        assert_eq!(gov.cancel_proposal("0x1234", id), Ok(deposit));
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::Cancelled);
+        assert!(gov.get_account_deposits("0x1234").is_empty());
+        assert!(gov.cancel_proposal("0x1234", id).is_err());
+    }
+
+    #[test]
+    fn test_veto_burns_deposit() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let mut staking = StakingManager::new(StakingConfig::default());
+        let min_stake = staking.config.min_validator_stake;
+        staking
+            .register_validator("val".to_string(), "op".to_string(), min_stake, 0.1, ValidatorDescription::default())
+            .unwrap();
+
+        let deposit = gov.config.min_deposit;
+        let propose = |gov: &mut GovernanceManager, option: VoteOption| {
+            let id = gov
+                .create_proposal(
+                    "0x1234".to_string(),
+                    "Contested".to_string(),
+                    "Put to a vote".to_string(),
+                    ProposalType::Text { content: "hello".to_string() },
+                    deposit,
+                )
+                .unwrap();
+            gov.vote("val".to_string(), id, option, &staking).unwrap();
+            gov.proposals.get_mut(&id).unwrap().voting_end_time = Some(0);
+            gov.finalize_proposal(id, &staking).unwrap()
+        };
+
+        let (status, refunds) = propose(&mut gov, VoteOption::No);
+        assert_eq!(status, ProposalStatus::Rejected);
+        assert_eq!(refunds, vec![("0x1234".to_string(), deposit)]);
+
+        let (status, refunds) = propose(&mut gov, VoteOption::NoWithVeto);
+        assert_eq!(status, ProposalStatus::Vetoed);
+        assert!(refunds.is_empty());
+        assert_eq!(gov.burned_deposits, deposit);
+        assert!(gov.get_account_deposits("0x1234").is_empty());
+    }
+
+    #[test]
+    fn test_block_application_finalizes_and_executes_proposals() {
+        use crate::blockchain::chain::ConsensusModules;
+
+        let mut chain = test_chain();
+        chain.mine_block("miner".to_string()).unwrap();
+        let treasury = chain.treasury_balance();
+        let mut gov = GovernanceManager::new(GovernanceConfig { execution_delay: 0, ..GovernanceConfig::default() });
+        let mut staking = StakingManager::new(StakingConfig::default());
+        let min_stake = staking.config.min_validator_stake;
+        staking
+            .register_validator("val".to_string(), "op".to_string(), min_stake, 0.1, ValidatorDescription::default())
+            .unwrap();
+        let id = gov
+            .create_proposal(
+                "0x1234".to_string(),
+                "Fund tooling".to_string(),
+                "Pay the SDK maintainers".to_string(),
+                ProposalType::TreasurySpend { recipient: "maintainers".to_string(), amount: 4, reason: "SDK work".to_string() },
+                gov.config.min_deposit,
+            )
+            .unwrap();
+        gov.vote("val".to_string(), id, VoteOption::Yes, &staking).unwrap();
+
+        // Still voting at the next block: nothing moves
+        chain.mine_block_within("miner".to_string(), MiningBudget::UNLIMITED, &mut ConsensusModules::new(&mut staking, &mut gov)).unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::VotingPeriod);
+
+        // The first block past the voting period finalizes and executes it
+        gov.proposals.get_mut(&id).unwrap().voting_end_time = Some(0);
+        chain.mine_block_within("miner".to_string(), MiningBudget::UNLIMITED, &mut ConsensusModules::new(&mut staking, &mut gov)).unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+        assert_eq!(chain.get_balance("maintainers"), 4);
+        assert!(chain.treasury_balance() > treasury - 4, "the treasury keeps its share of the new blocks");
+    }
+
+    #[test]
+    fn test_proposal_lifecycle_with_mock_clock() {
+        use crate::consensus::clock::MockClock;
+        use std::sync::Arc;
+
+        let clock = Arc::new(MockClock::new(chrono::Utc::now()));
+        let mut gov = GovernanceManager::with_clock(GovernanceConfig::default(), clock.clone());
+        let mut staking = StakingManager::with_clock(StakingConfig::default(), clock.clone());
+        let min_stake = staking.config.min_validator_stake;
+        staking
+            .register_validator("val".to_string(), "op".to_string(), min_stake, 0.1, ValidatorDescription::default())
+            .unwrap();
+        let (min_deposit, voting_period, execution_delay) =
+            (gov.config.min_deposit, gov.config.voting_period, gov.config.execution_delay);
+        let secs = |s: u64| chrono::Duration::seconds(s as i64);
+        let propose = |gov: &mut GovernanceManager| {
+            gov.create_proposal(
+                "0x1234".to_string(),
+                "Lifecycle".to_string(),
+                "Walks every stage".to_string(),
+                ProposalType::Text { content: "hello".to_string() },
+                min_deposit / 2,
+            )
+            .unwrap()
+        };
+
+        // Deposit period -> voting period
+        let id = propose(&mut gov);
+        clock.advance(secs(60));
+        gov.add_deposit("0x5678".to_string(), id, min_deposit / 2).unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::VotingPeriod);
+        gov.vote("val".to_string(), id, VoteOption::Yes, &staking).unwrap();
+
+        // Finalizing early is a no-op; after the period voting closes and it passes
+        gov.finalize_proposal(id, &staking).unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::VotingPeriod);
+        clock.advance(secs(voting_period + 1));
+        assert_eq!(
+            gov.vote("val".to_string(), id, VoteOption::No, &staking),
+            Err(GovernanceError::VotingEnded)
+        );
+        let (status, refunds) = gov.finalize_proposal(id, &staking).unwrap();
+        assert_eq!(status, ProposalStatus::Passed);
+        assert_eq!(refunds.len(), 2);
+
+        // Execution waits out the delay
+        let mut chain = test_chain();
+        assert!(matches!(gov.execute_proposal(id, &mut chain), Err(GovernanceError::ExecutionDelay { .. })));
+        clock.advance(secs(execution_delay));
+        gov.execute_proposal(id, &mut chain).unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+
+        // An underfunded proposal expires when its deposit period ends
+        let stale = propose(&mut gov);
+        gov.process_expired_deposits();
+        assert_eq!(gov.get_proposal(stale).unwrap().status, ProposalStatus::DepositPeriod);
+        clock.advance(chrono::Duration::days(2) + chrono::Duration::seconds(1));
+        gov.process_expired_deposits();
+        assert_eq!(gov.get_proposal(stale).unwrap().status, ProposalStatus::Expired);
+    }
+
+    #[test]
+    fn test_treasury_spend_moves_funds() {
+        let mut gov = GovernanceManager::new(GovernanceConfig::default());
+        let mut chain = test_chain();
+        chain.mine_block("miner".to_string()).unwrap();
+        let treasury = chain.treasury_balance();
+        assert!(treasury > 0);
+
+        let spend = |gov: &mut GovernanceManager, amount: u128| {
+            let id = gov
+                .create_proposal(
+                    "0x1234".to_string(),
+                    "Fund tooling".to_string(),
+                    "Pay the SDK maintainers".to_string(),
+                    ProposalType::TreasurySpend {
+                        recipient: "maintainers".to_string(),
+                        amount,
+                        reason: "SDK work".to_string(),
+                    },
+                    10_000_000_000_000_000_000_000,
+                )
+                .unwrap();
+            let proposal = gov.proposals.get_mut(&id).unwrap();
+            proposal.status = ProposalStatus::Passed;
+            proposal.execution_time = None;
+            id
+        };
+
+        let id = spend(&mut gov, 4);
+        gov.execute_proposal(id, &mut chain).unwrap();
+        assert_eq!(gov.get_proposal(id).unwrap().status, ProposalStatus::Executed);
+        let canonical = crate::blockchain::state_dump::CanonicalState::from_state(&chain.state);
+        assert_eq!(canonical.treasury, treasury - 4);
+        assert_eq!(chain.treasury_balance(), treasury - 4);
+        assert_eq!(chain.get_balance("maintainers"), 4);
+
+        // An overspend fails without moving anything
+        let id = spend(&mut gov, treasury as u128);
+        assert!(matches!(gov.execute_proposal(id, &mut chain), Err(GovernanceError::ExecutionFailed { .. })));
+        assert!(matches!(
+            gov.get_proposal(id).unwrap().status,
+            ProposalStatus::ExecutionFailed { .. }
+        ));
+        assert_eq!(chain.treasury_balance(), treasury - 4);
+        assert_eq!(chain.get_balance("maintainers"), 4);
+    }
+
     #[test]
     fn test_vote_tally() {
         let mut tally = VoteTally::default();
@@ -9,6 +9,9 @@ pub mod device_registry;
 pub mod data_quality;
 pub mod staking;
 pub mod governance;
+pub mod parameters;
+pub mod finality;
+pub mod clock;
 
 // Core consensus exports
 pub use poie::PoIEConsensus;
@@ -19,10 +22,13 @@ pub use device_registry::{DeviceRegistry, Device, DeviceType, GeoRegion};
 // Staking exports
 pub use staking::{
     StakingManager, StakingConfig, StakingValidator, ValidatorStatus,
-    ValidatorDescription, Delegation, UnbondingEntry, SlashEvent, SlashReason,
-    StakingStats,
+    ValidatorDescription, ValidatorFilter, ValidatorSortBy, Delegation, DelegatorSlash, UnbondingEntry, SlashEvent, SlashReason,
+    StakingStats, DEFAULT_PERFORMANCE_WINDOW,
 };
 
+// Finality exports
+pub use finality::Attestation;
+
 // Governance exports
 pub use governance::{
     GovernanceManager, GovernanceConfig, GovernanceStats,
@@ -0,0 +1,169 @@
+//! Governable protocol parameters
+//!
+//! A passed `ParameterChange` proposal names a `module` and `parameter`.
+//! Each module has a registry of the parameters governance may change, with
+//! a setter that parses the new value, rejects nonsensical ones and applies
+//! it to the live config. Anything not in a registry can't be changed.
+
+#![allow(dead_code)]
+
+use std::str::FromStr;
+
+use super::governance::GovernanceConfig;
+use super::staking::StakingConfig;
+use crate::blockchain::reward_policy::RewardPolicy;
+use crate::blockchain::Blockchain;
+use crate::blockchain::chain::MAX_REORG_DEPTH;
+use crate::blockchain::params::MAX_TX_POW_BITS;
+
+/// Largest category reward multiplier governance may set
+pub const MAX_REWARD_MULTIPLIER: f64 = 10.0;
+
+/// Parses, validates and applies a new parameter value
+pub type Setter<T> = fn(&mut T, &str) -> Result<(), String>;
+
+/// Parameters of the `blockchain` module
+pub const BLOCKCHAIN_PARAMETERS: &[(&str, Setter<Blockchain>)] = &[
+    ("block_reward", |c, v| { c.state.params.block_reward = positive(v)?; Ok(()) }),
+    ("data_reward_base", |c, v| { c.state.params.data_reward_base = positive(v)?; Ok(()) }),
+    ("treasury_share", |c, v| { c.state.params.treasury_share = fraction(v)?; Ok(()) }),
+    ("tx_pow_bits", |c, v| { c.state.params.tx_pow_bits = at_most(v, MAX_TX_POW_BITS)?; Ok(()) }),
+    ("finality_depth", |c, v| { c.state.params.finality_depth = finality_depth(v)?; Ok(()) }),
+    ("owner_reward_share", |c, v| { c.state.params.owner_reward_share = fraction(v)?; Ok(()) }),
+    ("data_royalty_share", |c, v| { c.state.params.data_royalty_share = fraction(v)?; Ok(()) }),
+    ("min_device_bond", |c, v| { c.state.params.min_device_bond = parse(v)?; Ok(()) }),
+    ("max_bond_multiplier", |c, v| { c.state.params.max_bond_multiplier = bond_multiplier(v)?; Ok(()) }),
+];
+
+/// Flat parameters of the `rewards` module; each category also has
+/// `<Category>.bonus` and `<Category>.multiplier`
+pub const REWARD_PARAMETERS: &[(&str, Setter<RewardPolicy>)] = &[
+    ("base_reward", |c, v| { c.base_reward = parse(v)?; Ok(()) }),
+    ("bytes_per_unit", |c, v| { c.bytes_per_unit = positive(v)?; Ok(()) }),
+];
+
+/// Parameters of the `staking` module
+pub const STAKING_PARAMETERS: &[(&str, Setter<StakingConfig>)] = &[
+    ("min_validator_stake", |c, v| { c.min_validator_stake = positive(v)?; Ok(()) }),
+    ("min_delegation", |c, v| { c.min_delegation = positive(v)?; Ok(()) }),
+    ("unbonding_period", |c, v| { c.unbonding_period = positive(v)?; Ok(()) }),
+    ("max_validators", |c, v| { c.max_validators = positive(v)?; Ok(()) }),
+    ("slash_double_sign", |c, v| { c.slash_double_sign = fraction(v)?; Ok(()) }),
+    ("slash_downtime", |c, v| { c.slash_downtime = fraction(v)?; Ok(()) }),
+    ("min_uptime", |c, v| { c.min_uptime = fraction(v)?; Ok(()) }),
+    ("downtime_window", |c, v| { c.downtime_window = positive(v)?; Ok(()) }),
+    ("reward_epoch_blocks", |c, v| { c.reward_epoch_blocks = positive(v)?; Ok(()) }),
+];
+
+/// Parameters of the `governance` module
+pub const GOVERNANCE_PARAMETERS: &[(&str, Setter<GovernanceConfig>)] = &[
+    ("min_deposit", |c, v| { c.min_deposit = positive(v)?; Ok(()) }),
+    ("voting_period", |c, v| { c.voting_period = positive(v)?; Ok(()) }),
+    ("quorum_percentage", |c, v| { c.quorum_percentage = percentage(v)?; Ok(()) }),
+    ("pass_threshold", |c, v| { c.pass_threshold = percentage(v)?; Ok(()) }),
+    ("veto_threshold", |c, v| { c.veto_threshold = percentage(v)?; Ok(()) }),
+    ("execution_delay", |c, v| { c.execution_delay = parse(v)?; Ok(()) }),
+    ("max_active_proposals", |c, v| { c.max_active_proposals = positive(v)?; Ok(()) }),
+    ("parameter_change_cooldown", |c, v| { c.parameter_change_cooldown = parse(v)?; Ok(()) }),
+];
+
+/// Apply `module.parameter = value` to `target` through `registry`
+pub fn set_parameter<T>(
+    registry: &[(&str, Setter<T>)],
+    target: &mut T,
+    module: &str,
+    parameter: &str,
+    value: &str,
+) -> Result<(), String> {
+    let (_, setter) = registry
+        .iter()
+        .find(|(name, _)| *name == parameter)
+        .ok_or_else(|| unknown_parameter(module, parameter))?;
+    setter(target, value).map_err(|e| format!("Invalid value for {}.{}: {}", module, parameter, e))
+}
+
+/// Apply `rewards.parameter = value` to `policy`
+pub fn set_reward_parameter(policy: &mut RewardPolicy, parameter: &str, value: &str) -> Result<(), String> {
+    let Some((category, field)) = parameter.split_once('.') else {
+        return set_parameter(REWARD_PARAMETERS, policy, "rewards", parameter, value);
+    };
+    let reward = policy
+        .categories
+        .get_mut(category)
+        .ok_or_else(|| unknown_parameter("rewards", parameter))?;
+    let applied = match field {
+        "bonus" => parse(value).map(|bonus| reward.bonus = bonus),
+        "multiplier" => multiplier(value).map(|multiplier| reward.multiplier = multiplier),
+        _ => return Err(unknown_parameter("rewards", parameter)),
+    };
+    applied.map_err(|e| format!("Invalid value for rewards.{}: {}", parameter, e))
+}
+
+pub fn unknown_parameter(module: &str, parameter: &str) -> String {
+    format!("Unknown parameter {}.{}", module, parameter)
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("cannot parse '{}'", value))
+}
+
+fn positive<T: FromStr + PartialOrd + Default>(value: &str) -> Result<T, String> {
+    let parsed = parse(value)?;
+    if parsed <= T::default() {
+        return Err("must be greater than zero".to_string());
+    }
+    Ok(parsed)
+}
+
+/// A share between 0 and 1
+fn fraction(value: &str) -> Result<f64, String> {
+    let parsed: f64 = parse(value)?;
+    if !(0.0..=1.0).contains(&parsed) {
+        return Err("must be between 0 and 1".to_string());
+    }
+    Ok(parsed)
+}
+
+/// A percentage between 1 and 100
+fn percentage(value: &str) -> Result<u8, String> {
+    let parsed: u8 = parse(value)?;
+    if !(1..=100).contains(&parsed) {
+        return Err("must be between 1 and 100".to_string());
+    }
+    Ok(parsed)
+}
+
+/// A reward multiplier between 0 and `MAX_REWARD_MULTIPLIER`
+fn multiplier(value: &str) -> Result<f64, String> {
+    let parsed: f64 = parse(value)?;
+    if !(0.0..=MAX_REWARD_MULTIPLIER).contains(&parsed) {
+        return Err(format!("must be between 0 and {}", MAX_REWARD_MULTIPLIER));
+    }
+    Ok(parsed)
+}
+
+/// A bond reward cap between 1 and `MAX_REWARD_MULTIPLIER`
+fn bond_multiplier(value: &str) -> Result<f64, String> {
+    let parsed: f64 = parse(value)?;
+    if !(1.0..=MAX_REWARD_MULTIPLIER).contains(&parsed) {
+        return Err(format!("must be between 1 and {}", MAX_REWARD_MULTIPLIER));
+    }
+    Ok(parsed)
+}
+
+/// A finality depth the undo log can cover
+fn finality_depth(value: &str) -> Result<u64, String> {
+    let parsed: u64 = positive(value)?;
+    if parsed > MAX_REORG_DEPTH {
+        return Err(format!("must be at most {}", MAX_REORG_DEPTH));
+    }
+    Ok(parsed)
+}
+
+fn at_most<T: FromStr + PartialOrd>(value: &str, max: T) -> Result<T, String> {
+    let parsed = parse(value)?;
+    if parsed > max {
+        return Err("too large".to_string());
+    }
+    Ok(parsed)
+}
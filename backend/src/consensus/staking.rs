@@ -7,11 +7,80 @@
 //! - Slashing for misbehavior (double signing, downtime)
 //! - Reward distribution to validators and delegators
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration};
 use log::{info, warn};
 
+use crate::consensus::clock::{system_clock, SharedClock};
+use crate::consensus::finality::{Attestation, FinalityGadget};
+
+/// Staking snapshot file name inside the data directory
+pub const STAKING_FILE: &str = "staking.json";
+
+/// Blocks of signed/missed history kept per validator
+pub const PERFORMANCE_HISTORY_CAP: usize = 1_000;
+
+/// Window used for recent performance when the caller doesn't pick one
+pub const DEFAULT_PERFORMANCE_WINDOW: usize = 100;
+
+/// Why a staking operation was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum StakingError {
+    #[error("Minimum stake required: {min} EDGE")]
+    BelowMinimumStake { min: u64 },
+    #[error("Minimum delegation: {min} EDGE")]
+    BelowMinimumDelegation { min: u64 },
+    #[error("Insufficient stake: has {have}, needs {need}")]
+    InsufficientStake { have: u64, need: u64 },
+    #[error("Amount must be positive")]
+    ZeroAmount,
+    #[error("Commission rate must be between {}% and {}%", .min * 100.0, .max * 100.0)]
+    InvalidCommission { min: f64, max: f64 },
+    #[error("Commission can change by at most {}% per day", .max * 100.0)]
+    CommissionChangeTooLarge { max: f64 },
+    #[error("Commission was changed less than 24h ago; next change allowed after {next}")]
+    CommissionCooldown { next: DateTime<Utc> },
+    #[error("Maximum validators reached")]
+    MaxValidators,
+    #[error("Maximum delegations reached: {max} validators per delegator")]
+    MaxDelegations { max: usize },
+    #[error("Validator already registered")]
+    ValidatorExists,
+    #[error("Validator not found")]
+    ValidatorNotFound,
+    #[error("Delegation not found")]
+    DelegationNotFound,
+    #[error("Unbonding entry not found")]
+    UnbondingNotFound,
+    #[error("Unbonding already completed")]
+    UnbondingCompleted,
+    #[error("Source and destination validators must differ")]
+    SameValidator,
+    #[error("Redelegation from this validator is on cooldown until {}", .until.to_rfc3339())]
+    RedelegationCooldown { until: DateTime<Utc> },
+    #[error("Cannot {action} jailed validator")]
+    ValidatorJailed { action: &'static str },
+    #[error("Validator is not jailed")]
+    NotJailed,
+    #[error("Jail period not over. Release at: {until}")]
+    JailPeriodActive { until: DateTime<Utc> },
+    #[error("Only the validator operator can change commission")]
+    NotOperator,
+    #[error("Only active validators can attest")]
+    NotEligible,
+    #[error("{0}")]
+    AttestationRejected(String),
+}
+
+impl From<StakingError> for String {
+    fn from(e: StakingError) -> Self {
+        e.to_string()
+    }
+}
+
 /// Staking configuration parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StakingConfig {
@@ -33,6 +102,76 @@ pub struct StakingConfig {
     pub downtime_window: u64,
     /// Commission rate range (min, max)
     pub commission_range: (f64, f64),
+    /// Share of active voting power whose attestations finalize a block
+    #[serde(default = "default_finality_threshold")]
+    pub finality_threshold: f64,
+    /// Blocks a validator flagged for downtime may spend in probation
+    /// before being slashed
+    #[serde(default = "default_downtime_grace_blocks")]
+    pub downtime_grace_blocks: u64,
+    /// Maximum number of distinct validators a single delegator may delegate to
+    #[serde(default = "default_max_delegations_per_delegator")]
+    pub max_delegations_per_delegator: usize,
+    /// Blocks per reward epoch; block rewards accrue in the reward pool and
+    /// are distributed to validators once per epoch
+    #[serde(default = "default_reward_epoch_blocks")]
+    pub reward_epoch_blocks: u64,
+    /// Extra reward weight for uptime above `min_uptime`: a validator at
+    /// 100% uptime earns `1 + uptime_reward_bonus` times the weight of one
+    /// at the minimum (0 disables the bonus)
+    #[serde(default = "default_uptime_reward_bonus")]
+    pub uptime_reward_bonus: f64,
+    /// Reward epochs a commission increase waits before taking effect,
+    /// giving delegators time to move their stake (decreases apply at once)
+    #[serde(default = "default_commission_increase_delay_epochs")]
+    pub commission_increase_delay_epochs: u64,
+    /// Largest commission change (as a fraction, e.g. 0.05 = 5 points) a
+    /// validator may make in one update; updates are at least 24h apart
+    #[serde(default = "default_max_commission_change_per_day")]
+    pub max_commission_change_per_day: f64,
+    /// Seconds a validator jailed for downtime stays jailed
+    #[serde(default = "default_downtime_jail_secs")]
+    pub downtime_jail_secs: i64,
+    /// Release downtime jails automatically once `jail_until` has passed and
+    /// the validator signs again; double-sign jails always need `unjail`
+    #[serde(default = "default_auto_unjail_downtime")]
+    pub auto_unjail_downtime: bool,
+}
+
+fn default_downtime_jail_secs() -> i64 {
+    60 * 60
+}
+
+fn default_auto_unjail_downtime() -> bool {
+    true
+}
+
+fn default_commission_increase_delay_epochs() -> u64 {
+    1
+}
+
+fn default_max_commission_change_per_day() -> f64 {
+    0.05
+}
+
+fn default_reward_epoch_blocks() -> u64 {
+    100
+}
+
+fn default_uptime_reward_bonus() -> f64 {
+    0.2
+}
+
+fn default_max_delegations_per_delegator() -> usize {
+    50
+}
+
+fn default_downtime_grace_blocks() -> u64 {
+    100
+}
+
+fn default_finality_threshold() -> f64 {
+    2.0 / 3.0
 }
 
 impl Default for StakingConfig {
@@ -47,6 +186,15 @@ impl Default for StakingConfig {
             min_uptime: 0.95,
             downtime_window: 1000,
             commission_range: (0.0, 0.25), // 0% - 25%
+            finality_threshold: default_finality_threshold(),
+            downtime_grace_blocks: default_downtime_grace_blocks(),
+            max_delegations_per_delegator: default_max_delegations_per_delegator(),
+            reward_epoch_blocks: default_reward_epoch_blocks(),
+            uptime_reward_bonus: default_uptime_reward_bonus(),
+            commission_increase_delay_epochs: default_commission_increase_delay_epochs(),
+            max_commission_change_per_day: default_max_commission_change_per_day(),
+            downtime_jail_secs: default_downtime_jail_secs(),
+            auto_unjail_downtime: default_auto_unjail_downtime(),
         }
     }
 }
@@ -62,6 +210,69 @@ pub enum ValidatorStatus {
     Jailed,
     /// Unbonding (withdrawing stake)
     Unbonding,
+    /// Flagged for downtime; slashed only if uptime has not recovered
+    /// by the end of the grace window
+    Probation,
+}
+
+impl std::str::FromStr for ValidatorStatus {
+    type Err = String;
+
+    /// Parse a status name case-insensitively (`active`, `Jailed`, ...)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "active" => Ok(ValidatorStatus::Active),
+            "inactive" => Ok(ValidatorStatus::Inactive),
+            "jailed" => Ok(ValidatorStatus::Jailed),
+            "unbonding" => Ok(ValidatorStatus::Unbonding),
+            "probation" => Ok(ValidatorStatus::Probation),
+            _ => Err(format!("Unknown validator status '{}'", s)),
+        }
+    }
+}
+
+/// Order of a validator listing; each key sorts best-first for delegators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValidatorSortBy {
+    /// Highest voting power first
+    #[default]
+    VotingPower,
+    /// Highest uptime first
+    Uptime,
+    /// Lowest commission first
+    Commission,
+    /// Largest total stake first
+    Stake,
+}
+
+impl std::str::FromStr for ValidatorSortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "voting_power" => Ok(ValidatorSortBy::VotingPower),
+            "uptime" => Ok(ValidatorSortBy::Uptime),
+            "commission" => Ok(ValidatorSortBy::Commission),
+            "stake" => Ok(ValidatorSortBy::Stake),
+            _ => Err(format!("Unknown sort_by '{}': expected voting_power, uptime, commission or stake", s)),
+        }
+    }
+}
+
+/// Criteria a validator must meet to be listed
+#[derive(Debug, Clone, Default)]
+pub struct ValidatorFilter {
+    pub status: Option<ValidatorStatus>,
+    pub max_commission: Option<f64>,
+    pub min_stake: Option<u64>,
+}
+
+impl ValidatorFilter {
+    pub fn matches(&self, v: &StakingValidator) -> bool {
+        self.status.as_ref().is_none_or(|status| v.status == *status)
+            && self.max_commission.is_none_or(|max| v.commission_rate <= max)
+            && self.min_stake.is_none_or(|min| v.total_stake() >= min)
+    }
 }
 
 /// Staking validator with enhanced features
@@ -85,10 +296,22 @@ pub struct StakingValidator {
     pub blocks_validated: u64,
     /// Blocks missed in current window
     pub blocks_missed: u64,
+    /// Blocks signed in current window
+    #[serde(default)]
+    pub window_blocks_signed: u64,
     /// Last block signed
     pub last_block_signed: u64,
     /// Jail release time (if jailed)
     pub jail_until: Option<DateTime<Utc>>,
+    /// Offense the validator is jailed for
+    #[serde(default)]
+    pub jail_reason: Option<SlashReason>,
+    /// Block height at which the validator was jailed
+    #[serde(default)]
+    pub jailed_at_height: Option<u64>,
+    /// Block height at which the downtime grace window ends (if on probation)
+    #[serde(default)]
+    pub probation_until: Option<u64>,
     /// Registration time
     pub created_at: DateTime<Utc>,
     /// Total rewards earned
@@ -97,6 +320,31 @@ pub struct StakingValidator {
     pub pending_rewards: u64,
     /// Validator description/metadata
     pub description: ValidatorDescription,
+    /// Commission increase waiting to take effect
+    #[serde(default)]
+    pub pending_commission: Option<PendingCommission>,
+    /// Time of the last commission change
+    #[serde(default)]
+    pub last_commission_update: Option<DateTime<Utc>>,
+    /// Most recent blocks, oldest first, capped at `PERFORMANCE_HISTORY_CAP`
+    #[serde(default)]
+    pub recent_blocks: VecDeque<BlockParticipation>,
+}
+
+/// A validator's participation in one block
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BlockParticipation {
+    pub signed: bool,
+    /// Rewards credited to the validator and its delegators at this block
+    pub reward: u64,
+}
+
+/// A scheduled commission increase
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PendingCommission {
+    pub rate: f64,
+    /// Applied once this many reward epochs have been distributed
+    pub effective_epoch: u64,
 }
 
 /// Validator description metadata
@@ -109,6 +357,12 @@ pub struct ValidatorDescription {
     pub details: Option<String>,
 }
 
+/// Voting power for ordering; NaN sorts last
+fn sortable_power(v: &StakingValidator) -> f64 {
+    let power = v.voting_power();
+    if power.is_nan() { f64::NEG_INFINITY } else { power }
+}
+
 impl StakingValidator {
     pub fn new(
         address: String,
@@ -127,12 +381,19 @@ impl StakingValidator {
             reputation: 50.0,
             blocks_validated: 0,
             blocks_missed: 0,
+            window_blocks_signed: 0,
             last_block_signed: 0,
             jail_until: None,
+            jail_reason: None,
+            jailed_at_height: None,
+            probation_until: None,
             created_at: Utc::now(),
             total_rewards: 0,
             pending_rewards: 0,
             description,
+            pending_commission: None,
+            last_commission_update: None,
+            recent_blocks: VecDeque::new(),
         }
     }
 
@@ -150,20 +411,80 @@ impl StakingValidator {
 
     /// Check if validator is eligible for block production
     pub fn is_eligible(&self) -> bool {
-        self.status == ValidatorStatus::Active && self.total_stake() > 0
+        matches!(self.status, ValidatorStatus::Active | ValidatorStatus::Probation)
+            && self.total_stake() > 0
     }
 
     /// Update uptime statistics
     pub fn record_block_signed(&mut self, block_height: u64) {
         self.blocks_validated += 1;
+        self.window_blocks_signed += 1;
         self.last_block_signed = block_height;
         self.reputation = (self.reputation + 0.1).min(100.0);
+        self.push_participation(true);
     }
 
     /// Record missed block
     pub fn record_block_missed(&mut self) {
         self.blocks_missed += 1;
         self.reputation = (self.reputation - 0.5).max(0.0);
+        self.push_participation(false);
+    }
+
+    fn push_participation(&mut self, signed: bool) {
+        if self.recent_blocks.len() >= PERFORMANCE_HISTORY_CAP {
+            self.recent_blocks.pop_front();
+        }
+        self.recent_blocks.push_back(BlockParticipation { signed, reward: 0 });
+    }
+
+    /// The last `k` blocks of history (fewer if not that many are recorded)
+    fn recent(&self, k: usize) -> impl Iterator<Item = &BlockParticipation> {
+        self.recent_blocks.iter().skip(self.recent_blocks.len().saturating_sub(k))
+    }
+
+    /// Share of the last `k` recorded blocks that were signed
+    pub fn uptime_window(&self, k: usize) -> f64 {
+        let total = self.recent(k).count();
+        if total == 0 {
+            return 1.0;
+        }
+        self.recent(k).filter(|b| b.signed).count() as f64 / total as f64
+    }
+
+    /// Average reward per block over the last `k` recorded blocks
+    pub fn reward_rate(&self, k: usize) -> f64 {
+        let total = self.recent(k).count();
+        if total == 0 {
+            return 0.0;
+        }
+        self.recent(k).map(|b| b.reward).sum::<u64>() as f64 / total as f64
+    }
+
+    /// Jail the validator for `reason` until `until`
+    pub fn jail(&mut self, reason: SlashReason, until: DateTime<Utc>, block_height: u64) {
+        self.status = ValidatorStatus::Jailed;
+        self.jail_until = Some(until);
+        self.jail_reason = Some(reason);
+        self.jailed_at_height = Some(block_height);
+    }
+
+    /// Return to Active and clear the jail record and missed-block count
+    fn release(&mut self) {
+        self.status = ValidatorStatus::Active;
+        self.jail_until = None;
+        self.jail_reason = None;
+        self.jailed_at_height = None;
+        self.blocks_missed = 0;
+    }
+
+    /// Share of blocks signed in the current downtime window
+    pub fn window_uptime(&self) -> f64 {
+        let total = self.window_blocks_signed + self.blocks_missed;
+        if total == 0 {
+            return 1.0;
+        }
+        self.window_blocks_signed as f64 / total as f64
     }
 
     /// Calculate uptime percentage
@@ -189,6 +510,9 @@ pub struct Delegation {
     pub created_at: DateTime<Utc>,
     /// Accumulated rewards
     pub rewards: u64,
+    /// Stake lost to slashes of the validator
+    #[serde(default)]
+    pub slashed_total: u64,
 }
 
 /// Unbonding entry for stake withdrawal
@@ -204,6 +528,23 @@ pub struct UnbondingEntry {
     pub completion_time: DateTime<Utc>,
 }
 
+/// Balance owed for a completed unbonding entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnbondingCredit {
+    /// Account to credit: the delegator, or the operator for a self-unbond
+    pub address: String,
+    pub amount: u64,
+    /// Validator self-stake rather than a delegation
+    pub self_stake: bool,
+}
+
+impl UnbondingEntry {
+    /// Seconds from `now` until the funds unlock (0 once complete)
+    pub fn remaining_secs(&self, now: DateTime<Utc>) -> u64 {
+        (self.completion_time - now).num_seconds().max(0) as u64
+    }
+}
+
 /// Slashing event record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlashEvent {
@@ -217,6 +558,20 @@ pub struct SlashEvent {
     pub block_height: u64,
     /// Timestamp
     pub timestamp: DateTime<Utc>,
+    /// Stake each delegator lost, by delegator address
+    #[serde(default)]
+    pub delegator_losses: HashMap<String, u64>,
+}
+
+/// A slash as seen by one delegator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegatorSlash {
+    pub validator: String,
+    pub reason: SlashReason,
+    /// Stake the delegator lost
+    pub amount: u64,
+    pub block_height: u64,
+    pub timestamp: DateTime<Utc>,
 }
 
 /// Reasons for slashing
@@ -232,7 +587,19 @@ pub enum SlashReason {
     Other(String),
 }
 
+/// Reward multiplier for uptime: 1.0 at or below `min_uptime`, rising
+/// linearly to `1 + uptime_reward_bonus` at 100% uptime
+fn uptime_multiplier(config: &StakingConfig, uptime: f64) -> f64 {
+    let headroom = 1.0 - config.min_uptime;
+    if headroom <= 0.0 {
+        return 1.0;
+    }
+    let above_min = ((uptime - config.min_uptime) / headroom).clamp(0.0, 1.0);
+    1.0 + config.uptime_reward_bonus * above_min
+}
+
 /// Main staking manager
+#[derive(Clone, Serialize, Deserialize)]
 pub struct StakingManager {
     /// Configuration
     pub config: StakingConfig,
@@ -248,11 +615,32 @@ pub struct StakingManager {
     pub total_staked: u64,
     /// Reward pool for distribution
     pub reward_pool: u64,
+    /// Blocks accrued into the reward pool in the current epoch
+    pub epoch_blocks: u64,
+    /// Number of reward distribution passes run
+    pub reward_distributions: u64,
+    /// Attestation-based finality tracking
+    pub finality: FinalityGadget,
+    /// Redelegation cooldowns: delegator -> source validator -> cooldown end
+    #[serde(default)]
+    pub redelegation_cooldowns: HashMap<String, HashMap<String, DateTime<Utc>>>,
+    /// Height at which the current downtime window started
+    #[serde(default)]
+    pub last_downtime_check: u64,
+    /// Time source for unbonding, cooldowns and jail terms
+    #[serde(skip, default = "system_clock")]
+    clock: SharedClock,
 }
 
 impl StakingManager {
     pub fn new(config: StakingConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    /// Staking manager reading time from `clock`
+    pub fn with_clock(config: StakingConfig, clock: SharedClock) -> Self {
         StakingManager {
+            finality: FinalityGadget::new(config.finality_threshold),
             config,
             validators: HashMap::new(),
             delegations: HashMap::new(),
@@ -260,9 +648,35 @@ impl StakingManager {
             slash_history: Vec::new(),
             total_staked: 0,
             reward_pool: 0,
+            epoch_blocks: 0,
+            reward_distributions: 0,
+            redelegation_cooldowns: HashMap::new(),
+            last_downtime_check: 0,
+            clock,
         }
     }
 
+    /// Save the staking state as JSON (written to a temp file, then renamed)
+    pub fn save_to_disk(&self, path: &Path) -> Result<(), String> {
+        let data = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize staking state: {}", e))?;
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, data).map_err(|e| format!("Failed to write staking state: {}", e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write staking state: {}", e))
+    }
+
+    /// Load staking state saved by `save_to_disk`. `Ok(None)` if there is no file.
+    pub fn load_from_disk(path: &Path) -> Result<Option<Self>, String> {
+        let data = match fs::read(path) {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(format!("Failed to read staking state: {}", e)),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse staking state: {}", e))
+    }
+
     /// Register a new validator
     pub fn register_validator(
         &mut self,
@@ -271,43 +685,40 @@ impl StakingManager {
         stake: u64,
         commission_rate: f64,
         description: ValidatorDescription,
-    ) -> Result<(), String> {
+    ) -> Result<(), StakingError> {
         // Check minimum stake
         if stake < self.config.min_validator_stake {
-            return Err(format!(
-                "Minimum stake required: {} EDGE",
-                self.config.min_validator_stake
-            ));
+            return Err(StakingError::BelowMinimumStake { min: self.config.min_validator_stake });
         }
 
         // Check max validators
         if self.validators.len() >= self.config.max_validators {
-            return Err("Maximum validators reached".to_string());
+            return Err(StakingError::MaxValidators);
         }
 
         // Check commission rate
         if commission_rate < self.config.commission_range.0
             || commission_rate > self.config.commission_range.1
         {
-            return Err(format!(
-                "Commission rate must be between {}% and {}%",
-                self.config.commission_range.0 * 100.0,
-                self.config.commission_range.1 * 100.0
-            ));
+            return Err(StakingError::InvalidCommission {
+                min: self.config.commission_range.0,
+                max: self.config.commission_range.1,
+            });
         }
 
         // Check if already registered
         if self.validators.contains_key(&address) {
-            return Err("Validator already registered".to_string());
+            return Err(StakingError::ValidatorExists);
         }
 
-        let validator = StakingValidator::new(
+        let mut validator = StakingValidator::new(
             address.clone(),
             operator_address,
             stake,
             commission_rate,
             description,
         );
+        validator.created_at = self.clock.now();
 
         self.total_staked += stake;
         self.validators.insert(address.clone(), validator);
@@ -326,23 +737,30 @@ impl StakingManager {
         delegator: String,
         validator_address: String,
         amount: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), StakingError> {
         // Check minimum delegation
         if amount < self.config.min_delegation {
-            return Err(format!(
-                "Minimum delegation: {} EDGE",
-                self.config.min_delegation
-            ));
+            return Err(StakingError::BelowMinimumDelegation { min: self.config.min_delegation });
+        }
+
+        // Bound state growth: cap the number of distinct validators per delegator
+        let existing_count = self.delegations.get(&delegator).map(|m| m.len()).unwrap_or(0);
+        let is_new_validator = self
+            .delegations
+            .get(&delegator)
+            .is_none_or(|m| !m.contains_key(&validator_address));
+        if is_new_validator && existing_count >= self.config.max_delegations_per_delegator {
+            return Err(StakingError::MaxDelegations { max: self.config.max_delegations_per_delegator });
         }
 
         // Check validator exists and is active
         let validator = self
             .validators
             .get_mut(&validator_address)
-            .ok_or("Validator not found")?;
+            .ok_or(StakingError::ValidatorNotFound)?;
 
         if validator.status == ValidatorStatus::Jailed {
-            return Err("Cannot delegate to jailed validator".to_string());
+            return Err(StakingError::ValidatorJailed { action: "delegate to" });
         }
 
         // Update validator's delegated stake
@@ -361,8 +779,9 @@ impl StakingManager {
                     delegator: delegator.clone(),
                     validator: validator_address.clone(),
                     amount,
-                    created_at: Utc::now(),
+                    created_at: self.clock.now(),
                     rewards: 0,
+                    slashed_total: 0,
                 },
             );
         }
@@ -382,19 +801,19 @@ impl StakingManager {
         delegator: String,
         validator_address: String,
         amount: u64,
-    ) -> Result<DateTime<Utc>, String> {
+    ) -> Result<DateTime<Utc>, StakingError> {
         // Check delegation exists
         let delegator_delegations = self
             .delegations
             .get_mut(&delegator)
-            .ok_or("No delegations found")?;
+            .ok_or(StakingError::DelegationNotFound)?;
 
         let delegation = delegator_delegations
             .get_mut(&validator_address)
-            .ok_or("Delegation not found")?;
+            .ok_or(StakingError::DelegationNotFound)?;
 
         if delegation.amount < amount {
-            return Err("Insufficient delegation amount".to_string());
+            return Err(StakingError::InsufficientStake { have: delegation.amount, need: amount });
         }
 
         // Update delegation
@@ -411,7 +830,7 @@ impl StakingManager {
         self.total_staked -= amount;
 
         // Create unbonding entry
-        let completion_time = Utc::now() + Duration::seconds(self.config.unbonding_period);
+        let completion_time = self.clock.now() + Duration::seconds(self.config.unbonding_period);
         self.unbonding_queue.push(UnbondingEntry {
             address: delegator.clone(),
             validator: Some(validator_address.clone()),
@@ -429,9 +848,217 @@ impl StakingManager {
         Ok(completion_time)
     }
 
-    /// Process completed unbonding entries
-    pub fn process_unbonding(&mut self) -> Vec<UnbondingEntry> {
-        let now = Utc::now();
+    /// Withdraw `amount` of a validator's self-stake (starts unbonding). The
+    /// remaining self-stake must stay above the minimum unless it is all withdrawn.
+    pub fn unbond_self_stake(&mut self, validator_address: &str, amount: u64) -> Result<DateTime<Utc>, StakingError> {
+        let validator = self
+            .validators
+            .get_mut(validator_address)
+            .ok_or(StakingError::ValidatorNotFound)?;
+        if amount == 0 {
+            return Err(StakingError::ZeroAmount);
+        }
+        if validator.self_stake < amount {
+            return Err(StakingError::InsufficientStake { have: validator.self_stake, need: amount });
+        }
+        let remaining = validator.self_stake - amount;
+        if remaining > 0 && remaining < self.config.min_validator_stake {
+            return Err(StakingError::BelowMinimumStake { min: self.config.min_validator_stake });
+        }
+
+        validator.self_stake = remaining;
+        if remaining == 0 {
+            validator.status = ValidatorStatus::Unbonding;
+        }
+        self.total_staked -= amount;
+
+        let completion_time = self.clock.now() + Duration::seconds(self.config.unbonding_period);
+        self.unbonding_queue.push(UnbondingEntry {
+            address: validator_address.to_string(),
+            validator: None,
+            amount,
+            completion_time,
+        });
+
+        info!(
+            "Validator {} started unbonding {} EDGE of self-stake",
+            &validator_address[..8.min(validator_address.len())],
+            amount
+        );
+        Ok(completion_time)
+    }
+
+    /// Move delegated stake from one validator to another without unbonding.
+    /// A delegator may redelegate away from the same source validator once
+    /// per unbonding period.
+    pub fn redelegate(
+        &mut self,
+        delegator: String,
+        src_validator: String,
+        dst_validator: String,
+        amount: u64,
+    ) -> Result<(), StakingError> {
+        if amount == 0 {
+            return Err(StakingError::ZeroAmount);
+        }
+        if src_validator == dst_validator {
+            return Err(StakingError::SameValidator);
+        }
+
+        let now = self.clock.now();
+        if let Some(until) = self
+            .redelegation_cooldowns
+            .get(&delegator)
+            .and_then(|m| m.get(&src_validator))
+        {
+            if now < *until {
+                return Err(StakingError::RedelegationCooldown { until: *until });
+            }
+        }
+
+        // Validate everything before mutating so the move is atomic
+        let dst = self
+            .validators
+            .get(&dst_validator)
+            .ok_or(StakingError::ValidatorNotFound)?;
+        if dst.status == ValidatorStatus::Jailed {
+            return Err(StakingError::ValidatorJailed { action: "redelegate to" });
+        }
+
+        let delegator_delegations = self
+            .delegations
+            .get(&delegator)
+            .ok_or(StakingError::DelegationNotFound)?;
+        let src = delegator_delegations
+            .get(&src_validator)
+            .ok_or(StakingError::DelegationNotFound)?;
+        if src.amount < amount {
+            return Err(StakingError::InsufficientStake { have: src.amount, need: amount });
+        }
+        if !delegator_delegations.contains_key(&dst_validator)
+            && delegator_delegations.len() >= self.config.max_delegations_per_delegator
+            && src.amount > amount
+        {
+            return Err(StakingError::MaxDelegations { max: self.config.max_delegations_per_delegator });
+        }
+
+        // Move the delegation
+        let delegator_delegations = self.delegations.get_mut(&delegator).ok_or(StakingError::DelegationNotFound)?;
+        if let Some(src) = delegator_delegations.get_mut(&src_validator) {
+            src.amount -= amount;
+            // Keep an emptied delegation around until its rewards are claimed
+            if src.amount == 0 && src.rewards == 0 {
+                delegator_delegations.remove(&src_validator);
+            }
+        }
+        delegator_delegations
+            .entry(dst_validator.clone())
+            .and_modify(|d| d.amount += amount)
+            .or_insert_with(|| Delegation {
+                delegator: delegator.clone(),
+                validator: dst_validator.clone(),
+                amount,
+                created_at: now,
+                rewards: 0,
+                slashed_total: 0,
+            });
+
+        if let Some(v) = self.validators.get_mut(&src_validator) {
+            v.delegated_stake -= amount;
+        }
+        if let Some(v) = self.validators.get_mut(&dst_validator) {
+            v.delegated_stake += amount;
+        }
+
+        self.redelegation_cooldowns
+            .entry(delegator.clone())
+            .or_default()
+            .insert(src_validator.clone(), now + Duration::seconds(self.config.unbonding_period));
+
+        info!(
+            "Delegator {} redelegated {} EDGE from {} to {}",
+            &delegator[..8.min(delegator.len())],
+            amount,
+            &src_validator[..8.min(src_validator.len())],
+            &dst_validator[..8.min(dst_validator.len())]
+        );
+        Ok(())
+    }
+
+    /// Unbonding entries of `address`, in the order they were queued
+    pub fn get_unbonding(&self, address: &str) -> Vec<&UnbondingEntry> {
+        self.unbonding_queue
+            .iter()
+            .filter(|entry| entry.address == address)
+            .collect()
+    }
+
+    /// Current time of the manager's clock
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    /// Cancel the newest in-progress unbonding of `amount` from
+    /// `validator_address` and bond the amount to the validator again.
+    /// Returns the amount re-bonded.
+    pub fn cancel_unbonding(
+        &mut self,
+        delegator: &str,
+        validator_address: &str,
+        amount: u64,
+    ) -> Result<u64, StakingError> {
+        let position = self
+            .unbonding_queue
+            .iter()
+            .rposition(|entry| {
+                entry.address == delegator
+                    && entry.validator.as_deref() == Some(validator_address)
+                    && entry.amount == amount
+            })
+            .ok_or(StakingError::UnbondingNotFound)?;
+        if self.unbonding_queue[position].completion_time <= self.clock.now() {
+            return Err(StakingError::UnbondingCompleted);
+        }
+
+        let validator = self
+            .validators
+            .get_mut(validator_address)
+            .ok_or(StakingError::ValidatorNotFound)?;
+        if validator.status == ValidatorStatus::Jailed {
+            return Err(StakingError::ValidatorJailed { action: "re-bond to" });
+        }
+
+        let amount = self.unbonding_queue.remove(position).amount;
+        validator.delegated_stake += amount;
+        self.total_staked += amount;
+        let now = self.clock.now();
+        self.delegations
+            .entry(delegator.to_string())
+            .or_default()
+            .entry(validator_address.to_string())
+            .and_modify(|d| d.amount += amount)
+            .or_insert_with(|| Delegation {
+                delegator: delegator.to_string(),
+                validator: validator_address.to_string(),
+                amount,
+                created_at: now,
+                rewards: 0,
+                slashed_total: 0,
+            });
+
+        info!(
+            "Delegator {} cancelled unbonding of {} EDGE from validator {}",
+            &delegator[..8.min(delegator.len())],
+            amount,
+            &validator_address[..8.min(validator_address.len())]
+        );
+        Ok(amount)
+    }
+
+    /// Remove completed unbonding entries from the queue and return the
+    /// balances owed for them. Self-unbonds are credited to the operator.
+    pub fn process_unbonding(&mut self) -> Vec<UnbondingCredit> {
+        let now = self.clock.now();
         let (completed, remaining): (Vec<_>, Vec<_>) = self
             .unbonding_queue
             .drain(..)
@@ -439,15 +1066,45 @@ impl StakingManager {
 
         self.unbonding_queue = remaining;
 
-        for entry in &completed {
-            info!(
-                "Unbonding completed: {} EDGE returned to {}",
-                entry.amount,
-                &entry.address[..8.min(entry.address.len())]
-            );
+        completed
+            .into_iter()
+            .map(|entry| {
+                let self_stake = entry.validator.is_none();
+                let address = match self.validators.get(&entry.address) {
+                    Some(validator) if self_stake => validator.operator_address.clone(),
+                    _ => entry.address,
+                };
+                info!(
+                    "Unbonding completed: {} EDGE returned to {}",
+                    entry.amount,
+                    &address[..8.min(address.len())]
+                );
+                UnbondingCredit { address, amount: entry.amount, self_stake }
+            })
+            .collect()
+    }
+
+    /// Account an unbonding entry is credited to: the delegator, or the
+    /// operator for a self-unbond
+    fn unbonding_payee<'a>(&'a self, entry: &'a UnbondingEntry) -> &'a str {
+        match self.validators.get(&entry.address) {
+            Some(validator) if entry.validator.is_none() => &validator.operator_address,
+            _ => &entry.address,
         }
+    }
 
-        completed
+    /// Drop the oldest queued unbonding credited to `address` for `amount`
+    /// once the chain has credited it back. Returns whether one was queued.
+    pub fn complete_unbonding(&mut self, address: &str, amount: u64) -> bool {
+        let Some(position) = self
+            .unbonding_queue
+            .iter()
+            .position(|entry| entry.amount == amount && self.unbonding_payee(entry) == address)
+        else {
+            return false;
+        };
+        self.unbonding_queue.remove(position);
+        true
     }
 
     /// Slash a validator for misbehavior
@@ -456,13 +1113,25 @@ impl StakingManager {
         validator_address: &str,
         reason: SlashReason,
         block_height: u64,
-    ) -> Result<u64, String> {
+    ) -> Result<u64, StakingError> {
+        let now = self.clock.now();
+        self.slash_at(validator_address, reason, block_height, now)
+    }
+
+    /// Slash as of `now`, which stamps the event and starts a double-signing jail
+    fn slash_at(
+        &mut self,
+        validator_address: &str,
+        reason: SlashReason,
+        block_height: u64,
+        now: DateTime<Utc>,
+    ) -> Result<u64, StakingError> {
         // First, get validator info without mutable borrow
         let (total_stake, delegated_stake, self_stake) = {
             let validator = self
                 .validators
                 .get(validator_address)
-                .ok_or("Validator not found")?;
+                .ok_or(StakingError::ValidatorNotFound)?;
             (validator.total_stake(), validator.delegated_stake, validator.self_stake)
         };
 
@@ -489,14 +1158,14 @@ impl StakingManager {
         };
 
         // Collect delegation updates to apply later
-        let delegation_updates: Vec<(String, u64)> = if delegated_slash > 0 {
+        let mut delegation_updates: Vec<(String, u64, u64)> = if delegated_slash > 0 {
             self.delegations
                 .iter()
                 .filter_map(|(delegator, del_map)| {
                     del_map.get(validator_address).map(|d| {
                         let proportion = d.amount as f64 / delegated_stake as f64;
                         let individual_slash = (delegated_slash as f64 * proportion) as u64;
-                        (delegator.clone(), individual_slash)
+                        (delegator.clone(), d.amount, individual_slash)
                     })
                 })
                 .collect()
@@ -504,11 +1173,29 @@ impl StakingManager {
             Vec::new()
         };
 
+        // Rounding down leaves a residual; the largest delegation absorbs it
+        let assigned: u64 = delegation_updates.iter().map(|(_, _, slash)| slash).sum();
+        let residual = delegated_slash.saturating_sub(assigned);
+        if let Some(largest) = delegation_updates
+            .iter_mut()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        {
+            largest.2 += residual;
+        }
+
         // Apply delegation updates
-        for (delegator, slash) in delegation_updates {
-            if let Some(del_map) = self.delegations.get_mut(&delegator) {
-                if let Some(del) = del_map.get_mut(validator_address) {
-                    del.amount = del.amount.saturating_sub(slash);
+        let mut delegator_losses = HashMap::new();
+        for (delegator, _, slash) in delegation_updates {
+            if let Some(del) = self
+                .delegations
+                .get_mut(&delegator)
+                .and_then(|del_map| del_map.get_mut(validator_address))
+            {
+                let loss = slash.min(del.amount);
+                del.amount -= loss;
+                del.slashed_total += loss;
+                if loss > 0 {
+                    delegator_losses.insert(delegator, loss);
                 }
             }
         }
@@ -521,8 +1208,7 @@ impl StakingManager {
 
         // Jail validator for double signing
         if reason == SlashReason::DoubleSigning {
-            validator.status = ValidatorStatus::Jailed;
-            validator.jail_until = Some(Utc::now() + Duration::days(7));
+            validator.jail(reason.clone(), now + Duration::days(7), block_height);
         }
 
         // Check if validator should be deactivated
@@ -538,7 +1224,8 @@ impl StakingManager {
             reason,
             amount: slash_amount,
             block_height,
-            timestamp: Utc::now(),
+            timestamp: now,
+            delegator_losses,
         });
 
         warn!(
@@ -550,31 +1237,127 @@ impl StakingManager {
         Ok(slash_amount)
     }
 
+    /// Validator expected to produce the block at `height`: eligible
+    /// validators take turns in address order
+    pub fn expected_proposer(&self, height: u64) -> Option<&str> {
+        let mut eligible: Vec<&str> = self
+            .validators
+            .values()
+            .filter(|v| v.is_eligible())
+            .map(|v| v.address.as_str())
+            .collect();
+        if eligible.is_empty() {
+            return None;
+        }
+        eligible.sort();
+        Some(eligible[(height % eligible.len() as u64) as usize])
+    }
+
+    /// Record who produced the block at `height`: the producer, if it is a
+    /// validator (jailed ones included), signed it, and the expected
+    /// proposer missed it if it did not
+    pub fn record_block(&mut self, height: u64, producer: &str) {
+        let expected = self.expected_proposer(height).map(str::to_string);
+        if let Some(validator) = self.validators.get_mut(producer) {
+            validator.record_block_signed(height);
+        }
+        if let Some(expected) = expected.filter(|expected| expected != producer) {
+            if let Some(validator) = self.validators.get_mut(&expected) {
+                validator.record_block_missed();
+            }
+        }
+    }
+
+    /// Evaluate validator downtime at `current_height`.
+    ///
+    /// Once every `downtime_window` blocks, each active validator that signed
+    /// less than `min_uptime` of the blocks it was due in the window enters
+    /// probation for `downtime_grace_blocks`, and the window counters start
+    /// over. When the grace window ends, a validator that is still below
+    /// `min_uptime` is slashed and jailed for downtime from `now`, the time
+    /// of the block at `current_height`; one that recovered returns to
+    /// Active. Returns the validators slashed and the amounts.
+    pub fn check_downtime(&mut self, current_height: u64, now: DateTime<Utc>) -> Vec<(String, u64)> {
+        let min_uptime = self.config.min_uptime;
+        let mut offenders: Vec<String> = Vec::new();
+        for (address, validator) in self.validators.iter_mut() {
+            if validator.status != ValidatorStatus::Probation
+                || validator.probation_until.is_some_and(|until| current_height < until)
+            {
+                continue;
+            }
+            validator.probation_until = None;
+            if validator.window_uptime() < min_uptime {
+                offenders.push(address.clone());
+            } else {
+                validator.status = ValidatorStatus::Active;
+                info!("Validator {} recovered from probation", &address[..8.min(address.len())]);
+            }
+        }
+        offenders.sort();
+
+        let jail_until = now + Duration::seconds(self.config.downtime_jail_secs);
+        let mut slashed = Vec::new();
+        for address in offenders {
+            let amount = self.slash_at(&address, SlashReason::Downtime, current_height, now).unwrap_or(0);
+            if let Some(validator) = self.validators.get_mut(&address) {
+                warn!(
+                    "Validator {} jailed for downtime (uptime {:.1}% after probation)",
+                    &address[..8.min(address.len())],
+                    validator.window_uptime() * 100.0
+                );
+                validator.jail(SlashReason::Downtime, jail_until, current_height);
+            }
+            slashed.push((address, amount));
+        }
+
+        if current_height >= self.last_downtime_check + self.config.downtime_window {
+            let grace = self.config.downtime_grace_blocks;
+            for (address, validator) in self.validators.iter_mut() {
+                if validator.status == ValidatorStatus::Active && validator.window_uptime() < min_uptime {
+                    validator.status = ValidatorStatus::Probation;
+                    validator.probation_until = Some(current_height + grace);
+                    warn!(
+                        "Validator {} on probation (uptime {:.1}%) until block {}",
+                        &address[..8.min(address.len())],
+                        validator.window_uptime() * 100.0,
+                        current_height + grace
+                    );
+                }
+                validator.window_blocks_signed = 0;
+                validator.blocks_missed = 0;
+            }
+            self.last_downtime_check = current_height;
+        }
+        slashed
+    }
+
     /// Unjail a validator (after jail period)
-    pub fn unjail(&mut self, validator_address: &str) -> Result<(), String> {
+    pub fn unjail(&mut self, validator_address: &str) -> Result<(), StakingError> {
         let validator = self
             .validators
             .get_mut(validator_address)
-            .ok_or("Validator not found")?;
+            .ok_or(StakingError::ValidatorNotFound)?;
 
         if validator.status != ValidatorStatus::Jailed {
-            return Err("Validator is not jailed".to_string());
+            return Err(StakingError::NotJailed);
         }
 
         if let Some(jail_until) = validator.jail_until {
-            if Utc::now() < jail_until {
-                return Err(format!("Jail period not over. Release at: {}", jail_until));
+            if self.clock.now() < jail_until {
+                return Err(StakingError::JailPeriodActive { until: jail_until });
             }
         }
 
         // Check minimum stake
         if validator.self_stake < self.config.min_validator_stake {
-            return Err("Insufficient stake to unjail".to_string());
+            return Err(StakingError::InsufficientStake {
+                have: validator.self_stake,
+                need: self.config.min_validator_stake,
+            });
         }
 
-        validator.status = ValidatorStatus::Active;
-        validator.jail_until = None;
-        validator.blocks_missed = 0;
+        validator.release();
 
         info!(
             "Validator {} unjailed",
@@ -583,31 +1366,155 @@ impl StakingManager {
         Ok(())
     }
 
-    /// Distribute rewards to validators and delegators
-    pub fn distribute_rewards(&mut self, block_reward: u64) {
+    /// Release validators jailed for downtime whose jail period is over at
+    /// `now`, the time of the block being applied, and that have signed a
+    /// block since being jailed. Returns their addresses.
+    pub fn process_auto_unjail(&mut self, now: DateTime<Utc>) -> Vec<String> {
+        if !self.config.auto_unjail_downtime {
+            return Vec::new();
+        }
+        let min_stake = self.config.min_validator_stake;
+        let mut released = Vec::new();
+
+        for (address, validator) in self.validators.iter_mut() {
+            let eligible = validator.status == ValidatorStatus::Jailed
+                && validator.jail_reason == Some(SlashReason::Downtime)
+                && validator.jail_until.is_none_or(|until| now >= until)
+                && validator.jailed_at_height.is_none_or(|height| validator.last_block_signed > height)
+                && validator.self_stake >= min_stake;
+            if eligible {
+                validator.release();
+                info!(
+                    "Validator {} automatically unjailed after downtime",
+                    &address[..8.min(address.len())]
+                );
+                released.push(address.clone());
+            }
+        }
+        released.sort();
+        released
+    }
+
+    /// Change a validator's commission rate by at most
+    /// `max_commission_change_per_day`, once per 24h. Decreases apply
+    /// immediately; increases are deferred by `commission_increase_delay_epochs`
+    /// so delegators can react before they are paid at the new rate.
+    pub fn update_commission(&mut self, operator: &str, validator_address: &str, new_rate: f64) -> Result<(), StakingError> {
+        let (min_rate, max_rate) = self.config.commission_range;
+        if !(min_rate..=max_rate).contains(&new_rate) {
+            return Err(StakingError::InvalidCommission { min: min_rate, max: max_rate });
+        }
+
+        let delay = self.config.commission_increase_delay_epochs;
+        let effective_epoch = self.reward_distributions + delay;
+        let max_change = self.config.max_commission_change_per_day;
+        let now = self.clock.now();
+        let validator = self
+            .validators
+            .get_mut(validator_address)
+            .ok_or(StakingError::ValidatorNotFound)?;
+        if validator.operator_address != operator {
+            return Err(StakingError::NotOperator);
+        }
+
+        if let Some(last) = validator.last_commission_update {
+            if now < last + Duration::hours(24) {
+                return Err(StakingError::CommissionCooldown { next: last + Duration::hours(24) });
+            }
+        }
+        // Measured from the rate the validator is headed to, including a scheduled increase
+        let current = validator.pending_commission.as_ref().map_or(validator.commission_rate, |p| p.rate);
+        if (new_rate - current).abs() > max_change + f64::EPSILON {
+            return Err(StakingError::CommissionChangeTooLarge { max: max_change });
+        }
+        validator.last_commission_update = Some(now);
+
+        if new_rate <= validator.commission_rate || delay == 0 {
+            validator.commission_rate = new_rate;
+            validator.pending_commission = None;
+            info!(
+                "Validator {} commission set to {:.2}%",
+                &validator_address[..8.min(validator_address.len())],
+                new_rate * 100.0
+            );
+        } else {
+            validator.pending_commission = Some(PendingCommission { rate: new_rate, effective_epoch });
+            info!(
+                "Validator {} commission increase to {:.2}% scheduled for epoch {}",
+                &validator_address[..8.min(validator_address.len())],
+                new_rate * 100.0,
+                effective_epoch
+            );
+        }
+        Ok(())
+    }
+
+    /// Apply scheduled commission increases that have come due
+    fn apply_pending_commissions(&mut self) {
+        let epoch = self.reward_distributions;
+        for validator in self.validators.values_mut() {
+            if let Some(pending) = validator.pending_commission.take() {
+                if pending.effective_epoch <= epoch {
+                    validator.commission_rate = pending.rate;
+                } else {
+                    validator.pending_commission = Some(pending);
+                }
+            }
+        }
+    }
+
+    /// Accrue a block reward into the reward pool, distributing the pool
+    /// at the end of each reward epoch. Returns the amount distributed, if any.
+    pub fn accrue_block_reward(&mut self, block_reward: u64) -> Option<u64> {
+        self.reward_pool += block_reward;
+        self.epoch_blocks += 1;
+
+        if self.epoch_blocks < self.config.reward_epoch_blocks.max(1) {
+            return None;
+        }
+
+        self.epoch_blocks = 0;
+        let pool = self.reward_pool;
+        let distributed = self.distribute_rewards(pool);
+        // Rounding dust stays in the pool for the next epoch
+        self.reward_pool -= distributed;
+        self.apply_pending_commissions();
+        info!("Reward epoch closed: {} EDGE distributed, {} EDGE carried over",
+            distributed, self.reward_pool);
+        Some(distributed)
+    }
+
+    /// Distribute rewards to validators and delegators.
+    /// Returns the amount actually credited (shares are rounded down).
+    pub fn distribute_rewards(&mut self, block_reward: u64) -> u64 {
         if self.validators.is_empty() {
-            return;
+            return 0;
         }
 
-        // Calculate total voting power
-        let total_power: f64 = self
+        // Reward weight: voting power scaled by the uptime multiplier
+        let config = &self.config;
+        let reward_weight = |v: &StakingValidator| v.voting_power() * uptime_multiplier(config, v.uptime());
+        let total_weight: f64 = self
             .validators
             .values()
             .filter(|v| v.is_eligible())
-            .map(|v| v.voting_power())
+            .map(reward_weight)
             .sum();
 
-        if total_power == 0.0 {
-            return;
+        if total_weight == 0.0 {
+            return 0;
         }
 
+        self.reward_distributions += 1;
+        let mut distributed = 0;
+
         // Distribute to each validator proportionally
-        for validator in self.validators.values_mut() {
+        for (address, validator) in self.validators.iter_mut() {
             if !validator.is_eligible() {
                 continue;
             }
 
-            let share = validator.voting_power() / total_power;
+            let share = reward_weight(validator) / total_weight;
             let validator_reward = (block_reward as f64 * share) as u64;
 
             // Commission goes to validator
@@ -615,16 +1522,42 @@ impl StakingManager {
             validator.pending_rewards += commission;
             validator.total_rewards += commission;
 
-            // Remaining goes to delegators (proportionally)
+            // Remaining goes to delegators (proportionally to their amount)
             let delegator_pool = validator_reward - commission;
-            if validator.delegated_stake > 0 && delegator_pool > 0 {
-                // Store for later distribution to delegators
-                validator.pending_rewards += delegator_pool;
-            } else {
-                // If no delegators, all goes to validator
-                validator.pending_rewards += delegator_pool;
+            let total_delegated: u64 = self.delegations
+                .values()
+                .filter_map(|del_map| del_map.get(address))
+                .map(|d| d.amount)
+                .sum();
+            let mut paid = 0;
+            if total_delegated > 0 {
+                for delegation in self.delegations.values_mut().filter_map(|del_map| del_map.get_mut(address)) {
+                    let share = (delegator_pool as u128 * delegation.amount as u128 / total_delegated as u128) as u64;
+                    delegation.rewards += share;
+                    paid += share;
+                }
             }
+            // If no delegators, all goes to validator; so does rounding dust
+            validator.pending_rewards += delegator_pool - paid;
+            if let Some(latest) = validator.recent_blocks.back_mut() {
+                latest.reward += validator_reward;
+            }
+            distributed += validator_reward;
         }
+
+        distributed
+    }
+
+    /// Claim a delegator's accumulated rewards from one validator.
+    /// Returns the claimed amount; the delegation's rewards reset to zero.
+    pub fn claim_rewards(&mut self, delegator: &str, validator: &str) -> Result<u64, StakingError> {
+        let delegation = self
+            .delegations
+            .get_mut(delegator)
+            .and_then(|del_map| del_map.get_mut(validator))
+            .ok_or(StakingError::DelegationNotFound)?;
+
+        Ok(std::mem::take(&mut delegation.rewards))
     }
 
     /// Get all delegations to a specific validator
@@ -647,17 +1580,75 @@ impl StakingManager {
         self.validators.get(address)
     }
 
-    /// Get all active validators sorted by voting power
+    /// Get all active validators sorted by voting power (descending), ties
+    /// broken by address so every node derives the same order
     pub fn get_active_validators(&self) -> Vec<&StakingValidator> {
         let mut validators: Vec<_> = self
             .validators
             .values()
             .filter(|v| v.is_eligible())
             .collect();
-        validators.sort_by(|a, b| b.voting_power().partial_cmp(&a.voting_power()).unwrap());
+        validators.sort_by(|a, b| {
+            sortable_power(b)
+                .total_cmp(&sortable_power(a))
+                .then_with(|| a.address.cmp(&b.address))
+        });
         validators
     }
 
+    /// Validators matching `filter` in `sort_by` order, ties broken by address
+    pub fn find_validators(&self, filter: &ValidatorFilter, sort_by: ValidatorSortBy) -> Vec<&StakingValidator> {
+        let mut validators: Vec<_> = self.validators.values().filter(|v| filter.matches(v)).collect();
+        validators.sort_by(|a, b| {
+            let order = match sort_by {
+                ValidatorSortBy::VotingPower => sortable_power(b).total_cmp(&sortable_power(a)),
+                ValidatorSortBy::Uptime => b.uptime().total_cmp(&a.uptime()),
+                ValidatorSortBy::Commission => a.commission_rate.total_cmp(&b.commission_rate),
+                ValidatorSortBy::Stake => b.total_stake().cmp(&a.total_stake()),
+            };
+            order.then_with(|| a.address.cmp(&b.address))
+        });
+        validators
+    }
+
+    /// Total voting power of the active validator set
+    pub fn total_voting_power(&self) -> f64 {
+        self.get_active_validators().iter().map(|v| v.voting_power()).sum()
+    }
+
+    /// Record a validator's attestation for a block.
+    /// Returns true if the attestation finalized the block.
+    pub fn attest(
+        &mut self,
+        validator_address: &str,
+        height: u64,
+        block_hash: &str,
+        signature: String,
+    ) -> Result<bool, StakingError> {
+        let validator = self
+            .validators
+            .get(validator_address)
+            .ok_or(StakingError::ValidatorNotFound)?;
+
+        if !validator.is_eligible() {
+            return Err(StakingError::NotEligible);
+        }
+
+        let attestation = Attestation {
+            validator: validator_address.to_string(),
+            height,
+            block_hash: block_hash.to_string(),
+            signature,
+            voting_power: validator.voting_power(),
+            timestamp: self.clock.now(),
+        };
+
+        let total_power = self.total_voting_power();
+        self.finality
+            .add_attestation(attestation, total_power)
+            .map_err(StakingError::AttestationRejected)
+    }
+
     /// Get delegations for a delegator
     pub fn get_delegations(&self, delegator: &str) -> Vec<&Delegation> {
         self.delegations
@@ -666,6 +1657,35 @@ impl StakingManager {
             .unwrap_or_default()
     }
 
+    /// Slashes that cost `delegator` stake, oldest first
+    pub fn delegator_slashes(&self, delegator: &str) -> Vec<DelegatorSlash> {
+        self.slash_history
+            .iter()
+            .filter_map(|event| {
+                event.delegator_losses.get(delegator).map(|amount| DelegatorSlash {
+                    validator: event.validator.clone(),
+                    reason: event.reason.clone(),
+                    amount: *amount,
+                    block_height: event.block_height,
+                    timestamp: event.timestamp,
+                })
+            })
+            .collect()
+    }
+
+    /// Uptime, reward rate and slash count of a validator over its last `window` blocks
+    pub fn validator_performance(&self, address: &str, window: usize) -> Option<ValidatorPerformance> {
+        let validator = self.validators.get(address)?;
+        Some(ValidatorPerformance {
+            address: validator.address.clone(),
+            window: validator.recent_blocks.len().min(window),
+            uptime: validator.uptime_window(window),
+            reward_rate: validator.reward_rate(window),
+            lifetime_uptime: validator.uptime(),
+            slash_count: self.slash_history.iter().filter(|e| e.validator == address).count(),
+        })
+    }
+
     /// Get staking statistics
     pub fn get_stats(&self) -> StakingStats {
         let active_validators = self.validators.values().filter(|v| v.is_eligible()).count();
@@ -690,6 +1710,19 @@ impl StakingManager {
     }
 }
 
+/// Recent validator performance, for delegators comparing validators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorPerformance {
+    pub address: String,
+    /// Blocks the figures cover (less than requested while history is short)
+    pub window: usize,
+    pub uptime: f64,
+    /// Average reward per block over the window
+    pub reward_rate: f64,
+    pub lifetime_uptime: f64,
+    pub slash_count: usize,
+}
+
 /// Staking statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StakingStats {
@@ -707,6 +1740,29 @@ pub struct StakingStats {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_find_validators_filters_and_sorts() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for (address, stake, commission) in [("v1", 30_000, 0.10), ("v2", 10_000, 0.05), ("v3", 20_000, 0.20), ("v4", 50_000, 0.01)] {
+            manager
+                .register_validator(address.to_string(), format!("op_{}", address), stake, commission, ValidatorDescription::default())
+                .unwrap();
+        }
+        manager.validators.get_mut("v4").unwrap().status = ValidatorStatus::Jailed;
+
+        let addresses = |validators: Vec<&StakingValidator>| -> Vec<String> {
+            validators.iter().map(|v| v.address.clone()).collect()
+        };
+        let active = ValidatorFilter { status: Some("active".parse().unwrap()), ..Default::default() };
+        assert_eq!(addresses(manager.find_validators(&active, ValidatorSortBy::Commission)), ["v2", "v1", "v3"]);
+        assert_eq!(addresses(manager.find_validators(&active, ValidatorSortBy::Stake)), ["v1", "v3", "v2"]);
+
+        // Without a status filter the jailed validator is listed too
+        let cheap = ValidatorFilter { max_commission: Some(0.10), min_stake: Some(20_000), ..Default::default() };
+        assert_eq!(addresses(manager.find_validators(&cheap, ValidatorSortBy::Commission)), ["v4", "v1"]);
+        assert!("fees".parse::<ValidatorSortBy>().is_err());
+    }
+
     #[test]
     fn test_validator_registration() {
         let mut manager = StakingManager::new(StakingConfig::default());
@@ -758,6 +1814,219 @@ mod tests {
         assert_eq!(validator.total_stake(), 11_000);
     }
 
+    #[test]
+    fn test_max_delegations_per_delegator() {
+        let config = StakingConfig {
+            max_delegations_per_delegator: 2,
+            ..StakingConfig::default()
+        };
+        let mut manager = StakingManager::new(config);
+        for v in ["v1", "v2", "v3"] {
+            manager
+                .register_validator(v.to_string(), "op".to_string(), 10_000, 0.1, ValidatorDescription::default())
+                .unwrap();
+        }
+
+        assert!(manager.delegate("d".to_string(), "v1".to_string(), 100).is_ok());
+        assert!(manager.delegate("d".to_string(), "v2".to_string(), 100).is_ok());
+        // Third distinct validator exceeds the cap
+        let err = manager.delegate("d".to_string(), "v3".to_string(), 100).unwrap_err();
+        assert_eq!(err, StakingError::MaxDelegations { max: 2 });
+        // Topping up an existing delegation is still allowed
+        assert!(manager.delegate("d".to_string(), "v1".to_string(), 100).is_ok());
+        assert_eq!(manager.get_delegations("d").len(), 2);
+    }
+
+    #[test]
+    fn test_active_validators_tie_break_by_address() {
+        let build = |addrs: [&str; 4]| {
+            let mut manager = StakingManager::new(StakingConfig::default());
+            for v in addrs {
+                manager
+                    .register_validator(v.to_string(), "op".to_string(), 10_000, 0.1, ValidatorDescription::default())
+                    .unwrap();
+            }
+            manager.validators.get_mut("v4").unwrap().self_stake = 20_000;
+            manager.validators.get_mut("v2").unwrap().reputation = f64::NAN;
+            manager
+        };
+        let order = |m: &StakingManager| -> Vec<String> {
+            m.get_active_validators().iter().map(|v| v.address.clone()).collect()
+        };
+
+        // Equal power is ordered by address; NaN power sorts last instead of panicking
+        let first = build(["v3", "v1", "v4", "v2"]);
+        assert_eq!(order(&first), vec!["v4", "v1", "v3", "v2"]);
+        // Registration (and HashMap) order doesn't matter
+        let second = build(["v2", "v4", "v1", "v3"]);
+        assert_eq!(order(&first), order(&second));
+    }
+
+    #[test]
+    fn test_unbonding_query_and_cancel() {
+        use crate::consensus::clock::MockClock;
+        use std::sync::Arc;
+
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let mut manager = StakingManager::with_clock(StakingConfig::default(), clock.clone());
+        manager
+            .register_validator("v1".to_string(), "op".to_string(), 10_000, 0.1, ValidatorDescription::default())
+            .unwrap();
+        manager.delegate("d".to_string(), "v1".to_string(), 1_000).unwrap();
+        manager.undelegate("d".to_string(), "v1".to_string(), 400).unwrap();
+        clock.advance(Duration::days(2));
+        manager.undelegate("d".to_string(), "v1".to_string(), 600).unwrap();
+        assert!(manager.get_delegations("d").is_empty());
+
+        let period = manager.config.unbonding_period as u64;
+        let entries = manager.get_unbonding("d");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].remaining_secs(manager.now()), period - 2 * 24 * 60 * 60);
+        assert_eq!(entries[1].remaining_secs(manager.now()), period);
+        assert!(manager.get_unbonding("v1").is_empty());
+
+        // Cancel the second entry mid-period: the delegation comes back
+        clock.advance(Duration::days(3));
+        let staked = manager.total_staked;
+        assert_eq!(manager.cancel_unbonding("d", "v1", 600).unwrap(), 600);
+        assert_eq!(manager.get_delegations("d")[0].amount, 600);
+        assert_eq!(manager.get_validator("v1").unwrap().delegated_stake, 600);
+        assert_eq!(manager.total_staked, staked + 600);
+        assert_eq!(manager.get_unbonding("d").len(), 1);
+        assert!(manager.cancel_unbonding("d", "v1", 600).is_err());
+
+        // A jailed validator can't take the stake back; a completed entry can't be cancelled
+        manager.validators.get_mut("v1").unwrap().status = ValidatorStatus::Jailed;
+        assert_eq!(
+            manager.cancel_unbonding("d", "v1", 400).unwrap_err().to_string(),
+            "Cannot re-bond to jailed validator"
+        );
+        manager.validators.get_mut("v1").unwrap().status = ValidatorStatus::Active;
+        clock.advance(Duration::days(2));
+        assert_eq!(manager.get_unbonding("d")[0].remaining_secs(manager.now()), 0);
+        assert_eq!(manager.cancel_unbonding("d", "v1", 400).unwrap_err(), StakingError::UnbondingCompleted);
+    }
+
+    #[test]
+    fn test_process_unbonding_yields_credits() {
+        use crate::consensus::clock::MockClock;
+        use std::sync::Arc;
+
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let mut manager = StakingManager::with_clock(StakingConfig::default(), clock.clone());
+        manager
+            .register_validator("v1".to_string(), "op".to_string(), 20_000, 0.1, ValidatorDescription::default())
+            .unwrap();
+        manager.delegate("d".to_string(), "v1".to_string(), 1_000).unwrap();
+        manager.undelegate("d".to_string(), "v1".to_string(), 400).unwrap();
+        assert!(manager.unbond_self_stake("v1", 15_000).is_err());
+        manager.unbond_self_stake("v1", 10_000).unwrap();
+        clock.advance(Duration::days(1));
+        manager.undelegate("d".to_string(), "v1".to_string(), 600).unwrap();
+
+        // Nothing is due before the period ends
+        assert!(manager.process_unbonding().is_empty());
+        assert_eq!(manager.unbonding_queue.len(), 3);
+
+        clock.advance(Duration::seconds(manager.config.unbonding_period - 24 * 60 * 60));
+        let credits = manager.process_unbonding();
+        assert_eq!(
+            credits,
+            vec![
+                UnbondingCredit { address: "d".to_string(), amount: 400, self_stake: false },
+                UnbondingCredit { address: "op".to_string(), amount: 10_000, self_stake: true },
+            ]
+        );
+        // The later entry stays queued and completed ones are not paid twice
+        assert_eq!(manager.get_unbonding("d").len(), 1);
+        assert!(manager.process_unbonding().is_empty());
+
+        clock.advance(Duration::days(1));
+        assert_eq!(manager.process_unbonding()[0].amount, 600);
+        assert!(manager.unbonding_queue.is_empty());
+    }
+
+    #[test]
+    fn test_commission_increase_deferred_decrease_immediate() {
+        use crate::consensus::clock::MockClock;
+        use std::sync::Arc;
+
+        let config = StakingConfig {
+            reward_epoch_blocks: 2,
+            max_commission_change_per_day: 1.0,
+            ..StakingConfig::default()
+        };
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let mut manager = StakingManager::with_clock(config, clock.clone());
+        manager
+            .register_validator("v1".to_string(), "op".to_string(), 10_000, 0.10, ValidatorDescription::default())
+            .unwrap();
+        manager.delegate("d".to_string(), "v1".to_string(), 10_000).unwrap();
+
+        assert!(manager.update_commission("mallory", "v1", 0.05).is_err());
+        assert!(manager.update_commission("op", "v1", 0.30).is_err());
+
+        // An increase waits for the current epoch to close
+        manager.update_commission("op", "v1", 0.25).unwrap();
+        let v = manager.get_validator("v1").unwrap();
+        assert_eq!(v.commission_rate, 0.10);
+        assert_eq!(v.pending_commission.as_ref().unwrap().rate, 0.25);
+
+        assert!(manager.accrue_block_reward(1_000).is_none());
+        assert_eq!(manager.get_validator("v1").unwrap().commission_rate, 0.10);
+        // Rewards for this epoch are still paid at the old rate
+        manager.accrue_block_reward(1_000).unwrap();
+        let v = manager.get_validator("v1").unwrap();
+        assert_eq!(v.pending_rewards, 200);
+        assert_eq!(v.commission_rate, 0.25);
+        assert!(v.pending_commission.is_none());
+
+        // A decrease applies at once and cancels any scheduled increase
+        for rate in [0.20, 0.15, 0.22, 0.05] {
+            clock.advance(Duration::days(1));
+            manager.update_commission("op", "v1", rate).unwrap();
+        }
+        let v = manager.get_validator("v1").unwrap();
+        assert_eq!(v.commission_rate, 0.05);
+        assert!(v.pending_commission.is_none());
+    }
+
+    #[test]
+    fn test_commission_change_limited_per_day() {
+        use crate::consensus::clock::{Clock, MockClock};
+        use std::sync::Arc;
+
+        let clock = Arc::new(MockClock::new(Utc::now()));
+        let mut manager = StakingManager::with_clock(StakingConfig::default(), clock.clone());
+        manager
+            .register_validator("v1".to_string(), "op".to_string(), 10_000, 0.10, ValidatorDescription::default())
+            .unwrap();
+
+        assert_eq!(manager.update_commission("mallory", "v1", 0.08).unwrap_err(), StakingError::NotOperator);
+        assert!(matches!(
+            manager.update_commission("op", "v1", 0.30).unwrap_err(),
+            StakingError::InvalidCommission { .. }
+        ));
+        assert!(matches!(
+            manager.update_commission("op", "v1", 0.20).unwrap_err(),
+            StakingError::CommissionChangeTooLarge { .. }
+        ));
+
+        manager.update_commission("op", "v1", 0.05).unwrap();
+        let v = manager.get_validator("v1").unwrap();
+        assert_eq!(v.commission_rate, 0.05);
+        assert_eq!(v.last_commission_update, Some(clock.now()));
+
+        // A second change has to wait a day
+        clock.advance(Duration::hours(23));
+        assert!(matches!(
+            manager.update_commission("op", "v1", 0.06).unwrap_err(),
+            StakingError::CommissionCooldown { .. }
+        ));
+        clock.advance(Duration::hours(1));
+        manager.update_commission("op", "v1", 0.06).unwrap();
+    }
+
     #[test]
     fn test_slashing() {
         let mut manager = StakingManager::new(StakingConfig::default());
@@ -782,4 +2051,422 @@ mod tests {
         assert_eq!(validator.self_stake, 9_500);
         assert_eq!(validator.status, ValidatorStatus::Jailed);
     }
+
+    #[test]
+    fn test_slash_records_delegator_losses() {
+        let config = StakingConfig { slash_double_sign: 0.9, ..StakingConfig::default() };
+        let mut manager = StakingManager::new(config);
+        manager
+            .register_validator("validator1".to_string(), "op1".to_string(), 10_000, 0.1, ValidatorDescription::default())
+            .unwrap();
+        for (delegator, amount) in [("d1", 1_001), ("d2", 2_003), ("d3", 3_007)] {
+            manager.delegate(delegator.to_string(), "validator1".to_string(), amount).unwrap();
+        }
+
+        // 90% of 16,011 is 14,409: 10,000 from self-stake, 4,409 from delegators
+        let slashed = manager.slash("validator1", SlashReason::DoubleSigning, 10).unwrap();
+        assert_eq!(slashed, 14_409);
+        let delegated_slash = slashed - 10_000;
+        assert_eq!(manager.get_validator("validator1").unwrap().delegated_stake, 6_011 - delegated_slash);
+
+        let event = manager.slash_history.last().unwrap();
+        assert_eq!(event.delegator_losses.values().sum::<u64>(), delegated_slash);
+        let slashed_totals: u64 = ["d1", "d2", "d3"]
+            .iter()
+            .map(|d| manager.get_delegations(d)[0].slashed_total)
+            .sum();
+        assert_eq!(slashed_totals, delegated_slash);
+        // Proportional shares round down to 734 + 1,469 + 2,205; d3 holds the
+        // largest delegation and absorbs the residual
+        assert_eq!(event.delegator_losses["d1"], 734);
+        assert_eq!(event.delegator_losses["d2"], 1_469);
+        assert_eq!(event.delegator_losses["d3"], 2_206);
+
+        let slashes = manager.delegator_slashes("d1");
+        assert_eq!(slashes.len(), 1);
+        assert_eq!(slashes[0].validator, "validator1");
+        assert_eq!(slashes[0].amount, manager.get_delegations("d1")[0].slashed_total);
+        assert!(manager.delegator_slashes("validator1").is_empty());
+    }
+
+    #[test]
+    fn test_auto_unjail_downtime_only() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for v in ["sleepy", "cheater"] {
+            manager
+                .register_validator(v.to_string(), format!("op_{}", v), 20_000, 0.1, ValidatorDescription::default())
+                .unwrap();
+        }
+        let past = Utc::now() - Duration::seconds(1);
+        manager.validators.get_mut("sleepy").unwrap().jail(SlashReason::Downtime, past, 50);
+        manager.slash("cheater", SlashReason::DoubleSigning, 50).unwrap();
+        manager.validators.get_mut("cheater").unwrap().jail_until = Some(past);
+
+        // Grace period over, but neither has signed since being jailed
+        assert!(manager.process_auto_unjail(Utc::now()).is_empty());
+
+        for v in ["sleepy", "cheater"] {
+            manager.validators.get_mut(v).unwrap().record_block_signed(51);
+        }
+        assert_eq!(manager.process_auto_unjail(Utc::now()), vec!["sleepy".to_string()]);
+        let sleepy = manager.get_validator("sleepy").unwrap();
+        assert_eq!(sleepy.status, ValidatorStatus::Active);
+        assert!(sleepy.jail_reason.is_none());
+        // The double signer stays jailed until unjailed by hand
+        assert_eq!(manager.get_validator("cheater").unwrap().status, ValidatorStatus::Jailed);
+        assert!(manager.unjail("cheater").is_ok());
+
+        // Disabled by configuration
+        manager.config.auto_unjail_downtime = false;
+        manager.validators.get_mut("sleepy").unwrap().jail(SlashReason::Downtime, past, 40);
+        assert!(manager.process_auto_unjail(Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_check_downtime_jails_below_min_uptime() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        let window = manager.config.downtime_window;
+        let grace = manager.config.downtime_grace_blocks;
+        for v in ["steady", "offline"] {
+            manager
+                .register_validator(v.to_string(), format!("op_{}", v), 20_000, 0.1, ValidatorDescription::default())
+                .unwrap();
+        }
+        // 96% and 90% uptime against a 95% minimum
+        for (v, missed) in [("steady", window / 25), ("offline", window / 10)] {
+            let validator = manager.validators.get_mut(v).unwrap();
+            for h in 0..window - missed {
+                validator.record_block_signed(h);
+            }
+            for _ in 0..missed {
+                validator.record_block_missed();
+            }
+        }
+
+        // Nothing happens before the window closes
+        let block_time = Utc::now() - Duration::days(30);
+        assert!(manager.check_downtime(window - 1, block_time).is_empty());
+        assert_eq!(manager.get_validator("offline").unwrap().status, ValidatorStatus::Active);
+
+        // Below the minimum: probation first, not slashing
+        assert!(manager.check_downtime(window, block_time).is_empty());
+        assert_eq!(manager.get_validator("offline").unwrap().status, ValidatorStatus::Probation);
+        let steady = manager.get_validator("steady").unwrap();
+        assert_eq!(steady.status, ValidatorStatus::Active);
+        // Counters start over for the next window
+        assert_eq!((steady.window_blocks_signed, steady.blocks_missed), (0, 0));
+
+        // Still missing blocks when the grace window ends
+        let offline = manager.validators.get_mut("offline").unwrap();
+        offline.record_block_signed(window + 1);
+        offline.record_block_missed();
+        assert!(manager.check_downtime(window + grace - 1, block_time).is_empty());
+        let slashed = manager.check_downtime(window + grace, block_time);
+        assert_eq!(slashed, vec![("offline".to_string(), 200)]);
+        let offline = manager.get_validator("offline").unwrap();
+        assert_eq!(offline.status, ValidatorStatus::Jailed);
+        assert_eq!(offline.jail_reason, Some(SlashReason::Downtime));
+        // Jailed from the block's time, not the wall clock
+        assert_eq!(offline.jail_until, Some(block_time + Duration::seconds(manager.config.downtime_jail_secs)));
+        assert_eq!(manager.get_validator("steady").unwrap().self_stake, 20_000);
+    }
+
+    #[test]
+    fn test_downtime_probation_grace_window() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        let window = manager.config.downtime_window;
+        let grace = manager.config.downtime_grace_blocks;
+        for v in ["flaky", "down"] {
+            manager
+                .register_validator(v.to_string(), "op".to_string(), 20_000, 0.1, ValidatorDescription::default())
+                .unwrap();
+            let validator = manager.validators.get_mut(v).unwrap();
+            for h in 0..90 {
+                validator.record_block_signed(h);
+            }
+            for _ in 0..10 {
+                validator.record_block_missed();
+            }
+        }
+
+        // Both fall below 95% uptime: probation, not slashing
+        let block_time = Utc::now();
+        assert!(manager.check_downtime(window, block_time).is_empty());
+        assert_eq!(manager.get_validator("flaky").unwrap().status, ValidatorStatus::Probation);
+        assert_eq!(manager.get_validator("down").unwrap().status, ValidatorStatus::Probation);
+
+        // "flaky" resumes signing within the grace window and recovers
+        let flaky = manager.validators.get_mut("flaky").unwrap();
+        for h in window..window + grace {
+            flaky.record_block_signed(h);
+        }
+        manager.validators.get_mut("down").unwrap().record_block_missed();
+        let slashed = manager.check_downtime(window + grace, block_time);
+        assert_eq!(manager.get_validator("flaky").unwrap().status, ValidatorStatus::Active);
+        assert_eq!(manager.get_validator("flaky").unwrap().self_stake, 20_000);
+
+        // "down" is still below the threshold when the window expires
+        assert_eq!(slashed, vec![("down".to_string(), 200)]);
+        assert_eq!(manager.get_validator("down").unwrap().self_stake, 19_800);
+        assert_eq!(manager.get_validator("down").unwrap().status, ValidatorStatus::Jailed);
+    }
+
+    #[test]
+    fn test_record_block_tracks_producer_and_expected_proposer() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for v in ["v1", "v2"] {
+            manager
+                .register_validator(v.to_string(), "op".to_string(), 20_000, 0.1, ValidatorDescription::default())
+                .unwrap();
+        }
+        assert_eq!(manager.expected_proposer(4), Some("v1"));
+        assert_eq!(manager.expected_proposer(5), Some("v2"));
+
+        // v1 produces its own block, then v2's
+        manager.record_block(4, "v1");
+        manager.record_block(5, "v1");
+        // A non-validator producing v1's block
+        manager.record_block(6, "miner");
+
+        let v1 = manager.get_validator("v1").unwrap();
+        assert_eq!((v1.blocks_validated, v1.blocks_missed, v1.last_block_signed), (2, 1, 5));
+        let v2 = manager.get_validator("v2").unwrap();
+        assert_eq!((v2.blocks_validated, v2.blocks_missed), (0, 1));
+        assert_eq!(manager.validator_performance("v1", 10).unwrap().uptime, 2.0 / 3.0);
+
+        // A jailed validator that produces a block has signed again
+        manager.validators.get_mut("v2").unwrap().jail(SlashReason::Downtime, Utc::now(), 6);
+        assert_eq!(manager.expected_proposer(7), Some("v1"));
+        manager.record_block(7, "v2");
+        assert_eq!(manager.get_validator("v2").unwrap().last_block_signed, 7);
+    }
+
+    #[test]
+    fn test_attestation_finality() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for v in ["v1", "v2", "v3", "v4"] {
+            manager
+                .register_validator(
+                    v.to_string(),
+                    "op".to_string(),
+                    10_000,
+                    0.1,
+                    ValidatorDescription::default(),
+                )
+                .unwrap();
+        }
+
+        // Two of four equal-power validators is not > 2/3
+        assert!(!manager.attest("v1", 5, "hash5", "sig".to_string()).unwrap());
+        assert!(!manager.attest("v2", 5, "hash5", "sig".to_string()).unwrap());
+        // A vote for a competing block does not count toward hash5
+        assert!(!manager.attest("v3", 5, "other", "sig".to_string()).unwrap());
+        assert!(!manager.finality.is_finalized(5));
+
+        // Conflicting or duplicate attestations are rejected
+        assert!(manager.attest("v3", 5, "hash5", "sig".to_string()).is_err());
+        assert!(manager.attest("unknown", 5, "hash5", "sig".to_string()).is_err());
+
+        // Third of four validators on hash5 crosses the threshold
+        assert!(manager.attest("v4", 5, "hash5", "sig".to_string()).unwrap());
+        assert!(manager.finality.is_finalized(5));
+        assert_eq!(manager.finality.get_height(5).finalized_hash.as_deref(), Some("hash5"));
+    }
+
+    #[test]
+    fn test_epoch_reward_distribution_reduces_rounding_loss() {
+        let setup = || {
+            let mut manager = StakingManager::new(StakingConfig::default());
+            for (addr, stake) in [("v1", 10_000), ("v2", 13_337), ("v3", 17_001)] {
+                manager.register_validator(
+                    addr.to_string(), format!("op_{}", addr), stake, 0.07, ValidatorDescription::default(),
+                ).unwrap();
+            }
+            manager
+        };
+        let credited = |m: &StakingManager| m.validators.values().map(|v| v.pending_rewards).sum::<u64>();
+        let blocks = 100;
+        let block_reward = 100;
+
+        let mut per_block = setup();
+        for _ in 0..blocks {
+            per_block.distribute_rewards(block_reward);
+        }
+
+        let mut epoch = setup();
+        epoch.config.reward_epoch_blocks = blocks;
+        for i in 0..blocks {
+            let result = epoch.accrue_block_reward(block_reward);
+            assert_eq!(result.is_some(), i == blocks - 1);
+        }
+
+        let total = blocks * block_reward;
+        let per_block_loss = total - credited(&per_block);
+        let epoch_loss = total - credited(&epoch);
+
+        // One distribution pass instead of one per block
+        assert_eq!(per_block.reward_distributions, blocks);
+        assert_eq!(epoch.reward_distributions, 1);
+        // Nothing is lost: undistributed dust stays in the pool
+        assert_eq!(credited(&epoch) + epoch.reward_pool, total);
+        assert!(epoch_loss < per_block_loss);
+        assert!(epoch_loss <= 3);
+    }
+
+    #[test]
+    fn test_delegator_rewards_split_by_amount() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        manager
+            .register_validator("v1".to_string(), "op".to_string(), 10_000, 0.1, ValidatorDescription::default())
+            .unwrap();
+        manager.delegate("small".to_string(), "v1".to_string(), 1_000).unwrap();
+        manager.delegate("large".to_string(), "v1".to_string(), 3_000).unwrap();
+
+        // 10% commission leaves 9_001 for delegators: 1:3 split, 1 EDGE of dust
+        assert_eq!(manager.distribute_rewards(10_001), 10_001);
+        let commission = 1_000;
+        assert_eq!(manager.delegations["small"]["v1"].rewards, 2_250);
+        assert_eq!(manager.delegations["large"]["v1"].rewards, 6_750);
+        assert_eq!(manager.get_validator("v1").unwrap().pending_rewards, commission + 1);
+
+        assert_eq!(manager.claim_rewards("large", "v1").unwrap(), 6_750);
+        assert_eq!(manager.claim_rewards("large", "v1").unwrap(), 0);
+        assert_eq!(manager.delegations["small"]["v1"].rewards, 2_250);
+        assert!(manager.claim_rewards("nobody", "v1").is_err());
+    }
+
+    #[test]
+    fn test_redelegation() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for v in ["v1", "v2", "jailed"] {
+            manager
+                .register_validator(v.to_string(), "op".to_string(), 10_000, 0.1, ValidatorDescription::default())
+                .unwrap();
+        }
+        manager.validators.get_mut("jailed").unwrap().status = ValidatorStatus::Jailed;
+        manager.delegate("d".to_string(), "v1".to_string(), 1_000).unwrap();
+        let total_staked = manager.total_staked;
+
+        // Jailed destinations and overdrawn amounts are rejected without side effects
+        let err = manager.redelegate("d".to_string(), "v1".to_string(), "jailed".to_string(), 100).unwrap_err();
+        assert_eq!(err, StakingError::ValidatorJailed { action: "redelegate to" });
+        assert!(manager.redelegate("d".to_string(), "v1".to_string(), "v2".to_string(), 1_001).is_err());
+        assert_eq!(manager.get_validator("v1").unwrap().delegated_stake, 1_000);
+
+        manager.redelegate("d".to_string(), "v1".to_string(), "v2".to_string(), 400).unwrap();
+        assert_eq!(manager.get_validator("v1").unwrap().delegated_stake, 600);
+        assert_eq!(manager.get_validator("v2").unwrap().delegated_stake, 400);
+        assert_eq!(manager.delegations["d"]["v1"].amount, 600);
+        assert_eq!(manager.delegations["d"]["v2"].amount, 400);
+        assert_eq!(manager.total_staked, total_staked);
+        assert!(manager.unbonding_queue.is_empty());
+
+        // A second redelegation from the same source is on cooldown...
+        let err = manager.redelegate("d".to_string(), "v1".to_string(), "v2".to_string(), 100).unwrap_err();
+        assert!(matches!(err, StakingError::RedelegationCooldown { .. }));
+        // ...but other sources are not
+        assert!(manager.redelegate("d".to_string(), "v2".to_string(), "v1".to_string(), 100).is_ok());
+    }
+
+    #[test]
+    fn test_uptime_bonus_rewards() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for v in ["reliable", "marginal"] {
+            manager
+                .register_validator(v.to_string(), "op".to_string(), 10_000, 0.0, ValidatorDescription::default())
+                .unwrap();
+        }
+        // 100% vs. exactly the 95% minimum uptime
+        let reliable = manager.validators.get_mut("reliable").unwrap();
+        for h in 0..100 {
+            reliable.record_block_signed(h);
+        }
+        let marginal = manager.validators.get_mut("marginal").unwrap();
+        for h in 0..95 {
+            marginal.record_block_signed(h);
+        }
+        for _ in 0..5 {
+            marginal.record_block_missed();
+        }
+        // Isolate the uptime effect from reputation
+        for v in manager.validators.values_mut() {
+            v.reputation = 50.0;
+        }
+
+        // Default 20% bonus: weights 1.2 : 1.0
+        manager.distribute_rewards(11_000);
+        let reliable = manager.get_validator("reliable").unwrap().pending_rewards;
+        let marginal = manager.get_validator("marginal").unwrap().pending_rewards;
+        assert!((5_999..=6_000).contains(&reliable));
+        assert!((4_999..=5_000).contains(&marginal));
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        for v in ["v1", "v2", "v3"] {
+            manager
+                .register_validator(v.to_string(), "op".to_string(), 10_000, 0.1, ValidatorDescription::default())
+                .unwrap();
+        }
+        manager.delegate("alice".to_string(), "v1".to_string(), 500).unwrap();
+        manager.attest("v1", 7, "hash7", "sig".to_string()).unwrap();
+        manager.attest("v2", 7, "hash7", "sig".to_string()).unwrap();
+        manager.attest("v3", 7, "hash7", "sig".to_string()).unwrap();
+
+        let dir = std::env::temp_dir().join(format!("edgeai-staking-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(STAKING_FILE);
+        assert!(StakingManager::load_from_disk(&path).unwrap().is_none());
+
+        manager.save_to_disk(&path).unwrap();
+        let loaded = StakingManager::load_from_disk(&path).unwrap().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.validators.len(), 3);
+        assert_eq!(loaded.total_staked, manager.total_staked);
+        assert_eq!(loaded.get_validator("v1").unwrap().delegated_stake, 500);
+        assert_eq!(loaded.delegations["alice"]["v1"].amount, 500);
+        assert!(loaded.finality.is_finalized(7));
+    }
+
+    #[test]
+    fn test_uptime_window_tracks_recent_blocks() {
+        let mut manager = StakingManager::new(StakingConfig::default());
+        manager
+            .register_validator("v1".to_string(), "op".to_string(), 10_000, 0.0, ValidatorDescription::default())
+            .unwrap();
+        let validator = manager.validators.get_mut("v1").unwrap();
+
+        // A long run of misses, then enough signed blocks to push them all out
+        for _ in 0..PERFORMANCE_HISTORY_CAP {
+            validator.record_block_missed();
+        }
+        for h in 0..PERFORMANCE_HISTORY_CAP as u64 {
+            validator.record_block_signed(h);
+        }
+        assert_eq!(validator.recent_blocks.len(), PERFORMANCE_HISTORY_CAP);
+        assert_eq!(validator.uptime_window(PERFORMANCE_HISTORY_CAP), 1.0);
+        assert_eq!(validator.uptime(), 0.5);
+
+        // 3 of the last 4 blocks missed
+        for _ in 0..3 {
+            validator.record_block_missed();
+        }
+        assert_eq!(validator.uptime_window(4), 0.25);
+        assert_eq!(validator.uptime_window(10), 0.7);
+        // Asking for more than is recorded uses what there is
+        assert_eq!(validator.uptime_window(usize::MAX), (PERFORMANCE_HISTORY_CAP - 3) as f64 / PERFORMANCE_HISTORY_CAP as f64);
+
+        manager.distribute_rewards(1_000);
+        manager.validators.get_mut("v1").unwrap().record_block_signed(1_000);
+        manager.slash("v1", SlashReason::InvalidData, 1_000).unwrap();
+
+        let performance = manager.validator_performance("v1", 2).unwrap();
+        assert_eq!(performance.window, 2);
+        assert_eq!(performance.uptime, 0.5);
+        assert_eq!(performance.reward_rate, 500.0);
+        assert_eq!(performance.slash_count, 1);
+        assert!(manager.validator_performance("unknown", 2).is_none());
+    }
 }
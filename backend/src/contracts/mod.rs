@@ -5,16 +5,17 @@
 //! - WASM runtime for contract execution
 //! - Gas metering and resource control
 //!
-//! NOTE: Smart contract execution is currently in development.
-//! The WASM runtime provides a secure sandbox for contract execution.
+//! `WasmRuntime` compiles contracts with Wasmtime, meters gas with fuel and
+//! runs `ContractDeploy` / `ContractCall` transactions as blocks are applied,
+//! against the `ContractStore` in the chain state.
 
 pub mod smart_contract;
 pub mod wasm_runtime;
 
 // Re-export commonly used types
-pub use smart_contract::{SmartContract, ContractType, ContractState};
+pub use smart_contract::{SmartContract, ContractType, ContractState, ContractManager};
 pub use wasm_runtime::{
     WasmRuntime, WasmError, ExecutionContext, ExecutionResult,
     ContractAbi, AbiFunction, AbiParam, AbiEvent, ContractInfo,
-    GasMeter, GasCosts,
+    ContractStore, DeployedContract, GasMeter, GasCosts,
 };
@@ -3,8 +3,9 @@
 //! This module defines smart contract structures and execution logic.
 //! Currently a placeholder for future WASM-based smart contract implementation.
 //!
-//! NOTE: This code is intentionally not integrated yet. It will be activated
-//! when the WASM runtime is implemented.
+//! NOTE: The built-in contracts are intentionally not integrated yet. The
+//! node does use `ContractManager`'s log index for logs emitted by WASM
+//! contracts in applied blocks.
 
 #![allow(dead_code)]
 
@@ -14,6 +15,11 @@ use sha2::{Digest, Sha256};
 use chrono::{DateTime, Utc};
 use log::info;
 
+use super::wasm_runtime::ContractLog as WasmLog;
+
+/// Logs kept per contract; the oldest are dropped beyond this
+pub const MAX_LOGS_PER_CONTRACT: usize = 10_000;
+
 /// Smart contract types for EdgeAI
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ContractType {
@@ -127,6 +133,24 @@ pub struct ContractLog {
     pub event: String,
     pub data: HashMap<String, String>,
     pub timestamp: DateTime<Utc>,
+    /// Block the emitting call was included in
+    #[serde(default)]
+    pub block_number: u64,
+}
+
+impl ContractLog {
+    /// Log emitted by a WASM contract: the first topic names the event and
+    /// the payload is kept hex encoded under `data`
+    pub fn from_wasm(log: &WasmLog, block_number: u64, timestamp: DateTime<Utc>) -> Self {
+        let mut topics = log.topics.iter();
+        let event = topics.next().cloned().unwrap_or_else(|| "log".to_string());
+        let mut data: HashMap<String, String> = topics
+            .enumerate()
+            .map(|(i, topic)| (format!("topic{}", i + 1), topic.clone()))
+            .collect();
+        data.insert("data".to_string(), hex::encode(&log.data));
+        ContractLog { event, data, timestamp, block_number }
+    }
 }
 
 /// Data Marketplace Contract
@@ -167,6 +191,7 @@ impl DataMarketplaceContract {
                     ("price".to_string(), price.to_string()),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -236,6 +261,7 @@ impl DataMarketplaceContract {
                     ("price".to_string(), price.to_string()),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -287,6 +313,7 @@ impl FederatedLearningContract {
                     ("reward_pool".to_string(), reward_pool.to_string()),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -341,6 +368,7 @@ impl FederatedLearningContract {
                     ("participant".to_string(), ctx.caller.clone()),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -376,6 +404,7 @@ impl FederatedLearningContract {
                     ("update_hash".to_string(), update_hash),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -418,6 +447,7 @@ impl DeviceRegistryContract {
                     ("device_type".to_string(), device_type),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -470,6 +500,7 @@ impl DeviceRegistryContract {
                     ("is_active".to_string(), is_active.to_string()),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -478,7 +509,7 @@ impl DeviceRegistryContract {
     /// Record data contribution from device
     pub fn record_contribution(
         contract: &mut SmartContract,
-        _ctx: &ExecutionContext,
+        ctx: &ExecutionContext,
         device_id: String,
         data_hash: String,
     ) -> ExecutionResult {
@@ -514,6 +545,7 @@ impl DeviceRegistryContract {
                     ("total_contributions".to_string(), contributions.to_string()),
                 ].into_iter().collect(),
                 timestamp: Utc::now(),
+                block_number: ctx.block_number,
             }],
             error: None,
         }
@@ -523,12 +555,65 @@ impl DeviceRegistryContract {
 /// Contract manager
 pub struct ContractManager {
     pub contracts: HashMap<String, SmartContract>,
+    /// Emitted logs per contract address, oldest first
+    logs: HashMap<String, Vec<ContractLog>>,
+    /// Positions in `logs` per contract address and event name
+    event_index: HashMap<String, HashMap<String, Vec<usize>>>,
 }
 
 impl ContractManager {
     pub fn new() -> Self {
         ContractManager {
             contracts: HashMap::new(),
+            logs: HashMap::new(),
+            event_index: HashMap::new(),
+        }
+    }
+
+    /// Store logs emitted by the contract at `address`
+    pub fn record_logs(&mut self, address: &str, logs: &[ContractLog]) {
+        if logs.is_empty() {
+            return;
+        }
+        let stored = self.logs.entry(address.to_string()).or_default();
+        let index = self.event_index.entry(address.to_string()).or_default();
+        for log in logs {
+            index.entry(log.event.clone()).or_default().push(stored.len());
+            stored.push(log.clone());
+        }
+
+        if stored.len() > MAX_LOGS_PER_CONTRACT {
+            stored.drain(..stored.len() - MAX_LOGS_PER_CONTRACT);
+            index.clear();
+            for (i, log) in stored.iter().enumerate() {
+                index.entry(log.event.clone()).or_default().push(i);
+            }
+        }
+    }
+
+    /// Logs of the contract at `address`, oldest first, optionally limited to
+    /// one event name and an inclusive block range
+    pub fn get_logs(
+        &self,
+        address: &str,
+        event_filter: Option<&str>,
+        from_block: Option<u64>,
+        to_block: Option<u64>,
+    ) -> Vec<ContractLog> {
+        let Some(stored) = self.logs.get(address) else {
+            return Vec::new();
+        };
+        let in_range = |log: &&ContractLog| {
+            from_block.is_none_or(|from| log.block_number >= from)
+                && to_block.is_none_or(|to| log.block_number <= to)
+        };
+        match event_filter {
+            Some(event) => self.event_index
+                .get(address)
+                .and_then(|index| index.get(event))
+                .map(|positions| positions.iter().map(|&i| &stored[i]).filter(in_range).cloned().collect())
+                .unwrap_or_default(),
+            None => stored.iter().filter(in_range).cloned().collect(),
         }
     }
     
@@ -552,7 +637,7 @@ impl ContractManager {
         self.contracts.get_mut(address)
     }
     
-    /// Execute contract call
+    /// Execute contract call, storing the logs of a successful one
     pub fn execute(
         &mut self,
         address: &str,
@@ -560,17 +645,47 @@ impl ContractManager {
         params: HashMap<String, String>,
         ctx: ExecutionContext,
     ) -> ExecutionResult {
-        let contract = match self.contracts.get_mut(address) {
-            Some(c) => c,
-            None => return ExecutionResult {
-                success: false,
-                return_value: None,
-                gas_used: 0,
-                logs: vec![],
-                error: Some("Contract not found".to_string()),
-            },
+        let Some(contract) = self.contracts.get_mut(address) else {
+            return Self::contract_not_found();
         };
-        
+        let result = Self::dispatch(contract, method, params, ctx);
+        if result.success {
+            self.record_logs(address, &result.logs);
+        }
+        result
+    }
+
+    /// Run a contract call against a copy of the contract's state: the
+    /// result and gas are what `execute` would return, but nothing is stored
+    pub fn dry_run(
+        &self,
+        address: &str,
+        method: &str,
+        params: HashMap<String, String>,
+        ctx: ExecutionContext,
+    ) -> ExecutionResult {
+        let Some(contract) = self.contracts.get(address) else {
+            return Self::contract_not_found();
+        };
+        Self::dispatch(&mut contract.clone(), method, params, ctx)
+    }
+
+    fn contract_not_found() -> ExecutionResult {
+        ExecutionResult {
+            success: false,
+            return_value: None,
+            gas_used: 0,
+            logs: vec![],
+            error: Some("Contract not found".to_string()),
+        }
+    }
+
+    fn dispatch(
+        contract: &mut SmartContract,
+        method: &str,
+        params: HashMap<String, String>,
+        ctx: ExecutionContext,
+    ) -> ExecutionResult {
         match contract.contract_type {
             ContractType::DataMarketplace => {
                 match method {
@@ -701,4 +816,83 @@ mod tests {
         
         assert!(result.success);
     }
+
+    #[test]
+    fn test_logs_indexed_by_event_and_block() {
+        let mut manager = ContractManager::new();
+        let address = manager.deploy(ContractType::DataMarketplace, "owner123".to_string());
+        let ctx = |caller: &str, value: u64, block_number: u64| ExecutionContext {
+            caller: caller.to_string(),
+            contract_address: address.clone(),
+            value,
+            gas_limit: 100000,
+            gas_used: 0,
+            block_number,
+            timestamp: Utc::now(),
+        };
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+
+        let listed = manager.execute(
+            &address, "list_data",
+            params(&[("data_hash", "data_hash_123"), ("price", "100")]),
+            ctx("seller123", 0, 5),
+        );
+        assert!(listed.success);
+        // A failed call stores nothing
+        assert!(!manager.execute(&address, "purchase_data", params(&[("data_hash", "data_hash_123")]), ctx("buyer456", 10, 6)).success);
+        assert!(manager.execute(&address, "purchase_data", params(&[("data_hash", "data_hash_123")]), ctx("buyer456", 100, 7)).success);
+
+        let all = manager.get_logs(&address, None, None, None);
+        assert_eq!(all.iter().map(|l| (l.event.as_str(), l.block_number)).collect::<Vec<_>>(),
+                   vec![("DataListed", 5), ("DataPurchased", 7)]);
+
+        let purchases = manager.get_logs(&address, Some("DataPurchased"), None, None);
+        assert_eq!(purchases.len(), 1);
+        assert_eq!(purchases[0].data["buyer"], "buyer456");
+        assert_eq!(manager.get_logs(&address, Some("DataListed"), Some(6), None).len(), 0);
+        assert_eq!(manager.get_logs(&address, None, Some(6), Some(7)).len(), 1);
+        assert!(manager.get_logs("0xunknown", None, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_gas_matches_execution() {
+        let mut manager = ContractManager::new();
+        let address = manager.deploy(ContractType::DataMarketplace, "owner123".to_string());
+        let ctx = |value: u64| ExecutionContext {
+            caller: "buyer456789".to_string(),
+            contract_address: address.clone(),
+            value,
+            gas_limit: 100000,
+            gas_used: 0,
+            block_number: 1,
+            timestamp: Utc::now(),
+        };
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+        };
+        let listing = params(&[("data_hash", "data_hash_123"), ("price", "100")]);
+        let purchase = params(&[("data_hash", "data_hash_123")]);
+
+        // Before listing, a purchase takes the cheaper not-found path
+        let missing = manager.dry_run(&address, "purchase_data", purchase.clone(), ctx(100));
+        assert!(!missing.success);
+        assert_eq!(missing.gas_used, 10000);
+
+        // Dry-running a listing leaves no listing behind
+        let estimate = manager.dry_run(&address, "list_data", listing.clone(), ctx(0));
+        assert_eq!(manager.dry_run(&address, "purchase_data", purchase.clone(), ctx(100)).gas_used, missing.gas_used);
+        assert!(manager.get_logs(&address, None, None, None).is_empty());
+        let listed = manager.execute(&address, "list_data", listing, ctx(0));
+        assert_eq!((estimate.success, estimate.gas_used), (listed.success, listed.gas_used));
+
+        let estimate = manager.dry_run(&address, "purchase_data", purchase.clone(), ctx(100));
+        let purchased = manager.execute(&address, "purchase_data", purchase, ctx(100));
+        assert!(purchased.success);
+        assert_eq!((estimate.success, estimate.gas_used), (purchased.success, purchased.gas_used));
+        assert!(estimate.gas_used > missing.gas_used);
+
+        assert_eq!(manager.dry_run("0xunknown", "list_data", HashMap::new(), ctx(0)).error.as_deref(), Some("Contract not found"));
+    }
 }
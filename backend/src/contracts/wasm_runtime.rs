@@ -2,16 +2,41 @@
 //!
 //! This module provides a WebAssembly execution environment for smart contracts.
 //! It uses Wasmtime as the WASM runtime and implements gas metering for resource control.
+//!
+//! Gas is metered with Wasmtime fuel: every executed instruction burns fuel,
+//! and host calls (storage, logging) burn their configured cost on top.
+//! Deployed contracts, their code and their storage live in a `ContractStore`,
+//! with storage keys and values hex-encoded so contracts can store arbitrary
+//! bytes. The chain keeps the store of contracts deployed by transactions in
+//! its state; the runtime only caches compiled modules.
+//!
+//! Host imports (module `env`):
+//! - `storage_get(key_ptr, key_len, value_ptr) -> value_len` (0 if missing)
+//! - `storage_set(key_ptr, key_len, value_ptr, value_len) -> 1`
+//! - `log(msg_ptr, msg_len)`
+//! - `get_caller(ptr) -> len`, `get_block_height()`, `get_block_timestamp()`, `get_value()`
+//!
+//! Functions taking `(ptr: i32, len: i32)` receive the call params as JSON
+//! written into memory obtained from the contract's exported `alloc(len) -> ptr`.
 
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use log::info;
-use wasmtime::{Engine, Store, Module, Instance, Linker, Memory, MemoryType, Val, Caller};
+use wasmtime::{Config, Engine, Store, Module, Instance, Linker, Memory, Val, Caller, Trap};
 use std::sync::{Arc, Mutex};
 
+use crate::blockchain::{Transaction, TransactionType};
+
+/// Largest accepted contract bytecode (256 KiB)
+pub const MAX_CODE_SIZE: usize = 256 * 1024;
+
+/// Largest storage key, storage value or log message a host call copies out
+/// of contract memory (16 KiB)
+pub const MAX_HOST_BUFFER: usize = 16 * 1024;
+
 /// Gas costs for different operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GasCosts {
@@ -188,7 +213,7 @@ pub struct ContractLog {
 #[derive(Clone)]
 pub struct HostEnv {
     /// Contract storage
-    storage: Arc<Mutex<HashMap<Vec<u8>, Vec<u8>>>>,
+    storage: Arc<Mutex<BTreeMap<String, String>>>,
     /// Execution context
     context: ExecutionContext,
     /// Gas meter
@@ -254,55 +279,162 @@ pub struct ContractInfo {
     pub deployed_at: DateTime<Utc>,
 }
 
-/// Compiled WASM contract
-pub struct CompiledContract {
-    /// Contract address
-    pub address: String,
-    /// Compiled module
-    module: Module,
-    /// Contract ABI (function signatures)
-    pub abi: ContractAbi,
-    /// Code hash
+/// A deployed contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeployedContract {
+    /// Key of the bytecode in `ContractStore::code`
     pub code_hash: String,
-    /// Compilation timestamp
-    pub compiled_at: DateTime<Utc>,
+    pub abi: ContractAbi,
+    /// Timestamp of the deploying transaction
+    pub deployed_at: DateTime<Utc>,
+    /// Hex encoded keys to hex encoded values
+    pub storage: BTreeMap<String, String>,
+}
+
+/// Deployed contracts with their code and storage, in key order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContractStore {
+    pub contracts: BTreeMap<String, DeployedContract>,
+    /// Hex encoded bytecode by code hash
+    pub code: BTreeMap<String, String>,
+}
+
+impl ContractStore {
+    /// Info about the contract at `address`
+    pub fn get_contract(&self, address: &str) -> Option<ContractInfo> {
+        self.contracts.get(address).map(|c| c.info(address))
+    }
+
+    /// Info about every contract, by address
+    pub fn list_contracts(&self) -> Vec<ContractInfo> {
+        self.contracts.iter().map(|(address, c)| c.info(address)).collect()
+    }
+
+    /// Value stored under `key` by the contract at `address`
+    pub fn get_storage(&self, address: &str, key: &[u8]) -> Option<Vec<u8>> {
+        let value = self.contracts.get(address)?.storage.get(&storage_key(key))?;
+        hex::decode(value).ok()
+    }
+
+    /// Copy of the contract at `address` and its code alone, to dry-run
+    /// calls against without touching this store
+    pub fn contract_only(&self, address: &str) -> Option<ContractStore> {
+        let contract = self.contracts.get(address)?;
+        let mut copy = ContractStore::default();
+        if let Some(code) = self.code.get(&contract.code_hash) {
+            copy.code.insert(contract.code_hash.clone(), code.clone());
+        }
+        copy.contracts.insert(address.to_string(), contract.clone());
+        Some(copy)
+    }
+}
+
+impl DeployedContract {
+    fn info(&self, address: &str) -> ContractInfo {
+        ContractInfo {
+            address: address.to_string(),
+            abi: self.abi.clone(),
+            code_hash: self.code_hash.clone(),
+            deployed_at: self.deployed_at,
+        }
+    }
 }
 
 /// WASM Runtime for executing smart contracts
 pub struct WasmRuntime {
     /// Wasmtime engine
     engine: Engine,
-    /// Compiled contracts cache
-    contracts: HashMap<String, CompiledContract>,
-    /// Contract storage
-    storage: HashMap<String, HashMap<Vec<u8>, Vec<u8>>>,
+    /// Compiled modules by code hash
+    modules: HashMap<String, Module>,
+    /// Contracts deployed through the API rather than by transactions; they
+    /// run on this node only
+    sandbox: ContractStore,
     /// Gas costs configuration
     gas_costs: GasCosts,
 }
 
+/// `data` of a `ContractDeploy` transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractDeployPayload {
+    /// Hex encoded WASM bytecode
+    pub code: String,
+    #[serde(default)]
+    pub abi: ContractAbi,
+}
+
+/// `data` of a `ContractCall` transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractCallPayload {
+    pub contract: String,
+    pub function: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, serde_json::Value>,
+}
+
+/// Burn `amount` fuel for a host operation, trapping when it runs out
+fn charge_host_gas(caller: &mut Caller<'_, HostEnv>, amount: u64) -> wasmtime::Result<()> {
+    let fuel = caller.get_fuel()?;
+    if fuel < amount {
+        caller.set_fuel(0)?;
+        return Err(Trap::OutOfFuel.into());
+    }
+    caller.set_fuel(fuel - amount)
+}
+
+/// Copy `len` bytes at `ptr` out of contract memory. `None` if the range is
+/// negative, over `MAX_HOST_BUFFER` or past the end of memory. Gas for the
+/// copy is charged before anything is allocated.
+fn read_guest(caller: &mut Caller<'_, HostEnv>, memory: &Memory, ptr: i32, len: i32) -> wasmtime::Result<Option<Vec<u8>>> {
+    let (Ok(ptr), Ok(len)) = (usize::try_from(ptr), usize::try_from(len)) else {
+        return Ok(None);
+    };
+    if len > MAX_HOST_BUFFER || ptr.saturating_add(len) > memory.data_size(&*caller) {
+        return Ok(None);
+    }
+    let cost = caller.data().gas_meter.lock().unwrap().costs.memory_byte.saturating_mul(len as u64);
+    charge_host_gas(caller, cost)?;
+    let mut buf = vec![0u8; len];
+    if memory.read(&*caller, ptr, &mut buf).is_err() {
+        return Ok(None);
+    }
+    Ok(Some(buf))
+}
+
+/// Map a Wasmtime error, turning fuel exhaustion into `OutOfGas`
+fn map_trap(e: wasmtime::Error, gas_limit: u64) -> WasmError {
+    if e.downcast_ref::<Trap>() == Some(&Trap::OutOfFuel) {
+        WasmError::OutOfGas { used: gas_limit, limit: gas_limit }
+    } else {
+        WasmError::RuntimeError(e.to_string())
+    }
+}
+
+/// Storage keys and values are kept hex-encoded in `ContractStore`
+fn storage_key(key: &[u8]) -> String {
+    hex::encode(key)
+}
+
 impl WasmRuntime {
     /// Create a new WASM runtime
     pub fn new() -> Self {
-        let engine = Engine::default();
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).expect("valid wasmtime config");
         WasmRuntime {
             engine,
-            contracts: HashMap::new(),
-            storage: HashMap::new(),
+            modules: HashMap::new(),
+            sandbox: ContractStore::default(),
             gas_costs: GasCosts::default(),
         }
     }
 
-    /// Compile and deploy a contract
+    /// Compile and deploy a contract to the local sandbox
     pub fn deploy_contract(
         &mut self,
         wasm_code: &[u8],
         owner: &str,
         abi: ContractAbi,
     ) -> Result<String, WasmError> {
-        // Compile the WASM module
-        let module = Module::new(&self.engine, wasm_code)
-            .map_err(|e| WasmError::CompilationError(e.to_string()))?;
-
         // Generate contract address
         use sha2::{Sha256, Digest};
         let mut hasher = Sha256::new();
@@ -311,28 +443,70 @@ impl WasmRuntime {
         hasher.update(Utc::now().timestamp().to_le_bytes());
         let address = format!("0x{}", hex::encode(&hasher.finalize()[..20]));
 
+        let mut sandbox = std::mem::take(&mut self.sandbox);
+        let deployed = self.deploy(&mut sandbox, address, wasm_code, abi, Utc::now());
+        self.sandbox = sandbox;
+        deployed
+    }
+
+    /// Compile a contract and register it in `store` under `address`
+    pub fn deploy(
+        &mut self,
+        store: &mut ContractStore,
+        address: String,
+        wasm_code: &[u8],
+        abi: ContractAbi,
+        deployed_at: DateTime<Utc>,
+    ) -> Result<String, WasmError> {
+        if wasm_code.len() > MAX_CODE_SIZE {
+            return Err(WasmError::InvalidContract(format!(
+                "Code size {} exceeds maximum of {} bytes", wasm_code.len(), MAX_CODE_SIZE
+            )));
+        }
+        if store.contracts.contains_key(&address) {
+            return Err(WasmError::InvalidContract(format!("Contract {} already exists", address)));
+        }
+
+        // Compile the WASM module
+        let module = Module::new(&self.engine, wasm_code)
+            .map_err(|e| WasmError::CompilationError(e.to_string()))?;
+
+        use sha2::{Sha256, Digest};
+
         // Calculate code hash
         let mut code_hasher = Sha256::new();
         code_hasher.update(wasm_code);
         let code_hash = hex::encode(code_hasher.finalize());
 
-        let compiled = CompiledContract {
-            address: address.clone(),
-            module,
-            abi,
+        self.modules.insert(code_hash.clone(), module);
+        store.code.entry(code_hash.clone()).or_insert_with(|| hex::encode(wasm_code));
+        store.contracts.insert(address.clone(), DeployedContract {
             code_hash,
-            compiled_at: Utc::now(),
-        };
-
-        // Initialize storage for this contract
-        self.storage.insert(address.clone(), HashMap::new());
-        self.contracts.insert(address.clone(), compiled);
+            abi,
+            deployed_at,
+            storage: BTreeMap::new(),
+        });
 
         info!("Contract deployed at {}", &address);
         Ok(address)
     }
 
-    /// Execute a contract function
+    /// Compiled module for `code_hash`, compiling the code kept in `store`
+    /// the first time it is needed
+    fn module(&mut self, store: &ContractStore, code_hash: &str) -> Result<Module, WasmError> {
+        if let Some(module) = self.modules.get(code_hash) {
+            return Ok(module.clone());
+        }
+        let code = store.code.get(code_hash)
+            .and_then(|code| hex::decode(code).ok())
+            .ok_or_else(|| WasmError::InvalidContract(format!("Code {} not found", code_hash)))?;
+        let module = Module::new(&self.engine, code)
+            .map_err(|e| WasmError::CompilationError(e.to_string()))?;
+        self.modules.insert(code_hash.to_string(), module.clone());
+        Ok(module)
+    }
+
+    /// Execute a sandbox contract function
     pub fn execute(
         &mut self,
         contract_address: &str,
@@ -340,13 +514,87 @@ impl WasmRuntime {
         args: &[Val],
         context: ExecutionContext,
     ) -> Result<ExecutionResult, WasmError> {
-        let contract = self.contracts.get(contract_address)
-            .ok_or_else(|| WasmError::InvalidContract("Contract not found".to_string()))?;
+        let mut sandbox = std::mem::take(&mut self.sandbox);
+        let result = self.run(&mut sandbox, contract_address, function_name, Some(args), None, context);
+        self.sandbox = sandbox;
+        result
+    }
+
+    /// Call an exported function of a contract in `store` with a params map.
+    /// Functions taking `(ptr, len)` receive the params as JSON in the
+    /// contract's memory; functions taking no arguments ignore them.
+    pub fn call(
+        &mut self,
+        store: &mut ContractStore,
+        contract_address: &str,
+        function_name: &str,
+        params: &BTreeMap<String, serde_json::Value>,
+        context: ExecutionContext,
+    ) -> Result<ExecutionResult, WasmError> {
+        let params = serde_json::to_vec(params)
+            .map_err(|e| WasmError::InvalidArgument(e.to_string()))?;
+        self.run(store, contract_address, function_name, None, Some(&params), context)
+    }
+
+    /// Deploy or call a contract in `store` from a transaction included in
+    /// block `block_height`. Other transaction types are ignored.
+    pub fn apply_transaction(
+        &mut self,
+        store: &mut ContractStore,
+        tx: &Transaction,
+        block_height: u64,
+    ) -> Result<Option<ExecutionResult>, WasmError> {
+        let data = tx.data.as_deref().unwrap_or_default();
+        match tx.tx_type {
+            TransactionType::ContractDeploy => {
+                let payload: ContractDeployPayload = serde_json::from_str(data)
+                    .map_err(|e| WasmError::InvalidArgument(format!("Invalid deploy payload: {}", e)))?;
+                let code = hex::decode(&payload.code)
+                    .map_err(|e| WasmError::InvalidArgument(format!("Invalid hex encoding: {}", e)))?;
+                // Derived from the transaction so every node picks the same address
+                let address = format!("0x{}", &tx.hash[..40.min(tx.hash.len())]);
+                self.deploy(store, address, &code, payload.abi, tx.timestamp)?;
+                Ok(None)
+            }
+            TransactionType::ContractCall => {
+                let payload: ContractCallPayload = serde_json::from_str(data)
+                    .map_err(|e| WasmError::InvalidArgument(format!("Invalid call payload: {}", e)))?;
+                let context = ExecutionContext {
+                    contract_address: payload.contract.clone(),
+                    caller: tx.sender.clone(),
+                    value: tx.outputs.iter()
+                        .filter(|o| o.recipient == payload.contract)
+                        .map(|o| o.amount)
+                        .sum(),
+                    block_height,
+                    block_timestamp: tx.timestamp.timestamp(),
+                    gas_limit: tx.gas_limit,
+                };
+                self.call(store, &payload.contract, &payload.function, &payload.params, context).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Instantiate a contract of `store` and run `function_name` with either
+    /// explicit arguments or a params buffer. Storage changes are kept only
+    /// on success.
+    fn run(
+        &mut self,
+        store: &mut ContractStore,
+        contract_address: &str,
+        function_name: &str,
+        args: Option<&[Val]>,
+        params: Option<&[u8]>,
+        context: ExecutionContext,
+    ) -> Result<ExecutionResult, WasmError> {
+        let code_hash = store.contracts.get(contract_address)
+            .ok_or_else(|| WasmError::InvalidContract("Contract not found".to_string()))?
+            .code_hash.clone();
+        let module = self.module(store, &code_hash)?;
 
         // Get contract storage
-        let storage = self.storage.get(contract_address)
-            .cloned()
-            .unwrap_or_default();
+        let storage = store.contracts[contract_address].storage.clone();
 
         // Create gas meter
         let gas_meter = GasMeter::with_costs(context.gas_limit, self.gas_costs.clone());
@@ -359,8 +607,10 @@ impl WasmRuntime {
             logs: Arc::new(Mutex::new(Vec::new())),
         };
 
-        // Create store with host environment
-        let mut store = Store::new(&self.engine, host_env.clone());
+        // Create store with host environment; fuel is the gas budget
+        let mut wasm_store = Store::new(&self.engine, host_env.clone());
+        wasm_store.set_fuel(context.gas_limit)
+            .map_err(|e| WasmError::RuntimeError(e.to_string()))?;
 
         // Create linker and add host functions
         let mut linker = Linker::new(&self.engine);
@@ -368,33 +618,59 @@ impl WasmRuntime {
         // Add host functions
         Self::add_host_functions(&mut linker)?;
 
+        let gas_limit = context.gas_limit;
+
         // Instantiate the module
-        let instance = linker.instantiate(&mut store, &contract.module)
-            .map_err(|e| WasmError::RuntimeError(e.to_string()))?;
+        let instance = linker.instantiate(&mut wasm_store, &module)
+            .map_err(|e| map_trap(e, gas_limit))?;
 
         // Get the function
-        let func = instance.get_func(&mut store, function_name)
+        let func = instance.get_func(&mut wasm_store, function_name)
             .ok_or_else(|| WasmError::FunctionNotFound(function_name.to_string()))?;
+        let func_ty = func.ty(&wasm_store);
+
+        let args: Vec<Val> = match (args, params) {
+            (Some(args), _) => args.to_vec(),
+            (None, params) => {
+                let params = params.unwrap_or_default();
+                match func_ty.params().len() {
+                    0 => Vec::new(),
+                    2 => {
+                        let (ptr, len) = Self::write_params(&instance, &mut wasm_store, params)?;
+                        vec![Val::I32(ptr), Val::I32(len)]
+                    }
+                    n => {
+                        return Err(WasmError::InvalidArgument(format!(
+                            "{} takes {} arguments; expected none or (ptr, len)", function_name, n
+                        )));
+                    }
+                }
+            }
+        };
 
         // Call the function
-        let mut results = vec![Val::I64(0)];
-        func.call(&mut store, args, &mut results)
-            .map_err(|e| WasmError::RuntimeError(e.to_string()))?;
+        let mut results: Vec<Val> = func_ty.results()
+            .map(|ty| Val::default_for_ty(&ty).unwrap_or(Val::I64(0)))
+            .collect();
+        func.call(&mut wasm_store, &args, &mut results)
+            .map_err(|e| map_trap(e, gas_limit))?;
 
         // Get final state
         let final_storage = host_env.storage.lock().unwrap().clone();
         let final_logs = host_env.logs.lock().unwrap().clone();
-        let final_gas = host_env.gas_meter.lock().unwrap().used;
-
-        // Update contract storage
-        self.storage.insert(contract_address.to_string(), final_storage.clone());
+        let final_gas = gas_limit.saturating_sub(wasm_store.get_fuel().unwrap_or(0));
 
         // Convert storage to state changes
         let state_changes: HashMap<String, Vec<u8>> = final_storage
             .iter()
-            .map(|(k, v)| (hex::encode(k), v.clone()))
+            .map(|(k, v)| (k.clone(), hex::decode(v).unwrap_or_default()))
             .collect();
 
+        // Update contract storage
+        if let Some(contract) = store.contracts.get_mut(contract_address) {
+            contract.storage = final_storage;
+        }
+
         Ok(ExecutionResult {
             success: true,
             return_data: results.first()
@@ -411,116 +687,112 @@ impl WasmRuntime {
         })
     }
 
+    /// Copy `params` into memory returned by the contract's `alloc` export
+    fn write_params(instance: &Instance, store: &mut Store<HostEnv>, params: &[u8]) -> Result<(i32, i32), WasmError> {
+        let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")
+            .map_err(|_| WasmError::InvalidContract("Contract must export alloc(len) -> ptr to receive params".to_string()))?;
+        let memory = instance.get_memory(&mut *store, "memory")
+            .ok_or_else(|| WasmError::InvalidContract("Contract does not export memory".to_string()))?;
+        let len = i32::try_from(params.len())
+            .map_err(|_| WasmError::InvalidArgument("Params too large".to_string()))?;
+        let gas_limit = store.data().context.gas_limit;
+        let ptr = alloc.call(&mut *store, len)
+            .map_err(|e| map_trap(e, gas_limit))?;
+        memory.write(&mut *store, ptr as usize, params)
+            .map_err(|e| WasmError::MemoryError(e.to_string()))?;
+        Ok((ptr, len))
+    }
+
     /// Add host functions to the linker
     fn add_host_functions(linker: &mut Linker<HostEnv>) -> Result<(), WasmError> {
-        // storage_read(key_ptr, key_len, value_ptr) -> value_len
-        linker.func_wrap("env", "storage_read", |mut caller: Caller<'_, HostEnv>, key_ptr: i32, key_len: i32, value_ptr: i32| -> i32 {
+        // storage_get(key_ptr, key_len, value_ptr) -> value_len
+        linker.func_wrap("env", "storage_get", |mut caller: Caller<'_, HostEnv>, key_ptr: i32, key_len: i32, value_ptr: i32| -> wasmtime::Result<i32> {
             let memory = match caller.get_export("memory") {
                 Some(wasmtime::Extern::Memory(mem)) => mem,
-                _ => return -1,
+                _ => return Ok(-1),
             };
 
             // Read key from memory
-            let mut key = vec![0u8; key_len as usize];
-            if memory.read(&caller, key_ptr as usize, &mut key).is_err() {
-                return -1;
-            }
+            let Some(key) = read_guest(&mut caller, &memory, key_ptr, key_len)? else {
+                return Ok(-1);
+            };
 
             // Consume gas
-            {
-                let data = caller.data();
-                let mut gas = data.gas_meter.lock().unwrap();
-                if gas.consume_storage_read().is_err() {
-                    return -2; // Out of gas
-                }
-            }
+            let cost = caller.data().gas_meter.lock().unwrap().costs.storage_read;
+            charge_host_gas(&mut caller, cost)?;
 
             // Read from storage
             let value = {
                 let data = caller.data();
                 let storage = data.storage.lock().unwrap();
-                storage.get(&key).cloned()
+                storage.get(&storage_key(&key)).and_then(|v| hex::decode(v).ok())
             };
 
             match value {
                 Some(v) => {
                     // Write value to memory
                     if memory.write(&mut caller, value_ptr as usize, &v).is_err() {
-                        return -1;
+                        return Ok(-1);
                     }
-                    v.len() as i32
+                    Ok(v.len() as i32)
                 }
-                None => 0,
+                None => Ok(0),
             }
         }).map_err(|e| WasmError::ImportError(e.to_string()))?;
 
-        // storage_write(key_ptr, key_len, value_ptr, value_len) -> success
-        linker.func_wrap("env", "storage_write", |mut caller: Caller<'_, HostEnv>, key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32| -> i32 {
+        // storage_set(key_ptr, key_len, value_ptr, value_len) -> success
+        linker.func_wrap("env", "storage_set", |mut caller: Caller<'_, HostEnv>, key_ptr: i32, key_len: i32, value_ptr: i32, value_len: i32| -> wasmtime::Result<i32> {
             let memory = match caller.get_export("memory") {
                 Some(wasmtime::Extern::Memory(mem)) => mem,
-                _ => return -1,
+                _ => return Ok(-1),
             };
 
             // Read key and value from memory
-            let mut key = vec![0u8; key_len as usize];
-            let mut value = vec![0u8; value_len as usize];
-            
-            if memory.read(&caller, key_ptr as usize, &mut key).is_err() {
-                return -1;
-            }
-            if memory.read(&caller, value_ptr as usize, &mut value).is_err() {
-                return -1;
-            }
+            let Some(key) = read_guest(&mut caller, &memory, key_ptr, key_len)? else {
+                return Ok(-1);
+            };
+            let Some(value) = read_guest(&mut caller, &memory, value_ptr, value_len)? else {
+                return Ok(-1);
+            };
 
             // Consume gas
-            {
-                let data = caller.data();
-                let mut gas = data.gas_meter.lock().unwrap();
-                if gas.consume_storage_write().is_err() {
-                    return -2; // Out of gas
-                }
-            }
+            let cost = caller.data().gas_meter.lock().unwrap().costs.storage_write;
+            charge_host_gas(&mut caller, cost)?;
 
             // Write to storage
             {
                 let data = caller.data();
                 let mut storage = data.storage.lock().unwrap();
-                storage.insert(key, value);
+                storage.insert(storage_key(&key), hex::encode(value));
             }
 
-            1 // Success
+            Ok(1) // Success
         }).map_err(|e| WasmError::ImportError(e.to_string()))?;
 
         // log(msg_ptr, msg_len)
-        linker.func_wrap("env", "log", |mut caller: Caller<'_, HostEnv>, msg_ptr: i32, msg_len: i32| {
+        linker.func_wrap("env", "log", |mut caller: Caller<'_, HostEnv>, msg_ptr: i32, msg_len: i32| -> wasmtime::Result<()> {
             let memory = match caller.get_export("memory") {
                 Some(wasmtime::Extern::Memory(mem)) => mem,
-                _ => return,
+                _ => return Ok(()),
             };
 
-            let mut msg = vec![0u8; msg_len as usize];
-            if memory.read(&caller, msg_ptr as usize, &mut msg).is_err() {
-                return;
-            }
+            let Some(msg) = read_guest(&mut caller, &memory, msg_ptr, msg_len)? else {
+                return Ok(());
+            };
 
-            let data = caller.data();
-            
             // Consume gas
-            {
-                let mut gas = data.gas_meter.lock().unwrap();
-                let log_cost = gas.costs.log;
-                let _ = gas.consume(log_cost);
-            }
+            let cost = caller.data().gas_meter.lock().unwrap().costs.log;
+            charge_host_gas(&mut caller, cost)?;
 
             // Add log
-            {
-                let mut logs = data.logs.lock().unwrap();
-                logs.push(ContractLog {
-                    contract: data.context.contract_address.clone(),
-                    topics: vec!["log".to_string()],
-                    data: msg,
-                });
-            }
+            let data = caller.data();
+            let mut logs = data.logs.lock().unwrap();
+            logs.push(ContractLog {
+                contract: data.context.contract_address.clone(),
+                topics: vec!["log".to_string()],
+                data: msg,
+            });
+            Ok(())
         }).map_err(|e| WasmError::ImportError(e.to_string()))?;
 
         // get_caller(ptr) -> len
@@ -564,29 +836,24 @@ impl WasmRuntime {
         Ok(())
     }
 
-    /// Get contract info
+    /// Info about a sandbox contract
     pub fn get_contract(&self, address: &str) -> Option<ContractInfo> {
-        self.contracts.get(address).map(|c| ContractInfo {
-            address: c.address.clone(),
-            abi: c.abi.clone(),
-            code_hash: c.code_hash.clone(),
-            deployed_at: c.compiled_at,
-        })
+        self.sandbox.get_contract(address)
     }
 
-    /// List all contracts
+    /// List the sandbox contracts
     pub fn list_contracts(&self) -> Vec<ContractInfo> {
-        self.contracts.values().map(|c| ContractInfo {
-            address: c.address.clone(),
-            abi: c.abi.clone(),
-            code_hash: c.code_hash.clone(),
-            deployed_at: c.compiled_at,
-        }).collect()
+        self.sandbox.list_contracts()
     }
 
-    /// Get contract storage
+    /// Get sandbox contract storage
     pub fn get_storage(&self, address: &str, key: &[u8]) -> Option<Vec<u8>> {
-        self.storage.get(address)?.get(key).cloned()
+        self.sandbox.get_storage(address, key)
+    }
+
+    /// Copy of a sandbox contract, see `ContractStore::contract_only`
+    pub fn sandbox_contract(&self, address: &str) -> Option<ContractStore> {
+        self.sandbox.contract_only(address)
     }
 }
 
@@ -595,3 +862,103 @@ impl Default for WasmRuntime {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counter stored as a little-endian u64 under the key "count"
+    const COUNTER_WAT: &str = r#"
+        (module
+          (import "env" "storage_get" (func $get (param i32 i32 i32) (result i32)))
+          (import "env" "storage_set" (func $set (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "count")
+          (func (export "increment") (result i64)
+            (local $n i64)
+            (if (i32.eq (call $get (i32.const 0) (i32.const 5) (i32.const 16)) (i32.const 8))
+              (then (local.set $n (i64.load (i32.const 16)))))
+            (local.set $n (i64.add (local.get $n) (i64.const 1)))
+            (i64.store (i32.const 16) (local.get $n))
+            (drop (call $set (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 8)))
+            (local.get $n))
+          (func (export "spin") (loop $l (br $l)))
+          (func (export "negative_len") (result i32)
+            (call $set (i32.const 0) (i32.const -1) (i32.const 16) (i32.const 8)))
+          (func (export "huge_len") (result i32)
+            (call $set (i32.const 0) (i32.const 5) (i32.const 16) (i32.const 0x7fffffff))))
+    "#;
+
+    fn contract_tx(tx_type: TransactionType, data: serde_json::Value) -> Transaction {
+        Transaction::new(tx_type, "alice".to_string(), vec![], vec![], Some(data.to_string()), 1, 100_000)
+    }
+
+    #[test]
+    fn test_deploy_and_call_counter() {
+        let mut runtime = WasmRuntime::new();
+        let mut store = ContractStore::default();
+        let deploy = contract_tx(
+            TransactionType::ContractDeploy,
+            serde_json::json!({ "code": hex::encode(COUNTER_WAT) }),
+        );
+        runtime.apply_transaction(&mut store, &deploy, 1).unwrap();
+        let address = format!("0x{}", &deploy.hash[..40]);
+        assert!(store.get_contract(&address).is_some());
+        assert!(runtime.get_contract(&address).is_none());
+
+        let call = contract_tx(
+            TransactionType::ContractCall,
+            serde_json::json!({ "contract": address, "function": "increment" }),
+        );
+        let first = runtime.apply_transaction(&mut store, &call, 2).unwrap().unwrap();
+        let second = runtime.apply_transaction(&mut store, &call, 3).unwrap().unwrap();
+        assert_eq!(first.return_data, 1i64.to_le_bytes().to_vec());
+        assert_eq!(second.return_data, 2i64.to_le_bytes().to_vec());
+        assert_eq!(store.get_storage(&address, b"count"), Some(2u64.to_le_bytes().to_vec()));
+
+        // A fresh runtime compiles the code kept in the store
+        let mut restarted = WasmRuntime::new();
+        let third = restarted.apply_transaction(&mut store.clone(), &call, 4).unwrap().unwrap();
+        assert_eq!(third.return_data, 3i64.to_le_bytes().to_vec());
+
+        // Bad lengths from the contract are refused instead of allocated
+        for function in ["negative_len", "huge_len"] {
+            let call = contract_tx(
+                TransactionType::ContractCall,
+                serde_json::json!({ "contract": address, "function": function }),
+            );
+            let result = runtime.apply_transaction(&mut store, &call, 4).unwrap().unwrap();
+            assert_eq!(result.return_data, (-1i32).to_le_bytes().to_vec());
+        }
+
+        // Gas covers the storage host calls plus executed instructions
+        let costs = GasCosts::default();
+        assert!(second.gas_used > costs.storage_read + costs.storage_write);
+
+        // Running out of gas fails the call and leaves storage untouched
+        let ctx = ExecutionContext {
+            contract_address: address.clone(),
+            caller: "alice".to_string(),
+            value: 0,
+            block_height: 4,
+            block_timestamp: 0,
+            gas_limit: 10_000,
+        };
+        assert!(matches!(
+            runtime.call(&mut store, &address, "spin", &BTreeMap::new(), ctx.clone()),
+            Err(WasmError::OutOfGas { .. })
+        ));
+        assert!(matches!(
+            runtime.call(&mut store, &address, "increment", &BTreeMap::new(), ExecutionContext { gas_limit: 1_000, ..ctx }),
+            Err(WasmError::OutOfGas { .. })
+        ));
+        assert_eq!(store.get_storage(&address, b"count"), Some(2u64.to_le_bytes().to_vec()));
+
+        // Oversized code is rejected before compilation
+        let too_big = vec![0u8; MAX_CODE_SIZE + 1];
+        assert!(matches!(
+            runtime.deploy_contract(&too_big, "alice", ContractAbi::default()),
+            Err(WasmError::InvalidContract(_))
+        ));
+    }
+}
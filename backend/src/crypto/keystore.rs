@@ -0,0 +1,171 @@
+//! Encrypted wallet keystore
+//!
+//! A keystore is a JSON document holding a wallet's secret key encrypted
+//! with AES-256-GCM under a key derived from a password with scrypt. The KDF
+//! parameters and salt are stored alongside the ciphertext, so a keystore can
+//! be decrypted with nothing but the password. The GCM tag authenticates the
+//! ciphertext, which is how a wrong password is detected.
+
+#![allow(dead_code)]
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+/// Keystore format version
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// Largest scrypt cost accepted when decrypting (2^18 uses 256 MiB with r = 8)
+pub const MAX_SCRYPT_LOG_N: u8 = 18;
+
+/// Largest scrypt block size accepted when decrypting
+pub const MAX_SCRYPT_R: u32 = 8;
+
+/// Largest scrypt parallelism accepted when decrypting
+pub const MAX_SCRYPT_P: u32 = 4;
+
+/// Largest scrypt working memory (128 * r * N bytes) accepted when decrypting
+pub const MAX_SCRYPT_MEMORY: u64 = 1 << 28;
+
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+/// scrypt cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    /// log2 of the CPU/memory cost N
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for ScryptParams {
+    /// N = 2^15, r = 8, p = 1: about 32 MiB and a fraction of a second
+    fn default() -> Self {
+        ScryptParams { log_n: 15, r: 8, p: 1 }
+    }
+}
+
+/// KDF section of a keystore
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    #[serde(flatten)]
+    pub scrypt: ScryptParams,
+    /// Hex-encoded salt
+    pub salt: String,
+    pub dklen: usize,
+}
+
+/// Encryption section of a keystore
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    /// Always `aes-256-gcm`
+    pub cipher: String,
+    /// Hex-encoded encrypted secret key followed by the GCM tag
+    pub ciphertext: String,
+    /// Hex-encoded GCM nonce
+    pub nonce: String,
+    /// Always `scrypt`
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+}
+
+/// Encrypted wallet keystore
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    /// Address of the encrypted key, for display; checked on decryption
+    pub address: String,
+    pub crypto: KeystoreCrypto,
+}
+
+/// Why a keystore could not be opened
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeystoreError {
+    /// Malformed JSON, unsupported cipher/KDF or out-of-range parameters
+    Invalid(String),
+    /// Wrong password or tampered ciphertext
+    DecryptionFailed,
+}
+
+fn derive_key(password: &str, salt: &[u8], params: ScryptParams) -> Result<[u8; KEY_LEN], KeystoreError> {
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, KEY_LEN)
+        .map_err(|e| KeystoreError::Invalid(format!("Invalid scrypt parameters: {}", e)))?;
+    let mut key = [0u8; KEY_LEN];
+    scrypt::scrypt(password.as_bytes(), salt, &scrypt_params, &mut key)
+        .map_err(|e| KeystoreError::Invalid(format!("Invalid scrypt parameters: {}", e)))?;
+    Ok(key)
+}
+
+/// Encrypt `secret` for `address` under `password`
+pub fn encrypt(secret: &[u8], address: &str, password: &str, params: ScryptParams) -> Result<Keystore, KeystoreError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let key = derive_key(password, &salt, params)?;
+    let cipher = Aes256Gcm::new_from_slice(&key).expect("key is 32 bytes");
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), secret)
+        .map_err(|_| KeystoreError::Invalid("Encryption failed".to_string()))?;
+
+    Ok(Keystore {
+        version: KEYSTORE_VERSION,
+        address: address.to_string(),
+        crypto: KeystoreCrypto {
+            cipher: "aes-256-gcm".to_string(),
+            ciphertext: hex::encode(ciphertext),
+            nonce: hex::encode(nonce),
+            kdf: "scrypt".to_string(),
+            kdfparams: KdfParams { scrypt: params, salt: hex::encode(salt), dklen: KEY_LEN },
+        },
+    })
+}
+
+impl ScryptParams {
+    /// Bytes of memory scrypt needs for these parameters (128 * r * 2^log_n)
+    pub fn memory_bytes(&self) -> Option<u64> {
+        1u64.checked_shl(self.log_n as u32)?
+            .checked_mul(128)?
+            .checked_mul(self.r as u64)
+    }
+
+    /// Whether a keystore with these parameters is cheap enough to open
+    fn within_limits(&self) -> bool {
+        self.log_n <= MAX_SCRYPT_LOG_N
+            && (1..=MAX_SCRYPT_R).contains(&self.r)
+            && (1..=MAX_SCRYPT_P).contains(&self.p)
+            && self.memory_bytes().is_some_and(|bytes| bytes <= MAX_SCRYPT_MEMORY)
+    }
+}
+
+/// Decrypt the secret stored in `keystore` with `password`
+pub fn decrypt(keystore: &Keystore, password: &str) -> Result<Vec<u8>, KeystoreError> {
+    let crypto = &keystore.crypto;
+    if keystore.version != KEYSTORE_VERSION {
+        return Err(KeystoreError::Invalid(format!("Unsupported keystore version {}", keystore.version)));
+    }
+    if crypto.cipher != "aes-256-gcm" || crypto.kdf != "scrypt" {
+        return Err(KeystoreError::Invalid(format!("Unsupported cipher/KDF {}/{}", crypto.cipher, crypto.kdf)));
+    }
+    let params = crypto.kdfparams.scrypt;
+    if !params.within_limits() || crypto.kdfparams.dklen != KEY_LEN {
+        return Err(KeystoreError::Invalid("KDF parameters out of range".to_string()));
+    }
+    let salt = hex::decode(&crypto.kdfparams.salt).map_err(|_| KeystoreError::Invalid("Invalid salt".to_string()))?;
+    let nonce = hex::decode(&crypto.nonce)
+        .ok()
+        .filter(|n| n.len() == NONCE_LEN)
+        .ok_or_else(|| KeystoreError::Invalid("Invalid nonce".to_string()))?;
+    let ciphertext = hex::decode(&crypto.ciphertext).map_err(|_| KeystoreError::Invalid("Invalid ciphertext".to_string()))?;
+
+    let key = derive_key(password, &salt, params)?;
+    let cipher = Aes256Gcm::new_from_slice(&key).expect("key is 32 bytes");
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| KeystoreError::DecryptionFailed)
+}
@@ -0,0 +1,149 @@
+//! BIP39 recovery phrases
+//!
+//! Encodes entropy as English BIP39 words with the standard checksum and
+//! stretches a phrase into a 64-byte seed (PBKDF2-HMAC-SHA512, 2048 rounds).
+//! The ed25519 key is the SLIP-0010 master key of that seed, so a phrase
+//! restores the same wallet in any SLIP-0010 compatible wallet.
+
+#![allow(dead_code)]
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256, Sha512};
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// BIP39 English wordlist (2048 words)
+const WORDLIST: &str = include_str!("bip39_english.txt");
+
+/// Supported phrase lengths
+pub const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+const PBKDF2_ROUNDS: u32 = 2048;
+
+fn words() -> impl Iterator<Item = &'static str> {
+    WORDLIST.lines()
+}
+
+fn word_index(word: &str) -> Option<usize> {
+    words().position(|w| w == word)
+}
+
+/// Encode 16-32 bytes of entropy (a multiple of 4) as a phrase
+pub fn entropy_to_mnemonic(entropy: &[u8]) -> Result<String, String> {
+    if entropy.len() < 16 || entropy.len() > 32 || !entropy.len().is_multiple_of(4) {
+        return Err(format!("Invalid entropy length: {} bytes", entropy.len()));
+    }
+    let checksum_bits = entropy.len() / 4;
+    let checksum = Sha256::digest(entropy)[0];
+
+    let mut bits: Vec<bool> = entropy
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 == 1))
+        .collect();
+    bits.extend((0..checksum_bits).map(|i| checksum >> (7 - i) & 1 == 1));
+
+    let wordlist: Vec<&str> = words().collect();
+    let phrase: Vec<&str> = bits
+        .chunks(11)
+        .map(|chunk| wordlist[chunk.iter().fold(0, |acc, bit| acc << 1 | *bit as usize)])
+        .collect();
+    Ok(phrase.join(" "))
+}
+
+/// Decode a phrase back to its entropy, checking word count, words and checksum
+pub fn mnemonic_to_entropy(phrase: &str) -> Result<Vec<u8>, String> {
+    let phrase_words: Vec<&str> = phrase.split_whitespace().collect();
+    if !WORD_COUNTS.contains(&phrase_words.len()) {
+        return Err(format!(
+            "Invalid word count: {} (expected 12, 15, 18, 21 or 24)",
+            phrase_words.len()
+        ));
+    }
+
+    let mut bits = Vec::with_capacity(phrase_words.len() * 11);
+    for word in &phrase_words {
+        let index = word_index(&word.to_lowercase())
+            .ok_or_else(|| format!("Unknown word: {}", word))?;
+        bits.extend((0..11).rev().map(|i| index >> i & 1 == 1));
+    }
+
+    let checksum_bits = bits.len() / 33;
+    let (entropy_bits, checksum) = bits.split_at(bits.len() - checksum_bits);
+    let entropy: Vec<u8> = entropy_bits
+        .chunks(8)
+        .map(|chunk| chunk.iter().fold(0u8, |acc, bit| acc << 1 | *bit as u8))
+        .collect();
+
+    let expected = Sha256::digest(&entropy)[0];
+    let valid = checksum
+        .iter()
+        .enumerate()
+        .all(|(i, bit)| (expected >> (7 - i) & 1 == 1) == *bit);
+    if !valid {
+        return Err("Invalid mnemonic checksum".to_string());
+    }
+    Ok(entropy)
+}
+
+/// BIP39 seed for a phrase and optional passphrase
+pub fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> [u8; 64] {
+    let normalized = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    let salt = format!("mnemonic{}", passphrase);
+    pbkdf2_sha512(normalized.as_bytes(), salt.as_bytes(), PBKDF2_ROUNDS)
+}
+
+/// SLIP-0010 ed25519 master secret key for a seed
+pub fn ed25519_master_key(seed: &[u8]) -> [u8; 32] {
+    let mut mac = HmacSha512::new_from_slice(b"ed25519 seed").expect("HMAC accepts any key length");
+    mac.update(seed);
+    let out = mac.finalize().into_bytes();
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&out[..32]);
+    key
+}
+
+/// PBKDF2 with a single 64-byte output block
+fn pbkdf2_sha512(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 64] {
+    let prf = HmacSha512::new_from_slice(password).expect("HMAC accepts any key length");
+
+    let mut mac = prf.clone();
+    mac.update(salt);
+    mac.update(&1u32.to_be_bytes());
+    let mut u = mac.finalize().into_bytes();
+    let mut out = [0u8; 64];
+    out.copy_from_slice(&u);
+
+    for _ in 1..rounds {
+        let mut mac = prf.clone();
+        mac.update(&u);
+        u = mac.finalize().into_bytes();
+        out.iter_mut().zip(u.iter()).for_each(|(o, b)| *o ^= b);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bip39_reference_vector() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(entropy_to_mnemonic(&[0u8; 16]).unwrap(), phrase);
+        assert_eq!(mnemonic_to_entropy(phrase).unwrap(), vec![0u8; 16]);
+        assert_eq!(
+            hex::encode(mnemonic_to_seed(phrase, "TREZOR")),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        let entropy = [0xffu8; 32];
+        let long = entropy_to_mnemonic(&entropy).unwrap();
+        assert_eq!(long.split(' ').count(), 24);
+        assert_eq!(mnemonic_to_entropy(&long).unwrap(), entropy.to_vec());
+
+        // Wrong checksum word, unknown word, wrong length
+        assert!(mnemonic_to_entropy(&phrase.replace("about", "abandon")).unwrap_err().contains("checksum"));
+        assert!(mnemonic_to_entropy(&phrase.replace("about", "bitcoinz")).unwrap_err().contains("Unknown word"));
+        assert!(mnemonic_to_entropy("abandon about").unwrap_err().contains("word count"));
+    }
+}
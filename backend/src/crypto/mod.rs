@@ -3,6 +3,8 @@
 //! This module provides wallet management, key generation,
 //! and signature verification using ed25519 cryptography.
 
+pub mod keystore;
+pub mod mnemonic;
 pub mod wallet;
 
 // Core crypto exports - only export what's actually used
@@ -7,11 +7,15 @@
 
 use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
 use rand::rngs::OsRng;
+use rand::RngCore;
 use sha2::{Sha256, Digest};
 use hex;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
+use crate::crypto::keystore::{self, Keystore, KeystoreError, ScryptParams};
+use crate::crypto::mnemonic;
+
 /// Represents a wallet with a key pair for signing transactions
 #[derive(Clone)]
 pub struct Wallet {
@@ -49,6 +53,33 @@ impl Wallet {
         Ok(Wallet { signing_key, address })
     }
     
+    /// Restore a wallet from a BIP39 recovery phrase
+    pub fn from_mnemonic(phrase: &str) -> Result<Self, WalletError> {
+        mnemonic::mnemonic_to_entropy(phrase).map_err(WalletError::InvalidMnemonic)?;
+        let seed = mnemonic::mnemonic_to_seed(&phrase.to_lowercase(), "");
+        let signing_key = SigningKey::from_bytes(&mnemonic::ed25519_master_key(&seed));
+        let address = Self::derive_address(&signing_key.verifying_key());
+        
+        Ok(Wallet { signing_key, address })
+    }
+    
+    /// Generate a wallet with a new 12-word recovery phrase
+    pub fn generate_mnemonic() -> (Self, String) {
+        Self::generate_mnemonic_with_words(12).expect("12 words is a valid phrase length")
+    }
+    
+    /// Generate a wallet with a new recovery phrase of `word_count` words
+    pub fn generate_mnemonic_with_words(word_count: usize) -> Result<(Self, String), WalletError> {
+        if !mnemonic::WORD_COUNTS.contains(&word_count) {
+            return Err(WalletError::InvalidMnemonic(format!("Unsupported word count: {}", word_count)));
+        }
+        let mut entropy = vec![0u8; word_count * 4 / 3];
+        OsRng.fill_bytes(&mut entropy);
+        let phrase = mnemonic::entropy_to_mnemonic(&entropy).map_err(WalletError::InvalidMnemonic)?;
+        let wallet = Self::from_mnemonic(&phrase)?;
+        Ok((wallet, phrase))
+    }
+    
     /// Derive a human-readable address from a public key
     /// Format: "edge" + first 40 chars of SHA256(public_key)
     fn derive_address(verifying_key: &VerifyingKey) -> String {
@@ -85,6 +116,30 @@ impl Wallet {
         self.sign(tx_hash.as_bytes())
     }
     
+    /// Encrypt the secret key into a JSON keystore under `password`
+    pub fn to_keystore(&self, password: &str) -> String {
+        self.to_keystore_with_params(password, ScryptParams::default())
+            .expect("default scrypt parameters are valid")
+    }
+    
+    /// Encrypt the secret key into a JSON keystore with explicit scrypt costs
+    pub fn to_keystore_with_params(&self, password: &str, params: ScryptParams) -> Result<String, WalletError> {
+        let keystore = keystore::encrypt(&self.signing_key.to_bytes(), &self.address, password, params)?;
+        Ok(serde_json::to_string(&keystore).expect("keystore serializes"))
+    }
+    
+    /// Restore a wallet from a JSON keystore
+    pub fn from_keystore(json: &str, password: &str) -> Result<Self, WalletError> {
+        let keystore: Keystore = serde_json::from_str(json)
+            .map_err(|e| WalletError::InvalidKeystore(e.to_string()))?;
+        let secret = keystore::decrypt(&keystore, password)?;
+        let wallet = Self::from_secret_key(&hex::encode(secret))?;
+        if wallet.address != keystore.address {
+            return Err(WalletError::InvalidKeystore("Address does not match the encrypted key".to_string()));
+        }
+        Ok(wallet)
+    }
+    
     /// Export wallet as JSON for storage
     pub fn export(&self) -> WalletExport {
         WalletExport {
@@ -120,6 +175,19 @@ pub enum WalletError {
     InvalidPublicKey,
     InvalidSignature,
     SignatureVerificationFailed,
+    InvalidMnemonic(String),
+    InvalidKeystore(String),
+    /// Wrong keystore password or corrupted ciphertext
+    DecryptionFailed,
+}
+
+impl From<KeystoreError> for WalletError {
+    fn from(e: KeystoreError) -> Self {
+        match e {
+            KeystoreError::Invalid(reason) => WalletError::InvalidKeystore(reason),
+            KeystoreError::DecryptionFailed => WalletError::DecryptionFailed,
+        }
+    }
 }
 
 impl fmt::Display for WalletError {
@@ -129,6 +197,9 @@ impl fmt::Display for WalletError {
             WalletError::InvalidPublicKey => write!(f, "Invalid public key"),
             WalletError::InvalidSignature => write!(f, "Invalid signature format"),
             WalletError::SignatureVerificationFailed => write!(f, "Signature verification failed"),
+            WalletError::InvalidMnemonic(reason) => write!(f, "Invalid mnemonic: {}", reason),
+            WalletError::InvalidKeystore(reason) => write!(f, "Invalid keystore: {}", reason),
+            WalletError::DecryptionFailed => write!(f, "Keystore decryption failed: wrong password or corrupted keystore"),
         }
     }
 }
@@ -213,6 +284,75 @@ mod tests {
         assert_eq!(wallet1.public_key_hex(), wallet2.public_key_hex());
     }
     
+    #[test]
+    fn test_wallet_from_mnemonic_is_deterministic() {
+        let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let wallet1 = Wallet::from_mnemonic(phrase).unwrap();
+        let wallet2 = Wallet::from_mnemonic(&format!("  {}  ", phrase.to_uppercase())).unwrap();
+        assert_eq!(wallet1.address(), wallet2.address());
+        assert_eq!(wallet1.public_key_hex(), wallet2.public_key_hex());
+        
+        let (generated, words) = Wallet::generate_mnemonic();
+        assert_eq!(words.split(' ').count(), 12);
+        assert_eq!(Wallet::from_mnemonic(&words).unwrap().address(), generated.address());
+        assert_ne!(generated.address(), wallet1.address());
+        
+        let (_, long) = Wallet::generate_mnemonic_with_words(24).unwrap();
+        assert_eq!(long.split(' ').count(), 24);
+        assert!(Wallet::generate_mnemonic_with_words(13).is_err());
+        assert!(matches!(
+            Wallet::from_mnemonic(&phrase.replace("yellow", "wave")),
+            Err(WalletError::InvalidMnemonic(_))
+        ));
+    }
+    
+    #[test]
+    fn test_keystore_round_trip() {
+        // Cheap scrypt costs keep the test fast; the format is the same
+        let params = ScryptParams { log_n: 4, r: 8, p: 1 };
+        let wallet = Wallet::new();
+        let json = wallet.to_keystore_with_params("correct horse", params).unwrap();
+        assert!(!json.contains(&wallet.secret_key_hex()));
+        
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["address"], wallet.address());
+        assert_eq!(value["crypto"]["kdf"], "scrypt");
+        assert_eq!(value["crypto"]["kdfparams"]["log_n"], 4);
+        
+        let restored = Wallet::from_keystore(&json, "correct horse").unwrap();
+        assert_eq!(restored.address(), wallet.address());
+        assert_eq!(restored.secret_key_hex(), wallet.secret_key_hex());
+        
+        // Each export uses a fresh salt and nonce
+        assert_ne!(json, wallet.to_keystore_with_params("correct horse", params).unwrap());
+    }
+    
+    #[test]
+    fn test_keystore_wrong_password() {
+        let params = ScryptParams { log_n: 4, r: 8, p: 1 };
+        let json = Wallet::new().to_keystore_with_params("correct horse", params).unwrap();
+        assert!(matches!(Wallet::from_keystore(&json, "battery staple"), Err(WalletError::DecryptionFailed)));
+        assert!(matches!(Wallet::from_keystore("{}", "correct horse"), Err(WalletError::InvalidKeystore(_))));
+        
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["crypto"]["kdfparams"]["log_n"] = serde_json::json!(40);
+        assert!(matches!(
+            Wallet::from_keystore(&value.to_string(), "correct horse"),
+            Err(WalletError::InvalidKeystore(_))
+        ));
+        
+        // r and p are capped too, as is their combined memory cost
+        for (log_n, r, p) in [(4, 1 << 20, 1), (4, 8, 1 << 20), (20, 16, 1), (19, 8, 1), (4, 16, 1), (4, 8, 5)] {
+            value["crypto"]["kdfparams"]["log_n"] = serde_json::json!(log_n);
+            value["crypto"]["kdfparams"]["r"] = serde_json::json!(r);
+            value["crypto"]["kdfparams"]["p"] = serde_json::json!(p);
+            assert!(matches!(
+                Wallet::from_keystore(&value.to_string(), "correct horse"),
+                Err(WalletError::InvalidKeystore(_))
+            ));
+        }
+    }
+    
     #[test]
     fn test_sign_and_verify() {
         let wallet = Wallet::new();
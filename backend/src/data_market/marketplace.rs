@@ -5,11 +5,14 @@
 
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use log::info;
 
+use crate::blockchain::block::Block;
+use crate::blockchain::TransactionType;
+
 /// Data category for marketplace
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum DataCategory {
@@ -139,6 +142,8 @@ pub struct PurchaseRecord {
 pub struct DataMarketplace {
     pub listings: HashMap<String, DataListing>,
     pub purchases: Vec<PurchaseRecord>,
+    /// Hashes of the DataPurchase transactions recorded in `purchases`
+    purchase_txs: HashSet<String>,
     pub category_index: HashMap<DataCategory, Vec<String>>,  // category -> listing_ids
     pub owner_index: HashMap<String, Vec<String>>,  // owner -> listing_ids
     pub platform_fee_rate: f64,  // Platform fee percentage (0.0 - 1.0)
@@ -150,6 +155,7 @@ impl DataMarketplace {
         DataMarketplace {
             listings: HashMap::new(),
             purchases: Vec::new(),
+            purchase_txs: HashSet::new(),
             category_index: HashMap::new(),
             owner_index: HashMap::new(),
             platform_fee_rate: 0.025,  // 2.5% platform fee
@@ -243,6 +249,40 @@ impl DataMarketplace {
         Ok(purchase)
     }
     
+    /// Record the DataPurchase transactions of a block applied to the chain
+    /// against their listings, at the price paid. A transaction is recorded
+    /// once, so a purchase a reorg moves to another block isn't counted twice.
+    pub fn record_block_purchases(&mut self, block: &Block) -> Vec<PurchaseRecord> {
+        let mut recorded = Vec::new();
+        for tx in block.transactions.iter().filter(|tx| tx.tx_type == TransactionType::DataPurchase) {
+            let Some(output) = tx.outputs.first() else { continue };
+            let Some(listing) = output.data_hash.as_ref().and_then(|hash| self.listings.get_mut(hash)) else {
+                continue;
+            };
+            if !self.purchase_txs.insert(tx.hash.clone()) {
+                continue;
+            }
+            let purchase = PurchaseRecord {
+                id: tx.hash.clone(),
+                listing_id: listing.id.clone(),
+                data_hash: listing.data_hash.clone(),
+                buyer: tx.sender.clone(),
+                seller: output.recipient.clone(),
+                price: output.amount,
+                purchased_at: block.header.timestamp,
+                access_granted: true,
+            };
+            listing.total_purchases += 1;
+            listing.total_revenue += output.amount;
+            listing.updated_at = Utc::now();
+            self.total_volume += output.amount;
+            info!("Data purchase {} recorded in block #{}", &tx.hash[..8.min(tx.hash.len())], block.index);
+            self.purchases.push(purchase.clone());
+            recorded.push(purchase);
+        }
+        recorded
+    }
+    
     /// Rate a purchased data
     pub fn rate_data(
         &mut self,
@@ -410,6 +450,20 @@ impl DataMarketplace {
         results
     }
     
+    /// One page of active listings, optionally in a single category, with
+    /// the total number of matching listings
+    pub fn listings(
+        &self,
+        category: Option<&DataCategory>,
+        sort_by: SortBy,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<&DataListing>, usize) {
+        let matching = self.search(None, category, None, None, None, sort_by, usize::MAX);
+        let total = matching.len();
+        (matching.into_iter().skip(offset).take(limit).collect(), total)
+    }
+    
     /// Get marketplace statistics
     pub fn get_stats(&self) -> MarketplaceStats {
         let active_listings = self.listings.values()
@@ -455,6 +509,20 @@ pub enum SortBy {
     RatingDesc,
 }
 
+impl SortBy {
+    /// Parse a `sort_by` query value; anything unrecognised sorts newest first
+    pub fn from_string(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "price_asc" => SortBy::PriceAsc,
+            "price_desc" => SortBy::PriceDesc,
+            "quality" => SortBy::QualityDesc,
+            "popularity" => SortBy::PopularityDesc,
+            "rating" => SortBy::RatingDesc,
+            _ => SortBy::Newest,
+        }
+    }
+}
+
 /// Marketplace statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketplaceStats {
@@ -11,16 +11,22 @@ mod validators;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use actix_web::{web, App, HttpServer, middleware};
-use actix_cors::Cors;
-use actix_web::http::header;
 use actix_files::Files;
 use log::{info, error, LevelFilter};
 use env_logger::Builder;
 use std::fs;
 use std::path::Path;
 
-use blockchain::{Blockchain, MempoolManager};
-use consensus::{PoIEConsensus, DeviceRegistry, StakingManager, StakingConfig, GovernanceManager, GovernanceConfig};
+use blockchain::{Blockchain, MempoolManager, MiningBudget, BLOCK_AHEAD_ERR, MEMPOOL_FILE, STATE_SAVE_INTERVAL};
+use consensus::{PoIEConsensus, DeviceRegistry, StakingManager, GovernanceManager};
+use blockchain::genesis::{genesis_path_from_env, Genesis};
+use blockchain::mempool::simulation_mode_from_env;
+use blockchain::chain::ConsensusModules;
+use api::request_id::{request_id_middleware, RequestId};
+use api::cors::{build_cors, cors_origins_from_env};
+use consensus::staking::STAKING_FILE;
+use consensus::governance::GOVERNANCE_FILE;
+use consensus::data_quality::score_contribution;
 
 /// Check disk usage for a given path using statvfs.
 /// Returns (used_percent, used_gb, total_gb) or None on failure.
@@ -56,18 +62,36 @@ fn check_disk_usage(path: &str) -> Option<(f64, f64, f64)> {
     Some((used_pct, used_gb, total_gb))
 }
 use data_market::DataMarketplace;
-use network::{NetworkManager, NodeType};
+use network::{NetworkManager, NodeType, SyncConfig, SyncManager};
 use network::libp2p_network::{NetworkConfig, NetworkCommand, NetworkEvent, start_p2p_network};
+use network::sync_protocol::serve_sync_request;
+use api::iot::IoTRateLimitState;
 use api::{
-    AppState, DeviceState, StakingState, ContractState, GovernanceState, DexState,
+    AppState, DeviceState, StakingState, ContractState, GovernanceState, DexState, Faucet,
     configure_routes, configure_wallet_routes, configure_data_routes, 
-    configure_device_routes, configure_staking_routes, configure_contract_routes,
-    configure_governance_routes, configure_dex_routes
+    configure_device_routes, configure_iot_routes, configure_validator_routes,
+    configure_staking_routes, configure_contract_routes,
+    configure_governance_routes, configure_dex_routes, configure_ws_routes,
+    configure_consensus_routes, configure_faucet_routes
 };
-use contracts::WasmRuntime;
+use contracts::{ContractManager, WasmRuntime};
 
 const DATA_DIR: &str = "/data";
 
+/// Interval between catch-up sync rounds
+const SYNC_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How often low-scoring and blacklisted peers are disconnected
+const PEER_SCORING_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Proof-of-work time per block production round: half the block interval
+fn mining_budget(block_interval: std::time::Duration) -> MiningBudget {
+    MiningBudget {
+        max_attempts: None,
+        max_duration: Some(block_interval / 2),
+    }
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Initialize logger
@@ -90,8 +114,19 @@ async fn main() -> std::io::Result<()> {
         info!("Data directory found at {}", DATA_DIR);
     }
 
+    // Load the network genesis; an inconsistent genesis stops the node
+    let genesis = Genesis::load(&genesis_path_from_env(Path::new(DATA_DIR)))
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
     // Initialize blockchain (will load from disk if available)
-    let blockchain = Arc::new(RwLock::new(Blockchain::new()));
+    let mut chain = Blockchain::new(&genesis);
+    let mempool_path = Path::new(DATA_DIR).join(MEMPOOL_FILE);
+    match chain.restore_mempool(&mempool_path) {
+        Ok(0) => {}
+        Ok(restored) => info!("Restored {} pending transactions from {}", restored, mempool_path.display()),
+        Err(e) => log::warn!("{}; starting with an empty mempool", e),
+    }
+    let blockchain = Arc::new(RwLock::new(chain));
     
     // Initialize consensus
     let consensus = Arc::new(RwLock::new(PoIEConsensus::new()));
@@ -101,66 +136,40 @@ async fn main() -> std::io::Result<()> {
     let device_registry = Arc::new(RwLock::new(DeviceRegistry::new()));
     info!("Device Registry initialized (PoIE 2.0)");
     
-    // Initialize staking manager with custom config
-    let staking_config = StakingConfig {
-        min_validator_stake: 10_000,
-        min_delegation: 100,
-        unbonding_period: 7 * 24 * 60 * 60, // 7 days
-        max_validators: 100,
-        slash_double_sign: 0.05,  // 5%
-        slash_downtime: 0.01,     // 1%
-        min_uptime: 0.95,         // 95%
-        downtime_window: 1000,
-        commission_range: (0.0, 0.25), // 0-25%
+    // Restore staking state from disk, or register the genesis validator set
+    let staking_path = Path::new(DATA_DIR).join(STAKING_FILE);
+    let staking_mgr = match StakingManager::load_from_disk(&staking_path) {
+        Ok(Some(mgr)) => {
+            info!("Loaded staking state from {} ({} validators)", staking_path.display(), mgr.validators.len());
+            mgr
+        }
+        Ok(None) => genesis.staking_manager()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+        Err(e) => {
+            log::warn!("{}; registering genesis validators", e);
+            genesis.staking_manager()
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        }
     };
-    // Create staking manager and register initial validators before wrapping in Arc
-    let mut staking_mgr = StakingManager::new(staking_config);
-    
-    // Register initial validators for testnet
-    {
-        use consensus::ValidatorDescription;
-        
-        let initial_validators = vec![
-            ("edge_validator_foundation", "EdgeAI Foundation", "Official foundation validator node", 15_000_000, 0.05),
-            ("edge_validator_iot_hub", "IoT Network Hub", "High-performance edge computing node", 12_000_000, 0.08),
-            ("edge_validator_datastream", "DataStream Validator", "Specialized in medical IoT data", 9_500_000, 0.10),
-            ("edge_validator_smartcity", "Smart City Node", "Urban infrastructure data processing", 8_200_000, 0.07),
-            ("edge_validator_green", "Green Energy Validator", "Renewable energy monitoring network", 7_100_000, 0.06),
-        ];
         
-        for (addr, name, desc, stake, commission) in initial_validators {
-            let description = ValidatorDescription {
-                moniker: name.to_string(),
-                identity: None,
-                website: Some(format!("https://{}.edgeai.network", addr)),
-                security_contact: None,
-                details: Some(desc.to_string()),
-            };
-            let _ = staking_mgr.register_validator(
-                addr.to_string(),
-                format!("{}_operator", addr),
-                stake,
-                commission,
-                description,
-            );
-        }
-        info!("Registered {} initial validators for testnet", 5);
-    }
-    
     let staking_manager = Arc::new(RwLock::new(staking_mgr));
     info!("Staking Manager initialized (Delegation + Slashing)");
     
-    // Initialize governance manager with custom config
-    let governance_config = GovernanceConfig {
-        min_deposit: 10_000_000_000_000_000_000_000, // 10,000 EDGE
-        voting_period: 7 * 24 * 60 * 60,             // 7 days
-        quorum_percentage: 33,                       // 33% participation
-        pass_threshold: 50,                          // 50% yes votes
-        veto_threshold: 33,                          // 33% veto to reject
-        execution_delay: 2 * 24 * 60 * 60,           // 2 days
-        max_active_proposals: 10,
+    // Initialize governance manager with the genesis parameters
+    let governance_config = genesis.governance.clone();
+    let governance_path = Path::new(DATA_DIR).join(GOVERNANCE_FILE);
+    let governance_mgr = match GovernanceManager::load_from_disk(&governance_path) {
+        Ok(Some(mgr)) => {
+            info!("Loaded governance state from {} ({} proposals)", governance_path.display(), mgr.proposals.len());
+            mgr
+        }
+        Ok(None) => GovernanceManager::new(governance_config),
+        Err(e) => {
+            log::warn!("{}; starting with empty governance state", e);
+            GovernanceManager::new(governance_config)
+        }
     };
-    let governance_manager = Arc::new(RwLock::new(GovernanceManager::new(governance_config)));
+    let governance_manager = Arc::new(RwLock::new(governance_mgr));
     info!("Governance Manager initialized (On-chain DAO)");
     
     // Initialize marketplace
@@ -169,6 +178,7 @@ async fn main() -> std::io::Result<()> {
     
     // Initialize WASM runtime for smart contracts
     let wasm_runtime = Arc::new(RwLock::new(WasmRuntime::new()));
+    let contract_manager = Arc::new(RwLock::new(ContractManager::new()));
     info!("WASM Smart Contract Runtime initialized");
     
     // Initialize network
@@ -198,15 +208,22 @@ async fn main() -> std::io::Result<()> {
         info!("Bootstrap nodes: {:?}", bootstrap_nodes);
     }
     
+    let min_peers: usize = std::env::var("EDGEAI_P2P_MIN_PEERS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1);
+    
     let p2p_config = NetworkConfig {
         listen_port: p2p_port,
         bootstrap_nodes,
         enable_mdns: true,
         max_peers: 50,
+        min_peers,
+        ..NetworkConfig::default()
     };
     
     #[allow(unused_mut)]
-    let (p2p_command_tx, mut p2p_event_rx) = match start_p2p_network(p2p_config).await {
+    let (p2p_command_tx, mut p2p_event_rx) = match start_p2p_network(p2p_config, network.scoring.clone()).await {
         Ok((tx, rx)) => {
             info!("libp2p P2P network started on port {}", p2p_port);
             (Some(tx), Some(rx))
@@ -223,6 +240,7 @@ async fn main() -> std::io::Result<()> {
     // Create app state
     let app_state = web::Data::new(AppState {
         blockchain: blockchain.clone(),
+        reorg: blockchain.read().await.reorg_circuit(),
         consensus: consensus.clone(),
         marketplace: marketplace.clone(),
         network: network.clone(),
@@ -235,14 +253,22 @@ async fn main() -> std::io::Result<()> {
         registry: device_registry.clone(),
     });
     
+    // Per-device IoT submission limits
+    let iot_rate_limit = web::Data::new(IoTRateLimitState::from_env());
+    
     // Create staking state
     let staking_state = web::Data::new(StakingState {
         manager: staking_manager.clone(),
     });
     
+    // Testnet faucet claim history
+    let faucet = web::Data::new(Faucet::from_env());
+    
     // Create contract state
     let contract_state = web::Data::new(ContractState {
+        blockchain: blockchain.clone(),
         runtime: wasm_runtime.clone(),
+        manager: contract_manager.clone(),
     });
     
     // Create governance state
@@ -252,41 +278,143 @@ async fn main() -> std::io::Result<()> {
     let dex_state = web::Data::new(DexState::new());
     info!("DEX initialized with default trading pairs");
 
+    // Chain event stream for WebSocket subscribers
+    let event_bus = web::Data::new(blockchain.read().await.events.clone());
+
+    // Catch-up sync: fetch blocks we are missing from peers that are ahead
+    let sync_config = SyncConfig::default();
+    let max_served_blocks = sync_config.max_blocks_per_request;
+    let (sync_manager, mut sync_request_rx, _) = SyncManager::new(sync_config);
+    let sync_manager = web::Data::new(sync_manager);
+    
+    // Forward the sync manager's requests to the network
+    let sync_p2p_tx = p2p_tx.clone();
+    tokio::spawn(async move {
+        while let Some((peer, request)) = sync_request_rx.recv().await {
+            let Ok(peer_id) = peer.parse() else { continue };
+            if let Some(tx) = sync_p2p_tx.read().await.as_ref() {
+                let _ = tx.send(NetworkCommand::SendSyncRequest { peer_id, request }).await;
+            }
+        }
+    });
+    
+    // Poll peer heights and download, validate and apply missing blocks
+    let sync_loop_manager = sync_manager.clone();
+    let sync_blockchain = blockchain.clone();
+    let sync_staking = staking_manager.clone();
+    let sync_governance = governance_manager.clone();
+    let sync_marketplace = marketplace.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SYNC_INTERVAL);
+        loop {
+            interval.tick().await;
+            sync_loop_manager.request_heights().await;
+            let applied = sync_loop_manager.sync_step(&sync_blockchain, &sync_staking, &sync_governance).await;
+            if !applied.is_empty() {
+                let mut marketplace = sync_marketplace.write().await;
+                for block in &applied {
+                    marketplace.record_block_purchases(block);
+                }
+            }
+        }
+    });
+    
     // Start P2P event handler
     if let Some(mut event_rx) = p2p_event_rx {
         let p2p_blockchain = blockchain.clone();
+        let p2p_reorg = blockchain.read().await.reorg_circuit();
+        let p2p_marketplace = marketplace.clone();
         let p2p_device_registry = device_registry.clone();
+        let p2p_network = network.clone();
+        let p2p_staking = staking_manager.clone();
+        let p2p_governance = governance_manager.clone();
+        let p2p_sync = sync_manager.clone();
+        let p2p_sync_tx = p2p_tx.clone();
         tokio::spawn(async move {
             info!("P2P event handler started");
             while let Some(event) = event_rx.recv().await {
                 match event {
                     NetworkEvent::PeerConnected(peer_id) => {
                         info!("P2P: Peer connected: {}", peer_id);
+                        p2p_sync.register_peer(&peer_id.to_string()).await;
                     }
                     NetworkEvent::PeerDisconnected(peer_id) => {
                         info!("P2P: Peer disconnected: {}", peer_id);
+                        p2p_network.scoring.unregister_peer(&peer_id.to_string()).await;
+                        let offset_ms = {
+                            let mut clock = p2p_network.clock.write().await;
+                            clock.remove_peer(&peer_id.to_string());
+                            clock.offset_ms()
+                        };
+                        p2p_blockchain.write().await.set_clock_offset_ms(offset_ms);
+                        p2p_sync.unregister_peer(&peer_id.to_string()).await;
+                    }
+                    NetworkEvent::SyncRequest { peer_id, request_id, request } => {
+                        let response = serve_sync_request(&*p2p_blockchain.read().await, &request, max_served_blocks);
+                        if let Some(tx) = p2p_sync_tx.read().await.as_ref() {
+                            let _ = tx.send(NetworkCommand::SendSyncResponse { peer_id, request_id, response }).await;
+                        }
                     }
-                    NetworkEvent::NewTransaction(tx) => {
-                        info!("P2P: Received transaction: {}", &tx.hash[..8]);
-                        let mut chain = p2p_blockchain.write().await;
-                        if let Err(e) = chain.add_transaction(tx) {
-                            log::warn!("P2P: Transaction rejected: {}", e);
+                    NetworkEvent::SyncResponse { peer_id, response } => {
+                        let peer = peer_id.to_string();
+                        p2p_sync.register_peer(&peer).await;
+                        p2p_sync.handle_response(&peer, response).await;
+                    }
+                    NetworkEvent::PeerTime { peer_id, timestamp_ms } => {
+                        let now = chrono::Utc::now().timestamp_millis();
+                        let offset_ms = p2p_network.clock.write().await
+                            .record_peer_time(&peer_id.to_string(), timestamp_ms, now);
+                        p2p_blockchain.write().await.set_clock_offset_ms(offset_ms);
+                    }
+                    NetworkEvent::NewTransaction { peer_id, tx } => {
+                        let request_id = RequestId::internal("p2p");
+                        info!("P2P: Received transaction: {} [request {}]", &tx.hash[..8], request_id);
+                        // Dropped without scoring the peer: the transaction isn't at fault
+                        if let Err(e) = p2p_reorg.check() {
+                            log::debug!("P2P: Transaction {} dropped: {} [request {}]", &tx.hash[..8], e, request_id);
+                            continue;
+                        }
+                        let result = p2p_blockchain.write().await.add_transaction(tx.clone());
+                        if let Err(e) = &result {
+                            log::warn!("P2P: Transaction rejected: {} [request {}]", e, request_id);
                         }
+                        p2p_network.scoring.record_transaction(&peer_id.to_string(), &tx, result.is_ok()).await;
                     }
-                    NetworkEvent::NewBlock(block) => {
+                    NetworkEvent::NewBlock { peer_id, block } => {
                         info!("P2P: Received block #{}", block.index);
-                        // TODO: Validate and add block from peer
+                        p2p_network.propagation.write().await
+                            .record_peer_block(&block, chrono::Utc::now());
+                        let index = block.index;
+                        let result = {
+                            let mut chain = p2p_blockchain.write().await;
+                            let mut staking = p2p_staking.write().await;
+                            let mut governance = p2p_governance.write().await;
+                            chain.accept_external_block(block.clone(), &mut ConsensusModules::new(&mut staking, &mut governance))
+                        };
+                        match &result {
+                            Ok(()) => {
+                                info!("P2P: Accepted block #{}", index);
+                                p2p_marketplace.write().await.record_block_purchases(&block);
+                            }
+                            Err(e) if e.starts_with(BLOCK_AHEAD_ERR) => {
+                                log::debug!("P2P: Block #{} is ahead of local chain, left to sync ({})", index, e);
+                            }
+                            Err(e) => log::warn!("P2P: Block #{} rejected: {}", index, e),
+                        }
+                        p2p_network.scoring.record_block(&peer_id.to_string(), &block, result.is_ok()).await;
                     }
                     NetworkEvent::NewContribution(contrib) => {
                         info!("P2P: Received contribution from {}", &contrib.device_id[..8]);
                         // Record contribution in device registry
                         let mut registry = p2p_device_registry.write().await;
-                        if let Some(device) = registry.get_device_mut(&contrib.device_id) {
-                            // Calculate quality score from contribution
-                            let quality_score = 0.7; // Default quality, should be calculated
-                            let points = 10.0; // Base points
-                            device.record_contribution(quality_score, points);
-                        }
+                        // Score the telemetry ourselves; contributions without any earn nothing
+                        let quality_score = match (registry.get_device(&contrib.device_id), &contrib.telemetry) {
+                            (Some(device), Some(telemetry)) => score_contribution(telemetry, &device.device_type),
+                            _ => 0.0,
+                        };
+                        let points = 10.0; // Base points
+                        // Unknown devices are ignored; too-frequent ones earn nothing
+                        let _ = registry.record_contribution(&contrib.device_id, quality_score, points);
                     }
                     NetworkEvent::Ready => {
                         info!("P2P: Network ready");
@@ -296,6 +424,30 @@ async fn main() -> std::io::Result<()> {
         });
     }
     
+    // Disconnect peers whose score fell too low or that were blacklisted
+    let scoring_network = network.clone();
+    let scoring_p2p_tx = p2p_tx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PEER_SCORING_INTERVAL);
+        loop {
+            interval.tick().await;
+            let scoring = &scoring_network.scoring;
+            scoring.cleanup_expired_bans().await;
+            let mut peers = scoring.get_peers_to_disconnect().await;
+            for peer in scoring.peer_ids().await {
+                if !peers.contains(&peer) && scoring.is_blacklisted(&peer).await {
+                    peers.push(peer);
+                }
+            }
+            let Some(tx) = scoring_p2p_tx.read().await.clone() else { continue };
+            for peer in peers {
+                let Ok(peer_id) = peer.parse() else { continue };
+                log::warn!("P2P: Disconnecting low-scoring peer {}", peer);
+                let _ = tx.send(NetworkCommand::DisconnectPeer(peer_id)).await;
+            }
+        }
+    });
+    
     // Start background mining task
     let mining_blockchain = blockchain.clone();
     let mining_validator = node_id.clone();
@@ -303,10 +455,19 @@ async fn main() -> std::io::Result<()> {
     let mining_device_registry = device_registry.clone();
     let mining_staking = staking_manager.clone();
     let mining_governance = governance_manager.clone();
+    let mining_network = network.clone();
+    let mining_marketplace = marketplace.clone();
+    let simulation_mode = simulation_mode_from_env();
+    if simulation_mode {
+        info!("Simulation mode enabled: synthetic transactions will fill blocks");
+    }
     
     tokio::spawn(async move {
-        info!("Block producer started (10s fixed interval)");
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10));
+        let target_block_secs = mining_blockchain.read().await.state.params.target_block_secs;
+        let block_interval = tokio::time::Duration::from_secs(target_block_secs);
+        let budget = mining_budget(block_interval);
+        info!("Block producer started ({}s target interval)", block_interval.as_secs());
+        let mut interval = tokio::time::interval(block_interval);
         let mut consecutive_errors: u32 = 0;
         
         loop {
@@ -325,15 +486,6 @@ async fn main() -> std::io::Result<()> {
                     let stats = registry.get_stats();
                     info!("Device Registry: {} total, {} active, {} regions", 
                         stats.total_devices, stats.active_devices, stats.regions_covered);
-                    
-                    let mut staking = mining_staking.write().await;
-                    let completed = staking.process_unbonding();
-                    if !completed.is_empty() {
-                        info!("Processed {} unbonding entries", completed.len());
-                    }
-                    
-                    let mut governance = mining_governance.write().await;
-                    governance.process_expired_deposits();
                 }
                 
                 // Disk usage monitoring every 60 blocks (~10 minutes)
@@ -383,21 +535,18 @@ async fn main() -> std::io::Result<()> {
                     }
                 }
                 
-                // Distribute staking rewards every block
-                {
-                    let mut staking = mining_staking.write().await;
-                    let block_reward = 100;
-                    staking.distribute_rewards(block_reward);
-                }
-                
-                // Collect pending transactions from mempool
-                let mut mempool = MempoolManager::with_block_context(current_height);
-                let pending_txs = mempool.collect_for_block(current_height);
+                // Synthetic demo load; submitted transactions are already in the pool
+                let pending_txs = if simulation_mode {
+                    MempoolManager::with_block_context(current_height, chain.state.reward_policy.clone())
+                        .collect_for_block(current_height)
+                } else {
+                    Vec::new()
+                };
                 
                 let mut added_count = 0;
                 let mut failed_count = 0;
                 for tx in pending_txs {
-                    match chain.add_transaction(tx) {
+                    match chain.add_synthetic_transaction(tx) {
                         Ok(_) => added_count += 1,
                         Err(e) => {
                             failed_count += 1;
@@ -409,19 +558,61 @@ async fn main() -> std::io::Result<()> {
                     log::warn!("Block {}: {} tx rejected out of {}", current_height, failed_count, added_count + failed_count);
                 }
                 
-                // Produce new block
-                match chain.mine_block(mining_validator.clone()) {
-                    Ok(block) => {
+                // Build the next block under the chain lock, then run proof-of-work
+                // on the blocking pool without it so the API and peer imports aren't
+                // held up; mining gives up after half the block interval
+                let mut template = {
+                    let mut staking = mining_staking.write().await;
+                    let mut governance = mining_governance.write().await;
+                    chain.prepare_block(mining_validator.clone(), &mut ConsensusModules::new(&mut staking, &mut governance))
+                };
+                drop(chain);
+                let (template, mined) = tokio::task::spawn_blocking(move || {
+                    let mined = template.mine(budget);
+                    (template, mined)
+                }).await?;
+                if !mined {
+                    info!("No block this round: mining budget exhausted at height {}", template.block.index);
+                    return Ok(());
+                }
+                let index = template.block.index;
+                
+                // Take the locks again only to append; a peer block appended meanwhile wins
+                let appended = {
+                    let mut chain = mining_blockchain.write().await;
+                    let mut staking = mining_staking.write().await;
+                    let mut governance = mining_governance.write().await;
+                    chain.append_mined_block(template, &mut ConsensusModules::new(&mut staking, &mut governance))
+                        .map(|block| (block, chain.total_blocks))
+                };
+                match appended {
+                    Ok((block, total_blocks)) => {
                         info!("Produced block #{} with {} transactions", 
                               block.index, block.transactions.len());
                         
+                        mining_network.propagation.write().await
+                            .record_local_block(&block, chrono::Utc::now());
+                        mining_marketplace.write().await.record_block_purchases(&block);
+                        
+                        // Snapshot staking and governance alongside the chain state
+                        if total_blocks.is_multiple_of(STATE_SAVE_INTERVAL) {
+                            if let Err(e) = mining_staking.read().await
+                                .save_to_disk(&Path::new(DATA_DIR).join(STAKING_FILE)) {
+                                error!("{}", e);
+                            }
+                            if let Err(e) = mining_governance.read().await
+                                .save_to_disk(&Path::new(DATA_DIR).join(GOVERNANCE_FILE)) {
+                                error!("{}", e);
+                            }
+                        }
+                        
                         let p2p_guard = mining_p2p_tx.read().await;
                         if let Some(ref tx) = *p2p_guard {
                             let _ = tx.send(NetworkCommand::BroadcastBlock(block.clone())).await;
                         }
                     },
                     Err(e) => {
-                        log::warn!("Block production failed: {}", e);
+                        log::warn!("Mined block #{} was not appended: {}", index, e);
                     }
                 }
                 
@@ -457,44 +648,60 @@ async fn main() -> std::io::Result<()> {
     info!("DEX API at http://{}/api/dex/", bind_address);
     info!("Block Explorer available at http://{}/", bind_address);
     
+    // CORS configuration - restrict to known origins for security
+    let cors_origins = cors_origins_from_env();
+    
     // Start HTTP server
     HttpServer::new(move || {
-        // CORS configuration - restrict to known origins for security
-        let cors = Cors::default()
-            .allowed_origin("https://edgeai-alpha.vercel.app")
-            .allowed_origin("https://edgeai-chain.github.io")
-            .allowed_origin("https://edgeaiexplorer.org")
-            .allowed_origin("https://www.edgeaiexplorer.org")
-            .allowed_origin("https://edgeaiexplor-hg7rs66y.manus.space")
-            .allowed_origin("http://localhost:3000")
-            .allowed_origin("http://localhost:5173")
-            .allowed_origin("http://127.0.0.1:3000")
-            .allowed_origin("http://127.0.0.1:5173")
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
-            .allowed_headers(vec![header::CONTENT_TYPE, header::AUTHORIZATION, header::ACCEPT])
-            .supports_credentials()
-            .max_age(3600);
+        let cors = build_cors(&cors_origins);
         
         App::new()
             .wrap(cors)
-            .wrap(middleware::Logger::default())
+            .wrap(middleware::from_fn(request_id_middleware))
+            .wrap(middleware::Logger::new(
+                r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}o"#,
+            ))
             .app_data(app_state.clone())
             .app_data(device_state.clone())
+            .app_data(iot_rate_limit.clone())
             .app_data(staking_state.clone())
+            .app_data(faucet.clone())
             .app_data(contract_state.clone())
             .app_data(governance_state.clone())
             .app_data(dex_state.clone())
+            .app_data(event_bus.clone())
+            .app_data(sync_manager.clone())
             .configure(configure_routes)
             .configure(configure_wallet_routes)
             .configure(configure_data_routes)
             .configure(configure_device_routes)
+            .configure(configure_iot_routes)
+            .configure(configure_validator_routes)
             .configure(configure_staking_routes)
             .configure(configure_contract_routes)
             .configure(configure_governance_routes)
             .configure(|cfg| configure_dex_routes(cfg, dex_state.clone()))
+            .configure(configure_ws_routes)
+            .configure(configure_consensus_routes)
+            .configure(configure_faucet_routes)
             .service(Files::new("/", "./static").index_file("index.html"))
     })
     .bind(bind_address)?
     .run()
-    .await
+    .await?;
+    
+    // The server returns once SIGINT/SIGTERM has stopped it; save what the
+    // periodic snapshots haven't yet
+    info!("Shutting down: flushing state to disk");
+    match blockchain.read().await.flush_on_shutdown(&mempool_path) {
+        Ok(saved) => info!("Saved {} pending transactions to {}", saved, mempool_path.display()),
+        Err(e) => error!("{}", e),
+    }
+    if let Err(e) = staking_manager.read().await.save_to_disk(&staking_path) {
+        error!("{}", e);
+    }
+    if let Err(e) = governance_manager.read().await.save_to_disk(&governance_path) {
+        error!("{}", e);
+    }
+    Ok(())
 }
@@ -0,0 +1,173 @@
+//! Network-adjusted time
+//!
+//! Estimates the local clock's offset from the network using the median of
+//! peers' reported times, similar to Bitcoin's network-adjusted time:
+//! - Each peer contributes one sample (its latest), so a single peer can't
+//!   skew the estimate by flooding
+//! - No adjustment is applied until enough peers have reported
+//! - Offsets beyond `MAX_ADJUSTMENT_MS` are never applied; the local clock
+//!   is assumed wrong and a warning is logged instead
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+/// Minimum number of peer samples before the offset is applied
+pub const MIN_PEER_SAMPLES: usize = 5;
+
+/// Maximum number of peers tracked
+const MAX_PEER_SAMPLES: usize = 200;
+
+/// Drift above which a warning is raised (10 seconds)
+pub const DRIFT_WARNING_MS: i64 = 10_000;
+
+/// Largest offset that will be applied (70 minutes)
+pub const MAX_ADJUSTMENT_MS: i64 = 70 * 60 * 1_000;
+
+/// Clock status exposed by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockStatus {
+    /// Offset applied to local time, in milliseconds
+    pub offset_ms: i64,
+    /// Median peer offset, if any samples exist
+    pub median_peer_offset_ms: Option<i64>,
+    /// Number of peers contributing samples
+    pub peer_samples: usize,
+    /// Local drift exceeds `DRIFT_WARNING_MS`
+    pub drift_warning: bool,
+    /// Network-adjusted time (unix millis)
+    pub adjusted_time_ms: i64,
+}
+
+/// Tracks peer clock offsets and the resulting adjustment
+#[derive(Debug, Default)]
+pub struct NetworkClock {
+    /// Peer id -> (peer time - local time) in milliseconds
+    samples: HashMap<String, i64>,
+    /// Currently applied offset
+    offset_ms: i64,
+}
+
+impl NetworkClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a peer's reported time (unix millis), observed at `local_ms`.
+    /// Returns the updated offset.
+    pub fn record_peer_time(&mut self, peer_id: &str, peer_time_ms: i64, local_ms: i64) -> i64 {
+        if self.samples.len() >= MAX_PEER_SAMPLES && !self.samples.contains_key(peer_id) {
+            return self.offset_ms;
+        }
+        self.samples.insert(peer_id.to_string(), peer_time_ms - local_ms);
+        self.recompute();
+        self.offset_ms
+    }
+
+    /// Forget a disconnected peer's sample
+    pub fn remove_peer(&mut self, peer_id: &str) {
+        if self.samples.remove(peer_id).is_some() {
+            self.recompute();
+        }
+    }
+
+    /// Median of the peer offsets
+    pub fn median_offset_ms(&self) -> Option<i64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let mut offsets: Vec<i64> = self.samples.values().copied().collect();
+        offsets.sort_unstable();
+        let mid = offsets.len() / 2;
+        Some(if offsets.len().is_multiple_of(2) {
+            (offsets[mid - 1] + offsets[mid]) / 2
+        } else {
+            offsets[mid]
+        })
+    }
+
+    /// Offset currently applied to local time
+    pub fn offset_ms(&self) -> i64 {
+        self.offset_ms
+    }
+
+    /// Local time corrected by the network offset
+    pub fn adjusted_now(&self) -> DateTime<Utc> {
+        Utc::now() + Duration::milliseconds(self.offset_ms)
+    }
+
+    /// Build the status report
+    pub fn status(&self) -> ClockStatus {
+        let median = self.median_offset_ms();
+        ClockStatus {
+            offset_ms: self.offset_ms,
+            median_peer_offset_ms: median,
+            peer_samples: self.samples.len(),
+            drift_warning: median.is_some_and(|m| m.abs() > DRIFT_WARNING_MS),
+            adjusted_time_ms: self.adjusted_now().timestamp_millis(),
+        }
+    }
+
+    fn recompute(&mut self) {
+        if self.samples.len() < MIN_PEER_SAMPLES {
+            self.offset_ms = 0;
+            return;
+        }
+        let median = self.median_offset_ms().unwrap_or(0);
+        if median.abs() > MAX_ADJUSTMENT_MS {
+            warn!(
+                "Local clock differs from network median by {}s; not adjusting. Check the system clock",
+                median / 1_000
+            );
+            self.offset_ms = 0;
+            return;
+        }
+        if median.abs() > DRIFT_WARNING_MS && self.offset_ms.abs() <= DRIFT_WARNING_MS {
+            warn!("Local clock drift of {}ms from network median, adjusting", median);
+        }
+        self.offset_ms = median;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consistent_peer_offset() {
+        let mut clock = NetworkClock::new();
+        let local = 1_700_000_000_000;
+
+        // Peers all run 30s ahead, with a little jitter and one outlier
+        let offsets = [30_000, 30_050, 29_950, 30_010, 29_990, 3_600_000];
+        for (i, offset) in offsets.iter().take(MIN_PEER_SAMPLES - 1).enumerate() {
+            assert_eq!(clock.record_peer_time(&format!("peer{}", i), local + offset, local), 0);
+        }
+        // Not applied until enough peers report
+        assert_eq!(clock.offset_ms(), 0);
+
+        clock.record_peer_time("peer4", local + offsets[4], local);
+        clock.record_peer_time("peer5", local + offsets[5], local);
+        assert_eq!(clock.offset_ms(), 30_005);
+        let status = clock.status();
+        assert_eq!(status.peer_samples, 6);
+        assert!(status.drift_warning);
+
+        // A peer re-reporting replaces its sample rather than adding one
+        clock.record_peer_time("peer5", local + 30_000, local);
+        assert_eq!(clock.status().peer_samples, 6);
+        assert_eq!(clock.offset_ms(), 30_000);
+
+        // Offsets beyond the adjustment cap are never applied
+        let mut skewed = NetworkClock::new();
+        for i in 0..MIN_PEER_SAMPLES {
+            skewed.record_peer_time(&format!("peer{}", i), local + MAX_ADJUSTMENT_MS + 1, local);
+        }
+        assert_eq!(skewed.offset_ms(), 0);
+        assert!(skewed.status().drift_warning);
+    }
+}
@@ -2,35 +2,83 @@
 //! 
 //! This module implements the P2P networking layer using libp2p,
 //! providing node discovery, gossip-based message propagation,
-//! and peer management.
+//! and peer management. Sync requests go straight to one peer over the
+//! `/edgeai/sync/1.0.0` request-response protocol, falling back to the sync
+//! gossip topic for peers that don't speak it.
 
 #![allow(dead_code)]
 
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
-use std::time::Duration;
+use std::io;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use async_trait::async_trait;
 use libp2p::{
-    futures::StreamExt,
+    futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, StreamExt},
     gossipsub::{self, IdentTopic, MessageAuthenticity, ValidationMode},
     identify,
     kad::{self, store::MemoryStore},
     mdns,
+    multiaddr::Protocol,
     noise,
+    request_response::{self, InboundRequestId, OutboundRequestId, ProtocolSupport, ResponseChannel},
     swarm::{NetworkBehaviour, SwarmEvent},
-    tcp, yamux, Multiaddr, PeerId, Swarm,
+    tcp, yamux, Multiaddr, PeerId, StreamProtocol, Swarm,
 };
 use tokio::sync::mpsc;
 use log::{info, debug, warn, error};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 use crate::blockchain::{Block, Transaction};
+use crate::network::peer_scoring::PeerScoringManager;
+use crate::network::sync_protocol::{SyncRequest, SyncResponse};
 
 /// Gossip topics for EdgeAI network
 pub mod topics {
     pub const TRANSACTIONS: &str = "edgeai/tx/1.0.0";
     pub const BLOCKS: &str = "edgeai/block/1.0.0";
     pub const CONTRIBUTIONS: &str = "edgeai/contribution/1.0.0";
+    pub const STATUS: &str = "edgeai/status/1.0.0";
+    /// Sync traffic for peers without the sync protocol
+    pub const SYNC: &str = "edgeai/sync/1.0.0";
+}
+
+/// Request-response protocol carrying sync requests to a single peer
+pub const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/edgeai/sync/1.0.0");
+
+/// Interval between clock heartbeats published on the status topic
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the peer count is checked against `min_peers`
+const REDISCOVERY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Largest gossip message sent or accepted (the gossipsub default). Block
+/// ranges go over the sync protocol, which has its own limit.
+const MAX_GOSSIP_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// Largest sync request read from a peer
+pub const MAX_SYNC_REQUEST_SIZE: usize = 64 * 1024;
+
+/// Largest sync response read from a peer; larger ones fail the request
+/// rather than being buffered
+pub const MAX_SYNC_RESPONSE_SIZE: usize = 8 * 1024 * 1024;
+
+/// How long a peer has to answer a sync request
+const SYNC_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Identifies an inbound sync request, so its response goes back on the
+/// stream it arrived on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncRequestId {
+    /// Received over the sync protocol
+    Stream(InboundRequestId),
+    /// Received over gossip, so answered over gossip
+    Gossip,
 }
 
 /// Network events that can be emitted to the application layer
@@ -40,12 +88,18 @@ pub enum NetworkEvent {
     PeerConnected(PeerId),
     /// A peer disconnected
     PeerDisconnected(PeerId),
-    /// Received a new transaction from the network
-    NewTransaction(Transaction),
-    /// Received a new block from the network
-    NewBlock(Block),
+    /// Received a new transaction from the network, authored by `peer_id`
+    NewTransaction { peer_id: PeerId, tx: Transaction },
+    /// Received a new block from the network, authored by `peer_id`
+    NewBlock { peer_id: PeerId, block: Block },
     /// Received a contribution proof
     NewContribution(ContributionMessage),
+    /// A peer reported its clock (unix millis)
+    PeerTime { peer_id: PeerId, timestamp_ms: i64 },
+    /// A peer asked us for chain data
+    SyncRequest { peer_id: PeerId, request_id: SyncRequestId, request: SyncRequest },
+    /// A peer answered one of our sync requests
+    SyncResponse { peer_id: PeerId, response: SyncResponse },
     /// Network is ready
     Ready,
 }
@@ -59,8 +113,14 @@ pub enum NetworkCommand {
     BroadcastBlock(Block),
     /// Broadcast a contribution proof
     BroadcastContribution(ContributionMessage),
+    /// Send a sync request to a peer
+    SendSyncRequest { peer_id: PeerId, request: SyncRequest },
+    /// Answer a peer's sync request
+    SendSyncResponse { peer_id: PeerId, request_id: SyncRequestId, response: SyncResponse },
     /// Connect to a specific peer
     ConnectPeer(Multiaddr),
+    /// Close all connections to a peer
+    DisconnectPeer(PeerId),
     /// Get current peer count
     GetPeerCount,
 }
@@ -72,6 +132,9 @@ pub struct ContributionMessage {
     pub data_hash: String,
     pub quality_score: f64,
     pub timestamp: i64,
+    /// Raw telemetry, scored locally rather than trusting `quality_score`
+    #[serde(default)]
+    pub telemetry: Option<String>,
 }
 
 /// Gossip message wrapper
@@ -80,6 +143,89 @@ pub enum GossipMessage {
     Transaction(Transaction),
     Block(Block),
     Contribution(ContributionMessage),
+    /// Periodic heartbeat carrying the sender's clock
+    Heartbeat { timestamp_ms: i64 },
+    /// Sync request addressed to the peer `to`. The nonce keeps repeated
+    /// requests from being dropped as duplicate gossip.
+    SyncRequest { to: String, nonce: u64, request: SyncRequest },
+    /// Sync response addressed to the peer `to`
+    SyncResponse { to: String, nonce: u64, response: SyncResponse },
+}
+
+/// Serialize a sync request or response for the sync protocol
+pub fn encode_sync_message<T: Serialize>(message: &T) -> io::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(message)?)
+}
+
+/// Parse a sync request or response, refusing messages over `max_size` bytes
+pub fn decode_sync_message<T: DeserializeOwned>(data: &[u8], max_size: usize) -> io::Result<T> {
+    if data.len() > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("sync message exceeds the {} byte limit", max_size),
+        ));
+    }
+    Ok(serde_json::from_slice(data)?)
+}
+
+/// Read a whole sync message, stopping one byte past `max_size` so an
+/// oversized message is detected without buffering all of it
+async fn read_sync_message<T, M>(io: &mut T, max_size: usize) -> io::Result<M>
+where
+    T: AsyncRead + Unpin + Send,
+    M: DeserializeOwned,
+{
+    let mut data = Vec::new();
+    io.take(max_size as u64 + 1).read_to_end(&mut data).await?;
+    decode_sync_message(&data, max_size)
+}
+
+async fn write_sync_message<T, M>(io: &mut T, message: &M) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+    M: Serialize,
+{
+    io.write_all(&encode_sync_message(message)?).await?;
+    io.close().await
+}
+
+/// JSON codec for the sync protocol
+#[derive(Debug, Clone, Default)]
+pub struct SyncCodec;
+
+#[async_trait]
+impl request_response::Codec for SyncCodec {
+    type Protocol = StreamProtocol;
+    type Request = SyncRequest;
+    type Response = SyncResponse;
+
+    async fn read_request<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<SyncRequest>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_sync_message(io, MAX_SYNC_REQUEST_SIZE).await
+    }
+
+    async fn read_response<T>(&mut self, _: &StreamProtocol, io: &mut T) -> io::Result<SyncResponse>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_sync_message(io, MAX_SYNC_RESPONSE_SIZE).await
+    }
+
+    async fn write_request<T>(&mut self, _: &StreamProtocol, io: &mut T, request: SyncRequest) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_sync_message(io, &request).await
+    }
+
+    async fn write_response<T>(&mut self, _: &StreamProtocol, io: &mut T, response: SyncResponse) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_sync_message(io, &response).await
+    }
 }
 
 /// Combined network behaviour for EdgeAI
@@ -93,6 +239,8 @@ pub struct EdgeAIBehaviour {
     pub mdns: mdns::tokio::Behaviour,
     /// Identify protocol for peer identification
     pub identify: identify::Behaviour,
+    /// Sync requests and responses exchanged with a single peer
+    pub sync: request_response::Behaviour<SyncCodec>,
 }
 
 /// Configuration for the P2P network
@@ -106,6 +254,12 @@ pub struct NetworkConfig {
     pub enable_mdns: bool,
     /// Maximum number of peers
     pub max_peers: usize,
+    /// Below this many connected peers the node re-dials known peers
+    pub min_peers: usize,
+    /// Delay before the second rediscovery attempt; doubles after each one
+    pub rediscovery_base_interval: Duration,
+    /// Cap on the delay between rediscovery attempts
+    pub rediscovery_max_interval: Duration,
 }
 
 impl Default for NetworkConfig {
@@ -115,20 +269,82 @@ impl Default for NetworkConfig {
             bootstrap_nodes: vec![],
             enable_mdns: true,
             max_peers: 50,
+            min_peers: 1,
+            rediscovery_base_interval: Duration::from_secs(10),
+            rediscovery_max_interval: Duration::from_secs(300),
         }
     }
 }
 
+/// Exponential backoff for re-dialing peers while the node is under-connected
+#[derive(Debug, Clone)]
+pub struct Rediscovery {
+    min_peers: usize,
+    base_interval: Duration,
+    max_interval: Duration,
+    attempts: u32,
+    next_attempt: Option<Instant>,
+}
+
+impl Rediscovery {
+    pub fn new(min_peers: usize, base_interval: Duration, max_interval: Duration) -> Self {
+        Self {
+            min_peers,
+            base_interval,
+            max_interval,
+            attempts: 0,
+            next_attempt: None,
+        }
+    }
+    
+    /// Whether to attempt rediscovery now. The first attempt fires as soon as
+    /// the peer count drops below `min_peers`, later ones back off
+    /// exponentially; reaching `min_peers` again resets the backoff.
+    pub fn should_attempt(&mut self, peer_count: usize, now: Instant) -> bool {
+        if peer_count >= self.min_peers {
+            self.attempts = 0;
+            self.next_attempt = None;
+            return false;
+        }
+        if self.next_attempt.is_some_and(|at| now < at) {
+            return false;
+        }
+        let delay = self.base_interval
+            .saturating_mul(1u32 << self.attempts.min(16))
+            .min(self.max_interval);
+        self.attempts += 1;
+        self.next_attempt = Some(now + delay);
+        true
+    }
+    
+    /// Attempts made since the node last had enough peers
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
 /// P2P Network Service
 pub struct P2PNetwork {
     /// Local peer ID
     pub local_peer_id: PeerId,
+    /// Node identity, shared by the swarm so peers see `local_peer_id`
+    local_key: libp2p::identity::Keypair,
     /// Channel to send events to application
     event_tx: mpsc::Sender<NetworkEvent>,
     /// Channel to receive commands from application
     command_rx: mpsc::Receiver<NetworkCommand>,
     /// Network configuration
     config: NetworkConfig,
+    /// Last known address of each peer found via mDNS, re-dialed on rediscovery
+    mdns_peers: HashMap<PeerId, Multiaddr>,
+    /// Peer reputation; blacklisted peer IDs and IPs are refused
+    scoring: Arc<PeerScoringManager>,
+    /// Channels to answer sync requests received over the sync protocol.
+    /// Dropped when the request fails or the peer disconnects.
+    sync_channels: HashMap<InboundRequestId, (PeerId, ResponseChannel<SyncResponse>)>,
+    /// Sync requests awaiting a response, resent over gossip if the peer
+    /// doesn't support the sync protocol
+    outbound_sync: HashMap<OutboundRequestId, SyncRequest>,
 }
 
 impl P2PNetwork {
@@ -148,23 +364,46 @@ impl P2PNetwork {
         
         let network = Self {
             local_peer_id,
+            local_key,
             event_tx,
             command_rx,
             config,
+            mdns_peers: HashMap::new(),
+            scoring: Arc::new(PeerScoringManager::new()),
+            sync_channels: HashMap::new(),
+            outbound_sync: HashMap::new(),
         };
         
         Ok((network, command_tx, event_rx))
     }
     
+    /// Use a scoring manager shared with the application
+    pub fn with_scoring(mut self, scoring: Arc<PeerScoringManager>) -> Self {
+        self.scoring = scoring;
+        self
+    }
+    
+    /// Whether a newly connected peer is banned by ID or remote IP
+    async fn is_banned(&self, peer_id: &PeerId, remote: &Multiaddr) -> bool {
+        if self.scoring.is_blacklisted(&peer_id.to_string()).await {
+            return true;
+        }
+        match multiaddr_ip(remote) {
+            Some(ip) => self.scoring.is_ip_blacklisted(&ip).await,
+            None => false,
+        }
+    }
+    
     /// Build the libp2p swarm
     fn build_swarm(&self) -> Result<Swarm<EdgeAIBehaviour>, Box<dyn std::error::Error + Send + Sync>> {
-        let local_key = libp2p::identity::Keypair::generate_ed25519();
-        let local_peer_id = PeerId::from(local_key.public());
+        let local_key = self.local_key.clone();
+        let local_peer_id = self.local_peer_id;
         
         // Configure gossipsub
         let gossipsub_config = gossipsub::ConfigBuilder::default()
             .heartbeat_interval(Duration::from_secs(1))
             .validation_mode(ValidationMode::Strict)
+            .max_transmit_size(MAX_GOSSIP_MESSAGE_SIZE)
             .message_id_fn(|message: &gossipsub::Message| {
                 let mut hasher = DefaultHasher::new();
                 message.data.hash(&mut hasher);
@@ -198,12 +437,20 @@ impl P2PNetwork {
             )
         );
         
+        // Configure the sync protocol
+        let sync = request_response::Behaviour::with_codec(
+            SyncCodec,
+            [(SYNC_PROTOCOL, ProtocolSupport::Full)],
+            request_response::Config::default().with_request_timeout(SYNC_REQUEST_TIMEOUT),
+        );
+        
         // Create the combined behaviour
         let behaviour = EdgeAIBehaviour {
             gossipsub,
             kademlia,
             mdns,
             identify,
+            sync,
         };
         
         // Build the swarm
@@ -229,10 +476,14 @@ impl P2PNetwork {
         let tx_topic = IdentTopic::new(topics::TRANSACTIONS);
         let block_topic = IdentTopic::new(topics::BLOCKS);
         let contribution_topic = IdentTopic::new(topics::CONTRIBUTIONS);
+        let status_topic = IdentTopic::new(topics::STATUS);
+        let sync_topic = IdentTopic::new(topics::SYNC);
         
         swarm.behaviour_mut().gossipsub.subscribe(&tx_topic)?;
         swarm.behaviour_mut().gossipsub.subscribe(&block_topic)?;
         swarm.behaviour_mut().gossipsub.subscribe(&contribution_topic)?;
+        swarm.behaviour_mut().gossipsub.subscribe(&status_topic)?;
+        swarm.behaviour_mut().gossipsub.subscribe(&sync_topic)?;
         
         // Start listening
         let listen_addr: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", self.config.listen_port).parse()?;
@@ -253,9 +504,38 @@ impl P2PNetwork {
         // Notify that network is ready
         let _ = self.event_tx.send(NetworkEvent::Ready).await;
         
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut rediscovery_check = tokio::time::interval(REDISCOVERY_CHECK_INTERVAL);
+        let mut rediscovery = Rediscovery::new(
+            self.config.min_peers,
+            self.config.rediscovery_base_interval,
+            self.config.rediscovery_max_interval,
+        );
+        // Give the startup dials a chance before the first rediscovery
+        rediscovery_check.reset();
+        
         // Main event loop
         loop {
             tokio::select! {
+                // Publish our clock so peers can estimate drift
+                _ = heartbeat.tick() => {
+                    let msg = GossipMessage::Heartbeat { timestamp_ms: chrono::Utc::now().timestamp_millis() };
+                    if let Ok(data) = serde_json::to_vec(&msg) {
+                        // Fails with InsufficientPeers until the mesh forms
+                        let _ = swarm.behaviour_mut().gossipsub.publish(status_topic.clone(), data);
+                    }
+                }
+                
+                // Re-dial known peers while under-connected
+                _ = rediscovery_check.tick() => {
+                    let peer_count = swarm.connected_peers().count();
+                    if rediscovery.should_attempt(peer_count, Instant::now()) {
+                        info!("Only {} peers connected (minimum {}), rediscovering (attempt {})",
+                            peer_count, self.config.min_peers, rediscovery.attempts());
+                        self.rediscover(&mut swarm);
+                    }
+                }
+                
                 // Handle swarm events
                 event = swarm.select_next_some() => {
                     self.handle_swarm_event(&mut swarm, event).await;
@@ -269,9 +549,28 @@ impl P2PNetwork {
         }
     }
     
+    /// Addresses dialed on rediscovery: bootstrap nodes, then peers found via mDNS
+    pub fn redial_targets(&self) -> Vec<Multiaddr> {
+        self.config.bootstrap_nodes.iter()
+            .filter_map(|addr| addr.parse::<Multiaddr>().ok())
+            .chain(self.mdns_peers.values().cloned())
+            .collect()
+    }
+    
+    /// Re-dial bootstrap and mDNS peers and restart the Kademlia bootstrap
+    fn rediscover(&self, swarm: &mut Swarm<EdgeAIBehaviour>) {
+        for addr in self.redial_targets() {
+            if let Err(e) = swarm.dial(addr.clone()) {
+                debug!("Rediscovery dial to {} failed: {}", addr, e);
+            }
+        }
+        // Fails with NoKnownPeers when the routing table is empty
+        let _ = swarm.behaviour_mut().kademlia.bootstrap();
+    }
+    
     /// Handle swarm events
     async fn handle_swarm_event(
-        &self,
+        &mut self,
         swarm: &mut Swarm<EdgeAIBehaviour>,
         event: SwarmEvent<EdgeAIBehaviourEvent>,
     ) {
@@ -287,23 +586,45 @@ impl P2PNetwork {
                 if let Ok(gossip_msg) = serde_json::from_slice::<GossipMessage>(&message.data) {
                     match gossip_msg {
                         GossipMessage::Transaction(tx) => {
-                            let _ = self.event_tx.send(NetworkEvent::NewTransaction(tx)).await;
+                            let peer_id = message.source.unwrap_or(propagation_source);
+                            let _ = self.event_tx.send(NetworkEvent::NewTransaction { peer_id, tx }).await;
                         }
                         GossipMessage::Block(block) => {
-                            let _ = self.event_tx.send(NetworkEvent::NewBlock(block)).await;
+                            let peer_id = message.source.unwrap_or(propagation_source);
+                            let _ = self.event_tx.send(NetworkEvent::NewBlock { peer_id, block }).await;
                         }
                         GossipMessage::Contribution(contrib) => {
                             let _ = self.event_tx.send(NetworkEvent::NewContribution(contrib)).await;
                         }
+                        GossipMessage::Heartbeat { timestamp_ms } => {
+                            let peer_id = message.source.unwrap_or(propagation_source);
+                            let _ = self.event_tx.send(NetworkEvent::PeerTime { peer_id, timestamp_ms }).await;
+                        }
+                        // Sync traffic is broadcast; only the addressee handles it
+                        GossipMessage::SyncRequest { to, request, .. } if to == self.local_peer_id.to_string() => {
+                            let peer_id = message.source.unwrap_or(propagation_source);
+                            let request_id = SyncRequestId::Gossip;
+                            let _ = self.event_tx.send(NetworkEvent::SyncRequest { peer_id, request_id, request }).await;
+                        }
+                        GossipMessage::SyncResponse { to, response, .. } if to == self.local_peer_id.to_string() => {
+                            let peer_id = message.source.unwrap_or(propagation_source);
+                            let _ = self.event_tx.send(NetworkEvent::SyncResponse { peer_id, response }).await;
+                        }
+                        GossipMessage::SyncRequest { .. } | GossipMessage::SyncResponse { .. } => {}
                     }
                 }
             }
             
+            SwarmEvent::Behaviour(EdgeAIBehaviourEvent::Sync(event)) => {
+                self.handle_sync_event(swarm, event).await;
+            }
+            
             SwarmEvent::Behaviour(EdgeAIBehaviourEvent::Mdns(mdns::Event::Discovered(peers))) => {
                 for (peer_id, addr) in peers {
                     info!("mDNS discovered peer: {} at {}", peer_id, addr);
                     swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
-                    swarm.behaviour_mut().kademlia.add_address(&peer_id, addr);
+                    swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
+                    self.mdns_peers.insert(peer_id, addr);
                 }
             }
             
@@ -311,6 +632,7 @@ impl P2PNetwork {
                 for (peer_id, _addr) in peers {
                     debug!("mDNS peer expired: {}", peer_id);
                     swarm.behaviour_mut().gossipsub.remove_explicit_peer(&peer_id);
+                    self.mdns_peers.remove(&peer_id);
                 }
             }
             
@@ -327,13 +649,22 @@ impl P2PNetwork {
                 }
             }
             
-            SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+            SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                if self.is_banned(&peer_id, endpoint.get_remote_address()).await {
+                    warn!("Refusing blacklisted peer {} at {}", peer_id, endpoint.get_remote_address());
+                    let _ = swarm.disconnect_peer_id(peer_id);
+                    return;
+                }
                 info!("Connection established with peer: {}", peer_id);
+                self.scoring.register_peer(&peer_id.to_string()).await;
                 let _ = self.event_tx.send(NetworkEvent::PeerConnected(peer_id)).await;
             }
             
-            SwarmEvent::ConnectionClosed { peer_id, .. } => {
+            SwarmEvent::ConnectionClosed { peer_id, num_established, .. } => {
                 info!("Connection closed with peer: {}", peer_id);
+                if num_established == 0 {
+                    self.sync_channels.retain(|_, (peer, _)| *peer != peer_id);
+                }
                 let _ = self.event_tx.send(NetworkEvent::PeerDisconnected(peer_id)).await;
             }
             
@@ -345,8 +676,72 @@ impl P2PNetwork {
         }
     }
     
+    /// Handle sync protocol events, passing requests and responses to the application
+    async fn handle_sync_event(
+        &mut self,
+        swarm: &mut Swarm<EdgeAIBehaviour>,
+        event: request_response::Event<SyncRequest, SyncResponse>,
+    ) {
+        match event {
+            request_response::Event::Message { peer, message } => match message {
+                request_response::Message::Request { request_id, request, channel } => {
+                    self.sync_channels.insert(request_id, (peer, channel));
+                    let request_id = SyncRequestId::Stream(request_id);
+                    let _ = self.event_tx.send(NetworkEvent::SyncRequest { peer_id: peer, request_id, request }).await;
+                }
+                request_response::Message::Response { request_id, response } => {
+                    self.outbound_sync.remove(&request_id);
+                    let _ = self.event_tx.send(NetworkEvent::SyncResponse { peer_id: peer, response }).await;
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error } => {
+                let request = self.outbound_sync.remove(&request_id);
+                match (error, request) {
+                    (request_response::OutboundFailure::UnsupportedProtocols, Some(request)) => {
+                        debug!("Peer {} lacks the sync protocol, sending sync request over gossip", peer);
+                        let msg = GossipMessage::SyncRequest { to: peer.to_string(), nonce: rand::random(), request };
+                        publish_sync_gossip(swarm, &msg, &peer);
+                    }
+                    (error, _) => warn!("Sync request to {} failed: {}", peer, error),
+                }
+            }
+            request_response::Event::InboundFailure { peer, request_id, error } => {
+                self.sync_channels.remove(&request_id);
+                debug!("Sync request from {} failed: {}", peer, error);
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+    
+    /// Answer sync request `request_id` from `peer_id` on the stream it
+    /// arrived on, or over gossip if it came over gossip or the stream has closed
+    fn send_sync_response(
+        &mut self,
+        swarm: &mut Swarm<EdgeAIBehaviour>,
+        peer_id: PeerId,
+        request_id: SyncRequestId,
+        mut response: SyncResponse,
+    ) {
+        if let SyncRequestId::Stream(id) = request_id {
+            if let Some((_, channel)) = self.sync_channels.remove(&id) {
+                match swarm.behaviour_mut().sync.send_response(channel, response) {
+                    Ok(()) => return,
+                    // The request timed out or the connection closed
+                    Err(unsent) => response = unsent,
+                }
+            }
+        }
+        let Some(data) = gossip_sync_response(&peer_id, response) else {
+            warn!("Sync response to {} does not fit in a gossip message; dropped", peer_id);
+            return;
+        };
+        if let Err(e) = swarm.behaviour_mut().gossipsub.publish(IdentTopic::new(topics::SYNC), data) {
+            warn!("Failed to send sync message to {}: {}", peer_id, e);
+        }
+    }
+    
     /// Handle commands from application
-    async fn handle_command(&self, swarm: &mut Swarm<EdgeAIBehaviour>, command: NetworkCommand) {
+    async fn handle_command(&mut self, swarm: &mut Swarm<EdgeAIBehaviour>, command: NetworkCommand) {
         match command {
             NetworkCommand::BroadcastTransaction(tx) => {
                 let msg = GossipMessage::Transaction(tx);
@@ -378,6 +773,15 @@ impl P2PNetwork {
                 }
             }
             
+            NetworkCommand::SendSyncRequest { peer_id, request } => {
+                let request_id = swarm.behaviour_mut().sync.send_request(&peer_id, request.clone());
+                self.outbound_sync.insert(request_id, request);
+            }
+            
+            NetworkCommand::SendSyncResponse { peer_id, request_id, response } => {
+                self.send_sync_response(swarm, peer_id, request_id, response);
+            }
+            
             NetworkCommand::ConnectPeer(addr) => {
                 info!("Connecting to peer: {}", addr);
                 if let Err(e) = swarm.dial(addr.clone()) {
@@ -385,6 +789,12 @@ impl P2PNetwork {
                 }
             }
             
+            NetworkCommand::DisconnectPeer(peer_id) => {
+                if swarm.disconnect_peer_id(peer_id).is_ok() {
+                    info!("Disconnecting peer: {}", peer_id);
+                }
+            }
+            
             NetworkCommand::GetPeerCount => {
                 let count = swarm.connected_peers().count();
                 debug!("Current peer count: {}", count);
@@ -393,11 +803,58 @@ impl P2PNetwork {
     }
 }
 
+/// Publish sync traffic addressed to `peer_id` on the sync gossip topic
+fn publish_sync_gossip(swarm: &mut Swarm<EdgeAIBehaviour>, msg: &GossipMessage, peer_id: &PeerId) {
+    if let Ok(data) = serde_json::to_vec(msg) {
+        let topic = IdentTopic::new(topics::SYNC);
+        if let Err(e) = swarm.behaviour_mut().gossipsub.publish(topic, data) {
+            warn!("Failed to send sync message to {}: {}", peer_id, e);
+        }
+    }
+}
+
+/// Encode a sync response for gossip, halving its blocks or headers until it
+/// fits in `MAX_GOSSIP_MESSAGE_SIZE`. The requester asks again for the rest.
+fn gossip_sync_response(peer_id: &PeerId, mut response: SyncResponse) -> Option<Vec<u8>> {
+    loop {
+        let msg = GossipMessage::SyncResponse { to: peer_id.to_string(), nonce: rand::random(), response };
+        let data = serde_json::to_vec(&msg).ok()?;
+        if data.len() <= MAX_GOSSIP_MESSAGE_SIZE {
+            return Some(data);
+        }
+        let GossipMessage::SyncResponse { response: oversized, .. } = msg else {
+            return None;
+        };
+        response = match oversized {
+            SyncResponse::Blocks { mut blocks } if blocks.len() > 1 => {
+                blocks.truncate(blocks.len() / 2);
+                SyncResponse::Blocks { blocks }
+            }
+            SyncResponse::Headers { mut headers } if headers.len() > 1 => {
+                headers.truncate(headers.len() / 2);
+                SyncResponse::Headers { headers }
+            }
+            _ => return None,
+        };
+    }
+}
+
+/// IP address of a multiaddr, if it has one
+fn multiaddr_ip(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
 /// Helper function to create and start the P2P network
 pub async fn start_p2p_network(
     config: NetworkConfig,
+    scoring: Arc<PeerScoringManager>,
 ) -> Result<(mpsc::Sender<NetworkCommand>, mpsc::Receiver<NetworkEvent>), Box<dyn std::error::Error>> {
     let (network, command_tx, event_rx) = P2PNetwork::new(config)?;
+    let network = network.with_scoring(scoring);
     
     // Spawn the network event loop
     tokio::spawn(async move {
@@ -412,6 +869,7 @@ pub async fn start_p2p_network(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blockchain::genesis::Genesis;
     
     #[tokio::test]
     async fn test_network_creation() {
@@ -419,4 +877,99 @@ mod tests {
         let result = P2PNetwork::new(config);
         assert!(result.is_ok());
     }
+    
+    #[test]
+    fn test_sync_messages_round_trip() {
+        use crate::network::sync_protocol::BlockHeader;
+        
+        let requests = vec![
+            SyncRequest::GetHeight,
+            SyncRequest::GetHeaders { start: 10, count: 50 },
+            SyncRequest::GetBlocks { hashes: vec!["abc".to_string(), "def".to_string()] },
+            SyncRequest::GetBlockRange { start: 1, end: 100 },
+            SyncRequest::GetBlockByHeight { height: 42 },
+        ];
+        for request in &requests {
+            let data = encode_sync_message(request).unwrap();
+            let decoded: SyncRequest = decode_sync_message(&data, MAX_SYNC_REQUEST_SIZE).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", request));
+        }
+        
+        let header = BlockHeader {
+            index: 7,
+            hash: "h7".to_string(),
+            previous_hash: "h6".to_string(),
+            timestamp: 1_700_000_000,
+            merkle_root: "root".to_string(),
+        };
+        let responses = vec![
+            SyncResponse::Height { height: 99, best_hash: "best".to_string() },
+            SyncResponse::Headers { headers: vec![header] },
+            SyncResponse::Blocks { blocks: vec![Block::genesis(&Genesis::default())] },
+            SyncResponse::NotFound { requested: "1-5".to_string() },
+            SyncResponse::Error { message: "busy".to_string() },
+        ];
+        for response in &responses {
+            let data = encode_sync_message(response).unwrap();
+            let decoded: SyncResponse = decode_sync_message(&data, MAX_SYNC_RESPONSE_SIZE).unwrap();
+            assert_eq!(format!("{:?}", decoded), format!("{:?}", response));
+        }
+        
+        // Oversized messages are refused before parsing
+        let data = encode_sync_message(&responses[2]).unwrap();
+        let err = decode_sync_message::<SyncResponse>(&data, data.len() - 1).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+    
+    #[test]
+    fn test_gossip_sync_responses_fit_the_gossip_limit() {
+        let peer = PeerId::random();
+        let block = Block::genesis(&Genesis::default());
+        let per_block = serde_json::to_vec(&block).unwrap().len();
+        let count = 2 * MAX_GOSSIP_MESSAGE_SIZE / per_block;
+        let response = SyncResponse::Blocks { blocks: vec![block.clone(); count] };
+        
+        let data = gossip_sync_response(&peer, response).unwrap();
+        assert!(data.len() <= MAX_GOSSIP_MESSAGE_SIZE);
+        let Ok(GossipMessage::SyncResponse { response: SyncResponse::Blocks { blocks }, .. }) = serde_json::from_slice(&data) else {
+            panic!("expected a blocks response");
+        };
+        assert!(!blocks.is_empty() && blocks.len() < count);
+        
+        // A single block too large for gossip is not sent at all
+        let mut huge = block;
+        huge.transactions[0].data = Some("x".repeat(MAX_GOSSIP_MESSAGE_SIZE));
+        assert!(gossip_sync_response(&peer, SyncResponse::Blocks { blocks: vec![huge] }).is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_rediscovery_after_losing_all_peers() {
+        let bootstrap = "/ip4/203.0.113.10/tcp/9000".to_string();
+        let config = NetworkConfig { bootstrap_nodes: vec![bootstrap.clone()], ..NetworkConfig::default() };
+        let (network, _, _) = P2PNetwork::new(config.clone()).unwrap();
+        let mut rediscovery = Rediscovery::new(1, Duration::from_secs(10), Duration::from_secs(30));
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        
+        // Connected: nothing to do
+        assert!(!rediscovery.should_attempt(3, at(0)));
+        
+        // All peers drop: an attempt fires immediately and re-dials the bootstrap node
+        assert!(rediscovery.should_attempt(0, at(5)));
+        assert_eq!(network.redial_targets(), vec![bootstrap.parse::<Multiaddr>().unwrap()]);
+        
+        // Still isolated: retries back off 10s, 20s, then cap at 30s
+        assert!(!rediscovery.should_attempt(0, at(14)));
+        assert!(rediscovery.should_attempt(0, at(15)));
+        assert!(!rediscovery.should_attempt(0, at(34)));
+        assert!(rediscovery.should_attempt(0, at(35)));
+        assert!(!rediscovery.should_attempt(0, at(64)));
+        assert!(rediscovery.should_attempt(0, at(65)));
+        assert_eq!(rediscovery.attempts(), 4);
+        
+        // Reconnecting resets the backoff
+        assert!(!rediscovery.should_attempt(1, at(70)));
+        assert_eq!(rediscovery.attempts(), 0);
+        assert!(rediscovery.should_attempt(0, at(71)));
+    }
 }
@@ -8,6 +8,8 @@ pub mod p2p;
 pub mod libp2p_network;
 pub mod peer_scoring;
 pub mod sync_protocol;
+pub mod propagation;
+pub mod clock;
 
 // Core network exports
 pub use p2p::{NetworkManager, NodeType};
@@ -16,6 +16,9 @@ use chrono::{DateTime, Utc};
 use log::{info, debug, warn};
 
 use crate::blockchain::{Block, Transaction};
+use crate::network::clock::NetworkClock;
+use crate::network::peer_scoring::PeerScoringManager;
+use crate::network::propagation::PropagationTracker;
 
 /// Peer information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -92,6 +95,9 @@ pub enum NetworkMessage {
     /// Pong response
     Pong {
         timestamp: i64,
+        /// Responder's clock when replying (unix millis)
+        #[serde(default)]
+        peer_time: i64,
     },
     /// Request peers list
     GetPeers,
@@ -186,6 +192,10 @@ pub struct NetworkManager {
     pub message_tx: mpsc::Sender<(String, NetworkMessage)>,
     pub message_rx: Arc<RwLock<mpsc::Receiver<(String, NetworkMessage)>>>,
     pub block_height: Arc<RwLock<u64>>,
+    pub propagation: Arc<RwLock<PropagationTracker>>,
+    pub clock: Arc<RwLock<NetworkClock>>,
+    /// Peer reputation, shared with the libp2p layer to refuse banned peers
+    pub scoring: Arc<PeerScoringManager>,
 }
 
 impl NetworkManager {
@@ -201,6 +211,9 @@ impl NetworkManager {
             message_tx: tx,
             message_rx: Arc::new(RwLock::new(rx)),
             block_height: Arc::new(RwLock::new(0)),
+            propagation: Arc::new(RwLock::new(PropagationTracker::new())),
+            clock: Arc::new(RwLock::new(NetworkClock::new())),
+            scoring: Arc::new(PeerScoringManager::new()),
         }
     }
     
@@ -228,6 +241,7 @@ impl NetworkManager {
         if peers.remove(peer_id).is_some() {
             info!("Peer disconnected: {}", &peer_id[..8]);
         }
+        self.clock.write().await.remove_peer(peer_id);
     }
     
     /// Get peer by ID
@@ -294,11 +308,16 @@ impl NetworkManager {
             }
             
             NetworkMessage::Ping { timestamp } => {
-                Some(NetworkMessage::Pong { timestamp })
+                Some(NetworkMessage::Pong { timestamp, peer_time: Utc::now().timestamp_millis() })
             }
             
-            NetworkMessage::Pong { timestamp } => {
-                let latency = Utc::now().timestamp_millis() - timestamp;
+            NetworkMessage::Pong { timestamp, peer_time } => {
+                let now = Utc::now().timestamp_millis();
+                let latency = now - timestamp;
+                if peer_time > 0 {
+                    // The reply was stamped roughly half a round trip ago
+                    self.clock.write().await.record_peer_time(from_peer, peer_time + latency / 2, now);
+                }
                 let mut peers = self.peers.write().await;
                 if let Some(peer) = peers.get_mut(from_peer) {
                     peer.latency_ms = latency as u64;
@@ -319,6 +338,7 @@ impl NetworkManager {
             
             NetworkMessage::NewBlock { block } => {
                 info!("Received new block #{} from {}", block.index, &from_peer[..8]);
+                self.propagation.write().await.record_peer_block(&block, Utc::now());
                 // Block will be processed by the node
                 None
             }
@@ -15,6 +15,9 @@ use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use log::{info, warn, debug};
 
+use crate::blockchain::{Block, Transaction};
+use crate::network::sync_protocol::validate_block;
+
 /// Peer score thresholds
 pub mod thresholds {
     /// Minimum score to remain connected
@@ -384,6 +387,30 @@ impl PeerScoringManager {
         }
     }
     
+    /// Score a block gossiped by `peer_id`. Blocks failing stateless checks
+    /// (hash, merkle root, transaction signatures) count against the peer;
+    /// well-formed blocks we couldn't apply (stale, ahead, forks) don't.
+    pub async fn record_block(&self, peer_id: &str, block: &Block, accepted: bool) {
+        self.register_peer(peer_id).await;
+        if accepted {
+            self.record_valid_block(peer_id).await;
+        } else if let Err(e) = validate_block(block) {
+            debug!("Peer {} sent invalid block #{}: {}", &peer_id[..8.min(peer_id.len())], block.index, e);
+            self.record_invalid_block(peer_id).await;
+        }
+    }
+    
+    /// Score a transaction gossiped by `peer_id`; only a bad hash or
+    /// signature counts against the peer, not mempool policy rejections
+    pub async fn record_transaction(&self, peer_id: &str, tx: &Transaction, accepted: bool) {
+        self.register_peer(peer_id).await;
+        if accepted {
+            self.record_valid_transaction(peer_id).await;
+        } else if !tx.verify() {
+            self.record_invalid_transaction(peer_id).await;
+        }
+    }
+    
     /// Record a valid transaction from peer
     pub async fn record_valid_transaction(&self, peer_id: &str) {
         let mut behaviors = self.behaviors.write().await;
@@ -577,6 +604,11 @@ impl PeerScoringManager {
             .collect()
     }
     
+    /// Peers currently tracked for scoring
+    pub async fn peer_ids(&self) -> Vec<String> {
+        self.behaviors.read().await.keys().cloned().collect()
+    }
+    
     /// Get peers that should be disconnected
     pub async fn get_peers_to_disconnect(&self) -> Vec<String> {
         let behaviors = self.behaviors.read().await;
@@ -677,6 +709,25 @@ mod tests {
         }
     }
     
+    #[tokio::test]
+    async fn test_peer_sending_invalid_blocks_is_disconnected() {
+        let manager = PeerScoringManager::new();
+        let mut block = Block::new(1, "0".repeat(64), vec![], 1, "validator".to_string());
+        block.hash = "f".repeat(64);
+        let good = Block::new(1, "0".repeat(64), vec![], 1, "validator".to_string());
+        
+        // A valid block we couldn't use doesn't move the score
+        manager.record_block("honest", &good, false).await;
+        assert_eq!(manager.get_score("honest").await, Some(thresholds::INITIAL_SCORE));
+        
+        for _ in 0..3 {
+            manager.record_block("bad_peer", &block, false).await;
+        }
+        assert!(manager.get_score("bad_peer").await.unwrap() < thresholds::MIN_SCORE);
+        assert_eq!(manager.get_peers_to_disconnect().await, vec!["bad_peer".to_string()]);
+        assert!(manager.is_blacklisted("bad_peer").await);
+    }
+    
     #[test]
     fn test_message_cache() {
         let mut cache = MessageCache::new(100, Duration::from_secs(60));
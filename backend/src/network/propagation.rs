@@ -0,0 +1,223 @@
+//! Block propagation latency tracking
+//!
+//! Measures how long blocks take to travel across the network:
+//! - Inbound: time between a block's header timestamp and local receipt
+//! - Outbound: time between mining a block locally and the first peer
+//!   acknowledgement (a peer re-announcing the same block hash)
+//!
+//! Latencies are aggregated into fixed-bucket histograms so the node can
+//! report network health without keeping every sample around.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::blockchain::Block;
+
+/// Upper bounds (inclusive, in milliseconds) of the histogram buckets.
+/// Samples above the last bound fall into an overflow bucket.
+pub const LATENCY_BUCKETS_MS: [u64; 8] = [50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// Maximum number of locally mined blocks awaiting acknowledgement
+const MAX_PENDING_ACKS: usize = 256;
+
+/// Fixed-bucket latency histogram
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    counts: [u64; LATENCY_BUCKETS_MS.len() + 1],
+    total: u64,
+    sum_ms: u64,
+    min_ms: Option<u64>,
+    max_ms: Option<u64>,
+}
+
+impl LatencyHistogram {
+    /// Record a single latency sample
+    pub fn record(&mut self, latency_ms: u64) {
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| latency_ms <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.counts[bucket] += 1;
+        self.total += 1;
+        self.sum_ms = self.sum_ms.saturating_add(latency_ms);
+        self.min_ms = Some(self.min_ms.map_or(latency_ms, |m| m.min(latency_ms)));
+        self.max_ms = Some(self.max_ms.map_or(latency_ms, |m| m.max(latency_ms)));
+    }
+
+    /// Serializable view of the histogram
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let buckets = self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| LatencyBucket {
+                le_ms: LATENCY_BUCKETS_MS.get(i).copied(),
+                count,
+            })
+            .collect();
+
+        HistogramSnapshot {
+            count: self.total,
+            avg_ms: if self.total > 0 { self.sum_ms as f64 / self.total as f64 } else { 0.0 },
+            min_ms: self.min_ms,
+            max_ms: self.max_ms,
+            buckets,
+        }
+    }
+}
+
+/// A single histogram bucket (`le_ms: None` is the overflow bucket)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyBucket {
+    pub le_ms: Option<u64>,
+    pub count: u64,
+}
+
+/// Serializable histogram summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramSnapshot {
+    pub count: u64,
+    pub avg_ms: f64,
+    pub min_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    pub buckets: Vec<LatencyBucket>,
+}
+
+/// Propagation report exposed by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropagationReport {
+    /// Block timestamp -> local receipt
+    pub inbound: HistogramSnapshot,
+    /// Local mining -> first peer acknowledgement
+    pub outbound: HistogramSnapshot,
+    /// Locally mined blocks still waiting for an acknowledgement
+    pub pending_acks: usize,
+}
+
+/// Tracks block propagation latencies in both directions
+#[derive(Debug, Default)]
+pub struct PropagationTracker {
+    inbound: LatencyHistogram,
+    outbound: LatencyHistogram,
+    /// Locally mined blocks: hash -> mined time
+    pending: HashMap<String, DateTime<Utc>>,
+}
+
+impl PropagationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an inbound block received at `received_at`.
+    /// Returns the measured latency in milliseconds.
+    pub fn record_inbound(&mut self, block: &Block, received_at: DateTime<Utc>) -> u64 {
+        // Clock skew can put the block timestamp slightly in the future
+        let latency_ms = (received_at - block.header.timestamp).num_milliseconds().max(0) as u64;
+        self.inbound.record(latency_ms);
+        latency_ms
+    }
+
+    /// Remember a locally mined block so a later acknowledgement can be timed
+    pub fn record_local_block(&mut self, block: &Block, mined_at: DateTime<Utc>) {
+        if self.pending.len() >= MAX_PENDING_ACKS {
+            // Drop the oldest entry; blocks nobody acknowledged aren't useful samples
+            if let Some(oldest) = self.pending
+                .iter()
+                .min_by_key(|(_, t)| **t)
+                .map(|(h, _)| h.clone())
+            {
+                self.pending.remove(&oldest);
+            }
+        }
+        self.pending.insert(block.hash.clone(), mined_at);
+    }
+
+    /// Record a peer acknowledgement for a block.
+    /// Returns the outbound latency if the block was mined locally and not yet acknowledged.
+    pub fn record_ack(&mut self, block_hash: &str, acked_at: DateTime<Utc>) -> Option<u64> {
+        let mined_at = self.pending.remove(block_hash)?;
+        let latency_ms = (acked_at - mined_at).num_milliseconds().max(0) as u64;
+        self.outbound.record(latency_ms);
+        Some(latency_ms)
+    }
+
+    /// Handle a block announced by a peer: an echo of one of our own blocks
+    /// counts as an acknowledgement, anything else is an inbound sample.
+    pub fn record_peer_block(&mut self, block: &Block, received_at: DateTime<Utc>) {
+        if self.record_ack(&block.hash, received_at).is_none() {
+            self.record_inbound(block, received_at);
+        }
+    }
+
+    /// Build the propagation report
+    pub fn report(&self) -> PropagationReport {
+        PropagationReport {
+            inbound: self.inbound.snapshot(),
+            outbound: self.outbound.snapshot(),
+            pending_acks: self.pending.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use crate::blockchain::genesis::Genesis;
+
+    fn block_at(index: u64, timestamp: DateTime<Utc>) -> Block {
+        let mut block = Block::genesis(&Genesis::default());
+        block.index = index;
+        block.header.timestamp = timestamp;
+        block.hash = block.calculate_hash();
+        block
+    }
+
+    #[test]
+    fn test_inbound_latency_histogram() {
+        let mut tracker = PropagationTracker::new();
+        let now = Utc::now();
+
+        for delay in [40, 200, 200, 3_000, 20_000] {
+            let block = block_at(1, now - Duration::milliseconds(delay));
+            assert_eq!(tracker.record_inbound(&block, now), delay as u64);
+        }
+
+        let report = tracker.report();
+        assert_eq!(report.inbound.count, 5);
+        assert_eq!(report.inbound.min_ms, Some(40));
+        assert_eq!(report.inbound.max_ms, Some(20_000));
+        assert!((report.inbound.avg_ms - 4_688.0).abs() < f64::EPSILON);
+
+        let count_for = |le: Option<u64>| {
+            report.inbound.buckets.iter().find(|b| b.le_ms == le).unwrap().count
+        };
+        assert_eq!(count_for(Some(50)), 1);
+        assert_eq!(count_for(Some(250)), 2);
+        assert_eq!(count_for(Some(5_000)), 1);
+        assert_eq!(count_for(None), 1);
+        assert_eq!(report.outbound.count, 0);
+    }
+
+    #[test]
+    fn test_outbound_ack_latency() {
+        let mut tracker = PropagationTracker::new();
+        let mined_at = Utc::now();
+        let block = block_at(7, mined_at);
+
+        tracker.record_local_block(&block, mined_at);
+        assert_eq!(tracker.report().pending_acks, 1);
+
+        // Peer echoes our block 300ms later
+        tracker.record_peer_block(&block, mined_at + Duration::milliseconds(300));
+        let report = tracker.report();
+        assert_eq!(report.outbound.count, 1);
+        assert_eq!(report.outbound.max_ms, Some(300));
+        assert_eq!(report.inbound.count, 0);
+        assert_eq!(report.pending_acks, 0);
+
+        // A second echo is not double-counted
+        assert_eq!(tracker.record_ack(&block.hash, mined_at), None);
+    }
+}
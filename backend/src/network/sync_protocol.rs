@@ -6,14 +6,17 @@
 
 #![allow(dead_code)]
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{mpsc, RwLock, Semaphore};
 use serde::{Deserialize, Serialize};
 use log::{info, warn, debug, error};
+use rayon::prelude::*;
 
-use crate::blockchain::Block;
+use crate::blockchain::{Block, Blockchain};
+use crate::blockchain::chain::ConsensusModules;
+use crate::consensus::{GovernanceManager, StakingManager};
 
 /// Sync state machine states
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -172,6 +175,8 @@ pub struct SyncConfig {
     pub max_retries: u32,
     /// Batch size for block validation
     pub validation_batch_size: usize,
+    /// Worker threads for parallel block validation (0 = one per CPU core)
+    pub validation_workers: usize,
 }
 
 impl Default for SyncConfig {
@@ -183,6 +188,7 @@ impl Default for SyncConfig {
             min_peers_for_sync: 1,
             max_retries: 3,
             validation_batch_size: 50,
+            validation_workers: 0,
         }
     }
 }
@@ -232,6 +238,89 @@ impl Default for SyncProgress {
     }
 }
 
+/// Outcome of validating one batch of downloaded blocks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BatchValidation {
+    /// Heights that passed validation
+    pub valid: Vec<u64>,
+    /// Heights that failed, with the reason
+    pub invalid: Vec<(u64, String)>,
+}
+
+/// Stateless checks on a downloaded block: block hash, merkle root,
+/// transaction hashes and signatures. Chain linkage is checked when applying.
+pub fn validate_block(block: &Block) -> Result<(), String> {
+    if !block.verify() {
+        return Err("Block hash mismatch".to_string());
+    }
+    if block.header.merkle_root != Block::calculate_merkle_root(&block.transactions) {
+        return Err("Merkle root mismatch".to_string());
+    }
+    for tx in &block.transactions {
+        if !tx.verify_hash() {
+            return Err(format!("Invalid transaction hash: {}", &tx.hash[..8.min(tx.hash.len())]));
+        }
+        // Unsigned transactions are accepted, as they are by the mempool
+        if tx.signature.is_some() && !tx.verify_signature().unwrap_or(false) {
+            return Err(format!("Invalid transaction signature: {}", &tx.hash[..8.min(tx.hash.len())]));
+        }
+    }
+    Ok(())
+}
+
+/// Answer a peer's sync request from the local chain. Block ranges are
+/// clipped to `max_blocks` and to the local tip.
+pub fn serve_sync_request(chain: &Blockchain, request: &SyncRequest, max_blocks: u64) -> SyncResponse {
+    let tip = chain.latest_block().index;
+    let range = |start: u64, end: u64| -> Vec<Block> {
+        if start > tip || max_blocks == 0 {
+            return Vec::new();
+        }
+        let end = end.min(tip).min(start + max_blocks - 1);
+        (start..=end).map_while(|h| chain.get_block_with_disk_fallback(h)).collect()
+    };
+    
+    match request {
+        SyncRequest::GetHeight => SyncResponse::Height {
+            height: tip,
+            best_hash: chain.latest_block().hash.clone(),
+        },
+        SyncRequest::GetBlockRange { start, end } => {
+            let blocks = range(*start, *end);
+            if blocks.is_empty() {
+                SyncResponse::NotFound { requested: format!("{}-{}", start, end) }
+            } else {
+                SyncResponse::Blocks { blocks }
+            }
+        }
+        SyncRequest::GetBlockByHeight { height } => match chain.get_block_with_disk_fallback(*height) {
+            Some(block) => SyncResponse::Blocks { blocks: vec![block] },
+            None => SyncResponse::NotFound { requested: height.to_string() },
+        },
+        SyncRequest::GetHeaders { start, count } => {
+            if *count == 0 {
+                return SyncResponse::Headers { headers: Vec::new() };
+            }
+            let headers = range(*start, start.saturating_add(count - 1))
+                .iter()
+                .map(BlockHeader::from)
+                .collect();
+            SyncResponse::Headers { headers }
+        }
+        SyncRequest::GetBlocks { hashes } => {
+            let blocks: Vec<Block> = hashes.iter()
+                .take(max_blocks as usize)
+                .filter_map(|hash| chain.get_block_by_hash(hash).cloned())
+                .collect();
+            if blocks.is_empty() {
+                SyncResponse::NotFound { requested: hashes.join(",") }
+            } else {
+                SyncResponse::Blocks { blocks }
+            }
+        }
+    }
+}
+
 /// Block Sync Manager
 pub struct SyncManager {
     /// Current sync state
@@ -260,12 +349,23 @@ pub struct SyncManager {
     request_tx: mpsc::Sender<(String, SyncRequest)>,
     /// Response receiver channel
     response_rx: Arc<RwLock<mpsc::Receiver<(String, SyncResponse)>>>,
+    /// Worker pool for parallel block validation
+    validation_pool: Arc<rayon::ThreadPool>,
+    /// Height to download from while fetching a peer branch that forked from our chain
+    fork_from: Arc<RwLock<Option<u64>>>,
+    /// Blocks of that branch collected so far
+    fork_branch: Arc<RwLock<Vec<Block>>>,
 }
 
 impl SyncManager {
     pub fn new(config: SyncConfig) -> (Self, mpsc::Receiver<(String, SyncRequest)>, mpsc::Sender<(String, SyncResponse)>) {
         let (request_tx, request_rx) = mpsc::channel(100);
         let (response_tx, response_rx) = mpsc::channel(100);
+        let validation_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.validation_workers)
+            .thread_name(|i| format!("sync-validate-{}", i))
+            .build()
+            .expect("failed to build block validation pool");
         
         let manager = Self {
             state: Arc::new(RwLock::new(SyncState::Idle)),
@@ -281,6 +381,9 @@ impl SyncManager {
             progress: Arc::new(RwLock::new(SyncProgress::new())),
             request_tx,
             response_rx: Arc::new(RwLock::new(response_rx)),
+            validation_pool: Arc::new(validation_pool),
+            fork_from: Arc::new(RwLock::new(None)),
+            fork_branch: Arc::new(RwLock::new(Vec::new())),
         };
         
         (manager, request_rx, response_tx)
@@ -437,9 +540,10 @@ impl SyncManager {
         let mut pending = self.pending_tasks.write().await;
         let mut active = self.active_tasks.write().await;
         let mut peers = self.peers.write().await;
+        let current_height = *self.current_height.read().await;
         
         let available_peers: Vec<String> = peers.iter()
-            .filter(|(_, p)| !p.is_syncing && p.failed_requests < 5)
+            .filter(|(_, p)| !p.is_syncing && p.failed_requests < 5 && p.height > current_height)
             .map(|(id, _)| id.clone())
             .collect();
         
@@ -495,16 +599,19 @@ impl SyncManager {
                     downloaded.insert(block.index, block);
                 }
                 
-                // Complete the task
+                // Complete the task; the peer may have served a shorter range
                 let mut active = self.active_tasks.write().await;
-                active.remove(&task_key);
+                let task = active.remove(&task_key).or_else(|| {
+                    let key = active.iter().find(|(_, t)| t.start_height == start).map(|(k, _)| k.clone())?;
+                    active.remove(&key)
+                });
                 
                 // Update peer status
                 let mut peers = self.peers.write().await;
                 if let Some(peer) = peers.get_mut(peer_id) {
                     peer.is_syncing = false;
                     // Update sync speed
-                    if let Some(started) = active.get(&task_key).and_then(|t| t.started_at) {
+                    if let Some(started) = task.and_then(|t| t.started_at) {
                         let elapsed = started.elapsed().as_secs_f64();
                         if elapsed > 0.0 {
                             peer.sync_speed = (end - start + 1) as f64 / elapsed;
@@ -599,6 +706,70 @@ impl SyncManager {
         blocks
     }
     
+    /// Validate the next batch of downloaded blocks on the worker pool.
+    ///
+    /// Blocks are checked in parallel, but only the valid run starting at
+    /// the current height is queued for application so blocks are applied
+    /// in order. Invalid blocks are dropped and re-queued for download;
+    /// valid blocks after a gap wait until the gap is filled.
+    pub async fn validate_batch(&self) -> BatchValidation {
+        let blocks = self.get_blocks_for_validation().await;
+        if blocks.is_empty() {
+            return BatchValidation::default();
+        }
+        self.set_state(SyncState::Validating).await;
+        
+        let pool = self.validation_pool.clone();
+        let results: Vec<(u64, Result<(), String>)> = tokio::task::spawn_blocking(move || {
+            pool.install(|| {
+                blocks.par_iter()
+                    .map(|block| (block.index, validate_block(block)))
+                    .collect()
+            })
+        })
+        .await
+        .unwrap_or_default();
+        
+        let mut report = BatchValidation::default();
+        for (height, result) in results {
+            match result {
+                Ok(()) => report.valid.push(height),
+                Err(e) => {
+                    warn!("Downloaded block #{} failed validation: {}", height, e);
+                    report.invalid.push((height, e));
+                }
+            }
+        }
+        
+        // Only the leading run of valid blocks can be applied now
+        let first_invalid = report.invalid.iter().map(|(h, _)| *h).min().unwrap_or(u64::MAX);
+        let ready: Vec<u64> = report.valid.iter().copied().filter(|h| *h < first_invalid).collect();
+        self.mark_validated(&ready).await;
+        
+        if !report.invalid.is_empty() {
+            let invalid: HashSet<u64> = report.invalid.iter().map(|(h, _)| *h).collect();
+            let mut downloaded = self.downloaded_blocks.write().await;
+            let mut pending = self.pending_tasks.write().await;
+            for height in &invalid {
+                downloaded.remove(height);
+                pending.push_back(DownloadTask::new(*height, *height));
+            }
+        }
+        
+        debug!("Validated batch: {} valid, {} invalid, {} ready to apply",
+            report.valid.len(), report.invalid.len(), ready.len());
+        if !ready.is_empty() {
+            self.set_state(SyncState::Applying).await;
+        }
+        report
+    }
+    
+    /// Update the sync state machine
+    async fn set_state(&self, new_state: SyncState) {
+        *self.state.write().await = new_state.clone();
+        self.progress.write().await.state = new_state;
+    }
+    
     /// Mark blocks as validated
     pub async fn mark_validated(&self, heights: &[u64]) {
         let mut downloaded = self.downloaded_blocks.write().await;
@@ -664,11 +835,127 @@ impl SyncManager {
         
         info!("Block synchronization completed at height {}", progress.current_height);
     }
+    
+    /// Ask every registered peer for its chain height
+    pub async fn request_heights(&self) {
+        let peers: Vec<String> = self.peers.read().await.keys().cloned().collect();
+        for peer_id in peers {
+            let _ = self.request_tx.send((peer_id, SyncRequest::GetHeight)).await;
+        }
+    }
+    
+    /// Drop all queued and downloaded blocks so the range is requested again
+    async fn reset_downloads(&self) {
+        self.pending_tasks.write().await.clear();
+        self.active_tasks.write().await.clear();
+        self.downloaded_blocks.write().await.clear();
+        self.validated_blocks.write().await.clear();
+        for peer in self.peers.write().await.values_mut() {
+            peer.is_syncing = false;
+        }
+    }
+    
+    /// Run one round of catch-up sync against `chain`: retry timed out
+    /// requests, queue and assign downloads, validate what has arrived and
+    /// apply it in order, keeping `staking` and `governance` in step with the
+    /// blocks. Returns the blocks appended to the chain.
+    pub async fn sync_step(
+        &self,
+        chain: &RwLock<Blockchain>,
+        staking: &RwLock<StakingManager>,
+        governance: &RwLock<GovernanceManager>,
+    ) -> Vec<Block> {
+        let local_height = chain.read().await.latest_block().index;
+        // While fetching a forked branch, download from below the tip so it includes the fork point
+        let from = self.fork_from.read().await.unwrap_or(local_height);
+        self.set_current_height(from).await;
+        
+        if !self.needs_sync().await {
+            let state = self.state.read().await.clone();
+            if matches!(state, SyncState::DownloadingBlocks | SyncState::Validating | SyncState::Applying)
+                && self.is_complete().await
+            {
+                self.complete_sync().await;
+            }
+            return Vec::new();
+        }
+        
+        self.check_timeouts().await;
+        let idle = self.pending_tasks.read().await.is_empty() && self.active_tasks.read().await.is_empty();
+        if idle {
+            self.create_download_tasks().await;
+            self.set_state(SyncState::DownloadingBlocks).await;
+            let mut progress = self.progress.write().await;
+            progress.started_at.get_or_insert(chrono::Utc::now().timestamp());
+        }
+        self.assign_tasks().await;
+        self.validate_batch().await;
+        
+        let blocks = self.get_validated_blocks(self.config.validation_batch_size).await;
+        if blocks.is_empty() {
+            return Vec::new();
+        }
+        let mut applied = Vec::new();
+        let mut chain = chain.write().await;
+        let mut staking = staking.write().await;
+        let mut governance = governance.write().await;
+        let mut modules = ConsensusModules::new(&mut staking, &mut governance);
+        if self.fork_from.read().await.is_some() {
+            // Collect the peer's branch until it passes our tip, then switch if it is heavier
+            let mut branch = self.fork_branch.write().await;
+            branch.extend(blocks);
+            let branch_tip = branch.last().map(|block| block.index).unwrap_or(0);
+            if branch_tip <= chain.latest_block().index {
+                *self.fork_from.write().await = Some(branch_tip);
+                return applied;
+            }
+            let branch = std::mem::take(&mut *branch);
+            *self.fork_from.write().await = None;
+            match chain.reorg_to(&branch, &mut modules) {
+                Ok(replaced) => {
+                    let fork_height = replaced.first().map(|block| block.index).unwrap_or(0);
+                    self.mark_applied(chain.latest_block().index).await;
+                    applied = branch.into_iter().filter(|block| block.index >= fork_height).collect();
+                }
+                Err(e) => {
+                    warn!("Not switching to the peer's branch: {}", e);
+                    self.reset_downloads().await;
+                }
+            }
+            return applied;
+        }
+        for block in blocks {
+            let height = block.index;
+            match chain.accept_external_block(block.clone(), &mut modules) {
+                Ok(()) => {
+                    self.mark_applied(height).await;
+                    applied.push(block);
+                }
+                Err(e) if e.contains("does not extend") => {
+                    warn!("Synced block #{} forks from our chain, fetching the branch from the finalized height", height);
+                    *self.fork_from.write().await = Some(chain.finalized_height());
+                    self.fork_branch.write().await.clear();
+                    self.reset_downloads().await;
+                    break;
+                }
+                Err(e) => {
+                    warn!("Failed to apply synced block #{}: {}", height, e);
+                    self.reset_downloads().await;
+                    break;
+                }
+            }
+        }
+        if !applied.is_empty() {
+            info!("Synced {} blocks, now at height {}", applied.len(), chain.latest_block().index);
+        }
+        applied
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blockchain::genesis::Genesis;
     
     #[tokio::test]
     async fn test_sync_manager_creation() {
@@ -703,4 +990,81 @@ mod tests {
         
         assert!(manager.needs_sync().await);
     }
+    
+    #[tokio::test]
+    async fn test_parallel_validation_isolates_invalid_block() {
+        use crate::blockchain::Transaction;
+        
+        let config = SyncConfig { validation_workers: 4, ..SyncConfig::default() };
+        let (manager, _, _) = SyncManager::new(config);
+        manager.set_current_height(0).await;
+        
+        let mut blocks = Vec::new();
+        let mut previous_hash = Block::genesis(&Genesis::default()).hash;
+        for i in 1..=6u64 {
+            let tx = Transaction::transfer("alice".to_string(), "bob".to_string(), i);
+            let block = Block::new(i, previous_hash.clone(), vec![tx], 1, "validator".to_string());
+            previous_hash = block.hash.clone();
+            blocks.push(block);
+        }
+        // Tamper with a transaction in block 3 (hash still recomputed)
+        blocks[2].transactions[0].outputs[0].amount = 1_000;
+        blocks[2].hash = blocks[2].calculate_hash();
+        manager.handle_response("peer1", SyncResponse::Blocks { blocks }).await;
+        
+        let report = manager.validate_batch().await;
+        let mut valid = report.valid.clone();
+        valid.sort();
+        assert_eq!(valid, vec![1, 2, 4, 5, 6]);
+        assert_eq!(report.invalid.len(), 1);
+        assert_eq!(report.invalid[0].0, 3);
+        
+        // Blocks before the gap are ready in order; block 3 is re-requested
+        let ready: Vec<u64> = manager.get_validated_blocks(10).await.iter().map(|b| b.index).collect();
+        assert_eq!(ready, vec![1, 2]);
+        let pending = manager.pending_tasks.read().await;
+        assert_eq!(pending.len(), 1);
+        assert_eq!((pending[0].start_height, pending[0].end_height), (3, 3));
+        assert_eq!(manager.downloaded_blocks.read().await.len(), 3);
+    }
+    
+    #[tokio::test]
+    async fn test_lagging_chain_syncs_to_leader() {
+        use crate::blockchain::chain::tests::test_chain;
+        
+        let mut leader = test_chain();
+        for _ in 0..5 {
+            leader.mine_block("miner".to_string()).unwrap();
+        }
+        let mut follower = test_chain();
+        // Both nodes must start from the same genesis block
+        follower.chain[0] = leader.chain[0].clone();
+        let follower = RwLock::new(follower);
+        let staking = RwLock::new(StakingManager::new(crate::consensus::StakingConfig::default()));
+        let governance = RwLock::new(GovernanceManager::new(crate::consensus::GovernanceConfig::default()));
+        
+        let config = SyncConfig { max_blocks_per_request: 2, ..SyncConfig::default() };
+        let (manager, mut requests, _) = SyncManager::new(config);
+        manager.register_peer("leader").await;
+        
+        for _ in 0..10 {
+            manager.request_heights().await;
+            while let Ok((peer, request)) = requests.try_recv() {
+                let response = serve_sync_request(&leader, &request, 2);
+                manager.handle_response(&peer, response).await;
+            }
+            manager.sync_step(&follower, &staking, &governance).await;
+            if follower.read().await.latest_block().index == 5 {
+                break;
+            }
+        }
+        
+        let follower = follower.read().await;
+        assert_eq!(follower.latest_block().index, 5);
+        assert_eq!(follower.latest_block().hash, leader.latest_block().hash);
+        let progress = manager.get_progress().await;
+        assert_eq!(progress.current_height, 5);
+        assert_eq!(progress.target_height, 5);
+        assert_eq!(progress.applied_blocks, 5);
+    }
 }
@@ -22,6 +22,7 @@ const sidebars: SidebarsConfig = {
         'api-reference/governance',
         'api-reference/contracts',
         'api-reference/devices',
+        'api-reference/marketplace',
       ],
     },
     {
@@ -76,6 +76,14 @@ export interface Block {
   previous_hash?: string;
 }
 
+// Envelope returned by paginated listing endpoints
+export interface Page<T> {
+  items: T[];
+  total: number;
+  offset: number;
+  limit: number;
+}
+
 export interface Transaction {
   id: string;
   tx_type: 'Genesis' | 'Transfer' | 'DataContribution' | 'DataPurchase' | 'Reward';
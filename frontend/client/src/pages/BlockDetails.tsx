@@ -2,7 +2,7 @@ import { useEffect, useState } from "react";
 import { useRoute, Link } from "wouter";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Badge } from "@/components/ui/badge";
-import { apiCall, Block } from "@/lib/api";
+import { apiCall, Block, Page } from "@/lib/api";
 import { ArrowLeft, Layers, Clock, Hash, ShieldCheck, Database, Activity } from "lucide-react";
 import { getRandomActiveValidator } from "@/lib/validators";
 
@@ -79,9 +79,9 @@ export default function BlockDetails() {
         setValidatorName(getRandomActiveValidator().name);
       } else {
         // Fallback: Fetch list and find
-        const listResponse = await apiCall<Block[]>('/blocks?limit=50');
-        if (listResponse.success && Array.isArray(listResponse.data)) {
-          const found = listResponse.data.find(b => 
+        const listResponse = await apiCall<Page<Block>>('/blocks?limit=50');
+        if (listResponse.success && Array.isArray(listResponse.data?.items)) {
+          const found = listResponse.data.items.find(b => 
             b.hash === params.id || b.index.toString() === params.id
           );
           
@@ -1,7 +1,7 @@
 import { useEffect, useState } from "react";
 import { Link } from "wouter";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
-import { apiCall, Block } from "@/lib/api";
+import { apiCall, Block, Page } from "@/lib/api";
 import { Copy, QrCode } from "lucide-react";
 import { toast } from "sonner";
 import QRCode from "react-qr-code";
@@ -27,11 +27,11 @@ export default function Blocks() {
     }
 
     const currentOffset = isLoadMore ? blocks.length : 0;
-    const response = await apiCall<Block[]>(`/blocks?limit=${LIMIT}&offset=${currentOffset}`);
+    const response = await apiCall<Page<Block>>(`/blocks?limit=${LIMIT}&offset=${currentOffset}`);
     
-    if (response.success && Array.isArray(response.data)) {
+    if (response.success && Array.isArray(response.data?.items)) {
       // Sort blocks by index in descending order (newest first)
-      const backendBlocks = [...response.data].sort((a, b) => b.index - a.index);
+      const backendBlocks = [...response.data.items].sort((a, b) => b.index - a.index);
 
       if (isLoadMore) {
         // When loading more, append and re-sort to maintain order
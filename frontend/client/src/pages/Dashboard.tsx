@@ -1,6 +1,6 @@
 import { useEffect, useState, useCallback } from "react";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
-import { apiCall, Block, Transaction, fetchChainStats, fetchIoTTransactions, ChainStats } from "@/lib/api";
+import { apiCall, Block, Page, Transaction, fetchChainStats, fetchIoTTransactions, ChainStats } from "@/lib/api";
 import { useSmartPolling } from "@/hooks/useSmartPolling";
 import { Link, useLocation } from "wouter";
 import { Input } from "@/components/ui/input";
@@ -68,14 +68,14 @@ export default function Dashboard() {
     }
 
     // Fetch blocks from backend
-    const blocksRes = await apiCall<Block[]>('/blocks?limit=20');
+    const blocksRes = await apiCall<Page<Block>>('/blocks?limit=20');
     
     if (!blocksRes.success) {
       throw new Error(blocksRes.error || "Failed to fetch blocks");
     }
 
-    if (Array.isArray(blocksRes.data)) {
-      const blocks = blocksRes.data;
+    if (Array.isArray(blocksRes.data?.items)) {
+      const blocks = blocksRes.data.items;
       
       // Use backend blocks directly - no simulation override
       setLatestBlocks(blocks.slice(0, 10));
@@ -173,7 +173,7 @@ export default function Dashboard() {
       console.error("Failed to fetch IoT transactions:", error);
     }
 
-    return blocksRes.data;
+    return blocksRes.data?.items;
   }, []);
 
   // Use smart polling hook - poll every 5 seconds to match backend block time
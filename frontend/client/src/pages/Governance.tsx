@@ -572,7 +572,6 @@ export default function Governance() {
       const voteData = {
         voter: wallet.address,
         option: vote,
-        voting_power: "1000000000000000000", // 1 EDGE
       };
       
       const signedRequest = await createSignedRequest(wallet, voteData);
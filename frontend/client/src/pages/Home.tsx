@@ -81,14 +81,10 @@ export default function Home() {
       if (data.balance === 0) {
         addLog("info", "Balance is 0. Requesting faucet funds...");
         // Auto-fund for demo purposes
-        await fetch("https://edgeai-blockchain-node.fly.dev/api/transactions/transfer", {
+        await fetch("https://edgeai-blockchain-node.fly.dev/api/faucet", {
           method: "POST",
           headers: { "Content-Type": "application/json" },
-          body: JSON.stringify({
-            from: "genesis",
-            to: wallet.address,
-            amount: 1000
-          })
+          body: JSON.stringify({ address: wallet.address })
         });
         
         // Mine a block to confirm
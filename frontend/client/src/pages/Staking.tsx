@@ -108,11 +108,11 @@ const fetchStakingStats = async (): Promise<StakingStats> => {
 
 const fetchValidators = async (): Promise<Validator[]> => {
   try {
-    const response = await fetch(`${API_BASE}/staking/validators`);
+    const response = await fetch(`${API_BASE}/staking/validators?limit=100`);
     const result = await response.json();
     
-    if (result.success && Array.isArray(result.data)) {
-      return result.data.map((v: any) => ({
+    if (result.success && Array.isArray(result.data?.items)) {
+      return result.data.items.map((v: any) => ({
         address: v.address,
         name: v.moniker || v.address.slice(0, 16),
         description: v.details || "EdgeAI Validator Node",
@@ -160,6 +160,7 @@ export default function Wallet() {
       });
       const prepareResult = await prepareRes.json();
       const messageToSign = prepareResult.data?.message_to_sign || prepareResult.message_to_sign;
+      const nonce = prepareResult.data?.nonce ?? prepareResult.nonce ?? 0;
       addLog("output", `Message to sign: ${messageToSign?.substring(0, 32)}...`);
       
       // 2. Sign the message with private key
@@ -185,6 +186,7 @@ export default function Wallet() {
           from: wallet.address,
           to: recipient,
           amount: parseInt(amount),
+          nonce: nonce,
           signature: signature,
           public_key: wallet.public_key
         })
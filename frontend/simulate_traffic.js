@@ -47,15 +47,11 @@ async function initializeWallets(count = 20) {
       
       wallets.push(wallet);
       
-      // Fund the wallet from genesis
-      await fetch(`${API_BASE_URL}/transactions/transfer`, {
+      // Fund the wallet from the testnet faucet
+      await fetch(`${API_BASE_URL}/faucet`, {
         method: 'POST',
         headers: { 'Content-Type': 'application/json' },
-        body: JSON.stringify({
-          from: 'genesis',
-          to: wallet.address,
-          amount: 10000
-        })
+        body: JSON.stringify({ address: wallet.address })
       });
       
       process.stdout.write('.');
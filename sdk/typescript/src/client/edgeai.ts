@@ -194,20 +194,24 @@ export class EdgeAIClient extends EventEmitter {
   }
 
   /**
-   * Send a transfer transaction
+   * Send a signed transfer transaction
    * @param from - Sender address
    * @param params - Transfer parameters
-   * @param signature - Transaction signature
+   * @param publicKey - Sender's public key (hex)
+   * @param signature - Signature over the message from /api/wallet/prepare-transfer
    * @returns Transaction hash
    */
   async transfer(
     from: string,
     params: TransferParams,
+    publicKey: string,
     signature: string
   ): Promise<{ hash: string }> {
-    return this.http.post<{ hash: string }>('/api/transactions/transfer', {
+    return this.http.post<{ hash: string }>('/api/wallet/transfer', {
       from,
       ...params,
+      amount: Number(params.amount),
+      public_key: publicKey,
       signature,
     });
   }
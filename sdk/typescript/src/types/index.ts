@@ -201,6 +201,8 @@ export interface TransferParams {
   to: string;
   /** Amount to transfer */
   amount: string;
+  /** Sender's next account nonce */
+  nonce: number;
   /** Optional memo/data */
   memo?: string;
 }